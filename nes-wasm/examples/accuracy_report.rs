@@ -0,0 +1,39 @@
+// ============================================================
+// 準確度報告 - 執行測試 ROM 目錄並輸出 JSON
+// ============================================================
+// cargo run --release --features native --example accuracy_report -- <目錄> [輸出檔]
+// 未指定輸出檔時寫到標準輸出；有測試未通過時結束碼為 1。
+// ============================================================
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use nes_wasm::accuracy;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(dir) = args.first() else {
+        eprintln!("用法：accuracy_report <測試 ROM 目錄> [輸出 JSON 檔]");
+        return ExitCode::from(2);
+    };
+    let report = match accuracy::run_directory(Path::new(dir)) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let json = report.to_json();
+    match args.get(1) {
+        Some(out) => {
+            if let Err(e) = std::fs::write(out, &json) {
+                eprintln!("無法寫入 {out}：{e}");
+                return ExitCode::from(2);
+            }
+        }
+        None => println!("{json}"),
+    }
+    eprintln!("{}/{} 通過", report.passed(), report.results.len());
+    if report.passed() == report.results.len() { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
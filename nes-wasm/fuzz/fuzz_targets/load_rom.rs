@@ -0,0 +1,18 @@
+// ============================================================
+// Fuzz：ROM 解析（iNES / NES 2.0 / NSF）
+// ============================================================
+// cargo +nightly fuzz run load_rom
+// 載入成功時再執行兩幀，涵蓋 Mapper 初始化與 bank 計算。
+// ============================================================
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nes_wasm::Emulator;
+
+fuzz_target!(|data: &[u8]| {
+    let mut emu = Emulator::new();
+    if emu.load_rom(data) {
+        emu.run_frames(2);
+    }
+});
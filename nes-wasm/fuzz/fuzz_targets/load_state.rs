@@ -0,0 +1,37 @@
+// ============================================================
+// Fuzz：存檔讀取（二進位與 hex 文字格式）
+// ============================================================
+// cargo +nightly fuzz run load_state
+// 先載入最小 NROM，再把輸入當成存檔讀入；讀檔成功時執行一幀，
+// 確認損毀的暫存器值不會讓渲染或 DMA 發生 panic。
+// ============================================================
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nes_wasm::Emulator;
+
+/// 開啟背景與精靈顯示後無限迴圈的 NROM
+fn boot() -> Emulator {
+    let mut prg = vec![0xEAu8; 0x4000];
+    prg[..8].copy_from_slice(&[0xA9, 0x1E, 0x8D, 0x01, 0x20, 0x4C, 0x05, 0xC0]);
+    prg[0x3FFA..].copy_from_slice(&[0x00, 0xC0, 0x00, 0xC0, 0x00, 0xC0]);
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend_from_slice(&prg);
+    rom.extend(std::iter::repeat_n(0u8, 0x2000));
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&rom));
+    emu
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut emu = boot();
+    if emu.load_state(data) {
+        emu.run_frames(1);
+    }
+    if let Ok(hex) = std::str::from_utf8(data) {
+        if emu.import_save_state(hex) {
+            emu.run_frames(1);
+        }
+    }
+});
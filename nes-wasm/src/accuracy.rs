@@ -0,0 +1,254 @@
+// ============================================================
+// 準確度報告 - 批次執行測試 ROM 並輸出 JSON（native feature）
+// ============================================================
+// 對一個目錄中的測試 ROM（blargg CPU/PPU/APU、sprite hit、MMC3 IRQ 等）
+// 逐一執行並判定結果，輸出 JSON 報告，方便比較不同版本的準確度。
+//
+// 判定方式：
+// - 預設使用 $6000 結果協定（Emulator::run_test_rom），結果碼 0 為通過
+// - 舊版測試只在畫面上顯示結果，可在清單中指定幀數與預期的畫面雜湊
+//
+// 目錄下的 accuracy.txt 為清單，每行一個 ROM（# 開頭為註解）：
+//
+//   <ROM 相對路徑> [幀數] [預期畫面雜湊（16 進位）]
+//
+// 沒有清單時遞迴收集目錄下所有 .nes 檔，全部以 $6000 協定判定。
+//
+// 參考：https://www.nesdev.org/wiki/Emulator_tests
+// ============================================================
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::emulator::Emulator;
+
+/// 清單檔名
+pub const MANIFEST_NAME: &str = "accuracy.txt";
+
+/// 未指定幀數時最多執行的幀數（約 60 秒）
+pub const DEFAULT_MAX_FRAMES: u32 = 3600;
+
+/// 一個測試 ROM 的設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccuracyTest {
+    /// ROM 路徑（相對於測試目錄）
+    pub rom: PathBuf,
+    /// 最多執行的幀數（畫面雜湊模式為固定執行的幀數）
+    pub frames: u32,
+    /// 預期的畫面雜湊；None 表示使用 $6000 協定
+    pub expected_hash: Option<u32>,
+}
+
+/// 單一測試的結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccuracyOutcome {
+    /// 通過
+    Passed,
+    /// $6000 回報非 0 結果碼
+    Failed(u8),
+    /// 畫面雜湊與預期不符（實際值）
+    HashMismatch(u32),
+    /// 超過幀數仍未回報結果
+    Timeout,
+    /// ROM 無法讀取或載入
+    Error(String),
+}
+
+/// 單一測試的報告
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccuracyResult {
+    /// 測試設定
+    pub test: AccuracyTest,
+    /// 結果
+    pub outcome: AccuracyOutcome,
+    /// 實際執行的幀數
+    pub frames_run: u64,
+    /// 結束時的畫面雜湊
+    pub frame_hash: u32,
+    /// $6004 結果訊息
+    pub message: String,
+}
+
+/// 整個目錄的報告
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccuracyReport {
+    /// 各測試結果（依清單或檔名排序）
+    pub results: Vec<AccuracyResult>,
+}
+
+impl AccuracyOutcome {
+    /// 報告中的狀態字串
+    pub fn status(&self) -> &'static str {
+        match self {
+            AccuracyOutcome::Passed => "pass",
+            AccuracyOutcome::Failed(_) | AccuracyOutcome::HashMismatch(_) => "fail",
+            AccuracyOutcome::Timeout => "timeout",
+            AccuracyOutcome::Error(_) => "error",
+        }
+    }
+}
+
+/// 解析清單內容；格式錯誤的行回傳 Err（含行號）
+pub fn parse_manifest(text: &str) -> Result<Vec<AccuracyTest>, String> {
+    let mut tests = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let rom = PathBuf::from(fields.next().unwrap_or_default());
+        let frames = match fields.next() {
+            Some(f) => f.parse().map_err(|_| format!("第 {} 行：幀數無效 {f}", line_no + 1))?,
+            None => DEFAULT_MAX_FRAMES,
+        };
+        let expected_hash = match fields.next() {
+            Some(h) => Some(
+                u32::from_str_radix(h.trim_start_matches("0x"), 16)
+                    .map_err(|_| format!("第 {} 行：畫面雜湊無效 {h}", line_no + 1))?,
+            ),
+            None => None,
+        };
+        tests.push(AccuracyTest { rom, frames, expected_hash });
+    }
+    Ok(tests)
+}
+
+/// 讀取目錄的測試清單：有 accuracy.txt 時依清單，否則收集所有 .nes 檔
+pub fn collect_tests(dir: &Path) -> Result<Vec<AccuracyTest>, String> {
+    let manifest = dir.join(MANIFEST_NAME);
+    if manifest.exists() {
+        let text = std::fs::read_to_string(&manifest)
+            .map_err(|e| format!("無法讀取 {}：{e}", manifest.display()))?;
+        return parse_manifest(&text);
+    }
+    let mut roms = Vec::new();
+    find_roms(dir, dir, &mut roms).map_err(|e| format!("無法讀取 {}：{e}", dir.display()))?;
+    roms.sort();
+    Ok(roms
+        .into_iter()
+        .map(|rom| AccuracyTest { rom, frames: DEFAULT_MAX_FRAMES, expected_hash: None })
+        .collect())
+}
+
+fn find_roms(root: &Path, dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_roms(root, &path, out)?;
+        } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("nes")) {
+            out.push(path.strip_prefix(root).unwrap_or(&path).to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+/// 執行單一測試 ROM
+pub fn run_test(rom: &[u8], test: &AccuracyTest) -> AccuracyResult {
+    let mut emu = Emulator::new();
+    let mut result = AccuracyResult {
+        test: test.clone(),
+        outcome: AccuracyOutcome::Error("ROM 載入失敗".to_string()),
+        frames_run: 0,
+        frame_hash: 0,
+        message: String::new(),
+    };
+    if !emu.load_rom(rom) {
+        return result;
+    }
+
+    result.outcome = match test.expected_hash {
+        Some(expected) => {
+            emu.run_frames(test.frames);
+            match emu.frame_hash() {
+                hash if hash == expected => AccuracyOutcome::Passed,
+                hash => AccuracyOutcome::HashMismatch(hash),
+            }
+        }
+        None => match emu.run_test_rom(test.frames) {
+            Some(0) => AccuracyOutcome::Passed,
+            Some(code) => AccuracyOutcome::Failed(code),
+            None => AccuracyOutcome::Timeout,
+        },
+    };
+    result.frames_run = emu.frame_count();
+    result.frame_hash = emu.frame_hash();
+    result.message = emu.test_rom_message();
+    result
+}
+
+/// 執行目錄中的所有測試
+pub fn run_directory(dir: &Path) -> Result<AccuracyReport, String> {
+    let results = collect_tests(dir)?
+        .into_iter()
+        .map(|test| match std::fs::read(dir.join(&test.rom)) {
+            Ok(rom) => run_test(&rom, &test),
+            Err(e) => AccuracyResult {
+                outcome: AccuracyOutcome::Error(e.to_string()),
+                test,
+                frames_run: 0,
+                frame_hash: 0,
+                message: String::new(),
+            },
+        })
+        .collect();
+    Ok(AccuracyReport { results })
+}
+
+impl AccuracyReport {
+    /// 通過的測試數
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|r| r.outcome == AccuracyOutcome::Passed).count()
+    }
+
+    /// 輸出 JSON 報告
+    pub fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\"version\":\"{}\",\"total\":{},\"passed\":{},\"results\":[",
+            env!("CARGO_PKG_VERSION"), self.results.len(), self.passed(),
+        );
+        for (i, r) in self.results.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str("{\"rom\":");
+            push_json_string(&mut json, &r.test.rom.to_string_lossy().replace('\\', "/"));
+            let _ = write!(
+                json,
+                ",\"status\":\"{}\",\"frames\":{},\"frameHash\":\"{:08x}\"",
+                r.outcome.status(), r.frames_run, r.frame_hash,
+            );
+            match &r.outcome {
+                AccuracyOutcome::Failed(code) => { let _ = write!(json, ",\"code\":{code}"); }
+                AccuracyOutcome::HashMismatch(_) => {
+                    let _ = write!(json, ",\"expectedHash\":\"{:08x}\"", r.test.expected_hash.unwrap_or(0));
+                }
+                AccuracyOutcome::Error(e) => {
+                    json.push_str(",\"error\":");
+                    push_json_string(&mut json, e);
+                }
+                AccuracyOutcome::Passed | AccuracyOutcome::Timeout => {}
+            }
+            json.push_str(",\"message\":");
+            push_json_string(&mut json, r.message.trim_end());
+            json.push('}');
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+/// 寫入加上引號與跳脫字元的 JSON 字串
+fn push_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
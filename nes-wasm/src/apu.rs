@@ -8,17 +8,83 @@
 // - 1 個 DMC（Delta Modulation Channel）聲道
 //
 // 以及幀計數器（Frame Counter）和混音器。
+// 混音器可以個別靜音聲道（含卡帶擴充音效），方便音樂播放器獨奏或
+// 排查音效問題；聲道本身照常運作，只是不加進輸出。
+//
+// 卡帶擴充音源（VRC6、VRC7、FDS、Namco 163、Sunsoft 5B）實作
+// ExpansionAudio，由 Mapper 交給 APU 隨 CPU 週期驅動並加進混音。
+// 各晶片的增益可以個別調整，因為不同卡帶與主機改裝的音量差異很大。
+//
+// 混音結果每個 CPU 週期交給 audio::BlipSynth 做頻寬受限的降頻，輸出取樣放進
+// audio::AudioRing 環形緩衝區等待前端取用。
+//
+// 立體聲模式下每個聲道依 pan（-1.0 最左、1.0 最右）分別混出左右兩路，
+// 緩衝區改為 L/R 交錯；pan 為 0 的聲道左右都與單聲道相同。
+//
+// 三角波預設在停止或週期小於 2（超音波）時靜音，聽起來乾淨但會改變
+// DAC 的輸出準位，而非線性混音下這會影響雜訊與 DMC 的音量。準確模式
+// 照實機讓序列器繼續計時、停止時保持最後的輸出。靜音模式下可以另外開啟
+// 「減少爆音」，讓輸出從停止時的準位逐步降到 0 而不是直接跳下去。
 //
 // 參考資料：
 // - https://www.nesdev.org/wiki/APU
 // - https://www.nesdev.org/wiki/APU_Mixer
+// - https://www.nesdev.org/wiki/Expansion_audio
 // ============================================================
 
-/// 音頻緩衝區大小（足夠儲存一幀的取樣）
-const AUDIO_BUFFER_SIZE: usize = 8192;
+/// 音頻環形緩衝區容量（44.1kHz 約 0.37 秒，前端取用不穩定時仍有餘裕）
+const AUDIO_BUFFER_SIZE: usize = 16384;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::audio::{AudioRing, BlipSynth, OutputFilter};
+use crate::config::Region;
+use crate::savestate::{impl_state_fields, StateField, StateReader};
+
+/// 混音器的聲道（數值即 channel_levels 中的索引）
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioChannel {
+    Pulse1 = 0,
+    Pulse2 = 1,
+    Triangle = 2,
+    Noise = 3,
+    Dmc = 4,
+    /// 卡帶擴充音效（VRC6、Namco 163 等）
+    Expansion = 5,
+}
 
-/// NES CPU 時鐘頻率（NTSC）
-const CPU_CLOCK_RATE: f64 = 1789773.0;
+/// 混音器聲道數
+pub const AUDIO_CHANNEL_COUNT: usize = 6;
+
+/// 卡帶擴充音源晶片（數值即增益表的索引）
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpansionChip {
+    Vrc6 = 0,
+    Vrc7 = 1,
+    Fds = 2,
+    N163 = 3,
+    Sunsoft5b = 4,
+}
+
+/// 擴充音源晶片種類數
+pub const EXPANSION_CHIP_COUNT: usize = 5;
+
+/// 卡帶上的擴充音源
+///
+/// APU 每個 CPU 週期呼叫一次 `clock`，再把 `output` 乘上該晶片的增益加進混音。
+pub trait ExpansionAudio {
+    /// 晶片種類（決定套用哪個增益）
+    fn chip(&self) -> ExpansionChip;
+
+    /// 推進一個 CPU 週期
+    fn clock(&mut self);
+
+    /// 目前的輸出，已換算成與 2A03 混音結果相同的尺度
+    fn output(&self) -> f32;
+}
 
 /// 脈衝波占空比查詢表
 /// 4 種不同的占空比波形，每種 8 步
@@ -35,15 +101,49 @@ const TRIANGLE_TABLE: [u8; 32] = [
     0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
 ];
 
-/// 雜訊聲道的週期查詢表（NTSC）
-const NOISE_PERIOD_TABLE: [u16; 16] = [
-    4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
-];
+/// 地區相關的 APU 時序參數
+///
+/// PAL 的 CPU 時鐘較慢，雜訊/DMC 週期表與幀計數器的步點也跟著調整，
+/// 讓音高與節奏接近 NTSC 版本。
+/// 參考：https://www.nesdev.org/wiki/Cycle_reference_chart
+struct RegionTiming {
+    /// CPU 時鐘頻率（Hz）
+    cpu_clock_rate: f64,
+    /// 雜訊聲道的週期查詢表
+    noise_periods: [u16; 16],
+    /// DMC 聲道的速率查詢表
+    dmc_rates: [u16; 16],
+    /// 幀計數器的步點（CPU 週期）：前 3 項為兩種模式共用的步驟，
+    /// 第 4 項為 4 步模式的最後一步，第 5 項為 5 步模式的最後一步
+    frame_steps: [u16; 5],
+}
 
-/// DMC 聲道的速率查詢表（NTSC）
-const DMC_RATE_TABLE: [u16; 16] = [
-    428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
-];
+/// NTSC（2C02）時序
+const NTSC_TIMING: RegionTiming = RegionTiming {
+    cpu_clock_rate: 1789773.0,
+    noise_periods: [4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068],
+    dmc_rates: [428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54],
+    frame_steps: [7457, 14913, 22371, 29829, 37281],
+};
+
+/// Dendy 時序：APU 的週期表與幀計數器同 NTSC，只有 CPU 時鐘
+/// （26.6017 MHz / 15）比 NTSC 慢，取樣間隔依此換算
+const DENDY_TIMING: RegionTiming = RegionTiming {
+    cpu_clock_rate: 1773448.0,
+    ..NTSC_TIMING
+};
+
+/// PAL（2C07）時序
+const PAL_TIMING: RegionTiming = RegionTiming {
+    cpu_clock_rate: 1662607.0,
+    noise_periods: [4, 8, 14, 30, 60, 88, 118, 148, 188, 236, 354, 472, 708, 944, 1890, 3778],
+    dmc_rates: [398, 354, 316, 298, 276, 236, 210, 198, 176, 148, 132, 118, 98, 78, 66, 50],
+    frame_steps: [8313, 16627, 24939, 33253, 41565],
+};
+
+/// 減少爆音模式下，三角波靜音後每隔幾個 CPU 週期把輸出降一階
+/// （從 15 降到 0 約 0.5 毫秒，高到不會聽成另一個音）
+const TRIANGLE_RAMP_PERIOD: u8 = 64;
 
 /// 長度計數器查詢表
 const LENGTH_TABLE: [u8; 32] = [
@@ -51,6 +151,43 @@ const LENGTH_TABLE: [u8; 32] = [
     12, 16, 24, 18, 48, 20, 96, 22, 192, 24, 72, 26, 16, 28, 32, 30,
 ];
 
+/// 長度計數器尚未生效的寫入
+///
+/// 硬體在寫入的那個週期先執行長度時鐘才套用新值：同一週期被時鐘過
+/// （計數值因此改變）的重新載入會被忽略，停止旗標也是時鐘之後才改變。
+#[derive(Debug, Clone, Copy, Default)]
+struct LengthWrite {
+    /// 寫入的停止旗標
+    halt: bool,
+    /// 等待載入的值（0 表示沒有）
+    reload: u8,
+    /// 寫入時的計數值
+    previous: u8,
+}
+
+impl_state_fields!(LengthWrite { halt, reload, previous });
+
+impl LengthWrite {
+    /// 記下載入值（聲道停用時寫入無效）
+    fn queue(&mut self, enabled: bool, index: u8, counter: u8) {
+        if enabled {
+            self.reload = LENGTH_TABLE[(index >> 3) as usize];
+            self.previous = counter;
+        }
+    }
+
+    /// 在這個週期的長度時鐘之後套用寫入
+    fn apply(&mut self, counter: &mut u8, halt: &mut bool) {
+        if self.reload != 0 {
+            if *counter == self.previous {
+                *counter = self.reload;
+            }
+            self.reload = 0;
+        }
+        *halt = self.halt;
+    }
+}
+
 // ===== 脈衝波聲道 =====
 
 /// 脈衝波聲道（Pulse）
@@ -77,6 +214,8 @@ struct PulseChannel {
     length_halt: bool,
     /// 長度計數器
     length_counter: u8,
+    /// 尚未生效的長度計數器寫入
+    length_write: LengthWrite,
 
     // 包絡線
     /// 包絡線啟用
@@ -120,6 +259,7 @@ impl PulseChannel {
             timer_value: 0,
             length_halt: false,
             length_counter: 0,
+            length_write: LengthWrite::default(),
             envelope_enabled: true,
             envelope_loop: false,
             envelope_start: false,
@@ -139,7 +279,7 @@ impl PulseChannel {
     /// 寫入暫存器 $4000/$4004
     fn write_ctrl(&mut self, data: u8) {
         self.duty = (data >> 6) & 0x03;
-        self.length_halt = data & 0x20 != 0;
+        self.length_write.halt = data & 0x20 != 0;
         self.envelope_loop = data & 0x20 != 0;
         self.envelope_enabled = data & 0x10 == 0;
         self.envelope_period = data & 0x0F;
@@ -163,9 +303,7 @@ impl PulseChannel {
     /// 寫入暫存器 $4003/$4007（長度計數器載入 + 定時器高位元組）
     fn write_length(&mut self, data: u8) {
         self.timer_period = (self.timer_period & 0x00FF) | ((data as u16 & 0x07) << 8);
-        if self.enabled {
-            self.length_counter = LENGTH_TABLE[(data >> 3) as usize];
-        }
+        self.length_write.queue(self.enabled, data, self.length_counter);
         self.duty_pos = 0;
         self.envelope_start = true;
     }
@@ -259,6 +397,13 @@ impl PulseChannel {
     }
 }
 
+impl_state_fields!(PulseChannel {
+    enabled, duty, duty_pos, timer_period, timer_value, length_halt, length_counter,
+    envelope_enabled, envelope_loop, envelope_start, envelope_period, envelope_divider,
+    envelope_decay, constant_volume,
+    sweep_enabled, sweep_negate, sweep_reload, sweep_period, sweep_shift, sweep_divider,
+});
+
 // ===== 三角波聲道 =====
 
 /// 三角波聲道（Triangle）
@@ -275,12 +420,18 @@ struct TriangleChannel {
     length_halt: bool,
     /// 長度計數器
     length_counter: u8,
+    /// 尚未生效的長度計數器寫入（停止旗標同時是線性計數器控制，立即生效）
+    length_write: LengthWrite,
     /// 線性計數器
     linear_counter: u8,
     /// 線性計數器重載值
     linear_counter_reload: u8,
     /// 線性計數器重載旗標
     linear_counter_reload_flag: bool,
+    /// 減少爆音模式的輸出準位（發聲時跟隨序列器，靜音後逐步降到 0）
+    ramp: u8,
+    /// 距離下一次降低準位的週期數
+    ramp_timer: u8,
 }
 
 impl TriangleChannel {
@@ -292,15 +443,18 @@ impl TriangleChannel {
             sequence_pos: 0,
             length_halt: false,
             length_counter: 0,
+            length_write: LengthWrite::default(),
             linear_counter: 0,
             linear_counter_reload: 0,
             linear_counter_reload_flag: false,
+            ramp: 0,
+            ramp_timer: TRIANGLE_RAMP_PERIOD,
         }
     }
 
     /// 寫入暫存器 $4008
     fn write_ctrl(&mut self, data: u8) {
-        self.length_halt = data & 0x80 != 0;
+        self.length_write.halt = data & 0x80 != 0;
         self.linear_counter_reload = data & 0x7F;
     }
 
@@ -312,9 +466,7 @@ impl TriangleChannel {
     /// 寫入暫存器 $400B（長度計數器載入 + 定時器高位元組）
     fn write_length(&mut self, data: u8) {
         self.timer_period = (self.timer_period & 0x00FF) | ((data as u16 & 0x07) << 8);
-        if self.enabled {
-            self.length_counter = LENGTH_TABLE[(data >> 3) as usize];
-        }
+        self.length_write.queue(self.enabled, data, self.length_counter);
         self.linear_counter_reload_flag = true;
     }
 
@@ -337,7 +489,7 @@ impl TriangleChannel {
         } else if self.linear_counter > 0 {
             self.linear_counter -= 1;
         }
-        if !self.length_halt {
+        if !self.length_write.halt {
             self.linear_counter_reload_flag = false;
         }
     }
@@ -349,19 +501,48 @@ impl TriangleChannel {
         }
     }
 
-    /// 取得輸出值
-    fn output(&self) -> u8 {
-        if !self.enabled || self.length_counter == 0 || self.linear_counter == 0 {
-            return 0;
+    /// 序列器的輸出；非準確模式下停止或超音波的三角波為 None（靜音）
+    fn sequencer_output(&self, accurate: bool) -> Option<u8> {
+        // 實機的 DAC 永遠輸出序列器目前的位置：停止時保持最後的值，
+        // 超音波頻率則在兩端之間快速切換（降頻後平均約 7.5）
+        let playing = self.enabled && self.length_counter > 0 && self.linear_counter > 0 &&
+            self.timer_period >= 2;
+        (accurate || playing).then(|| TRIANGLE_TABLE[self.sequence_pos as usize])
+    }
+
+    /// 更新減少爆音模式的輸出準位（每個 CPU 週期）
+    fn clock_ramp(&mut self, accurate: bool) {
+        match self.sequencer_output(accurate) {
+            Some(level) => {
+                self.ramp = level;
+                self.ramp_timer = TRIANGLE_RAMP_PERIOD;
+            }
+            None if self.ramp > 0 => {
+                self.ramp_timer -= 1;
+                if self.ramp_timer == 0 {
+                    self.ramp -= 1;
+                    self.ramp_timer = TRIANGLE_RAMP_PERIOD;
+                }
+            }
+            None => {}
         }
-        // 過低的頻率會導致超音波，靜音以避免雜音
-        if self.timer_period < 2 {
-            return 0;
+    }
+
+    /// 取得輸出值
+    fn output(&self, accurate: bool, reduce_popping: bool) -> u8 {
+        match self.sequencer_output(accurate) {
+            Some(level) => level,
+            None if reduce_popping => self.ramp,
+            None => 0,
         }
-        TRIANGLE_TABLE[self.sequence_pos as usize]
     }
 }
 
+impl_state_fields!(TriangleChannel {
+    enabled, timer_period, timer_value, sequence_pos, length_halt, length_counter,
+    linear_counter, linear_counter_reload, linear_counter_reload_flag,
+});
+
 // ===== 雜訊聲道 =====
 
 /// 雜訊聲道（Noise）
@@ -380,6 +561,8 @@ struct NoiseChannel {
     length_halt: bool,
     /// 長度計數器
     length_counter: u8,
+    /// 尚未生效的長度計數器寫入
+    length_write: LengthWrite,
 
     // 包絡線（與脈衝波共用結構）
     envelope_enabled: bool,
@@ -401,6 +584,7 @@ impl NoiseChannel {
             timer_value: 0,
             length_halt: false,
             length_counter: 0,
+            length_write: LengthWrite::default(),
             envelope_enabled: true,
             envelope_loop: false,
             envelope_start: false,
@@ -413,7 +597,7 @@ impl NoiseChannel {
 
     /// 寫入暫存器 $400C
     fn write_ctrl(&mut self, data: u8) {
-        self.length_halt = data & 0x20 != 0;
+        self.length_write.halt = data & 0x20 != 0;
         self.envelope_loop = data & 0x20 != 0;
         self.envelope_enabled = data & 0x10 == 0;
         self.envelope_period = data & 0x0F;
@@ -421,16 +605,14 @@ impl NoiseChannel {
     }
 
     /// 寫入暫存器 $400E
-    fn write_mode(&mut self, data: u8) {
+    fn write_mode(&mut self, data: u8, periods: &[u16; 16]) {
         self.mode = data & 0x80 != 0;
-        self.timer_period = NOISE_PERIOD_TABLE[(data & 0x0F) as usize];
+        self.timer_period = periods[(data & 0x0F) as usize];
     }
 
     /// 寫入暫存器 $400F
     fn write_length(&mut self, data: u8) {
-        if self.enabled {
-            self.length_counter = LENGTH_TABLE[(data >> 3) as usize];
-        }
+        self.length_write.queue(self.enabled, data, self.length_counter);
         self.envelope_start = true;
     }
 
@@ -486,6 +668,12 @@ impl NoiseChannel {
     }
 }
 
+impl_state_fields!(NoiseChannel {
+    enabled, shift_register, mode, timer_period, timer_value, length_halt, length_counter,
+    envelope_enabled, envelope_loop, envelope_start, envelope_period, envelope_divider,
+    envelope_decay, constant_volume,
+});
+
 // ===== DMC 聲道 =====
 
 /// DMC 聲道（Delta Modulation Channel）
@@ -524,16 +712,18 @@ struct DmcChannel {
     silence: bool,
     /// IRQ 旗標
     irq_flag: bool,
+    /// $4015 啟用後還要幾個 CPU 週期才開始讀取第一個位元組
+    start_delay: u8,
 }
 
 impl DmcChannel {
-    fn new() -> Self {
+    fn new(rates: &[u16; 16]) -> Self {
         DmcChannel {
             enabled: false,
             irq_enabled: false,
             loop_flag: false,
             rate_index: 0,
-            timer_period: DMC_RATE_TABLE[0],
+            timer_period: rates[0],
             timer_value: 0,
             output_level: 0,
             sample_address: 0xC000,
@@ -546,15 +736,16 @@ impl DmcChannel {
             sample_buffer_empty: true,
             silence: true,
             irq_flag: false,
+            start_delay: 0,
         }
     }
 
     /// 寫入暫存器 $4010
-    fn write_ctrl(&mut self, data: u8) {
+    fn write_ctrl(&mut self, data: u8, rates: &[u16; 16]) {
         self.irq_enabled = data & 0x80 != 0;
         self.loop_flag = data & 0x40 != 0;
         self.rate_index = data & 0x0F;
-        self.timer_period = DMC_RATE_TABLE[self.rate_index as usize];
+        self.timer_period = rates[self.rate_index as usize];
         if !self.irq_enabled {
             self.irq_flag = false;
         }
@@ -581,12 +772,42 @@ impl DmcChannel {
         self.bytes_remaining = self.sample_length;
     }
 
+    /// 讀取的位元組放進緩衝區，推進位址與剩餘位元組數；讀完最後一個
+    /// 位元組時依循環旗標重新開始或發出 IRQ
+    fn fill_buffer(&mut self, data: u8) {
+        // DMA 進行中被 $4015 停用時，讀到的位元組不會放進緩衝區
+        if self.bytes_remaining == 0 {
+            return;
+        }
+        self.sample_buffer = data;
+        self.sample_buffer_empty = false;
+        self.current_address = if self.current_address == 0xFFFF {
+            0x8000
+        } else {
+            self.current_address + 1
+        };
+        self.bytes_remaining -= 1;
+        if self.bytes_remaining == 0 {
+            if self.loop_flag {
+                self.restart();
+            } else if self.irq_enabled {
+                self.irq_flag = true;
+            }
+        }
+    }
+
     /// 取得輸出值
     fn output(&self) -> u8 {
         self.output_level
     }
 }
 
+impl_state_fields!(DmcChannel {
+    enabled, irq_enabled, loop_flag, rate_index, timer_period, timer_value, output_level,
+    sample_address, sample_length, current_address, bytes_remaining, shift_register,
+    bits_remaining, sample_buffer, sample_buffer_empty, silence, irq_flag,
+});
+
 // ===== APU 主結構 =====
 
 /// APU 結構體
@@ -613,59 +834,122 @@ pub struct Apu {
     frame_irq_inhibit: bool,
     /// 幀 IRQ 旗標
     frame_irq: bool,
+    /// 等待生效的 $4017 寫入值
+    frame_write: u8,
+    /// $4017 寫入還要幾個 CPU 週期才生效（0 表示沒有等待中的寫入）
+    frame_write_delay: u8,
 
     // 時序
     /// CPU 週期計數
     cycle: u64,
+    /// 目前地區的時序參數
+    timing: &'static RegionTiming,
 
     // 音頻輸出
     /// 取樣率
     sample_rate: f64,
-    /// 取樣計數器（用於音頻降頻取樣）
+    /// 取樣計數器（距離上一個輸出取樣的 CPU 週期數）
     sample_counter: f64,
     /// 取樣間隔（每個取樣之間的 CPU 週期數）
     sample_interval: f64,
-    /// 音頻輸出緩衝區
-    pub audio_buffer: Vec<f32>,
-    /// 緩衝區寫入位置
-    buffer_write_pos: usize,
-
-    // 濾波器（減少爆音和直流偏移）
-    /// 低通濾波器累加器
-    filter_accumulator: f32,
-    /// 高通濾波器前一個輸入值
-    highpass_prev: f32,
-    /// 高通濾波器前一個輸出值
-    highpass_output: f32,
+    /// 頻寬受限的階躍合成器（狀態在存檔的 v13 區段；立體聲時為左聲道）
+    pub(crate) synth: BlipSynth,
+    /// 音頻輸出環形緩衝區
+    audio_buffer: AudioRing,
+
+    /// 輸出濾波器（減少爆音和直流偏移；立體聲時為左聲道）
+    filter: OutputFilter,
+    /// 是否啟用輸出濾波器
+    filter_enabled: bool,
+    /// 三角波照實機行為（超音波繼續計時、停止時保持輸出）
+    accurate_triangle: bool,
+    /// 三角波靜音時逐步降低輸出以減少爆音（只在非準確模式下有作用）
+    reduce_popping: bool,
+
+    // 立體聲（右聲道的降頻與濾波狀態不存檔，讀檔後從左聲道複製）
+    /// 是否輸出 L/R 交錯的立體聲
+    stereo: bool,
+    /// 各聲道的左右位置（依 AudioChannel 的順序，-1.0 最左、1.0 最右）
+    channel_pan: [f32; AUDIO_CHANNEL_COUNT],
+    /// 右聲道的階躍合成器
+    right_synth: BlipSynth,
+    /// 右聲道的輸出濾波器
+    right_filter: OutputFilter,
 
     /// DMC 記憶體讀取請求（需要由匯流排處理）
     pub dmc_read_request: Option<u16>,
+
+    /// 卡帶擴充音效的目前輸出（clock_expansion 取得，尚未乘上增益）
+    expansion_output: f32,
+    /// 目前輸出的擴充音源晶片
+    expansion_chip: Option<ExpansionChip>,
+    /// 各擴充音源晶片的增益（依 ExpansionChip 的順序）
+    expansion_gain: [f32; EXPANSION_CHIP_COUNT],
+    /// 混音時啟用的聲道（位元 n 對應 AudioChannel n）
+    channel_mask: u8,
+}
+
+// 取樣率、濾波開關、增益與輸出緩衝區屬於設定或前端，不存檔
+impl_state_fields!(Apu {
+    pulse1, pulse2, triangle, noise, dmc,
+    frame_mode, frame_step, frame_value, frame_irq_inhibit, frame_irq, cycle,
+    sample_counter, filter,
+    dmc_read_request, expansion_output,
+});
+
+impl Default for Apu {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Apu {
     /// 建立新的 APU 實例
+    ///
+    /// 每個 CPU 週期呼叫一次 `clock`，取樣依 `set_sample_rate` 的頻率產生：
+    ///
+    /// ```
+    /// let mut apu = nes_wasm::apu::Apu::new();
+    /// apu.set_sample_rate(48000.0);
+    /// for _ in 0..29781 {
+    ///     apu.clock();
+    /// }
+    /// assert!(apu.get_available_samples() > 0);
+    /// ```
     pub fn new() -> Self {
         Apu {
             pulse1: PulseChannel::new(1),
             pulse2: PulseChannel::new(2),
             triangle: TriangleChannel::new(),
             noise: NoiseChannel::new(),
-            dmc: DmcChannel::new(),
+            dmc: DmcChannel::new(&NTSC_TIMING.dmc_rates),
             frame_mode: false,
             frame_step: 0,
             frame_value: 0,
             frame_irq_inhibit: false,
             frame_irq: false,
+            frame_write: 0,
+            frame_write_delay: 0,
             cycle: 0,
+            timing: &NTSC_TIMING,
             sample_rate: 44100.0,
             sample_counter: 0.0,
-            sample_interval: CPU_CLOCK_RATE / 44100.0,
-            audio_buffer: vec![0.0; AUDIO_BUFFER_SIZE],
-            buffer_write_pos: 0,
-            filter_accumulator: 0.0,
-            highpass_prev: 0.0,
-            highpass_output: 0.0,
+            sample_interval: NTSC_TIMING.cpu_clock_rate / 44100.0,
+            synth: BlipSynth::default(),
+            audio_buffer: AudioRing::new(AUDIO_BUFFER_SIZE),
+            filter: OutputFilter::default(),
+            filter_enabled: true,
+            accurate_triangle: false,
+            reduce_popping: false,
+            stereo: false,
+            channel_pan: [0.0; AUDIO_CHANNEL_COUNT],
+            right_synth: BlipSynth::default(),
+            right_filter: OutputFilter::default(),
             dmc_read_request: None,
+            expansion_output: 0.0,
+            expansion_chip: None,
+            expansion_gain: [1.0; EXPANSION_CHIP_COUNT],
+            channel_mask: (1 << AUDIO_CHANNEL_COUNT) - 1,
         }
     }
 
@@ -675,22 +959,218 @@ impl Apu {
         self.pulse2 = PulseChannel::new(2);
         self.triangle = TriangleChannel::new();
         self.noise = NoiseChannel::new();
-        self.dmc = DmcChannel::new();
+        self.dmc = DmcChannel::new(&self.timing.dmc_rates);
         self.frame_step = 0;
         self.frame_value = 0;
         self.frame_irq = false;
+        self.frame_write_delay = 0;
         self.cycle = 0;
         self.sample_counter = 0.0;
-        self.buffer_write_pos = 0;
-        self.filter_accumulator = 0.0;
-        self.highpass_prev = 0.0;
-        self.highpass_output = 0.0;
+        self.synth.reset();
+        self.audio_buffer.clear();
+        self.filter.reset();
+        self.right_synth.reset();
+        self.right_filter.reset();
+        self.expansion_output = 0.0;
+        self.expansion_chip = None;
+    }
+
+    /// 推進卡帶的擴充音源一個 CPU 週期並記下它的輸出（在 `clock` 之前呼叫）
+    #[inline]
+    pub fn clock_expansion(&mut self, source: &mut dyn ExpansionAudio) {
+        source.clock();
+        self.expansion_output = source.output();
+        self.expansion_chip = Some(source.chip());
+    }
+
+    /// 設定擴充音源晶片的增益（1.0 為預設音量，負值視為 0）
+    pub fn set_expansion_gain(&mut self, chip: ExpansionChip, gain: f32) {
+        self.expansion_gain[chip as usize] = gain.max(0.0);
+    }
+
+    /// 擴充音源晶片目前的增益
+    pub fn expansion_gain(&self, chip: ExpansionChip) -> f32 {
+        self.expansion_gain[chip as usize]
     }
 
     /// 設定取樣率
     pub fn set_sample_rate(&mut self, rate: f64) {
         self.sample_rate = rate;
-        self.sample_interval = CPU_CLOCK_RATE / rate;
+        self.sample_interval = self.timing.cpu_clock_rate / rate;
+    }
+
+    /// 依主機地區切換時序參數（CPU 時鐘、雜訊/DMC 週期表、幀計數器步點）
+    ///
+    /// 已寫入的雜訊/DMC 週期維持原值，下次寫入暫存器時才換成新的週期表。
+    pub fn set_region(&mut self, region: Region) {
+        self.timing = match region {
+            Region::Pal => &PAL_TIMING,
+            Region::Dendy => &DENDY_TIMING,
+            Region::Ntsc => &NTSC_TIMING,
+        };
+        self.set_sample_rate(self.sample_rate);
+    }
+
+    /// 讀回存檔中的聲道與幀計數器狀態，查表用的索引遮罩到有效範圍
+    pub fn load_channel_state(&mut self, r: &mut StateReader) -> Option<()> {
+        self.load(r)?;
+        for pulse in [&mut self.pulse1, &mut self.pulse2] {
+            pulse.duty &= 0x03;
+            pulse.duty_pos &= 0x07;
+        }
+        self.triangle.sequence_pos &= 0x1F;
+        self.dmc.rate_index &= 0x0F;
+        self.dmc.timer_period = self.dmc.timer_period.max(1);
+        self.dmc.bits_remaining = self.dmc.bits_remaining.clamp(1, 8);
+        Some(())
+    }
+
+    /// 存檔 v14 區段：等待中的 $4017 寫入與長度計數器寫入
+    pub(crate) fn save_pending_writes(&self, out: &mut Vec<u8>) {
+        self.frame_write.save(out);
+        self.frame_write_delay.save(out);
+        self.pulse1.length_write.save(out);
+        self.pulse2.length_write.save(out);
+        self.triangle.length_write.save(out);
+        self.noise.length_write.save(out);
+    }
+
+    /// 讀回 v14 區段；較舊的存檔沒有這些狀態，幀計數器值也還是以
+    /// 一半的週期數計算，換算成目前的步點
+    pub(crate) fn load_pending_writes(&mut self, r: &mut StateReader, version: u8) -> Option<()> {
+        if version >= 14 {
+            self.frame_write.load(r)?;
+            self.frame_write_delay.load(r)?;
+            self.frame_write_delay = self.frame_write_delay.min(4);
+            self.pulse1.length_write.load(r)?;
+            self.pulse2.length_write.load(r)?;
+            self.triangle.length_write.load(r)?;
+            self.noise.length_write.load(r)?;
+        } else {
+            self.frame_value = self.frame_value.saturating_mul(2);
+            self.frame_write_delay = 0;
+            self.pulse1.length_write = LengthWrite { halt: self.pulse1.length_halt, ..LengthWrite::default() };
+            self.pulse2.length_write = LengthWrite { halt: self.pulse2.length_halt, ..LengthWrite::default() };
+            self.triangle.length_write = LengthWrite { halt: self.triangle.length_halt, ..LengthWrite::default() };
+            self.noise.length_write = LengthWrite { halt: self.noise.length_halt, ..LengthWrite::default() };
+        }
+        Some(())
+    }
+
+    /// 存檔 v15 區段：三角波減少爆音模式的輸出準位
+    pub(crate) fn save_triangle_ramp(&self, out: &mut Vec<u8>) {
+        self.triangle.ramp.save(out);
+        self.triangle.ramp_timer.save(out);
+    }
+
+    /// 讀回 v15 區段；較舊的存檔從 0 開始
+    pub(crate) fn load_triangle_ramp(&mut self, r: &mut StateReader, version: u8) -> Option<()> {
+        if version >= 15 {
+            self.triangle.ramp.load(r)?;
+            self.triangle.ramp_timer.load(r)?;
+        } else {
+            self.triangle.ramp = 0;
+        }
+        self.triangle.ramp &= 0x0F;
+        self.triangle.ramp_timer = self.triangle.ramp_timer.clamp(1, TRIANGLE_RAMP_PERIOD);
+        Some(())
+    }
+
+    /// 存檔 v16 區段：DMC 第一次讀取前的延遲
+    pub(crate) fn save_dmc_timing(&self, out: &mut Vec<u8>) {
+        self.dmc.start_delay.save(out);
+    }
+
+    /// 讀回 v16 區段；較舊存檔的 DMC 定時器以 APU 週期計數，換算成 CPU 週期
+    pub(crate) fn load_dmc_timing(&mut self, r: &mut StateReader, version: u8) -> Option<()> {
+        if version >= 16 {
+            self.dmc.start_delay.load(r)?;
+            self.dmc.start_delay = self.dmc.start_delay.min(3);
+        } else {
+            self.dmc.start_delay = 0;
+            self.dmc.timer_value = self.dmc.timer_value.saturating_mul(2).min(self.dmc.timer_period - 1);
+        }
+        Some(())
+    }
+
+    /// 在混音中啟用或靜音聲道
+    pub fn set_channel_enabled(&mut self, channel: AudioChannel, enabled: bool) {
+        let bit = 1 << channel as u8;
+        if enabled {
+            self.channel_mask |= bit;
+        } else {
+            self.channel_mask &= !bit;
+        }
+    }
+
+    /// 聲道是否在混音中啟用
+    pub fn channel_enabled(&self, channel: AudioChannel) -> bool {
+        self.channel_mask & (1 << channel as u8) != 0
+    }
+
+    /// 各聲道目前的輸出準位（0.0-1.0，依 AudioChannel 的順序），靜音的聲道照樣回報
+    /// 2A03 聲道以 DAC 滿刻度正規化（脈衝/三角/雜訊 15、DMC 127），
+    /// 擴充音效為乘上增益前的原始值
+    pub fn channel_levels(&self) -> [f32; AUDIO_CHANNEL_COUNT] {
+        [
+            self.pulse1.output() as f32 / 15.0,
+            self.pulse2.output() as f32 / 15.0,
+            self.triangle_output() as f32 / 15.0,
+            self.noise.output() as f32 / 15.0,
+            self.dmc.output() as f32 / 127.0,
+            self.expansion_output,
+        ]
+    }
+
+    /// 切換立體聲輸出（緩衝區改為 L/R 交錯）；切換時清空尚未取出的取樣，
+    /// 避免前端把單聲道取樣當成交錯資料
+    pub fn set_stereo(&mut self, stereo: bool) {
+        if stereo != self.stereo {
+            self.stereo = stereo;
+            self.audio_buffer.clear();
+            self.sync_stereo_state();
+        }
+    }
+
+    /// 是否為立體聲輸出
+    pub fn is_stereo(&self) -> bool {
+        self.stereo
+    }
+
+    /// 設定聲道的左右位置（-1.0 最左、0.0 置中、1.0 最右），只影響立體聲輸出
+    pub fn set_channel_pan(&mut self, channel: AudioChannel, pan: f32) {
+        self.channel_pan[channel as usize] = if pan.is_nan() { 0.0 } else { pan.clamp(-1.0, 1.0) };
+    }
+
+    /// 聲道目前的左右位置
+    pub fn channel_pan(&self, channel: AudioChannel) -> f32 {
+        self.channel_pan[channel as usize]
+    }
+
+    /// 右聲道的降頻與濾波狀態改為與左聲道相同（讀檔或切換立體聲後）
+    pub(crate) fn sync_stereo_state(&mut self) {
+        self.right_synth = self.synth.clone();
+        self.right_filter = self.filter.clone();
+    }
+
+    /// 設定是否啟用輸出濾波器（關閉時直接輸出混音結果）
+    pub fn set_filter_enabled(&mut self, enabled: bool) {
+        self.filter_enabled = enabled;
+    }
+
+    /// 設定三角波是否照實機行為：超音波頻率繼續計時、停止時保持最後的輸出
+    pub fn set_accurate_triangle(&mut self, accurate: bool) {
+        self.accurate_triangle = accurate;
+    }
+
+    /// 設定三角波靜音時是否逐步降低輸出（減少爆音）
+    pub fn set_reduce_popping(&mut self, enabled: bool) {
+        self.reduce_popping = enabled;
+    }
+
+    /// 三角波目前的 DAC 輸出（0-15）
+    fn triangle_output(&self) -> u8 {
+        self.triangle.output(self.accurate_triangle, self.reduce_popping)
     }
 
     // ===== 暫存器讀寫 =====
@@ -714,10 +1194,10 @@ impl Apu {
             0x400B => self.triangle.write_length(data),
             // 雜訊
             0x400C => self.noise.write_ctrl(data),
-            0x400E => self.noise.write_mode(data),
+            0x400E => self.noise.write_mode(data, &self.timing.noise_periods),
             0x400F => self.noise.write_length(data),
             // DMC
-            0x4010 => self.dmc.write_ctrl(data),
+            0x4010 => self.dmc.write_ctrl(data, &self.timing.dmc_rates),
             0x4011 => self.dmc.write_direct_load(data),
             0x4012 => self.dmc.write_sample_addr(data),
             0x4013 => self.dmc.write_sample_length(data),
@@ -735,28 +1215,27 @@ impl Apu {
                 if !self.noise.enabled { self.noise.length_counter = 0; }
 
                 if self.dmc.enabled {
+                    // 重新開始時若緩衝區是空的，第一次讀取在 2-3 個週期後才開始
                     if self.dmc.bytes_remaining == 0 {
                         self.dmc.restart();
+                        if self.dmc.sample_buffer_empty {
+                            self.dmc.start_delay = if self.cycle.is_multiple_of(2) { 2 } else { 3 };
+                        }
                     }
                 } else {
                     self.dmc.bytes_remaining = 0;
                 }
                 self.dmc.irq_flag = false;
             }
-            // 幀計數器
+            // 幀計數器：IRQ 禁止立即生效，模式與重置要等 3-4 個 CPU 週期
+            // （寫在 APU 週期上等 3 個，寫在兩個 APU 週期之間等 4 個）
             0x4017 => {
-                self.frame_mode = data & 0x80 != 0;
                 self.frame_irq_inhibit = data & 0x40 != 0;
                 if self.frame_irq_inhibit {
                     self.frame_irq = false;
                 }
-                self.frame_step = 0;
-                self.frame_value = 0;
-                // 5 步模式下立即時鐘半幀和全幀
-                if self.frame_mode {
-                    self.clock_half_frame();
-                    self.clock_quarter_frame();
-                }
+                self.frame_write = data;
+                self.frame_write_delay = if self.cycle.is_multiple_of(2) { 3 } else { 4 };
             }
             _ => {}
         }
@@ -780,8 +1259,7 @@ impl Apu {
 
     /// 提供 DMC 記憶體讀取資料
     pub fn dmc_provide_sample(&mut self, data: u8) {
-        self.dmc.sample_buffer = data;
-        self.dmc.sample_buffer_empty = false;
+        self.dmc.fill_buffer(data);
         self.dmc_read_request = None;
     }
 
@@ -791,19 +1269,31 @@ impl Apu {
     pub fn clock(&mut self) {
         // 三角波每個 CPU 週期都計時
         self.triangle.clock_timer();
+        self.triangle.clock_ramp(self.accurate_triangle);
 
-        // 其他聲道每隔一個 CPU 週期計時（APU 週期）
-        if self.cycle % 2 == 0 {
+        // 脈衝波與雜訊每隔一個 CPU 週期計時（APU 週期）
+        if self.cycle.is_multiple_of(2) {
             self.pulse1.clock_timer();
             self.pulse2.clock_timer();
             self.noise.clock_timer();
-            self.clock_dmc();
         }
+        // DMC 的速率表以 CPU 週期為單位
+        self.clock_dmc();
 
-        // 幀計數器
+        // 幀計數器，之後才套用這個週期寫入的長度計數器
         self.clock_frame_counter();
-
-        // 音頻取樣
+        self.apply_length_writes();
+
+        // 音頻取樣：每個週期的混音連同它在輸出取樣週期中的位置交給合成器
+        let frac = (self.sample_counter / self.sample_interval) as f32;
+        if self.stereo {
+            let left = self.mix(|pan| (1.0 - pan).min(1.0));
+            let right = self.mix(|pan| (1.0 + pan).min(1.0));
+            self.synth.add(left, frac);
+            self.right_synth.add(right, frac);
+        } else {
+            self.synth.add(self.mix(|_| 1.0), frac);
+        }
         self.sample_counter += 1.0;
         if self.sample_counter >= self.sample_interval {
             self.sample_counter -= self.sample_interval;
@@ -813,13 +1303,13 @@ impl Apu {
         self.cycle += 1;
     }
 
-    /// DMC 時鐘
+    /// DMC 時鐘（每個 CPU 週期）
     /// 參考 NESdev wiki 和 TS 版本的正確 DMC 流程：
-    /// 1. 定時器倒數
+    /// 1. 定時器倒數（每個位元剛好 timer_period 個 CPU 週期）
     /// 2. 定時器歸零時：修改 output level → shift → bits 減到 0 → 從 buffer 載入 → fetch
     fn clock_dmc(&mut self) {
         if self.dmc.timer_value == 0 {
-            self.dmc.timer_value = self.dmc.timer_period;
+            self.dmc.timer_value = self.dmc.timer_period - 1;
 
             // Output cycle: 不管 enabled 狀態，只要不是 silence 就更新 output
             if !self.dmc.silence {
@@ -845,33 +1335,27 @@ impl Apu {
                     self.dmc.silence = false;
                     self.dmc.shift_register = self.dmc.sample_buffer;
                     self.dmc.sample_buffer_empty = true;
-                    // 嘗試獲取新的取樣
-                    self.fetch_dmc_sample();
                 }
             }
         } else {
             self.dmc.timer_value -= 1;
         }
+
+        // 記憶體讀取器獨立於輸出單元：緩衝區一空（包括剛以 $4015 啟用時）
+        // 就讀取下一個位元組，否則第一個位元組永遠不會被讀進來
+        if self.dmc.start_delay > 0 {
+            self.dmc.start_delay -= 1;
+        } else {
+            self.fetch_dmc_sample();
+        }
     }
 
     /// 從記憶體獲取 DMC 取樣
+    /// 上一次的讀取還在等 DMA 完成時不重複發出請求；位址、剩餘位元組數與
+    /// IRQ 在 DMA 讀到資料時才更新（dmc_provide_sample）
     fn fetch_dmc_sample(&mut self) {
-        if self.dmc.bytes_remaining > 0 && self.dmc.sample_buffer_empty {
+        if self.dmc.bytes_remaining > 0 && self.dmc.sample_buffer_empty && self.dmc_read_request.is_none() {
             self.dmc_read_request = Some(self.dmc.current_address);
-            self.dmc.current_address = if self.dmc.current_address == 0xFFFF {
-                0x8000
-            } else {
-                self.dmc.current_address + 1
-            };
-            self.dmc.bytes_remaining -= 1;
-
-            if self.dmc.bytes_remaining == 0 {
-                if self.dmc.loop_flag {
-                    self.dmc.restart();
-                } else if self.dmc.irq_enabled {
-                    self.dmc.irq_flag = true;
-                }
-            }
         }
     }
 
@@ -880,52 +1364,56 @@ impl Apu {
         // 幀計數器使用 CPU 週期計數
         self.frame_value += 1;
 
-        if !self.frame_mode {
-            // 4 步模式
-            match self.frame_value {
-                3729 => {
-                    self.clock_quarter_frame();
-                }
-                7457 => {
-                    self.clock_quarter_frame();
-                    self.clock_half_frame();
-                }
-                11186 => {
-                    self.clock_quarter_frame();
-                }
-                14915 => {
-                    self.clock_quarter_frame();
-                    self.clock_half_frame();
-                    if !self.frame_irq_inhibit {
-                        self.frame_irq = true;
-                    }
-                    self.frame_value = 0;
-                }
-                _ => {}
+        let [step1, step2, step3, step4, step5] = self.timing.frame_steps;
+        let value = self.frame_value;
+        if value == step1 || value == step3 {
+            self.clock_quarter_frame();
+        } else if value == step2 {
+            self.clock_quarter_frame();
+            self.clock_half_frame();
+        } else if !self.frame_mode && (step4 - 1..=step4 + 1).contains(&value) {
+            // 4 步模式：IRQ 旗標在最後一步前後連續 3 個週期設定，
+            // 在第一個週期讀 $4015 清除後還會再被設定
+            if !self.frame_irq_inhibit {
+                self.frame_irq = true;
             }
-        } else {
+            if value == step4 {
+                self.clock_quarter_frame();
+                self.clock_half_frame();
+            } else if value == step4 + 1 {
+                self.frame_value = 0;
+            }
+        } else if self.frame_mode && value == step5 {
             // 5 步模式（無 IRQ）
-            match self.frame_value {
-                3729 => {
-                    self.clock_quarter_frame();
-                }
-                7457 => {
-                    self.clock_quarter_frame();
+            self.clock_quarter_frame();
+            self.clock_half_frame();
+        } else if self.frame_mode && value == step5 + 1 {
+            self.frame_value = 0;
+        }
+
+        if self.frame_write_delay > 0 {
+            self.frame_write_delay -= 1;
+            if self.frame_write_delay == 0 {
+                self.frame_mode = self.frame_write & 0x80 != 0;
+                self.frame_step = 0;
+                self.frame_value = 0;
+                // 5 步模式下立即時鐘半幀和全幀
+                if self.frame_mode {
                     self.clock_half_frame();
-                }
-                11186 => {
                     self.clock_quarter_frame();
                 }
-                18641 => {
-                    self.clock_quarter_frame();
-                    self.clock_half_frame();
-                    self.frame_value = 0;
-                }
-                _ => {}
             }
         }
     }
 
+    /// 套用這個週期寫入的長度計數器載入值與停止旗標
+    fn apply_length_writes(&mut self) {
+        self.pulse1.length_write.apply(&mut self.pulse1.length_counter, &mut self.pulse1.length_halt);
+        self.pulse2.length_write.apply(&mut self.pulse2.length_counter, &mut self.pulse2.length_halt);
+        self.triangle.length_write.apply(&mut self.triangle.length_counter, &mut self.triangle.length_halt);
+        self.noise.length_write.apply(&mut self.noise.length_counter, &mut self.noise.length_halt);
+    }
+
     /// 四分之一幀時鐘（包絡線和線性計數器）
     fn clock_quarter_frame(&mut self) {
         self.pulse1.clock_envelope();
@@ -946,49 +1434,42 @@ impl Apu {
 
     // ===== 混音與輸出 =====
 
-    /// 輸出一個音頻取樣到緩衝區
+    /// 從合成器取出取樣、濾波後放進緩衝區（立體聲時依序放入 L、R）
     fn output_sample(&mut self) {
-        let mut sample = self.mix();
-
-        // 低通濾波器（減少高頻噪音 / 抗鋸齒）
-        const LOWPASS_COEFF: f32 = 0.9;
-        self.filter_accumulator = self.filter_accumulator * LOWPASS_COEFF +
-                                  sample * (1.0 - LOWPASS_COEFF);
-        sample = self.filter_accumulator;
-
-        // 高通濾波器（移除直流偏移）
-        const HIGHPASS_COEFF: f32 = 0.996;
-        let input = sample;
-        self.highpass_output = HIGHPASS_COEFF * self.highpass_output +
-                               input - self.highpass_prev;
-        self.highpass_prev = input;
-        sample = self.highpass_output;
-
-        // 縮放到合理範圍並加入軟削波防止爆音
-        sample *= 1.5;
-        if sample > 0.95 {
-            sample = 0.95 + (sample - 0.95) * 0.2;
-        } else if sample < -0.95 {
-            sample = -0.95 + (sample + 0.95) * 0.2;
-        }
-
-        // 最終限制在 [-1, 1] 範圍
-        sample = sample.max(-1.0).min(1.0);
-
-        if self.buffer_write_pos < self.audio_buffer.len() {
-            self.audio_buffer[self.buffer_write_pos] = sample;
-            self.buffer_write_pos += 1;
+        let sample = self.synth.next_sample();
+        let sample = self.filter.process(sample, self.filter_enabled);
+        self.audio_buffer.push(sample);
+        if self.stereo {
+            let right = self.right_synth.next_sample();
+            let right = self.right_filter.process(right, self.filter_enabled);
+            self.audio_buffer.push(right);
         }
     }
 
-    /// 混音器（使用 NESdev 非線性近似公式）
+    /// 混音器（使用 NESdev 非線性近似公式）；`gain` 把聲道的 pan 換算成
+    /// 這一路的音量（單聲道一律為 1.0）
     /// 參考：https://www.nesdev.org/wiki/APU_Mixer
-    fn mix(&self) -> f32 {
-        let p1 = self.pulse1.output() as f32;
-        let p2 = self.pulse2.output() as f32;
-        let t = self.triangle.output() as f32;
-        let n = self.noise.output() as f32;
-        let d = self.dmc.output() as f32;
+    #[inline]
+    fn mix(&self, gain: impl Fn(f32) -> f32) -> f32 {
+        let level = |channel: AudioChannel, output: u8| {
+            if self.channel_enabled(channel) {
+                output as f32 * gain(self.channel_pan[channel as usize])
+            } else {
+                0.0
+            }
+        };
+        let p1 = level(AudioChannel::Pulse1, self.pulse1.output());
+        let p2 = level(AudioChannel::Pulse2, self.pulse2.output());
+        let t = level(AudioChannel::Triangle, self.triangle_output());
+        let n = level(AudioChannel::Noise, self.noise.output());
+        let d = level(AudioChannel::Dmc, self.dmc.output());
+        let expansion = match self.expansion_chip {
+            Some(chip) if self.channel_enabled(AudioChannel::Expansion) => {
+                self.expansion_output * self.expansion_gain[chip as usize]
+                    * gain(self.channel_pan[AudioChannel::Expansion as usize])
+            }
+            _ => 0.0,
+        };
 
         // 脈衝波混音（非線性）
         let pulse_sum = p1 + p2;
@@ -1006,25 +1487,45 @@ impl Apu {
             0.0
         };
 
-        // 混音輸出範圍約 0.0 ~ 1.0
-        pulse_out + tnd_out
+        // 混音輸出範圍約 0.0 ~ 1.0，再加上卡帶擴充音效
+        pulse_out + tnd_out + expansion
     }
 
-    /// 取得音頻緩衝區指標
-    pub fn get_buffer_ptr(&self) -> *const f32 {
-        self.audio_buffer.as_ptr()
+    /// 取得音頻緩衝區指標（先把環形緩衝區的取樣搬到開頭，共
+    /// get_available_samples 個）
+    pub fn get_buffer_ptr(&mut self) -> *const f32 {
+        self.audio_buffer.make_contiguous().as_ptr()
     }
 
     /// 取得可用的取樣數
     pub fn get_available_samples(&self) -> usize {
-        self.buffer_write_pos
+        self.audio_buffer.len()
+    }
+
+    /// 音頻緩衝區容量（取樣數）
+    pub fn audio_buffer_capacity(&self) -> usize {
+        self.audio_buffer.capacity()
+    }
+
+    /// 前端來不及取用、因緩衝區已滿而丟棄的取樣總數
+    pub fn overflowed_samples(&self) -> u64 {
+        self.audio_buffer.overflowed()
     }
 
-    /// 消費音頻取樣（回傳取樣數並重置寫入位置）
+    /// 消費音頻取樣（回傳取樣數並清空緩衝區）
     pub fn consume_samples(&mut self) -> usize {
-        let count = self.buffer_write_pos;
-        self.buffer_write_pos = 0;
-        count
+        self.audio_buffer.clear()
+    }
+
+    /// 捨棄第 len 個之後的取樣（預先執行的幀不輸出聲音）
+    pub fn truncate_samples(&mut self, len: usize) {
+        self.audio_buffer.truncate(len);
+    }
+
+    /// 依序取出取樣到呼叫端的緩衝區，回傳實際取出的數量
+    /// 緩衝區放不下的取樣會保留到下次讀取
+    pub fn take_samples_into(&mut self, out: &mut [f32]) -> usize {
+        self.audio_buffer.read_into(out)
     }
 
     /// 檢查是否有 IRQ 待處理
@@ -0,0 +1,263 @@
+// ============================================================
+// 音訊輸出 - 環形緩衝區與降頻取樣
+// ============================================================
+// APU 每個 CPU 週期（約 1.79MHz）產生一個混音值，前端要的是 44.1/48kHz。
+//
+// 降頻使用頻寬受限的階躍合成（blip_buf 的做法）：混音值改變時，把這次
+// 的變化量以「頻寬受限的步階」加進差值緩衝區，步階的形狀依變化發生在
+// 兩個輸出取樣之間的位置，從預先算好的 BLIP_PHASES 組核心中挑選；輸出
+// 時再把差值累加回來。方波的邊緣因此被平滑到取樣率的奈奎斯特頻率以下，
+// 高音的脈衝波與三角波不會再有混疊雜音；混音值沒有改變的週期不需要運算。
+// 輸出固定延遲 BLIP_WIDTH / 2 個取樣。差值與核心都以定點整數運算，
+// 步階完全結束後輸出剛好等於輸入，不會累積浮點誤差造成直流漂移。
+//
+// 合成後的取樣經過 OutputFilter（低通、去除直流與軟削波）。立體聲模式
+// 左右聲道各有一組合成器與濾波器。
+//
+// 輸出取樣放進固定容量的環形緩衝區：JavaScript 取用的時間不穩定時，
+// 寫滿後覆寫最舊的取樣（保持延遲不增加）並記錄丟棄的數量，不再
+// 直接丟掉新的取樣。
+//
+// 參考：
+// - https://www.nesdev.org/wiki/APU_Mixer
+// - http://www.slack.net/~ant/bl-synth/
+// ============================================================
+
+use std::sync::OnceLock;
+
+use crate::savestate::impl_state_fields;
+
+/// 每個步階影響的輸出取樣數（核心長度）
+pub const BLIP_WIDTH: usize = 16;
+/// 兩個輸出取樣之間的步階位置分成幾段
+const BLIP_PHASES: usize = 32;
+/// 差值環形緩衝區長度（2 的次方，大於核心長度）
+const BLIP_RING: usize = 32;
+/// 截止頻率（相對於輸出取樣率，奈奎斯特為 0.5），稍低於奈奎斯特以保留過渡帶
+const BLIP_CUTOFF: f64 = 0.45;
+/// 混音值的定點小數位數
+const BLIP_INPUT_BITS: u32 = 30;
+/// 核心的定點小數位數（每組核心的總和剛好是 1 << BLIP_KERNEL_BITS）
+const BLIP_KERNEL_BITS: u32 = 15;
+
+/// 各相位的步階核心：Blackman 窗的 sinc，每組總和為 1（直流的步階高度不變）
+fn blip_kernels() -> &'static [[i32; BLIP_WIDTH]; BLIP_PHASES] {
+    static KERNELS: OnceLock<[[i32; BLIP_WIDTH]; BLIP_PHASES]> = OnceLock::new();
+    KERNELS.get_or_init(|| {
+        use std::f64::consts::PI;
+        let half = (BLIP_WIDTH / 2) as f64;
+        let mut kernels = [[0; BLIP_WIDTH]; BLIP_PHASES];
+        for (phase, kernel) in kernels.iter_mut().enumerate() {
+            let frac = phase as f64 / BLIP_PHASES as f64;
+            let mut taps = [0.0f64; BLIP_WIDTH];
+            for (k, tap) in taps.iter_mut().enumerate() {
+                // 第 k 個取樣相對於步階的時間（單位為輸出取樣）
+                let x = k as f64 - half + 1.0 - frac;
+                if x.abs() >= half {
+                    continue;
+                }
+                let arg = 2.0 * BLIP_CUTOFF * x;
+                let sinc = if arg == 0.0 { 1.0 } else { (PI * arg).sin() / (PI * arg) };
+                let window = 0.42 + 0.5 * (PI * x / half).cos() + 0.08 * (2.0 * PI * x / half).cos();
+                *tap = sinc * window;
+            }
+            let sum: f64 = taps.iter().sum();
+            let scale = (1 << BLIP_KERNEL_BITS) as f64 / sum;
+            for (out, tap) in kernel.iter_mut().zip(taps) {
+                *out = (tap * scale).round() as i32;
+            }
+            // 捨入的誤差補在中央，讓總和剛好是 1
+            let error = (1 << BLIP_KERNEL_BITS) - kernel.iter().sum::<i32>();
+            kernel[BLIP_WIDTH / 2] += error;
+        }
+        kernels
+    })
+}
+
+/// 頻寬受限的階躍合成器（單一聲道）
+///
+/// 每個 CPU 週期以 `add` 送入混音值與它在目前輸出取樣週期中的位置，
+/// 到了輸出時間點以 `next_sample` 取出一個取樣。
+#[derive(Debug, Clone)]
+pub struct BlipSynth {
+    /// 差值環形緩衝區（定點數）
+    deltas: [i64; BLIP_RING],
+    /// 下一個輸出取樣在緩衝區中的位置
+    pos: u32,
+    /// 已輸出的差值總和（即目前的輸出準位，定點數）
+    integrator: i64,
+    /// 上一次送入的混音值（定點數）
+    last: i64,
+}
+
+impl_state_fields!(BlipSynth { deltas, pos, integrator, last });
+
+impl Default for BlipSynth {
+    fn default() -> Self {
+        BlipSynth { deltas: [0; BLIP_RING], pos: 0, integrator: 0, last: 0 }
+    }
+}
+
+impl BlipSynth {
+    /// 送入混音值；`frac` 為目前時間在這個輸出取樣週期中的位置（0.0-1.0）
+    #[inline]
+    pub fn add(&mut self, input: f32, frac: f32) {
+        let level = (input as f64 * (1u64 << BLIP_INPUT_BITS) as f64) as i64;
+        let delta = level - self.last;
+        if delta == 0 {
+            return;
+        }
+        self.last = level;
+        let phase = ((frac * BLIP_PHASES as f32) as usize).min(BLIP_PHASES - 1);
+        let start = self.pos as usize;
+        for (k, &tap) in blip_kernels()[phase].iter().enumerate() {
+            self.deltas[(start + k) % BLIP_RING] += delta * tap as i64;
+        }
+    }
+
+    /// 取出下一個輸出取樣
+    #[inline]
+    pub fn next_sample(&mut self) -> f32 {
+        let pos = self.pos as usize;
+        self.integrator += std::mem::take(&mut self.deltas[pos]);
+        self.pos = ((pos + 1) % BLIP_RING) as u32;
+        (self.integrator as f64 / (1u64 << (BLIP_INPUT_BITS + BLIP_KERNEL_BITS)) as f64) as f32
+    }
+
+    /// 清除狀態
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// 輸出濾波器：低通（抗鋸齒）、高通（移除直流偏移）與軟削波
+#[derive(Debug, Clone, Default)]
+pub struct OutputFilter {
+    /// 低通濾波器累加器
+    lowpass: f32,
+    /// 高通濾波器前一個輸入值
+    highpass_prev: f32,
+    /// 高通濾波器前一個輸出值
+    highpass_output: f32,
+}
+
+impl_state_fields!(OutputFilter { lowpass, highpass_prev, highpass_output });
+
+impl OutputFilter {
+    /// 處理一個取樣；filtered 為 false 時只做縮放與削波
+    pub fn process(&mut self, mut sample: f32, filtered: bool) -> f32 {
+        if filtered {
+            // 低通濾波器（減少高頻噪音 / 抗鋸齒）
+            const LOWPASS_COEFF: f32 = 0.9;
+            self.lowpass = self.lowpass * LOWPASS_COEFF + sample * (1.0 - LOWPASS_COEFF);
+            sample = self.lowpass;
+
+            // 高通濾波器（移除直流偏移）
+            const HIGHPASS_COEFF: f32 = 0.996;
+            let input = sample;
+            self.highpass_output = HIGHPASS_COEFF * self.highpass_output + input - self.highpass_prev;
+            self.highpass_prev = input;
+            sample = self.highpass_output;
+        }
+
+        // 縮放到合理範圍並加入軟削波防止爆音
+        sample *= 1.5;
+        if sample > 0.95 {
+            sample = 0.95 + (sample - 0.95) * 0.2;
+        } else if sample < -0.95 {
+            sample = -0.95 + (sample + 0.95) * 0.2;
+        }
+
+        // 最終限制在 [-1, 1] 範圍
+        sample.clamp(-1.0, 1.0)
+    }
+
+    /// 清除狀態
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// 固定容量的音訊環形緩衝區，寫滿時覆寫最舊的取樣
+#[derive(Debug, Clone)]
+pub struct AudioRing {
+    data: Vec<f32>,
+    /// 最舊取樣的位置
+    read: usize,
+    /// 目前的取樣數
+    len: usize,
+    /// 因為緩衝區已滿而丟棄的取樣總數
+    overflowed: u64,
+}
+
+impl AudioRing {
+    /// 建立容量為 capacity 個取樣的緩衝區
+    pub fn new(capacity: usize) -> Self {
+        AudioRing { data: vec![0.0; capacity.max(1)], read: 0, len: 0, overflowed: 0 }
+    }
+
+    /// 加入一個取樣（已滿時丟棄最舊的一個）
+    #[inline]
+    pub fn push(&mut self, sample: f32) {
+        let capacity = self.data.len();
+        if self.len == capacity {
+            self.read = (self.read + 1) % capacity;
+            self.len -= 1;
+            self.overflowed += 1;
+        }
+        self.data[(self.read + self.len) % capacity] = sample;
+        self.len += 1;
+    }
+
+    /// 目前的取樣數
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 是否沒有取樣
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 容量（取樣數）
+    pub fn capacity(&self) -> usize {
+        self.data.len()
+    }
+
+    /// 因為緩衝區已滿而丟棄的取樣總數
+    pub fn overflowed(&self) -> u64 {
+        self.overflowed
+    }
+
+    /// 清除所有取樣，回傳清除的數量
+    pub fn clear(&mut self) -> usize {
+        let count = self.len;
+        self.read = 0;
+        self.len = 0;
+        count
+    }
+
+    /// 只保留最舊的 len 個取樣
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    /// 依序取出取樣到 out，回傳取出的數量；放不下的留到下次
+    pub fn read_into(&mut self, out: &mut [f32]) -> usize {
+        let count = self.len.min(out.len());
+        let capacity = self.data.len();
+        let first = count.min(capacity - self.read);
+        out[..first].copy_from_slice(&self.data[self.read..self.read + first]);
+        out[first..count].copy_from_slice(&self.data[..count - first]);
+        self.read = (self.read + count) % capacity;
+        self.len -= count;
+        count
+    }
+
+    /// 把取樣搬到緩衝區開頭並回傳（給直接讀取 WASM 記憶體的前端）
+    pub fn make_contiguous(&mut self) -> &[f32] {
+        self.data.rotate_left(self.read);
+        self.read = 0;
+        &self.data[..self.len]
+    }
+}
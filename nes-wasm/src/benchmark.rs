@@ -0,0 +1,190 @@
+// ============================================================
+// 效能基準測試 - 量測執行速度與各子系統耗時
+// ============================================================
+// 基準測試分兩輪執行同樣的幀數：
+// 1. 不加任何量測，取得真實的總耗時與 FPS
+// 2. 在主時鐘各階段之間插入計時探針，取得 CPU/PPU/APU 的耗時比例
+// 最後以第 2 輪的比例分配第 1 輪的總耗時，避免計時本身的開銷
+// 扭曲 FPS 數字。
+//
+// 計時函式由呼叫端提供（瀏覽器用 performance.now()，原生用 Instant），
+// 即使計時器解析度較粗，大量取樣的累計值仍是無偏的估計。
+//
+// 啟用 profiling feature 時，模擬器另外在每幀累計週期數、Mapper 同步
+// 次數與（提供計時函式時）各子系統耗時，供實機上的效能分析使用。
+// ============================================================
+
+/// 子系統分類（用於耗時統計）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subsystem {
+    /// CPU 指令執行與 OAM DMA
+    Cpu = 0,
+    /// PPU 渲染
+    Ppu = 1,
+    /// APU 音效合成與 DMC 讀取
+    Apu = 2,
+    /// 其他（中斷檢查）
+    Other = 3,
+    /// Mapper CPU 週期計時與 scanline 時的 bank 同步
+    Mapper = 4,
+}
+
+/// 子系統數量（耗時陣列的長度）
+pub const SUBSYSTEM_COUNT: usize = 5;
+
+/// 時鐘探針：主時鐘在進入每個子系統前呼叫 enter
+pub trait ClockProbe {
+    /// 標記接下來的工作屬於哪個子系統
+    fn enter(&mut self, subsystem: Subsystem);
+}
+
+/// 不做任何事的探針（一般執行用，編譯後完全消失）
+pub struct NoProbe;
+
+impl ClockProbe for NoProbe {
+    #[inline(always)]
+    fn enter(&mut self, _subsystem: Subsystem) {}
+}
+
+/// 計時探針：把兩次 enter 之間經過的時間累加到前一個子系統
+pub struct TimingProbe<F: FnMut() -> f64> {
+    now: F,
+    last_time: f64,
+    current: Subsystem,
+    /// 各子系統累計耗時（毫秒），以 Subsystem 為索引
+    pub totals: [f64; SUBSYSTEM_COUNT],
+}
+
+impl<F: FnMut() -> f64> TimingProbe<F> {
+    /// 建立計時探針，now 回傳目前時間（毫秒）
+    pub fn new(mut now: F) -> Self {
+        let last_time = now();
+        TimingProbe { now, last_time, current: Subsystem::Other, totals: [0.0; SUBSYSTEM_COUNT] }
+    }
+}
+
+impl<F: FnMut() -> f64> ClockProbe for TimingProbe<F> {
+    #[inline]
+    fn enter(&mut self, subsystem: Subsystem) {
+        let t = (self.now)();
+        self.totals[self.current as usize] += t - self.last_time;
+        self.last_time = t;
+        self.current = subsystem;
+    }
+}
+
+/// 基準測試結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkResult {
+    /// 執行幀數
+    pub frames: u32,
+    /// 總耗時（毫秒）
+    pub total_ms: f64,
+    /// 每秒幀數
+    pub fps: f64,
+    /// CPU 耗時（毫秒）
+    pub cpu_ms: f64,
+    /// PPU 耗時（毫秒）
+    pub ppu_ms: f64,
+    /// APU 耗時（毫秒）
+    pub apu_ms: f64,
+    /// Mapper 耗時（毫秒）
+    pub mapper_ms: f64,
+    /// 其他耗時（毫秒）
+    pub other_ms: f64,
+}
+
+impl BenchmarkResult {
+    /// 以總耗時與各子系統的量測值建立結果（依比例分配總耗時）
+    pub fn new(frames: u32, total_ms: f64, sampled: [f64; SUBSYSTEM_COUNT]) -> Self {
+        let sum: f64 = sampled.iter().sum();
+        let share = |i: usize| if sum > 0.0 { total_ms * sampled[i] / sum } else { 0.0 };
+        BenchmarkResult {
+            frames,
+            total_ms,
+            fps: if total_ms > 0.0 { frames as f64 * 1000.0 / total_ms } else { 0.0 },
+            cpu_ms: share(Subsystem::Cpu as usize),
+            ppu_ms: share(Subsystem::Ppu as usize),
+            apu_ms: share(Subsystem::Apu as usize),
+            mapper_ms: share(Subsystem::Mapper as usize),
+            other_ms: share(Subsystem::Other as usize),
+        }
+    }
+
+    /// 輸出為 JSON 字串
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"frames\":{},\"totalMs\":{:.3},\"fps\":{:.2},\"cpuMs\":{:.3},\"ppuMs\":{:.3},\
+             \"apuMs\":{:.3},\"mapperMs\":{:.3},\"otherMs\":{:.3}}}",
+            self.frames, self.total_ms, self.fps,
+            self.cpu_ms, self.ppu_ms, self.apu_ms, self.mapper_ms, self.other_ms,
+        )
+    }
+}
+
+// ============================================================
+// 每幀效能計數（profiling feature）
+// ============================================================
+
+/// 一幀的效能計數
+#[cfg(feature = "profiling")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameProfile {
+    /// 幀編號
+    pub frame: u64,
+    /// CPU 週期數（含 DMA）
+    pub cpu_cycles: u64,
+    /// DMA（OAM 與 DMC 取樣讀取）佔用的 CPU 週期數
+    pub dma_cycles: u64,
+    /// 執行的指令數
+    pub instructions: u64,
+    /// PPU dot 數
+    pub ppu_dots: u64,
+    /// APU 產生的音訊取樣數
+    pub audio_samples: u64,
+    /// 整幀耗時（毫秒，未設定計時函式時為 0）
+    pub total_ms: f64,
+    /// 各子系統耗時（毫秒），以 Subsystem 為索引
+    pub subsystem_ms: [f64; SUBSYSTEM_COUNT],
+}
+
+#[cfg(feature = "profiling")]
+impl FrameProfile {
+    /// 輸出為 JSON 字串
+    pub fn to_json(&self) -> String {
+        let ms = |s: Subsystem| self.subsystem_ms[s as usize];
+        format!(
+            "{{\"frame\":{},\"cpuCycles\":{},\"dmaCycles\":{},\"instructions\":{},\"ppuDots\":{},\
+             \"audioSamples\":{},\"totalMs\":{:.3},\"cpuMs\":{:.3},\
+             \"ppuMs\":{:.3},\"apuMs\":{:.3},\"mapperMs\":{:.3},\"otherMs\":{:.3}}}",
+            self.frame, self.cpu_cycles, self.dma_cycles, self.instructions, self.ppu_dots,
+            self.audio_samples, self.total_ms,
+            ms(Subsystem::Cpu), ms(Subsystem::Ppu), ms(Subsystem::Apu),
+            ms(Subsystem::Mapper), ms(Subsystem::Other),
+        )
+    }
+}
+
+/// 每幀效能計數器
+///
+/// 計數（週期、指令數）幾乎沒有開銷；設定計時函式後每個主時鐘
+/// 會呼叫數次計時函式，執行速度明顯變慢，耗時只適合看比例。
+#[cfg(feature = "profiling")]
+#[derive(Default)]
+pub struct Profiler {
+    /// 進行中的幀（中斷點暫停時跨多次 frame 呼叫累計）
+    pub current: FrameProfile,
+    /// 上一個完成的幀
+    pub last: FrameProfile,
+    /// 計時函式（回傳毫秒），None 表示只計數不計時
+    pub clock: Option<Box<dyn FnMut() -> f64>>,
+}
+
+#[cfg(feature = "profiling")]
+impl Profiler {
+    /// 幀結束：保存計數並開始新的一幀
+    pub fn finish_frame(&mut self, frame: u64) {
+        self.current.frame = frame;
+        self.last = std::mem::take(&mut self.current);
+    }
+}
@@ -14,9 +14,22 @@
 //
 // DMA 傳輸：
 // 寫入 $4014 會觸發 OAM DMA，將 256 位元組從 CPU 記憶體
-// 複製到 PPU 的 OAM（精靈屬性記憶體）
+// 複製到 PPU 的 OAM（精靈屬性記憶體）；DMC 聲道的緩衝區空了也會
+// 以 DMA 讀取下一個取樣位元組。兩者共用同一個仲裁器：CPU 週期分成
+// 交錯的 get（讀取）與 put（寫入）週期，DMA 只能在 get 週期讀取，
+// 同時發生時 DMC 優先。
 //
-// 參考：https://www.nesdev.org/wiki/CPU_memory_map
+// 開路（open bus）：沒有裝置驅動資料匯流排時，CPU 讀到的是匯流排上
+// 殘留的最後一個值（通常是指令運算元的高位元組）。控制器埠只驅動
+// 低 5 位元、$4015 的位元 5 也沒有接線。
+//
+// 週邊裝置由模擬器一次分開借出成 Devices，匯流排的讀寫只拿這一組引用；
+// CPU 則透過 cpu::CpuBus 存取整台模擬器，兩者不必互相持有。
+//
+// 參考：
+// - https://www.nesdev.org/wiki/CPU_memory_map
+// - https://www.nesdev.org/wiki/DMA
+// - https://www.nesdev.org/wiki/Open_bus_behavior
 // ============================================================
 
 use crate::ppu::Ppu;
@@ -24,6 +37,18 @@ use crate::apu::Apu;
 use crate::cartridge::Cartridge;
 use crate::controller::Controller;
 
+/// DMC DMA 取得匯流排前的暫停（halt）與空轉（dummy）週期數
+const DMC_HALT_CYCLES: u8 = 2;
+
+/// 匯流排上的週邊裝置（從模擬器分開借用的引用）
+pub struct Devices<'a> {
+    pub ppu: &'a mut Ppu,
+    pub apu: &'a mut Apu,
+    pub cartridge: &'a mut Cartridge,
+    pub ctrl1: &'a mut Controller,
+    pub ctrl2: &'a mut Controller,
+}
+
 /// NES 記憶體匯流排
 pub struct Bus {
     /// 2KB 內部 RAM
@@ -39,6 +64,20 @@ pub struct Bus {
     pub dma_transfer: bool,
     /// DMA 等待對齊旗標
     pub dma_dummy: bool,
+    /// DMC DMA 已經過的暫停/空轉週期數（0 = 尚未暫停 CPU）
+    pub dmc_halt: u8,
+    /// CPU 最後一次讀取的位址：DMC DMA 暫停 CPU 時會重複這次讀取
+    pub last_read_addr: u16,
+    /// 上一條指令開始後 DMC DMA 讓 CPU 多暫停的週期數（追蹤記錄用，不存檔）
+    pub dmc_stall: u8,
+    /// 資料匯流排上最後的值（開路讀取時回傳）
+    pub open_bus: u8,
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Bus {
@@ -51,35 +90,49 @@ impl Bus {
             dma_data: 0,
             dma_transfer: false,
             dma_dummy: true,
+            dmc_halt: 0,
+            last_read_addr: 0,
+            dmc_stall: 0,
+            open_bus: 0,
         }
     }
 
     /// 重置匯流排狀態
     pub fn reset(&mut self) {
         self.ram = [0; 2048];
+        self.open_bus = 0;
+        self.reset_dma();
+    }
+
+    /// 只重置 DMA 狀態（軟體重置時 RAM 保留）
+    pub fn reset_dma(&mut self) {
         self.dma_page = 0;
         self.dma_address = 0;
         self.dma_data = 0;
         self.dma_transfer = false;
         self.dma_dummy = true;
+        self.dmc_halt = 0;
     }
 
-    /// CPU 讀取記憶體
-    /// 需要傳入 PPU、APU、卡帶、控制器的引用
-    pub fn cpu_read(
-        &self,
-        addr: u16,
-        ppu: &mut Ppu,
-        apu: &mut Apu,
-        cartridge: &Cartridge,
-        ctrl1: &mut Controller,
-        ctrl2: &mut Controller,
-    ) -> u8 {
-        let addr = addr & 0xFFFF;
+    /// 這個 CPU 週期是否由 DMA 佔用匯流排（CPU 暫停）
+    ///
+    /// dmc_request 為 APU 尚未完成的 DMC 讀取請求。DMC 只在 put 週期
+    /// 開始暫停 CPU，所以單獨發生時固定是 暫停、空轉、對齊、讀取 4 個週期。
+    pub fn dma_active(&self, dmc_request: bool, odd_cycle: bool) -> bool {
+        self.dma_transfer || (dmc_request && (self.dmc_halt > 0 || odd_cycle))
+    }
 
+    /// 這個週期是否為 DMC DMA 暫停 CPU 的第一個週期（OAM DMA 進行中時
+    /// CPU 早已暫停，不算）
+    pub fn dmc_halts_cpu(&self, dmc_request: bool, odd_cycle: bool) -> bool {
+        dmc_request && self.dmc_halt == 0 && odd_cycle && !self.dma_transfer
+    }
+
+    /// CPU 讀取記憶體
+    pub fn cpu_read(&self, addr: u16, dev: &mut Devices) -> u8 {
         // 卡帶空間 ($4020-$FFFF)
         if addr >= 0x4020 {
-            return cartridge.cpu_read(addr);
+            return dev.cartridge.cpu_read_mapped(addr).unwrap_or(self.open_bus);
         }
 
         // 內部 RAM ($0000-$1FFF，每 2KB 鏡像)
@@ -89,44 +142,31 @@ impl Bus {
 
         // PPU 暫存器 ($2000-$3FFF，每 8 位元組鏡像)
         if addr < 0x4000 {
-            return ppu.cpu_read(addr & 0x2007);
+            return dev.ppu.cpu_read(addr & 0x2007, dev.cartridge);
         }
 
-        // 控制器 1 ($4016)
+        // 控制器 1 ($4016)、控制器 2 ($4017)：高 3 位元為開路
         if addr == 0x4016 {
-            return ctrl1.read();
+            return dev.ctrl1.read(dev.ppu) | (self.open_bus & 0xE0);
         }
-
-        // 控制器 2 ($4017)
         if addr == 0x4017 {
-            return ctrl2.read();
+            return dev.ctrl2.read(dev.ppu) | (self.open_bus & 0xE0);
         }
 
         // APU 狀態暫存器 ($4015)
         if addr == 0x4015 {
-            return apu.cpu_read();
+            return dev.apu.cpu_read() | (self.open_bus & 0x20);
         }
 
-        0
+        // 唯寫的 APU 暫存器與 $4018-$401F
+        self.open_bus
     }
 
     /// CPU 寫入記憶體
-    pub fn cpu_write(
-        &mut self,
-        addr: u16,
-        data: u8,
-        ppu: &mut Ppu,
-        apu: &mut Apu,
-        cartridge: &mut Cartridge,
-        ctrl1: &mut Controller,
-        ctrl2: &mut Controller,
-    ) {
-        let addr = addr & 0xFFFF;
-        let data = data & 0xFF;
-
+    pub fn cpu_write(&mut self, addr: u16, data: u8, dev: &mut Devices) {
         // 卡帶空間 ($4020-$FFFF)
         if addr >= 0x4020 {
-            cartridge.cpu_write(addr, data);
+            dev.cartridge.cpu_write(addr, data);
             return;
         }
 
@@ -138,7 +178,7 @@ impl Bus {
 
         // PPU 暫存器 ($2000-$3FFF)
         if addr < 0x4000 {
-            ppu.cpu_write(addr & 0x2007, data);
+            dev.ppu.cpu_write(addr & 0x2007, data, dev.cartridge);
             return;
         }
 
@@ -153,34 +193,51 @@ impl Bus {
 
         // 控制器 ($4016) - 寫入會鎖存控制器狀態
         if addr == 0x4016 {
-            ctrl1.write(data);
-            ctrl2.write(data);
+            dev.ctrl1.write(data);
+            dev.ctrl2.write(data);
             return;
         }
 
         // APU 暫存器 ($4000-$4013, $4015, $4017)
-        if (addr >= 0x4000 && addr <= 0x4013) || addr == 0x4015 || addr == 0x4017 {
-            apu.cpu_write(addr, data);
-            return;
+        if (0x4000..=0x4013).contains(&addr) || addr == 0x4015 || addr == 0x4017 {
+            dev.apu.cpu_write(addr, data);
         }
     }
 
-    /// 執行 DMA 時鐘週期
-    /// 在 DMA 傳輸期間，CPU 被暫停，匯流排忙於搬運資料
-    pub fn do_dma_cycle(
-        &mut self,
-        odd_cycle: bool,
-        ppu: &mut Ppu,
-        apu: &mut Apu,
-        cartridge: &Cartridge,
-        ctrl1: &mut Controller,
-        ctrl2: &mut Controller,
-    ) {
+    /// 執行 DMA 時鐘週期，回傳這個週期要讀取的 DMC 取樣位址
+    ///
+    /// 在 DMA 傳輸期間，CPU 被暫停，匯流排忙於搬運資料（偶數週期為 get）：
+    /// - OAM DMA：暫停 1 週期，落在 get 週期時再多 1 個對齊週期，之後
+    ///   256 組 get/put，共 513 或 514 週期
+    /// - DMC DMA：暫停與空轉各 1 週期，等到 get 週期讀取
+    /// - 兩者重疊時 DMC 的暫停與空轉和 OAM 搬運同時進行，DMC 讀取佔掉一個
+    ///   OAM 的 get 週期，OAM 再花 1 週期重新對齊，通常只多 2 週期
+    ///
+    /// DMC 讀取要經過 Mapper、PRG RAM 與金手指，由呼叫端以完整的匯流排
+    /// 讀取完成後交給 APU。
+    pub fn do_dma_cycle(&mut self, odd_cycle: bool, dmc_request: Option<u16>, dev: &mut Devices) -> Option<u16> {
+        let mut dmc_fetch = None;
+        if let Some(addr) = dmc_request {
+            if self.dmc_halt < DMC_HALT_CYCLES {
+                if self.dmc_halt > 0 || odd_cycle {
+                    self.dmc_halt += 1;
+                }
+            } else if !odd_cycle {
+                self.dmc_halt = 0;
+                dmc_fetch = Some(addr);
+            }
+        }
+
         if !self.dma_transfer {
-            return;
+            self.dmc_stall = self.dmc_stall.saturating_add(1);
+            return dmc_fetch;
         }
 
-        if self.dma_dummy {
+        if dmc_fetch.is_some() {
+            // 這個 get 週期被 DMC 拿走，OAM DMA 重新等待對齊（多出 2 週期）
+            self.dma_dummy = true;
+            self.dmc_stall = self.dmc_stall.saturating_add(2);
+        } else if self.dma_dummy {
             // 等待 CPU 週期對齊到奇數週期
             if odd_cycle {
                 self.dma_dummy = false;
@@ -189,10 +246,11 @@ impl Bus {
             if !odd_cycle {
                 // 偶數週期：從 CPU 記憶體讀取
                 let addr = (self.dma_page as u16) << 8 | self.dma_address as u16;
-                self.dma_data = self.cpu_read(addr, ppu, apu, cartridge, ctrl1, ctrl2);
+                self.dma_data = self.cpu_read(addr, dev);
+                self.open_bus = self.dma_data;
             } else {
                 // 奇數週期：寫入 PPU OAM
-                ppu.oam[self.dma_address as usize] = self.dma_data;
+                dev.ppu.oam[self.dma_address as usize] = self.dma_data;
                 self.dma_address = self.dma_address.wrapping_add(1);
                 if self.dma_address == 0 {
                     // 已傳輸 256 位元組，DMA 完成
@@ -200,5 +258,6 @@ impl Bus {
                 }
             }
         }
+        dmc_fetch
     }
 }
@@ -2,7 +2,10 @@
 // NES 卡帶模擬 - iNES 格式解析與記憶體管理
 // ============================================================
 // 負責解析 iNES 和 NES 2.0 格式的 ROM 檔案，
-// 並管理 PRG ROM、CHR ROM/RAM 的存取。
+// 並管理 PRG ROM/RAM 的存取。
+// CHR ROM/RAM 留在卡帶上，卡帶實作 PpuBus 接在 PPU 的圖案表匯流排：
+// 每次圖案讀取都向 Mapper 查詢目前的 bank，沒有要同步的映射副本，
+// 掃描線中途的 bank 切換也立即生效。
 //
 // iNES 格式：
 // - 16 位元組標頭
@@ -13,8 +16,13 @@
 // 參考：https://www.nesdev.org/wiki/INES
 // ============================================================
 
-use crate::ppu::MirrorMode;
+use crate::ppu::{MirrorMode, PpuBus};
 use crate::mappers::*;
+use crate::config::Region;
+use crate::compat::{self, CompatHack};
+use crate::fds::{DiskDrive, FdsImage, FdsMapper, FDS_RAM_SIZE};
+use crate::nsf::{NsfFile, NsfMapper};
+use crate::vs::{VsPpu, VsProtection};
 
 /// iNES 標頭結構
 pub struct CartridgeHeader {
@@ -30,24 +38,96 @@ pub struct CartridgeHeader {
     pub has_battery: bool,
     /// 是否有訓練器資料
     pub has_trainer: bool,
+    /// 是否為 NES 2.0 格式
+    pub is_nes2: bool,
+    /// 完整 Mapper 編號（NES 2.0 可達 12 位元；iNES 與 mapper_id 相同）
+    pub mapper_number: u16,
+    /// NES 2.0 子 Mapper 編號（iNES 為 0）
+    pub submapper: u8,
+    /// 標頭標示的地區
+    pub region: Region,
+    /// 位元組 7-15 含有垃圾資料，已忽略（只採用 flags6 的 Mapper 低 4 位元）
+    pub header_cleaned: bool,
+    /// VS. System 的 PPU 型號（不是 VS. 卡帶時為 None）
+    pub vs_ppu: Option<VsPpu>,
+    /// VS. System 的保護晶片
+    pub vs_protection: VsProtection,
+}
+
+/// ROM 資訊（載入後提供給前端顯示與相容性判斷）
+#[derive(Debug, Clone, PartialEq)]
+pub struct RomInfo {
+    /// Mapper 編號（NES 2.0 為完整 12 位元）
+    pub mapper_id: u16,
+    /// 子 Mapper 編號
+    pub submapper: u8,
+    /// Mapper 名稱
+    pub mapper_name: &'static str,
+    /// Mapper 是否有專屬實作（false 表示退回 Mapper 0，畫面可能錯亂）
+    pub mapper_supported: bool,
+    /// PRG ROM 大小（位元組）
+    pub prg_rom_size: usize,
+    /// CHR ROM 大小（位元組，0 表示使用 CHR RAM）
+    pub chr_rom_size: usize,
+    /// 是否使用 CHR RAM
+    pub chr_ram: bool,
+    /// 是否有電池
+    pub has_battery: bool,
+    /// PRG RAM 大小（位元組，0 表示沒有）
+    pub prg_ram_size: usize,
+    /// 標頭指定的鏡像模式
+    pub mirror_mode: MirrorMode,
+    /// 是否有訓練器
+    pub has_trainer: bool,
+    /// 是否為 NES 2.0 格式
+    pub is_nes2: bool,
+    /// 偵測到的地區
+    pub region: Region,
+    /// ROM 內容 CRC32
+    pub crc32: u32,
+    /// 套用的相容性修正名稱
+    pub compat_hacks: Vec<&'static str>,
+    /// 標頭位元組 7-15 含有垃圾資料，已修正 Mapper 編號
+    pub header_cleaned: bool,
+    /// VS. System 的 PPU 型號名稱（不是 VS. 卡帶時為 None）
+    pub vs_ppu: Option<&'static str>,
 }
 
 /// NES 卡帶
 pub struct Cartridge {
     /// 卡帶標頭資訊
     pub header: CartridgeHeader,
-    /// PRG ROM 資料
+    /// PRG ROM 資料（更換時使用 set_prg_rom，讓位址遮罩保持一致）
     pub prg_rom: Vec<u8>,
-    /// CHR ROM/RAM 資料
+    /// PRG ROM 位址遮罩（大小為 2 的次方時為 len - 1，否則為 None 改用取餘數）
+    prg_mask: Option<usize>,
+    /// CHR ROM/RAM 資料（PPU 經由 PpuBus 存取；更換時使用 set_chr_data）
     pub chr_data: Vec<u8>,
-    /// PRG RAM（8KB，可能有電池供電）
+    /// CHR 位址遮罩（規則同 prg_mask）
+    chr_mask: Option<usize>,
+    /// PRG RAM（iNES 1.0 為 8KB；NES 2.0 依標頭大小配置，標示沒有時為空）
     pub prg_ram: Vec<u8>,
+    /// PRG RAM 視窗的結束位址（一般為 $8000；FDS 的 32KB RAM 延伸到 $DFFF）
+    prg_ram_end: u16,
     /// 是否使用 CHR RAM
     pub chr_ram: bool,
     /// Mapper 實例
-    pub mapper: Box<dyn MapperTrait>,
+    pub mapper: Mapper,
     /// 是否已載入 ROM
     pub loaded: bool,
+    /// ROM 內容的 CRC32（只含 PRG+CHR，不含標頭、訓練器與檔尾的多餘資料，
+    /// 與常見 ROM 資料庫相同）
+    pub crc32: u32,
+    /// 電池 PRG RAM 自上次檢查後是否有變更
+    pub prg_ram_dirty: bool,
+    /// 載入時依 CRC32 套用的相容性修正
+    pub compat_hacks: &'static [CompatHack],
+}
+
+impl Default for Cartridge {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Cartridge {
@@ -61,13 +141,26 @@ impl Cartridge {
                 mirror_mode: MirrorMode::Horizontal,
                 has_battery: false,
                 has_trainer: false,
+                is_nes2: false,
+                mapper_number: 0,
+                submapper: 0,
+                region: Region::Ntsc,
+                header_cleaned: false,
+                vs_ppu: None,
+                vs_protection: VsProtection::None,
             },
             prg_rom: Vec::new(),
+            prg_mask: None,
             chr_data: Vec::new(),
+            chr_mask: None,
+            prg_ram_end: 0x8000,
             prg_ram: vec![0; 8192], // 8KB PRG RAM
             chr_ram: false,
-            mapper: Box::new(Mapper0::new(1, 1)),
+            mapper: Mapper0::new(1, 1).into(),
             loaded: false,
+            crc32: 0,
+            prg_ram_dirty: false,
+            compat_hacks: &[],
         }
     }
 
@@ -83,7 +176,16 @@ impl Cartridge {
         let prg_banks = data[4];
         let chr_banks = data[5];
         let flags6 = data[6];
-        let flags7 = data[7];
+
+        // 舊的 dump 工具會在位元組 7-15 留下垃圾（最常見的是 "DiskDude!" 簽名），
+        // 讓 Mapper 高 4 位元與地區位元錯亂。非 NES 2.0 標頭的位元組 12-15
+        // 應為 0，不是的話視為舊版 iNES，位元組 7-15 全部忽略
+        // 參考：https://www.nesdev.org/wiki/INES#Variant_comparison
+        let header_cleaned = data[7] & 0x0C != 0x08
+            && (data[7..16].starts_with(b"DiskDude!")
+                || data[7] & 0x0C == 0x04
+                || data[12..16].iter().any(|&b| b != 0));
+        let flags7 = if header_cleaned { 0 } else { data[7] };
 
         // Mapper 編號（低 4 位元在 flags6，高 4 位元在 flags7）
         let mapper_id = (flags7 & 0xF0) | (flags6 >> 4);
@@ -100,13 +202,31 @@ impl Cartridge {
         let has_battery = flags6 & 0x02 != 0;
         let has_trainer = flags6 & 0x04 != 0;
 
-        self.header = CartridgeHeader {
-            prg_rom_banks: prg_banks,
-            chr_rom_banks: chr_banks,
-            mapper_id,
-            mirror_mode,
-            has_battery,
-            has_trainer,
+        // NES 2.0：flags7 位元 2-3 為 10
+        // 參考：https://www.nesdev.org/wiki/NES_2.0
+        let is_nes2 = flags7 & 0x0C == 0x08;
+        let (mapper_number, submapper, region) = if is_nes2 {
+            let number = ((data[8] as u16 & 0x0F) << 8) | mapper_id as u16;
+            let region = match data[12] & 0x03 {
+                1 => Region::Pal,
+                3 => Region::Dendy,
+                _ => Region::Ntsc, // 0 = NTSC，2 = 多地區
+            };
+            (number, data[8] >> 4, region)
+        } else {
+            let region = if !header_cleaned && data[9] & 0x01 != 0 { Region::Pal } else { Region::Ntsc };
+            (mapper_id as u16, 0, region)
+        };
+
+        // VS. System：iNES 為 flags7 位元 0；NES 2.0 為主機類型 1，
+        // 位元組 13 標示 PPU 型號（低 4 位元）與保護晶片（高 4 位元）
+        // 參考：https://www.nesdev.org/wiki/NES_2.0#Vs._System_Type
+        let (vs_ppu, vs_protection) = if is_nes2 && flags7 & 0x03 == 0x01 {
+            (Some(VsPpu::from_nes2(data[13])), VsProtection::from_nes2(data[13]))
+        } else if !is_nes2 && flags7 & 0x01 != 0 {
+            (Some(VsPpu::Rp2c03), VsProtection::None)
+        } else {
+            (None, VsProtection::None)
         };
 
         // 計算資料偏移
@@ -115,12 +235,32 @@ impl Cartridge {
             offset += 512; // 跳過訓練器
         }
 
-        // 讀取 PRG ROM
+        // 讀取 PRG ROM：不完整或大小為 0 時拒絕載入。在此之前不修改任何欄位，
+        // 載入失敗時卡帶維持原本的內容
         let prg_size = prg_banks as usize * 16384; // 16KB per bank
-        if offset + prg_size > data.len() {
+        if prg_size == 0 || offset + prg_size > data.len() {
             return false;
         }
-        self.prg_rom = data[offset..offset + prg_size].to_vec();
+        self.header = CartridgeHeader {
+            prg_rom_banks: prg_banks,
+            chr_rom_banks: chr_banks,
+            mapper_id,
+            mirror_mode,
+            has_battery,
+            has_trainer,
+            is_nes2,
+            mapper_number,
+            submapper,
+            region,
+            header_cleaned,
+            vs_ppu,
+            vs_protection,
+        };
+
+        // 只計算 PRG+CHR：檔尾多出的資料（標題等）不影響查表
+        let chr_end = offset + prg_size + chr_banks as usize * 8192;
+        self.crc32 = crc32(&data[offset..chr_end.min(data.len())]);
+        self.set_prg_rom(data[offset..offset + prg_size].to_vec());
         offset += prg_size;
 
         // 讀取 CHR ROM/RAM
@@ -128,110 +268,301 @@ impl Cartridge {
             let chr_size = chr_banks as usize * 8192; // 8KB per bank
             if offset + chr_size > data.len() {
                 // 某些 ROM 的 CHR 資料可能不完整，用 0 填充
-                self.chr_data = vec![0; chr_size];
+                let mut chr = vec![0; chr_size];
                 let available = data.len().saturating_sub(offset);
                 if available > 0 {
-                    self.chr_data[..available].copy_from_slice(&data[offset..offset + available]);
+                    chr[..available].copy_from_slice(&data[offset..offset + available]);
                 }
+                self.set_chr_data(chr);
             } else {
-                self.chr_data = data[offset..offset + chr_size].to_vec();
+                self.set_chr_data(data[offset..offset + chr_size].to_vec());
             }
             self.chr_ram = false;
         } else {
             // 使用 CHR RAM（8KB）
-            self.chr_data = vec![0; 8192];
+            self.set_chr_data(vec![0; 8192]);
             self.chr_ram = true;
         }
 
-        // 重置 PRG RAM
-        self.prg_ram = vec![0; 8192];
+        // 重置 PRG RAM：iNES 1.0 無法得知有沒有 PRG RAM，一律配置 8KB；
+        // NES 2.0 依位元組 10 配置（低 4 位元為一般 RAM、高 4 位元為電池 NVRAM）
+        self.prg_ram = vec![0; if is_nes2 { nes2_prg_ram_size(data[10], has_battery) } else { 8192 }];
+        self.prg_ram_end = 0x8000;
+        self.prg_ram_dirty = false;
 
-        // 建立 Mapper
-        self.mapper = create_mapper(mapper_id, prg_banks, chr_banks);
+        // 建立 Mapper；MMC1 依 PRG RAM 大小決定 RAM bank 切換，MMC3 的子 Mapper 4
+        // 為舊版 IRQ 的 MMC3A，Mapper 210 以子 Mapper 區分 Namco 175（1）與 340（2），
+        // 沒有子 Mapper 時依電池判斷（只有 175 的卡帶有 PRG RAM）；Mapper 16 的
+        // 子 Mapper 5（LZ93D50）以 24C02 EEPROM 存檔，沒有子 Mapper 時同樣依電池判斷
+        self.mapper = match mapper_number {
+            210 if submapper == 2 || (submapper == 0 && !has_battery) => {
+                Mapper210::new_namco340(prg_banks, chr_banks).into()
+            }
+            1 => Mapper1::with_prg_ram_size(prg_banks, chr_banks, self.prg_ram.len()).into(),
+            4 if submapper == 4 => Mapper4::new_mmc3a(prg_banks, chr_banks).into(),
+            16 if submapper == 5 || (submapper == 0 && has_battery) => {
+                Mapper16::with_eeprom(prg_banks, chr_banks).into()
+            }
+            _ => create_mapper(mapper_number, prg_banks, chr_banks),
+        };
 
         // Mapper 253 (Waixing VRC4) 需要額外的 CHR RAM 空間
         // 在 CHR ROM 末尾追加 8KB CHR RAM，用於動態 CHR bank 替換
-        if mapper_id == 253 && !self.chr_ram {
-            let chr_rom_size = self.chr_data.len();
-            self.chr_data.resize(chr_rom_size + 8192, 0);
+        if mapper_number == 253 && !self.chr_ram {
+            let mut chr = std::mem::take(&mut self.chr_data);
+            chr.resize(chr.len() + 8192, 0);
+            self.set_chr_data(chr);
         }
 
+        self.set_compat_hacks(compat::lookup(self.crc32));
         self.loaded = true;
 
         true
     }
 
+    /// 載入 NSF 音樂檔（PRG 為音樂資料，CHR 為 8KB RAM）
+    pub fn load_nsf(&mut self, nsf: &NsfFile) {
+        let (prg, banks) = nsf.build_prg();
+        self.header = CartridgeHeader {
+            prg_rom_banks: (prg.len() / 16384) as u8,
+            chr_rom_banks: 0,
+            mapper_id: 0,
+            mirror_mode: MirrorMode::Horizontal,
+            has_battery: false,
+            has_trainer: false,
+            is_nes2: false,
+            mapper_number: 0,
+            submapper: 0,
+            region: if nsf.header.region_flags & 0x03 == 0x01 { Region::Pal } else { Region::Ntsc },
+            header_cleaned: false,
+            vs_ppu: None,
+            vs_protection: VsProtection::None,
+        };
+        self.crc32 = crc32(&nsf.data);
+        self.mapper = NsfMapper::new(banks, prg.len()).into();
+        self.set_prg_rom(prg);
+        self.set_chr_data(vec![0; 8192]);
+        self.chr_ram = true;
+        self.prg_ram = vec![0; 8192];
+        self.prg_ram_end = 0x8000;
+        self.prg_ram_dirty = false;
+        self.compat_hacks = &[];
+        self.loaded = true;
+    }
+
+    /// 載入 FDS 磁碟（PRG 為 8KB BIOS，$6000-$DFFF 為 32KB RAM，CHR 為 8KB RAM）
+    pub fn load_fds(&mut self, bios: &[u8], image: FdsImage) {
+        self.header = CartridgeHeader {
+            prg_rom_banks: 0,
+            chr_rom_banks: 0,
+            mapper_id: 20,
+            mirror_mode: MirrorMode::Horizontal,
+            has_battery: false,
+            has_trainer: false,
+            is_nes2: false,
+            mapper_number: 20,
+            submapper: 0,
+            region: Region::Ntsc,
+            header_cleaned: false,
+            vs_ppu: None,
+            vs_protection: VsProtection::None,
+        };
+        self.crc32 = image.crc32();
+        self.mapper = FdsMapper::new(image).into();
+        self.set_prg_rom(bios.to_vec());
+        self.set_chr_data(vec![0; 8192]);
+        self.chr_ram = true;
+        self.prg_ram = vec![0; FDS_RAM_SIZE];
+        self.prg_ram_end = 0x6000 + FDS_RAM_SIZE as u16;
+        self.prg_ram_dirty = false;
+        self.compat_hacks = &[];
+        self.loaded = true;
+    }
+
+    /// FDS 磁碟機（載入 FDS 磁碟時才有）
+    pub fn disk_drive(&self) -> Option<&DiskDrive> {
+        match &self.mapper {
+            Mapper::FdsMapper(fds) => Some(fds.drive()),
+            _ => None,
+        }
+    }
+
+    /// FDS 磁碟機（可換面）
+    pub fn disk_drive_mut(&mut self) -> Option<&mut DiskDrive> {
+        match &mut self.mapper {
+            Mapper::FdsMapper(fds) => Some(fds.drive_mut()),
+            _ => None,
+        }
+    }
+
+    /// 套用相容性修正（載入 ROM 時依資料庫自動呼叫；Mapper 需已建立）
+    pub fn set_compat_hacks(&mut self, hacks: &'static [CompatHack]) {
+        self.compat_hacks = hacks;
+        for &hack in hacks {
+            if hack == CompatHack::FourScreen {
+                self.header.mirror_mode = MirrorMode::FourScreen;
+            }
+            self.mapper.apply_hack(hack);
+        }
+    }
+
+    /// 是否套用了指定的相容性修正
+    pub fn has_hack(&self, hack: CompatHack) -> bool {
+        self.compat_hacks.contains(&hack)
+    }
+
+    /// 更換 PRG ROM 並預先計算位址遮罩
+    /// 一般卡帶的 PRG 都是 2 的次方大小，讀取只需一次 AND；
+    /// 其他大小（例如 48KB 或 NSF）維持原本的取餘數
+    pub fn set_prg_rom(&mut self, prg: Vec<u8>) {
+        self.prg_mask = prg.len().is_power_of_two().then(|| prg.len() - 1);
+        self.prg_rom = prg;
+    }
+
+    /// 更換 CHR ROM/RAM 並預先計算位址遮罩（規則同 set_prg_rom）
+    pub fn set_chr_data(&mut self, chr: Vec<u8>) {
+        self.chr_mask = chr.len().is_power_of_two().then(|| chr.len() - 1);
+        self.chr_data = chr;
+    }
+
+    /// 是否有電池供電的 PRG RAM（只有這種卡帶需要在前端保存 SRAM）
+    pub fn has_battery_ram(&self) -> bool {
+        self.header.has_battery && !self.prg_ram.is_empty()
+    }
+
+    /// 需要在前端保存的存檔資料：Mapper 的 EEPROM，或電池供電的 PRG RAM
+    pub fn save_data(&self) -> Option<&[u8]> {
+        match self.mapper.eeprom() {
+            Some(eeprom) => Some(&eeprom.data),
+            None => self.has_battery_ram().then_some(&self.prg_ram[..]),
+        }
+    }
+
+    /// 載入存檔資料（大小必須與 save_data 相同），沒有存檔媒體時回傳 false
+    pub fn load_save_data(&mut self, data: &[u8]) -> bool {
+        let battery = self.has_battery_ram();
+        let target = match self.mapper.eeprom_mut() {
+            Some(eeprom) => &mut eeprom.data[..],
+            None if battery => &mut self.prg_ram[..],
+            None => return false,
+        };
+        if target.len() != data.len() { return false; }
+        target.copy_from_slice(data);
+        true
+    }
+
+    /// 取出並清除存檔資料的變更旗標
+    pub fn take_save_dirty(&mut self) -> bool {
+        let eeprom = self.mapper.eeprom_mut().is_some_and(|e| std::mem::take(&mut e.dirty));
+        std::mem::take(&mut self.prg_ram_dirty) || eeprom
+    }
+
+    /// 卡帶是否有 PRG RAM（沒有時前端可以隱藏 SRAM 存檔按鈕）
+    pub fn has_prg_ram(&self) -> bool {
+        !self.prg_ram.is_empty()
+    }
+
+    /// $6000 起的位址在 PRG RAM 中的索引；小於視窗的 RAM（例如 2KB）會鏡像，
+    /// 超過 8KB 的 RAM 由 Mapper 選擇 bank
+    fn prg_ram_index(&self, addr: u16) -> Option<usize> {
+        let len = self.prg_ram.len();
+        let offset = self.mapper.prg_ram_bank() * 0x2000 + (addr - 0x6000) as usize;
+        (len > 0).then(|| offset % len)
+    }
+
     /// 重置卡帶
     pub fn reset(&mut self) {
         self.mapper.reset();
     }
 
-    /// CPU 讀取
+    /// CPU 讀取（沒有映射的位址回傳 0）
     pub fn cpu_read(&self, addr: u16) -> u8 {
-        // PRG RAM ($6000-$7FFF) — 直接存取，不依賴 Mapper
-        if addr >= 0x6000 && addr < 0x8000 {
-            let index = (addr - 0x6000) as usize;
-            return self.prg_ram.get(index).copied().unwrap_or(0);
-        }
+        self.cpu_read_mapped(addr).unwrap_or(0)
+    }
 
-        if let Some(mapped) = self.mapper.cpu_read(addr) {
-            if addr >= 0x8000 {
-                // PRG ROM
-                let index = mapped as usize % self.prg_rom.len().max(1);
-                self.prg_rom.get(index).copied().unwrap_or(0)
-            } else {
-                0
+    /// CPU 讀取；卡帶沒有驅動資料匯流排時（未映射的位址、PRG RAM
+    /// 未啟用）回傳 None，由匯流排補上開路值
+    pub fn cpu_read_mapped(&self, addr: u16) -> Option<u8> {
+        // PRG RAM ($6000-$7FFF) — 資料在卡帶，啟用與防寫由 Mapper 決定
+        if (0x6000..self.prg_ram_end).contains(&addr) {
+            if !self.mapper.prg_ram_access().readable() {
+                // RAM 未啟用時 FME-7 等 Mapper 可以把 PRG ROM 映射到這裡
+                return self.mapper.cpu_read(addr).map(|mapped| self.prg_rom_byte(mapped));
             }
-        } else {
-            0
+            return self.prg_ram_index(addr).map(|index| self.prg_ram[index]);
+        }
+
+        if addr < 0x8000 {
+            return None;
         }
+        self.mapper.cpu_read(addr).map(|mapped| self.prg_rom_byte(mapped))
+    }
+
+    /// 依 Mapper 換算的偏移量讀取 PRG ROM（超出大小時折返）
+    fn prg_rom_byte(&self, mapped: u32) -> u8 {
+        let index = match self.prg_mask {
+            Some(mask) => mapped as usize & mask,
+            None => mapped as usize % self.prg_rom.len().max(1),
+        };
+        self.prg_rom.get(index).copied().unwrap_or(0)
     }
 
     /// CPU 寫入
     pub fn cpu_write(&mut self, addr: u16, data: u8) {
-        if addr >= 0x6000 && addr < 0x8000 {
+        if (0x6000..self.prg_ram_end).contains(&addr) && self.mapper.prg_ram_access().writable() {
             // PRG RAM 寫入
-            let index = (addr - 0x6000) as usize;
-            if index < self.prg_ram.len() {
+            if let Some(index) = self.prg_ram_index(addr) {
+                if self.header.has_battery && self.prg_ram[index] != data {
+                    self.prg_ram_dirty = true;
+                }
                 self.prg_ram[index] = data;
             }
         }
 
         // 通知 Mapper（可能觸發 bank 切換等）
         if let Some(result) = self.mapper.cpu_write(addr, data) {
-            if let Some(mode) = result.mirror_mode {
+            // 四畫面卡帶的 VRAM 接線固定，Mapper 的鏡像切換不起作用
+            let four_screen = self.header.mirror_mode == MirrorMode::FourScreen;
+            if let Some(mode) = result.mirror_mode.filter(|_| !four_screen) {
                 self.header.mirror_mode = mode;
             }
         }
     }
 
-    /// PPU 讀取（CHR ROM/RAM）
-    pub fn ppu_read(&self, addr: u16) -> u8 {
-        if let Some(mapped) = self.mapper.ppu_read(addr) {
-            let index = mapped as usize % self.chr_data.len().max(1);
-            self.chr_data.get(index).copied().unwrap_or(0)
-        } else {
-            0
-        }
-    }
-
-    /// PPU 寫入（只有 CHR RAM 可寫）
-    pub fn ppu_write(&mut self, addr: u16, data: u8) {
-        if self.chr_ram {
-            if let Some(mapped) = self.mapper.ppu_write(addr) {
-                let index = mapped as usize;
-                if index < self.chr_data.len() {
-                    self.chr_data[index] = data;
-                }
-            }
+    /// 取得 ROM 資訊摘要
+    pub fn rom_info(&self) -> RomInfo {
+        let h = &self.header;
+        RomInfo {
+            mapper_id: h.mapper_number,
+            submapper: h.submapper,
+            mapper_name: mapper_name(h.mapper_number),
+            mapper_supported: is_mapper_supported(h.mapper_number) || self.disk_drive().is_some(),
+            prg_rom_size: self.prg_rom.len(),
+            chr_rom_size: h.chr_rom_banks as usize * 8192,
+            chr_ram: self.chr_ram,
+            has_battery: h.has_battery,
+            prg_ram_size: self.prg_ram.len(),
+            mirror_mode: h.mirror_mode,
+            has_trainer: h.has_trainer,
+            is_nes2: h.is_nes2,
+            region: h.region,
+            crc32: self.crc32,
+            compat_hacks: self.compat_hacks.iter().map(|h| h.name()).collect(),
+            header_cleaned: h.header_cleaned,
+            vs_ppu: h.vs_ppu.map(VsPpu::name),
         }
     }
 
-    /// 通知 Mapper 掃描線計數（用於 MMC3 等）
+    /// 通知 Mapper 掃描線計數（固定在每條掃描線的第 260 週期）
     pub fn scanline(&mut self) {
         self.mapper.scanline();
     }
 
+    /// 通知 Mapper PPU A12 的上升緣（用於 MMC3 等）
+    pub fn a12_clock(&mut self) {
+        self.mapper.a12_clock();
+    }
+
     /// 通知 Mapper CPU 週期計數
     pub fn cpu_clock(&mut self) {
         self.mapper.cpu_clock();
@@ -246,4 +577,94 @@ impl Cartridge {
     pub fn mirror_mode(&self) -> MirrorMode {
         self.header.mirror_mode
     }
+
+    /// 圖案表位址在 chr_data 中的索引
+    ///
+    /// CHR RAM 直接對應；CHR ROM 依 Mapper 回傳的 1KB bank 起點加上 bank 內的
+    /// 偏移量，超出大小時折返（2 的次方大小只需一次 AND）。
+    #[inline]
+    fn chr_index(&self, addr: u16) -> usize {
+        let addr = addr & 0x1FFF;
+        if self.chr_ram {
+            return addr as usize;
+        }
+        let bank = addr & 0x1C00;
+        let mapped = self.mapper.ppu_read(bank).unwrap_or(bank as u32) as usize + (addr & 0x03FF) as usize;
+        match self.chr_mask {
+            Some(mask) => mapped & mask,
+            None => mapped % self.chr_data.len().max(1),
+        }
+    }
+}
+
+impl PpuBus for Cartridge {
+    /// 圖磚 $FD/$FE 的高位元平面讀取通知 Mapper（MMC2/MMC4 的 CHR 鎖存器），
+    /// 這次讀取仍使用切換前的 bank
+    #[inline]
+    fn read(&mut self, addr: u16) -> u8 {
+        let value = self.peek(addr);
+        if matches!(addr & 0x0FF8, 0x0FD8 | 0x0FE8) && self.mapper.watches_ppu_fetch() {
+            self.mapper.ppu_fetch(addr);
+        }
+        value
+    }
+
+    #[inline]
+    fn peek(&self, addr: u16) -> u8 {
+        self.chr_data.get(self.chr_index(addr)).copied().unwrap_or(0)
+    }
+
+    /// CHR RAM 可寫入，或混合模式下 Mapper 標示可寫入的 bank
+    fn write(&mut self, addr: u16, data: u8) {
+        let bank = (addr >> 10) & 0x07;
+        if self.chr_ram || self.mapper.chr_writable_mask() & (1 << bank) != 0 {
+            let index = self.chr_index(addr);
+            if let Some(byte) = self.chr_data.get_mut(index) {
+                *byte = data;
+            }
+        }
+    }
+
+    #[inline]
+    fn mirroring(&self) -> MirrorMode {
+        self.header.mirror_mode
+    }
+}
+
+// ============================================================
+// CRC32（IEEE 802.3 多項式，與 zip/NesCartDB 相同）
+// ============================================================
+
+/// CRC32 查表（編譯期產生）
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+/// 計算資料的 CRC32
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in data {
+        crc = CRC32_TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// NES 2.0 標頭位元組 10 換算的 PRG RAM 總大小：兩個 4 位元欄位各為
+/// 64 << n 位元組（0 表示沒有）。標示電池卻沒有填大小的標頭視為 8KB
+fn nes2_prg_ram_size(value: u8, has_battery: bool) -> usize {
+    let field = |shift: u8| if shift == 0 { 0 } else { 64usize << shift };
+    let size = field(value & 0x0F) + field(value >> 4);
+    if size == 0 && has_battery { 8192 } else { size }
 }
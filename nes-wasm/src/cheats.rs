@@ -0,0 +1,248 @@
+// ============================================================
+// 金手指 - Game Genie 與原始位址碼
+// ============================================================
+// 金手指以「讀取替換」實作：CPU 讀取指定位址時回傳替換值，
+// 記憶體本身不被修改，停用金手指後立即恢復原狀。
+//
+// 支援格式：
+// - Game Genie 6 字母（位址 + 替換值）
+// - Game Genie 8 字母（位址 + 替換值 + 比較值，原值相符才替換，
+//   用於 ROM 在 bank 切換後同一位址出現不同內容的情況）
+// - 原始碼：「AAAA:VV」、「AAAA?CC:VV」或「AAAA:VV:CC」（十六進位，CC 為比較值）
+//
+// 金手指清單隨存檔保存，讀檔後回到存檔當時的清單與啟用狀態。
+//
+// 參考：https://www.nesdev.org/wiki/Game_Genie
+// ============================================================
+
+use crate::savestate::{StateField, StateReader};
+
+/// Game Genie 字母表（索引即為 4 位元數值）
+const GAME_GENIE_LETTERS: &[u8; 16] = b"APZLGITYEOXUKSVN";
+
+/// 金手指種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheatKind {
+    /// Game Genie 碼
+    GameGenie,
+    /// 原始位址碼
+    Raw,
+}
+
+impl CheatKind {
+    /// 種類名稱（用於 JSON）
+    pub fn name(self) -> &'static str {
+        match self {
+            CheatKind::GameGenie => "gameGenie",
+            CheatKind::Raw => "raw",
+        }
+    }
+}
+
+/// 解碼後的金手指
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cheat {
+    /// 識別碼（新增時配發，不重複使用）
+    pub id: u32,
+    /// 原始輸入（已轉為大寫、去除空白）
+    pub code: String,
+    /// 種類
+    pub kind: CheatKind,
+    /// CPU 位址
+    pub address: u16,
+    /// 替換值
+    pub value: u8,
+    /// 比較值（原值相符才替換）
+    pub compare: Option<u8>,
+    /// 是否啟用
+    pub enabled: bool,
+}
+
+impl Cheat {
+    /// 解碼金手指（含「:」視為原始碼，否則為 Game Genie 碼），新增時為啟用狀態
+    fn decode(id: u32, code: &str) -> Option<Cheat> {
+        let code: String = code.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase();
+        let (kind, (address, value, compare)) = if code.contains(':') {
+            (CheatKind::Raw, decode_raw(&code)?)
+        } else {
+            (CheatKind::GameGenie, decode_game_genie(&code)?)
+        };
+        Some(Cheat { id, code, kind, address, value, compare, enabled: true })
+    }
+}
+
+/// 解碼 Game Genie 碼（6 或 8 字母），回傳（位址, 替換值, 比較值）
+pub fn decode_game_genie(code: &str) -> Option<(u16, u8, Option<u8>)> {
+    let n: Vec<u16> = code
+        .bytes()
+        .map(|c| GAME_GENIE_LETTERS.iter().position(|&l| l == c.to_ascii_uppercase()).map(|i| i as u16))
+        .collect::<Option<_>>()?;
+    if n.len() != 6 && n.len() != 8 {
+        return None;
+    }
+    let address = 0x8000
+        | ((n[3] & 7) << 12)
+        | ((n[5] & 7) << 8)
+        | ((n[4] & 8) << 8)
+        | ((n[2] & 7) << 4)
+        | ((n[1] & 8) << 4)
+        | (n[4] & 7)
+        | (n[3] & 8);
+    let value_low = ((n[1] & 7) << 4) | ((n[0] & 8) << 4) | (n[0] & 7);
+    if n.len() == 6 {
+        let value = value_low | (n[5] & 8);
+        Some((address, value as u8, None))
+    } else {
+        let value = value_low | (n[7] & 8);
+        let compare = ((n[7] & 7) << 4) | ((n[6] & 8) << 4) | (n[6] & 7) | (n[5] & 8);
+        Some((address, value as u8, Some(compare as u8)))
+    }
+}
+
+/// 解碼原始碼「AAAA:VV」、「AAAA?CC:VV」或「AAAA:VV:CC」，回傳（位址, 替換值, 比較值）
+pub fn decode_raw(code: &str) -> Option<(u16, u8, Option<u8>)> {
+    let code = code.strip_prefix('$').unwrap_or(code);
+    let (target, value) = code.split_once(':')?;
+    let (address, compare) = match target.split_once('?') {
+        Some((a, c)) => (a, Some(c)),
+        None => (target, None),
+    };
+    let (value, compare) = match value.split_once(':') {
+        Some((v, c)) if compare.is_none() => (v, Some(c)),
+        Some(_) => return None,
+        None => (value, compare),
+    };
+    let compare = match compare {
+        Some(c) if !c.is_empty() && c.len() <= 2 => Some(u8::from_str_radix(c, 16).ok()?),
+        Some(_) => return None,
+        None => None,
+    };
+    if address.is_empty() || address.len() > 4 || value.is_empty() || value.len() > 2 {
+        return None;
+    }
+    let address = u16::from_str_radix(address, 16).ok()?;
+    let value = u8::from_str_radix(value, 16).ok()?;
+    Some((address, value, compare))
+}
+
+/// 金手指管理
+#[derive(Debug, Clone)]
+pub struct CheatEngine {
+    cheats: Vec<Cheat>,
+    next_id: u32,
+    /// 是否有任何啟用中的金手指（CPU 讀取時的快速判斷）
+    active: bool,
+}
+
+impl Default for CheatEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CheatEngine {
+    /// 建立空的金手指清單
+    pub fn new() -> Self {
+        CheatEngine { cheats: Vec::new(), next_id: 1, active: false }
+    }
+
+    /// 新增金手指（自動判斷 Game Genie 或原始碼），格式錯誤回傳 None
+    pub fn add(&mut self, code: &str) -> Option<u32> {
+        let id = self.next_id;
+        self.cheats.push(Cheat::decode(id, code)?);
+        self.next_id += 1;
+        self.update_active();
+        Some(id)
+    }
+
+    /// 移除金手指，找不到時回傳 false
+    pub fn remove(&mut self, id: u32) -> bool {
+        let before = self.cheats.len();
+        self.cheats.retain(|c| c.id != id);
+        self.update_active();
+        self.cheats.len() != before
+    }
+
+    /// 啟用或停用金手指，找不到時回傳 false
+    pub fn set_enabled(&mut self, id: u32, enabled: bool) -> bool {
+        let Some(cheat) = self.cheats.iter_mut().find(|c| c.id == id) else { return false };
+        cheat.enabled = enabled;
+        self.update_active();
+        true
+    }
+
+    /// 移除所有金手指
+    pub fn clear(&mut self) {
+        self.cheats.clear();
+        self.active = false;
+    }
+
+    /// 所有金手指
+    pub fn list(&self) -> &[Cheat] {
+        &self.cheats
+    }
+
+    /// 是否有啟用中的金手指
+    #[inline(always)]
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// 套用讀取替換：回傳 CPU 實際看到的值
+    pub fn apply(&self, addr: u16, value: u8) -> u8 {
+        self.cheats
+            .iter()
+            .find(|c| c.enabled && c.address == addr && c.compare.is_none_or(|cmp| cmp == value))
+            .map_or(value, |c| c.value)
+    }
+
+    /// 輸出為 JSON 陣列
+    pub fn to_json(&self) -> String {
+        let items: Vec<String> = self.cheats.iter().map(|c| {
+            let compare = c.compare.map_or("null".to_string(), |v| v.to_string());
+            format!(
+                "{{\"id\":{},\"code\":\"{}\",\"kind\":\"{}\",\"address\":{},\"value\":{},\
+                 \"compare\":{},\"enabled\":{}}}",
+                c.id, c.code, c.kind.name(), c.address, c.value, compare, c.enabled,
+            )
+        }).collect();
+        format!("[{}]", items.join(","))
+    }
+
+    fn update_active(&mut self) {
+        self.active = self.cheats.iter().any(|c| c.enabled);
+    }
+}
+
+/// 存檔只記錄 ID、啟用狀態與原始輸入，讀檔時重新解碼
+impl StateField for CheatEngine {
+    fn save(&self, out: &mut Vec<u8>) {
+        self.next_id.save(out);
+        (self.cheats.len() as u32).save(out);
+        for cheat in &self.cheats {
+            cheat.id.save(out);
+            cheat.enabled.save(out);
+            (cheat.code.len() as u8).save(out);
+            out.extend_from_slice(cheat.code.as_bytes());
+        }
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> Option<()> {
+        let next_id = u32::from_le_bytes(r.array()?);
+        let count = u32::from_le_bytes(r.array()?) as usize;
+        let mut cheats = Vec::new();
+        for _ in 0..count {
+            let id = u32::from_le_bytes(r.array()?);
+            let [enabled] = r.array()?;
+            let [len] = r.array()?;
+            let code = std::str::from_utf8(r.take(len as usize)?).ok()?;
+            let mut cheat = Cheat::decode(id, code)?;
+            cheat.enabled = enabled != 0;
+            cheats.push(cheat);
+        }
+        self.cheats = cheats;
+        self.next_id = next_id;
+        self.update_active();
+        Some(())
+    }
+}
@@ -0,0 +1,63 @@
+// ============================================================
+// 相容性修正資料庫 - 以 ROM CRC32 對應個別遊戲需要的特殊處理
+// ============================================================
+// 少數遊戲依賴特定卡帶版本或實機的非預期行為，光靠 iNES 標頭無法判斷：
+// - MMC3 IRQ 舊版行為（MMC3A/NEC 版）：計數器重新載入為 0 時不觸發 IRQ，
+//   只有遞減到 0 或寫入 $C001 後才觸發
+// - 強制四畫面：四畫面卡帶（TR1ROM 等）的常見 dump 標頭沒有標示
+//   四畫面位元
+//
+// 載入 ROM 時以 CRC32（PRG+CHR，不含標頭、訓練器與檔尾的多餘資料）
+// 查表並套用，套用的項目會列在 RomInfo 中，讓前端可以顯示。
+// 表中的 CRC 為 NesCartDB 列出的 PRG+CHR CRC32，註解標示對應的 dump。
+//
+// 參考：
+// - https://www.nesdev.org/wiki/MMC3#IRQ_Specifics
+// - https://nescartdb.com/
+// ============================================================
+
+/// 相容性修正項目
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompatHack {
+    /// MMC3 IRQ 舊版行為
+    Mmc3AltIrq,
+    /// 強制四畫面鏡像，忽略 Mapper 的鏡像切換
+    FourScreen,
+}
+
+impl CompatHack {
+    /// 項目名稱（用於 RomInfo 與 JSON）
+    pub fn name(self) -> &'static str {
+        match self {
+            CompatHack::Mmc3AltIrq => "mmc3AltIrq",
+            CompatHack::FourScreen => "fourScreen",
+        }
+    }
+}
+
+/// 資料庫中的一筆遊戲
+#[derive(Debug, Clone, Copy)]
+pub struct CompatEntry {
+    /// ROM CRC32（與 Cartridge::crc32 相同的計算範圍）
+    pub crc32: u32,
+    /// 遊戲名稱（僅供辨識）
+    pub title: &'static str,
+    /// 需要套用的修正
+    pub hacks: &'static [CompatHack],
+}
+
+/// 相容性修正表
+pub const COMPAT_DB: &[CompatEntry] = &[
+    // Low G Man - The Low Gravity Man (USA).nes（No-Intro），MMC3A 卡帶
+    CompatEntry { crc32: 0x6B4CAC80, title: "Low G Man (USA)", hacks: &[CompatHack::Mmc3AltIrq] },
+    // Rad Racer II (USA).nes（No-Intro），TVROM 四畫面卡帶
+    CompatEntry { crc32: 0x4B4BD1F7, title: "Rad Racer II (USA)", hacks: &[CompatHack::FourScreen] },
+];
+
+/// 查詢 ROM 需要的修正（沒有登錄時回傳空切片）
+pub fn lookup(crc32: u32) -> &'static [CompatHack] {
+    COMPAT_DB
+        .iter()
+        .find(|entry| entry.crc32 == crc32)
+        .map_or(&[], |entry| entry.hacks)
+}
@@ -0,0 +1,364 @@
+// ============================================================
+// 模擬器設定 - 集中管理各子系統選項
+// ============================================================
+// 所有可調整的選項集中在 EmulatorConfig，由 Emulator::set_config
+// 一次推送到 PPU/APU 等子系統，避免各處零散的 setter。
+//
+// JavaScript 端透過 setConfig(json) 傳入扁平 JSON 物件，
+// 只需包含要變更的欄位，其餘欄位維持原值：
+//   { "sampleRate": 48000, "spriteLimit": false, "region": "pal" }
+// ============================================================
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::cpu::DEFAULT_UNSTABLE_MAGIC;
+
+/// 主機地區（決定時序與調色盤）
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    /// 北美/日本 NTSC（2C02）
+    Ntsc,
+    /// 歐洲 PAL（2C07）
+    Pal,
+    /// 俄羅斯/中國相容機 Dendy
+    Dendy,
+}
+
+impl Region {
+    /// 地區名稱（小寫英文，用於 JSON）
+    pub fn name(self) -> &'static str {
+        match self {
+            Region::Ntsc => "ntsc",
+            Region::Pal => "pal",
+            Region::Dendy => "dendy",
+        }
+    }
+
+    /// 由名稱取得地區（不分大小寫，例如 "PAL"、"ntsc"）
+    pub fn from_name(name: &str) -> Option<Region> {
+        [Region::Ntsc, Region::Pal, Region::Dendy]
+            .into_iter()
+            .find(|region| region.name().eq_ignore_ascii_case(name))
+    }
+
+    /// 每幀掃描線數（含預渲染線）
+    pub fn scanlines_per_frame(self) -> u16 {
+        match self {
+            Region::Ntsc => 262,
+            Region::Pal | Region::Dendy => 312,
+        }
+    }
+
+    /// 設定 VBlank 旗標的掃描線：Dendy 把 PAL 多出的 50 條放在
+    /// VBlank 之前（後渲染期間），VBlank 長度與 NTSC 相同都是 20 條
+    pub fn vblank_start_line(self) -> i16 {
+        match self {
+            Region::Ntsc | Region::Pal => 241,
+            Region::Dendy => 291,
+        }
+    }
+
+    /// 每個 CPU 週期對應的 PPU 週期數，以（分子, 分母）表示：
+    /// NTSC 與 Dendy 為 3，PAL 為 16/5 = 3.2
+    pub fn ppu_dots_per_cpu_cycle(self) -> (u64, u64) {
+        match self {
+            Region::Ntsc | Region::Dendy => (3, 1),
+            Region::Pal => (16, 5),
+        }
+    }
+}
+
+/// 快轉時的聲音處理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurboAudio {
+    /// 快轉期間不輸出聲音
+    Mute,
+    /// 把快轉的多幀聲音降頻成一幀的取樣數（音高隨倍率升高）
+    Resample,
+}
+
+impl TurboAudio {
+    /// 名稱（小寫英文，用於 JSON）
+    pub fn name(self) -> &'static str {
+        match self {
+            TurboAudio::Mute => "mute",
+            TurboAudio::Resample => "resample",
+        }
+    }
+
+    /// 由名稱取得處理方式（不分大小寫）
+    pub fn from_name(name: &str) -> Option<TurboAudio> {
+        [TurboAudio::Mute, TurboAudio::Resample]
+            .into_iter()
+            .find(|policy| policy.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// 模擬器設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmulatorConfig {
+    /// 音頻取樣率（Hz）
+    pub sample_rate: f64,
+    /// 是否保留每條掃描線 8 個精靈的硬體限制（關閉可減少閃爍）
+    pub sprite_limit: bool,
+    /// 是否裁切上下各 8 條掃描線（電視過掃描區域）；開啟時 `Emulator::visible_frame`
+    /// 輸出 256×224 的畫面
+    pub crop_overscan: bool,
+    /// 是否啟用音頻輸出濾波器（低通 + 高通）
+    pub audio_filter: bool,
+    /// 是否輸出 L/R 交錯的立體聲（各聲道的左右位置以 Apu::set_channel_pan 設定）
+    pub stereo: bool,
+    /// 三角波照實機行為：超音波頻率與停止時不靜音，保持 DAC 的輸出準位
+    pub accurate_triangle: bool,
+    /// 三角波靜音時逐步降低輸出以減少爆音（準確模式下沒有作用）
+    pub reduce_popping: bool,
+    /// 快轉（Emulator::set_speed 大於 1）時的聲音處理方式
+    pub turbo_audio: TurboAudio,
+    /// 電池 RAM 停止寫入多少幀後觸發儲存事件
+    pub sram_flush_delay: u32,
+    /// 重新開機時保留電池 RAM（關閉則像拔掉電池一樣清除）
+    pub keep_battery_ram: bool,
+    /// 開機時 CPU 與 PPU 的時鐘相位：第一個 CPU 週期前 PPU 先走的點數
+    /// （NTSC/Dendy 為 0-2，PAL 為 0-3，超出範圍時折返），
+    /// None 表示每次開機以 power_on_seed 產生的序列隨機選擇
+    pub power_on_alignment: Option<u8>,
+    /// 隨機開機相位的種子（同一個種子的開機相位序列可重現）
+    pub power_on_seed: u64,
+    /// 強制指定主機地區，None 表示依 ROM 標頭判斷
+    pub region: Option<Region>,
+    /// 倒帶保留的存檔份數（0 為停用）
+    pub rewind_depth: u32,
+    /// 倒帶每隔幾幀擷取一次存檔
+    pub rewind_interval: u32,
+    /// 不穩定指令 XAA（$8B）與 LAX #imm（$AB）中與 A 做 OR 的常數，
+    /// 實機依晶片個體與溫度而不同，常見值為 $EE、$FF 與 $00
+    pub unstable_magic: u8,
+}
+
+impl Default for EmulatorConfig {
+    fn default() -> Self {
+        EmulatorConfig {
+            sample_rate: 44100.0,
+            sprite_limit: true,
+            crop_overscan: false,
+            audio_filter: true,
+            stereo: false,
+            accurate_triangle: false,
+            reduce_popping: false,
+            turbo_audio: TurboAudio::Resample,
+            sram_flush_delay: 30,
+            keep_battery_ram: true,
+            power_on_alignment: Some(0),
+            power_on_seed: 0,
+            region: None,
+            // 每 2 幀一份、保留 300 份：約 10 秒
+            rewind_depth: 300,
+            rewind_interval: 2,
+            unstable_magic: DEFAULT_UNSTABLE_MAGIC,
+        }
+    }
+}
+
+impl EmulatorConfig {
+    /// 以 JSON 物件更新設定，只覆寫有出現的欄位
+    /// 格式錯誤或型別不符時回傳 false，且不修改任何欄位
+    pub fn merge_json(&mut self, json: &str) -> bool {
+        match self.merged(json) {
+            Some(next) => { *self = next; true }
+            None => false,
+        }
+    }
+
+    fn merged(&self, json: &str) -> Option<Self> {
+        let mut next = self.clone();
+        for (key, value) in parse_flat_json(json)? {
+            match key.as_str() {
+                "sampleRate" => next.sample_rate = value.as_f64().filter(|&n| n > 0.0)?,
+                "spriteLimit" => next.sprite_limit = value.as_bool()?,
+                "cropOverscan" => next.crop_overscan = value.as_bool()?,
+                "audioFilter" => next.audio_filter = value.as_bool()?,
+                "stereo" => next.stereo = value.as_bool()?,
+                "accurateTriangle" => next.accurate_triangle = value.as_bool()?,
+                "reducePopping" => next.reduce_popping = value.as_bool()?,
+                "turboAudio" => next.turbo_audio = TurboAudio::from_name(value.as_str()?)?,
+                "sramFlushDelay" => next.sram_flush_delay = value.as_f64().filter(|&n| n >= 0.0)? as u32,
+                "keepBatteryRam" => next.keep_battery_ram = value.as_bool()?,
+                // 0-3 為固定相位（3 只有 PAL 使用），"random" 為隨機
+                "powerOnAlignment" => next.power_on_alignment = match value {
+                    JsonValue::Str(s) if s == "random" => None,
+                    _ => Some(value.as_f64().filter(|&n| n.fract() == 0.0 && (0.0..=3.0).contains(&n))? as u8),
+                },
+                "powerOnSeed" => next.power_on_seed = value.as_f64().filter(|&n| n >= 0.0)? as u64,
+                // "auto" 為依 ROM 標頭判斷
+                "region" => next.region = match value.as_str()? {
+                    "auto" => None,
+                    name => Some(Region::from_name(name)?),
+                },
+                "rewindDepth" => next.rewind_depth = value.as_f64().filter(|&n| n >= 0.0)? as u32,
+                "rewindInterval" => next.rewind_interval = value.as_f64().filter(|&n| n >= 1.0)? as u32,
+                "unstableMagic" => next.unstable_magic = value.as_f64().filter(|&n| (0.0..=255.0).contains(&n))? as u8,
+                // 未知欄位忽略，方便前端傳入較新版本的設定
+                _ => {}
+            }
+        }
+        Some(next)
+    }
+
+    /// 將設定輸出為 JSON 字串
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"sampleRate\":{},\"spriteLimit\":{},\"cropOverscan\":{},\"audioFilter\":{},\"stereo\":{},\
+             \"accurateTriangle\":{},\"reducePopping\":{},\"turboAudio\":\"{}\",\
+             \"sramFlushDelay\":{},\"keepBatteryRam\":{},\"powerOnAlignment\":{},\"powerOnSeed\":{},\
+             \"region\":\"{}\",\"rewindDepth\":{},\"rewindInterval\":{},\"unstableMagic\":{}}}",
+            self.sample_rate, self.sprite_limit, self.crop_overscan, self.audio_filter, self.stereo,
+            self.accurate_triangle, self.reduce_popping, self.turbo_audio.name(),
+            self.sram_flush_delay, self.keep_battery_ram,
+            self.power_on_alignment.map_or("\"random\"".to_string(), |d| d.to_string()),
+            self.power_on_seed,
+            self.region.map_or("auto", Region::name),
+            self.rewind_depth, self.rewind_interval, self.unstable_magic,
+        )
+    }
+}
+
+// ============================================================
+// 扁平 JSON 解析（只支援一層物件，值為數字、布林或字串）
+// ============================================================
+
+/// JSON 值
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Number(f64),
+    Bool(bool),
+    Str(String),
+}
+
+impl JsonValue {
+    /// 取出數字值
+    pub fn as_f64(&self) -> Option<f64> {
+        if let JsonValue::Number(n) = self { Some(*n) } else { None }
+    }
+
+    /// 取出布林值
+    pub fn as_bool(&self) -> Option<bool> {
+        if let JsonValue::Bool(b) = self { Some(*b) } else { None }
+    }
+
+    /// 取出字串值
+    pub fn as_str(&self) -> Option<&str> {
+        if let JsonValue::Str(s) = self { Some(s) } else { None }
+    }
+}
+
+/// 解析 `{"key": value, ...}` 形式的扁平 JSON 物件
+pub fn parse_flat_json(json: &str) -> Option<Vec<(String, JsonValue)>> {
+    let mut p = JsonCursor { bytes: json.as_bytes(), pos: 0 };
+    let mut entries = Vec::new();
+
+    p.skip_ws();
+    p.expect(b'{')?;
+    p.skip_ws();
+    if p.peek() == Some(b'}') {
+        p.pos += 1;
+    } else {
+        loop {
+            p.skip_ws();
+            let key = p.string()?;
+            p.skip_ws();
+            p.expect(b':')?;
+            p.skip_ws();
+            let value = p.value()?;
+            entries.push((key, value));
+            p.skip_ws();
+            match p.next()? {
+                b',' => continue,
+                b'}' => break,
+                _ => return None,
+            }
+        }
+    }
+    p.skip_ws();
+    if p.pos != p.bytes.len() { return None; }
+    Some(entries)
+}
+
+struct JsonCursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl JsonCursor<'_> {
+    fn peek(&self) -> Option<u8> { self.bytes.get(self.pos).copied() }
+
+    fn next(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn expect(&mut self, c: u8) -> Option<()> {
+        if self.next()? == c { Some(()) } else { None }
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.expect(b'"')?;
+        let mut out = String::new();
+        loop {
+            match self.next()? {
+                b'"' => return Some(out),
+                b'\\' => match self.next()? {
+                    b'"' => out.push('"'),
+                    b'\\' => out.push('\\'),
+                    b'/' => out.push('/'),
+                    b'n' => out.push('\n'),
+                    b't' => out.push('\t'),
+                    _ => return None,
+                },
+                c if c < 0x80 => out.push(c as char),
+                _ => {
+                    // 多位元組 UTF-8：整段複製
+                    let start = self.pos - 1;
+                    while matches!(self.peek(), Some(c) if c & 0xC0 == 0x80) {
+                        self.pos += 1;
+                    }
+                    out.push_str(std::str::from_utf8(&self.bytes[start..self.pos]).ok()?);
+                }
+            }
+        }
+    }
+
+    fn value(&mut self) -> Option<JsonValue> {
+        match self.peek()? {
+            b'"' => self.string().map(JsonValue::Str),
+            b't' => self.literal("true").map(|_| JsonValue::Bool(true)),
+            b'f' => self.literal("false").map(|_| JsonValue::Bool(false)),
+            _ => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')) {
+                    self.pos += 1;
+                }
+                let text = std::str::from_utf8(&self.bytes[start..self.pos]).ok()?;
+                text.parse::<f64>().ok().filter(|n| n.is_finite()).map(JsonValue::Number)
+            }
+        }
+    }
+
+    fn literal(&mut self, word: &str) -> Option<()> {
+        let end = self.pos + word.len();
+        if self.bytes.get(self.pos..end)? == word.as_bytes() {
+            self.pos = end;
+            Some(())
+        } else {
+            None
+        }
+    }
+}
@@ -6,8 +6,48 @@
 //
 // CPU 通過寫入 $4016 來鎖存按鈕狀態，
 // 然後逐位元讀取 $4016/$4017 來取得各按鈕狀態。
+//
+// 同一個埠也可以改接其他裝置（InputDevice）：
+// - Zapper 光線槍：位元 3 = 0 表示感應到光，位元 4 = 1 表示扣下扳機。
+//   感光元件看到的是瞄準點周圍一小塊區域：電子束掃過區域內足夠多的
+//   亮像素後，輸出一段約 20 條掃描線的脈衝，之後即使畫面仍亮也讀不到光。
+//   扳機是機械開關，扣下後只會短暫讀到 1（約 100ms），按住不放不會連發。
+//   瞄準畫面外扣扳機（Duck Hunt 等遊戲的「打空」、部分遊戲的換彈）
+//   由 fire_zapper_offscreen 模擬，不需要前端移開游標。
+// - Arkanoid 旋鈕（NES 版）：選通時鎖存旋鈕位置，之後從位元 4
+//   以 MSB 優先、反相的方式逐位元讀出；位元 3 為按鈕。
+//
+// Four Score 四人轉接器接在兩個埠上，每個埠多串一支控制器：
+// 一次讀出 24 位元，依序為本埠玩家（1P/2P）、轉接玩家（3P/4P）的
+// 8 個按鈕，再接 8 位元的識別碼（$4016 為 $08、$4017 為 $04，
+// LSB 優先），之後固定讀到 1。
+//
+// 參考：
+// - https://www.nesdev.org/wiki/Zapper
+// - https://www.nesdev.org/wiki/Arkanoid_controller
+// - https://www.nesdev.org/wiki/Four_Score
 // ============================================================
 
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::ppu::Ppu;
+use crate::savestate::impl_state_fields;
+
+/// 感光脈衝寬度：累積到足夠亮度後維持反應的掃描線數
+const ZAPPER_LIGHT_LINES: i32 = 20;
+/// 判定為「亮」的亮度門檻（0-255）
+const ZAPPER_BRIGHTNESS: u32 = 0x80;
+/// 感光元件的視野半徑（瞄準點上下左右各幾個像素）
+const ZAPPER_RADIUS: i32 = 3;
+/// 視野內至少要掃過幾個亮像素才會觸發脈衝（只照到一兩個點不夠）
+const ZAPPER_MIN_LIT: u32 = 4;
+/// 扳機脈衝長度（幀，約 100ms）
+const ZAPPER_TRIGGER_FRAMES: u8 = 6;
+
+/// Four Score 識別碼（第 17-24 次讀取，LSB 優先）
+pub const FOUR_SCORE_SIGNATURES: [u8; 2] = [0x08, 0x04];
+
 /// 按鈕定義（與 JavaScript 端一致）
 pub const BTN_A: u8 = 0;
 pub const BTN_B: u8 = 1;
@@ -18,14 +58,69 @@ pub const BTN_DOWN: u8 = 5;
 pub const BTN_LEFT: u8 = 6;
 pub const BTN_RIGHT: u8 = 7;
 
+/// 控制器按鈕（匯出給 JavaScript，數值與 BTN_* 常數相同）
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Button {
+    A = 0,
+    B = 1,
+    Select = 2,
+    Start = 3,
+    Up = 4,
+    Down = 5,
+    Left = 6,
+    Right = 7,
+}
+
+/// 控制器埠上連接的裝置
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputDevice {
+    /// 未連接（讀取一律回傳 0）
+    None = 0,
+    /// 標準控制器
+    Gamepad = 1,
+    /// Zapper 光線槍
+    Zapper = 2,
+    /// Arkanoid 旋鈕控制器（NES 版）
+    Paddle = 3,
+}
+
 /// NES 控制器
 pub struct Controller {
     /// 按鈕狀態（8 位元，每位元代表一個按鈕）
     button_state: u8,
-    /// 目前讀取的移位暫存器
-    shift_register: u8,
+    /// 目前讀取的移位暫存器（標準控制器用低 8 位元，Four Score 用 24 位元）
+    shift_register: u32,
     /// 選通（strobe）模式
     strobe: bool,
+    /// 連接的裝置
+    device: InputDevice,
+    /// Zapper 瞄準位置（畫面像素座標，畫面外為負值）
+    zapper_x: i32,
+    zapper_y: i32,
+    /// Zapper 扳機是否扣下（前端輸入）
+    zapper_trigger: bool,
+    /// 扳機脈衝剩餘幀數（大於 0 時位元 4 讀到 1）
+    zapper_trigger_frames: u8,
+    /// 瞄準畫面外射擊的剩餘幀數（期間感光元件看不到畫面）
+    zapper_offscreen_frames: u8,
+    /// 旋鈕位置
+    paddle_value: u8,
+    /// 旋鈕按鈕是否按下
+    paddle_button: bool,
+    /// 經 Four Score 串在本埠的第二支控制器按鈕（3P/4P）
+    extra_buttons: u8,
+    /// Four Score 識別碼（None 表示未接轉接器）
+    four_score: Option<u8>,
+}
+
+impl_state_fields!(Controller { shift_register });
+
+impl Default for Controller {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Controller {
@@ -35,6 +130,16 @@ impl Controller {
             button_state: 0,
             shift_register: 0,
             strobe: false,
+            device: InputDevice::Gamepad,
+            zapper_x: -1,
+            zapper_y: -1,
+            zapper_trigger: false,
+            zapper_trigger_frames: 0,
+            zapper_offscreen_frames: 0,
+            paddle_value: 0,
+            paddle_button: false,
+            extra_buttons: 0,
+            four_score: None,
         }
     }
 
@@ -50,39 +155,203 @@ impl Controller {
         }
     }
 
+    /// 取得所有按鈕狀態（位元 0-7 對應 A, B, Select, Start, Up, Down, Left, Right）
+    pub fn buttons(&self) -> u8 {
+        self.button_state
+    }
+
+    /// 一次設定所有按鈕狀態
+    pub fn set_buttons(&mut self, state: u8) {
+        self.button_state = state;
+    }
+
+    /// 設定經 Four Score 串在本埠的第二支控制器按鈕（3P/4P）
+    pub fn set_extra_button(&mut self, button: u8, pressed: bool) {
+        if button > 7 { return; }
+        if pressed {
+            self.extra_buttons |= 1 << button;
+        } else {
+            self.extra_buttons &= !(1 << button);
+        }
+    }
+
+    /// 第二支控制器的按鈕狀態
+    pub fn extra_buttons(&self) -> u8 {
+        self.extra_buttons
+    }
+
+    /// 接上或拔除 Four Score（signature 為本埠的識別碼，見 FOUR_SCORE_SIGNATURES）
+    pub fn set_four_score(&mut self, signature: Option<u8>) {
+        self.four_score = signature;
+    }
+
     /// CPU 寫入（$4016）
     /// 寫入的最低位元控制選通模式
     pub fn write(&mut self, data: u8) {
         let new_strobe = data & 0x01 != 0;
         if self.strobe && !new_strobe {
             // 選通從高到低，鎖存目前的按鈕狀態
-            self.shift_register = self.button_state;
+            self.shift_register = self.latch_value();
         }
         self.strobe = new_strobe;
         if self.strobe {
             // 選通為高時，持續重新載入
-            self.shift_register = self.button_state;
+            self.shift_register = self.latch_value();
+        }
+    }
+
+    /// 選通時載入移位暫存器的值（讀完的位元之後補 1）
+    fn latch_value(&self) -> u32 {
+        match (self.device, self.four_score) {
+            // 旋鈕資料反相輸出
+            (InputDevice::Paddle, _) => u32::from(!self.paddle_value),
+            (InputDevice::Gamepad, Some(signature)) => {
+                u32::from(self.button_state)
+                    | u32::from(self.extra_buttons) << 8
+                    | u32::from(signature) << 16
+                    | 0xFF00_0000
+            }
+            _ => u32::from(self.button_state) | 0xFFFF_FF00,
         }
     }
 
     /// CPU 讀取（$4016/$4017）
-    /// 每次讀取回傳一個按鈕的狀態（最低位元）
-    pub fn read(&mut self) -> u8 {
+    /// 標準控制器每次讀取回傳一個按鈕的狀態（最低位元）；
+    /// Zapper 需要 PPU 目前的掃描位置與畫面內容判斷是否感應到光
+    pub fn read(&mut self, ppu: &Ppu) -> u8 {
+        match self.device {
+            InputDevice::None => 0,
+            InputDevice::Gamepad => self.read_gamepad(),
+            InputDevice::Zapper => {
+                let light = if self.zapper_senses_light(ppu) { 0 } else { 0x08 };
+                let trigger = if self.zapper_trigger_frames > 0 { 0x10 } else { 0 };
+                light | trigger
+            }
+            InputDevice::Paddle => {
+                let data = ((self.shift_register >> 7) as u8 & 1) << 4;
+                if !self.strobe {
+                    self.shift_register <<= 1;
+                }
+                let button = if self.paddle_button { 0x08 } else { 0 };
+                data | button
+            }
+        }
+    }
+
+    /// 標準控制器的串列讀取
+    fn read_gamepad(&mut self) -> u8 {
         if self.strobe {
             // 選通模式下，永遠回傳 A 按鈕的狀態
             return self.button_state & 1;
         }
-        let value = self.shift_register & 1;
+        let value = (self.shift_register & 1) as u8;
         self.shift_register >>= 1;
         // 移位完畢後填入 1（open bus 行為）
-        self.shift_register |= 0x80;
+        self.shift_register |= 0x8000_0000;
         value
     }
 
+    /// 取得讀取鎖存狀態（移位暫存器低 8 位元、選通），供存檔使用
+    pub fn latch_state(&self) -> (u8, bool) {
+        (self.shift_register as u8, self.strobe)
+    }
+
+    /// 還原讀取鎖存狀態（讀檔時使用，按鈕狀態仍由前端輸入決定）
+    /// 只有低 8 位元時其餘位元視為已讀完（補 1）；完整的暫存器另外存在 v7 區段
+    pub fn set_latch_state(&mut self, shift_register: u8, strobe: bool) {
+        self.shift_register = u32::from(shift_register) | 0xFFFF_FF00;
+        self.strobe = strobe;
+    }
+
+    /// 設定連接的裝置
+    pub fn set_device(&mut self, device: InputDevice) {
+        self.device = device;
+    }
+
+    /// 連接的裝置
+    pub fn device(&self) -> InputDevice {
+        self.device
+    }
+
+    /// 設定 Zapper 瞄準位置（畫面像素座標，超出 256x240 視為瞄準畫面外）
+    pub fn set_zapper_position(&mut self, x: i32, y: i32) {
+        self.zapper_x = x;
+        self.zapper_y = y;
+    }
+
+    /// 設定 Zapper 扳機；從放開變成扣下時產生一次扳機脈衝
+    pub fn set_zapper_trigger(&mut self, pulled: bool) {
+        if pulled && !self.zapper_trigger {
+            self.zapper_trigger_frames = ZAPPER_TRIGGER_FRAMES;
+        }
+        self.zapper_trigger = pulled;
+    }
+
+    /// 瞄準畫面外扣一次扳機：扳機脈衝期間感光元件看不到光，
+    /// 結束後回到原本的瞄準位置
+    pub fn fire_zapper_offscreen(&mut self) {
+        self.zapper_trigger_frames = ZAPPER_TRIGGER_FRAMES;
+        self.zapper_offscreen_frames = ZAPPER_TRIGGER_FRAMES;
+    }
+
+    /// 每幀結束時呼叫，推進扳機脈衝計時
+    pub fn end_frame(&mut self) {
+        self.zapper_trigger_frames = self.zapper_trigger_frames.saturating_sub(1);
+        self.zapper_offscreen_frames = self.zapper_offscreen_frames.saturating_sub(1);
+    }
+
+    /// 設定旋鈕位置（Arkanoid 實際使用的範圍約為 $62-$F2）
+    pub fn set_paddle_value(&mut self, value: u8) {
+        self.paddle_value = value;
+    }
+
+    /// 設定旋鈕按鈕
+    pub fn set_paddle_button(&mut self, pressed: bool) {
+        self.paddle_button = pressed;
+    }
+
+    /// Zapper 是否感應到光
+    ///
+    /// 依電子束掃描順序累計視野內已畫出的亮像素，達到 ZAPPER_MIN_LIT 的
+    /// 那一列開始輸出脈衝，持續 ZAPPER_LIGHT_LINES 條掃描線。尚未掃到的
+    /// 像素（上一幀的內容，或背景區段一次先寫好的同一圖磚剩餘像素）不列入
+    /// 計算；模擬器在接著 Zapper 時不跳過像素輸出，幀緩衝區一定是這一幀的畫面。
+    fn zapper_senses_light(&self, ppu: &Ppu) -> bool {
+        let (x, y) = (self.zapper_x, self.zapper_y);
+        if self.zapper_offscreen_frames > 0 || !(0..256).contains(&x) || !(0..240).contains(&y) {
+            return false;
+        }
+        let scanline = ppu.scanline as i32;
+        let dot = ppu.cycle as i32;
+        let mut lit = 0;
+        for row in (y - ZAPPER_RADIUS).max(0)..=(y + ZAPPER_RADIUS).min(239) {
+            if row > scanline {
+                break;
+            }
+            for col in (x - ZAPPER_RADIUS).max(0)..=(x + ZAPPER_RADIUS).min(255) {
+                // 第 col 個像素在第 col + 1 點輸出
+                if row == scanline && col >= dot {
+                    break;
+                }
+                let i = ((row * 256 + col) * 4) as usize;
+                let [r, g, b] = [0, 1, 2].map(|c| ppu.frame_buffer[i + c] as u32);
+                if (r * 299 + g * 587 + b * 114) / 1000 >= ZAPPER_BRIGHTNESS {
+                    lit += 1;
+                    if lit == ZAPPER_MIN_LIT {
+                        return scanline - row <= ZAPPER_LIGHT_LINES;
+                    }
+                }
+            }
+        }
+        false
+    }
+
     /// 重置控制器
     pub fn reset(&mut self) {
         self.button_state = 0;
         self.shift_register = 0;
         self.strobe = false;
+        self.zapper_trigger_frames = 0;
+        self.zapper_offscreen_frames = 0;
     }
 }
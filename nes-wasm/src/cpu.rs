@@ -1,18 +1,26 @@
 // ============================================================
-// NES CPU 模擬 - MOS 6502 處理器
+// NES CPU 模擬 - MOS 6502 處理器（2A03 核心）
 // ============================================================
-// 完整實作 NMOS 6502 CPU，包含：
-// - 所有 56 條合法指令
-// - 13 種定址模式
-// - 中斷處理（NMI、IRQ、BRK）
-// - 精確的週期計數（含跨頁額外週期）
+// 模擬器唯一的 6502 實作，包含：
+// - 所有合法指令與非官方指令（LAX、SAX、DCP、ISB、SLO、RLA、SRE、RRA、
+//   ANC、ALR、ARR、AXS、SHY、SHX、SHA、TAS、LAS、XAA 等）
+// - 13 種定址模式，含索引跨頁的 dummy read 與 RMW 的雙重寫入
+// - 中斷處理（NMI、IRQ、BRK）與倒數第二個週期的中斷取樣
+// - 週期計數（含跨頁與分支的額外週期）
+//
+// CPU 只透過 CpuBus trait 存取記憶體：模擬器本體把整台主機的位址空間
+// 實作成 CpuBus，單元測試與工具則可以接上一塊簡單的 RAM。指令在第一個
+// 週期一次執行完畢，之後以 tick 倒數剩餘週期。
 //
 // 參考資料：
 // - http://www.obelisk.me.uk/6502/reference.html
 // - https://www.nesdev.org/wiki/CPU
+// - https://www.nesdev.org/wiki/CPU_unofficial_opcodes
 // - https://www.masswerk.at/6502/6502_instruction_set.html
 // ============================================================
 
+use crate::savestate::impl_state_fields;
+
 /// CPU 狀態旗標位元定義
 /// 狀態暫存器（P）的各個位元：
 /// 7  bit  0
@@ -57,7 +65,39 @@ pub enum AddressingMode {
     IndirectY,      // (間接),Y：先取間接位址再加 Y（如 LDA ($10),Y）
 }
 
+/// CPU 看到的記憶體匯流排
+///
+/// 每次呼叫都是一次實際的匯流排存取（含 dummy read 與 RMW 的第一次寫入），
+/// 有副作用的暫存器（$2007、$4015、Mapper）依呼叫次數反應。
+pub trait CpuBus {
+    fn read(&mut self, addr: u16) -> u8;
+    fn write(&mut self, addr: u16, data: u8);
+}
+
+/// XAA（$8B）與 LAX #imm（$AB）預設的不穩定常數
+pub const DEFAULT_UNSTABLE_MAGIC: u8 = 0xEE;
+
+/// 中斷輪詢狀態
+///
+/// 6502 在每條指令的倒數第二個週期取樣 NMI 邊緣與 IRQ 電位，取樣結果
+/// 決定下一條指令前是否進入中斷；之後才出現的中斷要再等一條指令。
+/// 參考：https://www.nesdev.org/wiki/CPU_interrupts
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct InterruptPoll {
+    /// 取樣到 NMI
+    pub(crate) nmi: bool,
+    /// 取樣到 IRQ（已套用 I 旗標）
+    pub(crate) irq: bool,
+    /// 指令剩餘週期數等於這個值時取樣（0 = 這條指令不取樣）
+    pub(crate) at: u8,
+    /// CLI/SEI/PLP 在最後一個週期才改變 I 旗標，取樣時改看執行前的值
+    pub(crate) i_flag: Option<bool>,
+}
+
+impl_state_fields!(InterruptPoll { nmi, irq, at, i_flag });
+
 /// 6502 CPU 結構體
+#[derive(Debug, Clone)]
 pub struct Cpu {
     // ===== 暫存器 =====
     /// 累加器（Accumulator）
@@ -76,18 +116,34 @@ pub struct Cpu {
     // ===== 週期管理 =====
     /// 目前指令剩餘週期數
     pub cycles: u8,
-    /// 總週期計數
+    /// 總週期計數（DMA 暫停 CPU 的週期不計入）
     pub total_cycles: u64,
 
-    // ===== 中斷旗標 =====
-    /// NMI 中斷待處理
+    // ===== 中斷 =====
+    /// NMI 中斷待處理（PPU 的 NMI 邊緣）
     pub nmi_pending: bool,
-    /// IRQ 中斷待處理
+    /// IRQ 線（APU 與 Mapper 的 IRQ 電位）
     pub irq_pending: bool,
+    /// 中斷輪詢（取樣時間點與結果）
+    pub(crate) poll: InterruptPoll,
+
+    /// XAA / LAX #imm 與 A 做 OR 的常數（由 EmulatorConfig::unstable_magic 設定）
+    pub unstable_magic: u8,
+}
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Cpu {
     /// 建立新的 CPU 實例（初始化所有暫存器）
+    ///
+    /// ```
+    /// let cpu = nes_wasm::cpu::Cpu::new();
+    /// assert_eq!(cpu.sp, 0xFD);
+    /// ```
     pub fn new() -> Self {
         Cpu {
             a: 0,
@@ -100,994 +156,795 @@ impl Cpu {
             total_cycles: 0,
             nmi_pending: false,
             irq_pending: false,
+            poll: InterruptPoll::default(),
+            unstable_magic: DEFAULT_UNSTABLE_MAGIC,
         }
     }
 
-    /// 重置 CPU（模擬按下 RESET 按鈕）
-    /// 從 $FFFC-$FFFD 讀取重置向量作為新的 PC
-    pub fn reset(&mut self, read_fn: &dyn Fn(u16) -> u8) {
-        let lo = read_fn(0xFFFC) as u16;
-        let hi = read_fn(0xFFFD) as u16;
-        self.pc = (hi << 8) | lo;
-
+    /// 開機重置：清除暫存器，從 $FFFC-$FFFD 讀取重置向量作為新的 PC
+    pub fn reset<B: CpuBus>(&mut self, bus: &mut B) {
+        self.pc = self.read_vector(bus, 0xFFFC);
+        self.sp = 0xFD;
+        self.status = flags::UNUSED | flags::IRQ_DISABLE;
         self.a = 0;
         self.x = 0;
         self.y = 0;
-        self.sp = 0xFD;
-        self.status = flags::UNUSED | flags::IRQ_DISABLE;
-        self.cycles = 8; // 重置需要 8 個週期
-        self.total_cycles = 0; // 重置後清零
+        self.cycles = 0;
+        self.total_cycles = 0;
+        self.nmi_pending = false;
+        self.irq_pending = false;
+        self.poll = InterruptPoll::default();
     }
 
-    // ===== 旗標操作輔助方法 =====
+    /// 按下 Reset 鍵：A/X/Y 保留，SP 減 3，設定 I 旗標，從重置向量重新開始
+    pub fn soft_reset<B: CpuBus>(&mut self, bus: &mut B) {
+        self.pc = self.read_vector(bus, 0xFFFC);
+        self.sp = self.sp.wrapping_sub(3);
+        self.set_flag(flags::IRQ_DISABLE, true);
+        self.cycles = 0;
+        self.nmi_pending = false;
+        self.irq_pending = false;
+        self.poll = InterruptPoll::default();
+    }
 
-    /// 取得指定旗標的值
-    #[inline]
-    fn get_flag(&self, flag: u8) -> bool {
-        (self.status & flag) != 0
+    fn read_vector<B: CpuBus>(&mut self, bus: &mut B, addr: u16) -> u16 {
+        let lo = bus.read(addr) as u16;
+        let hi = bus.read(addr + 1) as u16;
+        (hi << 8) | lo
     }
 
-    /// 設定指定旗標
-    #[inline]
-    fn set_flag(&mut self, flag: u8, value: bool) {
-        if value {
-            self.status |= flag;
+    /// 觸發 NMI（不可遮罩中斷）
+    /// 由 PPU 在 VBlank 開始時觸發
+    pub fn trigger_nmi(&mut self) {
+        self.nmi_pending = true;
+    }
+
+    /// 觸發 IRQ（可遮罩中斷）
+    /// 可由 APU 或 Mapper 觸發
+    pub fn trigger_irq(&mut self) {
+        self.irq_pending = true;
+    }
+
+    // ===== 主要執行方法 =====
+
+    /// 推進一個 CPU 週期，指令還在倒數時回傳 true；回傳 false 表示
+    /// 位於指令邊界，呼叫端接著呼叫 service_interrupts 或 step
+    pub fn tick(&mut self) -> bool {
+        self.total_cycles += 1;
+        if self.cycles == 0 {
+            return false;
+        }
+        self.cycles -= 1;
+        if self.cycles == self.poll.at && self.poll.at != 0 {
+            self.poll_interrupts();
+        }
+        true
+    }
+
+    /// 指令邊界：進入上一條指令取樣到的中斷（NMI 優先），有進入時回傳 true
+    pub fn service_interrupts<B: CpuBus>(&mut self, bus: &mut B) -> bool {
+        let poll = std::mem::take(&mut self.poll);
+        if poll.nmi {
+            self.nmi_pending = false;
+            self.do_nmi(bus);
+            true
+        } else if poll.irq {
+            self.do_irq(bus);
+            true
         } else {
-            self.status &= !flag;
+            false
         }
     }
 
-    /// 根據結果值更新零旗標和負數旗標
-    #[inline]
-    fn update_zn(&mut self, value: u8) {
-        self.set_flag(flags::ZERO, value == 0);
-        self.set_flag(flags::NEGATIVE, value & 0x80 != 0);
+    /// 取樣 NMI 邊緣與 IRQ 電位，下一條指令開始前處理
+    /// （沒有執行指令的閒置週期，例如停在中斷點上，也由呼叫端取樣）
+    pub fn poll_interrupts(&mut self) {
+        let i_flag = self.poll.i_flag.unwrap_or(self.get_flag(flags::IRQ_DISABLE));
+        self.poll.nmi = self.nmi_pending;
+        self.poll.irq = self.irq_pending && !i_flag;
     }
 
-    // ===== 堆疊操作 =====
+    /// 取指令並執行；一般指令在倒數第二個週期取樣中斷
+    pub fn step<B: CpuBus>(&mut self, bus: &mut B) {
+        self.poll.at = 1;
+        let i_flag = self.get_flag(flags::IRQ_DISABLE);
+        let opcode = bus.read(self.pc);
+        self.pc = self.pc.wrapping_add(1);
+        self.execute(bus, opcode);
+        if matches!(opcode, 0x58 | 0x78 | 0x28) {
+            self.poll.i_flag = Some(i_flag);
+        }
+    }
 
-    /// 推入一個位元組到堆疊
-    #[inline]
-    fn push(&mut self, value: u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        write_fn(0x0100 | self.sp as u16, value);
-        self.sp = self.sp.wrapping_sub(1);
+    /// 以 JSR 的方式呼叫常式（NSF 的 INIT/PLAY），RTS 會回到 return_addr
+    pub fn call<B: CpuBus>(&mut self, bus: &mut B, addr: u16, return_addr: u16) {
+        self.push16(bus, return_addr.wrapping_sub(1));
+        self.pc = addr;
+        self.cycles = 0;
     }
 
-    /// 推入一個 16 位元值到堆疊（高位元組先推）
-    #[inline]
-    fn push16(&mut self, value: u16, write_fn: &mut dyn FnMut(u16, u8)) {
-        self.push((value >> 8) as u8, write_fn);
-        self.push(value as u8, write_fn);
+    // ============================================================
+    // 堆疊、旗標與中斷序列
+    // ============================================================
+
+    /// 推入堆疊
+    fn push<B: CpuBus>(&mut self, bus: &mut B, data: u8) {
+        bus.write(0x0100 | self.sp as u16, data);
+        self.sp = self.sp.wrapping_sub(1);
     }
 
-    /// 從堆疊彈出一個位元組
-    #[inline]
-    fn pull(&mut self, read_fn: &dyn Fn(u16) -> u8) -> u8 {
+    /// 從堆疊彈出
+    fn pop<B: CpuBus>(&mut self, bus: &mut B) -> u8 {
         self.sp = self.sp.wrapping_add(1);
-        read_fn(0x0100 | self.sp as u16)
+        bus.read(0x0100 | self.sp as u16)
     }
 
-    /// 從堆疊彈出一個 16 位元值
-    #[inline]
-    fn pull16(&mut self, read_fn: &dyn Fn(u16) -> u8) -> u16 {
-        let lo = self.pull(read_fn) as u16;
-        let hi = self.pull(read_fn) as u16;
+    /// 推入 16 位元值
+    fn push16<B: CpuBus>(&mut self, bus: &mut B, data: u16) {
+        self.push(bus, (data >> 8) as u8);
+        self.push(bus, data as u8);
+    }
+
+    /// 彈出 16 位元值
+    fn pop16<B: CpuBus>(&mut self, bus: &mut B) -> u16 {
+        let lo = self.pop(bus) as u16;
+        let hi = self.pop(bus) as u16;
         (hi << 8) | lo
     }
 
-    // ===== 中斷處理 =====
+    /// 取得指定旗標的值
+    #[inline]
+    fn get_flag(&self, flag: u8) -> bool {
+        (self.status & flag) != 0
+    }
 
-    /// 觸發 NMI（不可遮罩中斷）
-    /// 由 PPU 在 VBlank 開始時觸發
-    pub fn trigger_nmi(&mut self) {
-        self.nmi_pending = true;
+    /// 設定指定旗標
+    #[inline]
+    fn set_flag(&mut self, flag: u8, value: bool) {
+        if value {
+            self.status |= flag;
+        } else {
+            self.status &= !flag;
+        }
     }
 
-    /// 觸發 IRQ（可遮罩中斷）
-    /// 可由 APU 或 Mapper 觸發
-    pub fn trigger_irq(&mut self) {
-        self.irq_pending = true;
+    /// 根據結果值更新零旗標和負數旗標
+    #[inline]
+    fn update_zn(&mut self, value: u8) {
+        self.set_flag(flags::ZERO, value == 0);
+        self.set_flag(flags::NEGATIVE, value & 0x80 != 0);
     }
 
-    /// 處理 NMI 中斷
-    fn handle_nmi(&mut self, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        // 推入 PC 和狀態暫存器
-        self.push16(self.pc, write_fn);
-        // 推入狀態時清除 B 旗標，設定未使用位元
-        self.push((self.status & !flags::BREAK) | flags::UNUSED, write_fn);
-        // 設定中斷禁止旗標
+    /// NMI（中斷序列不取樣，處理常式的第一條指令一定會執行）
+    fn do_nmi<B: CpuBus>(&mut self, bus: &mut B) {
+        self.push16(bus, self.pc);
+        self.push(bus, (self.status & !flags::BREAK) | flags::UNUSED);
         self.set_flag(flags::IRQ_DISABLE, true);
-        // 從 NMI 向量讀取新的 PC
-        let lo = read_fn(0xFFFA) as u16;
-        let hi = read_fn(0xFFFB) as u16;
+        let lo = bus.read(0xFFFA) as u16;
+        let hi = bus.read(0xFFFB) as u16;
         self.pc = (hi << 8) | lo;
         self.cycles = 7;
+        self.poll.at = 0;
     }
 
-    /// 處理 IRQ 中斷
-    fn handle_irq(&mut self, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        // 推入 PC 和狀態暫存器
-        self.push16(self.pc, write_fn);
-        self.push((self.status & !flags::BREAK) | flags::UNUSED, write_fn);
+    /// IRQ
+    fn do_irq<B: CpuBus>(&mut self, bus: &mut B) {
+        self.push16(bus, self.pc);
+        self.push(bus, (self.status & !flags::BREAK) | flags::UNUSED);
         self.set_flag(flags::IRQ_DISABLE, true);
-        // 從 IRQ/BRK 向量讀取新的 PC
-        let lo = read_fn(0xFFFE) as u16;
-        let hi = read_fn(0xFFFF) as u16;
+        let lo = bus.read(0xFFFE) as u16;
+        let hi = bus.read(0xFFFF) as u16;
         self.pc = (hi << 8) | lo;
         self.cycles = 7;
+        self.poll.at = 0;
     }
 
-    // ===== 定址模式解析 =====
-    // 每個定址模式回傳 (有效位址, 是否跨頁)
-
-    /// 解析運算元的有效位址
-    /// 回傳 (位址, 額外週期)
-    fn resolve_address(
-        &mut self,
-        mode: AddressingMode,
-        read_fn: &dyn Fn(u16) -> u8,
-    ) -> (u16, bool) {
-        match mode {
-            AddressingMode::Implicit | AddressingMode::Accumulator => (0, false),
-
-            AddressingMode::Immediate => {
-                let addr = self.pc;
-                self.pc = self.pc.wrapping_add(1);
-                (addr, false)
-            }
-
-            AddressingMode::ZeroPage => {
-                let addr = read_fn(self.pc) as u16;
-                self.pc = self.pc.wrapping_add(1);
-                (addr, false)
-            }
-
-            AddressingMode::ZeroPageX => {
-                let base = read_fn(self.pc);
-                self.pc = self.pc.wrapping_add(1);
-                (base.wrapping_add(self.x) as u16, false)
-            }
-
-            AddressingMode::ZeroPageY => {
-                let base = read_fn(self.pc);
-                self.pc = self.pc.wrapping_add(1);
-                (base.wrapping_add(self.y) as u16, false)
-            }
-
-            AddressingMode::Relative => {
-                let offset = read_fn(self.pc) as i8;
-                self.pc = self.pc.wrapping_add(1);
-                let target = self.pc.wrapping_add(offset as u16);
-                let page_cross = (self.pc & 0xFF00) != (target & 0xFF00);
-                (target, page_cross)
-            }
-
-            AddressingMode::Absolute => {
-                let lo = read_fn(self.pc) as u16;
-                let hi = read_fn(self.pc.wrapping_add(1)) as u16;
-                self.pc = self.pc.wrapping_add(2);
-                ((hi << 8) | lo, false)
-            }
-
-            AddressingMode::AbsoluteX => {
-                let lo = read_fn(self.pc) as u16;
-                let hi = read_fn(self.pc.wrapping_add(1)) as u16;
-                self.pc = self.pc.wrapping_add(2);
-                let base = (hi << 8) | lo;
-                let addr = base.wrapping_add(self.x as u16);
-                let page_cross = (base & 0xFF00) != (addr & 0xFF00);
-                (addr, page_cross)
-            }
-
-            AddressingMode::AbsoluteY => {
-                let lo = read_fn(self.pc) as u16;
-                let hi = read_fn(self.pc.wrapping_add(1)) as u16;
-                self.pc = self.pc.wrapping_add(2);
-                let base = (hi << 8) | lo;
-                let addr = base.wrapping_add(self.y as u16);
-                let page_cross = (base & 0xFF00) != (addr & 0xFF00);
-                (addr, page_cross)
-            }
-
-            AddressingMode::Indirect => {
-                let ptr_lo = read_fn(self.pc) as u16;
-                let ptr_hi = read_fn(self.pc.wrapping_add(1)) as u16;
-                self.pc = self.pc.wrapping_add(2);
-                let ptr = (ptr_hi << 8) | ptr_lo;
-
-                // 6502 的間接尋址 Bug：如果指標位於頁面邊界，
-                // 高位元組會從同一頁的開頭讀取而非下一頁
-                let lo = read_fn(ptr) as u16;
-                let hi = if ptr_lo == 0xFF {
-                    read_fn(ptr & 0xFF00) as u16  // 頁面邊界 bug
-                } else {
-                    read_fn(ptr.wrapping_add(1)) as u16
-                };
-                ((hi << 8) | lo, false)
-            }
-
-            AddressingMode::IndirectX => {
-                let base = read_fn(self.pc);
-                self.pc = self.pc.wrapping_add(1);
-                let ptr = base.wrapping_add(self.x);
-                let lo = read_fn(ptr as u16) as u16;
-                let hi = read_fn(ptr.wrapping_add(1) as u16) as u16;
-                ((hi << 8) | lo, false)
-            }
-
-            AddressingMode::IndirectY => {
-                let ptr = read_fn(self.pc);
-                self.pc = self.pc.wrapping_add(1);
-                let lo = read_fn(ptr as u16) as u16;
-                let hi = read_fn(ptr.wrapping_add(1) as u16) as u16;
-                let base = (hi << 8) | lo;
-                let addr = base.wrapping_add(self.y as u16);
-                let page_cross = (base & 0xFF00) != (addr & 0xFF00);
-                (addr, page_cross)
-            }
-        }
+    /// 讀取 16 位元（帶頁面邊界 bug）
+    fn read16_bug<B: CpuBus>(&mut self, bus: &mut B, addr: u16) -> u16 {
+        let lo = bus.read(addr) as u16;
+        let hi_addr = (addr & 0xFF00) | ((addr.wrapping_add(1)) & 0x00FF);
+        let hi = bus.read(hi_addr) as u16;
+        (hi << 8) | lo
     }
 
-    // ===== 主要執行方法 =====
-
-    /// 執行一個 CPU 週期
-    /// 如果目前指令已完成（cycles == 0），則讀取並執行下一條指令
-    pub fn clock(
-        &mut self,
-        read_fn: &dyn Fn(u16) -> u8,
-        write_fn: &mut dyn FnMut(u16, u8),
-    ) {
-        if self.cycles == 0 {
-            // 檢查中斷
-            if self.nmi_pending {
-                self.nmi_pending = false;
-                self.handle_nmi(read_fn, write_fn);
-                return;
-            }
-
-            if self.irq_pending && !self.get_flag(flags::IRQ_DISABLE) {
-                self.irq_pending = false;
-                self.handle_irq(read_fn, write_fn);
-                return;
+    /// 分支指令
+    fn branch<B: CpuBus>(&mut self, bus: &mut B, condition: bool) {
+        let offset = bus.read(self.pc) as i8;
+        self.pc = self.pc.wrapping_add(1);
+        if condition {
+            let new_pc = self.pc.wrapping_add(offset as u16);
+            // 沒有跨頁的跳躍在第 2 週期就取樣中斷，第 3 週期才出現的中斷延後一條指令
+            if (self.pc & 0xFF00) != (new_pc & 0xFF00) {
+                self.cycles += 1;
+            } else {
+                self.poll.at = 2;
             }
-
-            // 讀取操作碼
-            let opcode = read_fn(self.pc);
-            self.pc = self.pc.wrapping_add(1);
-
-            // 確保未使用位元永遠為 1
-            self.set_flag(flags::UNUSED, true);
-
-            // 解碼並執行指令
-            self.execute(opcode, read_fn, write_fn);
-
-            // 確保未使用位元永遠為 1
-            self.set_flag(flags::UNUSED, true);
+            self.cycles += 1;
+            self.pc = new_pc;
         }
-
-        self.cycles = self.cycles.saturating_sub(1);
-        self.total_cycles += 1;
     }
 
-    /// 解碼並執行一條指令
-    /// 使用大型 match 表達式對應所有 256 個操作碼
-    fn execute(
-        &mut self,
-        opcode: u8,
-        read_fn: &dyn Fn(u16) -> u8,
-        write_fn: &mut dyn FnMut(u16, u8),
-    ) {
+    // ============================================================
+    // CPU 指令執行
+    // ============================================================
+    fn execute<B: CpuBus>(&mut self, bus: &mut B, opcode: u8) {
         match opcode {
-            // ===== 載入指令 =====
-
-            // LDA - 載入到累加器
-            0xA9 => { self.cycles = 2; self.lda(AddressingMode::Immediate, read_fn); }
-            0xA5 => { self.cycles = 3; self.lda(AddressingMode::ZeroPage, read_fn); }
-            0xB5 => { self.cycles = 4; self.lda(AddressingMode::ZeroPageX, read_fn); }
-            0xAD => { self.cycles = 4; self.lda(AddressingMode::Absolute, read_fn); }
-            0xBD => { self.cycles = 4; self.lda(AddressingMode::AbsoluteX, read_fn); }
-            0xB9 => { self.cycles = 4; self.lda(AddressingMode::AbsoluteY, read_fn); }
-            0xA1 => { self.cycles = 6; self.lda(AddressingMode::IndirectX, read_fn); }
-            0xB1 => { self.cycles = 5; self.lda(AddressingMode::IndirectY, read_fn); }
-
-            // LDX - 載入到 X 暫存器
-            0xA2 => { self.cycles = 2; self.ldx(AddressingMode::Immediate, read_fn); }
-            0xA6 => { self.cycles = 3; self.ldx(AddressingMode::ZeroPage, read_fn); }
-            0xB6 => { self.cycles = 4; self.ldx(AddressingMode::ZeroPageY, read_fn); }
-            0xAE => { self.cycles = 4; self.ldx(AddressingMode::Absolute, read_fn); }
-            0xBE => { self.cycles = 4; self.ldx(AddressingMode::AbsoluteY, read_fn); }
-
-            // LDY - 載入到 Y 暫存器
-            0xA0 => { self.cycles = 2; self.ldy(AddressingMode::Immediate, read_fn); }
-            0xA4 => { self.cycles = 3; self.ldy(AddressingMode::ZeroPage, read_fn); }
-            0xB4 => { self.cycles = 4; self.ldy(AddressingMode::ZeroPageX, read_fn); }
-            0xAC => { self.cycles = 4; self.ldy(AddressingMode::Absolute, read_fn); }
-            0xBC => { self.cycles = 4; self.ldy(AddressingMode::AbsoluteX, read_fn); }
-
-            // ===== 儲存指令 =====
-
-            // STA - 儲存累加器
-            0x85 => { self.cycles = 3; self.sta(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0x95 => { self.cycles = 4; self.sta(AddressingMode::ZeroPageX, read_fn, write_fn); }
-            0x8D => { self.cycles = 4; self.sta(AddressingMode::Absolute, read_fn, write_fn); }
-            0x9D => { self.cycles = 5; self.sta(AddressingMode::AbsoluteX, read_fn, write_fn); }
-            0x99 => { self.cycles = 5; self.sta(AddressingMode::AbsoluteY, read_fn, write_fn); }
-            0x81 => { self.cycles = 6; self.sta(AddressingMode::IndirectX, read_fn, write_fn); }
-            0x91 => { self.cycles = 6; self.sta(AddressingMode::IndirectY, read_fn, write_fn); }
-
-            // STX - 儲存 X 暫存器
-            0x86 => { self.cycles = 3; self.stx(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0x96 => { self.cycles = 4; self.stx(AddressingMode::ZeroPageY, read_fn, write_fn); }
-            0x8E => { self.cycles = 4; self.stx(AddressingMode::Absolute, read_fn, write_fn); }
-
-            // STY - 儲存 Y 暫存器
-            0x84 => { self.cycles = 3; self.sty(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0x94 => { self.cycles = 4; self.sty(AddressingMode::ZeroPageX, read_fn, write_fn); }
-            0x8C => { self.cycles = 4; self.sty(AddressingMode::Absolute, read_fn, write_fn); }
-
-            // ===== 暫存器轉移指令 =====
-            0xAA => { self.cycles = 2; self.x = self.a; self.update_zn(self.x); }  // TAX
-            0xA8 => { self.cycles = 2; self.y = self.a; self.update_zn(self.y); }  // TAY
-            0x8A => { self.cycles = 2; self.a = self.x; self.update_zn(self.a); }  // TXA
-            0x98 => { self.cycles = 2; self.a = self.y; self.update_zn(self.a); }  // TYA
-            0xBA => { self.cycles = 2; self.x = self.sp; self.update_zn(self.x); } // TSX
-            0x9A => { self.cycles = 2; self.sp = self.x; }                          // TXS
-
-            // ===== 算術指令 =====
-
-            // ADC - 帶進位加法
-            0x69 => { self.cycles = 2; self.adc(AddressingMode::Immediate, read_fn); }
-            0x65 => { self.cycles = 3; self.adc(AddressingMode::ZeroPage, read_fn); }
-            0x75 => { self.cycles = 4; self.adc(AddressingMode::ZeroPageX, read_fn); }
-            0x6D => { self.cycles = 4; self.adc(AddressingMode::Absolute, read_fn); }
-            0x7D => { self.cycles = 4; self.adc(AddressingMode::AbsoluteX, read_fn); }
-            0x79 => { self.cycles = 4; self.adc(AddressingMode::AbsoluteY, read_fn); }
-            0x61 => { self.cycles = 6; self.adc(AddressingMode::IndirectX, read_fn); }
-            0x71 => { self.cycles = 5; self.adc(AddressingMode::IndirectY, read_fn); }
-
-            // SBC - 帶借位減法
-            0xE9 => { self.cycles = 2; self.sbc(AddressingMode::Immediate, read_fn); }
-            0xE5 => { self.cycles = 3; self.sbc(AddressingMode::ZeroPage, read_fn); }
-            0xF5 => { self.cycles = 4; self.sbc(AddressingMode::ZeroPageX, read_fn); }
-            0xED => { self.cycles = 4; self.sbc(AddressingMode::Absolute, read_fn); }
-            0xFD => { self.cycles = 4; self.sbc(AddressingMode::AbsoluteX, read_fn); }
-            0xF9 => { self.cycles = 4; self.sbc(AddressingMode::AbsoluteY, read_fn); }
-            0xE1 => { self.cycles = 6; self.sbc(AddressingMode::IndirectX, read_fn); }
-            0xF1 => { self.cycles = 5; self.sbc(AddressingMode::IndirectY, read_fn); }
-
-            // CMP - 比較累加器
-            0xC9 => { self.cycles = 2; self.cmp(AddressingMode::Immediate, read_fn); }
-            0xC5 => { self.cycles = 3; self.cmp(AddressingMode::ZeroPage, read_fn); }
-            0xD5 => { self.cycles = 4; self.cmp(AddressingMode::ZeroPageX, read_fn); }
-            0xCD => { self.cycles = 4; self.cmp(AddressingMode::Absolute, read_fn); }
-            0xDD => { self.cycles = 4; self.cmp(AddressingMode::AbsoluteX, read_fn); }
-            0xD9 => { self.cycles = 4; self.cmp(AddressingMode::AbsoluteY, read_fn); }
-            0xC1 => { self.cycles = 6; self.cmp(AddressingMode::IndirectX, read_fn); }
-            0xD1 => { self.cycles = 5; self.cmp(AddressingMode::IndirectY, read_fn); }
-
-            // CPX - 比較 X 暫存器
-            0xE0 => { self.cycles = 2; self.cpx(AddressingMode::Immediate, read_fn); }
-            0xE4 => { self.cycles = 3; self.cpx(AddressingMode::ZeroPage, read_fn); }
-            0xEC => { self.cycles = 4; self.cpx(AddressingMode::Absolute, read_fn); }
-
-            // CPY - 比較 Y 暫存器
-            0xC0 => { self.cycles = 2; self.cpy(AddressingMode::Immediate, read_fn); }
-            0xC4 => { self.cycles = 3; self.cpy(AddressingMode::ZeroPage, read_fn); }
-            0xCC => { self.cycles = 4; self.cpy(AddressingMode::Absolute, read_fn); }
-
-            // ===== 遞增/遞減指令 =====
-
-            // INC - 記憶體遞增
-            0xE6 => { self.cycles = 5; self.inc(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0xF6 => { self.cycles = 6; self.inc(AddressingMode::ZeroPageX, read_fn, write_fn); }
-            0xEE => { self.cycles = 6; self.inc(AddressingMode::Absolute, read_fn, write_fn); }
-            0xFE => { self.cycles = 7; self.inc(AddressingMode::AbsoluteX, read_fn, write_fn); }
-
-            // DEC - 記憶體遞減
-            0xC6 => { self.cycles = 5; self.dec(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0xD6 => { self.cycles = 6; self.dec(AddressingMode::ZeroPageX, read_fn, write_fn); }
-            0xCE => { self.cycles = 6; self.dec(AddressingMode::Absolute, read_fn, write_fn); }
-            0xDE => { self.cycles = 7; self.dec(AddressingMode::AbsoluteX, read_fn, write_fn); }
-
-            // INX/INY/DEX/DEY - 暫存器遞增/遞減
-            0xE8 => { self.cycles = 2; self.x = self.x.wrapping_add(1); self.update_zn(self.x); } // INX
-            0xC8 => { self.cycles = 2; self.y = self.y.wrapping_add(1); self.update_zn(self.y); } // INY
-            0xCA => { self.cycles = 2; self.x = self.x.wrapping_sub(1); self.update_zn(self.x); } // DEX
-            0x88 => { self.cycles = 2; self.y = self.y.wrapping_sub(1); self.update_zn(self.y); } // DEY
-
-            // ===== 邏輯運算指令 =====
-
-            // AND - 邏輯與
-            0x29 => { self.cycles = 2; self.and(AddressingMode::Immediate, read_fn); }
-            0x25 => { self.cycles = 3; self.and(AddressingMode::ZeroPage, read_fn); }
-            0x35 => { self.cycles = 4; self.and(AddressingMode::ZeroPageX, read_fn); }
-            0x2D => { self.cycles = 4; self.and(AddressingMode::Absolute, read_fn); }
-            0x3D => { self.cycles = 4; self.and(AddressingMode::AbsoluteX, read_fn); }
-            0x39 => { self.cycles = 4; self.and(AddressingMode::AbsoluteY, read_fn); }
-            0x21 => { self.cycles = 6; self.and(AddressingMode::IndirectX, read_fn); }
-            0x31 => { self.cycles = 5; self.and(AddressingMode::IndirectY, read_fn); }
-
-            // ORA - 邏輯或
-            0x09 => { self.cycles = 2; self.ora(AddressingMode::Immediate, read_fn); }
-            0x05 => { self.cycles = 3; self.ora(AddressingMode::ZeroPage, read_fn); }
-            0x15 => { self.cycles = 4; self.ora(AddressingMode::ZeroPageX, read_fn); }
-            0x0D => { self.cycles = 4; self.ora(AddressingMode::Absolute, read_fn); }
-            0x1D => { self.cycles = 4; self.ora(AddressingMode::AbsoluteX, read_fn); }
-            0x19 => { self.cycles = 4; self.ora(AddressingMode::AbsoluteY, read_fn); }
-            0x01 => { self.cycles = 6; self.ora(AddressingMode::IndirectX, read_fn); }
-            0x11 => { self.cycles = 5; self.ora(AddressingMode::IndirectY, read_fn); }
-
-            // EOR - 邏輯互斥或
-            0x49 => { self.cycles = 2; self.eor(AddressingMode::Immediate, read_fn); }
-            0x45 => { self.cycles = 3; self.eor(AddressingMode::ZeroPage, read_fn); }
-            0x55 => { self.cycles = 4; self.eor(AddressingMode::ZeroPageX, read_fn); }
-            0x4D => { self.cycles = 4; self.eor(AddressingMode::Absolute, read_fn); }
-            0x5D => { self.cycles = 4; self.eor(AddressingMode::AbsoluteX, read_fn); }
-            0x59 => { self.cycles = 4; self.eor(AddressingMode::AbsoluteY, read_fn); }
-            0x41 => { self.cycles = 6; self.eor(AddressingMode::IndirectX, read_fn); }
-            0x51 => { self.cycles = 5; self.eor(AddressingMode::IndirectY, read_fn); }
-
-            // BIT - 位元測試
-            0x24 => { self.cycles = 3; self.bit(AddressingMode::ZeroPage, read_fn); }
-            0x2C => { self.cycles = 4; self.bit(AddressingMode::Absolute, read_fn); }
-
-            // ===== 位移指令 =====
-
-            // ASL - 算術左移
-            0x0A => { self.cycles = 2; self.asl_acc(); }
-            0x06 => { self.cycles = 5; self.asl_mem(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0x16 => { self.cycles = 6; self.asl_mem(AddressingMode::ZeroPageX, read_fn, write_fn); }
-            0x0E => { self.cycles = 6; self.asl_mem(AddressingMode::Absolute, read_fn, write_fn); }
-            0x1E => { self.cycles = 7; self.asl_mem(AddressingMode::AbsoluteX, read_fn, write_fn); }
-
-            // LSR - 邏輯右移
-            0x4A => { self.cycles = 2; self.lsr_acc(); }
-            0x46 => { self.cycles = 5; self.lsr_mem(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0x56 => { self.cycles = 6; self.lsr_mem(AddressingMode::ZeroPageX, read_fn, write_fn); }
-            0x4E => { self.cycles = 6; self.lsr_mem(AddressingMode::Absolute, read_fn, write_fn); }
-            0x5E => { self.cycles = 7; self.lsr_mem(AddressingMode::AbsoluteX, read_fn, write_fn); }
-
-            // ROL - 帶進位左旋轉
-            0x2A => { self.cycles = 2; self.rol_acc(); }
-            0x26 => { self.cycles = 5; self.rol_mem(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0x36 => { self.cycles = 6; self.rol_mem(AddressingMode::ZeroPageX, read_fn, write_fn); }
-            0x2E => { self.cycles = 6; self.rol_mem(AddressingMode::Absolute, read_fn, write_fn); }
-            0x3E => { self.cycles = 7; self.rol_mem(AddressingMode::AbsoluteX, read_fn, write_fn); }
-
-            // ROR - 帶進位右旋轉
-            0x6A => { self.cycles = 2; self.ror_acc(); }
-            0x66 => { self.cycles = 5; self.ror_mem(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0x76 => { self.cycles = 6; self.ror_mem(AddressingMode::ZeroPageX, read_fn, write_fn); }
-            0x6E => { self.cycles = 6; self.ror_mem(AddressingMode::Absolute, read_fn, write_fn); }
-            0x7E => { self.cycles = 7; self.ror_mem(AddressingMode::AbsoluteX, read_fn, write_fn); }
-
-            // ===== 分支指令 =====
-            0x90 => { self.cycles = 2; self.branch(!self.get_flag(flags::CARRY), read_fn); }    // BCC
-            0xB0 => { self.cycles = 2; self.branch(self.get_flag(flags::CARRY), read_fn); }     // BCS
-            0xF0 => { self.cycles = 2; self.branch(self.get_flag(flags::ZERO), read_fn); }      // BEQ
-            0xD0 => { self.cycles = 2; self.branch(!self.get_flag(flags::ZERO), read_fn); }     // BNE
-            0x30 => { self.cycles = 2; self.branch(self.get_flag(flags::NEGATIVE), read_fn); }  // BMI
-            0x10 => { self.cycles = 2; self.branch(!self.get_flag(flags::NEGATIVE), read_fn); } // BPL
-            0x70 => { self.cycles = 2; self.branch(self.get_flag(flags::OVERFLOW), read_fn); }  // BVS
-            0x50 => { self.cycles = 2; self.branch(!self.get_flag(flags::OVERFLOW), read_fn); } // BVC
-
-            // ===== 跳躍指令 =====
-
-            // JMP - 無條件跳躍
-            0x4C => {
-                self.cycles = 3;
-                let (addr, _) = self.resolve_address(AddressingMode::Absolute, read_fn);
-                self.pc = addr;
-            }
-            0x6C => {
-                self.cycles = 5;
-                let (addr, _) = self.resolve_address(AddressingMode::Indirect, read_fn);
-                self.pc = addr;
-            }
-
-            // JSR - 跳躍到子程式
-            0x20 => {
-                self.cycles = 6;
-                let (addr, _) = self.resolve_address(AddressingMode::Absolute, read_fn);
-                // 推入返回位址 - 1（RTS 會加 1）
-                self.push16(self.pc.wrapping_sub(1), write_fn);
-                self.pc = addr;
-            }
-
-            // RTS - 從子程式返回
-            0x60 => {
-                self.cycles = 6;
-                self.pc = self.pull16(read_fn).wrapping_add(1);
-            }
-
-            // RTI - 從中斷返回
-            0x40 => {
-                self.cycles = 6;
-                self.status = self.pull(read_fn);
-                self.set_flag(flags::BREAK, false);
-                self.set_flag(flags::UNUSED, true);
-                self.pc = self.pull16(read_fn);
-            }
-
-            // ===== 堆疊指令 =====
-            0x48 => { self.cycles = 3; let a = self.a; self.push(a, write_fn); }  // PHA
-            0x68 => {                                                              // PLA
-                self.cycles = 4;
-                self.a = self.pull(read_fn);
-                self.update_zn(self.a);
-            }
-            0x08 => {                                                              // PHP
-                self.cycles = 3;
-                let s = self.status | flags::BREAK | flags::UNUSED;
-                self.push(s, write_fn);
-            }
-            0x28 => {                                                              // PLP
-                self.cycles = 4;
-                self.status = self.pull(read_fn);
-                self.set_flag(flags::BREAK, false);
-                self.set_flag(flags::UNUSED, true);
-            }
-
-            // ===== 旗標指令 =====
-            0x18 => { self.cycles = 2; self.set_flag(flags::CARRY, false); }       // CLC
-            0x38 => { self.cycles = 2; self.set_flag(flags::CARRY, true); }        // SEC
-            0x58 => { self.cycles = 2; self.set_flag(flags::IRQ_DISABLE, false); } // CLI
-            0x78 => { self.cycles = 2; self.set_flag(flags::IRQ_DISABLE, true); }  // SEI
-            0xD8 => { self.cycles = 2; self.set_flag(flags::DECIMAL, false); }     // CLD
-            0xF8 => { self.cycles = 2; self.set_flag(flags::DECIMAL, true); }      // SED
-            0xB8 => { self.cycles = 2; self.set_flag(flags::OVERFLOW, false); }    // CLV
-
-            // ===== 系統指令 =====
-
-            // BRK - 觸發中斷
+            // ADC
+            0x69 => { let v = self.imm(bus); self.op_adc(v); self.cycles = 2; }
+            0x65 => { let v = self.zp_r(bus); self.op_adc(v); self.cycles = 3; }
+            0x75 => { let v = self.zpx_r(bus); self.op_adc(v); self.cycles = 4; }
+            0x6D => { let (v, _) = self.abs_r(bus); self.op_adc(v); self.cycles = 4; }
+            0x7D => { let (v, e) = self.abx_r(bus); self.op_adc(v); self.cycles = 4 + e; }
+            0x79 => { let (v, e) = self.aby_r(bus); self.op_adc(v); self.cycles = 4 + e; }
+            0x61 => { let v = self.izx_r(bus); self.op_adc(v); self.cycles = 6; }
+            0x71 => { let (v, e) = self.izy_r(bus); self.op_adc(v); self.cycles = 5 + e; }
+
+            // AND
+            0x29 => { let v = self.imm(bus); self.a &= v; self.update_zn(self.a); self.cycles = 2; }
+            0x25 => { let v = self.zp_r(bus); self.a &= v; self.update_zn(self.a); self.cycles = 3; }
+            0x35 => { let v = self.zpx_r(bus); self.a &= v; self.update_zn(self.a); self.cycles = 4; }
+            0x2D => { let (v, _) = self.abs_r(bus); self.a &= v; self.update_zn(self.a); self.cycles = 4; }
+            0x3D => { let (v, e) = self.abx_r(bus); self.a &= v; self.update_zn(self.a); self.cycles = 4 + e; }
+            0x39 => { let (v, e) = self.aby_r(bus); self.a &= v; self.update_zn(self.a); self.cycles = 4 + e; }
+            0x21 => { let v = self.izx_r(bus); self.a &= v; self.update_zn(self.a); self.cycles = 6; }
+            0x31 => { let (v, e) = self.izy_r(bus); self.a &= v; self.update_zn(self.a); self.cycles = 5 + e; }
+
+            // ASL
+            0x0A => { self.set_flag(flags::CARRY, self.a & 0x80 != 0); self.a <<= 1; self.update_zn(self.a); self.cycles = 2; }
+            0x06 => { let a = self.zp(bus); self.op_asl_m(bus, a); self.cycles = 5; }
+            0x16 => { let a = self.zpx(bus); self.op_asl_m(bus, a); self.cycles = 6; }
+            0x0E => { let a = self.abs(bus); self.op_asl_m(bus, a); self.cycles = 6; }
+            0x1E => { let a = self.abx_w(bus); self.op_asl_m(bus, a); self.cycles = 7; }
+
+            // 分支
+            0x90 => { self.cycles = 2; let c = !self.get_flag(flags::CARRY); self.branch(bus, c); }
+            0xB0 => { self.cycles = 2; let c = self.get_flag(flags::CARRY); self.branch(bus, c); }
+            0xF0 => { self.cycles = 2; let c = self.get_flag(flags::ZERO); self.branch(bus, c); }
+            0x30 => { self.cycles = 2; let c = self.get_flag(flags::NEGATIVE); self.branch(bus, c); }
+            0xD0 => { self.cycles = 2; let c = !self.get_flag(flags::ZERO); self.branch(bus, c); }
+            0x10 => { self.cycles = 2; let c = !self.get_flag(flags::NEGATIVE); self.branch(bus, c); }
+            0x50 => { self.cycles = 2; let c = !self.get_flag(flags::OVERFLOW); self.branch(bus, c); }
+            0x70 => { self.cycles = 2; let c = self.get_flag(flags::OVERFLOW); self.branch(bus, c); }
+
+            // BIT
+            0x24 => { let v = self.zp_r(bus); self.op_bit(v); self.cycles = 3; }
+            0x2C => { let (v, _) = self.abs_r(bus); self.op_bit(v); self.cycles = 4; }
+
+            // BRK
             0x00 => {
-                self.cycles = 7;
-                self.pc = self.pc.wrapping_add(1); // BRK 跳過下一個位元組
-                self.push16(self.pc, write_fn);
-                self.push(self.status | flags::BREAK | flags::UNUSED, write_fn);
+                self.pc = self.pc.wrapping_add(1);
+                self.push16(bus, self.pc);
+                self.push(bus, self.status | flags::BREAK | flags::UNUSED);
                 self.set_flag(flags::IRQ_DISABLE, true);
-                let lo = read_fn(0xFFFE) as u16;
-                let hi = read_fn(0xFFFF) as u16;
+                let lo = bus.read(0xFFFE) as u16;
+                let hi = bus.read(0xFFFF) as u16;
                 self.pc = (hi << 8) | lo;
+                self.cycles = 7;
             }
 
-            // NOP - 無操作
-            0xEA => { self.cycles = 2; }
-
-            // ===== 非官方操作碼（常見的需要處理以避免卡住） =====
-            // 這些非官方操作碼在某些遊戲中會被使用
-
-            // NOP 變體（不同的定址模式和週期數）
-            0x1A | 0x3A | 0x5A | 0x7A | 0xDA | 0xFA => { self.cycles = 2; } // NOP (隱式)
-            0x80 | 0x82 | 0x89 | 0xC2 | 0xE2 => { // NOP #imm (跳過一個位元組)
-                self.cycles = 2;
+            // 旗標
+            0x18 => { self.set_flag(flags::CARRY, false); self.cycles = 2; }
+            0xD8 => { self.set_flag(flags::DECIMAL, false); self.cycles = 2; }
+            0x58 => { self.set_flag(flags::IRQ_DISABLE, false); self.cycles = 2; }
+            0xB8 => { self.set_flag(flags::OVERFLOW, false); self.cycles = 2; }
+            0x38 => { self.set_flag(flags::CARRY, true); self.cycles = 2; }
+            0xF8 => { self.set_flag(flags::DECIMAL, true); self.cycles = 2; }
+            0x78 => { self.set_flag(flags::IRQ_DISABLE, true); self.cycles = 2; }
+
+            // CMP
+            0xC9 => { let v = self.imm(bus); let a = self.a; self.op_cmp(a, v); self.cycles = 2; }
+            0xC5 => { let v = self.zp_r(bus); let a = self.a; self.op_cmp(a, v); self.cycles = 3; }
+            0xD5 => { let v = self.zpx_r(bus); let a = self.a; self.op_cmp(a, v); self.cycles = 4; }
+            0xCD => { let (v, _) = self.abs_r(bus); let a = self.a; self.op_cmp(a, v); self.cycles = 4; }
+            0xDD => { let (v, e) = self.abx_r(bus); let a = self.a; self.op_cmp(a, v); self.cycles = 4 + e; }
+            0xD9 => { let (v, e) = self.aby_r(bus); let a = self.a; self.op_cmp(a, v); self.cycles = 4 + e; }
+            0xC1 => { let v = self.izx_r(bus); let a = self.a; self.op_cmp(a, v); self.cycles = 6; }
+            0xD1 => { let (v, e) = self.izy_r(bus); let a = self.a; self.op_cmp(a, v); self.cycles = 5 + e; }
+
+            // CPX
+            0xE0 => { let v = self.imm(bus); let x = self.x; self.op_cmp(x, v); self.cycles = 2; }
+            0xE4 => { let v = self.zp_r(bus); let x = self.x; self.op_cmp(x, v); self.cycles = 3; }
+            0xEC => { let (v, _) = self.abs_r(bus); let x = self.x; self.op_cmp(x, v); self.cycles = 4; }
+
+            // CPY
+            0xC0 => { let v = self.imm(bus); let y = self.y; self.op_cmp(y, v); self.cycles = 2; }
+            0xC4 => { let v = self.zp_r(bus); let y = self.y; self.op_cmp(y, v); self.cycles = 3; }
+            0xCC => { let (v, _) = self.abs_r(bus); let y = self.y; self.op_cmp(y, v); self.cycles = 4; }
+
+            // DEC
+            0xC6 => { let a = self.zp(bus); self.op_dec_m(bus, a); self.cycles = 5; }
+            0xD6 => { let a = self.zpx(bus); self.op_dec_m(bus, a); self.cycles = 6; }
+            0xCE => { let a = self.abs(bus); self.op_dec_m(bus, a); self.cycles = 6; }
+            0xDE => { let a = self.abx_w(bus); self.op_dec_m(bus, a); self.cycles = 7; }
+            0xCA => { self.x = self.x.wrapping_sub(1); self.update_zn(self.x); self.cycles = 2; }
+            0x88 => { self.y = self.y.wrapping_sub(1); self.update_zn(self.y); self.cycles = 2; }
+
+            // EOR
+            0x49 => { let v = self.imm(bus); self.a ^= v; self.update_zn(self.a); self.cycles = 2; }
+            0x45 => { let v = self.zp_r(bus); self.a ^= v; self.update_zn(self.a); self.cycles = 3; }
+            0x55 => { let v = self.zpx_r(bus); self.a ^= v; self.update_zn(self.a); self.cycles = 4; }
+            0x4D => { let (v, _) = self.abs_r(bus); self.a ^= v; self.update_zn(self.a); self.cycles = 4; }
+            0x5D => { let (v, e) = self.abx_r(bus); self.a ^= v; self.update_zn(self.a); self.cycles = 4 + e; }
+            0x59 => { let (v, e) = self.aby_r(bus); self.a ^= v; self.update_zn(self.a); self.cycles = 4 + e; }
+            0x41 => { let v = self.izx_r(bus); self.a ^= v; self.update_zn(self.a); self.cycles = 6; }
+            0x51 => { let (v, e) = self.izy_r(bus); self.a ^= v; self.update_zn(self.a); self.cycles = 5 + e; }
+
+            // INC
+            0xE6 => { let a = self.zp(bus); self.op_inc_m(bus, a); self.cycles = 5; }
+            0xF6 => { let a = self.zpx(bus); self.op_inc_m(bus, a); self.cycles = 6; }
+            0xEE => { let a = self.abs(bus); self.op_inc_m(bus, a); self.cycles = 6; }
+            0xFE => { let a = self.abx_w(bus); self.op_inc_m(bus, a); self.cycles = 7; }
+            0xE8 => { self.x = self.x.wrapping_add(1); self.update_zn(self.x); self.cycles = 2; }
+            0xC8 => { self.y = self.y.wrapping_add(1); self.update_zn(self.y); self.cycles = 2; }
+
+            // JMP
+            0x4C => { let addr = self.abs(bus); self.pc = addr; self.cycles = 3; }
+            0x6C => { let ptr = self.abs(bus); let addr = self.read16_bug(bus, ptr); self.pc = addr; self.cycles = 5; }
+
+            // JSR
+            0x20 => { let addr = self.abs(bus); let ret = self.pc.wrapping_sub(1); self.push16(bus, ret); self.pc = addr; self.cycles = 6; }
+
+            // LDA
+            0xA9 => { self.a = self.imm(bus); self.update_zn(self.a); self.cycles = 2; }
+            0xA5 => { self.a = self.zp_r(bus); self.update_zn(self.a); self.cycles = 3; }
+            0xB5 => { self.a = self.zpx_r(bus); self.update_zn(self.a); self.cycles = 4; }
+            0xAD => { let (v, _) = self.abs_r(bus); self.a = v; self.update_zn(self.a); self.cycles = 4; }
+            0xBD => { let (v, e) = self.abx_r(bus); self.a = v; self.update_zn(self.a); self.cycles = 4 + e; }
+            0xB9 => { let (v, e) = self.aby_r(bus); self.a = v; self.update_zn(self.a); self.cycles = 4 + e; }
+            0xA1 => { self.a = self.izx_r(bus); self.update_zn(self.a); self.cycles = 6; }
+            0xB1 => { let (v, e) = self.izy_r(bus); self.a = v; self.update_zn(self.a); self.cycles = 5 + e; }
+
+            // LDX
+            0xA2 => { self.x = self.imm(bus); self.update_zn(self.x); self.cycles = 2; }
+            0xA6 => { self.x = self.zp_r(bus); self.update_zn(self.x); self.cycles = 3; }
+            0xB6 => { // zp,Y
+                let base = bus.read(self.pc) as u16;
                 self.pc = self.pc.wrapping_add(1);
+                let addr = (base.wrapping_add(self.y as u16)) & 0xFF;
+                self.x = bus.read(addr); self.update_zn(self.x); self.cycles = 4;
             }
-            0x04 | 0x44 | 0x64 => { // NOP zp (跳過一個位元組)
-                self.cycles = 3;
-                self.pc = self.pc.wrapping_add(1);
-            }
-            0x14 | 0x34 | 0x54 | 0x74 | 0xD4 | 0xF4 => { // NOP zp,X (跳過一個位元組)
-                self.cycles = 4;
+            0xAE => { let (v, _) = self.abs_r(bus); self.x = v; self.update_zn(self.x); self.cycles = 4; }
+            0xBE => { let (v, e) = self.aby_r(bus); self.x = v; self.update_zn(self.x); self.cycles = 4 + e; }
+
+            // LDY
+            0xA0 => { self.y = self.imm(bus); self.update_zn(self.y); self.cycles = 2; }
+            0xA4 => { self.y = self.zp_r(bus); self.update_zn(self.y); self.cycles = 3; }
+            0xB4 => { self.y = self.zpx_r(bus); self.update_zn(self.y); self.cycles = 4; }
+            0xAC => { let (v, _) = self.abs_r(bus); self.y = v; self.update_zn(self.y); self.cycles = 4; }
+            0xBC => { let (v, e) = self.abx_r(bus); self.y = v; self.update_zn(self.y); self.cycles = 4 + e; }
+
+            // LSR
+            0x4A => { self.set_flag(flags::CARRY, self.a & 0x01 != 0); self.a >>= 1; self.update_zn(self.a); self.cycles = 2; }
+            0x46 => { let a = self.zp(bus); self.op_lsr_m(bus, a); self.cycles = 5; }
+            0x56 => { let a = self.zpx(bus); self.op_lsr_m(bus, a); self.cycles = 6; }
+            0x4E => { let a = self.abs(bus); self.op_lsr_m(bus, a); self.cycles = 6; }
+            0x5E => { let a = self.abx_w(bus); self.op_lsr_m(bus, a); self.cycles = 7; }
+
+            // NOP
+            0xEA => { self.cycles = 2; }
+
+            // ORA
+            0x09 => { let v = self.imm(bus); self.a |= v; self.update_zn(self.a); self.cycles = 2; }
+            0x05 => { let v = self.zp_r(bus); self.a |= v; self.update_zn(self.a); self.cycles = 3; }
+            0x15 => { let v = self.zpx_r(bus); self.a |= v; self.update_zn(self.a); self.cycles = 4; }
+            0x0D => { let (v, _) = self.abs_r(bus); self.a |= v; self.update_zn(self.a); self.cycles = 4; }
+            0x1D => { let (v, e) = self.abx_r(bus); self.a |= v; self.update_zn(self.a); self.cycles = 4 + e; }
+            0x19 => { let (v, e) = self.aby_r(bus); self.a |= v; self.update_zn(self.a); self.cycles = 4 + e; }
+            0x01 => { let v = self.izx_r(bus); self.a |= v; self.update_zn(self.a); self.cycles = 6; }
+            0x11 => { let (v, e) = self.izy_r(bus); self.a |= v; self.update_zn(self.a); self.cycles = 5 + e; }
+
+            // 堆疊
+            0x48 => { let a = self.a; self.push(bus, a); self.cycles = 3; }
+            0x08 => { let s = self.status | flags::BREAK | flags::UNUSED; self.push(bus, s); self.cycles = 3; }
+            0x68 => { self.a = self.pop(bus); self.update_zn(self.a); self.cycles = 4; }
+            0x28 => { let v = self.pop(bus); self.status = (v & !(flags::BREAK | flags::UNUSED)) | (self.status & flags::BREAK) | flags::UNUSED; self.cycles = 4; }
+
+            // ROL
+            0x2A => { let c = self.get_flag(flags::CARRY) as u8; self.set_flag(flags::CARRY, self.a & 0x80 != 0); self.a = (self.a << 1) | c; self.update_zn(self.a); self.cycles = 2; }
+            0x26 => { let a = self.zp(bus); self.op_rol_m(bus, a); self.cycles = 5; }
+            0x36 => { let a = self.zpx(bus); self.op_rol_m(bus, a); self.cycles = 6; }
+            0x2E => { let a = self.abs(bus); self.op_rol_m(bus, a); self.cycles = 6; }
+            0x3E => { let a = self.abx_w(bus); self.op_rol_m(bus, a); self.cycles = 7; }
+
+            // ROR
+            0x6A => { let c = if self.get_flag(flags::CARRY) { 0x80u8 } else { 0 }; self.set_flag(flags::CARRY, self.a & 0x01 != 0); self.a = (self.a >> 1) | c; self.update_zn(self.a); self.cycles = 2; }
+            0x66 => { let a = self.zp(bus); self.op_ror_m(bus, a); self.cycles = 5; }
+            0x76 => { let a = self.zpx(bus); self.op_ror_m(bus, a); self.cycles = 6; }
+            0x6E => { let a = self.abs(bus); self.op_ror_m(bus, a); self.cycles = 6; }
+            0x7E => { let a = self.abx_w(bus); self.op_ror_m(bus, a); self.cycles = 7; }
+
+            // RTI
+            0x40 => { let s = self.pop(bus); self.status = (s & !(flags::BREAK | flags::UNUSED)) | flags::UNUSED; self.pc = self.pop16(bus); self.cycles = 6; }
+
+            // RTS
+            0x60 => { self.pc = self.pop16(bus).wrapping_add(1); self.cycles = 6; }
+
+            // SBC
+            0xE9 | 0xEB => { let v = self.imm(bus); self.op_sbc(v); self.cycles = 2; }
+            0xE5 => { let v = self.zp_r(bus); self.op_sbc(v); self.cycles = 3; }
+            0xF5 => { let v = self.zpx_r(bus); self.op_sbc(v); self.cycles = 4; }
+            0xED => { let (v, _) = self.abs_r(bus); self.op_sbc(v); self.cycles = 4; }
+            0xFD => { let (v, e) = self.abx_r(bus); self.op_sbc(v); self.cycles = 4 + e; }
+            0xF9 => { let (v, e) = self.aby_r(bus); self.op_sbc(v); self.cycles = 4 + e; }
+            0xE1 => { let v = self.izx_r(bus); self.op_sbc(v); self.cycles = 6; }
+            0xF1 => { let (v, e) = self.izy_r(bus); self.op_sbc(v); self.cycles = 5 + e; }
+
+            // STA
+            0x85 => { let a = self.zp(bus); let v = self.a; bus.write(a, v); self.cycles = 3; }
+            0x95 => { let a = self.zpx(bus); let v = self.a; bus.write(a, v); self.cycles = 4; }
+            0x8D => { let a = self.abs(bus); let v = self.a; bus.write(a, v); self.cycles = 4; }
+            0x9D => { let a = self.abx_w(bus); let v = self.a; bus.write(a, v); self.cycles = 5; }
+            0x99 => { let a = self.aby_w(bus); let v = self.a; bus.write(a, v); self.cycles = 5; }
+            0x81 => { let a = self.izx(bus); let v = self.a; bus.write(a, v); self.cycles = 6; }
+            0x91 => { let a = self.izy_w(bus); let v = self.a; bus.write(a, v); self.cycles = 6; }
+
+            // STX
+            0x86 => { let a = self.zp(bus); let v = self.x; bus.write(a, v); self.cycles = 3; }
+            0x96 => { // zp,Y
+                let base = bus.read(self.pc).wrapping_add(self.y) as u16 & 0xFF;
                 self.pc = self.pc.wrapping_add(1);
+                let v = self.x; bus.write(base, v); self.cycles = 4;
             }
-            0x0C => { // NOP abs (跳過兩個位元組)
-                self.cycles = 4;
-                self.pc = self.pc.wrapping_add(2);
-            }
-            0x1C | 0x3C | 0x5C | 0x7C | 0xDC | 0xFC => { // NOP abs,X (跳過兩個位元組)
-                self.cycles = 4;
-                // 需要讀取以觸發跨頁週期
-                let lo = read_fn(self.pc) as u16;
-                let hi = read_fn(self.pc.wrapping_add(1)) as u16;
-                self.pc = self.pc.wrapping_add(2);
-                let base = (hi << 8) | lo;
-                let addr = base.wrapping_add(self.x as u16);
-                if (base & 0xFF00) != (addr & 0xFF00) {
-                    self.cycles += 1;
-                }
-            }
-
-            // LAX - LDA + LDX 合併（非官方）
-            0xA7 => { self.cycles = 3; self.lax(AddressingMode::ZeroPage, read_fn); }
-            0xB7 => { self.cycles = 4; self.lax(AddressingMode::ZeroPageY, read_fn); }
-            0xAF => { self.cycles = 4; self.lax(AddressingMode::Absolute, read_fn); }
-            0xBF => { self.cycles = 4; self.lax(AddressingMode::AbsoluteY, read_fn); }
-            0xA3 => { self.cycles = 6; self.lax(AddressingMode::IndirectX, read_fn); }
-            0xB3 => { self.cycles = 5; self.lax(AddressingMode::IndirectY, read_fn); }
-
-            // SAX - A & X -> M（非官方）
-            0x87 => { self.cycles = 3; self.sax(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0x97 => { self.cycles = 4; self.sax(AddressingMode::ZeroPageY, read_fn, write_fn); }
-            0x8F => { self.cycles = 4; self.sax(AddressingMode::Absolute, read_fn, write_fn); }
-            0x83 => { self.cycles = 6; self.sax(AddressingMode::IndirectX, read_fn, write_fn); }
-
-            // DCP - DEC + CMP（非官方）
-            0xC7 => { self.cycles = 5; self.dcp(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0xD7 => { self.cycles = 6; self.dcp(AddressingMode::ZeroPageX, read_fn, write_fn); }
-            0xCF => { self.cycles = 6; self.dcp(AddressingMode::Absolute, read_fn, write_fn); }
-            0xDF => { self.cycles = 7; self.dcp(AddressingMode::AbsoluteX, read_fn, write_fn); }
-            0xDB => { self.cycles = 7; self.dcp(AddressingMode::AbsoluteY, read_fn, write_fn); }
-            0xC3 => { self.cycles = 8; self.dcp(AddressingMode::IndirectX, read_fn, write_fn); }
-            0xD3 => { self.cycles = 8; self.dcp(AddressingMode::IndirectY, read_fn, write_fn); }
-
-            // ISB/ISC - INC + SBC（非官方）
-            0xE7 => { self.cycles = 5; self.isb(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0xF7 => { self.cycles = 6; self.isb(AddressingMode::ZeroPageX, read_fn, write_fn); }
-            0xEF => { self.cycles = 6; self.isb(AddressingMode::Absolute, read_fn, write_fn); }
-            0xFF => { self.cycles = 7; self.isb(AddressingMode::AbsoluteX, read_fn, write_fn); }
-            0xFB => { self.cycles = 7; self.isb(AddressingMode::AbsoluteY, read_fn, write_fn); }
-            0xE3 => { self.cycles = 8; self.isb(AddressingMode::IndirectX, read_fn, write_fn); }
-            0xF3 => { self.cycles = 8; self.isb(AddressingMode::IndirectY, read_fn, write_fn); }
-
-            // SLO - ASL + ORA（非官方）
-            0x07 => { self.cycles = 5; self.slo(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0x17 => { self.cycles = 6; self.slo(AddressingMode::ZeroPageX, read_fn, write_fn); }
-            0x0F => { self.cycles = 6; self.slo(AddressingMode::Absolute, read_fn, write_fn); }
-            0x1F => { self.cycles = 7; self.slo(AddressingMode::AbsoluteX, read_fn, write_fn); }
-            0x1B => { self.cycles = 7; self.slo(AddressingMode::AbsoluteY, read_fn, write_fn); }
-            0x03 => { self.cycles = 8; self.slo(AddressingMode::IndirectX, read_fn, write_fn); }
-            0x13 => { self.cycles = 8; self.slo(AddressingMode::IndirectY, read_fn, write_fn); }
-
-            // RLA - ROL + AND（非官方）
-            0x27 => { self.cycles = 5; self.rla(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0x37 => { self.cycles = 6; self.rla(AddressingMode::ZeroPageX, read_fn, write_fn); }
-            0x2F => { self.cycles = 6; self.rla(AddressingMode::Absolute, read_fn, write_fn); }
-            0x3F => { self.cycles = 7; self.rla(AddressingMode::AbsoluteX, read_fn, write_fn); }
-            0x3B => { self.cycles = 7; self.rla(AddressingMode::AbsoluteY, read_fn, write_fn); }
-            0x23 => { self.cycles = 8; self.rla(AddressingMode::IndirectX, read_fn, write_fn); }
-            0x33 => { self.cycles = 8; self.rla(AddressingMode::IndirectY, read_fn, write_fn); }
-
-            // SRE - LSR + EOR（非官方）
-            0x47 => { self.cycles = 5; self.sre(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0x57 => { self.cycles = 6; self.sre(AddressingMode::ZeroPageX, read_fn, write_fn); }
-            0x4F => { self.cycles = 6; self.sre(AddressingMode::Absolute, read_fn, write_fn); }
-            0x5F => { self.cycles = 7; self.sre(AddressingMode::AbsoluteX, read_fn, write_fn); }
-            0x5B => { self.cycles = 7; self.sre(AddressingMode::AbsoluteY, read_fn, write_fn); }
-            0x43 => { self.cycles = 8; self.sre(AddressingMode::IndirectX, read_fn, write_fn); }
-            0x53 => { self.cycles = 8; self.sre(AddressingMode::IndirectY, read_fn, write_fn); }
-
-            // RRA - ROR + ADC（非官方）
-            0x67 => { self.cycles = 5; self.rra(AddressingMode::ZeroPage, read_fn, write_fn); }
-            0x77 => { self.cycles = 6; self.rra(AddressingMode::ZeroPageX, read_fn, write_fn); }
-            0x6F => { self.cycles = 6; self.rra(AddressingMode::Absolute, read_fn, write_fn); }
-            0x7F => { self.cycles = 7; self.rra(AddressingMode::AbsoluteX, read_fn, write_fn); }
-            0x7B => { self.cycles = 7; self.rra(AddressingMode::AbsoluteY, read_fn, write_fn); }
-            0x63 => { self.cycles = 8; self.rra(AddressingMode::IndirectX, read_fn, write_fn); }
-            0x73 => { self.cycles = 8; self.rra(AddressingMode::IndirectY, read_fn, write_fn); }
-
-            // SBC 非官方別名
-            0xEB => { self.cycles = 2; self.sbc(AddressingMode::Immediate, read_fn); }
-
-            // 其他未知操作碼 - 當作 NOP 處理
-            _ => {
+            0x8E => { let a = self.abs(bus); let v = self.x; bus.write(a, v); self.cycles = 4; }
+
+            // STY
+            0x84 => { let a = self.zp(bus); let v = self.y; bus.write(a, v); self.cycles = 3; }
+            0x94 => { let a = self.zpx(bus); let v = self.y; bus.write(a, v); self.cycles = 4; }
+            0x8C => { let a = self.abs(bus); let v = self.y; bus.write(a, v); self.cycles = 4; }
+
+            // 暫存器傳輸
+            0xAA => { self.x = self.a; self.update_zn(self.x); self.cycles = 2; }
+            0xA8 => { self.y = self.a; self.update_zn(self.y); self.cycles = 2; }
+            0xBA => { self.x = self.sp; self.update_zn(self.x); self.cycles = 2; }
+            0x8A => { self.a = self.x; self.update_zn(self.a); self.cycles = 2; }
+            0x9A => { self.sp = self.x; self.cycles = 2; }
+            0x98 => { self.a = self.y; self.update_zn(self.a); self.cycles = 2; }
+
+            // === 非官方指令 ===
+            // LAX
+            0xA7 => { let v = self.zp_r(bus); self.a = v; self.x = v; self.update_zn(v); self.cycles = 3; }
+            0xB7 => { let base = bus.read(self.pc) as u16; self.pc = self.pc.wrapping_add(1); let addr = (base.wrapping_add(self.y as u16)) & 0xFF; let v = bus.read(addr); self.a = v; self.x = v; self.update_zn(v); self.cycles = 4; }
+            0xAF => { let (v, _) = self.abs_r(bus); self.a = v; self.x = v; self.update_zn(v); self.cycles = 4; }
+            0xBF => { let (v, e) = self.aby_r(bus); self.a = v; self.x = v; self.update_zn(v); self.cycles = 4 + e; }
+            0xA3 => { let v = self.izx_r(bus); self.a = v; self.x = v; self.update_zn(v); self.cycles = 6; }
+            0xB3 => { let (v, e) = self.izy_r(bus); self.a = v; self.x = v; self.update_zn(v); self.cycles = 5 + e; }
+
+            // SAX
+            0x87 => { let a = self.zp(bus); let v = self.a & self.x; bus.write(a, v); self.cycles = 3; }
+            0x97 => { let base = bus.read(self.pc).wrapping_add(self.y) as u16 & 0xFF; self.pc = self.pc.wrapping_add(1); let v = self.a & self.x; bus.write(base, v); self.cycles = 4; }
+            0x8F => { let a = self.abs(bus); let v = self.a & self.x; bus.write(a, v); self.cycles = 4; }
+            0x83 => { let a = self.izx(bus); let v = self.a & self.x; bus.write(a, v); self.cycles = 6; }
+
+            // DCP
+            0xC7 => { let a = self.zp(bus); self.op_dcp(bus, a); self.cycles = 5; }
+            0xD7 => { let a = self.zpx(bus); self.op_dcp(bus, a); self.cycles = 6; }
+            0xCF => { let a = self.abs(bus); self.op_dcp(bus, a); self.cycles = 6; }
+            0xDF => { let a = self.abx_w(bus); self.op_dcp(bus, a); self.cycles = 7; }
+            0xDB => { let a = self.aby_w(bus); self.op_dcp(bus, a); self.cycles = 7; }
+            0xC3 => { let a = self.izx(bus); self.op_dcp(bus, a); self.cycles = 8; }
+            0xD3 => { let a = self.izy_w(bus); self.op_dcp(bus, a); self.cycles = 8; }
+
+            // ISB
+            0xE7 => { let a = self.zp(bus); self.op_isb(bus, a); self.cycles = 5; }
+            0xF7 => { let a = self.zpx(bus); self.op_isb(bus, a); self.cycles = 6; }
+            0xEF => { let a = self.abs(bus); self.op_isb(bus, a); self.cycles = 6; }
+            0xFF => { let a = self.abx_w(bus); self.op_isb(bus, a); self.cycles = 7; }
+            0xFB => { let a = self.aby_w(bus); self.op_isb(bus, a); self.cycles = 7; }
+            0xE3 => { let a = self.izx(bus); self.op_isb(bus, a); self.cycles = 8; }
+            0xF3 => { let a = self.izy_w(bus); self.op_isb(bus, a); self.cycles = 8; }
+
+            // SLO
+            0x07 => { let a = self.zp(bus); self.op_slo(bus, a); self.cycles = 5; }
+            0x17 => { let a = self.zpx(bus); self.op_slo(bus, a); self.cycles = 6; }
+            0x0F => { let a = self.abs(bus); self.op_slo(bus, a); self.cycles = 6; }
+            0x1F => { let a = self.abx_w(bus); self.op_slo(bus, a); self.cycles = 7; }
+            0x1B => { let a = self.aby_w(bus); self.op_slo(bus, a); self.cycles = 7; }
+            0x03 => { let a = self.izx(bus); self.op_slo(bus, a); self.cycles = 8; }
+            0x13 => { let a = self.izy_w(bus); self.op_slo(bus, a); self.cycles = 8; }
+
+            // RLA
+            0x27 => { let a = self.zp(bus); self.op_rla(bus, a); self.cycles = 5; }
+            0x37 => { let a = self.zpx(bus); self.op_rla(bus, a); self.cycles = 6; }
+            0x2F => { let a = self.abs(bus); self.op_rla(bus, a); self.cycles = 6; }
+            0x3F => { let a = self.abx_w(bus); self.op_rla(bus, a); self.cycles = 7; }
+            0x3B => { let a = self.aby_w(bus); self.op_rla(bus, a); self.cycles = 7; }
+            0x23 => { let a = self.izx(bus); self.op_rla(bus, a); self.cycles = 8; }
+            0x33 => { let a = self.izy_w(bus); self.op_rla(bus, a); self.cycles = 8; }
+
+            // SRE
+            0x47 => { let a = self.zp(bus); self.op_sre(bus, a); self.cycles = 5; }
+            0x57 => { let a = self.zpx(bus); self.op_sre(bus, a); self.cycles = 6; }
+            0x4F => { let a = self.abs(bus); self.op_sre(bus, a); self.cycles = 6; }
+            0x5F => { let a = self.abx_w(bus); self.op_sre(bus, a); self.cycles = 7; }
+            0x5B => { let a = self.aby_w(bus); self.op_sre(bus, a); self.cycles = 7; }
+            0x43 => { let a = self.izx(bus); self.op_sre(bus, a); self.cycles = 8; }
+            0x53 => { let a = self.izy_w(bus); self.op_sre(bus, a); self.cycles = 8; }
+
+            // RRA
+            0x67 => { let a = self.zp(bus); self.op_rra(bus, a); self.cycles = 5; }
+            0x77 => { let a = self.zpx(bus); self.op_rra(bus, a); self.cycles = 6; }
+            0x6F => { let a = self.abs(bus); self.op_rra(bus, a); self.cycles = 6; }
+            0x7F => { let a = self.abx_w(bus); self.op_rra(bus, a); self.cycles = 7; }
+            0x7B => { let a = self.aby_w(bus); self.op_rra(bus, a); self.cycles = 7; }
+            0x63 => { let a = self.izx(bus); self.op_rra(bus, a); self.cycles = 8; }
+            0x73 => { let a = self.izy_w(bus); self.op_rra(bus, a); self.cycles = 8; }
+
+            // 立即值組合運算：ANC / ALR / ARR / AXS
+            0x0B | 0x2B => { let v = self.imm(bus); self.a &= v; self.update_zn(self.a); self.set_flag(flags::CARRY, self.a & 0x80 != 0); self.cycles = 2; }
+            0x4B => { let v = self.imm(bus); let r = self.a & v; self.set_flag(flags::CARRY, r & 0x01 != 0); self.a = r >> 1; self.update_zn(self.a); self.cycles = 2; }
+            0x6B => {
+                let v = self.imm(bus);
+                let r = ((self.a & v) >> 1) | ((self.get_flag(flags::CARRY) as u8) << 7);
+                self.a = r;
+                self.update_zn(r);
+                // C = 位元 6，V = 位元 6 XOR 位元 5
+                self.set_flag(flags::CARRY, r & 0x40 != 0);
+                self.set_flag(flags::OVERFLOW, ((r >> 6) ^ (r >> 5)) & 0x01 != 0);
                 self.cycles = 2;
             }
-        }
-    }
-
-    // ===== 指令實作 =====
-
-    /// LDA - 載入到累加器
-    fn lda(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8) {
-        let (addr, page_cross) = self.resolve_address(mode, read_fn);
-        self.a = read_fn(addr);
-        self.update_zn(self.a);
-        if page_cross { self.cycles += 1; }
-    }
+            0xCB => { let v = self.imm(bus); let ax = self.a & self.x; self.set_flag(flags::CARRY, ax >= v); self.x = ax.wrapping_sub(v); self.update_zn(self.x); self.cycles = 2; }
+
+            // 不穩定的立即值指令：結果取決於晶片個體與溫度，以設定的常數近似
+            0x8B => { let v = self.imm(bus); self.a = (self.a | self.unstable_magic) & self.x & v; self.update_zn(self.a); self.cycles = 2; }
+            0xAB => { let v = self.imm(bus); let r = (self.a | self.unstable_magic) & v; self.a = r; self.x = r; self.update_zn(r); self.cycles = 2; }
+
+            // SHY / SHX / SHA / TAS：寫入 暫存器 & (位址高位元組 + 1)
+            0x9C => { let (base, idx) = (self.abs(bus), self.x); let v = self.y; self.op_sh(bus, base, idx, v); self.cycles = 5; }
+            0x9E => { let (base, idx) = (self.abs(bus), self.y); let v = self.x; self.op_sh(bus, base, idx, v); self.cycles = 5; }
+            0x9F => { let (base, idx) = (self.abs(bus), self.y); let v = self.a & self.x; self.op_sh(bus, base, idx, v); self.cycles = 5; }
+            0x93 => {
+                let ptr = self.zp(bus);
+                let lo = bus.read(ptr) as u16;
+                let hi = bus.read((ptr + 1) & 0xFF) as u16;
+                let v = self.a & self.x;
+                self.op_sh(bus, (hi << 8) | lo, self.y, v);
+                self.cycles = 6;
+            }
+            0x9B => { let (base, idx) = (self.abs(bus), self.y); self.sp = self.a & self.x; let v = self.sp; self.op_sh(bus, base, idx, v); self.cycles = 5; }
 
-    /// LDX - 載入到 X 暫存器
-    fn ldx(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8) {
-        let (addr, page_cross) = self.resolve_address(mode, read_fn);
-        self.x = read_fn(addr);
-        self.update_zn(self.x);
-        if page_cross { self.cycles += 1; }
-    }
+            // LAS：記憶體 & SP 同時載入 A、X、SP
+            0xBB => { let (v, e) = self.aby_r(bus); let r = v & self.sp; self.a = r; self.x = r; self.sp = r; self.update_zn(r); self.cycles = 4 + e; }
 
-    /// LDY - 載入到 Y 暫存器
-    fn ldy(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8) {
-        let (addr, page_cross) = self.resolve_address(mode, read_fn);
-        self.y = read_fn(addr);
-        self.update_zn(self.y);
-        if page_cross { self.cycles += 1; }
-    }
+            // NOP 變體
+            0x1A | 0x3A | 0x5A | 0x7A | 0xDA | 0xFA => { self.cycles = 2; }
+            0x80 | 0x82 | 0x89 | 0xC2 | 0xE2 => { self.pc = self.pc.wrapping_add(1); self.cycles = 2; }
+            0x04 | 0x44 | 0x64 => { self.pc = self.pc.wrapping_add(1); self.cycles = 3; }
+            0x14 | 0x34 | 0x54 | 0x74 | 0xD4 | 0xF4 => { self.pc = self.pc.wrapping_add(1); self.cycles = 4; }
+            0x0C => { self.pc = self.pc.wrapping_add(2); self.cycles = 4; }
+            // 絕對+X 的 NOP 與 LDA 一樣會讀取（含跨頁的 dummy read）
+            0x1C | 0x3C | 0x5C | 0x7C | 0xDC | 0xFC => { let (_, e) = self.abx_r(bus); self.cycles = 4 + e; }
 
-    /// STA - 儲存累加器到記憶體
-    fn sta(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        write_fn(addr, self.a);
+            _ => { self.cycles = 2; }
+        }
     }
 
-    /// STX - 儲存 X 暫存器到記憶體
-    fn stx(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        write_fn(addr, self.x);
-    }
+    // ============================================================
+    // 定址模式輔助函數（簡短命名以減少重複碼量）
+    // ============================================================
 
-    /// STY - 儲存 Y 暫存器到記憶體
-    fn sty(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        write_fn(addr, self.y);
+    /// 立即值
+    fn imm<B: CpuBus>(&mut self, bus: &mut B) -> u8 {
+        let v = bus.read(self.pc);
+        self.pc = self.pc.wrapping_add(1);
+        v
     }
 
-    /// ADC - 帶進位加法
-    /// A = A + M + C，更新 C、Z、V、N 旗標
-    fn adc(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8) {
-        let (addr, page_cross) = self.resolve_address(mode, read_fn);
-        let value = read_fn(addr);
-        self.adc_value(value);
-        if page_cross { self.cycles += 1; }
+    /// 零頁位址
+    fn zp<B: CpuBus>(&mut self, bus: &mut B) -> u16 {
+        let a = bus.read(self.pc) as u16;
+        self.pc = self.pc.wrapping_add(1);
+        a
     }
 
-    /// ADC 的核心運算（也供 RRA 使用）
-    fn adc_value(&mut self, value: u8) {
-        let carry = if self.get_flag(flags::CARRY) { 1u16 } else { 0u16 };
-        let sum = self.a as u16 + value as u16 + carry;
-
-        // 設定進位旗標（結果超過 255）
-        self.set_flag(flags::CARRY, sum > 255);
+    /// 零頁讀取
+    fn zp_r<B: CpuBus>(&mut self, bus: &mut B) -> u8 { let a = self.zp(bus); bus.read(a) }
 
-        // 設定溢位旗標（兩個同號數相加得到異號結果）
-        let result = sum as u8;
-        self.set_flag(
-            flags::OVERFLOW,
-            (!(self.a ^ value) & (self.a ^ result)) & 0x80 != 0,
-        );
-
-        self.a = result;
-        self.update_zn(self.a);
+    /// 零頁+X 位址
+    fn zpx<B: CpuBus>(&mut self, bus: &mut B) -> u16 {
+        let a = bus.read(self.pc).wrapping_add(self.x) as u16 & 0xFF;
+        self.pc = self.pc.wrapping_add(1);
+        a
     }
 
-    /// SBC - 帶借位減法
-    /// A = A - M - (1 - C)，等價於 A + (~M) + C
-    fn sbc(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8) {
-        let (addr, page_cross) = self.resolve_address(mode, read_fn);
-        let value = read_fn(addr);
-        // SBC 等價於 ADC 取反值
-        self.adc_value(value ^ 0xFF);
-        if page_cross { self.cycles += 1; }
-    }
+    /// 零頁+X 讀取
+    fn zpx_r<B: CpuBus>(&mut self, bus: &mut B) -> u8 { let a = self.zpx(bus); bus.read(a) }
 
-    /// CMP - 比較累加器
-    fn cmp(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8) {
-        let (addr, page_cross) = self.resolve_address(mode, read_fn);
-        let value = read_fn(addr);
-        self.compare(self.a, value);
-        if page_cross { self.cycles += 1; }
+    /// 絕對位址
+    fn abs<B: CpuBus>(&mut self, bus: &mut B) -> u16 {
+        let lo = bus.read(self.pc) as u16;
+        let hi = bus.read(self.pc.wrapping_add(1)) as u16;
+        self.pc = self.pc.wrapping_add(2);
+        (hi << 8) | lo
     }
 
-    /// CPX - 比較 X 暫存器
-    fn cpx(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let value = read_fn(addr);
-        self.compare(self.x, value);
-    }
+    /// 絕對讀取
+    fn abs_r<B: CpuBus>(&mut self, bus: &mut B) -> (u8, u8) { let a = self.abs(bus); (bus.read(a), 0) }
 
-    /// CPY - 比較 Y 暫存器
-    fn cpy(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let value = read_fn(addr);
-        self.compare(self.y, value);
+    /// 索引位址計算：CPU 先用還沒進位的高位元組讀一次（dummy read），
+    /// 讀取指令只在跨頁時發生，寫入與 RMW 指令一律發生。
+    /// 這次讀取對 $2007、$4015 等有副作用的暫存器是可觀察的。
+    /// 回傳（位址, 是否跨頁）
+    fn index<B: CpuBus>(&mut self, bus: &mut B, base: u16, index: u8, always_dummy: bool) -> (u16, u8) {
+        let addr = base.wrapping_add(index as u16);
+        let crossed = (base & 0xFF00) != (addr & 0xFF00);
+        if crossed || always_dummy {
+            bus.read((base & 0xFF00) | (addr & 0x00FF));
+        }
+        (addr, crossed as u8)
     }
 
-    /// 比較操作的共用邏輯
-    #[inline]
-    fn compare(&mut self, reg: u8, value: u8) {
-        let result = reg.wrapping_sub(value);
-        self.set_flag(flags::CARRY, reg >= value);
-        self.update_zn(result);
+    /// 絕對+X 讀取（含頁面交叉檢查）
+    fn abx_r<B: CpuBus>(&mut self, bus: &mut B) -> (u8, u8) {
+        let base = self.abs(bus);
+        let (addr, e) = self.index(bus, base, self.x, false);
+        (bus.read(addr), e)
     }
 
-    /// INC - 記憶體遞增
-    fn inc(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let value = read_fn(addr).wrapping_add(1);
-        write_fn(addr, value);
-        self.update_zn(value);
+    /// 絕對+X 位址（寫入用）
+    fn abx_w<B: CpuBus>(&mut self, bus: &mut B) -> u16 {
+        let base = self.abs(bus);
+        self.index(bus, base, self.x, true).0
     }
 
-    /// DEC - 記憶體遞減
-    fn dec(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let value = read_fn(addr).wrapping_sub(1);
-        write_fn(addr, value);
-        self.update_zn(value);
+    /// 絕對+Y 讀取
+    fn aby_r<B: CpuBus>(&mut self, bus: &mut B) -> (u8, u8) {
+        let base = self.abs(bus);
+        let (addr, e) = self.index(bus, base, self.y, false);
+        (bus.read(addr), e)
     }
 
-    /// AND - 邏輯與
-    fn and(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8) {
-        let (addr, page_cross) = self.resolve_address(mode, read_fn);
-        self.a &= read_fn(addr);
-        self.update_zn(self.a);
-        if page_cross { self.cycles += 1; }
+    /// 絕對+Y 位址（寫入用）
+    fn aby_w<B: CpuBus>(&mut self, bus: &mut B) -> u16 {
+        let base = self.abs(bus);
+        self.index(bus, base, self.y, true).0
     }
 
-    /// ORA - 邏輯或
-    fn ora(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8) {
-        let (addr, page_cross) = self.resolve_address(mode, read_fn);
-        self.a |= read_fn(addr);
-        self.update_zn(self.a);
-        if page_cross { self.cycles += 1; }
+    /// (間接,X) 位址
+    fn izx<B: CpuBus>(&mut self, bus: &mut B) -> u16 {
+        let ptr = bus.read(self.pc).wrapping_add(self.x) as u16;
+        self.pc = self.pc.wrapping_add(1);
+        let lo = bus.read(ptr & 0xFF) as u16;
+        let hi = bus.read((ptr.wrapping_add(1)) & 0xFF) as u16;
+        (hi << 8) | lo
     }
 
-    /// EOR - 邏輯互斥或
-    fn eor(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8) {
-        let (addr, page_cross) = self.resolve_address(mode, read_fn);
-        self.a ^= read_fn(addr);
+    /// (間接,X) 讀取
+    fn izx_r<B: CpuBus>(&mut self, bus: &mut B) -> u8 { let a = self.izx(bus); bus.read(a) }
+
+    /// (間接),Y 讀取
+    fn izy_r<B: CpuBus>(&mut self, bus: &mut B) -> (u8, u8) {
+        let ptr = bus.read(self.pc) as u16;
+        self.pc = self.pc.wrapping_add(1);
+        let lo = bus.read(ptr) as u16;
+        let hi = bus.read((ptr.wrapping_add(1)) & 0xFF) as u16;
+        let (addr, e) = self.index(bus, (hi << 8) | lo, self.y, false);
+        (bus.read(addr), e)
+    }
+
+    /// (間接),Y 位址（寫入用）
+    fn izy_w<B: CpuBus>(&mut self, bus: &mut B) -> u16 {
+        let ptr = bus.read(self.pc) as u16;
+        self.pc = self.pc.wrapping_add(1);
+        let lo = bus.read(ptr) as u16;
+        let hi = bus.read((ptr.wrapping_add(1)) & 0xFF) as u16;
+        self.index(bus, (hi << 8) | lo, self.y, true).0
+    }
+
+    // ============================================================
+    // 指令操作
+    // ============================================================
+
+    fn op_adc(&mut self, value: u8) {
+        let a = self.a as u16;
+        let v = value as u16;
+        let c = self.get_flag(flags::CARRY) as u16;
+        let result = a + v + c;
+        self.set_flag(flags::CARRY, result > 0xFF);
+        self.set_flag(flags::OVERFLOW, ((a ^ result) & (v ^ result) & 0x80) != 0);
+        self.a = result as u8;
         self.update_zn(self.a);
-        if page_cross { self.cycles += 1; }
-    }
-
-    /// BIT - 位元測試
-    /// Z = A & M，N = M[7]，V = M[6]
-    fn bit(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let value = read_fn(addr);
-        self.set_flag(flags::ZERO, (self.a & value) == 0);
-        self.set_flag(flags::NEGATIVE, value & 0x80 != 0);
-        self.set_flag(flags::OVERFLOW, value & 0x40 != 0);
     }
 
-    /// ASL - 累加器算術左移
-    fn asl_acc(&mut self) {
-        self.set_flag(flags::CARRY, self.a & 0x80 != 0);
-        self.a <<= 1;
+    fn op_sbc(&mut self, value: u8) {
+        let a = self.a as u16;
+        let v = value as u16;
+        let c = self.get_flag(flags::CARRY) as u16;
+        let result = a.wrapping_sub(v).wrapping_sub(1 - c);
+        self.set_flag(flags::CARRY, result < 0x100);
+        self.set_flag(flags::OVERFLOW, ((a ^ result) & (a ^ v) & 0x80) != 0);
+        self.a = result as u8;
         self.update_zn(self.a);
     }
 
-    /// ASL - 記憶體算術左移
-    fn asl_mem(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let mut value = read_fn(addr);
-        self.set_flag(flags::CARRY, value & 0x80 != 0);
-        value <<= 1;
-        write_fn(addr, value);
-        self.update_zn(value);
+    fn op_cmp(&mut self, reg: u8, value: u8) {
+        self.set_flag(flags::CARRY, reg >= value);
+        self.update_zn(reg.wrapping_sub(value));
     }
 
-    /// LSR - 累加器邏輯右移
-    fn lsr_acc(&mut self) {
-        self.set_flag(flags::CARRY, self.a & 0x01 != 0);
-        self.a >>= 1;
-        self.update_zn(self.a);
+    /// SHY/SHX/SHA/TAS 的寫入：值與基底位址的高位元組 + 1 做 AND，
+    /// 跨頁時位址的高位元組也被替換成寫入的值
+    fn op_sh<B: CpuBus>(&mut self, bus: &mut B, base: u16, index: u8, value: u8) {
+        let (addr, _) = self.index(bus, base, index, true);
+        let v = value & ((base >> 8) as u8).wrapping_add(1);
+        let addr = if (base & 0xFF00) != (addr & 0xFF00) { ((v as u16) << 8) | (addr & 0xFF) } else { addr };
+        bus.write(addr, v);
     }
 
-    /// LSR - 記憶體邏輯右移
-    fn lsr_mem(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let mut value = read_fn(addr);
-        self.set_flag(flags::CARRY, value & 0x01 != 0);
-        value >>= 1;
-        write_fn(addr, value);
-        self.update_zn(value);
+    fn op_bit(&mut self, value: u8) {
+        self.set_flag(flags::OVERFLOW, value & 0x40 != 0);
+        self.set_flag(flags::NEGATIVE, value & 0x80 != 0);
+        self.set_flag(flags::ZERO, self.a & value == 0);
     }
 
-    /// ROL - 累加器帶進位左旋轉
-    fn rol_acc(&mut self) {
-        let carry = if self.get_flag(flags::CARRY) { 1 } else { 0 };
-        self.set_flag(flags::CARRY, self.a & 0x80 != 0);
-        self.a = (self.a << 1) | carry;
-        self.update_zn(self.a);
+    /// RMW 指令的讀取：ALU 運算的那個週期 CPU 會先把原值寫回去，
+    /// 所以目標位址會連續收到兩次寫入（舊值、新值）
+    fn rmw_read<B: CpuBus>(&mut self, bus: &mut B, addr: u16) -> u8 {
+        let v = bus.read(addr);
+        bus.write(addr, v);
+        v
     }
 
-    /// ROL - 記憶體帶進位左旋轉
-    fn rol_mem(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let mut value = read_fn(addr);
-        let carry = if self.get_flag(flags::CARRY) { 1 } else { 0 };
-        self.set_flag(flags::CARRY, value & 0x80 != 0);
-        value = (value << 1) | carry;
-        write_fn(addr, value);
-        self.update_zn(value);
+    fn op_asl_m<B: CpuBus>(&mut self, bus: &mut B, addr: u16) {
+        let mut v = self.rmw_read(bus, addr); self.set_flag(flags::CARRY, v & 0x80 != 0);
+        v <<= 1; bus.write(addr, v); self.update_zn(v);
     }
 
-    /// ROR - 累加器帶進位右旋轉
-    fn ror_acc(&mut self) {
-        let carry = if self.get_flag(flags::CARRY) { 0x80 } else { 0 };
-        self.set_flag(flags::CARRY, self.a & 0x01 != 0);
-        self.a = (self.a >> 1) | carry;
-        self.update_zn(self.a);
+    fn op_lsr_m<B: CpuBus>(&mut self, bus: &mut B, addr: u16) {
+        let mut v = self.rmw_read(bus, addr); self.set_flag(flags::CARRY, v & 0x01 != 0);
+        v >>= 1; bus.write(addr, v); self.update_zn(v);
     }
 
-    /// ROR - 記憶體帶進位右旋轉
-    fn ror_mem(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let mut value = read_fn(addr);
-        let carry = if self.get_flag(flags::CARRY) { 0x80 } else { 0 };
-        self.set_flag(flags::CARRY, value & 0x01 != 0);
-        value = (value >> 1) | carry;
-        write_fn(addr, value);
-        self.update_zn(value);
+    fn op_rol_m<B: CpuBus>(&mut self, bus: &mut B, addr: u16) {
+        let mut v = self.rmw_read(bus, addr); let c = self.get_flag(flags::CARRY) as u8;
+        self.set_flag(flags::CARRY, v & 0x80 != 0); v = (v << 1) | c;
+        bus.write(addr, v); self.update_zn(v);
     }
 
-    /// 分支指令的共用邏輯
-    fn branch(&mut self, condition: bool, read_fn: &dyn Fn(u16) -> u8) {
-        let (target, page_cross) = self.resolve_address(AddressingMode::Relative, read_fn);
-        if condition {
-            self.cycles += 1; // 分支成功額外 +1 週期
-            if page_cross {
-                self.cycles += 1; // 跨頁額外 +1 週期
-            }
-            self.pc = target;
-        }
+    fn op_ror_m<B: CpuBus>(&mut self, bus: &mut B, addr: u16) {
+        let mut v = self.rmw_read(bus, addr); let c = if self.get_flag(flags::CARRY) { 0x80u8 } else { 0 };
+        self.set_flag(flags::CARRY, v & 0x01 != 0); v = (v >> 1) | c;
+        bus.write(addr, v); self.update_zn(v);
     }
 
-    // ===== 非官方指令實作 =====
-
-    /// LAX - LDA + LDX（非官方）
-    fn lax(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8) {
-        let (addr, page_cross) = self.resolve_address(mode, read_fn);
-        let value = read_fn(addr);
-        self.a = value;
-        self.x = value;
-        self.update_zn(value);
-        if page_cross { self.cycles += 1; }
+    fn op_dec_m<B: CpuBus>(&mut self, bus: &mut B, addr: u16) {
+        let v = self.rmw_read(bus, addr).wrapping_sub(1); bus.write(addr, v); self.update_zn(v);
     }
 
-    /// SAX - A & X -> M（非官方）
-    fn sax(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        write_fn(addr, self.a & self.x);
+    fn op_inc_m<B: CpuBus>(&mut self, bus: &mut B, addr: u16) {
+        let v = self.rmw_read(bus, addr).wrapping_add(1); bus.write(addr, v); self.update_zn(v);
     }
 
-    /// DCP - DEC + CMP（非官方）
-    fn dcp(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let value = read_fn(addr).wrapping_sub(1);
-        write_fn(addr, value);
-        self.compare(self.a, value);
+    fn op_dcp<B: CpuBus>(&mut self, bus: &mut B, addr: u16) {
+        let v = self.rmw_read(bus, addr).wrapping_sub(1); bus.write(addr, v);
+        let a = self.a; self.op_cmp(a, v);
     }
 
-    /// ISB/ISC - INC + SBC（非官方）
-    fn isb(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let value = read_fn(addr).wrapping_add(1);
-        write_fn(addr, value);
-        self.adc_value(value ^ 0xFF);
+    fn op_isb<B: CpuBus>(&mut self, bus: &mut B, addr: u16) {
+        let v = self.rmw_read(bus, addr).wrapping_add(1); bus.write(addr, v);
+        self.op_sbc(v);
     }
 
-    /// SLO - ASL + ORA（非官方）
-    fn slo(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let mut value = read_fn(addr);
-        self.set_flag(flags::CARRY, value & 0x80 != 0);
-        value <<= 1;
-        write_fn(addr, value);
-        self.a |= value;
-        self.update_zn(self.a);
+    fn op_slo<B: CpuBus>(&mut self, bus: &mut B, addr: u16) {
+        let mut v = self.rmw_read(bus, addr); self.set_flag(flags::CARRY, v & 0x80 != 0);
+        v <<= 1; bus.write(addr, v);
+        self.a |= v; self.update_zn(self.a);
     }
 
-    /// RLA - ROL + AND（非官方）
-    fn rla(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let mut value = read_fn(addr);
-        let carry = if self.get_flag(flags::CARRY) { 1 } else { 0 };
-        self.set_flag(flags::CARRY, value & 0x80 != 0);
-        value = (value << 1) | carry;
-        write_fn(addr, value);
-        self.a &= value;
-        self.update_zn(self.a);
+    fn op_rla<B: CpuBus>(&mut self, bus: &mut B, addr: u16) {
+        let mut v = self.rmw_read(bus, addr); let c = self.get_flag(flags::CARRY) as u8;
+        self.set_flag(flags::CARRY, v & 0x80 != 0); v = (v << 1) | c;
+        bus.write(addr, v); self.a &= v; self.update_zn(self.a);
     }
 
-    /// SRE - LSR + EOR（非官方）
-    fn sre(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let mut value = read_fn(addr);
-        self.set_flag(flags::CARRY, value & 0x01 != 0);
-        value >>= 1;
-        write_fn(addr, value);
-        self.a ^= value;
-        self.update_zn(self.a);
+    fn op_sre<B: CpuBus>(&mut self, bus: &mut B, addr: u16) {
+        let mut v = self.rmw_read(bus, addr); self.set_flag(flags::CARRY, v & 0x01 != 0);
+        v >>= 1; bus.write(addr, v);
+        self.a ^= v; self.update_zn(self.a);
     }
 
-    /// RRA - ROR + ADC（非官方）
-    fn rra(&mut self, mode: AddressingMode, read_fn: &dyn Fn(u16) -> u8, write_fn: &mut dyn FnMut(u16, u8)) {
-        let (addr, _) = self.resolve_address(mode, read_fn);
-        let mut value = read_fn(addr);
-        let carry = if self.get_flag(flags::CARRY) { 0x80 } else { 0 };
-        self.set_flag(flags::CARRY, value & 0x01 != 0);
-        value = (value >> 1) | carry;
-        write_fn(addr, value);
-        self.adc_value(value);
+    fn op_rra<B: CpuBus>(&mut self, bus: &mut B, addr: u16) {
+        let mut v = self.rmw_read(bus, addr); let c = if self.get_flag(flags::CARRY) { 0x80u8 } else { 0 };
+        self.set_flag(flags::CARRY, v & 0x01 != 0); v = (v >> 1) | c;
+        bus.write(addr, v); self.op_adc(v);
     }
 }
@@ -0,0 +1,416 @@
+// ============================================================
+// 除錯器 - 中斷點、指令追蹤與反組譯
+// ============================================================
+// 除錯狀態集中在 Debugger，由模擬器在每條指令取指前檢查：
+// - 中斷點：PC 命中時停止執行，frame() 會提前返回
+// - 監看點：CPU 讀寫指定位址時，等該指令執行完、下一條指令取指前停止
+//   （與中斷點相同的暫停狀態，停止原因以 StopReason 區分）
+// - 指令追蹤：記錄每條指令執行前的狀態，格式與 nestest.log 相同
+//   （PC、機器碼、附有效位址的反組譯、A/X/Y/P/SP、PPU 掃描線/點、CPU 週期），
+//   可以直接與 nestest.log 逐行比對
+//
+// 沒有中斷點也沒開啟追蹤時，取指前只多一次布林判斷。
+//
+// 反組譯表包含非官方指令（以 * 標示，與 nestest 記錄相同）。
+// 參考：
+// - https://www.nesdev.org/wiki/CPU_unofficial_opcodes
+// - https://www.qmtpro.com/~nes/misc/nestest.log
+// ============================================================
+
+use std::collections::{BTreeSet, VecDeque};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::cpu::AddressingMode;
+use crate::cpu::AddressingMode::*;
+
+/// 追蹤記錄保留的最大行數（超過時捨棄最舊的記錄）
+pub const TRACE_LIMIT: usize = 10_000;
+
+/// 指令表：助記符與定址模式，以 opcode 為索引
+const OPCODES: [(&str, AddressingMode); 256] = [
+    // 0x00
+    ("BRK", Implicit), ("ORA", IndirectX), ("*KIL", Implicit), ("*SLO", IndirectX),
+    ("*NOP", ZeroPage), ("ORA", ZeroPage), ("ASL", ZeroPage), ("*SLO", ZeroPage),
+    ("PHP", Implicit), ("ORA", Immediate), ("ASL", Accumulator), ("*ANC", Immediate),
+    ("*NOP", Absolute), ("ORA", Absolute), ("ASL", Absolute), ("*SLO", Absolute),
+    // 0x10
+    ("BPL", Relative), ("ORA", IndirectY), ("*KIL", Implicit), ("*SLO", IndirectY),
+    ("*NOP", ZeroPageX), ("ORA", ZeroPageX), ("ASL", ZeroPageX), ("*SLO", ZeroPageX),
+    ("CLC", Implicit), ("ORA", AbsoluteY), ("*NOP", Implicit), ("*SLO", AbsoluteY),
+    ("*NOP", AbsoluteX), ("ORA", AbsoluteX), ("ASL", AbsoluteX), ("*SLO", AbsoluteX),
+    // 0x20
+    ("JSR", Absolute), ("AND", IndirectX), ("*KIL", Implicit), ("*RLA", IndirectX),
+    ("BIT", ZeroPage), ("AND", ZeroPage), ("ROL", ZeroPage), ("*RLA", ZeroPage),
+    ("PLP", Implicit), ("AND", Immediate), ("ROL", Accumulator), ("*ANC", Immediate),
+    ("BIT", Absolute), ("AND", Absolute), ("ROL", Absolute), ("*RLA", Absolute),
+    // 0x30
+    ("BMI", Relative), ("AND", IndirectY), ("*KIL", Implicit), ("*RLA", IndirectY),
+    ("*NOP", ZeroPageX), ("AND", ZeroPageX), ("ROL", ZeroPageX), ("*RLA", ZeroPageX),
+    ("SEC", Implicit), ("AND", AbsoluteY), ("*NOP", Implicit), ("*RLA", AbsoluteY),
+    ("*NOP", AbsoluteX), ("AND", AbsoluteX), ("ROL", AbsoluteX), ("*RLA", AbsoluteX),
+    // 0x40
+    ("RTI", Implicit), ("EOR", IndirectX), ("*KIL", Implicit), ("*SRE", IndirectX),
+    ("*NOP", ZeroPage), ("EOR", ZeroPage), ("LSR", ZeroPage), ("*SRE", ZeroPage),
+    ("PHA", Implicit), ("EOR", Immediate), ("LSR", Accumulator), ("*ALR", Immediate),
+    ("JMP", Absolute), ("EOR", Absolute), ("LSR", Absolute), ("*SRE", Absolute),
+    // 0x50
+    ("BVC", Relative), ("EOR", IndirectY), ("*KIL", Implicit), ("*SRE", IndirectY),
+    ("*NOP", ZeroPageX), ("EOR", ZeroPageX), ("LSR", ZeroPageX), ("*SRE", ZeroPageX),
+    ("CLI", Implicit), ("EOR", AbsoluteY), ("*NOP", Implicit), ("*SRE", AbsoluteY),
+    ("*NOP", AbsoluteX), ("EOR", AbsoluteX), ("LSR", AbsoluteX), ("*SRE", AbsoluteX),
+    // 0x60
+    ("RTS", Implicit), ("ADC", IndirectX), ("*KIL", Implicit), ("*RRA", IndirectX),
+    ("*NOP", ZeroPage), ("ADC", ZeroPage), ("ROR", ZeroPage), ("*RRA", ZeroPage),
+    ("PLA", Implicit), ("ADC", Immediate), ("ROR", Accumulator), ("*ARR", Immediate),
+    ("JMP", Indirect), ("ADC", Absolute), ("ROR", Absolute), ("*RRA", Absolute),
+    // 0x70
+    ("BVS", Relative), ("ADC", IndirectY), ("*KIL", Implicit), ("*RRA", IndirectY),
+    ("*NOP", ZeroPageX), ("ADC", ZeroPageX), ("ROR", ZeroPageX), ("*RRA", ZeroPageX),
+    ("SEI", Implicit), ("ADC", AbsoluteY), ("*NOP", Implicit), ("*RRA", AbsoluteY),
+    ("*NOP", AbsoluteX), ("ADC", AbsoluteX), ("ROR", AbsoluteX), ("*RRA", AbsoluteX),
+    // 0x80
+    ("*NOP", Immediate), ("STA", IndirectX), ("*NOP", Immediate), ("*SAX", IndirectX),
+    ("STY", ZeroPage), ("STA", ZeroPage), ("STX", ZeroPage), ("*SAX", ZeroPage),
+    ("DEY", Implicit), ("*NOP", Immediate), ("TXA", Implicit), ("*XAA", Immediate),
+    ("STY", Absolute), ("STA", Absolute), ("STX", Absolute), ("*SAX", Absolute),
+    // 0x90
+    ("BCC", Relative), ("STA", IndirectY), ("*KIL", Implicit), ("*AHX", IndirectY),
+    ("STY", ZeroPageX), ("STA", ZeroPageX), ("STX", ZeroPageY), ("*SAX", ZeroPageY),
+    ("TYA", Implicit), ("STA", AbsoluteY), ("TXS", Implicit), ("*TAS", AbsoluteY),
+    ("*SHY", AbsoluteX), ("STA", AbsoluteX), ("*SHX", AbsoluteY), ("*AHX", AbsoluteY),
+    // 0xA0
+    ("LDY", Immediate), ("LDA", IndirectX), ("LDX", Immediate), ("*LAX", IndirectX),
+    ("LDY", ZeroPage), ("LDA", ZeroPage), ("LDX", ZeroPage), ("*LAX", ZeroPage),
+    ("TAY", Implicit), ("LDA", Immediate), ("TAX", Implicit), ("*LAX", Immediate),
+    ("LDY", Absolute), ("LDA", Absolute), ("LDX", Absolute), ("*LAX", Absolute),
+    // 0xB0
+    ("BCS", Relative), ("LDA", IndirectY), ("*KIL", Implicit), ("*LAX", IndirectY),
+    ("LDY", ZeroPageX), ("LDA", ZeroPageX), ("LDX", ZeroPageY), ("*LAX", ZeroPageY),
+    ("CLV", Implicit), ("LDA", AbsoluteY), ("TSX", Implicit), ("*LAS", AbsoluteY),
+    ("LDY", AbsoluteX), ("LDA", AbsoluteX), ("LDX", AbsoluteY), ("*LAX", AbsoluteY),
+    // 0xC0
+    ("CPY", Immediate), ("CMP", IndirectX), ("*NOP", Immediate), ("*DCP", IndirectX),
+    ("CPY", ZeroPage), ("CMP", ZeroPage), ("DEC", ZeroPage), ("*DCP", ZeroPage),
+    ("INY", Implicit), ("CMP", Immediate), ("DEX", Implicit), ("*AXS", Immediate),
+    ("CPY", Absolute), ("CMP", Absolute), ("DEC", Absolute), ("*DCP", Absolute),
+    // 0xD0
+    ("BNE", Relative), ("CMP", IndirectY), ("*KIL", Implicit), ("*DCP", IndirectY),
+    ("*NOP", ZeroPageX), ("CMP", ZeroPageX), ("DEC", ZeroPageX), ("*DCP", ZeroPageX),
+    ("CLD", Implicit), ("CMP", AbsoluteY), ("*NOP", Implicit), ("*DCP", AbsoluteY),
+    ("*NOP", AbsoluteX), ("CMP", AbsoluteX), ("DEC", AbsoluteX), ("*DCP", AbsoluteX),
+    // 0xE0
+    ("CPX", Immediate), ("SBC", IndirectX), ("*NOP", Immediate), ("*ISB", IndirectX),
+    ("CPX", ZeroPage), ("SBC", ZeroPage), ("INC", ZeroPage), ("*ISB", ZeroPage),
+    ("INX", Implicit), ("SBC", Immediate), ("NOP", Implicit), ("*SBC", Immediate),
+    ("CPX", Absolute), ("SBC", Absolute), ("INC", Absolute), ("*ISB", Absolute),
+    // 0xF0
+    ("BEQ", Relative), ("SBC", IndirectY), ("*KIL", Implicit), ("*ISB", IndirectY),
+    ("*NOP", ZeroPageX), ("SBC", ZeroPageX), ("INC", ZeroPageX), ("*ISB", ZeroPageX),
+    ("SED", Implicit), ("SBC", AbsoluteY), ("*NOP", Implicit), ("*ISB", AbsoluteY),
+    ("*NOP", AbsoluteX), ("SBC", AbsoluteX), ("INC", AbsoluteX), ("*ISB", AbsoluteX),
+];
+
+/// 指令長度（位元組，含 opcode）
+pub fn instruction_length(opcode: u8) -> u16 {
+    match OPCODES[opcode as usize].1 {
+        Implicit | Accumulator => 1,
+        Absolute | AbsoluteX | AbsoluteY | Indirect => 3,
+        _ => 2,
+    }
+}
+
+/// 反組譯一條指令，回傳（組合語言文字, 指令長度）
+/// read 必須是沒有副作用的讀取（不可觸發 PPU 暫存器等行為）
+pub fn disassemble(addr: u16, read: impl Fn(u16) -> u8) -> (String, u16) {
+    let opcode = read(addr);
+    let (name, mode) = OPCODES[opcode as usize];
+    let lo = read(addr.wrapping_add(1));
+    let hi = read(addr.wrapping_add(2));
+    let word = u16::from_le_bytes([lo, hi]);
+    let operand = match mode {
+        Implicit => String::new(),
+        Accumulator => "A".to_string(),
+        Immediate => format!("#${:02X}", lo),
+        ZeroPage => format!("${:02X}", lo),
+        ZeroPageX => format!("${:02X},X", lo),
+        ZeroPageY => format!("${:02X},Y", lo),
+        Relative => format!("${:04X}", addr.wrapping_add(2).wrapping_add(lo as i8 as u16)),
+        Absolute => format!("${:04X}", word),
+        AbsoluteX => format!("${:04X},X", word),
+        AbsoluteY => format!("${:04X},Y", word),
+        Indirect => format!("(${:04X})", word),
+        IndirectX => format!("(${:02X},X)", lo),
+        IndirectY => format!("(${:02X}),Y", lo),
+    };
+    let text = if operand.is_empty() { name.to_string() } else { format!("{} {}", name, operand) };
+    (text, instruction_length(opcode))
+}
+
+/// 以 nestest.log 的格式反組譯：操作數附上有效位址與該位址目前的值
+/// （例如「LDA ($80),Y = 0200 @ 0203 = 5A」）。x、y 為執行前的索引暫存器，
+/// read 同樣必須沒有副作用
+pub fn disassemble_annotated(addr: u16, x: u8, y: u8, read: impl Fn(u16) -> u8) -> String {
+    let opcode = read(addr);
+    let (name, mode) = OPCODES[opcode as usize];
+    let lo = read(addr.wrapping_add(1));
+    let hi = read(addr.wrapping_add(2));
+    let word = u16::from_le_bytes([lo, hi]);
+    // 零頁指標的高位元組不跨頁（$FF 的下一個位元組是 $00）
+    let zero_page_pointer = |p: u8| u16::from_le_bytes([read(p as u16), read(p.wrapping_add(1) as u16)]);
+    let operand = match mode {
+        Implicit => String::new(),
+        Accumulator => "A".to_string(),
+        Immediate => format!("#${:02X}", lo),
+        ZeroPage => format!("${:02X} = {:02X}", lo, read(lo as u16)),
+        ZeroPageX => {
+            let a = lo.wrapping_add(x);
+            format!("${:02X},X @ {:02X} = {:02X}", lo, a, read(a as u16))
+        }
+        ZeroPageY => {
+            let a = lo.wrapping_add(y);
+            format!("${:02X},Y @ {:02X} = {:02X}", lo, a, read(a as u16))
+        }
+        Relative => format!("${:04X}", addr.wrapping_add(2).wrapping_add(lo as i8 as u16)),
+        Absolute if name == "JMP" || name == "JSR" => format!("${:04X}", word),
+        Absolute => format!("${:04X} = {:02X}", word, read(word)),
+        AbsoluteX => {
+            let a = word.wrapping_add(x as u16);
+            format!("${:04X},X @ {:04X} = {:02X}", word, a, read(a))
+        }
+        AbsoluteY => {
+            let a = word.wrapping_add(y as u16);
+            format!("${:04X},Y @ {:04X} = {:02X}", word, a, read(a))
+        }
+        Indirect => {
+            // JMP ($xxFF) 的高位元組從同一頁的 $xx00 讀取（6502 的頁面錯誤）
+            let high = (word & 0xFF00) | (word.wrapping_add(1) & 0x00FF);
+            format!("(${:04X}) = {:04X}", word, u16::from_le_bytes([read(word), read(high)]))
+        }
+        IndirectX => {
+            let p = lo.wrapping_add(x);
+            let a = zero_page_pointer(p);
+            format!("(${:02X},X) @ {:02X} = {:04X} = {:02X}", lo, p, a, read(a))
+        }
+        IndirectY => {
+            let base = zero_page_pointer(lo);
+            let a = base.wrapping_add(y as u16);
+            format!("(${:02X}),Y = {:04X} @ {:04X} = {:02X}", lo, base, a, read(a))
+        }
+    };
+    if operand.is_empty() { name.to_string() } else { format!("{} {}", name, operand) }
+}
+
+/// 執行停止的原因
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// 單步執行完成（一條指令、一條掃描線或一幀）
+    Completed = 0,
+    /// 命中執行中斷點
+    Breakpoint = 1,
+    /// 讀取了監看位址
+    ReadWatch = 2,
+    /// 寫入了監看位址
+    WriteWatch = 3,
+}
+
+/// 除錯器狀態
+#[derive(Debug, Clone, Default)]
+pub struct Debugger {
+    /// 是否需要在取指前檢查（有中斷點、監看點或開啟追蹤時為 true）
+    active: bool,
+    /// 中斷點位址
+    breakpoints: BTreeSet<u16>,
+    /// 讀取監看位址
+    read_watches: BTreeSet<u16>,
+    /// 寫入監看位址
+    write_watches: BTreeSet<u16>,
+    /// 本條指令觸發的監看（停止原因, 位址），下一次取指前轉為暫停
+    pending_watch: Option<(StopReason, u16)>,
+    /// 暫停中的 PC（未暫停時為 None）
+    break_hit: Option<u16>,
+    /// 暫停的原因
+    stop_reason: Option<StopReason>,
+    /// 觸發暫停的監看位址
+    watch_hit: Option<u16>,
+    /// 恢復執行時略過一次的位址（避免停在同一個中斷點上）
+    resume_pc: Option<u16>,
+    /// 是否記錄指令追蹤
+    trace_enabled: bool,
+    /// 指令追蹤記錄
+    trace: VecDeque<String>,
+}
+
+impl Debugger {
+    /// 建立除錯器（沒有中斷點、追蹤關閉）
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 取指前是否需要呼叫 should_break / 記錄追蹤
+    #[inline(always)]
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn update_active(&mut self) {
+        self.active = !self.breakpoints.is_empty()
+            || !self.read_watches.is_empty()
+            || !self.write_watches.is_empty()
+            || self.trace_enabled;
+    }
+
+    /// 新增中斷點
+    pub fn add_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.insert(addr);
+        self.update_active();
+    }
+
+    /// 移除中斷點
+    pub fn remove_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.remove(&addr);
+        self.update_active();
+    }
+
+    /// 清除所有中斷點
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+        self.update_active();
+    }
+
+    /// 新增監看點（write 為 true 時監看寫入，否則監看讀取）
+    pub fn add_watchpoint(&mut self, addr: u16, write: bool) {
+        if write {
+            self.write_watches.insert(addr);
+        } else {
+            self.read_watches.insert(addr);
+        }
+        self.update_active();
+    }
+
+    /// 移除監看點
+    pub fn remove_watchpoint(&mut self, addr: u16, write: bool) {
+        if write {
+            self.write_watches.remove(&addr);
+        } else {
+            self.read_watches.remove(&addr);
+        }
+        self.update_active();
+    }
+
+    /// 清除所有監看點
+    pub fn clear_watchpoints(&mut self) {
+        self.read_watches.clear();
+        self.write_watches.clear();
+        self.update_active();
+    }
+
+    /// 列出監看點（由小到大）
+    pub fn watchpoints(&self, write: bool) -> Vec<u16> {
+        let set = if write { &self.write_watches } else { &self.read_watches };
+        set.iter().copied().collect()
+    }
+
+    /// CPU 讀寫時檢查監看點（同一條指令只記錄第一次命中）
+    #[inline]
+    pub fn check_access(&mut self, addr: u16, write: bool) {
+        if self.pending_watch.is_some() {
+            return;
+        }
+        if write && self.write_watches.contains(&addr) {
+            self.pending_watch = Some((StopReason::WriteWatch, addr));
+        } else if !write && self.read_watches.contains(&addr) {
+            self.pending_watch = Some((StopReason::ReadWatch, addr));
+        }
+    }
+
+    /// 把已觸發的監看轉為暫停（停在 pc），回傳是否暫停
+    pub fn take_pending_watch(&mut self, pc: u16) -> bool {
+        let Some((reason, addr)) = self.pending_watch.take() else { return false };
+        self.break_hit = Some(pc);
+        self.stop_reason = Some(reason);
+        self.watch_hit = Some(addr);
+        true
+    }
+
+    /// 暫停的原因（未暫停時為 None）
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        self.break_hit.and(self.stop_reason)
+    }
+
+    /// 觸發暫停的監看位址（因監看點暫停時才有值）
+    pub fn watch_hit(&self) -> Option<u16> {
+        self.break_hit.and(self.watch_hit)
+    }
+
+    /// 列出所有中斷點（由小到大）
+    pub fn breakpoints(&self) -> Vec<u16> {
+        self.breakpoints.iter().copied().collect()
+    }
+
+    /// 目前停住的中斷點位址（未暫停時為 None）
+    pub fn break_hit(&self) -> Option<u16> {
+        self.break_hit
+    }
+
+    /// 準備恢復執行：解除暫停，並略過一次目前的中斷點
+    pub fn resume(&mut self) {
+        if let Some(pc) = self.break_hit.take() {
+            self.resume_pc = Some(pc);
+        }
+    }
+
+    /// 取指前檢查：上一條指令觸發監看點或 PC 命中中斷點時回傳 true（並進入暫停）
+    pub fn should_break(&mut self, pc: u16) -> bool {
+        if self.take_pending_watch(pc) {
+            return true;
+        }
+        if self.resume_pc.take() == Some(pc) {
+            return false;
+        }
+        if self.breakpoints.contains(&pc) {
+            self.break_hit = Some(pc);
+            self.stop_reason = Some(StopReason::Breakpoint);
+            return true;
+        }
+        false
+    }
+
+    /// 開啟或關閉指令追蹤（關閉時不清除已記錄的內容）
+    pub fn set_trace_enabled(&mut self, enabled: bool) {
+        self.trace_enabled = enabled;
+        self.update_active();
+    }
+
+    /// 是否正在記錄指令追蹤
+    pub fn trace_enabled(&self) -> bool {
+        self.trace_enabled
+    }
+
+    /// 加入一行追蹤記錄
+    pub fn push_trace(&mut self, line: String) {
+        if self.trace.len() >= TRACE_LIMIT {
+            self.trace.pop_front();
+        }
+        self.trace.push_back(line);
+    }
+
+    /// 追蹤記錄佔用的位元組數
+    pub fn trace_size(&self) -> usize {
+        self.trace.iter().map(|l| l.len()).sum()
+    }
+
+    /// 目前保留的追蹤記錄（以換行分隔，不清空）
+    pub fn trace_log(&self) -> String {
+        let lines: Vec<&str> = self.trace.iter().map(String::as_str).collect();
+        lines.join("\n")
+    }
+
+    /// 取出所有追蹤記錄（以換行分隔）並清空
+    pub fn take_trace(&mut self) -> String {
+        let lines: Vec<String> = self.trace.drain(..).collect();
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,219 @@
+// ============================================================
+// 24C02 序列 EEPROM - Bandai FCG（LZ93D50）卡帶的存檔晶片
+// ============================================================
+// 256 位元組、以 I²C 存取。Mapper 每次寫入控制暫存器時傳入 SCL/SDA
+// 兩條線的電位，這裡依電位變化跑協定：
+// - SCL 高電位時 SDA 由高變低為 START，由低變高為 STOP
+// - START 後先收 8 位元晶片位址（1010xxxR），最低位元為 1 是讀取
+// - 寫入：收 8 位元字組位址，之後每收 8 位元寫入一個位元組並遞增位址
+// - 讀取：從目前位址逐位元送出，主控端回 ACK（SDA 低）就繼續下一個位元組
+// - 位元在 SCL 上升緣取樣，ACK 在下降緣切換
+//
+// 不模擬寫入週期的延遲與 16 位元組分頁折返（遊戲都等到寫完才讀）。
+//
+// 參考：
+// - https://www.nesdev.org/wiki/Bandai_FCG_board
+// - Xicor X24C02 規格書
+// ============================================================
+
+use crate::savestate::{impl_state_fields, StateField, StateReader};
+
+/// EEPROM 容量（位元組）
+pub const EEPROM_SIZE: usize = 256;
+
+/// I²C 協定目前的階段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// 等待 START
+    Idle,
+    /// 接收晶片位址
+    ChipAddress,
+    /// 接收字組位址
+    WordAddress,
+    /// 接收要寫入的資料
+    Write,
+    /// 送出資料
+    Read,
+    /// 送出 ACK
+    SendAck,
+    /// 等待主控端的 ACK
+    WaitAck,
+}
+
+impl StateField for Mode {
+    fn save(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> Option<()> {
+        *self = match r.array::<1>()?[0] {
+            0 => Mode::Idle,
+            1 => Mode::ChipAddress,
+            2 => Mode::WordAddress,
+            3 => Mode::Write,
+            4 => Mode::Read,
+            5 => Mode::SendAck,
+            6 => Mode::WaitAck,
+            _ => return None,
+        };
+        Some(())
+    }
+}
+
+/// 24C02 序列 EEPROM
+#[derive(Debug, Clone)]
+pub struct Eeprom24C02 {
+    /// 儲存內容
+    pub data: Vec<u8>,
+    /// 上次取出後內容是否有變更（觸發存檔事件）
+    pub dirty: bool,
+    mode: Mode,
+    /// ACK 之後要進入的階段
+    next_mode: Mode,
+    /// 目前位元組已收送的位元數
+    bit: u8,
+    chip_address: u8,
+    address: u8,
+    /// 正在收送的位元組
+    shift: u8,
+    /// SDA 輸出（1 = 放開，由上拉電阻拉高）
+    output: u8,
+    prev_scl: u8,
+    prev_sda: u8,
+}
+
+impl_state_fields!(Eeprom24C02 {
+    data, mode, next_mode, bit, chip_address, address, shift, output, prev_scl, prev_sda,
+});
+
+impl Default for Eeprom24C02 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Eeprom24C02 {
+    /// 建立內容全為 $FF（出廠狀態）的 EEPROM
+    pub fn new() -> Self {
+        Eeprom24C02 {
+            data: vec![0xFF; EEPROM_SIZE],
+            dirty: false,
+            mode: Mode::Idle,
+            next_mode: Mode::Idle,
+            bit: 0,
+            chip_address: 0,
+            address: 0,
+            shift: 0,
+            output: 1,
+            prev_scl: 0,
+            prev_sda: 0,
+        }
+    }
+
+    /// 重置協定狀態（內容不變）
+    pub fn reset(&mut self) {
+        self.mode = Mode::Idle;
+        self.next_mode = Mode::Idle;
+        self.bit = 0;
+        self.output = 1;
+    }
+
+    /// 目前 SDA 線上的輸出位元
+    pub fn output(&self) -> u8 {
+        self.output
+    }
+
+    /// 設定 SCL 與 SDA 的電位（0 或 1）
+    pub fn write(&mut self, scl: u8, sda: u8) {
+        if self.prev_scl == 1 && scl == 1 && sda != self.prev_sda {
+            if sda == 0 {
+                // START
+                self.mode = Mode::ChipAddress;
+                self.bit = 0;
+            } else {
+                // STOP
+                self.mode = Mode::Idle;
+            }
+            self.output = 1;
+        } else if scl > self.prev_scl {
+            self.clock_rise(sda);
+        } else if scl < self.prev_scl {
+            self.clock_fall();
+        }
+        self.prev_scl = scl;
+        self.prev_sda = sda;
+    }
+
+    /// SCL 上升緣：取樣或送出一個位元
+    fn clock_rise(&mut self, sda: u8) {
+        match self.mode {
+            Mode::ChipAddress | Mode::WordAddress | Mode::Write if self.bit < 8 => {
+                self.shift = self.shift << 1 | sda;
+                self.bit += 1;
+            }
+            Mode::Read if self.bit < 8 => {
+                self.output = self.shift >> (7 - self.bit) & 1;
+                self.bit += 1;
+            }
+            Mode::SendAck => self.output = 0,
+            Mode::WaitAck if sda == 0 => {
+                self.next_mode = Mode::Read;
+                self.shift = self.data[self.address as usize];
+            }
+            // 主控端沒有回 ACK：讀取結束，等待 STOP
+            Mode::WaitAck => self.next_mode = Mode::Idle,
+            _ => {}
+        }
+    }
+
+    /// SCL 下降緣：一個位元組收送完後切換階段
+    fn clock_fall(&mut self) {
+        match self.mode {
+            Mode::ChipAddress if self.bit == 8 => {
+                self.chip_address = self.shift;
+                self.bit = 0;
+                if self.chip_address & 0xF0 != 0xA0 {
+                    // 不是這顆晶片
+                    self.mode = Mode::Idle;
+                    return;
+                }
+                self.mode = Mode::SendAck;
+                if self.chip_address & 0x01 != 0 {
+                    // 從目前位址讀取
+                    self.next_mode = Mode::Read;
+                    self.shift = self.data[self.address as usize];
+                } else {
+                    self.next_mode = Mode::WordAddress;
+                }
+            }
+            Mode::WordAddress if self.bit == 8 => {
+                self.address = self.shift;
+                self.bit = 0;
+                self.mode = Mode::SendAck;
+                self.next_mode = Mode::Write;
+            }
+            Mode::Write if self.bit == 8 => {
+                let index = self.address as usize;
+                if self.data[index] != self.shift {
+                    self.data[index] = self.shift;
+                    self.dirty = true;
+                }
+                self.address = self.address.wrapping_add(1);
+                self.bit = 0;
+                self.mode = Mode::SendAck;
+                self.next_mode = Mode::Write;
+            }
+            Mode::Read if self.bit == 8 => {
+                self.address = self.address.wrapping_add(1);
+                self.mode = Mode::WaitAck;
+                self.output = 1;
+            }
+            Mode::SendAck | Mode::WaitAck => {
+                self.mode = self.next_mode;
+                self.bit = 0;
+                self.output = 1;
+            }
+            _ => {}
+        }
+    }
+}
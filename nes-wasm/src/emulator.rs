@@ -9,20 +9,86 @@
 //
 // NES 時序關係：
 // - 主時鐘 = PPU 時鐘
-// - CPU 時鐘 = 主時鐘 / 3
+// - CPU 時鐘 = 主時鐘 / 3（PAL 為主時鐘 / 3.2，每 16 個 PPU 週期執行 5 個 CPU 週期）
 // - APU 時鐘 = CPU 時鐘
 //
-// 每一幀 = 262 條掃描線 × 341 個 PPU 週期 = 89342 個 PPU 週期
+// 每一幀 = 262 條掃描線 × 341 個 PPU 週期 = 89342 個 PPU 週期（PAL 為 312 條）
+// Dendy 相容機：312 條掃描線但 CPU 仍為主時鐘 / 3，VBlank 從第 291 條開始
+//
+// 參考：https://www.nesdev.org/wiki/Cycle_reference_chart
 // ============================================================
 
-use crate::cpu::Cpu;
+use std::collections::BTreeMap;
+
+use crate::cpu::{Cpu, CpuBus, InterruptPoll};
 use crate::ppu::Ppu;
 use crate::apu::Apu;
-use crate::bus::Bus;
+use crate::bus::{Bus, Devices};
 use crate::cartridge::Cartridge;
-use crate::controller::Controller;
+use crate::mappers::MapperTrait;
+use crate::controller::{Controller, InputDevice, FOUR_SCORE_SIGNATURES};
+use crate::keyboard::{DataRecorder, FamilyKeyboard};
+use crate::config::{EmulatorConfig, Region, TurboAudio};
+use crate::savestate::{self, SaveSlots, StateField, StateReader};
+use crate::rewind::RewindBuffer;
+use crate::movie::{Movie, MovieMode};
+use crate::benchmark::{BenchmarkResult, ClockProbe, NoProbe, Subsystem, TimingProbe};
+#[cfg(feature = "profiling")]
+use crate::benchmark::{FrameProfile, Profiler};
+use crate::debugger::{self, Debugger, StopReason};
+use crate::cheats::CheatEngine;
+use crate::fds::{DiskDrive, FdsImage, BIOS_SIZE};
+use crate::nsf::{NsfFile, NsfPlayer, NSF_RETURN_ADDR};
+use crate::vs::{VsPpu, VsSystem};
+
+/// 存檔格式版本
+/// - 1：CPU 暫存器、RAM、PPU、PRG RAM
+/// - 2：加入匯流排 DMA、控制器讀取鎖存、主時鐘
+/// - 3：加入幀計數（影片重錄時用來定位截斷點）
+/// - 4：PRG RAM 以長度開頭（不再固定 8KB）
+/// - 5：加入 CPU/PPU/APU 內部狀態、Mapper 暫存器與 CHR RAM
+/// - 6：v5 區段末尾加入金手指清單
+/// - 7：v5 區段末尾加入控制器完整的移位暫存器（Four Score 為 24 位元）
+/// - 8：v5 區段末尾加入 DMC DMA 的暫停進度與 CPU 最後讀取的位址
+/// - 9：v5 區段末尾加入資料匯流排的開路值
+/// - 10：v5 區段末尾加入 PPU 開路鎖存器與各位元的衰減計時
+/// - 11：v5 區段末尾加入中斷輪詢狀態
+/// - 12：v5 區段末尾加入降頻取樣器狀態
+/// - 13：v12 的降頻取樣器（24 位元組）換成頻寬受限階躍合成器的狀態；
+///   讀 v12 存檔時略過那 24 位元組
+/// - 14：v5 區段末尾加入等待生效的 $4017 寫入與長度計數器寫入；較舊存檔的
+///   幀計數器值以一半的週期數計算，讀取時加倍
+/// - 15：v5 區段末尾加入三角波減少爆音模式的輸出準位
+/// - 16：v5 區段末尾加入 DMC 開始讀取前的延遲；較舊存檔的 DMC 定時器以 APU
+///   週期計數，讀取時換算成 CPU 週期
+/// - 17：v5 區段末尾加入 Mapper EEPROM 的內容與協定狀態（只有帶 EEPROM 的卡帶）
+const STATE_VERSION: u8 = 17;
+
+/// 快轉倍率上限
+pub const MAX_SPEED: u32 = 16;
+
+/// crop_overscan 開啟時上下各裁掉的掃描線數
+const OVERSCAN_LINES: u32 = 8;
 
 /// NES 模擬器
+///
+/// 整合 CPU、PPU、APU 與卡帶的完整主機。典型的使用流程是
+/// `new` → `load_rom` → 每幀設定輸入後呼叫 `frame`，再取出畫面與音訊：
+///
+/// ```
+/// use nes_wasm::Emulator;
+///
+/// # let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+/// # let mut prg = vec![0xEA; 0x4000];
+/// # prg[..3].copy_from_slice(&[0x4C, 0x00, 0x80]);
+/// # prg[0x3FFC..0x3FFE].copy_from_slice(&[0x00, 0x80]);
+/// # rom.extend_from_slice(&prg);
+/// # rom.extend_from_slice(&[0; 0x2000]);
+/// let mut emu = Emulator::new();
+/// assert!(emu.load_rom(&rom));
+/// emu.run_frames(3);
+/// assert_eq!(emu.frame_count(), 3);
+/// ```
 pub struct Emulator {
     /// 6502 CPU
     pub cpu: Cpu,
@@ -38,13 +104,179 @@ pub struct Emulator {
     pub ctrl1: Controller,
     /// 控制器 2
     pub ctrl2: Controller,
+    /// 擴充埠的 Family BASIC 鍵盤
+    pub keyboard: FamilyKeyboard,
+    /// 資料記錄器（錄音機）
+    pub data_recorder: DataRecorder,
 
     /// 系統主時鐘計數器
     system_clock: u64,
+    /// 目前採用的主機地區（設定指定或 ROM 標頭判斷）
+    region: Region,
+    /// 每個 CPU 週期的 PPU 週期數（分子, 分母），由 region 決定
+    cpu_divider: (u64, u64),
+    /// 已執行的幀數
+    frame_count: u64,
+    /// 目前這一幀已經開始（輸入與 NSF PLAY 已套用），幀結束時清除
+    frame_started: bool,
+
+    /// 目前套用中的設定
+    config: EmulatorConfig,
+    /// 隨機開機相位的亂數狀態（設定種子時重設）
+    alignment_rng: u64,
+
+    /// 快速存檔槽（保存在 WASM 記憶體中）
+    slots: SaveSlots,
+    /// 存檔用的暫存緩衝區（hex 匯出入、存檔槽、複製到 JS 時重複使用）
+    state_scratch: Vec<u8>,
+    /// 讀檔前的狀態備份（讀檔失敗時還原），每次讀檔重複使用
+    state_backup: Vec<u8>,
+    /// 倒帶記錄
+    rewind: RewindBuffer,
+    /// 快轉倍率（frame_at_speed 每次執行的幀數）
+    speed: u32,
+
+    /// 每幀效能計數
+    #[cfg(feature = "profiling")]
+    profiler: Profiler,
+
+    // 電池 RAM 儲存事件（寫入停止一段時間後才觸發，避免每幀都要儲存）
+    /// 有尚未儲存的變更
+    sram_pending: bool,
+    /// 距離最後一次寫入經過的幀數
+    sram_quiet_frames: u32,
+    /// 已達靜止時間，等待前端取走
+    sram_flush_ready: bool,
+
+    /// 輸入影片（TAS）
+    movie: Option<Movie>,
+    /// 影片模式
+    movie_mode: MovieMode,
+
+    /// 預先排程的輸入腳本：幀數 → [(控制器編號, 按鈕狀態)]
+    input_queue: BTreeMap<u64, Vec<(u8, u8)>>,
+
+    /// 除錯器（中斷點、指令追蹤）
+    pub debugger: Debugger,
+    /// 已執行的指令數（單步執行用來判斷指令邊界）
+    instruction_count: u64,
+
+    /// 金手指（CPU 讀取替換）
+    pub cheats: CheatEngine,
+
+    /// FDS BIOS（載入後保留，之後載入的磁碟都用它開機）
+    fds_bios: Option<Vec<u8>>,
+
+    /// NSF 播放狀態（載入 NSF 檔案時才有）
+    nsf: Option<NsfPlayer>,
+
+    /// VS. System 的 DIP 開關、投幣與保護晶片（載入 VS. 卡帶時才有；不存入存檔）
+    vs: Option<VsSystem>,
+}
+
+/// 模擬器狀態快照（HUD 用，一次取得常用數值）
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmulatorStatus {
+    /// CPU 暫存器
+    pub pc: u16,
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub sp: u8,
+    /// 狀態旗標
+    pub p: u8,
+    /// 目前掃描線（-1 為預渲染線）
+    pub scanline: i16,
+    /// 掃描線內的點（PPU 週期）
+    pub dot: u16,
+    /// 已執行的幀數
+    pub frame: u64,
+    /// CPU 週期數
+    pub cpu_cycles: u64,
+    /// 已執行的指令數
+    pub instructions: u64,
+    /// $8000/$A000/$C000/$E000 各 8KB 視窗對應的 PRG bank
+    pub prg_banks: [u32; 4],
+    /// $0000-$1FFF 各 1KB 視窗對應的 CHR bank
+    pub chr_banks: [u32; 8],
+    /// 緩衝區中尚未取走的音頻取樣數
+    pub audio_samples: usize,
+    /// 是否停在中斷點
+    pub paused: bool,
+}
+
+impl EmulatorStatus {
+    /// 輸出為 JSON 字串
+    pub fn to_json(&self) -> String {
+        let list = |banks: &[u32]| {
+            banks.iter().map(|b| b.to_string()).collect::<Vec<_>>().join(",")
+        };
+        format!(
+            "{{\"pc\":{},\"a\":{},\"x\":{},\"y\":{},\"sp\":{},\"p\":{},\"scanline\":{},\"dot\":{},\
+             \"frame\":{},\"cpuCycles\":{},\"instructions\":{},\"prgBanks\":[{}],\"chrBanks\":[{}],\
+             \"audioSamples\":{},\"paused\":{}}}",
+            self.pc, self.a, self.x, self.y, self.sp, self.p, self.scanline, self.dot,
+            self.frame, self.cpu_cycles, self.instructions,
+            list(&self.prg_banks), list(&self.chr_banks), self.audio_samples, self.paused,
+        )
+    }
+}
+
+/// 記憶體用量（位元組），供同一頁面嵌入多個模擬器時管理記憶體
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// 畫面緩衝區
+    pub frame_buffer: usize,
+    /// 音頻緩衝區
+    pub audio_buffer: usize,
+    /// PRG ROM
+    pub prg_rom: usize,
+    /// CHR ROM/RAM
+    pub chr: usize,
+    /// 內建 RAM 與 PRG RAM
+    pub ram: usize,
+    /// 快速存檔槽（壓縮後）與存檔暫存緩衝區
+    pub save_slots: usize,
+    /// 倒帶記錄
+    pub rewind: usize,
+    /// 輸入影片（起始存檔與輸入）
+    pub movie: usize,
+    /// 指令追蹤記錄
+    pub trace: usize,
+}
+
+impl MemoryUsage {
+    /// 總計
+    pub fn total(&self) -> usize {
+        self.frame_buffer + self.audio_buffer + self.prg_rom + self.chr
+            + self.ram + self.save_slots + self.rewind + self.movie + self.trace
+    }
+
+    /// 輸出為 JSON 字串
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"frameBuffer\":{},\"audioBuffer\":{},\"prgRom\":{},\"chr\":{},\"ram\":{},\
+             \"saveSlots\":{},\"rewind\":{},\"movie\":{},\"trace\":{},\"total\":{}}}",
+            self.frame_buffer, self.audio_buffer, self.prg_rom, self.chr, self.ram,
+            self.save_slots, self.rewind, self.movie, self.trace, self.total(),
+        )
+    }
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// CPU 看到的整台主機位址空間
+impl CpuBus for Emulator {
+    fn read(&mut self, addr: u16) -> u8 { self.bus_read(addr) }
+    fn write(&mut self, addr: u16, data: u8) { self.bus_write(addr, data) }
 }
 
 impl Emulator {
-    /// 建立新的模擬器實例
+    /// 建立新的模擬器實例（尚未載入卡帶，需先呼叫 `load_rom`）
     pub fn new() -> Self {
         Emulator {
             cpu: Cpu::new(),
@@ -54,49 +286,280 @@ impl Emulator {
             cartridge: Cartridge::new(),
             ctrl1: Controller::new(),
             ctrl2: Controller::new(),
+            keyboard: FamilyKeyboard::new(),
+            data_recorder: DataRecorder::new(),
             system_clock: 0,
+            region: Region::Ntsc,
+            cpu_divider: Region::Ntsc.ppu_dots_per_cpu_cycle(),
+            frame_count: 0,
+            frame_started: false,
+            config: EmulatorConfig::default(),
+            alignment_rng: 0,
+            slots: SaveSlots::new(),
+            state_scratch: Vec::new(),
+            state_backup: Vec::new(),
+            speed: 1,
+            rewind: {
+                let config = EmulatorConfig::default();
+                RewindBuffer::with_settings(config.rewind_depth as usize, config.rewind_interval)
+            },
+            #[cfg(feature = "profiling")]
+            profiler: Profiler::default(),
+            sram_pending: false,
+            sram_quiet_frames: 0,
+            sram_flush_ready: false,
+            movie: None,
+            movie_mode: MovieMode::Inactive,
+            input_queue: BTreeMap::new(),
+            debugger: Debugger::new(),
+            instruction_count: 0,
+            cheats: CheatEngine::new(),
+            fds_bios: None,
+            nsf: None,
+            vs: None,
         }
     }
 
     /// 載入 ROM
+    /// 也接受 NSF 音樂檔（載入後自動播放起始曲目）與 FDS 磁碟映像檔（需先載入 BIOS）
+    ///
+    /// 格式錯誤或 Mapper 不支援時回傳 false，模擬器維持原本的狀態：
+    ///
+    /// ```
+    /// let mut emu = nes_wasm::Emulator::new();
+    /// assert!(!emu.load_rom(b"not a rom"));
+    /// ```
     pub fn load_rom(&mut self, data: &[u8]) -> bool {
+        if NsfFile::is_nsf(data) {
+            return self.load_nsf(data);
+        }
+        if FdsImage::is_fds(data) {
+            return self.load_disk_image(data);
+        }
         let success = self.cartridge.load_rom(data);
         if success {
-            // 將卡帶的 CHR 資料同步到 PPU
-            let chr_data = self.cartridge.chr_data.clone();
-            let chr_ram = self.cartridge.chr_ram;
-            self.ppu.set_chr_data(chr_data, chr_ram);
-            // 同步 Mapper 的 CHR bank 映射和鏡像模式
-            self.sync_mapper_to_ppu();
+            self.nsf = None;
+            self.end_session();
+            let header = &self.cartridge.header;
+            self.vs = header.vs_ppu.map(|_| VsSystem::new(header.vs_protection));
+            self.ppu.set_vs_ppu(header.vs_ppu);
+            // 時序與調色盤跟隨卡帶標示的地區（設定未強制指定時）
+            self.apply_region();
+            self.sram_pending = false;
+            self.sram_flush_ready = false;
             self.reset();
         }
         success
     }
 
+    /// 載入 NSF 音樂檔並播放起始曲目
+    fn load_nsf(&mut self, data: &[u8]) -> bool {
+        let Some(nsf) = NsfFile::parse(data) else { return false };
+        self.end_session();
+        self.cartridge.load_nsf(&nsf);
+        self.apply_region();
+        self.sram_pending = false;
+        self.sram_flush_ready = false;
+        self.reset();
+        let start = nsf.header.starting_song - 1;
+        self.nsf = Some(NsfPlayer::new(nsf.header));
+        self.nsf_play_track(start);
+        true
+    }
+
+    /// 卸下卡帶並釋放 ROM 相關的記憶體（PRG/CHR、存檔槽、影片）
+    ///
+    /// 設定與輸入裝置保留，之後可以直接 load_rom 載入下一個遊戲。
+    /// 卸下後執行幀不會有畫面：CPU 從開放匯流排讀到 0，停在 BRK 迴圈。
+    pub fn unload_rom(&mut self) {
+        self.end_session();
+        self.nsf = None;
+        self.cartridge = Cartridge::new();
+        self.cartridge.prg_ram = Vec::new();
+        self.state_scratch = Vec::new();
+        self.state_backup = Vec::new();
+        self.sram_pending = false;
+        self.sram_flush_ready = false;
+        self.reset();
+    }
+
+    /// 換卡時清除只屬於上一個遊戲的狀態
+    ///
+    /// 設定（取樣率、濾波、精靈限制、過掃描）、輸入裝置與除錯器中斷點保留；
+    /// 金手指、存檔槽、輸入影片、輸入腳本與錄音帶屬於上一個遊戲，一併清除。
+    /// VRAM/OAM/調色盤回到開機狀態，新遊戲的第一幀不會看到上一個遊戲的殘留。
+    fn end_session(&mut self) {
+        self.cheats.clear();
+        self.slots = SaveSlots::new();
+        self.rewind.clear();
+        self.movie = None;
+        self.movie_mode = MovieMode::Inactive;
+        self.input_queue.clear();
+        self.data_recorder = DataRecorder::new();
+        self.vs = None;
+        self.ppu.set_vs_ppu(None);
+        self.frame_count = 0;
+        self.frame_started = false;
+        self.instruction_count = 0;
+        self.ppu.nametable = [0; 2048];
+        self.ppu.palette = [0; 32];
+        self.ppu.oam = [0; 256];
+        self.ppu.frame_buffer.fill(0);
+        self.apu.consume_samples();
+    }
+
+    /// 載入 FDS BIOS（8KB 的 disksys.rom），大小不符回傳 false
+    ///
+    /// BIOS 在換卡後保留，之後載入的磁碟映像檔都用它開機。
+    pub fn load_fds_bios(&mut self, data: &[u8]) -> bool {
+        if data.len() != BIOS_SIZE {
+            return false;
+        }
+        self.fds_bios = Some(data.to_vec());
+        true
+    }
+
+    /// 是否已載入 FDS BIOS
+    pub fn has_fds_bios(&self) -> bool {
+        self.fds_bios.is_some()
+    }
+
+    /// 載入 FDS 磁碟映像檔並插入第一面開機
+    /// 尚未載入 BIOS 或格式錯誤時回傳 false，模擬器維持原本的狀態
+    pub fn load_disk_image(&mut self, data: &[u8]) -> bool {
+        let Some(image) = FdsImage::parse(data).filter(|_| self.fds_bios.is_some()) else { return false };
+        self.nsf = None;
+        self.end_session();
+        self.cartridge.load_fds(self.fds_bios.as_deref().unwrap_or_default(), image);
+        self.apply_region();
+        self.sram_pending = false;
+        self.sram_flush_ready = false;
+        self.reset();
+        true
+    }
+
+    /// FDS 磁碟機（沒有載入磁碟時為 None）
+    pub fn disk_drive(&self) -> Option<&DiskDrive> {
+        self.cartridge.disk_drive()
+    }
+
+    /// FDS 磁碟機（可插入、退出磁碟）
+    pub fn disk_drive_mut(&mut self) -> Option<&mut DiskDrive> {
+        self.cartridge.disk_drive_mut()
+    }
+
+    /// 換面：先退出磁碟，約半秒後插入指定的面；索引超出範圍回傳 false
+    pub fn set_disk_side(&mut self, side: usize) -> bool {
+        self.disk_drive_mut().is_some_and(|drive| drive.swap_to(side))
+    }
+
+    /// 是否為 VS. System 卡帶
+    pub fn is_vs_system(&self) -> bool {
+        self.vs.is_some()
+    }
+
+    /// VS. System 的機台輸入（不是 VS. 卡帶時為 None）
+    pub fn vs_system(&self) -> Option<&VsSystem> {
+        self.vs.as_ref()
+    }
+
+    /// 投幣（slot 0 或 1）；不是 VS. 卡帶時回傳 false
+    pub fn insert_coin(&mut self, slot: usize) -> bool {
+        self.vs.as_mut().map(|vs| vs.insert_coin(slot)).is_some()
+    }
+
+    /// 設定 VS. System 的 DIP 開關（位元 0 = DIP 1）
+    pub fn set_dip_switches(&mut self, value: u8) {
+        if let Some(vs) = &mut self.vs {
+            vs.set_dip_switches(value);
+        }
+    }
+
+    /// 按住或放開 VS. System 的服務鍵
+    pub fn set_vs_service(&mut self, pressed: bool) {
+        if let Some(vs) = &mut self.vs {
+            vs.set_service(pressed);
+        }
+    }
+
+    /// 指定 VS. System 的 PPU 型號（iNES 1.0 標頭無法標示時使用）；
+    /// 不是 VS. 卡帶時回傳 false
+    pub fn set_vs_ppu(&mut self, vs_ppu: VsPpu) -> bool {
+        if self.vs.is_none() {
+            return false;
+        }
+        self.cartridge.header.vs_ppu = Some(vs_ppu);
+        self.ppu.set_vs_ppu(Some(vs_ppu));
+        true
+    }
+
     /// 重置模擬器
     pub fn reset(&mut self) {
         self.cartridge.reset();
         self.ppu.reset();
         self.apu.reset();
         self.bus.reset();
-        self.system_clock = 0;
-
-        // 同步 Mapper 狀態到 PPU（鏡像模式和 CHR bank 映射）
-        self.sync_mapper_to_ppu();
+        self.system_clock = self.next_power_on_phase();
 
         // CPU 重置 - 需要從重置向量讀取 PC
-        let lo = self.bus_read(0xFFFC) as u16;
-        let hi = self.bus_read(0xFFFD) as u16;
+        self.with_cpu(|cpu, bus| cpu.reset(bus));
+    }
 
-        self.cpu.pc = (hi << 8) | lo;
-        self.cpu.sp = 0xFD;
-        self.cpu.status = 0x24; // IRQ 禁止旗標設定
-        self.cpu.a = 0;
-        self.cpu.x = 0;
-        self.cpu.y = 0;
-        self.cpu.cycles = 0;
-        self.cpu.nmi_pending = false;
-        self.cpu.irq_pending = false;
+    /// 取得這次開機的主時鐘起始值
+    ///
+    /// 起始值決定第一個 CPU 週期前 PPU 先走幾個點。實機的 CPU/PPU 分頻器
+    /// 開機時狀態不定，相位不同會讓 $2002 輪詢、NMI 與 DMA 的時間點錯開一個點。
+    ///
+    /// 主時鐘 0 一定是 CPU 週期，下一個在第 ceil(點數/週期數) 個主時鐘
+    /// （NTSC/Dendy 為 3、PAL 為 4），兩者之間是 CPU 週期間隔最長的一段；
+    /// 從下一個 CPU 週期往前退 d 個主時鐘開機，PPU 就會先走 d 個點。
+    /// 參考：https://www.nesdev.org/wiki/PPU_frame_timing#CPU-PPU_Clock_Alignment
+    fn next_power_on_phase(&mut self) -> u64 {
+        let (dots, cycles) = self.region.ppu_dots_per_cpu_cycle();
+        let phases = dots.div_ceil(cycles);
+        let lead = match self.config.power_on_alignment {
+            Some(lead) => lead as u64 % phases,
+            None => {
+                // SplitMix64：任何種子（含 0）都能產生均勻序列
+                self.alignment_rng = self.alignment_rng.wrapping_add(0x9E37_79B9_7F4A_7C15);
+                let mut z = self.alignment_rng;
+                z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                (z ^ (z >> 31)) % phases
+            }
+        };
+        (phases - lead) % phases
+    }
+
+    /// 軟體重置（按下主機的 Reset 鍵）
+    ///
+    /// Reset 線只接到 CPU/PPU/APU，卡帶收不到訊號：
+    /// - CPU：A/X/Y 保留，SP 減 3，設定 I 旗標，從重置向量重新開始
+    /// - PPU/APU：暫存器清除（VRAM、OAM、調色盤保留）
+    /// - 內建 RAM、PRG RAM、Mapper 暫存器全部保留
+    ///
+    /// 參考：https://www.nesdev.org/wiki/CPU_power_up_state#After_reset
+    pub fn soft_reset(&mut self) {
+        self.ppu.reset();
+        self.apu.reset();
+        self.bus.reset_dma();
+
+        self.with_cpu(|cpu, bus| cpu.soft_reset(bus));
+    }
+
+    /// 重新開機（關閉電源再開啟）
+    ///
+    /// 除了 reset 的內容，另外清除 VRAM、OAM、調色盤，
+    /// 以及沒有電池備份的 PRG RAM；有電池的 PRG RAM 依 keep_battery_ram 設定保留。
+    /// 參考：https://www.nesdev.org/wiki/CPU_power_up_state
+    pub fn power_cycle(&mut self) {
+        self.ppu.nametable = [0; 2048];
+        self.ppu.palette = [0; 32];
+        self.ppu.oam = [0; 256];
+        if !(self.cartridge.has_battery_ram() && self.config.keep_battery_ram) {
+            self.cartridge.prg_ram.fill(0);
+        }
+        self.reset();
     }
 
     /// 執行一個主時鐘週期
@@ -106,797 +569,1121 @@ impl Emulator {
     /// - CPU 每 3 個主時鐘執行一次
     /// - APU 跟 CPU 同步
     fn clock(&mut self) {
+        self.clock_with(&mut NoProbe);
+    }
+
+    /// 執行一個主時鐘週期，並在各子系統之間呼叫探針（基準測試用）
+    #[inline(always)]
+    fn clock_with<P: ClockProbe>(&mut self, probe: &mut P) {
         // === PPU 時鐘（每個主時鐘） ===
-        self.ppu.clock();
+        probe.enter(Subsystem::Ppu);
+        self.ppu.clock(&mut self.cartridge);
 
         // === CPU 時鐘（每 3 個主時鐘）===
         // 重要：CPU 在 NMI/IRQ 檢查之前執行，與 TypeScript 版本一致
-        if self.system_clock % 3 == 0 {
-            // 檢查 DMA 傳輸
-            if self.bus.dma_transfer {
-                let odd = self.system_clock % 2 == 1;
-                self.bus.do_dma_cycle(
-                    odd,
-                    &mut self.ppu, &mut self.apu, &self.cartridge,
-                    &mut self.ctrl1, &mut self.ctrl2,
-                );
+        if self.cpu_tick_due() {
+            probe.enter(Subsystem::Cpu);
+            // 檢查 DMA 傳輸（OAM DMA 與 DMC 取樣讀取由匯流排仲裁）
+            let odd = self.cpu_cycle_count() % 2 == 1;
+            let dmc_request = self.apu.dmc_read_request;
+            if self.bus.dma_active(dmc_request.is_some(), odd) {
+                #[cfg(feature = "profiling")]
+                { self.profiler.current.dma_cycles += 1; }
+                // DMC 暫停 CPU 時，被打斷的讀取在 DMA 期間重複進行；讀的是
+                // 控制器埠就會多移一位（DPCM 遊戲讀兩次比對的原因）
+                if self.bus.dmc_halts_cpu(dmc_request.is_some(), odd)
+                    && self.cpu.cycles == 1
+                    && matches!(self.bus.last_read_addr, 0x4016 | 0x4017)
+                {
+                    self.bus_read(self.bus.last_read_addr);
+                }
+                let (bus, mut dev) = self.split_bus();
+                let fetch = bus.do_dma_cycle(odd, dmc_request, &mut dev);
+                // DMC 讀取與 CPU 讀取走同一條匯流排（Mapper 的 bank 映射、
+                // PRG RAM 與金手指都會生效）
+                if let Some(addr) = fetch {
+                    let data = self.bus_read(addr);
+                    self.apu.dmc_provide_sample(data);
+                }
             } else {
                 // 執行 CPU
                 self.cpu_clock();
             }
 
-            // APU 時鐘（與 CPU 同步）
-            self.apu.clock();
-
-            // 處理 DMC 讀取請求
-            if let Some(addr) = self.apu.dmc_read_request.take() {
-                let data = self.bus_read(addr);
-                self.apu.dmc_provide_sample(data);
-            }
-
-            // APU IRQ → CPU
-            if self.apu.check_irq() {
-                self.cpu.irq_pending = true;
+            // APU 時鐘（與 CPU 同步），卡帶擴充音效一併混入
+            probe.enter(Subsystem::Apu);
+            if let Some(source) = self.cartridge.mapper.expansion_audio() {
+                self.apu.clock_expansion(source);
             }
+            self.apu.clock();
 
             // Mapper CPU 週期計時（用於 Bandai FCG 等）
+            probe.enter(Subsystem::Mapper);
             self.cartridge.cpu_clock();
         }
+        probe.enter(Subsystem::Other);
 
         // === 檢查 NMI（PPU VBlank 觸發）===
         if self.ppu.check_nmi() {
             self.cpu.nmi_pending = true;
         }
 
-        // === 檢查 Scanline IRQ（用於 MMC3 等 Mapper）===
+        // === 檢查 Scanline IRQ 與 A12 上升緣（用於 MMC3 等 Mapper）===
         if self.ppu.check_scanline_irq() {
+            probe.enter(Subsystem::Mapper);
             self.cartridge.scanline();
-            // 同步 Mapper 狀態到 PPU（scanline 可能改變 bank 映射）
-            self.sync_mapper_to_ppu();
+            probe.enter(Subsystem::Other);
         }
-
-        // === Mapper IRQ → CPU ===
-        if self.cartridge.check_irq() {
-            self.cpu.irq_pending = true;
+        if self.ppu.check_a12_rise() {
+            probe.enter(Subsystem::Mapper);
+            self.cartridge.a12_clock();
+            probe.enter(Subsystem::Other);
         }
 
+        // === IRQ 線（APU 與 Mapper 的電位，來源確認後就放開）===
+        self.cpu.irq_pending = self.apu.check_irq() || self.cartridge.check_irq();
+
         self.system_clock += 1;
     }
 
+    /// 目前的主時鐘是否輪到 CPU 執行（NTSC 每 3 個週期一次，PAL 每 16 個週期 5 次）
+    #[inline(always)]
+    fn cpu_tick_due(&self) -> bool {
+        let (dots, cycles) = self.cpu_divider;
+        (self.system_clock * cycles) % dots < cycles
+    }
+
+    /// 開機以來的 CPU 週期數（由主時鐘換算）
+    fn cpu_cycle_count(&self) -> u64 {
+        let (dots, cycles) = self.cpu_divider;
+        self.system_clock * cycles / dots
+    }
+
     /// 執行一個 CPU 時鐘週期
     fn cpu_clock(&mut self) {
-        if self.cpu.cycles > 0 {
-            self.cpu.cycles -= 1;
+        if self.cpu.tick() {
             return;
         }
 
-        // 處理 NMI
-        if self.cpu.nmi_pending {
-            self.cpu.nmi_pending = false;
-            self.do_nmi();
+        // 處理上一條指令取樣到的中斷
+        if self.with_cpu(|cpu, bus| cpu.service_interrupts(bus)) {
             return;
         }
 
-        // 處理 IRQ
-        if self.cpu.irq_pending && (self.cpu.status & 0x04 == 0) {
-            self.cpu.irq_pending = false;
-            self.do_irq();
+        // NSF：INIT/PLAY 返回後在此閒置，等下一幀再呼叫 PLAY
+        if self.cpu.pc == NSF_RETURN_ADDR && self.nsf.is_some() {
+            self.cpu.poll_interrupts();
             return;
         }
 
-        // 取指令並執行
-        let opcode = self.bus_read(self.cpu.pc);
-        self.cpu.pc = self.cpu.pc.wrapping_add(1);
-        self.execute_cpu_instruction(opcode);
+        // 除錯器：中斷點命中時停在取指前，追蹤記錄執行前的狀態
+        if self.debugger.is_active() {
+            if self.debugger.should_break(self.cpu.pc) {
+                self.cpu.poll_interrupts();
+                return;
+            }
+            if self.debugger.trace_enabled() {
+                let line = self.trace_line();
+                self.debugger.push_trace(line);
+            }
+        }
+
+        self.bus.dmc_stall = 0;
+        self.with_cpu(|cpu, bus| cpu.step(bus));
+        self.instruction_count += 1;
+    }
+
+    /// 以模擬器本身當作 CPU 的匯流排執行 f
+    ///
+    /// CPU 在 f 執行期間暫時移出（匯流排存取只用到週邊，不會讀取 CPU 暫存器）。
+    fn with_cpu<R>(&mut self, f: impl FnOnce(&mut Cpu, &mut Self) -> R) -> R {
+        let mut cpu = std::mem::take(&mut self.cpu);
+        let result = f(&mut cpu, self);
+        self.cpu = cpu;
+        result
+    }
+
+    /// 分開借出匯流排與它上面的週邊裝置
+    fn split_bus(&mut self) -> (&mut Bus, Devices<'_>) {
+        let dev = Devices {
+            ppu: &mut self.ppu,
+            apu: &mut self.apu,
+            cartridge: &mut self.cartridge,
+            ctrl1: &mut self.ctrl1,
+            ctrl2: &mut self.ctrl2,
+        };
+        (&mut self.bus, dev)
     }
 
     /// 匯流排讀取
     fn bus_read(&mut self, addr: u16) -> u8 {
-        self.bus.cpu_read(
-            addr,
-            &mut self.ppu, &mut self.apu, &self.cartridge,
-            &mut self.ctrl1, &mut self.ctrl2,
-        )
+        self.bus.last_read_addr = addr;
+        // 擴充區（$4020-$5FFF）的 Mapper 暫存器讀取可能有副作用，不經過唯讀的卡帶讀取；
+        // VS. System 的保護晶片也在這一區。$6000-$7FFF 可能接著 EEPROM 而不是 PRG RAM
+        let register = match addr {
+            0x4020..=0x5FFF => self.cartridge.mapper.read_register(addr)
+                .or_else(|| self.vs.as_mut().and_then(|vs| vs.read_protection(addr))),
+            0x6000..=0x7FFF => self.cartridge.mapper.read_register(addr),
+            _ => None,
+        };
+        let mut value = match register {
+            Some(value) => value,
+            None => {
+                let (bus, mut dev) = self.split_bus();
+                bus.cpu_read(addr, &mut dev)
+            }
+        };
+        // 擴充埠裝置與控制器共用 $4016/$4017 的其他位元
+        match addr {
+            0x4016 => value |= self.data_recorder.read(self.cpu_cycle_count()),
+            0x4017 => value |= self.keyboard.read(),
+            _ => {}
+        }
+        if let (Some(vs), 0x4016 | 0x4017) = (&self.vs, addr) {
+            // 機台輸入接在控制器埠原本開路的位元上
+            let driven = if addr == 0x4016 { 0x7C } else { 0xFC };
+            value = (value & !driven) | vs.read_inputs(addr);
+        }
+        if self.debugger.is_active() {
+            self.debugger.check_access(addr, false);
+        }
+        if self.cheats.is_active() {
+            value = self.cheats.apply(addr, value);
+        }
+        // $4015 在 CPU 內部讀取，不會改變外部資料匯流排
+        if addr != 0x4015 {
+            self.bus.open_bus = value;
+        }
+        value
     }
 
     /// 匯流排寫入
     fn bus_write(&mut self, addr: u16, data: u8) {
-        self.bus.cpu_write(
-            addr, data,
-            &mut self.ppu, &mut self.apu, &mut self.cartridge,
-            &mut self.ctrl1, &mut self.ctrl2,
-        );
+        self.bus.open_bus = data;
+        if self.debugger.is_active() {
+            self.debugger.check_access(addr, true);
+        }
+        let (bus, mut dev) = self.split_bus();
+        bus.cpu_write(addr, data, &mut dev);
+        if addr == 0x4016 {
+            self.keyboard.write(data);
+            self.data_recorder.write(data, self.cpu_cycle_count());
+            // VS. System 以 $4016 位元 2 切換 CHR（Mapper 99）
+            if self.vs.is_some() {
+                self.cartridge.cpu_write(addr, data);
+            }
+        }
+    }
+
+    // ============================================================
+    // 公開 API
+    // ============================================================
 
-        // 寫入 Mapper 暫存器空間後同步 Mapper 狀態到 PPU
-        // 大多數 Mapper 的 bank 切換暫存器在 $8000-$FFFF
-        // 部分 Mapper（如 Mapper 16）使用 $6000-$7FFF
-        if addr >= 0x6000 {
-            self.sync_mapper_to_ppu();
+    /// 執行一幀
+    /// 命中中斷點時提前返回，再次呼叫會從中斷處繼續執行到幀結束
+    ///
+    /// 結束後畫面在 `frame_buffer`，音訊取樣以 `take_audio_samples`
+    /// 或 `fill_audio_samples` 取出。
+    pub fn frame(&mut self) {
+        self.begin_frame();
+        #[cfg(not(feature = "profiling"))]
+        let completed = self.run_frame_with(&mut NoProbe);
+        #[cfg(feature = "profiling")]
+        let completed = self.run_profiled_frame();
+        if completed {
+            self.end_frame();
         }
     }
 
-    /// 同步 Mapper 的 CHR bank 映射和鏡像模式到 PPU
-    fn sync_mapper_to_ppu(&mut self) {
-        // 同步鏡像模式
-        let mirror = self.cartridge.mirror_mode();
-        self.ppu.set_mirror_mode(mirror);
+    /// 設定快轉倍率（1 為正常速度，範圍 1 到 MAX_SPEED）
+    pub fn set_speed(&mut self, multiplier: u32) {
+        self.speed = multiplier.clamp(1, MAX_SPEED);
+    }
 
-        // 同步 CHR bank 映射（透過 Mapper 計算每個 1KB bank 的偏移量）
-        let mut offsets = [0u32; 8];
-        for i in 0..8u16 {
-            let addr = i * 0x0400; // 每個 bank 起始地址：$0000, $0400, ..., $1C00
-            if let Some(mapped) = self.cartridge.mapper.ppu_read(addr) {
-                // mapped 是 Mapper 回傳的位元組偏移量
-                // 我們需要計算 bank 的起始偏移（去掉 bank 內的偏移）
-                offsets[i as usize] = mapped; // mapped 已經是 addr 0 在 bank 內的偏移
-            } else {
-                offsets[i as usize] = addr as u32;
+    /// 目前的快轉倍率
+    pub fn speed(&self) -> u32 { self.speed }
+
+    /// 依快轉倍率執行：連續執行 speed 幀，只有最後一幀輸出畫面
+    ///
+    /// 跳過的幀 PPU 照常計時並觸發 NMI 與 Mapper IRQ，只是不寫入幀緩衝區，
+    /// 4-8 倍快轉時省下大部分的像素輸出成本（接著 Zapper 時每幀都輸出）。聲音依 `turbo_audio` 設定
+    /// 靜音，或把這些幀降頻成一幀的取樣數，讓前端的音訊排程不必改變。
+    /// 命中中斷點時停止。
+    pub fn frame_at_speed(&mut self) {
+        let speed = self.speed;
+        if speed == 1 {
+            self.frame();
+            return;
+        }
+        let samples = self.apu.get_available_samples();
+        let resample = self.config.turbo_audio == TurboAudio::Resample;
+        if resample {
+            self.apu.set_sample_rate(self.config.sample_rate / speed as f64);
+        }
+        for n in 1..=speed {
+            self.set_skip_output(n < speed);
+            self.frame();
+            if self.debugger.break_hit().is_some() {
+                break;
             }
         }
-        self.ppu.set_chr_bank_offsets(offsets);
+        self.set_skip_output(false);
+        if resample {
+            self.apu.set_sample_rate(self.config.sample_rate);
+        } else {
+            self.apu.truncate_samples(samples);
+        }
+    }
 
-        // 同步 CHR bank 可寫入遮罩（用於混合 CHR ROM/RAM mapper 如 253）
-        let writable_mask = self.cartridge.mapper.chr_writable_mask();
-        self.ppu.set_chr_writable_mask(writable_mask);
+    /// 執行 n 個 CPU 週期，回傳實際執行的週期數（命中中斷點時較少）
+    ///
+    /// 可以停在幀的任何位置，跨過的幀照 `frame` 的流程收尾（幀數、輸入
+    /// 排程與影片、倒帶、電池 RAM 儲存事件）；之後呼叫 `frame` 會把目前
+    /// 這一幀執行完。效能計數只統計 `frame` 執行的幀。
+    pub fn run_cycles(&mut self, n: u32) -> u64 {
+        let start = self.cpu_cycle_count();
+        self.run_until(|emu| emu.cpu_cycle_count() - start >= n as u64);
+        self.cpu_cycle_count() - start
     }
 
-    /// 推入堆疊
-    fn push(&mut self, data: u8) {
-        self.bus_write(0x0100 | self.cpu.sp as u16, data);
-        self.cpu.sp = self.cpu.sp.wrapping_sub(1);
+    /// 執行到第 n 條掃描線的開頭（跨過 n 次掃描線邊界），回傳實際執行的
+    /// CPU 週期數；幀的處理與 `run_cycles` 相同
+    pub fn run_scanlines(&mut self, n: u32) -> u64 {
+        let start = self.cpu_cycle_count();
+        let mut line = self.ppu.scanline;
+        let mut remaining = n;
+        self.run_until(|emu| {
+            if emu.ppu.scanline != line {
+                line = emu.ppu.scanline;
+                remaining -= 1;
+            }
+            remaining == 0
+        });
+        self.cpu_cycle_count() - start
     }
 
-    /// 從堆疊彈出
-    fn pop(&mut self) -> u8 {
-        self.cpu.sp = self.cpu.sp.wrapping_add(1);
-        self.bus_read(0x0100 | self.cpu.sp as u16)
+    /// 逐個主時鐘執行到 done 成立或命中中斷點，途中完成的幀照 `frame` 收尾
+    ///
+    /// 下一幀在真的執行到時才開始，停在幀邊界上時由之後的 `frame` 開始。
+    fn run_until(&mut self, mut done: impl FnMut(&Self) -> bool) {
+        self.debugger.resume();
+        while !done(self) {
+            self.start_frame();
+            self.clock();
+            if self.ppu.frame_complete {
+                self.end_frame();
+            }
+            if self.debugger.break_hit().is_some() {
+                break;
+            }
+        }
+    }
+
+    /// 幀開始（或從中斷處繼續）時：清除中斷點狀態，新的一幀才開始
+    fn begin_frame(&mut self) {
+        self.debugger.resume();
+        self.start_frame();
     }
 
-    /// 推入 16 位元值
-    fn push16(&mut self, data: u16) {
-        self.push((data >> 8) as u8);
-        self.push(data as u8);
+    /// 新的一幀開始時套用這一幀的輸入並呼叫 NSF 的 PLAY（幀中間呼叫不做事）
+    fn start_frame(&mut self) {
+        if self.frame_started {
+            return;
+        }
+        self.frame_started = true;
+        self.apply_queued_input();
+        self.latch_movie_input();
+        self.nsf_tick();
+        self.ppu.frame_complete = false;
     }
 
-    /// 彈出 16 位元值
-    fn pop16(&mut self) -> u16 {
-        let lo = self.pop() as u16;
-        let hi = self.pop() as u16;
-        (hi << 8) | lo
+    /// 幀結束時的收尾：幀數、倒帶、控制器與電池 RAM 儲存事件
+    fn end_frame(&mut self) {
+        self.frame_started = false;
+        self.frame_count += 1;
+        self.capture_rewind();
+        self.ctrl1.end_frame();
+        self.ctrl2.end_frame();
+        if let Some(vs) = &mut self.vs {
+            vs.end_frame();
+        }
+        self.update_sram_flush();
     }
 
-    /// 設定零旗標和負旗標
-    fn set_zn(&mut self, value: u8) {
-        if value == 0 { self.cpu.status |= 0x02; } else { self.cpu.status &= !0x02; }
-        if value & 0x80 != 0 { self.cpu.status |= 0x80; } else { self.cpu.status &= !0x80; }
+    /// 預先執行（run-ahead）：正常執行一幀後存檔，以目前的輸入再往前
+    /// 執行 frames 幀，顯示最後一幀的畫面，再讀回存檔
+    ///
+    /// 遊戲從讀到輸入到畫面反應通常要 1-2 幀，預先執行相同的幀數可以
+    /// 抵銷這段延遲。預先執行的幀不輸出聲音，也不影響輸入影片、倒帶與
+    /// 電池 RAM 儲存事件；除錯器啟用（中斷點或追蹤）或接著 Zapper 時
+    /// 不預先執行（光線感應要看到這一幀真正的畫面）。
+    /// 存檔只在 WASM 記憶體中往返，不經過 hex 字串。
+    pub fn frame_with_runahead(&mut self, frames: u32) {
+        if frames == 0 || self.debugger.is_active() || self.zapper_attached() {
+            self.frame();
+            return;
+        }
+        // 只有最後一個預先執行的幀需要畫面
+        self.set_skip_output(true);
+        self.frame();
+        let mut state = std::mem::take(&mut self.state_scratch);
+        self.save_state_into(&mut state);
+        let samples = self.apu.get_available_samples();
+        let prg_ram_dirty = self.cartridge.prg_ram_dirty;
+        for ahead in 1..=frames {
+            self.set_skip_output(ahead < frames);
+            self.ppu.frame_complete = false;
+            self.run_frame_with(&mut NoProbe);
+        }
+        // 畫面緩衝區不在存檔內，讀回後保留預先執行的畫面；
+        // 存檔剛由本實例產生，不必像外部讀檔那樣先備份
+        let restored = self.apply_state_binary(&state);
+        debug_assert!(restored);
+        self.apu.truncate_samples(samples);
+        self.cartridge.prg_ram_dirty = prg_ram_dirty;
+        self.ppu.frame_complete = true;
+        self.state_scratch = state;
     }
 
-    fn carry(&self) -> bool { self.cpu.status & 0x01 != 0 }
-    fn zero(&self) -> bool { self.cpu.status & 0x02 != 0 }
-    fn overflow_flag(&self) -> bool { self.cpu.status & 0x40 != 0 }
-    fn negative(&self) -> bool { self.cpu.status & 0x80 != 0 }
+    /// 執行到幀結束，命中中斷點時提前返回 false
+    fn run_frame_with<P: ClockProbe>(&mut self, probe: &mut P) -> bool {
+        while !self.ppu.frame_complete {
+            self.clock_with(probe);
+            if self.debugger.break_hit().is_some() {
+                return false;
+            }
+        }
+        true
+    }
 
-    fn set_carry(&mut self, v: bool) {
-        if v { self.cpu.status |= 0x01; } else { self.cpu.status &= !0x01; }
+    /// 執行到幀結束並累計效能計數（有計時函式時一併量測各子系統耗時）
+    #[cfg(feature = "profiling")]
+    fn run_profiled_frame(&mut self) -> bool {
+        let start_clock = self.system_clock;
+        let start_instructions = self.instruction_count;
+        let start_samples = self.apu.get_available_samples();
+        let completed = match self.profiler.clock.take() {
+            Some(mut now) => {
+                let start = now();
+                let mut probe = TimingProbe::new(&mut now);
+                let completed = self.run_frame_with(&mut probe);
+                probe.enter(Subsystem::Other);
+                let totals = probe.totals;
+                let profile = &mut self.profiler.current;
+                for (ms, t) in profile.subsystem_ms.iter_mut().zip(totals) {
+                    *ms += t;
+                }
+                profile.total_ms += now() - start;
+                self.profiler.clock = Some(now);
+                completed
+            }
+            None => self.run_frame_with(&mut NoProbe),
+        };
+
+        let profile = &mut self.profiler.current;
+        let dots = self.system_clock - start_clock;
+        profile.ppu_dots += dots;
+        profile.cpu_cycles += dots * self.cpu_divider.1 / self.cpu_divider.0;
+        profile.instructions += self.instruction_count - start_instructions;
+        profile.audio_samples += self.apu.get_available_samples().saturating_sub(start_samples) as u64;
+        if completed {
+            self.profiler.finish_frame(self.frame_count + 1);
+        }
+        completed
     }
-    fn set_overflow(&mut self, v: bool) {
-        if v { self.cpu.status |= 0x40; } else { self.cpu.status &= !0x40; }
+
+    /// 設定效能計數的計時函式（回傳毫秒），None 表示只計數不計時
+    #[cfg(feature = "profiling")]
+    pub fn set_profiler_clock(&mut self, now: Option<Box<dyn FnMut() -> f64>>) {
+        self.profiler.clock = now;
     }
 
-    /// NMI
-    fn do_nmi(&mut self) {
-        self.push16(self.cpu.pc);
-        self.push((self.cpu.status & !0x10) | 0x20);
-        self.cpu.status |= 0x04;
-        let lo = self.bus_read(0xFFFA) as u16;
-        let hi = self.bus_read(0xFFFB) as u16;
-        self.cpu.pc = (hi << 8) | lo;
-        self.cpu.cycles = 7;
+    /// 上一個完成的幀的效能計數
+    #[cfg(feature = "profiling")]
+    pub fn last_frame_profile(&self) -> &FrameProfile {
+        &self.profiler.last
     }
 
-    /// IRQ
-    fn do_irq(&mut self) {
-        self.push16(self.cpu.pc);
-        self.push((self.cpu.status & !0x10) | 0x20);
-        self.cpu.status |= 0x04;
-        let lo = self.bus_read(0xFFFE) as u16;
-        let hi = self.bus_read(0xFFFF) as u16;
-        self.cpu.pc = (hi << 8) | lo;
-        self.cpu.cycles = 7;
+    /// 已執行的幀數
+    pub fn frame_count(&self) -> u64 { self.frame_count }
+
+    /// 各項資料的記憶體用量
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            frame_buffer: self.ppu.frame_buffer.len(),
+            audio_buffer: self.apu.audio_buffer_capacity() * std::mem::size_of::<f32>(),
+            prg_rom: self.cartridge.prg_rom.len(),
+            chr: self.cartridge.chr_data.len(),
+            ram: self.bus.ram.len() + self.cartridge.prg_ram.len(),
+            save_slots: self.slots.total_size() + self.state_scratch.capacity() + self.state_backup.capacity(),
+            rewind: self.rewind.memory_size(),
+            movie: self.movie.as_ref().map_or(0, |m| m.start_state.len() + m.inputs.len() * 2),
+            trace: self.debugger.trace_size(),
+        }
     }
 
-    /// 讀取 16 位元（帶頁面邊界 bug）
-    fn read16_bug(&mut self, addr: u16) -> u16 {
-        let lo = self.bus_read(addr) as u16;
-        let hi_addr = (addr & 0xFF00) | ((addr.wrapping_add(1)) & 0x00FF);
-        let hi = self.bus_read(hi_addr) as u16;
-        (hi << 8) | lo
+    /// 取得狀態快照（CPU 暫存器、PPU 位置、計數器與目前的 bank 映射）
+    pub fn status(&self) -> EmulatorStatus {
+        let mapper = &self.cartridge.mapper;
+        let prg_banks = [0u16, 1, 2, 3].map(|i| {
+            mapper.cpu_read(0x8000 + i * 0x2000).map_or(0, |offset| offset / 0x2000)
+        });
+        let chr_banks = [0u16, 1, 2, 3, 4, 5, 6, 7].map(|i| {
+            mapper.ppu_read(i * 0x0400).map_or(i as u32, |offset| offset / 0x0400)
+        });
+        EmulatorStatus {
+            pc: self.cpu.pc,
+            a: self.cpu.a,
+            x: self.cpu.x,
+            y: self.cpu.y,
+            sp: self.cpu.sp,
+            p: self.cpu.status,
+            scanline: self.ppu.scanline,
+            dot: self.ppu.cycle,
+            frame: self.frame_count,
+            cpu_cycles: self.cpu_cycle_count(),
+            instructions: self.instruction_count,
+            prg_banks,
+            chr_banks,
+            audio_samples: self.apu.get_available_samples(),
+            paused: self.debugger.break_hit().is_some(),
+        }
     }
 
-    /// 分支指令
-    fn branch(&mut self, condition: bool) {
-        let offset = self.bus_read(self.cpu.pc) as i8;
-        self.cpu.pc = self.cpu.pc.wrapping_add(1);
-        if condition {
-            let new_pc = self.cpu.pc.wrapping_add(offset as u16);
-            if (self.cpu.pc & 0xFF00) != (new_pc & 0xFF00) { self.cpu.cycles += 1; }
-            self.cpu.cycles += 1;
-            self.cpu.pc = new_pc;
+    /// 基準測試：盡速執行 n 幀，回傳 FPS 與各子系統耗時
+    /// now 回傳目前時間（毫秒）；測試結束後還原執行前的存檔狀態，以及
+    /// 存檔不含的畫面緩衝區與幀進度
+    pub fn benchmark<F: FnMut() -> f64>(&mut self, frames: u32, mut now: F) -> BenchmarkResult {
+        let snapshot = self.export_save_state_bytes();
+        let frame_buffer = self.ppu.frame_buffer.clone();
+        let (frame_started, frame_complete) = (self.frame_started, self.ppu.frame_complete);
+
+        // 第 1 輪：不量測，取得真實總耗時
+        let start = now();
+        for _ in 0..frames {
+            self.ppu.frame_complete = false;
+            while !self.ppu.frame_complete {
+                self.clock();
+            }
+        }
+        let total_ms = now() - start;
+
+        // 第 2 輪：插入計時探針，取得各子系統的耗時比例
+        self.import_state_binary(&snapshot);
+        let mut probe = TimingProbe::new(&mut now);
+        for _ in 0..frames {
+            self.ppu.frame_complete = false;
+            while !self.ppu.frame_complete {
+                self.clock_with(&mut probe);
+            }
         }
+        probe.enter(Subsystem::Other);
+        let sampled = probe.totals;
+
+        self.import_state_binary(&snapshot);
+        self.ppu.frame_buffer.copy_from_slice(&frame_buffer);
+        self.frame_started = frame_started;
+        self.ppu.frame_complete = frame_complete;
+        self.apu.consume_samples();
+        BenchmarkResult::new(frames, total_ms, sampled)
+    }
+
+    /// 執行一幀但不輸出畫面（CPU/APU/遊戲邏輯照常，幀緩衝區保留上一幀內容）
+    /// 用於快轉或低階裝置以 30fps 顯示、60fps 執行；接著 Zapper 時照常輸出
+    pub fn frame_skipped(&mut self) {
+        self.set_skip_output(true);
+        self.frame();
+        self.set_skip_output(false);
+    }
+
+    /// 設定是否跳過像素輸出
+    ///
+    /// Zapper 的光線感應讀取幀緩衝區中電子束已經掃過的像素，跳過輸出時
+    /// 讀到的是舊畫面，所以接著 Zapper 時一律輸出。
+    fn set_skip_output(&mut self, skip: bool) {
+        self.ppu.set_skip_output(skip && !self.zapper_attached());
+    }
+
+    /// 是否有控制器埠接著 Zapper
+    fn zapper_attached(&self) -> bool {
+        self.ctrl1.device() == InputDevice::Zapper || self.ctrl2.device() == InputDevice::Zapper
     }
 
     // ============================================================
-    // CPU 指令執行
+    // 輸入腳本（預先排程的按鈕狀態）
     // ============================================================
-    fn execute_cpu_instruction(&mut self, opcode: u8) {
-        match opcode {
-            // ADC
-            0x69 => { let v = self.imm(); self.op_adc(v); self.cpu.cycles = 2; }
-            0x65 => { let v = self.zp_r(); self.op_adc(v); self.cpu.cycles = 3; }
-            0x75 => { let v = self.zpx_r(); self.op_adc(v); self.cpu.cycles = 4; }
-            0x6D => { let (v, _) = self.abs_r(); self.op_adc(v); self.cpu.cycles = 4; }
-            0x7D => { let (v, e) = self.abx_r(); self.op_adc(v); self.cpu.cycles = 4 + e; }
-            0x79 => { let (v, e) = self.aby_r(); self.op_adc(v); self.cpu.cycles = 4 + e; }
-            0x61 => { let v = self.izx_r(); self.op_adc(v); self.cpu.cycles = 6; }
-            0x71 => { let (v, e) = self.izy_r(); self.op_adc(v); self.cpu.cycles = 5 + e; }
-
-            // AND
-            0x29 => { let v = self.imm(); self.cpu.a &= v; self.set_zn(self.cpu.a); self.cpu.cycles = 2; }
-            0x25 => { let v = self.zp_r(); self.cpu.a &= v; self.set_zn(self.cpu.a); self.cpu.cycles = 3; }
-            0x35 => { let v = self.zpx_r(); self.cpu.a &= v; self.set_zn(self.cpu.a); self.cpu.cycles = 4; }
-            0x2D => { let (v, _) = self.abs_r(); self.cpu.a &= v; self.set_zn(self.cpu.a); self.cpu.cycles = 4; }
-            0x3D => { let (v, e) = self.abx_r(); self.cpu.a &= v; self.set_zn(self.cpu.a); self.cpu.cycles = 4 + e; }
-            0x39 => { let (v, e) = self.aby_r(); self.cpu.a &= v; self.set_zn(self.cpu.a); self.cpu.cycles = 4 + e; }
-            0x21 => { let v = self.izx_r(); self.cpu.a &= v; self.set_zn(self.cpu.a); self.cpu.cycles = 6; }
-            0x31 => { let (v, e) = self.izy_r(); self.cpu.a &= v; self.set_zn(self.cpu.a); self.cpu.cycles = 5 + e; }
-
-            // ASL
-            0x0A => { self.set_carry(self.cpu.a & 0x80 != 0); self.cpu.a <<= 1; self.set_zn(self.cpu.a); self.cpu.cycles = 2; }
-            0x06 => { let a = self.zp(); self.op_asl_m(a); self.cpu.cycles = 5; }
-            0x16 => { let a = self.zpx(); self.op_asl_m(a); self.cpu.cycles = 6; }
-            0x0E => { let a = self.abs(); self.op_asl_m(a); self.cpu.cycles = 6; }
-            0x1E => { let a = self.abx_w(); self.op_asl_m(a); self.cpu.cycles = 7; }
-
-            // 分支
-            0x90 => { self.cpu.cycles = 2; let c = !self.carry(); self.branch(c); }
-            0xB0 => { self.cpu.cycles = 2; let c = self.carry(); self.branch(c); }
-            0xF0 => { self.cpu.cycles = 2; let c = self.zero(); self.branch(c); }
-            0x30 => { self.cpu.cycles = 2; let c = self.negative(); self.branch(c); }
-            0xD0 => { self.cpu.cycles = 2; let c = !self.zero(); self.branch(c); }
-            0x10 => { self.cpu.cycles = 2; let c = !self.negative(); self.branch(c); }
-            0x50 => { self.cpu.cycles = 2; let c = !self.overflow_flag(); self.branch(c); }
-            0x70 => { self.cpu.cycles = 2; let c = self.overflow_flag(); self.branch(c); }
-
-            // BIT
-            0x24 => { let v = self.zp_r(); self.op_bit(v); self.cpu.cycles = 3; }
-            0x2C => { let (v, _) = self.abs_r(); self.op_bit(v); self.cpu.cycles = 4; }
-
-            // BRK
-            0x00 => {
-                self.cpu.pc = self.cpu.pc.wrapping_add(1);
-                self.push16(self.cpu.pc);
-                self.push(self.cpu.status | 0x30);
-                self.cpu.status |= 0x04;
-                let lo = self.bus_read(0xFFFE) as u16;
-                let hi = self.bus_read(0xFFFF) as u16;
-                self.cpu.pc = (hi << 8) | lo;
-                self.cpu.cycles = 7;
-            }
 
-            // 旗標
-            0x18 => { self.cpu.status &= !0x01; self.cpu.cycles = 2; }
-            0xD8 => { self.cpu.status &= !0x08; self.cpu.cycles = 2; }
-            0x58 => { self.cpu.status &= !0x04; self.cpu.cycles = 2; }
-            0xB8 => { self.cpu.status &= !0x40; self.cpu.cycles = 2; }
-            0x38 => { self.cpu.status |= 0x01; self.cpu.cycles = 2; }
-            0xF8 => { self.cpu.status |= 0x08; self.cpu.cycles = 2; }
-            0x78 => { self.cpu.status |= 0x04; self.cpu.cycles = 2; }
-
-            // CMP
-            0xC9 => { let v = self.imm(); let a = self.cpu.a; self.op_cmp(a, v); self.cpu.cycles = 2; }
-            0xC5 => { let v = self.zp_r(); let a = self.cpu.a; self.op_cmp(a, v); self.cpu.cycles = 3; }
-            0xD5 => { let v = self.zpx_r(); let a = self.cpu.a; self.op_cmp(a, v); self.cpu.cycles = 4; }
-            0xCD => { let (v, _) = self.abs_r(); let a = self.cpu.a; self.op_cmp(a, v); self.cpu.cycles = 4; }
-            0xDD => { let (v, e) = self.abx_r(); let a = self.cpu.a; self.op_cmp(a, v); self.cpu.cycles = 4 + e; }
-            0xD9 => { let (v, e) = self.aby_r(); let a = self.cpu.a; self.op_cmp(a, v); self.cpu.cycles = 4 + e; }
-            0xC1 => { let v = self.izx_r(); let a = self.cpu.a; self.op_cmp(a, v); self.cpu.cycles = 6; }
-            0xD1 => { let (v, e) = self.izy_r(); let a = self.cpu.a; self.op_cmp(a, v); self.cpu.cycles = 5 + e; }
-
-            // CPX
-            0xE0 => { let v = self.imm(); let x = self.cpu.x; self.op_cmp(x, v); self.cpu.cycles = 2; }
-            0xE4 => { let v = self.zp_r(); let x = self.cpu.x; self.op_cmp(x, v); self.cpu.cycles = 3; }
-            0xEC => { let (v, _) = self.abs_r(); let x = self.cpu.x; self.op_cmp(x, v); self.cpu.cycles = 4; }
-
-            // CPY
-            0xC0 => { let v = self.imm(); let y = self.cpu.y; self.op_cmp(y, v); self.cpu.cycles = 2; }
-            0xC4 => { let v = self.zp_r(); let y = self.cpu.y; self.op_cmp(y, v); self.cpu.cycles = 3; }
-            0xCC => { let (v, _) = self.abs_r(); let y = self.cpu.y; self.op_cmp(y, v); self.cpu.cycles = 4; }
-
-            // DEC
-            0xC6 => { let a = self.zp(); self.op_dec_m(a); self.cpu.cycles = 5; }
-            0xD6 => { let a = self.zpx(); self.op_dec_m(a); self.cpu.cycles = 6; }
-            0xCE => { let a = self.abs(); self.op_dec_m(a); self.cpu.cycles = 6; }
-            0xDE => { let a = self.abx_w(); self.op_dec_m(a); self.cpu.cycles = 7; }
-            0xCA => { self.cpu.x = self.cpu.x.wrapping_sub(1); self.set_zn(self.cpu.x); self.cpu.cycles = 2; }
-            0x88 => { self.cpu.y = self.cpu.y.wrapping_sub(1); self.set_zn(self.cpu.y); self.cpu.cycles = 2; }
-
-            // EOR
-            0x49 => { let v = self.imm(); self.cpu.a ^= v; self.set_zn(self.cpu.a); self.cpu.cycles = 2; }
-            0x45 => { let v = self.zp_r(); self.cpu.a ^= v; self.set_zn(self.cpu.a); self.cpu.cycles = 3; }
-            0x55 => { let v = self.zpx_r(); self.cpu.a ^= v; self.set_zn(self.cpu.a); self.cpu.cycles = 4; }
-            0x4D => { let (v, _) = self.abs_r(); self.cpu.a ^= v; self.set_zn(self.cpu.a); self.cpu.cycles = 4; }
-            0x5D => { let (v, e) = self.abx_r(); self.cpu.a ^= v; self.set_zn(self.cpu.a); self.cpu.cycles = 4 + e; }
-            0x59 => { let (v, e) = self.aby_r(); self.cpu.a ^= v; self.set_zn(self.cpu.a); self.cpu.cycles = 4 + e; }
-            0x41 => { let v = self.izx_r(); self.cpu.a ^= v; self.set_zn(self.cpu.a); self.cpu.cycles = 6; }
-            0x51 => { let (v, e) = self.izy_r(); self.cpu.a ^= v; self.set_zn(self.cpu.a); self.cpu.cycles = 5 + e; }
-
-            // INC
-            0xE6 => { let a = self.zp(); self.op_inc_m(a); self.cpu.cycles = 5; }
-            0xF6 => { let a = self.zpx(); self.op_inc_m(a); self.cpu.cycles = 6; }
-            0xEE => { let a = self.abs(); self.op_inc_m(a); self.cpu.cycles = 6; }
-            0xFE => { let a = self.abx_w(); self.op_inc_m(a); self.cpu.cycles = 7; }
-            0xE8 => { self.cpu.x = self.cpu.x.wrapping_add(1); self.set_zn(self.cpu.x); self.cpu.cycles = 2; }
-            0xC8 => { self.cpu.y = self.cpu.y.wrapping_add(1); self.set_zn(self.cpu.y); self.cpu.cycles = 2; }
-
-            // JMP
-            0x4C => { let addr = self.abs(); self.cpu.pc = addr; self.cpu.cycles = 3; }
-            0x6C => { let ptr = self.abs(); let addr = self.read16_bug(ptr); self.cpu.pc = addr; self.cpu.cycles = 5; }
-
-            // JSR
-            0x20 => { let addr = self.abs(); let ret = self.cpu.pc.wrapping_sub(1); self.push16(ret); self.cpu.pc = addr; self.cpu.cycles = 6; }
-
-            // LDA
-            0xA9 => { self.cpu.a = self.imm(); self.set_zn(self.cpu.a); self.cpu.cycles = 2; }
-            0xA5 => { self.cpu.a = self.zp_r(); self.set_zn(self.cpu.a); self.cpu.cycles = 3; }
-            0xB5 => { self.cpu.a = self.zpx_r(); self.set_zn(self.cpu.a); self.cpu.cycles = 4; }
-            0xAD => { let (v, _) = self.abs_r(); self.cpu.a = v; self.set_zn(self.cpu.a); self.cpu.cycles = 4; }
-            0xBD => { let (v, e) = self.abx_r(); self.cpu.a = v; self.set_zn(self.cpu.a); self.cpu.cycles = 4 + e; }
-            0xB9 => { let (v, e) = self.aby_r(); self.cpu.a = v; self.set_zn(self.cpu.a); self.cpu.cycles = 4 + e; }
-            0xA1 => { self.cpu.a = self.izx_r(); self.set_zn(self.cpu.a); self.cpu.cycles = 6; }
-            0xB1 => { let (v, e) = self.izy_r(); self.cpu.a = v; self.set_zn(self.cpu.a); self.cpu.cycles = 5 + e; }
-
-            // LDX
-            0xA2 => { self.cpu.x = self.imm(); self.set_zn(self.cpu.x); self.cpu.cycles = 2; }
-            0xA6 => { self.cpu.x = self.zp_r(); self.set_zn(self.cpu.x); self.cpu.cycles = 3; }
-            0xB6 => { // zp,Y
-                let base = self.bus_read(self.cpu.pc) as u16;
-                self.cpu.pc = self.cpu.pc.wrapping_add(1);
-                let addr = (base.wrapping_add(self.cpu.y as u16)) & 0xFF;
-                self.cpu.x = self.bus_read(addr); self.set_zn(self.cpu.x); self.cpu.cycles = 4;
-            }
-            0xAE => { let (v, _) = self.abs_r(); self.cpu.x = v; self.set_zn(self.cpu.x); self.cpu.cycles = 4; }
-            0xBE => { let (v, e) = self.aby_r(); self.cpu.x = v; self.set_zn(self.cpu.x); self.cpu.cycles = 4 + e; }
-
-            // LDY
-            0xA0 => { self.cpu.y = self.imm(); self.set_zn(self.cpu.y); self.cpu.cycles = 2; }
-            0xA4 => { self.cpu.y = self.zp_r(); self.set_zn(self.cpu.y); self.cpu.cycles = 3; }
-            0xB4 => { self.cpu.y = self.zpx_r(); self.set_zn(self.cpu.y); self.cpu.cycles = 4; }
-            0xAC => { let (v, _) = self.abs_r(); self.cpu.y = v; self.set_zn(self.cpu.y); self.cpu.cycles = 4; }
-            0xBC => { let (v, e) = self.abx_r(); self.cpu.y = v; self.set_zn(self.cpu.y); self.cpu.cycles = 4 + e; }
-
-            // LSR
-            0x4A => { self.set_carry(self.cpu.a & 0x01 != 0); self.cpu.a >>= 1; self.set_zn(self.cpu.a); self.cpu.cycles = 2; }
-            0x46 => { let a = self.zp(); self.op_lsr_m(a); self.cpu.cycles = 5; }
-            0x56 => { let a = self.zpx(); self.op_lsr_m(a); self.cpu.cycles = 6; }
-            0x4E => { let a = self.abs(); self.op_lsr_m(a); self.cpu.cycles = 6; }
-            0x5E => { let a = self.abx_w(); self.op_lsr_m(a); self.cpu.cycles = 7; }
-
-            // NOP
-            0xEA => { self.cpu.cycles = 2; }
-
-            // ORA
-            0x09 => { let v = self.imm(); self.cpu.a |= v; self.set_zn(self.cpu.a); self.cpu.cycles = 2; }
-            0x05 => { let v = self.zp_r(); self.cpu.a |= v; self.set_zn(self.cpu.a); self.cpu.cycles = 3; }
-            0x15 => { let v = self.zpx_r(); self.cpu.a |= v; self.set_zn(self.cpu.a); self.cpu.cycles = 4; }
-            0x0D => { let (v, _) = self.abs_r(); self.cpu.a |= v; self.set_zn(self.cpu.a); self.cpu.cycles = 4; }
-            0x1D => { let (v, e) = self.abx_r(); self.cpu.a |= v; self.set_zn(self.cpu.a); self.cpu.cycles = 4 + e; }
-            0x19 => { let (v, e) = self.aby_r(); self.cpu.a |= v; self.set_zn(self.cpu.a); self.cpu.cycles = 4 + e; }
-            0x01 => { let v = self.izx_r(); self.cpu.a |= v; self.set_zn(self.cpu.a); self.cpu.cycles = 6; }
-            0x11 => { let (v, e) = self.izy_r(); self.cpu.a |= v; self.set_zn(self.cpu.a); self.cpu.cycles = 5 + e; }
-
-            // 堆疊
-            0x48 => { let a = self.cpu.a; self.push(a); self.cpu.cycles = 3; }
-            0x08 => { let s = self.cpu.status | 0x30; self.push(s); self.cpu.cycles = 3; }
-            0x68 => { self.cpu.a = self.pop(); self.set_zn(self.cpu.a); self.cpu.cycles = 4; }
-            0x28 => { let v = self.pop(); self.cpu.status = (v & !0x30) | (self.cpu.status & 0x30); self.cpu.status |= 0x20; self.cpu.cycles = 4; }
-
-            // ROL
-            0x2A => { let c = self.carry() as u8; self.set_carry(self.cpu.a & 0x80 != 0); self.cpu.a = (self.cpu.a << 1) | c; self.set_zn(self.cpu.a); self.cpu.cycles = 2; }
-            0x26 => { let a = self.zp(); self.op_rol_m(a); self.cpu.cycles = 5; }
-            0x36 => { let a = self.zpx(); self.op_rol_m(a); self.cpu.cycles = 6; }
-            0x2E => { let a = self.abs(); self.op_rol_m(a); self.cpu.cycles = 6; }
-            0x3E => { let a = self.abx_w(); self.op_rol_m(a); self.cpu.cycles = 7; }
-
-            // ROR
-            0x6A => { let c = if self.carry() { 0x80u8 } else { 0 }; self.set_carry(self.cpu.a & 0x01 != 0); self.cpu.a = (self.cpu.a >> 1) | c; self.set_zn(self.cpu.a); self.cpu.cycles = 2; }
-            0x66 => { let a = self.zp(); self.op_ror_m(a); self.cpu.cycles = 5; }
-            0x76 => { let a = self.zpx(); self.op_ror_m(a); self.cpu.cycles = 6; }
-            0x6E => { let a = self.abs(); self.op_ror_m(a); self.cpu.cycles = 6; }
-            0x7E => { let a = self.abx_w(); self.op_ror_m(a); self.cpu.cycles = 7; }
-
-            // RTI
-            0x40 => { let s = self.pop(); self.cpu.status = (s & !0x30) | 0x20; self.cpu.pc = self.pop16(); self.cpu.cycles = 6; }
-
-            // RTS
-            0x60 => { self.cpu.pc = self.pop16().wrapping_add(1); self.cpu.cycles = 6; }
-
-            // SBC
-            0xE9 | 0xEB => { let v = self.imm(); self.op_sbc(v); self.cpu.cycles = 2; }
-            0xE5 => { let v = self.zp_r(); self.op_sbc(v); self.cpu.cycles = 3; }
-            0xF5 => { let v = self.zpx_r(); self.op_sbc(v); self.cpu.cycles = 4; }
-            0xED => { let (v, _) = self.abs_r(); self.op_sbc(v); self.cpu.cycles = 4; }
-            0xFD => { let (v, e) = self.abx_r(); self.op_sbc(v); self.cpu.cycles = 4 + e; }
-            0xF9 => { let (v, e) = self.aby_r(); self.op_sbc(v); self.cpu.cycles = 4 + e; }
-            0xE1 => { let v = self.izx_r(); self.op_sbc(v); self.cpu.cycles = 6; }
-            0xF1 => { let (v, e) = self.izy_r(); self.op_sbc(v); self.cpu.cycles = 5 + e; }
-
-            // STA
-            0x85 => { let a = self.zp(); let v = self.cpu.a; self.bus_write(a, v); self.cpu.cycles = 3; }
-            0x95 => { let a = self.zpx(); let v = self.cpu.a; self.bus_write(a, v); self.cpu.cycles = 4; }
-            0x8D => { let a = self.abs(); let v = self.cpu.a; self.bus_write(a, v); self.cpu.cycles = 4; }
-            0x9D => { let a = self.abx_w(); let v = self.cpu.a; self.bus_write(a, v); self.cpu.cycles = 5; }
-            0x99 => { let a = self.aby_w(); let v = self.cpu.a; self.bus_write(a, v); self.cpu.cycles = 5; }
-            0x81 => { let a = self.izx(); let v = self.cpu.a; self.bus_write(a, v); self.cpu.cycles = 6; }
-            0x91 => { let a = self.izy_w(); let v = self.cpu.a; self.bus_write(a, v); self.cpu.cycles = 6; }
-
-            // STX
-            0x86 => { let a = self.zp(); let v = self.cpu.x; self.bus_write(a, v); self.cpu.cycles = 3; }
-            0x96 => { // zp,Y
-                let base = self.bus_read(self.cpu.pc).wrapping_add(self.cpu.y) as u16 & 0xFF;
-                self.cpu.pc = self.cpu.pc.wrapping_add(1);
-                let v = self.cpu.x; self.bus_write(base, v); self.cpu.cycles = 4;
-            }
-            0x8E => { let a = self.abs(); let v = self.cpu.x; self.bus_write(a, v); self.cpu.cycles = 4; }
-
-            // STY
-            0x84 => { let a = self.zp(); let v = self.cpu.y; self.bus_write(a, v); self.cpu.cycles = 3; }
-            0x94 => { let a = self.zpx(); let v = self.cpu.y; self.bus_write(a, v); self.cpu.cycles = 4; }
-            0x8C => { let a = self.abs(); let v = self.cpu.y; self.bus_write(a, v); self.cpu.cycles = 4; }
-
-            // 暫存器傳輸
-            0xAA => { self.cpu.x = self.cpu.a; self.set_zn(self.cpu.x); self.cpu.cycles = 2; }
-            0xA8 => { self.cpu.y = self.cpu.a; self.set_zn(self.cpu.y); self.cpu.cycles = 2; }
-            0xBA => { self.cpu.x = self.cpu.sp; self.set_zn(self.cpu.x); self.cpu.cycles = 2; }
-            0x8A => { self.cpu.a = self.cpu.x; self.set_zn(self.cpu.a); self.cpu.cycles = 2; }
-            0x9A => { self.cpu.sp = self.cpu.x; self.cpu.cycles = 2; }
-            0x98 => { self.cpu.a = self.cpu.y; self.set_zn(self.cpu.a); self.cpu.cycles = 2; }
-
-            // === 非官方指令 ===
-            // LAX
-            0xA7 => { let v = self.zp_r(); self.cpu.a = v; self.cpu.x = v; self.set_zn(v); self.cpu.cycles = 3; }
-            0xB7 => { let base = self.bus_read(self.cpu.pc) as u16; self.cpu.pc = self.cpu.pc.wrapping_add(1); let addr = (base.wrapping_add(self.cpu.y as u16)) & 0xFF; let v = self.bus_read(addr); self.cpu.a = v; self.cpu.x = v; self.set_zn(v); self.cpu.cycles = 4; }
-            0xAF => { let (v, _) = self.abs_r(); self.cpu.a = v; self.cpu.x = v; self.set_zn(v); self.cpu.cycles = 4; }
-            0xBF => { let (v, e) = self.aby_r(); self.cpu.a = v; self.cpu.x = v; self.set_zn(v); self.cpu.cycles = 4 + e; }
-            0xA3 => { let v = self.izx_r(); self.cpu.a = v; self.cpu.x = v; self.set_zn(v); self.cpu.cycles = 6; }
-            0xB3 => { let (v, e) = self.izy_r(); self.cpu.a = v; self.cpu.x = v; self.set_zn(v); self.cpu.cycles = 5 + e; }
-
-            // SAX
-            0x87 => { let a = self.zp(); let v = self.cpu.a & self.cpu.x; self.bus_write(a, v); self.cpu.cycles = 3; }
-            0x97 => { let base = self.bus_read(self.cpu.pc).wrapping_add(self.cpu.y) as u16 & 0xFF; self.cpu.pc = self.cpu.pc.wrapping_add(1); let v = self.cpu.a & self.cpu.x; self.bus_write(base, v); self.cpu.cycles = 4; }
-            0x8F => { let a = self.abs(); let v = self.cpu.a & self.cpu.x; self.bus_write(a, v); self.cpu.cycles = 4; }
-            0x83 => { let a = self.izx(); let v = self.cpu.a & self.cpu.x; self.bus_write(a, v); self.cpu.cycles = 6; }
-
-            // DCP
-            0xC7 => { let a = self.zp(); self.op_dcp(a); self.cpu.cycles = 5; }
-            0xD7 => { let a = self.zpx(); self.op_dcp(a); self.cpu.cycles = 6; }
-            0xCF => { let a = self.abs(); self.op_dcp(a); self.cpu.cycles = 6; }
-            0xDF => { let a = self.abx_w(); self.op_dcp(a); self.cpu.cycles = 7; }
-            0xDB => { let a = self.aby_w(); self.op_dcp(a); self.cpu.cycles = 7; }
-            0xC3 => { let a = self.izx(); self.op_dcp(a); self.cpu.cycles = 8; }
-            0xD3 => { let a = self.izy_w(); self.op_dcp(a); self.cpu.cycles = 8; }
-
-            // ISB
-            0xE7 => { let a = self.zp(); self.op_isb(a); self.cpu.cycles = 5; }
-            0xF7 => { let a = self.zpx(); self.op_isb(a); self.cpu.cycles = 6; }
-            0xEF => { let a = self.abs(); self.op_isb(a); self.cpu.cycles = 6; }
-            0xFF => { let a = self.abx_w(); self.op_isb(a); self.cpu.cycles = 7; }
-            0xFB => { let a = self.aby_w(); self.op_isb(a); self.cpu.cycles = 7; }
-            0xE3 => { let a = self.izx(); self.op_isb(a); self.cpu.cycles = 8; }
-            0xF3 => { let a = self.izy_w(); self.op_isb(a); self.cpu.cycles = 8; }
-
-            // SLO
-            0x07 => { let a = self.zp(); self.op_slo(a); self.cpu.cycles = 5; }
-            0x17 => { let a = self.zpx(); self.op_slo(a); self.cpu.cycles = 6; }
-            0x0F => { let a = self.abs(); self.op_slo(a); self.cpu.cycles = 6; }
-            0x1F => { let a = self.abx_w(); self.op_slo(a); self.cpu.cycles = 7; }
-            0x1B => { let a = self.aby_w(); self.op_slo(a); self.cpu.cycles = 7; }
-            0x03 => { let a = self.izx(); self.op_slo(a); self.cpu.cycles = 8; }
-            0x13 => { let a = self.izy_w(); self.op_slo(a); self.cpu.cycles = 8; }
-
-            // RLA
-            0x27 => { let a = self.zp(); self.op_rla(a); self.cpu.cycles = 5; }
-            0x37 => { let a = self.zpx(); self.op_rla(a); self.cpu.cycles = 6; }
-            0x2F => { let a = self.abs(); self.op_rla(a); self.cpu.cycles = 6; }
-            0x3F => { let a = self.abx_w(); self.op_rla(a); self.cpu.cycles = 7; }
-            0x3B => { let a = self.aby_w(); self.op_rla(a); self.cpu.cycles = 7; }
-            0x23 => { let a = self.izx(); self.op_rla(a); self.cpu.cycles = 8; }
-            0x33 => { let a = self.izy_w(); self.op_rla(a); self.cpu.cycles = 8; }
-
-            // SRE
-            0x47 => { let a = self.zp(); self.op_sre(a); self.cpu.cycles = 5; }
-            0x57 => { let a = self.zpx(); self.op_sre(a); self.cpu.cycles = 6; }
-            0x4F => { let a = self.abs(); self.op_sre(a); self.cpu.cycles = 6; }
-            0x5F => { let a = self.abx_w(); self.op_sre(a); self.cpu.cycles = 7; }
-            0x5B => { let a = self.aby_w(); self.op_sre(a); self.cpu.cycles = 7; }
-            0x43 => { let a = self.izx(); self.op_sre(a); self.cpu.cycles = 8; }
-            0x53 => { let a = self.izy_w(); self.op_sre(a); self.cpu.cycles = 8; }
-
-            // RRA
-            0x67 => { let a = self.zp(); self.op_rra(a); self.cpu.cycles = 5; }
-            0x77 => { let a = self.zpx(); self.op_rra(a); self.cpu.cycles = 6; }
-            0x6F => { let a = self.abs(); self.op_rra(a); self.cpu.cycles = 6; }
-            0x7F => { let a = self.abx_w(); self.op_rra(a); self.cpu.cycles = 7; }
-            0x7B => { let a = self.aby_w(); self.op_rra(a); self.cpu.cycles = 7; }
-            0x63 => { let a = self.izx(); self.op_rra(a); self.cpu.cycles = 8; }
-            0x73 => { let a = self.izy_w(); self.op_rra(a); self.cpu.cycles = 8; }
-
-            // NOP 變體
-            0x1A | 0x3A | 0x5A | 0x7A | 0xDA | 0xFA => { self.cpu.cycles = 2; }
-            0x80 | 0x82 | 0x89 | 0xC2 | 0xE2 => { self.cpu.pc = self.cpu.pc.wrapping_add(1); self.cpu.cycles = 2; }
-            0x04 | 0x44 | 0x64 => { self.cpu.pc = self.cpu.pc.wrapping_add(1); self.cpu.cycles = 3; }
-            0x14 | 0x34 | 0x54 | 0x74 | 0xD4 | 0xF4 => { self.cpu.pc = self.cpu.pc.wrapping_add(1); self.cpu.cycles = 4; }
-            0x0C => { self.cpu.pc = self.cpu.pc.wrapping_add(2); self.cpu.cycles = 4; }
-            0x1C | 0x3C | 0x5C | 0x7C | 0xDC | 0xFC => {
-                let lo = self.bus_read(self.cpu.pc) as u16;
-                let hi = self.bus_read(self.cpu.pc.wrapping_add(1)) as u16;
-                self.cpu.pc = self.cpu.pc.wrapping_add(2);
-                let base = (hi << 8) | lo;
-                let addr = base.wrapping_add(self.cpu.x as u16);
-                let extra = if (base & 0xFF00) != (addr & 0xFF00) { 1u8 } else { 0 };
-                self.cpu.cycles = 4 + extra;
-            }
+    /// 排程在第 frame 幀開始時，把控制器 port 的按鈕狀態設為 buttons
+    /// buttons 位元 0-7 對應 A, B, Select, Start, Up, Down, Left, Right；
+    /// 狀態會一直維持到下一筆排程，例如「第 180-600 幀按住右」為
+    /// queue_input(180, 0, 0x80) 加上 queue_input(601, 0, 0)
+    pub fn queue_input(&mut self, frame: u64, port: u8, buttons: u8) {
+        if port > 1 { return; }
+        self.input_queue.entry(frame).or_default().push((port, buttons));
+    }
+
+    /// 清除所有尚未套用的排程輸入
+    pub fn clear_input_queue(&mut self) { self.input_queue.clear(); }
 
-            _ => { self.cpu.cycles = 2; }
+    /// 尚未套用的排程筆數
+    pub fn queued_input_count(&self) -> usize {
+        self.input_queue.values().map(Vec::len).sum()
+    }
+
+    /// 套用所有排定在目前幀（含已錯過的幀）的輸入
+    fn apply_queued_input(&mut self) {
+        while let Some(entry) = self.input_queue.first_entry() {
+            if *entry.key() > self.frame_count { break; }
+            for (port, buttons) in entry.remove() {
+                match port {
+                    0 => self.ctrl1.set_buttons(buttons),
+                    _ => self.ctrl2.set_buttons(buttons),
+                }
+            }
         }
     }
 
     // ============================================================
-    // 定址模式輔助函數（簡短命名以減少重複碼量）
+    // NSF 播放
     // ============================================================
 
-    /// 立即值
-    fn imm(&mut self) -> u8 {
-        let v = self.bus_read(self.cpu.pc);
-        self.cpu.pc = self.cpu.pc.wrapping_add(1);
-        v
-    }
-
-    /// 零頁位址
-    fn zp(&mut self) -> u16 {
-        let a = self.bus_read(self.cpu.pc) as u16;
-        self.cpu.pc = self.cpu.pc.wrapping_add(1);
-        a
+    /// NSF 播放狀態（目前載入的不是 NSF 時為 None）
+    pub fn nsf(&self) -> Option<&NsfPlayer> { self.nsf.as_ref() }
+
+    /// 播放指定曲目（從 0 開始），曲目不存在或未載入 NSF 時回傳 false
+    /// 參考：https://www.nesdev.org/wiki/NSF#Initializing_a_tune
+    pub fn nsf_play_track(&mut self, track: u8) -> bool {
+        let Some(nsf) = &mut self.nsf else { return false };
+        if track >= nsf.header.total_songs {
+            return false;
+        }
+        nsf.current_track = Some(track);
+        let init_addr = nsf.header.init_addr;
+        let region_x = nsf.header.init_region_x(self.region);
+
+        self.bus.ram = [0; 2048];
+        self.cartridge.prg_ram.fill(0);
+        self.cartridge.reset();
+        self.apu.reset();
+        for addr in 0x4000..=0x4013 {
+            self.bus_write(addr, 0);
+        }
+        self.bus_write(0x4015, 0x0F);
+        self.bus_write(0x4017, 0x40);
+
+        self.cpu.a = track;
+        self.cpu.x = region_x;
+        self.cpu.y = 0;
+        self.cpu.sp = 0xFD;
+        self.cpu.status = 0x24;
+        self.call_nsf_routine(init_addr);
+        true
     }
 
-    /// 零頁讀取
-    fn zp_r(&mut self) -> u8 { let a = self.zp(); self.bus_read(a) }
+    /// 停止播放（靜音並停止呼叫 PLAY）
+    pub fn nsf_stop(&mut self) {
+        let Some(nsf) = &mut self.nsf else { return };
+        nsf.current_track = None;
+        self.bus_write(0x4015, 0);
+        self.cpu.pc = NSF_RETURN_ADDR;
+        self.cpu.cycles = 0;
+    }
+
+    /// 每幀開始時：上一個常式已返回就呼叫 PLAY
+    fn nsf_tick(&mut self) {
+        let Some(nsf) = &self.nsf else { return };
+        if nsf.current_track.is_some() && self.cpu.pc == NSF_RETURN_ADDR {
+            let play_addr = nsf.header.play_addr;
+            self.call_nsf_routine(play_addr);
+        }
+    }
 
-    /// 零頁+X 位址
-    fn zpx(&mut self) -> u16 {
-        let a = self.bus_read(self.cpu.pc).wrapping_add(self.cpu.x) as u16 & 0xFF;
-        self.cpu.pc = self.cpu.pc.wrapping_add(1);
-        a
+    /// 以 JSR 的方式呼叫常式，RTS 後 PC 停在 NSF_RETURN_ADDR
+    fn call_nsf_routine(&mut self, addr: u16) {
+        self.with_cpu(|cpu, bus| cpu.call(bus, addr, NSF_RETURN_ADDR));
     }
 
-    /// 零頁+X 讀取
-    fn zpx_r(&mut self) -> u8 { let a = self.zpx(); self.bus_read(a) }
+    // ============================================================
+    // 除錯（單步執行、記憶體存取、反組譯）
+    // ============================================================
 
-    /// 絕對位址
-    fn abs(&mut self) -> u16 {
-        let lo = self.bus_read(self.cpu.pc) as u16;
-        let hi = self.bus_read(self.cpu.pc.wrapping_add(1)) as u16;
-        self.cpu.pc = self.cpu.pc.wrapping_add(2);
-        (hi << 8) | lo
+    /// 執行一條指令（若停在中斷點上，會略過該中斷點執行），回傳停止的原因
+    ///
+    /// 跨過幀界線時照 `frame` 的流程收尾並開始下一幀。CPU 沒有指令可執行
+    /// 時（NSF 停止播放後停在返回位址）最多執行一幀的主時鐘就停下。
+    pub fn step_instruction(&mut self) -> StopReason {
+        let start = self.instruction_count;
+        let limit = self.system_clock + 341 * self.region.scanlines_per_frame() as u64;
+        self.run_until(|emu| emu.instruction_count != start || emu.system_clock >= limit);
+        self.step_result()
     }
 
-    /// 絕對讀取
-    fn abs_r(&mut self) -> (u8, u8) { let a = self.abs(); (self.bus_read(a), 0) }
+    /// 執行到目前掃描線結束（下一條掃描線開始），回傳停止的原因
+    pub fn step_scanline(&mut self) -> StopReason {
+        let start = self.ppu.scanline;
+        self.run_until(|emu| emu.ppu.scanline != start);
+        self.step_result()
+    }
 
-    /// 絕對+X 讀取（含頁面交叉檢查）
-    fn abx_r(&mut self) -> (u8, u8) {
-        let lo = self.bus_read(self.cpu.pc) as u16;
-        let hi = self.bus_read(self.cpu.pc.wrapping_add(1)) as u16;
-        self.cpu.pc = self.cpu.pc.wrapping_add(2);
-        let base = (hi << 8) | lo;
-        let addr = base.wrapping_add(self.cpu.x as u16);
-        let e = if (base & 0xFF00) != (addr & 0xFF00) { 1u8 } else { 0 };
-        (self.bus_read(addr), e)
+    /// 執行到幀結束（與 frame 相同），回傳停止的原因
+    pub fn step_frame(&mut self) -> StopReason {
+        self.frame();
+        self.step_result()
     }
 
-    /// 絕對+X 位址（寫入用）
-    fn abx_w(&mut self) -> u16 {
-        let lo = self.bus_read(self.cpu.pc) as u16;
-        let hi = self.bus_read(self.cpu.pc.wrapping_add(1)) as u16;
-        self.cpu.pc = self.cpu.pc.wrapping_add(2);
-        ((hi << 8) | lo).wrapping_add(self.cpu.x as u16)
+    /// 單步結束時的停止原因；單步最後一條指令觸發的監看點立即轉為暫停
+    fn step_result(&mut self) -> StopReason {
+        if self.debugger.break_hit().is_none() {
+            self.debugger.take_pending_watch(self.cpu.pc);
+        }
+        self.debugger.stop_reason().unwrap_or(StopReason::Completed)
     }
 
-    /// 絕對+Y 讀取
-    fn aby_r(&mut self) -> (u8, u8) {
-        let lo = self.bus_read(self.cpu.pc) as u16;
-        let hi = self.bus_read(self.cpu.pc.wrapping_add(1)) as u16;
-        self.cpu.pc = self.cpu.pc.wrapping_add(2);
-        let base = (hi << 8) | lo;
-        let addr = base.wrapping_add(self.cpu.y as u16);
-        let e = if (base & 0xFF00) != (addr & 0xFF00) { 1u8 } else { 0 };
-        (self.bus_read(addr), e)
+    /// 讀取 CPU 位址空間但不產生副作用
+    /// PPU/APU/控制器暫存器（$2000-$401F）讀取會改變狀態，一律回傳 0
+    pub fn peek(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x1FFF => self.bus.ram[(addr & 0x07FF) as usize],
+            0x2000..=0x401F => 0,
+            _ => self.cartridge.cpu_read(addr),
+        }
     }
 
-    /// 絕對+Y 位址（寫入用）
-    fn aby_w(&mut self) -> u16 {
-        let lo = self.bus_read(self.cpu.pc) as u16;
-        let hi = self.bus_read(self.cpu.pc.wrapping_add(1)) as u16;
-        self.cpu.pc = self.cpu.pc.wrapping_add(2);
-        ((hi << 8) | lo).wrapping_add(self.cpu.y as u16)
+    /// 寫入 CPU 位址空間
+    /// RAM 與 PRG RAM 直接修改，其他位址如同 CPU 寫入（會觸發暫存器行為）
+    pub fn poke(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.bus.ram[(addr & 0x07FF) as usize] = value,
+            0x6000..=0x7FFF => {
+                if let Some(byte) = self.cartridge.prg_ram.get_mut((addr - 0x6000) as usize) {
+                    *byte = value;
+                }
+            }
+            _ => self.bus_write(addr, value),
+        }
     }
 
-    /// (間接,X) 位址
-    fn izx(&mut self) -> u16 {
-        let ptr = self.bus_read(self.cpu.pc).wrapping_add(self.cpu.x) as u16;
-        self.cpu.pc = self.cpu.pc.wrapping_add(1);
-        let lo = self.bus_read(ptr & 0xFF) as u16;
-        let hi = self.bus_read((ptr.wrapping_add(1)) & 0xFF) as u16;
-        (hi << 8) | lo
+    /// 從 addr 開始反組譯 count 條指令，每行格式：「C000  A9 00     LDA #$00」
+    pub fn disassemble(&self, addr: u16, count: usize) -> Vec<String> {
+        let mut lines = Vec::with_capacity(count);
+        let mut pc = addr;
+        for _ in 0..count {
+            let (text, len) = debugger::disassemble(pc, |a| self.peek(a));
+            lines.push(format!("{:04X}  {:<8}  {}", pc, self.hex_bytes(pc, len), text));
+            pc = pc.wrapping_add(len);
+        }
+        lines
     }
 
-    /// (間接,X) 讀取
-    fn izx_r(&mut self) -> u8 { let a = self.izx(); self.bus_read(a) }
+    /// 已執行的指令數
+    pub fn instruction_count(&self) -> u64 { self.instruction_count }
 
-    /// (間接),Y 讀取
-    fn izy_r(&mut self) -> (u8, u8) {
-        let ptr = self.bus_read(self.cpu.pc) as u16;
-        self.cpu.pc = self.cpu.pc.wrapping_add(1);
-        let lo = self.bus_read(ptr) as u16;
-        let hi = self.bus_read((ptr.wrapping_add(1)) & 0xFF) as u16;
-        let base = (hi << 8) | lo;
-        let addr = base.wrapping_add(self.cpu.y as u16);
-        let e = if (base & 0xFF00) != (addr & 0xFF00) { 1u8 } else { 0 };
-        (self.bus_read(addr), e)
+    /// 指令機器碼的十六進位文字（以空白分隔）
+    fn hex_bytes(&self, addr: u16, len: u16) -> String {
+        let bytes: Vec<String> = (0..len)
+            .map(|i| format!("{:02X}", self.peek(addr.wrapping_add(i))))
+            .collect();
+        bytes.join(" ")
     }
 
-    /// (間接),Y 位址（寫入用）
-    fn izy_w(&mut self) -> u16 {
-        let ptr = self.bus_read(self.cpu.pc) as u16;
-        self.cpu.pc = self.cpu.pc.wrapping_add(1);
-        let lo = self.bus_read(ptr) as u16;
-        let hi = self.bus_read((ptr.wrapping_add(1)) & 0xFF) as u16;
-        ((hi << 8) | lo).wrapping_add(self.cpu.y as u16)
+    /// 目前指令的追蹤記錄行（nestest.log 格式，執行前的狀態）
+    ///
+    /// 反組譯欄的第一個字元是非官方指令的「*」，官方指令為空白。上一條
+    /// 指令期間 DMC DMA 暫停過 CPU 時，行尾另外加上「STALL:週期數」。
+    fn trace_line(&self) -> String {
+        let cpu = &self.cpu;
+        let pc = cpu.pc;
+        let len = debugger::instruction_length(self.peek(pc));
+        let text = debugger::disassemble_annotated(pc, cpu.x, cpu.y, |a| self.peek(a));
+        let text = if text.starts_with('*') { text } else { format!(" {}", text) };
+        let mut line = format!(
+            "{:04X}  {:<9}{:<33}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3} CYC:{}",
+            pc, self.hex_bytes(pc, len), text,
+            cpu.a, cpu.x, cpu.y, cpu.status, cpu.sp,
+            self.ppu.scanline, self.ppu.cycle, self.cpu_cycle_count(),
+        );
+        if self.bus.dmc_stall > 0 {
+            line.push_str(&format!(" STALL:{}", self.bus.dmc_stall));
+        }
+        line
     }
 
     // ============================================================
-    // 指令操作
+    // 輸入影片（TAS 錄製、播放與重錄）
     // ============================================================
 
-    fn op_adc(&mut self, value: u8) {
-        let a = self.cpu.a as u16;
-        let v = value as u16;
-        let c = self.carry() as u16;
-        let result = a + v + c;
-        self.set_carry(result > 0xFF);
-        self.set_overflow(((a ^ result) & (v ^ result) & 0x80) != 0);
-        self.cpu.a = result as u8;
-        self.set_zn(self.cpu.a);
+    /// 開始錄製影片
+    /// from_power_on 為 true 時先重置再錄製，否則以目前狀態為起點
+    pub fn movie_start_recording(&mut self, from_power_on: bool) {
+        if from_power_on {
+            self.power_cycle();
+            self.frame_count = 0;
+        }
+        let start_state = self.export_save_state_bytes();
+        self.movie = Some(Movie::new(start_state, self.frame_count, from_power_on));
+        self.movie_mode = MovieMode::Recording;
     }
 
-    fn op_sbc(&mut self, value: u8) {
-        let a = self.cpu.a as u16;
-        let v = value as u16;
-        let c = self.carry() as u16;
-        let result = a.wrapping_sub(v).wrapping_sub(1 - c);
-        self.set_carry(result < 0x100);
-        self.set_overflow(((a ^ result) & (a ^ v) & 0x80) != 0);
-        self.cpu.a = result as u8;
-        self.set_zn(self.cpu.a);
+    /// 從起始存檔開始播放目前的影片，沒有影片時回傳 false
+    pub fn movie_start_playback(&mut self) -> bool {
+        let Some(movie) = &self.movie else { return false };
+        let state = movie.start_state.clone();
+        if !self.import_state_binary(&state) { return false; }
+        self.movie_mode = MovieMode::Playing;
+        true
     }
 
-    fn op_cmp(&mut self, reg: u8, value: u8) {
-        self.set_carry(reg >= value);
-        self.set_zn(reg.wrapping_sub(value));
+    /// 停止錄製或播放（影片內容保留）
+    pub fn movie_stop(&mut self) {
+        self.movie_mode = MovieMode::Inactive;
+    }
+
+    /// 讀入影片檔（Movie::to_bytes 的格式）並從起點開始播放
+    /// 影片檔無效或起始存檔不屬於目前的遊戲時回傳 false，原本的影片保留
+    pub fn movie_play(&mut self, data: &[u8]) -> bool {
+        let Some(movie) = Movie::from_bytes(data) else { return false };
+        if !self.import_state_binary(&movie.start_state) { return false; }
+        self.rewind.clear();
+        self.movie = Some(movie);
+        self.movie_mode = MovieMode::Playing;
+        true
     }
 
-    fn op_bit(&mut self, value: u8) {
-        self.set_overflow(value & 0x40 != 0);
-        if value & 0x80 != 0 { self.cpu.status |= 0x80; } else { self.cpu.status &= !0x80; }
-        let r = self.cpu.a & value;
-        if r == 0 { self.cpu.status |= 0x02; } else { self.cpu.status &= !0x02; }
+    /// 在目前的幀截斷影片並從這裡繼續錄製（計為一次重錄）
+    pub fn movie_truncate(&mut self) -> bool {
+        let frame = self.frame_count;
+        let Some(movie) = &mut self.movie else { return false };
+        let Some(index) = movie.index_of(frame) else { return false };
+        movie.rerecord_at(index);
+        self.movie_mode = MovieMode::Recording;
+        true
     }
 
-    fn op_asl_m(&mut self, addr: u16) {
-        let mut v = self.bus_read(addr); self.set_carry(v & 0x80 != 0);
-        v <<= 1; self.bus_write(addr, v); self.set_zn(v);
+    /// 取得目前的影片
+    pub fn movie(&self) -> Option<&Movie> { self.movie.as_ref() }
+
+    /// 取得影片模式
+    pub fn movie_mode(&self) -> MovieMode { self.movie_mode }
+
+    /// 每幀開始時處理影片輸入：錄製時記錄、播放時套用
+    fn latch_movie_input(&mut self) {
+        let Some(movie) = &mut self.movie else { return };
+        let Some(index) = movie.index_of(self.frame_count) else {
+            self.movie_mode = MovieMode::Inactive;
+            return;
+        };
+        match self.movie_mode {
+            MovieMode::Recording => {
+                movie.record(index, [self.ctrl1.buttons(), self.ctrl2.buttons()]);
+            }
+            MovieMode::Playing => match movie.input_at(index) {
+                Some([p1, p2]) => {
+                    self.ctrl1.set_buttons(p1);
+                    self.ctrl2.set_buttons(p2);
+                }
+                // 影片播放完畢
+                None => self.movie_mode = MovieMode::Inactive,
+            },
+            MovieMode::Inactive => {}
+        }
     }
 
-    fn op_lsr_m(&mut self, addr: u16) {
-        let mut v = self.bus_read(addr); self.set_carry(v & 0x01 != 0);
-        v >>= 1; self.bus_write(addr, v); self.set_zn(v);
+    /// 讀檔；錄製中讀檔視為重錄，影片截斷在存檔所在的幀
+    /// 讀入的存檔與倒帶記錄不一定在同一條時間線上，倒帶記錄一併清除
+    fn load_state_for_movie(&mut self, state: &[u8]) -> bool {
+        if !self.import_state_binary(state) { return false; }
+        self.rewind.clear();
+        if self.movie_mode == MovieMode::Recording {
+            let frame = self.frame_count;
+            if let Some(movie) = &mut self.movie {
+                match movie.index_of(frame) {
+                    Some(index) => movie.rerecord_at(index),
+                    // 讀到影片起點之前的存檔，無法接續錄製
+                    None => self.movie_mode = MovieMode::Inactive,
+                }
+            }
+        }
+        true
     }
 
-    fn op_rol_m(&mut self, addr: u16) {
-        let mut v = self.bus_read(addr); let c = self.carry() as u8;
-        self.set_carry(v & 0x80 != 0); v = (v << 1) | c;
-        self.bus_write(addr, v); self.set_zn(v);
+    /// 每幀更新電池 RAM（或 Mapper EEPROM）的去抖動計時
+    fn update_sram_flush(&mut self) {
+        if self.cartridge.take_save_dirty() {
+            self.sram_pending = true;
+            self.sram_quiet_frames = 0;
+        } else if self.sram_pending {
+            self.sram_quiet_frames += 1;
+            if self.sram_quiet_frames >= self.config.sram_flush_delay {
+                self.sram_pending = false;
+                self.sram_flush_ready = true;
+            }
+        }
     }
 
-    fn op_ror_m(&mut self, addr: u16) {
-        let mut v = self.bus_read(addr); let c = if self.carry() { 0x80u8 } else { 0 };
-        self.set_carry(v & 0x01 != 0); v = (v >> 1) | c;
-        self.bus_write(addr, v); self.set_zn(v);
+    /// 取出已穩定的電池 RAM 資料（寫入停止超過設定幀數後才會有值）
+    /// 卡帶以 EEPROM 存檔時取出的是 EEPROM 內容
+    pub fn take_sram_flush(&mut self) -> Option<Vec<u8>> {
+        if !self.sram_flush_ready { return None; }
+        self.sram_flush_ready = false;
+        self.cartridge.save_data().map(<[u8]>::to_vec)
     }
 
-    fn op_dec_m(&mut self, addr: u16) {
-        let v = self.bus_read(addr).wrapping_sub(1); self.bus_write(addr, v); self.set_zn(v);
+    /// 載入先前儲存的電池 RAM 或 EEPROM 內容（不會觸發儲存事件）
+    /// 卡帶沒有存檔媒體時回傳 false，避免把其他遊戲的存檔寫進工作 RAM
+    pub fn load_sram(&mut self, data: &[u8]) -> bool {
+        self.cartridge.load_save_data(data)
     }
 
-    fn op_inc_m(&mut self, addr: u16) {
-        let v = self.bus_read(addr).wrapping_add(1); self.bus_write(addr, v); self.set_zn(v);
+    /// 立即取出尚未儲存的電池 RAM 資料（例如頁面關閉前），不等待靜止時間
+    /// 沒有電池或 EEPROM 的卡帶一律回傳 None
+    pub fn flush_sram_now(&mut self) -> Option<Vec<u8>> {
+        self.cartridge.save_data()?;
+        let dirty = self.cartridge.take_save_dirty() || self.sram_pending || self.sram_flush_ready;
+        self.sram_pending = false;
+        self.sram_flush_ready = false;
+        if dirty { self.cartridge.save_data().map(<[u8]>::to_vec) } else { None }
     }
 
-    fn op_dcp(&mut self, addr: u16) {
-        let v = self.bus_read(addr).wrapping_sub(1); self.bus_write(addr, v);
-        let a = self.cpu.a; self.op_cmp(a, v);
+    /// 連續執行 n 幀（無頭模式，供自動化回歸測試使用）
+    pub fn run_frames(&mut self, n: u32) {
+        for _ in 0..n {
+            self.frame();
+        }
     }
 
-    fn op_isb(&mut self, addr: u16) {
-        let v = self.bus_read(addr).wrapping_add(1); self.bus_write(addr, v);
-        self.op_sbc(v);
+    /// 計算目前畫面緩衝區的雜湊值（32 位元 FNV-1a）
+    /// 用於與黃金值比對，判斷畫面輸出是否與預期一致
+    pub fn frame_hash(&self) -> u32 {
+        let mut hash: u32 = 0x811C_9DC5;
+        for &b in self.ppu.frame_buffer.iter() {
+            hash ^= b as u32;
+            hash = hash.wrapping_mul(0x0100_0193);
+        }
+        hash
     }
 
-    fn op_slo(&mut self, addr: u16) {
-        let mut v = self.bus_read(addr); self.set_carry(v & 0x80 != 0);
-        v <<= 1; self.bus_write(addr, v);
-        self.cpu.a |= v; self.set_zn(self.cpu.a);
+    // ============================================================
+    // 測試 ROM 結果協定（blargg 系列）
+    // ============================================================
+    // $6000：狀態（0x80 = 執行中，0x81 = 需要按下重置，0x00-0x7F = 結果碼）
+    // $6001-$6003：簽章 DE B0 61，用來確認 ROM 確實使用此協定
+    // $6004-：以 0 結尾的 ASCII 結果訊息
+    // 參考：https://www.nesdev.org/wiki/Emulator_tests
+
+    /// 取得測試 ROM 狀態碼；若 PRG RAM 沒有協定簽章則回傳 None
+    pub fn test_rom_status(&self) -> Option<u8> {
+        let ram = &self.cartridge.prg_ram;
+        if ram.len() < 4 || ram[1..4] != [0xDE, 0xB0, 0x61] {
+            return None;
+        }
+        Some(ram[0])
     }
 
-    fn op_rla(&mut self, addr: u16) {
-        let mut v = self.bus_read(addr); let c = self.carry() as u8;
-        self.set_carry(v & 0x80 != 0); v = (v << 1) | c;
-        self.bus_write(addr, v); self.cpu.a &= v; self.set_zn(self.cpu.a);
+    /// 取得測試 ROM 寫在 $6004 的結果訊息
+    pub fn test_rom_message(&self) -> String {
+        if self.test_rom_status().is_none() {
+            return String::new();
+        }
+        self.cartridge.prg_ram[4..]
+            .iter()
+            .take_while(|&&c| c != 0)
+            .map(|&c| c as char)
+            .collect()
     }
 
-    fn op_sre(&mut self, addr: u16) {
-        let mut v = self.bus_read(addr); self.set_carry(v & 0x01 != 0);
-        v >>= 1; self.bus_write(addr, v);
-        self.cpu.a ^= v; self.set_zn(self.cpu.a);
+    /// 執行測試 ROM 直到回報結果或超過 max_frames 幀
+    /// 狀態為 0x81 時，約 100ms（6 幀）後自動按下重置
+    pub fn run_test_rom(&mut self, max_frames: u32) -> Option<u8> {
+        let mut prev = None;
+        let mut reset_wait = 0u32;
+        for _ in 0..max_frames {
+            self.frame();
+            let status = self.test_rom_status();
+            match status {
+                Some(code) if code < 0x80 => return Some(code),
+                Some(0x81) => {
+                    if prev != Some(0x81) {
+                        reset_wait = 6;
+                    }
+                    if reset_wait > 0 {
+                        reset_wait -= 1;
+                        if reset_wait == 0 {
+                            self.soft_reset();
+                        }
+                    }
+                }
+                _ => {}
+            }
+            prev = status;
+        }
+        None
     }
 
-    fn op_rra(&mut self, addr: u16) {
-        let mut v = self.bus_read(addr); let c = if self.carry() { 0x80u8 } else { 0 };
-        self.set_carry(v & 0x01 != 0); v = (v >> 1) | c;
-        self.bus_write(addr, v); self.op_adc(v);
+    /// 畫面緩衝區（256×240，每像素 RGBA 4 位元組，由左上角逐列排列）
+    pub fn frame_buffer(&self) -> &[u8] { &self.ppu.frame_buffer }
+
+    /// 要顯示的畫面：開啟 crop_overscan 時去掉上下各 8 條掃描線
+    /// （256×224），否則為完整的 256×240；大小見 `frame_size`
+    ///
+    /// 保留的掃描線在緩衝區中是連續的，不需要複製。
+    pub fn visible_frame(&self) -> &[u8] {
+        let top = self.visible_top() as usize;
+        let (_, height) = self.frame_size();
+        &self.ppu.frame_buffer[top * 256 * 4..(top + height as usize) * 256 * 4]
     }
 
-    // ============================================================
-    // 公開 API
-    // ============================================================
+    /// 要顯示的畫面大小（寬, 高）
+    pub fn frame_size(&self) -> (u32, u32) {
+        if self.config.crop_overscan { (256, 240 - 2 * OVERSCAN_LINES) } else { (256, 240) }
+    }
 
-    /// 執行一幀
-    pub fn frame(&mut self) {
-        self.ppu.frame_complete = false;
-        while !self.ppu.frame_complete {
-            self.clock();
-        }
+    /// 要顯示的畫面在完整畫面中的第一條掃描線
+    fn visible_top(&self) -> u32 {
+        if self.config.crop_overscan { OVERSCAN_LINES } else { 0 }
     }
 
-    /// 取得畫面緩衝區指標
-    pub fn get_frame_buffer_ptr(&self) -> *const u8 { self.ppu.frame_buffer.as_ptr() }
+    /// 取得要顯示的畫面指標（見 `visible_frame`）
+    pub fn get_frame_buffer_ptr(&self) -> *const u8 { self.visible_frame().as_ptr() }
 
-    /// 取得畫面緩衝區長度
-    pub fn get_frame_buffer_len(&self) -> usize { self.ppu.frame_buffer.len() }
+    /// 取得要顯示的畫面長度（位元組數）
+    pub fn get_frame_buffer_len(&self) -> usize { self.visible_frame().len() }
 
-    /// 設定控制器按鈕
+    /// 設定控制器按鈕（controller：0-3，button：`Button` 的數值）
+    /// 3P/4P 經 Four Score 分別串在埠 1、埠 2，需先以 set_four_score 接上轉接器
     pub fn set_button(&mut self, controller: u8, button: u8, pressed: bool) {
         match controller {
             0 => self.ctrl1.set_button(button, pressed),
             1 => self.ctrl2.set_button(button, pressed),
+            2 => self.ctrl1.set_extra_button(button, pressed),
+            3 => self.ctrl2.set_extra_button(button, pressed),
             _ => {}
         }
     }
 
+    /// 接上或拔除 Four Score 四人轉接器
+    pub fn set_four_score(&mut self, connected: bool) {
+        let [sig1, sig2] = FOUR_SCORE_SIGNATURES.map(|sig| connected.then_some(sig));
+        self.ctrl1.set_four_score(sig1);
+        self.ctrl2.set_four_score(sig2);
+    }
+
+    /// 設定控制器埠上連接的裝置
+    pub fn set_input_device(&mut self, port: u8, device: InputDevice) {
+        match port {
+            0 => self.ctrl1.set_device(device),
+            1 => self.ctrl2.set_device(device),
+            _ => {}
+        }
+    }
+
+    /// 設定 Zapper 瞄準位置（套用到所有接 Zapper 的埠）
+    /// 座標以完整的 256×240 畫面為準，不受 crop_overscan 影響
+    pub fn set_zapper_position(&mut self, x: i32, y: i32) {
+        self.ctrl1.set_zapper_position(x, y);
+        self.ctrl2.set_zapper_position(x, y);
+    }
+
+    /// 設定 Zapper 扳機
+    pub fn set_zapper_trigger(&mut self, pulled: bool) {
+        self.ctrl1.set_zapper_trigger(pulled);
+        self.ctrl2.set_zapper_trigger(pulled);
+    }
+
+    /// 接上或拔除 Family BASIC 鍵盤
+    pub fn set_keyboard_connected(&mut self, connected: bool) {
+        self.keyboard.set_connected(connected);
+    }
+
+    /// 設定 Family BASIC 鍵盤按鍵（row 0-8、col 0-7，對應表見 keyboard 模組）
+    pub fn set_key_state(&mut self, row: usize, col: usize, pressed: bool) {
+        self.keyboard.set_key_state(row, col, pressed);
+    }
+
+    /// 資料記錄器開始錄音
+    pub fn tape_record(&mut self) {
+        self.data_recorder.record(self.cpu_cycle_count());
+    }
+
+    /// 資料記錄器開始播放錄音帶（tape_stop 取得的內容）
+    pub fn tape_play(&mut self, tape: &[u8]) {
+        self.data_recorder.play(tape, self.cpu_cycle_count());
+    }
+
+    /// 資料記錄器停止，回傳錄音帶內容
+    pub fn tape_stop(&mut self) -> Vec<u8> {
+        self.data_recorder.stop(self.cpu_cycle_count())
+    }
+
+    /// 瞄準畫面外扣一次 Zapper 扳機（打空或換彈）
+    pub fn fire_zapper_offscreen(&mut self) {
+        self.ctrl1.fire_zapper_offscreen();
+        self.ctrl2.fire_zapper_offscreen();
+    }
+
+    /// 設定旋鈕位置
+    pub fn set_paddle_value(&mut self, value: u8) {
+        self.ctrl1.set_paddle_value(value);
+        self.ctrl2.set_paddle_value(value);
+    }
+
+    /// 設定旋鈕按鈕
+    pub fn set_paddle_button(&mut self, pressed: bool) {
+        self.ctrl1.set_paddle_button(pressed);
+        self.ctrl2.set_paddle_button(pressed);
+    }
+
     /// 設定音頻取樣率
-    pub fn set_audio_sample_rate(&mut self, rate: f64) { self.apu.set_sample_rate(rate); }
+    pub fn set_audio_sample_rate(&mut self, rate: f64) {
+        let mut config = self.config.clone();
+        config.sample_rate = rate;
+        self.set_config(config);
+    }
+
+    /// 取得目前設定
+    pub fn config(&self) -> &EmulatorConfig { &self.config }
+
+    /// 強制指定主機地區，None 表示回到依 ROM 標頭判斷
+    pub fn set_region(&mut self, region: Option<Region>) {
+        let mut config = self.config.clone();
+        config.region = region;
+        self.set_config(config);
+    }
+
+    /// 目前採用的主機地區
+    pub fn region(&self) -> Region { self.region }
+
+    /// 依設定（或 ROM 標頭）決定地區，並切換 PPU/APU 的時序與調色盤
+    fn apply_region(&mut self) {
+        self.region = self.config.region.unwrap_or(self.cartridge.header.region);
+        self.cpu_divider = self.region.ppu_dots_per_cpu_cycle();
+        self.ppu.set_region(self.region);
+        self.apu.set_region(self.region);
+    }
+
+    /// 套用設定，並一次推送到各子系統
+    pub fn set_config(&mut self, config: EmulatorConfig) {
+        self.apu.set_sample_rate(config.sample_rate);
+        self.apu.set_filter_enabled(config.audio_filter);
+        self.apu.set_stereo(config.stereo);
+        self.apu.set_accurate_triangle(config.accurate_triangle);
+        self.apu.set_reduce_popping(config.reduce_popping);
+        self.ppu.set_sprite_limit(config.sprite_limit);
+        self.cpu.unstable_magic = config.unstable_magic;
+        if config.power_on_seed != self.config.power_on_seed
+            || config.power_on_alignment != self.config.power_on_alignment
+        {
+            self.alignment_rng = config.power_on_seed;
+        }
+        self.rewind.configure(config.rewind_depth as usize, config.rewind_interval);
+        let region_changed = config.region != self.config.region;
+        self.config = config;
+        if region_changed {
+            self.apply_region();
+        }
+    }
 
     /// 取得音頻緩衝區指標
-    pub fn get_audio_buffer_ptr(&self) -> *const f32 { self.apu.get_buffer_ptr() }
+    pub fn get_audio_buffer_ptr(&mut self) -> *const f32 { self.apu.get_buffer_ptr() }
 
     /// 取得音頻緩衝區可用取樣數
     pub fn get_audio_buffer_len(&self) -> usize { self.apu.get_available_samples() }
@@ -904,38 +1691,135 @@ impl Emulator {
     /// 消耗音頻取樣
     pub fn consume_audio_samples(&mut self) -> usize { self.apu.consume_samples() }
 
-    /// 匯出存檔（hex 編碼）
+    /// 音頻緩衝區已滿而丟棄的取樣總數（持續增加表示前端取用太慢）
+    pub fn audio_overflow_count(&self) -> u64 { self.apu.overflowed_samples() }
+
+    /// 取出所有可用的音頻取樣（複製一份，不受 WASM 記憶體成長影響）
+    pub fn take_audio_samples(&mut self) -> Vec<f32> {
+        let mut out = vec![0.0; self.apu.get_available_samples()];
+        self.apu.take_samples_into(&mut out);
+        out
+    }
+
+    /// 取出音頻取樣到呼叫端的緩衝區，回傳取出的數量
+    pub fn fill_audio_samples(&mut self, out: &mut [f32]) -> usize {
+        self.apu.take_samples_into(out)
+    }
+
+    /// 匯出二進位存檔
+    pub fn export_save_state_bytes(&self) -> Vec<u8> {
+        let mut d = Vec::new();
+        self.write_state_binary(&mut d);
+        d
+    }
+
+    /// 匯出存檔（hex 編碼，內容與 export_save_state_bytes 相同）
     pub fn export_save_state(&self) -> String {
-        self.export_state_binary().iter().map(|b| format!("{:02x}", b)).collect()
+        let state = self.export_save_state_bytes();
+        let mut hex = String::with_capacity(state.len() * 2);
+        savestate::hex_encode_into(&state, &mut hex);
+        hex
+    }
+
+    /// 匯出 hex 存檔到呼叫端的字串（先清空，沿用既有容量）
+    pub fn export_save_state_into(&mut self, out: &mut String) {
+        let mut state = std::mem::take(&mut self.state_scratch);
+        self.save_state_into(&mut state);
+        out.clear();
+        savestate::hex_encode_into(&state, out);
+        self.state_scratch = state;
     }
 
-    /// 匯入存檔
+    /// 匯入 hex 編碼的存檔
     pub fn import_save_state(&mut self, hex: &str) -> bool {
-        if hex.len() % 2 != 0 { return false; }
-        let mut data = Vec::with_capacity(hex.len() / 2);
-        let bytes = hex.as_bytes();
-        for i in (0..bytes.len()).step_by(2) {
-            let hi = Self::hex_char(bytes[i]);
-            let lo = Self::hex_char(bytes[i + 1]);
-            if hi == 0xFF || lo == 0xFF { return false; }
-            data.push((hi << 4) | lo);
+        let mut state = std::mem::take(&mut self.state_scratch);
+        let ok = savestate::hex_decode_into(hex, &mut state) && self.load_state_for_movie(&state);
+        self.state_scratch = state;
+        ok
+    }
+
+    /// 把二進位存檔寫入呼叫端的緩衝區（先清空，沿用既有容量）
+    /// 每幀存檔（倒帶、預先執行）時重複傳入同一個 Vec 就不會再配置記憶體
+    pub fn save_state_into(&self, out: &mut Vec<u8>) {
+        out.clear();
+        self.write_state_binary(out);
+    }
+
+    /// 把二進位存檔複製到固定大小的緩衝區（例如 JS 端重複使用的 Uint8Array）
+    /// 回傳存檔的完整大小；緩衝區放不下時不寫入，呼叫端依回傳值配置後再呼叫
+    pub fn save_state_to_slice(&mut self, out: &mut [u8]) -> usize {
+        let mut state = std::mem::take(&mut self.state_scratch);
+        self.save_state_into(&mut state);
+        let len = state.len();
+        if let Some(dest) = out.get_mut(..len) {
+            dest.copy_from_slice(&state);
         }
-        self.import_state_binary(&data)
+        self.state_scratch = state;
+        len
     }
 
-    fn hex_char(c: u8) -> u8 {
-        match c {
-            b'0'..=b'9' => c - b'0',
-            b'a'..=b'f' => c - b'a' + 10,
-            b'A'..=b'F' => c - b'A' + 10,
-            _ => 0xFF,
+    /// 讀取二進位存檔（save_state_into 的格式），資料無效時回傳 false
+    pub fn load_state(&mut self, data: &[u8]) -> bool {
+        self.load_state_for_movie(data)
+    }
+
+    /// 存入快速存檔槽（timestamp 由前端提供，單位毫秒）
+    pub fn save_slot(&mut self, slot: usize, timestamp: f64) -> bool {
+        let mut state = std::mem::take(&mut self.state_scratch);
+        self.save_state_into(&mut state);
+        let hash = self.frame_hash();
+        let ok = self.slots.store(slot, &state, timestamp, hash);
+        self.state_scratch = state;
+        ok
+    }
+
+    /// 從快速存檔槽讀檔，槽位為空或資料無效時回傳 false
+    pub fn load_slot(&mut self, slot: usize) -> bool {
+        let mut state = std::mem::take(&mut self.state_scratch);
+        let ok = self.slots.load_into(slot, &mut state) && self.load_state_for_movie(&state);
+        self.state_scratch = state;
+        ok
+    }
+
+    /// 幀結束時依間隔擷取倒帶記錄
+    fn capture_rewind(&mut self) {
+        if self.rewind.tick() {
+            let mut rewind = std::mem::take(&mut self.rewind);
+            rewind.capture(self.frame_count, |out| self.save_state_into(out));
+            self.rewind = rewind;
         }
     }
 
-    fn export_state_binary(&self) -> Vec<u8> {
-        let mut d = Vec::new();
+    /// 倒退約 frames 幀（實際落在最近一份倒帶記錄上），回傳實際倒退的幀數
+    ///
+    /// 沒有記錄時不動作並回傳 0；連續呼叫會沿著記錄一路往回。
+    pub fn rewind(&mut self, frames: u32) -> u32 {
+        let before = self.frame_count;
+        let target = before.saturating_sub(u64::from(frames));
+        let mut rewind = std::mem::take(&mut self.rewind);
+        let restored = rewind.restore(target).is_some_and(|state| self.load_state_for_movie(state));
+        self.rewind = rewind;
+        if !restored {
+            return 0;
+        }
+        self.rewind.restart_interval();
+        before.saturating_sub(self.frame_count) as u32
+    }
+
+    /// 目前最多可以倒退的幀數
+    pub fn rewind_available(&self) -> u64 {
+        self.rewind.oldest_frame().map_or(0, |frame| self.frame_count.saturating_sub(frame))
+    }
+
+    /// 清除快速存檔槽
+    pub fn clear_slot(&mut self, slot: usize) { self.slots.clear(slot); }
+
+    /// 取得存檔槽集合
+    pub fn slots(&self) -> &SaveSlots { &self.slots }
+
+    fn write_state_binary(&self, d: &mut Vec<u8>) {
         d.extend_from_slice(b"NESW");
-        d.push(1);
+        d.push(STATE_VERSION);
         d.push(self.cpu.a); d.push(self.cpu.x); d.push(self.cpu.y);
         d.push(self.cpu.sp); d.push(self.cpu.status);
         d.extend_from_slice(&self.cpu.pc.to_le_bytes());
@@ -949,38 +1833,240 @@ impl Emulator {
         d.extend_from_slice(&self.ppu.nametable);
         d.extend_from_slice(&self.ppu.palette);
         d.extend_from_slice(&self.ppu.oam);
+        // v4：PRG RAM 以長度開頭，卡帶沒有 PRG RAM 時長度為 0
+        d.extend_from_slice(&(self.cartridge.prg_ram.len() as u32).to_le_bytes());
         d.extend_from_slice(&self.cartridge.prg_ram);
-        d
+        // v2：匯流排 DMA、控制器讀取鎖存與主時鐘（DMA 奇偶週期對齊依賴主時鐘）
+        d.push(self.bus.dma_page); d.push(self.bus.dma_address);
+        d.push(self.bus.dma_data); d.push(self.bus.dma_transfer as u8);
+        d.push(self.bus.dma_dummy as u8);
+        for ctrl in [&self.ctrl1, &self.ctrl2] {
+            let (shift, strobe) = ctrl.latch_state();
+            d.push(shift); d.push(strobe as u8);
+        }
+        d.extend_from_slice(&self.system_clock.to_le_bytes());
+        d.extend_from_slice(&self.frame_count.to_le_bytes());
+        // v5：各子系統的內部狀態，以長度開頭（讀檔時先確認整段都在）
+        let section = d.len();
+        d.extend_from_slice(&[0; 4]);
+        self.write_internal_state(d);
+        let len = (d.len() - section - 4) as u32;
+        d[section..section + 4].copy_from_slice(&len.to_le_bytes());
+    }
+
+    /// v5 區段：CPU 週期與中斷、PPU/APU 內部狀態、Mapper 暫存器與 CHR RAM
+    fn write_internal_state(&self, d: &mut Vec<u8>) {
+        self.cpu.cycles.save(d);
+        self.cpu.total_cycles.save(d);
+        self.cpu.nmi_pending.save(d);
+        self.cpu.irq_pending.save(d);
+        self.instruction_count.save(d);
+        self.cartridge.header.mirror_mode.save(d);
+        self.ppu.save(d);
+        self.apu.save(d);
+        self.cartridge.mapper.save_state(d);
+        // CHR ROM 不必存；CHR RAM（含混合 CHR ROM/RAM 的卡帶）整塊保存
+        let chr: &[u8] = if self.chr_writable() { &self.cartridge.chr_data } else { &[] };
+        (chr.len() as u32).save(d);
+        d.extend_from_slice(chr);
+        // v6
+        self.cheats.save(d);
+        // v7
+        self.ctrl1.save(d);
+        self.ctrl2.save(d);
+        // v8
+        self.bus.dmc_halt.save(d);
+        self.bus.last_read_addr.save(d);
+        // v9
+        self.bus.open_bus.save(d);
+        // v10
+        self.ppu.open_bus.save(d);
+        self.ppu.open_bus_decay.save(d);
+        // v11
+        self.cpu.poll.save(d);
+        // v13（v12 的降頻取樣器已由階躍合成器取代）
+        self.apu.synth.save(d);
+        // v14
+        self.apu.save_pending_writes(d);
+        // v15
+        self.apu.save_triangle_ramp(d);
+        // v16
+        self.apu.save_dmc_timing(d);
+        // v17
+        if let Some(eeprom) = self.cartridge.mapper.eeprom() {
+            eeprom.save(d);
+        }
+    }
+
+    /// 讀回 v5 區段（必須剛好用完整段資料）；v5 存檔沒有金手指，保留目前的清單
+    fn read_internal_state(&mut self, data: &[u8], version: u8) -> Option<()> {
+        let mut r = StateReader::new(data);
+        self.cpu.cycles.load(&mut r)?;
+        self.cpu.total_cycles.load(&mut r)?;
+        self.cpu.nmi_pending.load(&mut r)?;
+        self.cpu.irq_pending.load(&mut r)?;
+        self.instruction_count.load(&mut r)?;
+        self.cartridge.header.mirror_mode.load(&mut r)?;
+        self.ppu.load_render_state(&mut r)?;
+        self.apu.load_channel_state(&mut r)?;
+        self.cartridge.mapper.load_state(&mut r)?;
+        let len = u32::from_le_bytes(r.array()?) as usize;
+        let expected = if self.chr_writable() { self.cartridge.chr_data.len() } else { 0 };
+        if len != expected { return None; }
+        let chr = r.take(len)?;
+        if len > 0 {
+            self.cartridge.chr_data.copy_from_slice(chr);
+        }
+        if version >= 6 {
+            self.cheats.load(&mut r)?;
+        }
+        if version >= 7 {
+            self.ctrl1.load(&mut r)?;
+            self.ctrl2.load(&mut r)?;
+        }
+        if version >= 8 {
+            self.bus.dmc_halt.load(&mut r)?;
+            self.bus.last_read_addr.load(&mut r)?;
+        } else {
+            self.bus.dmc_halt = 0;
+        }
+        if version >= 9 {
+            self.bus.open_bus.load(&mut r)?;
+        }
+        if version >= 10 {
+            self.ppu.open_bus.load(&mut r)?;
+            self.ppu.open_bus_decay.load(&mut r)?;
+        }
+        if version >= 11 {
+            self.cpu.poll.load(&mut r)?;
+        } else {
+            // 舊存檔在指令邊界直接檢查待處理的中斷
+            self.cpu.poll = InterruptPoll { at: 1, ..InterruptPoll::default() };
+            self.cpu.poll_interrupts();
+        }
+        if version >= 13 {
+            self.apu.synth.load(&mut r)?;
+        } else {
+            // v12 的降頻取樣器狀態（24 位元組）不再使用
+            if version == 12 {
+                r.take(24)?;
+            }
+            self.apu.synth.reset();
+        }
+        self.apu.load_pending_writes(&mut r, version)?;
+        self.apu.load_triangle_ramp(&mut r, version)?;
+        self.apu.load_dmc_timing(&mut r, version)?;
+        if version >= 17 {
+            if let Some(eeprom) = self.cartridge.mapper.eeprom_mut() {
+                eeprom.load(&mut r)?;
+            }
+        }
+        self.apu.sync_stereo_state();
+        (r.position() == data.len()).then_some(())
+    }
+
+    /// 卡帶是否有可寫入的 CHR（需要存檔）
+    fn chr_writable(&self) -> bool {
+        self.cartridge.chr_ram || self.cartridge.mapper.chr_writable_mask() != 0
     }
 
+    /// 讀取二進位存檔；資料無效時模擬器維持原本的狀態
     fn import_state_binary(&mut self, data: &[u8]) -> bool {
-        if data.len() < 9 || &data[0..4] != b"NESW" || data[4] != 1 { return false; }
+        // 長度等基本檢查在寫入任何欄位前完成；v5 區段的內容（鏡像模式、
+        // Mapper 狀態等）要讀到才知道是否有效，失敗時以讀檔前的狀態還原。
+        // 備份寫進重複使用的緩衝區，倒帶每一步讀檔都不必配置記憶體
+        let mut backup = std::mem::take(&mut self.state_backup);
+        self.save_state_into(&mut backup);
+        let ok = self.apply_state_binary(data);
+        if !ok {
+            self.apply_state_binary(&backup);
+        }
+        self.state_backup = backup;
+        ok
+    }
+
+    fn apply_state_binary(&mut self, data: &[u8]) -> bool {
+        if data.len() < 9 || &data[0..4] != b"NESW" { return false; }
+        let version = data[4];
+        if version == 0 || version > STATE_VERSION { return false; }
+        // 先用標頭算出完整長度再寫入：資料被截斷或 PRG RAM 長度不符時
+        // 模擬器維持原本的狀態，不會只載入一半
+        const RAM_OFFSET: usize = 5 + 7 + 2048 + 11 + 2048 + 32 + 256;
+        let (ram_start, ram_len) = if version < 4 {
+            (RAM_OFFSET, 8192)
+        } else {
+            let Some(len) = data.get(RAM_OFFSET..RAM_OFFSET + 4) else { return false };
+            (RAM_OFFSET + 4, u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize)
+        };
+        let tail = match version { 1 => 0, 2 => 5 + 4 + 8, _ => 5 + 4 + 8 + 8 };
+        let Some(end) = ram_start.checked_add(ram_len).and_then(|n| n.checked_add(tail)) else { return false };
+        if data.len() < end { return false; }
+        let section = if version >= 5 {
+            let Some(len) = data.get(end..end + 4) else { return false };
+            let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+            let Some(section) = data.get(end + 4..).filter(|s| s.len() == len) else { return false };
+            Some(section)
+        } else {
+            None
+        };
+        // v3 以前固定存 8KB；沒有 PRG RAM 的卡帶直接略過
+        let ram_matches = ram_len == self.cartridge.prg_ram.len();
+        if !ram_matches && (version >= 4 || !self.cartridge.prg_ram.is_empty()) { return false; }
+
         let mut p = 5;
-        if p + 7 > data.len() { return false; }
         self.cpu.a = data[p]; p += 1;
         self.cpu.x = data[p]; p += 1;
         self.cpu.y = data[p]; p += 1;
         self.cpu.sp = data[p]; p += 1;
         self.cpu.status = data[p]; p += 1;
         self.cpu.pc = u16::from_le_bytes([data[p], data[p+1]]); p += 2;
-        if p + 2048 > data.len() { return false; }
         self.bus.ram.copy_from_slice(&data[p..p+2048]); p += 2048;
-        if p + 9 > data.len() { return false; }
         self.ppu.ctrl = data[p]; p += 1;
         self.ppu.mask = data[p]; p += 1;
         self.ppu.status = data[p]; p += 1;
         self.ppu.oam_addr = data[p]; p += 1;
-        self.ppu.v = u16::from_le_bytes([data[p], data[p+1]]); p += 2;
-        self.ppu.t = u16::from_le_bytes([data[p], data[p+1]]); p += 2;
-        self.ppu.fine_x = data[p]; p += 1;
+        // v/t 為 15 位元、fine_x 為 3 位元，遮罩掉損毀資料的多餘位元
+        self.ppu.v = u16::from_le_bytes([data[p], data[p+1]]) & 0x7FFF; p += 2;
+        self.ppu.t = u16::from_le_bytes([data[p], data[p+1]]) & 0x7FFF; p += 2;
+        self.ppu.fine_x = data[p] & 0x07; p += 1;
         self.ppu.write_latch = data[p] != 0; p += 1;
         self.ppu.data_buffer = data[p]; p += 1;
-        if p + 2048 + 32 + 256 > data.len() { return false; }
         self.ppu.nametable.copy_from_slice(&data[p..p+2048]); p += 2048;
         self.ppu.palette.copy_from_slice(&data[p..p+32]); p += 32;
-        self.ppu.oam.copy_from_slice(&data[p..p+256]); p += 256;
-        if p + 8192 > data.len() { return false; }
-        self.cartridge.prg_ram.copy_from_slice(&data[p..p+8192]);
+        self.ppu.oam.copy_from_slice(&data[p..p+256]);
+        p = ram_start;
+        if ram_matches {
+            self.cartridge.prg_ram.copy_from_slice(&data[p..p+ram_len]);
+        }
+        p += ram_len;
+        if version < 2 {
+            // v1 存檔沒有匯流排/控制器狀態，視為不在 DMA 中
+            self.bus.dma_transfer = false;
+            return true;
+        }
+        self.bus.dma_page = data[p]; p += 1;
+        self.bus.dma_address = data[p]; p += 1;
+        self.bus.dma_data = data[p]; p += 1;
+        self.bus.dma_transfer = data[p] != 0; p += 1;
+        self.bus.dma_dummy = data[p] != 0; p += 1;
+        for ctrl in [&mut self.ctrl1, &mut self.ctrl2] {
+            ctrl.set_latch_state(data[p], data[p + 1] != 0); p += 2;
+        }
+        let mut clock = [0u8; 8];
+        clock.copy_from_slice(&data[p..p+8]);
+        self.system_clock = u64::from_le_bytes(clock); p += 8;
+        if version < 3 {
+            return true;
+        }
+        let mut frames = [0u8; 8];
+        frames.copy_from_slice(&data[p..p+8]);
+        self.frame_count = u64::from_le_bytes(frames);
+        self.frame_started = false;
+        if let Some(section) = section {
+            if self.read_internal_state(section, version).is_none() {
+                return false;
+            }
+        }
         true
     }
 }
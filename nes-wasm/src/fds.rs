@@ -0,0 +1,896 @@
+// ============================================================
+// FDS - Famicom Disk System（磁碟映像檔、磁碟機與 RAM 轉接器）
+// ============================================================
+// Famicom Disk System 的遊戲以磁碟面為單位（每面 65500 位元組），
+// 映像檔可能帶有 16 位元組的 fwNES 標頭（"FDS\x1A" + 面數），
+// 也可能直接從第一面的磁碟資訊區塊開始（"\x01*NINTENDO-HVC*"）。
+//
+// .fds 只保存區塊內容，實際的磁碟在區塊之間有間隙（0）、區塊前有
+// 起始標記 $80、區塊後有 2 位元組 CRC。BIOS 靠間隙與起始標記找區塊，
+// 所以插入磁碟機的是補上間隙後的磁碟面；CRC 不驗證（$4030 的 CRC
+// 錯誤位元恆為 0），寫入時的 CRC 一律寫 0。
+//
+// RAM 轉接器（FdsMapper）：
+// - $6000-$DFFF：32KB RAM（放在卡帶的 PRG RAM），$E000-$FFFF：8KB BIOS
+// - CHR：8KB RAM
+// - $4020-$4026：計時器 IRQ、磁碟讀寫與鏡像控制；$4030-$4033：狀態
+// - $4040-$408A：波表擴充音效（64 步 6 位元波表，含頻率調變單元）
+//
+// 磁頭每約 150 個 CPU 週期讀寫一個位元組，轉到底後馬達停止；
+// 重新啟動馬達要等磁頭回到起點。遊戲要求換面時，通常會在畫面上提示
+// 並緊密輪詢 $4032 等待磁碟退出，轉接器偵測到這種輪詢就呼叫
+// request_swap，模擬器在幀結束後通知前端顯示「請插入 B 面」之類的提示。
+//
+// 存檔包含轉接器、音效與磁頭位置，不包含磁碟內容（遊戲寫回磁碟的
+// 資料在讀檔後保留目前的內容）。
+//
+// 參考：
+// - https://www.nesdev.org/wiki/FDS_file_format
+// - https://www.nesdev.org/wiki/Family_Computer_Disk_System
+// - https://www.nesdev.org/wiki/FDS_audio
+// ============================================================
+
+use crate::apu::{ExpansionAudio, ExpansionChip};
+use crate::cartridge::crc32;
+use crate::mappers::{MapperTrait, MapperWriteResult};
+use crate::ppu::MirrorMode;
+use crate::savestate::{impl_state_fields, mapper_state};
+
+/// 每個磁碟面的大小（位元組，不含 CRC 與間隙）
+pub const SIDE_SIZE: usize = 65500;
+
+/// BIOS 大小（$E000-$FFFF）
+pub const BIOS_SIZE: usize = 0x2000;
+
+/// 轉接器 RAM 大小（$6000-$DFFF）
+pub const FDS_RAM_SIZE: usize = 0x8000;
+
+/// 磁碟資訊區塊的識別字串
+const DISK_VERIFY: &[u8; 15] = b"\x01*NINTENDO-HVC*";
+
+/// 磁碟面開頭的間隙（28300 位元）
+const LEAD_IN_GAP: usize = 28300 / 8;
+/// 區塊之間的間隙（976 位元）
+const BLOCK_GAP: usize = 976 / 8;
+/// 區塊起始標記
+const BLOCK_START: u8 = 0x80;
+
+/// 換面時從退出到插入新磁碟面的間隔（CPU 週期，約半秒），
+/// BIOS 要先看到磁碟退出才會重新讀取
+pub const DISK_SWAP_DELAY: u32 = 900_000;
+
+/// 單一磁碟面的資訊（取自磁碟資訊區塊）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskSideInfo {
+    /// 在映像檔中的索引
+    pub index: usize,
+    /// 遊戲代碼（3 個字元）
+    pub game_name: String,
+    /// 磁碟編號（第幾片磁碟）
+    pub disk_number: u8,
+    /// 面（0 = A 面，1 = B 面）
+    pub side: u8,
+}
+
+/// FDS 磁碟映像檔
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FdsImage {
+    sides: Vec<Vec<u8>>,
+}
+
+impl FdsImage {
+    /// 是否為 FDS 映像檔（fwNES 標頭或磁碟資訊區塊開頭）
+    pub fn is_fds(data: &[u8]) -> bool {
+        data.starts_with(b"FDS\x1A") || data.starts_with(DISK_VERIFY)
+    }
+
+    /// 解析映像檔，格式錯誤時回傳 None
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let body = if data.starts_with(b"FDS\x1A") { data.get(16..)? } else { data };
+        let count = body.len() / SIDE_SIZE;
+        if count == 0 {
+            return None;
+        }
+        let sides: Vec<Vec<u8>> = body.chunks_exact(SIDE_SIZE).map(|s| s.to_vec()).collect();
+        if !sides.iter().all(|s| s.starts_with(DISK_VERIFY)) {
+            return None;
+        }
+        Some(FdsImage { sides })
+    }
+
+    /// 磁碟面數
+    pub fn side_count(&self) -> usize {
+        self.sides.len()
+    }
+
+    /// 磁碟面資料
+    pub fn side(&self, index: usize) -> Option<&[u8]> {
+        self.sides.get(index).map(|s| s.as_slice())
+    }
+
+    /// 磁碟面資料（可寫，遊戲存檔寫回磁碟時使用）
+    pub fn side_mut(&mut self, index: usize) -> Option<&mut [u8]> {
+        self.sides.get_mut(index).map(|s| s.as_mut_slice())
+    }
+
+    /// 所有磁碟面內容的 CRC32（不含 fwNES 標頭）
+    pub fn crc32(&self) -> u32 {
+        crc32(&self.sides.concat())
+    }
+
+    /// 補上間隙、起始標記與 CRC 位置後的磁碟面（磁碟機實際轉動的內容）
+    ///
+    /// 依區塊類型計算長度（1：磁碟資訊 56、2：檔案數 2、3：檔頭 16、
+    /// 4：檔案內容，長度取自前一個檔頭），遇到未知類型即視為結束。
+    pub fn track(&self, index: usize) -> Option<Vec<u8>> {
+        let side = self.sides.get(index)?;
+        let mut track = vec![0; LEAD_IN_GAP];
+        let mut pos = 0;
+        while pos < side.len() {
+            let len = match side[pos] {
+                1 => 56,
+                2 => 2,
+                3 => 16,
+                4 if pos >= 3 => 1 + u16::from_le_bytes([side[pos - 3], side[pos - 2]]) as usize,
+                _ => break,
+            };
+            let Some(block) = side.get(pos..pos + len) else { break };
+            track.push(BLOCK_START);
+            track.extend_from_slice(block);
+            track.extend_from_slice(&[0, 0]);
+            track.resize(track.len() + BLOCK_GAP, 0);
+            pos += len;
+        }
+        track.resize(track.len().max(LEAD_IN_GAP + SIDE_SIZE), 0);
+        Some(track)
+    }
+
+    /// 各磁碟面的資訊
+    pub fn side_info(&self) -> Vec<DiskSideInfo> {
+        self.sides.iter().enumerate().map(|(index, s)| DiskSideInfo {
+            index,
+            game_name: s[16..19].iter().map(|&c| if c.is_ascii_alphanumeric() { c as char } else { '?' }).collect(),
+            disk_number: s[22],
+            side: s[21] & 1,
+        }).collect()
+    }
+}
+
+/// 磁碟機
+#[derive(Debug, Clone, Default)]
+pub struct DiskDrive {
+    image: Option<FdsImage>,
+    /// 各磁碟面轉動的內容（含間隙，寫入會改變這裡）
+    tracks: Vec<Vec<u8>>,
+    inserted: Option<usize>,
+    /// 磁頭位置（在 tracks 中的偏移）
+    head: usize,
+    /// 換面中：等待插入的磁碟面與剩餘週期
+    pending_side: Option<usize>,
+    swap_delay: u32,
+    swap_requested: bool,
+}
+
+impl_state_fields!(DiskDrive { inserted, head, pending_side, swap_delay });
+
+impl DiskDrive {
+    /// 建立空的磁碟機
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 放入新的映像檔（預設插入第一面）
+    pub fn load_image(&mut self, image: FdsImage) {
+        self.tracks = (0..image.side_count()).filter_map(|i| image.track(i)).collect();
+        self.image = Some(image);
+        self.inserted = Some(0);
+        self.head = 0;
+        self.pending_side = None;
+        self.swap_requested = false;
+    }
+
+    /// 映像檔
+    pub fn image(&self) -> Option<&FdsImage> {
+        self.image.as_ref()
+    }
+
+    /// 插入指定的磁碟面，索引超出範圍回傳 false
+    pub fn insert(&mut self, side: usize) -> bool {
+        if side >= self.tracks.len() {
+            return false;
+        }
+        self.inserted = Some(side);
+        self.head = 0;
+        self.pending_side = None;
+        self.swap_requested = false;
+        true
+    }
+
+    /// 換面：立即退出，DISK_SWAP_DELAY 週期後插入指定的面（由 clock 推進）
+    pub fn swap_to(&mut self, side: usize) -> bool {
+        if side >= self.tracks.len() {
+            return false;
+        }
+        self.eject();
+        self.pending_side = Some(side);
+        self.swap_delay = DISK_SWAP_DELAY;
+        true
+    }
+
+    /// 退出磁碟（取消進行中的換面）
+    pub fn eject(&mut self) {
+        self.inserted = None;
+        self.pending_side = None;
+    }
+
+    /// 目前插入的磁碟面
+    pub fn inserted(&self) -> Option<usize> {
+        self.inserted
+    }
+
+    /// 每個 CPU 週期呼叫，推進換面的等待時間
+    pub fn clock(&mut self) {
+        let Some(side) = self.pending_side else { return };
+        self.swap_delay = self.swap_delay.saturating_sub(1);
+        if self.swap_delay == 0 {
+            self.insert(side);
+        }
+    }
+
+    /// 磁頭回到磁碟面起點
+    pub fn rewind_head(&mut self) {
+        self.head = 0;
+    }
+
+    /// 讀取磁頭下的位元組（沒有磁碟時為 0）
+    pub fn read_head(&self) -> u8 {
+        self.track().and_then(|t| t.get(self.head)).copied().unwrap_or(0)
+    }
+
+    /// 寫入磁頭下的位元組
+    pub fn write_head(&mut self, data: u8) {
+        let head = self.head;
+        if let Some(byte) = self.inserted.and_then(|i| self.tracks.get_mut(i)).and_then(|t| t.get_mut(head)) {
+            *byte = data;
+        }
+    }
+
+    /// 磁頭前進一個位元組，到達磁碟面盡頭時回傳 false
+    pub fn advance_head(&mut self) -> bool {
+        self.head += 1;
+        self.head < self.track().map_or(0, |t| t.len())
+    }
+
+    /// 目前插入的磁碟面內容
+    fn track(&self) -> Option<&Vec<u8>> {
+        self.tracks.get(self.inserted?)
+    }
+
+    /// 遊戲要求換面（由 RAM 轉接器呼叫）
+    pub fn request_swap(&mut self) {
+        self.swap_requested = true;
+    }
+
+    /// 取出換面要求（每次要求只回傳一次 true）
+    pub fn take_swap_request(&mut self) -> bool {
+        std::mem::take(&mut self.swap_requested)
+    }
+
+    /// 將磁碟面清單輸出為 JSON 陣列
+    pub fn sides_json(&self) -> String {
+        let items: Vec<String> = self.image.as_ref().map_or(Vec::new(), |image| {
+            image.side_info().iter().map(|s| format!(
+                "{{\"index\":{},\"gameName\":\"{}\",\"diskNumber\":{},\"side\":\"{}\",\"inserted\":{}}}",
+                s.index, s.game_name, s.disk_number,
+                if s.side == 0 { "A" } else { "B" }, self.inserted == Some(s.index),
+            )).collect()
+        });
+        format!("[{}]", items.join(","))
+    }
+}
+
+// ============================================================
+// FDS 擴充音效 - 64 步波表與頻率調變
+// ============================================================
+// - 波表：64 個 6 位元取樣（$4040-$407F，$4089 位元 7 開啟時才可寫入，
+//   寫入期間輸出維持不變）；12 位元頻率每個 CPU 週期加進 16 位元累加器，
+//   溢位時前進一步
+// - 音量與調變各有一個包絡（$4080/$4084），週期為
+//   8 × (速度 + 1) × 主包絡速度（$408A）個 CPU 週期
+// - 調變單元：32 個 3 位元項目（每項用兩次）依調變頻率逐步改變 7 位元
+//   有號計數器，計數器乘上調變增益後改變波表的頻率
+// - 主音量（$4089 位元 0-1）：2/2、2/3、2/4、2/5
+// ============================================================
+
+/// 主音量對應的倍率（輸出 = 波表 × min(增益, 32) × 倍率 / 1152，最大 63）
+const WAVE_VOLUME: [u32; 4] = [36, 24, 17, 14];
+/// 調變表項目對計數器的影響（4 表示歸零）
+const MOD_STEPS: [i16; 8] = [0, 1, 2, 4, 0, -4, -2, -1];
+/// FDS 相對於 APU 混音的音量（滿音量約為 2A03 方波的 2.4 倍）
+const FDS_GAIN: f32 = 0.36;
+
+/// 音量/調變包絡
+#[derive(Debug, Clone, Default)]
+struct FdsEnvelope {
+    /// 速度（或直接模式下的增益）
+    speed: u8,
+    /// 遞增模式
+    increase: bool,
+    /// 直接模式：增益固定為 speed，包絡不動作
+    direct: bool,
+    /// 目前增益（0-63，輸出時以 32 為上限）
+    gain: u8,
+    /// 距離下一步的 CPU 週期
+    timer: u32,
+}
+
+impl_state_fields!(FdsEnvelope { speed, increase, direct, gain, timer });
+
+impl FdsEnvelope {
+    /// 寫入 $4080/$4084
+    fn write(&mut self, data: u8, master_speed: u8) {
+        self.speed = data & 0x3F;
+        self.increase = data & 0x40 != 0;
+        self.direct = data & 0x80 != 0;
+        if self.direct {
+            self.gain = self.speed;
+        }
+        self.reset_timer(master_speed);
+    }
+
+    fn reset_timer(&mut self, master_speed: u8) {
+        self.timer = 8 * (self.speed as u32 + 1) * master_speed as u32;
+    }
+
+    /// 推進一個 CPU 週期，增益改變時回傳 true
+    fn tick(&mut self, master_speed: u8) -> bool {
+        if self.direct || master_speed == 0 {
+            return false;
+        }
+        if self.timer > 1 {
+            self.timer -= 1;
+            return false;
+        }
+        self.reset_timer(master_speed);
+        if self.increase && self.gain < 32 {
+            self.gain += 1;
+        } else if !self.increase && self.gain > 0 {
+            self.gain -= 1;
+        }
+        true
+    }
+}
+
+/// FDS 波表聲道
+#[derive(Debug, Clone)]
+pub struct FdsAudio {
+    wave_table: [u8; 64],
+    /// $4089 位元 7：可寫入波表（波形暫停）
+    wave_write: bool,
+    master_volume: u8,
+    /// $408A 主包絡速度
+    master_speed: u8,
+    /// $4083 位元 7：停止波形並回到第 0 步
+    halt_wave: bool,
+    /// $4083 位元 6：停止兩個包絡
+    halt_envelopes: bool,
+    wave_freq: u16,
+    wave_accumulator: u32,
+    wave_pos: u8,
+    volume: FdsEnvelope,
+    mod_envelope: FdsEnvelope,
+    mod_freq: u16,
+    /// $4087 位元 7：停止調變（此時才可寫入調變表）
+    mod_halted: bool,
+    mod_table: [u8; 64],
+    mod_pos: u8,
+    /// 7 位元有號計數器（-64 ~ 63）
+    mod_counter: i16,
+    mod_accumulator: u16,
+    /// 調變對波表頻率的偏移
+    mod_pitch: i32,
+    /// 目前輸出（0-63）
+    output: u8,
+}
+
+impl_state_fields!(FdsAudio {
+    wave_table, wave_write, master_volume, master_speed, halt_wave, halt_envelopes, wave_freq,
+    wave_accumulator, wave_pos, volume, mod_envelope, mod_freq, mod_halted, mod_table, mod_pos,
+    mod_counter, mod_accumulator, mod_pitch, output,
+});
+
+impl Default for FdsAudio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FdsAudio {
+    pub fn new() -> Self {
+        FdsAudio {
+            wave_table: [0; 64],
+            wave_write: false,
+            master_volume: 0,
+            master_speed: 0xE8,
+            halt_wave: true,
+            halt_envelopes: false,
+            wave_freq: 0,
+            wave_accumulator: 0,
+            wave_pos: 0,
+            volume: FdsEnvelope::default(),
+            mod_envelope: FdsEnvelope::default(),
+            mod_freq: 0,
+            mod_halted: true,
+            mod_table: [0; 64],
+            mod_pos: 0,
+            mod_counter: 0,
+            mod_accumulator: 0,
+            mod_pitch: 0,
+            output: 0,
+        }
+    }
+
+    /// 寫入 $4040-$408A
+    pub fn write(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x4040..=0x407F if self.wave_write => self.wave_table[(addr - 0x4040) as usize] = data & 0x3F,
+            0x4080 => self.volume.write(data, self.master_speed),
+            0x4082 => self.wave_freq = (self.wave_freq & 0x0F00) | data as u16,
+            0x4083 => {
+                self.wave_freq = (self.wave_freq & 0x00FF) | ((data as u16 & 0x0F) << 8);
+                self.halt_wave = data & 0x80 != 0;
+                self.halt_envelopes = data & 0x40 != 0;
+                if self.halt_wave {
+                    self.wave_pos = 0;
+                    self.wave_accumulator = 0;
+                }
+            }
+            0x4084 => {
+                self.mod_envelope.write(data, self.master_speed);
+                self.update_mod_pitch();
+            }
+            0x4085 => {
+                self.set_mod_counter((data & 0x7F) as i16);
+                self.update_mod_pitch();
+            }
+            0x4086 => self.mod_freq = (self.mod_freq & 0x0F00) | data as u16,
+            0x4087 => {
+                self.mod_freq = (self.mod_freq & 0x00FF) | ((data as u16 & 0x0F) << 8);
+                self.mod_halted = data & 0x80 != 0;
+                if self.mod_halted {
+                    self.mod_accumulator = 0;
+                }
+            }
+            0x4088 if self.mod_halted => {
+                let pos = self.mod_pos as usize;
+                self.mod_table[pos] = data & 0x07;
+                self.mod_table[(pos + 1) & 0x3F] = data & 0x07;
+                self.mod_pos = (self.mod_pos + 2) & 0x3F;
+            }
+            0x4089 => {
+                self.wave_write = data & 0x80 != 0;
+                self.master_volume = data & 0x03;
+            }
+            0x408A => self.master_speed = data,
+            _ => {}
+        }
+    }
+
+    /// 讀取 $4040-$407F 與 $4090/$4092（高位元為開放匯流排 $40）
+    pub fn read(&self, addr: u16) -> Option<u8> {
+        match addr {
+            0x4040..=0x407F => Some(0x40 | self.wave_table[(addr - 0x4040) as usize]),
+            0x4090 => Some(0x40 | self.volume.gain),
+            0x4092 => Some(0x40 | self.mod_envelope.gain),
+            _ => None,
+        }
+    }
+
+    /// 推進一個 CPU 週期
+    pub fn clock(&mut self) {
+        if !self.halt_wave && !self.halt_envelopes {
+            self.volume.tick(self.master_speed);
+            if self.mod_envelope.tick(self.master_speed) {
+                self.update_mod_pitch();
+            }
+        }
+        if self.tick_modulator() {
+            self.update_mod_pitch();
+        }
+
+        if !self.wave_write {
+            self.update_output();
+        }
+        if self.halt_wave {
+            return;
+        }
+        let step = self.wave_freq as i32 + self.mod_pitch;
+        if step > 0 && !self.wave_write {
+            self.wave_accumulator += step as u32;
+            if self.wave_accumulator > 0xFFFF {
+                self.wave_accumulator -= 0x10000;
+                self.wave_pos = (self.wave_pos + 1) & 0x3F;
+            }
+        }
+    }
+
+    /// 目前的輸出（0.0-1.0）
+    pub fn level(&self) -> f32 {
+        self.output as f32 / 63.0
+    }
+
+    fn update_output(&mut self) {
+        let level = (self.volume.gain as u32).min(32) * WAVE_VOLUME[self.master_volume as usize];
+        self.output = (self.wave_table[self.wave_pos as usize] as u32 * level / 1152) as u8;
+    }
+
+    /// 調變計數器依 7 位元有號數環繞
+    fn set_mod_counter(&mut self, value: i16) {
+        self.mod_counter = ((value + 64) & 0x7F) - 64;
+    }
+
+    /// 調變累加器溢位時套用下一個調變表項目，回傳是否有動作
+    fn tick_modulator(&mut self) -> bool {
+        if self.mod_halted || self.mod_freq == 0 {
+            return false;
+        }
+        let (accumulator, overflow) = self.mod_accumulator.overflowing_add(self.mod_freq);
+        self.mod_accumulator = accumulator;
+        if !overflow {
+            return false;
+        }
+        let entry = self.mod_table[self.mod_pos as usize] as usize;
+        let counter = if entry == 4 { 0 } else { self.mod_counter + MOD_STEPS[entry] };
+        self.set_mod_counter(counter);
+        self.mod_pos = (self.mod_pos + 1) & 0x3F;
+        true
+    }
+
+    /// 由計數器與調變增益算出頻率偏移（依 nesdev wiki 的實機捨入方式）
+    fn update_mod_pitch(&mut self) {
+        let mut temp = self.mod_counter as i32 * self.mod_envelope.gain as i32;
+        let remainder = temp & 0x0F;
+        temp >>= 4;
+        if remainder > 0 && temp & 0x80 == 0 {
+            temp += if self.mod_counter < 0 { -1 } else { 2 };
+        }
+        if temp >= 192 {
+            temp -= 256;
+        } else if temp < -64 {
+            temp += 256;
+        }
+        temp *= self.wave_freq as i32;
+        let remainder = temp & 0x3F;
+        temp >>= 6;
+        if remainder >= 32 {
+            temp += 1;
+        }
+        self.mod_pitch = temp;
+    }
+}
+
+// ============================================================
+// FDS Mapper - RAM 轉接器
+// ============================================================
+
+/// 磁頭讀寫一個位元組的間隔（CPU 週期）
+const BYTE_CYCLES: u32 = 150;
+/// 馬達啟動後磁頭回到起點、開始讀寫前的等待（CPU 週期）
+const SPIN_UP_CYCLES: u32 = 50_000;
+/// 兩次 $4032 讀取間隔在這之內視為緊密輪詢（CPU 週期）
+const SWAP_POLL_GAP: u32 = 1_000;
+/// 馬達停止時連續緊密輪詢 $4032 達到這個次數就視為要求換面
+const SWAP_POLL_READS: u32 = 2_000;
+
+pub struct FdsMapper {
+    drive: DiskDrive,
+    audio: FdsAudio,
+    /// $4023：磁碟暫存器與音效暫存器啟用
+    disk_io: bool,
+    sound_io: bool,
+    irq_reload: u16,
+    irq_counter: u16,
+    irq_repeat: bool,
+    irq_enabled: bool,
+    timer_irq: bool,
+    // $4025 控制位元
+    motor_on: bool,
+    reset_transfer: bool,
+    read_mode: bool,
+    mirror_mode: MirrorMode,
+    crc_control: bool,
+    /// 位元 6：開始傳輸（讀取時等待起始標記、寫入時送出資料）
+    transfer_start: bool,
+    disk_irq_enabled: bool,
+    /// $4024 寫入資料
+    write_data: u8,
+    /// $4031 讀取資料
+    read_data: u8,
+    /// 傳輸完一個位元組（$4030 位元 1）
+    transfer_complete: bool,
+    disk_irq: bool,
+    /// 磁頭在盡頭或馬達停止，下次啟動要重新轉到起點
+    end_of_head: bool,
+    /// 磁頭正在讀寫（$4032 位元 1 的反相）
+    scanning: bool,
+    /// 讀取時已經越過間隙、找到起始標記
+    gap_ended: bool,
+    /// 距離下一個位元組的 CPU 週期
+    delay: u32,
+    /// $4026 擴充埠輸出
+    ext_output: u8,
+    /// 換面偵測：距上次 $4032 讀取的週期與連續緊密輪詢次數
+    poll_gap: u32,
+    poll_reads: u32,
+}
+
+impl FdsMapper {
+    pub fn new(image: FdsImage) -> Self {
+        let mut drive = DiskDrive::new();
+        drive.load_image(image);
+        let mut mapper = FdsMapper {
+            drive,
+            audio: FdsAudio::new(),
+            disk_io: false,
+            sound_io: false,
+            irq_reload: 0,
+            irq_counter: 0,
+            irq_repeat: false,
+            irq_enabled: false,
+            timer_irq: false,
+            motor_on: false,
+            reset_transfer: false,
+            read_mode: true,
+            mirror_mode: MirrorMode::Horizontal,
+            crc_control: false,
+            transfer_start: false,
+            disk_irq_enabled: false,
+            write_data: 0,
+            read_data: 0,
+            transfer_complete: false,
+            disk_irq: false,
+            end_of_head: true,
+            scanning: false,
+            gap_ended: false,
+            delay: 0,
+            ext_output: 0,
+            poll_gap: 0,
+            poll_reads: 0,
+        };
+        mapper.reset();
+        mapper
+    }
+
+    /// 磁碟機
+    pub fn drive(&self) -> &DiskDrive {
+        &self.drive
+    }
+
+    /// 磁碟機（可換面）
+    pub fn drive_mut(&mut self) -> &mut DiskDrive {
+        &mut self.drive
+    }
+
+    /// 計時器 IRQ：啟用時每個 CPU 週期遞減，到 0 時觸發並重新載入
+    fn clock_timer(&mut self) {
+        if !self.irq_enabled {
+            return;
+        }
+        if self.irq_counter == 0 {
+            self.timer_irq = true;
+            self.irq_counter = self.irq_reload;
+            if !self.irq_repeat {
+                self.irq_enabled = false;
+            }
+        } else {
+            self.irq_counter -= 1;
+        }
+    }
+
+    /// 磁頭每 BYTE_CYCLES 週期讀寫一個位元組
+    fn clock_disk(&mut self) {
+        if self.drive.inserted().is_none() || !self.motor_on {
+            self.end_of_head = true;
+            self.scanning = false;
+            return;
+        }
+        if self.reset_transfer && !self.scanning {
+            return;
+        }
+        if self.end_of_head {
+            self.end_of_head = false;
+            self.drive.rewind_head();
+            self.gap_ended = false;
+            self.delay = SPIN_UP_CYCLES;
+            return;
+        }
+        if self.delay > 0 {
+            self.delay -= 1;
+            return;
+        }
+
+        self.scanning = true;
+        if self.read_mode {
+            let data = self.drive.read_head();
+            let mut irq = self.disk_irq_enabled;
+            if !self.transfer_start {
+                self.gap_ended = false;
+            } else if data != 0 && !self.gap_ended {
+                // 起始標記：開始傳輸，但標記本身不觸發 IRQ
+                self.gap_ended = true;
+                irq = false;
+            }
+            if self.gap_ended {
+                self.transfer_complete = true;
+                self.read_data = data;
+                self.disk_irq |= irq;
+            }
+        } else {
+            let data = if self.crc_control || !self.transfer_start {
+                0
+            } else {
+                self.transfer_complete = true;
+                self.disk_irq |= self.disk_irq_enabled;
+                self.write_data
+            };
+            self.drive.write_head(data);
+            self.gap_ended = false;
+        }
+
+        if self.drive.advance_head() {
+            self.delay = BYTE_CYCLES;
+        } else {
+            self.motor_on = false;
+        }
+    }
+
+    /// $4032 讀取：馬達停止時被緊密輪詢，視為遊戲在等待換面
+    fn watch_swap_poll(&mut self) {
+        if self.motor_on || self.drive.inserted().is_none() || self.poll_gap > SWAP_POLL_GAP {
+            self.poll_reads = 0;
+        }
+        self.poll_gap = 0;
+        self.poll_reads += 1;
+        if self.poll_reads == SWAP_POLL_READS {
+            self.drive.request_swap();
+        }
+    }
+}
+
+impl MapperTrait for FdsMapper {
+    mapper_state!(
+        drive, audio, disk_io, sound_io, irq_reload, irq_counter, irq_repeat, irq_enabled, timer_irq,
+        motor_on, reset_transfer, read_mode, mirror_mode, crc_control, transfer_start, disk_irq_enabled,
+        write_data, read_data, transfer_complete, disk_irq, end_of_head, scanning, gap_ended, delay,
+        ext_output, poll_gap, poll_reads,
+    );
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        // $6000-$DFFF 是卡帶的 PRG RAM，只有 BIOS 經過這裡
+        if addr >= 0xE000 { Some((addr - 0xE000) as u32) } else { None }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        match addr {
+            0x4020 => self.irq_reload = (self.irq_reload & 0xFF00) | data as u16,
+            0x4021 => self.irq_reload = (self.irq_reload & 0x00FF) | (data as u16) << 8,
+            0x4022 => {
+                self.irq_repeat = data & 0x01 != 0;
+                self.irq_enabled = data & 0x02 != 0 && self.disk_io;
+                if self.irq_enabled {
+                    self.irq_counter = self.irq_reload;
+                } else {
+                    self.timer_irq = false;
+                }
+            }
+            0x4023 => {
+                self.disk_io = data & 0x01 != 0;
+                self.sound_io = data & 0x02 != 0;
+                if !self.disk_io {
+                    self.irq_enabled = false;
+                    self.timer_irq = false;
+                    self.disk_irq = false;
+                }
+            }
+            0x4024 if self.disk_io => {
+                self.write_data = data;
+                self.transfer_complete = false;
+                self.disk_irq = false;
+            }
+            0x4025 if self.disk_io => {
+                self.motor_on = data & 0x01 != 0;
+                self.reset_transfer = data & 0x02 != 0;
+                self.read_mode = data & 0x04 != 0;
+                self.mirror_mode = if data & 0x08 != 0 { MirrorMode::Horizontal } else { MirrorMode::Vertical };
+                self.crc_control = data & 0x10 != 0;
+                self.transfer_start = data & 0x40 != 0;
+                self.disk_irq_enabled = data & 0x80 != 0;
+                self.disk_irq = false;
+                return Some(MapperWriteResult::with_mirror(self.mirror_mode));
+            }
+            0x4026 if self.disk_io => self.ext_output = data,
+            0x4040..=0x408A if self.sound_io => self.audio.write(addr, data),
+            _ => {}
+        }
+        None
+    }
+
+    fn read_register(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x4030 if self.disk_io => {
+                let value = self.timer_irq as u8
+                    | (self.transfer_complete as u8) << 1
+                    | ((self.mirror_mode == MirrorMode::Horizontal) as u8) << 3;
+                self.timer_irq = false;
+                self.transfer_complete = false;
+                self.disk_irq = false;
+                Some(value)
+            }
+            0x4031 if self.disk_io => {
+                self.transfer_complete = false;
+                self.disk_irq = false;
+                Some(self.read_data)
+            }
+            0x4032 if self.disk_io => {
+                self.watch_swap_poll();
+                let ejected = self.drive.inserted().is_none();
+                Some(0x40 | ejected as u8 | ((ejected || !self.scanning) as u8) << 1 | (ejected as u8) << 2)
+            }
+            // 位元 7：電池正常；擴充埠輸入沒有接裝置，讀回輸出值
+            0x4033 if self.disk_io => Some(0x80 | (self.ext_output & 0x7F)),
+            0x4040..=0x409F if self.sound_io => self.audio.read(addr),
+            _ => None,
+        }
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 { Some(addr as u32) } else { None }
+    }
+
+    fn ppu_write(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 { Some(addr as u32) } else { None } // CHR RAM
+    }
+
+    fn reset(&mut self) {
+        self.audio = FdsAudio::new();
+        self.disk_io = false;
+        self.sound_io = false;
+        self.irq_enabled = false;
+        self.timer_irq = false;
+        self.motor_on = false;
+        self.transfer_complete = false;
+        self.disk_irq = false;
+        self.end_of_head = true;
+        self.scanning = false;
+        self.poll_reads = 0;
+    }
+
+    fn cpu_clock(&mut self) {
+        self.clock_timer();
+        self.drive.clock();
+        self.clock_disk();
+        self.poll_gap = self.poll_gap.saturating_add(1);
+    }
+
+    fn check_irq(&mut self) -> bool {
+        self.timer_irq || self.disk_irq
+    }
+
+    fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> {
+        Some(self)
+    }
+}
+
+impl ExpansionAudio for FdsMapper {
+    fn chip(&self) -> ExpansionChip {
+        ExpansionChip::Fds
+    }
+
+    fn clock(&mut self) {
+        self.audio.clock();
+    }
+
+    fn output(&self) -> f32 {
+        self.audio.level() * FDS_GAIN
+    }
+}
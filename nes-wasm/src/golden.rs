@@ -0,0 +1,513 @@
+// ============================================================
+// Golden image 比對 - PPU 回歸測試的參考畫面工具（native feature）
+// ============================================================
+// 執行 ROM 指定幀數後，把幀緩衝區與儲存的參考 PNG 逐像素比對：
+// - 每個通道的差異不超過 tolerance 視為相同（容許調色盤微調）
+// - 比對失敗時在參考圖旁輸出 *.actual.png 與 *.diff.png，
+//   diff 圖中不同的像素以紅色標示、相同的像素以淡灰階顯示
+// - 參考圖不存在且設定環境變數 NES_UPDATE_GOLDEN 時，改為寫入新的參考圖
+//
+// 不依賴外部 crate：PNG 編碼只找與前一像素、上一列重複的片段，解碼支援
+// 一般工具輸出的 8 位元 RGB/RGBA、非交錯格式（含完整 inflate）。
+//
+// 參考：
+// - https://www.w3.org/TR/png/
+// - https://www.rfc-editor.org/rfc/rfc1951
+// ============================================================
+
+use std::path::{Path, PathBuf};
+
+use crate::cartridge::crc32;
+use crate::emulator::Emulator;
+
+/// 畫面寬度（像素）
+pub const WIDTH: u32 = 256;
+/// 畫面高度（像素）
+pub const HEIGHT: u32 = 240;
+
+/// 更新參考圖的環境變數
+pub const UPDATE_ENV: &str = "NES_UPDATE_GOLDEN";
+
+/// 逐像素比對結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameDiff {
+    /// 超出容許值的像素數
+    pub mismatched: usize,
+    /// 所有像素中最大的通道差異
+    pub max_delta: u8,
+    /// 差異圖（RGBA，與輸入同尺寸）
+    pub diff_image: Vec<u8>,
+}
+
+/// 逐像素比對兩張 RGBA 影像（只比較 RGB，忽略 alpha）
+/// 尺寸不同時回傳 None
+pub fn compare_frames(actual: &[u8], expected: &[u8], tolerance: u8) -> Option<FrameDiff> {
+    if actual.len() != expected.len() || !actual.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut mismatched = 0;
+    let mut max_delta = 0;
+    let mut diff_image = Vec::with_capacity(actual.len());
+    for (a, e) in actual.chunks_exact(4).zip(expected.chunks_exact(4)) {
+        let delta = (0..3).map(|c| a[c].abs_diff(e[c])).max().unwrap_or(0);
+        max_delta = max_delta.max(delta);
+        if delta > tolerance {
+            mismatched += 1;
+            diff_image.extend_from_slice(&[0xFF, 0x00, 0x00, 0xFF]);
+        } else {
+            let luma = ((a[0] as u32 * 77 + a[1] as u32 * 150 + a[2] as u32 * 29) >> 8) as u8;
+            let faded = 0xC0 + luma / 4;
+            diff_image.extend_from_slice(&[faded, faded, faded, 0xFF]);
+        }
+    }
+    Some(FrameDiff { mismatched, max_delta, diff_image })
+}
+
+/// 載入 ROM 並執行 frames 幀，回傳最後一幀的幀緩衝區；ROM 無效時回傳 None
+pub fn render_frames(rom: &[u8], frames: u32) -> Option<Vec<u8>> {
+    let mut emu = Emulator::new();
+    if !emu.load_rom(rom) {
+        return None;
+    }
+    emu.run_frames(frames);
+    Some(emu.frame_buffer().to_vec())
+}
+
+/// 執行 ROM 並與參考 PNG 比對，失敗時輸出實際畫面與差異圖並回傳說明
+pub fn check_golden(rom: &[u8], frames: u32, reference: &Path, tolerance: u8) -> Result<(), String> {
+    let actual = render_frames(rom, frames).ok_or("ROM 載入失敗")?;
+    check_frame(&actual, reference, tolerance)
+}
+
+/// 把 256×240 的幀緩衝區與參考 PNG 比對（行為同 check_golden）
+pub fn check_frame(actual: &[u8], reference: &Path, tolerance: u8) -> Result<(), String> {
+    let expected = match std::fs::read(reference) {
+        Ok(data) => data,
+        Err(_) if std::env::var_os(UPDATE_ENV).is_some() => {
+            std::fs::write(reference, encode_png(WIDTH, HEIGHT, actual))
+                .map_err(|e| format!("無法寫入參考圖 {}：{e}", reference.display()))?;
+            return Ok(());
+        }
+        Err(e) => {
+            return Err(format!(
+                "找不到參考圖 {}（{e}），設定 {UPDATE_ENV}=1 可產生",
+                reference.display()
+            ));
+        }
+    };
+    let (width, height, expected) = decode_png(&expected)
+        .ok_or_else(|| format!("無法解碼參考圖 {}", reference.display()))?;
+    if (width, height) != (WIDTH, HEIGHT) {
+        return Err(format!("參考圖尺寸為 {width}×{height}，應為 {WIDTH}×{HEIGHT}"));
+    }
+    let diff = compare_frames(actual, &expected, tolerance).ok_or("幀緩衝區大小不符")?;
+    if diff.mismatched == 0 {
+        return Ok(());
+    }
+
+    let actual_path = sibling(reference, "actual");
+    let diff_path = sibling(reference, "diff");
+    let _ = std::fs::write(&actual_path, encode_png(WIDTH, HEIGHT, actual));
+    let _ = std::fs::write(&diff_path, encode_png(WIDTH, HEIGHT, &diff.diff_image));
+    Err(format!(
+        "{} 個像素超出容許值 {tolerance}（最大差異 {}），實際畫面：{}，差異圖：{}",
+        diff.mismatched, diff.max_delta, actual_path.display(), diff_path.display(),
+    ))
+}
+
+/// foo.png → foo.<suffix>.png
+fn sibling(reference: &Path, suffix: &str) -> PathBuf {
+    let stem = reference.file_stem().and_then(|s| s.to_str()).unwrap_or("frame");
+    reference.with_file_name(format!("{stem}.{suffix}.png"))
+}
+
+// ============================================================
+// PNG 編碼（8 位元 RGBA，固定 Huffman deflate）
+// ============================================================
+
+/// 把 RGBA 影像編碼為 PNG
+pub fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let stride = width as usize * 4;
+    let mut raw = Vec::with_capacity((stride + 1) * height as usize);
+    for row in rgba.chunks_exact(stride).take(height as usize) {
+        raw.push(0); // 濾波器：None
+        raw.extend_from_slice(row);
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    zlib.extend_from_slice(&deflate(&raw, stride + 1));
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]); // 8 位元 RGBA、非交錯
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    write_chunk(&mut png, b"IDAT", &zlib);
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// 以單一固定 Huffman 區塊壓縮：每個位置嘗試距離 4（前一像素）與
+/// row_len（上一列同位置），取較長的重複片段；畫面多為大片同色，這樣就足夠
+fn deflate(data: &[u8], row_len: usize) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    writer.bits(1, 1); // 最後一個區塊
+    writer.bits(1, 2); // 固定 Huffman
+    let mut i = 0;
+    while i < data.len() {
+        let (len, distance) = [4, row_len]
+            .into_iter()
+            .filter(|&d| d <= i)
+            .map(|d| {
+                let len = (0..258.min(data.len() - i)).take_while(|&k| data[i + k] == data[i + k - d]).count();
+                (len, d)
+            })
+            .max()
+            .unwrap_or((0, 0));
+        if len >= 3 {
+            let l = LENGTH_BASE.iter().rposition(|&b| b as usize <= len).unwrap_or(0);
+            writer.fixed_literal(257 + l as u16);
+            writer.bits((len - LENGTH_BASE[l] as usize) as u32, LENGTH_EXTRA[l]);
+            let d = DIST_BASE.iter().rposition(|&b| b as usize <= distance).unwrap_or(0);
+            writer.huffman(d as u32, 5);
+            writer.bits((distance - DIST_BASE[d] as usize) as u32, DIST_EXTRA[d]);
+            i += len;
+        } else {
+            writer.fixed_literal(data[i] as u16);
+            i += 1;
+        }
+    }
+    writer.fixed_literal(256);
+    writer.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u32,
+    count: u8,
+}
+
+impl BitWriter {
+    /// 由低位元開始寫入（一般欄位與額外位元）
+    fn bits(&mut self, value: u32, n: u8) {
+        for i in 0..n {
+            self.acc |= ((value >> i) & 1) << self.count;
+            self.count += 1;
+            if self.count == 8 {
+                self.out.push(self.acc as u8);
+                self.acc = 0;
+                self.count = 0;
+            }
+        }
+    }
+
+    /// Huffman 碼由高位元開始寫入
+    fn huffman(&mut self, code: u32, len: u8) {
+        for i in (0..len).rev() {
+            self.bits((code >> i) & 1, 1);
+        }
+    }
+
+    /// 固定 Huffman 表的字面值/長度碼
+    fn fixed_literal(&mut self, symbol: u16) {
+        let symbol = symbol as u32;
+        match symbol {
+            0..=143 => self.huffman(0x30 + symbol, 8),
+            144..=255 => self.huffman(0x190 + symbol - 144, 9),
+            256..=279 => self.huffman(symbol - 256, 7),
+            _ => self.huffman(0xC0 + symbol - 280, 8),
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.out
+    }
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+// ============================================================
+// PNG 解碼（8 位元 RGB/RGBA，非交錯）
+// ============================================================
+
+/// 解碼 PNG，回傳（寬, 高, RGBA 資料）；不支援的格式回傳 None
+pub fn decode_png(data: &[u8]) -> Option<(u32, u32, Vec<u8>)> {
+    if !data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return None;
+    }
+    let mut pos = 8;
+    let mut header = None;
+    let mut idat = Vec::new();
+    while pos + 8 <= data.len() {
+        let len = u32::from_be_bytes(data[pos..pos + 4].try_into().ok()?) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let body = data.get(pos + 8..pos + 8 + len)?;
+        match kind {
+            b"IHDR" if len == 13 => {
+                let width = u32::from_be_bytes(body[0..4].try_into().ok()?);
+                let height = u32::from_be_bytes(body[4..8].try_into().ok()?);
+                header = Some((width, height, body[8], body[9], body[12]));
+            }
+            b"IDAT" => idat.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += 12 + len;
+    }
+
+    let (width, height, depth, color_type, interlace) = header?;
+    let channels = match color_type {
+        2 => 3,
+        6 => 4,
+        _ => return None,
+    };
+    if depth != 8 || interlace != 0 || idat.len() < 2 {
+        return None;
+    }
+    let raw = inflate(&idat[2..])?;
+    let stride = width as usize * channels;
+    if raw.len() < (stride + 1) * height as usize {
+        return None;
+    }
+
+    let mut pixels = vec![0u8; stride * height as usize];
+    for y in 0..height as usize {
+        let filter = raw[y * (stride + 1)];
+        let line = &raw[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        for x in 0..stride {
+            let a = if x >= channels { pixels[y * stride + x - channels] } else { 0 };
+            let b = if y > 0 { pixels[(y - 1) * stride + x] } else { 0 };
+            let c = if x >= channels && y > 0 { pixels[(y - 1) * stride + x - channels] } else { 0 };
+            let predictor = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return None,
+            };
+            pixels[y * stride + x] = line[x].wrapping_add(predictor);
+        }
+    }
+
+    let rgba = if channels == 4 {
+        pixels
+    } else {
+        pixels.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 0xFF]).collect()
+    };
+    Some((width, height, rgba))
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+}
+
+// ============================================================
+// Inflate（RFC 1951：未壓縮、固定與動態 Huffman 區塊）
+// ============================================================
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+    257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+/// 動態區塊中碼長碼的排列順序
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u8,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, n: u8) -> Option<u32> {
+        let mut value = 0;
+        for i in 0..n {
+            let byte = *self.data.get(self.pos)?;
+            value |= (((byte >> self.bit) & 1) as u32) << i;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+        Some(value)
+    }
+
+    fn align(&mut self) {
+        if self.bit != 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+}
+
+/// 正規 Huffman 碼表（依碼長排列的符號）
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Option<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return self.symbols.get((index + code - first) as usize).copied();
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        None
+    }
+}
+
+/// 解壓 raw deflate 資料，格式錯誤回傳 None
+fn inflate(data: &[u8]) -> Option<Vec<u8>> {
+    let mut reader = BitReader { data, pos: 0, bit: 0 };
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let header = data.get(reader.pos..reader.pos + 4)?;
+                let len = u16::from_le_bytes([header[0], header[1]]) as usize;
+                reader.pos += 4;
+                out.extend_from_slice(data.get(reader.pos..reader.pos + len)?);
+                reader.pos += len;
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                let lit = Huffman::new(&lengths);
+                let dist = Huffman::new(&[5; 30]);
+                inflate_block(&mut reader, &mut out, &lit, &dist)?;
+            }
+            2 => {
+                let (lit, dist) = read_dynamic_tables(&mut reader)?;
+                inflate_block(&mut reader, &mut out, &lit, &dist)?;
+            }
+            _ => return None,
+        }
+        if last {
+            return Some(out);
+        }
+    }
+}
+
+fn read_dynamic_tables(reader: &mut BitReader) -> Option<(Huffman, Huffman)> {
+    let hlit = reader.bits(5)? as usize + 257;
+    let hdist = reader.bits(5)? as usize + 1;
+    let hclen = reader.bits(4)? as usize + 4;
+    let mut code_lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..hclen] {
+        code_lengths[i] = reader.bits(3)? as u8;
+    }
+    let code_table = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(hlit + hdist);
+    while lengths.len() < hlit + hdist {
+        let symbol = code_table.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => (*lengths.last()?, 3 + reader.bits(2)?),
+            17 => (0, 3 + reader.bits(3)?),
+            18 => (0, 11 + reader.bits(7)?),
+            _ => return None,
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() != hlit + hdist {
+        return None;
+    }
+    Some((Huffman::new(&lengths[..hlit]), Huffman::new(&lengths[hlit..])))
+}
+
+fn inflate_block(reader: &mut BitReader, out: &mut Vec<u8>, lit: &Huffman, dist: &Huffman) -> Option<()> {
+    loop {
+        let symbol = lit.decode(reader)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Some(()),
+            _ => {
+                let i = symbol - 257;
+                let len = *LENGTH_BASE.get(i)? as usize + reader.bits(LENGTH_EXTRA[i])? as usize;
+                let d = dist.decode(reader)? as usize;
+                let distance = *DIST_BASE.get(d)? as usize + reader.bits(DIST_EXTRA[d])? as usize;
+                if distance > out.len() {
+                    return None;
+                }
+                let start = out.len() - distance;
+                for k in 0..len {
+                    out.push(out[start + k]);
+                }
+            }
+        }
+    }
+}
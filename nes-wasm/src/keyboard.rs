@@ -0,0 +1,212 @@
+// ============================================================
+// Famicom 擴充埠裝置 - Family BASIC 鍵盤與資料記錄器
+// ============================================================
+// 鍵盤（HVC-007）接在擴充埠，與控制器共用 $4016/$4017：
+// - $4016 寫入：位元 0 = 回到第 0 列，位元 1 = 選擇欄（0/1），
+//   欄從 1 變回 0 時前進到下一列；位元 2 = 啟用鍵盤矩陣
+// - $4017 讀取：位元 1-4 為目前列/欄的 4 個按鍵，0 表示按下；
+//   矩陣停用時讀到 0，超過第 8 列時讀到全部放開
+//
+// 按鍵矩陣（每列 8 鍵，欄 0 為前 4 鍵、欄 1 為後 4 鍵，依位元 1-4 排列）：
+//   列 0：]      [      RETURN F8    STOP  ¥     RSHIFT KANA
+//   列 1：;      :      @      F7    ^     -     /      _
+//   列 2：K      L      O      F6    0     P     ,      .
+//   列 3：J      U      I      F5    8     9     N      M
+//   列 4：H      G      Y      F4    6     7     V      B
+//   列 5：D      R      T      F3    4     5     C      F
+//   列 6：A      S      W      F2    3     E     Z      X
+//   列 7：CTR    Q      ESC    F1    2     1     GRPH   LSHIFT
+//   列 8：LEFT   RIGHT  UP     CLR   INS   DEL   SPACE  DOWN
+//
+// 資料記錄器（HVC-008）：$4016 寫入的位元 2 是送往錄音機的訊號，
+// 播放時錄音帶的訊號從 $4016 讀取的位元 1 進來。這裡不模擬音訊，
+// 只以固定的 CPU 週期間隔取樣訊號電位，錄下的位元序列可以原樣播放
+// （Family BASIC 的 SAVE/LOAD、Lode Runner 等遊戲的關卡存取）。
+//
+// 參考：
+// - https://www.nesdev.org/wiki/Family_BASIC_Keyboard
+// - https://www.nesdev.org/wiki/Family_BASIC_Data_Recorder
+// ============================================================
+
+/// 鍵盤矩陣列數
+pub const KEYBOARD_ROWS: usize = 9;
+
+/// 錄音帶取樣間隔（CPU 週期，約 56kHz，足以涵蓋 Family BASIC 的 1-2kHz 訊號）
+pub const TAPE_SAMPLE_CYCLES: u64 = 32;
+
+/// Family BASIC 鍵盤
+#[derive(Debug, Clone, Default)]
+pub struct FamilyKeyboard {
+    /// 是否接上擴充埠（未接上時不影響 $4017）
+    connected: bool,
+    /// 各列按下的按鍵（位元 0-7 對應該列的 8 個鍵）
+    keys: [u8; KEYBOARD_ROWS],
+    /// 目前選擇的列
+    row: usize,
+    /// 目前選擇的欄（0/1）
+    column: u8,
+    /// 鍵盤矩陣是否啟用（$4016 位元 2）
+    enabled: bool,
+}
+
+impl FamilyKeyboard {
+    /// 建立未接上的鍵盤
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 接上或拔除鍵盤（拔除時清除所有按鍵）
+    pub fn set_connected(&mut self, connected: bool) {
+        self.connected = connected;
+        if !connected {
+            self.keys = [0; KEYBOARD_ROWS];
+        }
+    }
+
+    /// 是否接上
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// 設定按鍵狀態（row 0-8、col 0-7，超出範圍忽略）
+    pub fn set_key_state(&mut self, row: usize, col: usize, pressed: bool) {
+        let Some(keys) = self.keys.get_mut(row) else { return };
+        if col >= 8 {
+            return;
+        }
+        if pressed {
+            *keys |= 1 << col;
+        } else {
+            *keys &= !(1 << col);
+        }
+    }
+
+    /// CPU 寫入（$4016）
+    pub fn write(&mut self, data: u8) {
+        let column = (data >> 1) & 1;
+        if data & 0x01 != 0 {
+            self.row = 0;
+        } else if self.column == 1 && column == 0 {
+            self.row += 1;
+        }
+        self.column = column;
+        self.enabled = data & 0x04 != 0;
+    }
+
+    /// CPU 讀取（$4017 的位元 1-4）
+    pub fn read(&self) -> u8 {
+        if !self.connected || !self.enabled {
+            return 0;
+        }
+        let pressed = self.keys.get(self.row).map_or(0, |&keys| (keys >> (self.column * 4)) & 0x0F);
+        (!pressed & 0x0F) << 1
+    }
+}
+
+/// 錄音機狀態
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapeMode {
+    /// 停止
+    Stopped,
+    /// 錄音中
+    Recording,
+    /// 播放中
+    Playing,
+}
+
+/// Family BASIC 資料記錄器
+///
+/// 錄音帶內容為每 TAPE_SAMPLE_CYCLES 個 CPU 週期一個位元的電位序列，
+/// 以 LSB 優先打包成位元組。
+#[derive(Debug, Clone)]
+pub struct DataRecorder {
+    mode: TapeMode,
+    /// 錄音帶內容
+    tape: Vec<u8>,
+    /// 錄音帶位元數
+    samples: u64,
+    /// 開始錄音/播放時的 CPU 週期
+    start_cycle: u64,
+    /// 目前送往錄音機的電位（$4016 位元 2）
+    level: bool,
+}
+
+impl Default for DataRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataRecorder {
+    /// 建立空的錄音機
+    pub fn new() -> Self {
+        DataRecorder { mode: TapeMode::Stopped, tape: Vec::new(), samples: 0, start_cycle: 0, level: false }
+    }
+
+    /// 目前狀態
+    pub fn mode(&self) -> TapeMode {
+        self.mode
+    }
+
+    /// 從 cycle 開始錄音（清除原本的內容）
+    pub fn record(&mut self, cycle: u64) {
+        self.mode = TapeMode::Recording;
+        self.tape.clear();
+        self.samples = 0;
+        self.start_cycle = cycle;
+    }
+
+    /// 從 cycle 開始播放錄音帶
+    pub fn play(&mut self, tape: &[u8], cycle: u64) {
+        self.mode = TapeMode::Playing;
+        self.tape = tape.to_vec();
+        self.samples = tape.len() as u64 * 8;
+        self.start_cycle = cycle;
+    }
+
+    /// 停止，回傳錄音帶內容（錄音中停止時補齊到目前週期）
+    pub fn stop(&mut self, cycle: u64) -> Vec<u8> {
+        if self.mode == TapeMode::Recording {
+            self.fill_to(cycle);
+        }
+        self.mode = TapeMode::Stopped;
+        self.tape.clone()
+    }
+
+    /// CPU 寫入（$4016），錄音中時記錄位元 2 的電位
+    pub fn write(&mut self, data: u8, cycle: u64) {
+        if self.mode == TapeMode::Recording {
+            self.fill_to(cycle);
+        }
+        self.level = data & 0x04 != 0;
+    }
+
+    /// CPU 讀取（$4016 的位元 1），播放到結尾後自動停止
+    pub fn read(&mut self, cycle: u64) -> u8 {
+        if self.mode != TapeMode::Playing {
+            return 0;
+        }
+        let index = cycle.saturating_sub(self.start_cycle) / TAPE_SAMPLE_CYCLES;
+        if index >= self.samples {
+            self.mode = TapeMode::Stopped;
+            return 0;
+        }
+        let bit = (self.tape[(index / 8) as usize] >> (index % 8)) & 1;
+        bit << 1
+    }
+
+    /// 以目前電位補齊到 cycle 為止的取樣
+    fn fill_to(&mut self, cycle: u64) {
+        let target = cycle.saturating_sub(self.start_cycle) / TAPE_SAMPLE_CYCLES;
+        while self.samples < target {
+            let bit = self.samples % 8;
+            if bit == 0 {
+                self.tape.push(0);
+            }
+            if let (true, Some(byte)) = (self.level, self.tape.last_mut()) {
+                *byte |= 1 << bit;
+            }
+            self.samples += 1;
+        }
+    }
+}
@@ -5,127 +5,104 @@
 // 架構設計上保持模組化，方便未來擴充支援 GameBoy、SFC 等其他主機。
 //
 // 模組結構：
-// - cpu: 6502 CPU 模擬（含所有合法指令與定址模式）
+// - cpu: 6502 CPU 核心（含非官方指令，透過 CpuBus trait 存取記憶體）
 // - ppu: 圖形處理器模擬（背景、精靈、捲軸）
 // - apu: 音效處理器模擬（脈衝、三角、雜訊、DMC、混音）
 // - bus: 記憶體匯流排（CPU/PPU 位址空間映射）
 // - cartridge: 卡帶與 iNES 格式解析
 // - mappers: 各種記憶體映射器（Mapper 0~4 等）
+// - vrc7: Konami VRC7（Mapper 85）與 FM 擴充音效
+// - eeprom: 卡帶上的 24C02 序列 EEPROM（Bandai FCG 的存檔晶片）
+// - compat: 個別遊戲的相容性修正資料庫（依 ROM CRC32 套用）
 // - controller: 控制器輸入處理
+// - keyboard: 擴充埠裝置（Family BASIC 鍵盤、資料記錄器）
 // - emulator: 整合所有元件的模擬器主體
+// - config: 模擬器設定（集中管理各子系統選項）
+// - savestate: 快速存檔槽、存檔壓縮與狀態欄位序列化
+// - rewind: 倒帶（定期擷取壓縮存檔的環形緩衝區）
+// - benchmark: 效能基準測試（FPS 與各子系統耗時）
+// - movie: 輸入影片（TAS 錄製、播放與重錄）
+// - debugger: 除錯器（中斷點、指令追蹤、反組譯）
+// - cheats: 金手指（Game Genie 與原始位址碼）
+// - fds: FDS 磁碟映像檔、磁碟機、RAM 轉接器與波表音效
+// - nsf: NSF 音樂檔播放
+// - vs: VS. System（PPU 型號、DIP 開關、投幣與保護晶片）
+// - golden: golden image 比對（PNG 參考畫面，需啟用 native feature）
+// - accuracy: 測試 ROM 目錄的準確度報告（需啟用 native feature）
+// - wasm: JavaScript 介面（NesWasm、NesDebugger，需啟用 wasm feature）
+//
+// 預設啟用 wasm feature；以 --no-default-features 建置時不依賴
+// wasm-bindgen/js-sys，可作為一般 Rust 函式庫給原生前端、測試與 fuzz 使用。
 // ============================================================
 
-use wasm_bindgen::prelude::*;
+//! NES 模擬器核心
+//!
+//! 除了 `NesWasm` 給瀏覽器使用外，模擬器本體 [`Emulator`] 也可以直接
+//! 嵌入其他 Rust 專案（原生前端、多主機外殼、測試工具）。關閉預設的
+//! `wasm` feature 即可不依賴 wasm-bindgen：
+//!
+//! ```toml
+//! nes-wasm = { path = "../nes-wasm", default-features = false }
+//! ```
+//!
+//! # 範例
+//!
+//! ```
+//! use nes_wasm::{Button, Emulator};
+//!
+//! // 最小的 NROM：16KB PRG 只有一個無窮迴圈（JMP $8000）
+//! let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+//! let mut prg = vec![0xEA; 0x4000];
+//! prg[..3].copy_from_slice(&[0x4C, 0x00, 0x80]);
+//! prg[0x3FFC..0x3FFE].copy_from_slice(&[0x00, 0x80]);
+//! rom.extend_from_slice(&prg);
+//! rom.extend_from_slice(&[0; 0x2000]);
+//!
+//! let mut emu = Emulator::new();
+//! assert!(emu.load_rom(&rom));
+//!
+//! emu.set_button(0, Button::Start as u8, true);
+//! emu.frame();
+//!
+//! // 256×240 RGBA 畫面與本幀產生的音訊取樣
+//! assert_eq!(emu.frame_buffer().len(), 256 * 240 * 4);
+//! let samples = emu.take_audio_samples();
+//! assert!(!samples.is_empty());
+//! ```
 
 pub mod cpu;
 pub mod ppu;
 pub mod apu;
+pub mod audio;
 pub mod bus;
 pub mod cartridge;
 pub mod mappers;
+pub mod vrc7;
+pub mod eeprom;
+pub mod compat;
 pub mod controller;
+pub mod keyboard;
 pub mod emulator;
-
-// ============================================================
-// WASM 匯出介面 - 供 JavaScript 呼叫
-// ============================================================
-
-/// NES 模擬器 WASM 包裝器
-/// 這是暴露給 JavaScript 的主要介面
-#[wasm_bindgen]
-pub struct NesWasm {
-    /// 內部模擬器實例
-    emu: emulator::Emulator,
-}
-
-#[wasm_bindgen]
-impl NesWasm {
-    /// 建立新的 NES 模擬器實例
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> NesWasm {
-        NesWasm {
-            emu: emulator::Emulator::new(),
-        }
-    }
-
-    /// 載入 ROM 資料
-    /// 傳入 ROM 的 Uint8Array，回傳是否載入成功
-    #[wasm_bindgen(js_name = "loadRom")]
-    pub fn load_rom(&mut self, rom_data: &[u8]) -> bool {
-        self.emu.load_rom(rom_data)
-    }
-
-    /// 重置模擬器
-    pub fn reset(&mut self) {
-        self.emu.reset();
-    }
-
-    /// 執行一幀（包含所有 CPU/PPU/APU 週期）
-    pub fn frame(&mut self) {
-        self.emu.frame();
-    }
-
-    /// 取得畫面緩衝區指標（256x240 的 RGBA 像素資料）
-    /// 回傳的是 WASM 記憶體中的指標，JavaScript 可直接存取
-    #[wasm_bindgen(js_name = "getFrameBufferPtr")]
-    pub fn get_frame_buffer_ptr(&self) -> *const u8 {
-        self.emu.get_frame_buffer_ptr()
-    }
-
-    /// 取得畫面緩衝區長度（位元組數）
-    #[wasm_bindgen(js_name = "getFrameBufferLen")]
-    pub fn get_frame_buffer_len(&self) -> usize {
-        self.emu.get_frame_buffer_len()
-    }
-
-    /// 設定控制器按鈕狀態
-    /// controller: 控制器編號（0 或 1）
-    /// button: 按鈕編號（0=A, 1=B, 2=Select, 3=Start, 4=Up, 5=Down, 6=Left, 7=Right）
-    /// pressed: 是否按下
-    #[wasm_bindgen(js_name = "setButton")]
-    pub fn set_button(&mut self, controller: u8, button: u8, pressed: bool) {
-        self.emu.set_button(controller, button, pressed);
-    }
-
-    /// 設定音頻取樣率
-    #[wasm_bindgen(js_name = "setAudioSampleRate")]
-    pub fn set_audio_sample_rate(&mut self, rate: f64) {
-        self.emu.set_audio_sample_rate(rate);
-    }
-
-    /// 取得音頻緩衝區指標
-    #[wasm_bindgen(js_name = "getAudioBufferPtr")]
-    pub fn get_audio_buffer_ptr(&self) -> *const f32 {
-        self.emu.get_audio_buffer_ptr()
-    }
-
-    /// 取得可用的音頻取樣數
-    #[wasm_bindgen(js_name = "getAudioBufferLen")]
-    pub fn get_audio_buffer_len(&self) -> usize {
-        self.emu.get_audio_buffer_len()
-    }
-
-    /// 消費音頻取樣（讀取後清除緩衝區）
-    #[wasm_bindgen(js_name = "consumeAudioSamples")]
-    pub fn consume_audio_samples(&mut self) -> usize {
-        self.emu.consume_audio_samples()
-    }
-
-    /// 匯出存檔資料為 JSON 字串
-    #[wasm_bindgen(js_name = "exportSaveState")]
-    pub fn export_save_state(&self) -> String {
-        self.emu.export_save_state()
-    }
-
-    /// 從 JSON 字串匯入存檔
-    #[wasm_bindgen(js_name = "importSaveState")]
-    pub fn import_save_state(&mut self, json: &str) -> bool {
-        self.emu.import_save_state(json)
-    }
-
-    /// 取得 WASM 記憶體（供 JavaScript 直接存取畫面/音頻緩衝區）
-    #[wasm_bindgen(js_name = "getWasmMemory")]
-    pub fn get_wasm_memory(&self) -> JsValue {
-        wasm_bindgen::memory()
-    }
-}
+pub mod config;
+pub mod savestate;
+pub mod rewind;
+pub mod benchmark;
+pub mod movie;
+pub mod debugger;
+pub mod cheats;
+pub mod fds;
+pub mod nsf;
+pub mod vs;
+#[cfg(feature = "native")]
+pub mod golden;
+#[cfg(feature = "native")]
+pub mod accuracy;
+
+pub use config::{EmulatorConfig, Region};
+pub use controller::{Button, InputDevice};
+pub use emulator::{Emulator, EmulatorStatus, MemoryUsage};
+
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "wasm")]
+pub use wasm::{NesDebugger, NesWasm};
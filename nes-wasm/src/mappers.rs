@@ -10,23 +10,46 @@
 // - Mapper 3 (CNROM): CHR ROM 切換
 // - Mapper 4 (MMC3): Nintendo MMC3，掃描線 IRQ
 // - Mapper 7 (AxROM): 32KB PRG 切換，單屏鏡像
+// - Mapper 9 (MMC2): Punch-Out!!，PPU 讀取觸發 CHR 切換
+// - Mapper 10 (MMC4): 聖火徽章等，MMC2 的 16KB PRG 版本
 // - Mapper 11 (Color Dreams): 簡單 PRG/CHR 切換
 // - Mapper 15 (100-in-1): 多合一卡帶
 // - Mapper 16 (Bandai FCG): 龍珠系列等
+// - Mapper 18 (Jaleco SS88006): 4 位元 bank 暫存器與可選寬度的 CPU 週期 IRQ
+// - Mapper 19 (Namco 163): 內建 RAM、IRQ 與波表擴充音效
 // - Mapper 23 (VRC2b/VRC4): Konami VRC 系列
+// - Mapper 24/26 (VRC6a/VRC6b): 兩個脈衝波與鋸齒波擴充音效
+// - Mapper 64 (Tengen RAMBO-1): MMC3 加上 1KB CHR 模式與 CPU 週期 IRQ
+// - Mapper 65 (Irem H3001): 16 位元 CPU 週期 IRQ
 // - Mapper 66 (GxROM): 簡單 PRG/CHR 切換
+// - Mapper 69 (Sunsoft FME-7/5B): 命令暫存器、CPU 週期 IRQ 與 5B 擴充音效
 // - Mapper 71 (Camerica): Camerica/Codemasters 遊戲
+// - Mapper 85 (VRC7): Konami VRC7 與 FM 擴充音效（vrc7.rs）
+// - Mapper 99 (VS. UniSystem): VS. 系統，以 $4016 切換 CHR
 // - Mapper 113 (NINA-03/06): 台灣麻將等
+// - Mapper 163 (Nanjing): 中文 RPG，CHR RAM 依掃描線自動切換
 // - Mapper 202: 150合1 等合集卡帶
+// - Mapper 206 (Namco 108): 沒有 IRQ 的 MMC3 前身，Gauntlet 等
+// - Mapper 210 (Namco 175/340): Famista 系列等，依子 Mapper 決定鏡像控制
 // - Mapper 225: 52/64/72合1 等合集卡帶
 // - Mapper 227: 1200合1 等合集卡帶
 // - Mapper 245 (Waixing MMC3): 中文版遊戲
 // - Mapper 253 (Waixing VRC4): 龍珠等中文版
 //
+// 新增 Mapper 時，除了 create_mapper 與 is_mapper_supported，也要把型別
+// 加進檔案末尾的 mapper_enum! 清單。
+//
 // 參考：https://www.nesdev.org/wiki/Mapper
 // ============================================================
 
+use crate::apu::{ExpansionAudio, ExpansionChip};
+use crate::compat::CompatHack;
+use crate::eeprom::Eeprom24C02;
+use crate::fds::FdsMapper;
+use crate::nsf::NsfMapper;
+use crate::vrc7::Mapper85;
 use crate::ppu::MirrorMode;
+use crate::savestate::{impl_state_fields, mapper_state, StateField, StateReader};
 
 /// Mapper 寫入操作的結果
 pub struct MapperWriteResult {
@@ -54,6 +77,29 @@ impl MapperWriteResult {
     }
 }
 
+/// PRG RAM（$6000-$7FFF）的存取權限，由 Mapper 的啟用/防寫位元決定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrgRamAccess {
+    /// 晶片未啟用：讀取為開放匯流排、寫入無效
+    Disabled,
+    /// 防寫：可讀取、寫入無效
+    ReadOnly,
+    /// 可讀寫
+    ReadWrite,
+}
+
+impl PrgRamAccess {
+    /// 是否可讀取
+    pub fn readable(self) -> bool {
+        self != PrgRamAccess::Disabled
+    }
+
+    /// 是否可寫入
+    pub fn writable(self) -> bool {
+        self == PrgRamAccess::ReadWrite
+    }
+}
+
 /// Mapper 特性（介面）
 /// 所有 Mapper 都必須實作此特性
 pub trait MapperTrait {
@@ -62,11 +108,12 @@ pub trait MapperTrait {
     fn cpu_read(&self, addr: u16) -> Option<u32>;
 
     /// CPU 寫入映射
-    /// 傳入 CPU 位址與資料，回傳寫入結果（可能觸發 bank 切換等）
+    /// 傳入 CPU 位址與資料，回傳寫入結果（鏡像模式有變更時必須在結果中標示）
     fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult>;
 
     /// PPU 讀取映射
-    /// 傳入 PPU 位址，回傳映射後的 CHR ROM/RAM 偏移量
+    /// 傳入 PPU 位址，回傳映射後的 CHR ROM/RAM 偏移量；每次圖案讀取都會
+    /// 呼叫，bank 切換（含掃描線中途）立即生效
     fn ppu_read(&self, addr: u16) -> Option<u32>;
 
     /// PPU 寫入映射
@@ -76,18 +123,61 @@ pub trait MapperTrait {
     /// 重置 Mapper 狀態
     fn reset(&mut self);
 
-    /// 掃描線通知（用於 MMC3 等 scanline-based IRQ）
+    /// 掃描線通知：渲染中每條掃描線的第 260 週期一次，與圖案表位址無關
     fn scanline(&mut self) {}
 
+    /// PPU 位址線 A12 經過濾波的上升緣（MMC3 系列以此計數掃描線）；
+    /// 一般設定（背景 $0000、精靈 $1000）下每條掃描線一次
+    fn a12_clock(&mut self) {}
+
     /// CPU 週期通知（用於 Bandai FCG 等 cycle-based IRQ）
     fn cpu_clock(&mut self) {}
 
-    /// 檢查並消耗 IRQ 請求
+    /// IRQ 輸出目前是否有效；與硬體的 IRQ 線一樣是電位，遊戲寫入確認
+    /// 暫存器之前持續回傳 true
     fn check_irq(&mut self) -> bool { false }
 
     /// 取得 CHR bank 可寫入遮罩（用於混合 CHR ROM/RAM mapper）
     /// 每個位元代表一個 1KB bank 是否可寫入
     fn chr_writable_mask(&self) -> u8 { 0 }
+
+    /// PRG RAM 目前的存取權限（沒有啟用/防寫位元的 Mapper 一律可讀寫）
+    fn prg_ram_access(&self) -> PrgRamAccess { PrgRamAccess::ReadWrite }
+
+    /// 目前映射到 $6000-$7FFF 的 8KB PRG RAM bank（PRG RAM 超過 8KB 的板子才需要）
+    fn prg_ram_bank(&self) -> usize { 0 }
+
+    /// 套用相容性修正（只處理與自己有關的項目，其餘忽略）
+    fn apply_hack(&mut self, _hack: CompatHack) {}
+
+    /// PPU 讀取圖案表 $xFD8-$xFEF 的通知（MMC2/MMC4 的 CHR 鎖存器），
+    /// 回傳 CHR bank 映射是否改變
+    fn ppu_fetch(&mut self, _addr: u16) -> bool { false }
+
+    /// 是否需要 ppu_fetch 通知；不需要時卡帶不轉送觸發位址的讀取
+    fn watches_ppu_fetch(&self) -> bool { false }
+
+    /// $4020-$7FFF 的暫存器讀取（讀取可能有副作用，例如位址自動遞增）；
+    /// 回傳 None 表示該位址沒有暫存器（$6000-$7FFF 則照常讀取 PRG RAM）
+    fn read_register(&mut self, _addr: u16) -> Option<u8> { None }
+
+    /// 卡帶上的 EEPROM（電池 PRG RAM 以外的存檔媒體），沒有時回傳 None；
+    /// 內容與協定狀態由模擬器另外存檔，不必寫進 save_state
+    fn eeprom(&self) -> Option<&Eeprom24C02> { None }
+
+    /// 同 eeprom，可寫入（載入存檔、取出變更旗標）
+    fn eeprom_mut(&mut self) -> Option<&mut Eeprom24C02> { None }
+
+    /// 卡帶的擴充音源，由 APU 每個 CPU 週期驅動並混音；
+    /// 沒有擴充音效的 Mapper 回傳 None
+    fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> { None }
+
+    /// 存檔：依序寫入暫存器與內部計數器（由 ROM 決定的常數不必存）；
+    /// 內建 Mapper 以 mapper_state! 產生，沒有可變狀態的 Mapper 不需實作
+    fn save_state(&self, _out: &mut Vec<u8>) {}
+
+    /// 讀檔：依 save_state 的順序讀回，資料不足或無效時回傳 None
+    fn load_state(&mut self, _r: &mut StateReader) -> Option<()> { Some(()) }
 }
 
 // ============================================================
@@ -149,6 +239,15 @@ impl MapperTrait for Mapper0 {
 // 使用串列寫入（shift register）來設定暫存器
 // 支援 PRG/CHR bank 切換與鏡像控制
 // 用於：塞爾達傳說、洛克人2、最終幻想 等
+//
+// 使用 CHR RAM 的 SxROM 板子把用不到的 CHR bank 位元接到別處：
+// - SUROM/SXROM（512KB PRG）：CHR bank 0 位元 4 為 PRG A18，選擇 256KB 的一半
+//   （4KB CHR 模式下遊戲會把兩個 CHR 暫存器寫成相同值，這裡只看 CHR bank 0）
+// - SOROM（16KB PRG RAM）：位元 3 選擇 8KB 的 PRG RAM bank
+// - SXROM（32KB PRG RAM）：位元 3-2 選擇 8KB 的 PRG RAM bank
+// PRG RAM 大小只有 NES 2.0 標頭會標示，iNES 1.0 一律視為 8KB。
+//
+// 參考：https://www.nesdev.org/wiki/MMC1#SxROM_connection_variants
 // ============================================================
 pub struct Mapper1 {
     prg_banks: u8,
@@ -164,10 +263,22 @@ pub struct Mapper1 {
     chr_bank1: u8,
     /// PRG bank
     prg_bank: u8,
+    /// PRG RAM 停用（PRG bank 暫存器位元 4）
+    prg_ram_disabled: bool,
+    /// 這個 CPU 週期已經寫過序列埠：MMC1 忽略連續週期的第二次寫入，
+    /// RMW 指令的雙重寫入（如 INC $FFFF 重設移位暫存器）因此只生效一次
+    wrote_this_cycle: bool,
+    /// PRG RAM 的 8KB bank 數（SOROM 為 2、SXROM 為 4，其他為 1）
+    prg_ram_banks: usize,
 }
 
 impl Mapper1 {
     pub fn new(prg_banks: u8, chr_banks: u8) -> Self {
+        Self::with_prg_ram_size(prg_banks, chr_banks, 8192)
+    }
+
+    /// 依 PRG RAM 大小建立（大於 8KB 時為 SOROM/SXROM 的 RAM bank 切換）
+    pub fn with_prg_ram_size(prg_banks: u8, chr_banks: u8, prg_ram_size: usize) -> Self {
         Mapper1 {
             prg_banks,
             chr_banks,
@@ -176,32 +287,45 @@ impl Mapper1 {
             chr_bank0: 0,
             chr_bank1: 0,
             prg_bank: 0,
+            prg_ram_disabled: false,
+            wrote_this_cycle: false,
+            prg_ram_banks: (prg_ram_size / 8192).max(1),
         }
     }
+
+    /// SUROM/SXROM 的 PRG A18（256KB 外層 bank 的偏移量）
+    fn prg_outer_offset(&self) -> u32 {
+        if self.prg_banks > 16 { (self.chr_bank0 as u32 >> 4 & 1) * 0x40000 } else { 0 }
+    }
 }
 
 impl MapperTrait for Mapper1 {
+    mapper_state!(shift_register, control, chr_bank0, chr_bank1, prg_bank, prg_ram_disabled);
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
         if addr >= 0x8000 {
             let prg_mode = (self.control >> 2) & 0x03;
+            // 固定的「第一個」與「最後一個」bank 都在目前的 256KB 之內
+            let outer = self.prg_outer_offset();
+            let last_bank = self.prg_banks.min(16) as u32 - 1;
 
             if prg_mode <= 1 {
                 // 32KB 模式：忽略 bank 最低位
                 let bank = (self.prg_bank & 0x0E) as u32 * 16384;
-                Some(bank + (addr & 0x7FFF) as u32)
+                Some(outer + bank + (addr & 0x7FFF) as u32)
             } else if prg_mode == 2 {
                 // 固定第一個 bank 在 $8000，切換 $C000
                 if addr < 0xC000 {
-                    Some((addr & 0x3FFF) as u32)
+                    Some(outer + (addr & 0x3FFF) as u32)
                 } else {
-                    Some(self.prg_bank as u32 * 16384 + (addr & 0x3FFF) as u32)
+                    Some(outer + self.prg_bank as u32 * 16384 + (addr & 0x3FFF) as u32)
                 }
             } else {
                 // 切換 $8000，固定最後一個 bank 在 $C000
                 if addr < 0xC000 {
-                    Some(self.prg_bank as u32 * 16384 + (addr & 0x3FFF) as u32)
+                    Some(outer + self.prg_bank as u32 * 16384 + (addr & 0x3FFF) as u32)
                 } else {
-                    Some((self.prg_banks as u32 - 1) * 16384 + (addr & 0x3FFF) as u32)
+                    Some(outer + last_bank * 16384 + (addr & 0x3FFF) as u32)
                 }
             }
         } else {
@@ -211,6 +335,9 @@ impl MapperTrait for Mapper1 {
 
     fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
         if addr >= 0x8000 {
+            if std::mem::replace(&mut self.wrote_this_cycle, true) {
+                return None;
+            }
             // 位元 7：重置移位暫存器
             if data & 0x80 != 0 {
                 self.shift_register = 0x10;
@@ -229,7 +356,11 @@ impl MapperTrait for Mapper1 {
                     0 => self.control = value,       // 控制暫存器
                     1 => self.chr_bank0 = value,     // CHR bank 0
                     2 => self.chr_bank1 = value,     // CHR bank 1
-                    3 => self.prg_bank = value & 0x0F, // PRG bank
+                    3 => {
+                        // PRG bank；位元 4 為 PRG RAM 停用（MMC1B 之後）
+                        self.prg_bank = value & 0x0F;
+                        self.prg_ram_disabled = value & 0x10 != 0;
+                    }
                     _ => {}
                 }
 
@@ -286,6 +417,24 @@ impl MapperTrait for Mapper1 {
         self.chr_bank0 = 0;
         self.chr_bank1 = 0;
         self.prg_bank = 0;
+        self.prg_ram_disabled = false;
+        self.wrote_this_cycle = false;
+    }
+
+    fn cpu_clock(&mut self) {
+        self.wrote_this_cycle = false;
+    }
+
+    fn prg_ram_access(&self) -> PrgRamAccess {
+        if self.prg_ram_disabled { PrgRamAccess::Disabled } else { PrgRamAccess::ReadWrite }
+    }
+
+    fn prg_ram_bank(&self) -> usize {
+        match self.prg_ram_banks {
+            2 => (self.chr_bank0 >> 3 & 1) as usize,
+            4 => (self.chr_bank0 >> 2 & 3) as usize,
+            _ => 0,
+        }
     }
 }
 
@@ -311,8 +460,10 @@ impl Mapper2 {
 }
 
 impl MapperTrait for Mapper2 {
+    mapper_state!(selected_bank);
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
-        if addr >= 0x8000 && addr < 0xC000 {
+        if (0x8000..0xC000).contains(&addr) {
             Some(self.selected_bank as u32 * 16384 + (addr & 0x3FFF) as u32)
         } else if addr >= 0xC000 {
             Some((self.prg_banks as u32 - 1) * 16384 + (addr & 0x3FFF) as u32)
@@ -365,6 +516,8 @@ impl Mapper3 {
 }
 
 impl MapperTrait for Mapper3 {
+    mapper_state!(selected_chr_bank);
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
         if addr >= 0x8000 {
             let mask = if self.prg_banks > 1 { 0x7FFF } else { 0x3FFF };
@@ -377,6 +530,7 @@ impl MapperTrait for Mapper3 {
     fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
         if addr >= 0x8000 {
             self.selected_chr_bank = data & 0x03;
+            return Some(MapperWriteResult::none());
         }
         None
     }
@@ -407,6 +561,13 @@ impl MapperTrait for Mapper3 {
 // - 掃描線計數器（用於 IRQ）
 // - 可控的鏡像模式
 // 用於：超級瑪利歐兄弟3、忍者龍劍傳、大金剛3 等
+//
+// IRQ 依晶片版本有兩種行為：MMC3C（與 Sharp 版）在計數器重新載入為 0 時
+// 每條掃描線都觸發，MMC3A（與 NEC 版）只在遞減到 0 或寫入 $C001 後觸發。
+// NES 2.0 子 Mapper 4 指定 MMC3A；沒有標示時預設為常見的 MMC3C，
+// 已知依賴舊版行為的遊戲由相容性資料庫（Mmc3AltIrq）切換。
+//
+// 參考：https://www.nesdev.org/wiki/MMC3#IRQ_Specifics
 // ============================================================
 pub struct Mapper4 {
     prg_banks: u8,
@@ -422,6 +583,8 @@ pub struct Mapper4 {
     chr_a12_inversion: bool,
     /// 鏡像模式
     mirror_mode: MirrorMode,
+    /// PRG RAM 保護暫存器（$A001：位元 7 啟用、位元 6 防寫）
+    prg_ram_protect: u8,
 
     // IRQ 相關
     irq_counter: u8,
@@ -429,8 +592,14 @@ pub struct Mapper4 {
     irq_enabled: bool,
     irq_reload: bool,
     irq_pending: bool,
+    /// 舊版 IRQ 行為（MMC3A/NEC）：計數器重新載入為 0 時不觸發
+    alt_irq: bool,
 }
 
+/// MMC3 開機時的 $A001 值：實機為不定值，許多遊戲從不寫入 $A001
+/// 卻依賴 PRG RAM，因此預設為啟用且可寫入
+const MMC3_PRG_RAM_DEFAULT: u8 = 0x80;
+
 impl Mapper4 {
     pub fn new(prg_banks: u8, chr_banks: u8) -> Self {
         Mapper4 {
@@ -441,14 +610,21 @@ impl Mapper4 {
             prg_rom_bank_mode: false,
             chr_a12_inversion: false,
             mirror_mode: MirrorMode::Vertical,
+            prg_ram_protect: MMC3_PRG_RAM_DEFAULT,
             irq_counter: 0,
             irq_latch: 0,
             irq_enabled: false,
             irq_reload: false,
             irq_pending: false,
+            alt_irq: false,
         }
     }
 
+    /// MMC3A（NES 2.0 子 Mapper 4）：使用舊版 IRQ 行為
+    pub fn new_mmc3a(prg_banks: u8, chr_banks: u8) -> Self {
+        Mapper4 { alt_irq: true, ..Self::new(prg_banks, chr_banks) }
+    }
+
     /// 取得 PRG bank 編號（以 8KB 為單位）
     fn get_prg_bank(&self, addr: u16) -> u32 {
         let last_bank = self.prg_banks as u32 * 2 - 1;
@@ -503,6 +679,11 @@ impl Mapper4 {
 }
 
 impl MapperTrait for Mapper4 {
+    mapper_state!(
+        registers, bank_select, prg_rom_bank_mode, chr_a12_inversion, mirror_mode, prg_ram_protect,
+        irq_counter, irq_latch, irq_enabled, irq_reload, irq_pending,
+    );
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
         if addr >= 0x8000 {
             let bank = self.get_prg_bank(addr);
@@ -525,19 +706,22 @@ impl MapperTrait for Mapper4 {
                         self.prg_rom_bank_mode = (data & 0x40) != 0;
                         self.chr_a12_inversion = (data & 0x80) != 0;
                     } else {
-                        self.registers[self.bank_select as usize] = data;
+                        self.registers[(self.bank_select & 0x07) as usize] = data;
                     }
+                    return Some(MapperWriteResult::none());
                 }
-                1 => {
+                1 if even => {
                     // $A000-$BFFF
-                    if even {
-                        self.mirror_mode = if data & 1 != 0 {
-                            MirrorMode::Horizontal
-                        } else {
-                            MirrorMode::Vertical
-                        };
-                        return Some(MapperWriteResult::with_mirror(self.mirror_mode));
-                    }
+                    self.mirror_mode = if data & 1 != 0 {
+                        MirrorMode::Horizontal
+                    } else {
+                        MirrorMode::Vertical
+                    };
+                    return Some(MapperWriteResult::with_mirror(self.mirror_mode));
+                }
+                1 => {
+                    // $A001：PRG RAM 保護
+                    self.prg_ram_protect = data & 0xC0;
                 }
                 2 => {
                     // $C000-$DFFF
@@ -584,6 +768,7 @@ impl MapperTrait for Mapper4 {
         self.prg_rom_bank_mode = false;
         self.chr_a12_inversion = false;
         self.mirror_mode = MirrorMode::Vertical;
+        self.prg_ram_protect = MMC3_PRG_RAM_DEFAULT;
         self.irq_counter = 0;
         self.irq_latch = 0;
         self.irq_enabled = false;
@@ -591,7 +776,17 @@ impl MapperTrait for Mapper4 {
         self.irq_pending = false;
     }
 
-    fn scanline(&mut self) {
+    fn prg_ram_access(&self) -> PrgRamAccess {
+        match self.prg_ram_protect & 0xC0 {
+            0x80 => PrgRamAccess::ReadWrite,
+            0xC0 => PrgRamAccess::ReadOnly,
+            _ => PrgRamAccess::Disabled,
+        }
+    }
+
+    fn a12_clock(&mut self) {
+        // 舊版只在遞減到 0 或寫入 $C001 後觸發，計數器停在 0 時不會每條掃描線都觸發
+        let triggerable = !self.alt_irq || self.irq_counter != 0 || self.irq_reload;
         if self.irq_counter == 0 || self.irq_reload {
             self.irq_counter = self.irq_latch;
             self.irq_reload = false;
@@ -599,15 +794,19 @@ impl MapperTrait for Mapper4 {
             self.irq_counter -= 1;
         }
 
-        if self.irq_counter == 0 && self.irq_enabled {
+        if self.irq_counter == 0 && self.irq_enabled && triggerable {
             self.irq_pending = true;
         }
     }
 
     fn check_irq(&mut self) -> bool {
-        let pending = self.irq_pending;
-        self.irq_pending = false;
-        pending
+        self.irq_pending
+    }
+
+    fn apply_hack(&mut self, hack: CompatHack) {
+        if hack == CompatHack::Mmc3AltIrq {
+            self.alt_irq = true;
+        }
     }
 }
 
@@ -636,6 +835,8 @@ impl Mapper7 {
 }
 
 impl MapperTrait for Mapper7 {
+    mapper_state!(selected_bank, mirror_mode);
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
         if addr >= 0x8000 {
             Some(self.selected_bank as u32 * 32768 + (addr & 0x7FFF) as u32)
@@ -671,6 +872,229 @@ impl MapperTrait for Mapper7 {
     }
 }
 
+// ============================================================
+// CHR 鎖存器 - MMC2/MMC4 共用
+// ============================================================
+// 兩個 4KB CHR 區（$0000、$1000）各有兩組 bank 暫存器，由「鎖存器」
+// 選擇使用哪一組。PPU 讀取圖磚 $FD 或 $FE 的高位元平面時切換鎖存器，
+// 讀取當下仍用舊的 bank，之後的讀取才換成新的 bank：
+// - $0FD8 / $0FE8：第 0 區切到 $FD / $FE 組（MMC4 為 $0FD8-$0FDF / $0FE8-$0FEF）
+// - $1FD8-$1FDF / $1FE8-$1FEF：第 1 區切到 $FD / $FE 組
+//
+// 遊戲把這兩個圖磚放在畫面特定位置（例如拳擊手與觀眾的交界），
+// 讓一幀畫面可以用到超過 8KB 的圖案。
+//
+// 參考：https://www.nesdev.org/wiki/MMC2
+// ============================================================
+
+/// MMC2/MMC4 的 CHR 鎖存器
+#[derive(Debug, Clone)]
+pub struct ChrLatch {
+    /// 4KB bank 編號：[區][0 = $FD 組, 1 = $FE 組]
+    banks: [[u8; 2]; 2],
+    /// 各區目前選擇的組
+    latch: [usize; 2],
+    /// 第 0 區只在 $0FD8/$0FE8 觸發（MMC2）
+    exact_low: bool,
+}
+
+impl StateField for ChrLatch {
+    fn save(&self, out: &mut Vec<u8>) {
+        self.banks.save(out);
+        out.extend(self.latch.map(|set| set as u8));
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> Option<()> {
+        self.banks.load(r)?;
+        self.latch = r.array::<2>()?.map(|set| (set & 1) as usize);
+        Some(())
+    }
+}
+
+impl ChrLatch {
+    fn new(exact_low: bool) -> Self {
+        // 開機時鎖存器為不定值，這裡與多數模擬器相同選擇 $FE 組
+        ChrLatch { banks: [[0; 2]; 2], latch: [1; 2], exact_low }
+    }
+
+    /// 寫入 $B000-$EFFF 的 bank 暫存器
+    fn write_bank(&mut self, addr: u16, data: u8) {
+        let reg = ((addr >> 12) - 0xB) as usize;
+        self.banks[reg >> 1][reg & 1] = data & 0x1F;
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        if addr >= 0x2000 {
+            return None;
+        }
+        let half = (addr >> 12) as usize;
+        Some(self.banks[half][self.latch[half]] as u32 * 4096 + (addr & 0x0FFF) as u32)
+    }
+
+    /// PPU 讀取圖案表後更新鎖存器，回傳 CHR 映射是否改變
+    fn fetch(&mut self, addr: u16) -> bool {
+        let half = (addr >> 12) as usize & 1;
+        if half == 0 && self.exact_low && addr & 0x0007 != 0 {
+            return false;
+        }
+        let select = match addr & 0x0FF8 {
+            0x0FD8 => 0,
+            0x0FE8 => 1,
+            _ => return false,
+        };
+        let changed = self.latch[half] != select
+            && self.banks[half][0] != self.banks[half][1];
+        self.latch[half] = select;
+        changed
+    }
+
+    fn reset(&mut self) {
+        self.banks = [[0; 2]; 2];
+        self.latch = [1; 2];
+    }
+}
+
+/// MMC2/MMC4 $F000 的鏡像設定
+fn latch_mirror(data: u8) -> MirrorMode {
+    if data & 1 != 0 { MirrorMode::Horizontal } else { MirrorMode::Vertical }
+}
+
+// ============================================================
+// Mapper 9 (MMC2) - PPU 讀取觸發 CHR 切換
+// ============================================================
+// PRG ROM: $8000-$9FFF 可切換 8KB，$A000-$FFFF 固定為最後三個 8KB
+// CHR ROM: 兩個 4KB 區，由 CHR 鎖存器選擇 bank
+// 用於：Punch-Out!!
+// ============================================================
+pub struct Mapper9 {
+    prg_banks: u8,
+    prg_bank: u8,
+    chr: ChrLatch,
+}
+
+impl Mapper9 {
+    pub fn new(prg_banks: u8, _chr_banks: u8) -> Self {
+        Mapper9 { prg_banks, prg_bank: 0, chr: ChrLatch::new(true) }
+    }
+}
+
+impl MapperTrait for Mapper9 {
+    mapper_state!(prg_bank, chr);
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        let banks_8k = self.prg_banks as u32 * 2;
+        match addr {
+            0x8000..=0x9FFF => Some(self.prg_bank as u32 * 8192 + (addr & 0x1FFF) as u32),
+            0xA000..=0xFFFF => {
+                // $A000/$C000/$E000 → 倒數第三、二、一個 bank
+                let bank = banks_8k.saturating_sub(3) + ((addr - 0xA000) >> 13) as u32;
+                Some(bank * 8192 + (addr & 0x1FFF) as u32)
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        match addr {
+            0xA000..=0xAFFF => self.prg_bank = data & 0x0F,
+            0xB000..=0xEFFF => {
+                self.chr.write_bank(addr, data);
+                return Some(MapperWriteResult::none());
+            }
+            0xF000..=0xFFFF => return Some(MapperWriteResult::with_mirror(latch_mirror(data))),
+            _ => {}
+        }
+        None
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        self.chr.ppu_read(addr)
+    }
+
+    fn ppu_write(&self, _addr: u16) -> Option<u32> {
+        None
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank = 0;
+        self.chr.reset();
+    }
+
+    fn ppu_fetch(&mut self, addr: u16) -> bool {
+        self.chr.fetch(addr)
+    }
+
+    fn watches_ppu_fetch(&self) -> bool {
+        true
+    }
+}
+
+// ============================================================
+// Mapper 10 (MMC4) - MMC2 的 16KB PRG 版本
+// ============================================================
+// PRG ROM: $8000-$BFFF 可切換 16KB，$C000-$FFFF 固定為最後一個 bank
+// PRG RAM: 8KB（電池備份）
+// CHR ROM: 與 MMC2 相同的 CHR 鎖存器，但第 0 區也接受 8 個位址
+// 用於：聖火徽章、聖火徽章外傳、Famicom Wars
+// ============================================================
+pub struct Mapper10 {
+    prg_banks: u8,
+    prg_bank: u8,
+    chr: ChrLatch,
+}
+
+impl Mapper10 {
+    pub fn new(prg_banks: u8, _chr_banks: u8) -> Self {
+        Mapper10 { prg_banks, prg_bank: 0, chr: ChrLatch::new(false) }
+    }
+}
+
+impl MapperTrait for Mapper10 {
+    mapper_state!(prg_bank, chr);
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        match addr {
+            0x8000..=0xBFFF => Some(self.prg_bank as u32 * 16384 + (addr & 0x3FFF) as u32),
+            0xC000..=0xFFFF => Some((self.prg_banks as u32 - 1) * 16384 + (addr & 0x3FFF) as u32),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        match addr {
+            0xA000..=0xAFFF => self.prg_bank = data & 0x0F,
+            0xB000..=0xEFFF => {
+                self.chr.write_bank(addr, data);
+                return Some(MapperWriteResult::none());
+            }
+            0xF000..=0xFFFF => return Some(MapperWriteResult::with_mirror(latch_mirror(data))),
+            _ => {}
+        }
+        None
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        self.chr.ppu_read(addr)
+    }
+
+    fn ppu_write(&self, _addr: u16) -> Option<u32> {
+        None
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank = 0;
+        self.chr.reset();
+    }
+
+    fn ppu_fetch(&mut self, addr: u16) -> bool {
+        self.chr.fetch(addr)
+    }
+
+    fn watches_ppu_fetch(&self) -> bool {
+        true
+    }
+}
+
 // ============================================================
 // Mapper 11 (Color Dreams) - 簡單 PRG/CHR 切換
 // ============================================================
@@ -688,6 +1112,8 @@ impl Mapper11 {
 }
 
 impl MapperTrait for Mapper11 {
+    mapper_state!(prg_bank, chr_bank);
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
         if addr >= 0x8000 {
             let bank = self.prg_bank as u32 % self.prg_banks.max(1) as u32;
@@ -701,6 +1127,7 @@ impl MapperTrait for Mapper11 {
         if addr >= 0x8000 {
             self.prg_bank = data & 0x03;
             self.chr_bank = (data >> 4) & 0x0F;
+            return Some(MapperWriteResult::none());
         }
         None
     }
@@ -744,6 +1171,8 @@ impl Mapper15 {
 }
 
 impl MapperTrait for Mapper15 {
+    mapper_state!(latch_addr, latch_data, mirror_mode);
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
         if addr >= 0x8000 {
             let total_8k = self.prg_banks as u32 * 2; // 8KB banks
@@ -779,7 +1208,7 @@ impl MapperTrait for Mapper15 {
                 _ => 0,
             };
 
-            let offset = (bank8k % total_8k) as u32 * 8192 + (addr & 0x1FFF) as u32;
+            let offset = (bank8k % total_8k) * 8192 + (addr & 0x1FFF) as u32;
             Some(offset)
         } else {
             None
@@ -816,6 +1245,9 @@ impl MapperTrait for Mapper15 {
 // Mapper 16 (Bandai FCG) - 龍珠系列
 // ============================================================
 // 支援 PRG/CHR bank 切換和 CPU 週期 IRQ
+// LZ93D50 的卡帶以 24C02 EEPROM 存檔（見 eeprom.rs）：$xxxD 的位元 5 為
+// SCL、位元 6 為 SDA，讀取 $6000-$7FFF 的位元 4 為 EEPROM 的 SDA 輸出
+// （其餘位元讀為 0）。位元 7 的讀取方向不影響結果。
 // 用於：龍珠Z 系列等
 // ============================================================
 pub struct Mapper16 {
@@ -829,9 +1261,16 @@ pub struct Mapper16 {
     irq_enabled: bool,
     irq_pending: bool,
     mirror_mode: MirrorMode,
+    /// 存檔用的 EEPROM（FCG-1/2 沒有）
+    eeprom: Option<Eeprom24C02>,
 }
 
 impl Mapper16 {
+    /// 帶 24C02 EEPROM 的 LZ93D50 卡帶
+    pub fn with_eeprom(prg_banks: u8, chr_banks: u8) -> Self {
+        Mapper16 { eeprom: Some(Eeprom24C02::new()), ..Mapper16::new(prg_banks, chr_banks) }
+    }
+
     pub fn new(prg_banks: u8, chr_banks: u8) -> Self {
         Mapper16 {
             prg_banks,
@@ -843,13 +1282,16 @@ impl Mapper16 {
             irq_enabled: false,
             irq_pending: false,
             mirror_mode: MirrorMode::Vertical,
+            eeprom: None,
         }
     }
 }
 
 impl MapperTrait for Mapper16 {
+    mapper_state!(chr_bank_regs, prg_bank, irq_counter, irq_latch, irq_enabled, irq_pending, mirror_mode);
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
-        if addr >= 0x8000 && addr < 0xC000 {
+        if (0x8000..0xC000).contains(&addr) {
             let bank = self.prg_bank as u32 % self.prg_banks.max(1) as u32;
             Some(bank * 16384 + (addr & 0x3FFF) as u32)
         } else if addr >= 0xC000 {
@@ -870,6 +1312,7 @@ impl MapperTrait for Mapper16 {
 
         if reg < 8 {
             self.chr_bank_regs[reg as usize] = data;
+            return Some(MapperWriteResult::none());
         } else if reg == 8 {
             self.prg_bank = data & 0x0F;
         } else if reg == 9 {
@@ -888,10 +1331,28 @@ impl MapperTrait for Mapper16 {
             self.irq_latch = (self.irq_latch & 0xFF00) | data as u16;
         } else if reg == 0x0C {
             self.irq_latch = (self.irq_latch & 0x00FF) | ((data as u16) << 8);
+        } else if reg == 0x0D {
+            if let Some(eeprom) = &mut self.eeprom {
+                eeprom.write(data >> 5 & 1, data >> 6 & 1);
+            }
         }
         None
     }
 
+    /// 有 EEPROM 時 $6000-$7FFF 不是 PRG RAM
+    fn prg_ram_access(&self) -> PrgRamAccess {
+        if self.eeprom.is_some() { PrgRamAccess::Disabled } else { PrgRamAccess::ReadWrite }
+    }
+
+    fn read_register(&mut self, addr: u16) -> Option<u8> {
+        let eeprom = self.eeprom.as_ref().filter(|_| (0x6000..0x8000).contains(&addr))?;
+        Some(eeprom.output() << 4)
+    }
+
+    fn eeprom(&self) -> Option<&Eeprom24C02> { self.eeprom.as_ref() }
+
+    fn eeprom_mut(&mut self) -> Option<&mut Eeprom24C02> { self.eeprom.as_mut() }
+
     fn ppu_read(&self, addr: u16) -> Option<u32> {
         if addr < 0x2000 {
             let region = (addr >> 10) as usize;
@@ -912,6 +1373,9 @@ impl MapperTrait for Mapper16 {
         self.irq_latch = 0;
         self.irq_enabled = false;
         self.irq_pending = false;
+        if let Some(eeprom) = &mut self.eeprom {
+            eeprom.reset();
+        }
     }
 
     /// Bandai FCG 使用 CPU 週期計時器
@@ -926,78 +1390,602 @@ impl MapperTrait for Mapper16 {
     }
 
     fn check_irq(&mut self) -> bool {
-        let pending = self.irq_pending;
-        self.irq_pending = false;
-        pending
+        self.irq_pending
     }
 }
 
 // ============================================================
-// Mapper 23 (VRC2b/VRC4) - Konami VRC 系列
+// Mapper 18 (Jaleco SS88006) - 以 4 位元暫存器拼出 bank 編號
 // ============================================================
-// 支援精細的 PRG/CHR bank 切換和 IRQ
-// 用於：魂斗羅 Force 等 Konami 遊戲
+// 每個 bank 編號由兩個位址分別寫入低/高 4 位元（位址以 $F003 遮罩）：
+// - $8000-$8003、$9000-$9001：$8000/$A000/$C000 的 8KB PRG bank，$E000 固定為最後一個
+// - $9002：位元 0 啟用 PRG RAM、位元 1 允許寫入
+// - $A000-$D003：8 個 1KB CHR bank
+// - $E000-$E003：16 位元 IRQ 重新載入值（4 個 4 位元）
+// - $F000：重新載入計數器並確認 IRQ；$F001：位元 0 啟用計數，位元 1-3 選擇
+//   計數器寬度（位元 3 = 4、位元 2 = 8、位元 1 = 12 位元，皆 0 為 16 位元），寫入時也確認 IRQ
+// - $F002：鏡像（0 = 水平、1 = 垂直、2 = 單屏 A、3 = 單屏 B）
+// 計數器每個 CPU 週期只遞減選定寬度的低位元，從 0 繞回時觸發 IRQ（其餘位元不變）。
+// $F003 的 µPD7756 語音晶片沒有模擬。
+// 用於：Pizza Pop!、Plazma Ball、Ninja Jajamaru: Ginga Daisakusen 等
+//
+// 參考：https://www.nesdev.org/wiki/INES_Mapper_018
 // ============================================================
-pub struct Mapper23 {
+pub struct Mapper18 {
     prg_banks: u8,
     chr_banks: u8,
-    prg_bank0: u8,
-    prg_bank1: u8,
-    chr_bank_regs: [u8; 8],
-    prg_swap_mode: u8,
+    prg_regs: [u8; 3],
+    chr_regs: [u8; 8],
+    /// $9002：位元 0 啟用、位元 1 可寫入
+    prg_ram_control: u8,
     mirror_mode: MirrorMode,
-    // IRQ (VRC4)
-    irq_latch: u8,
+    irq_reload: u16,
+    irq_counter: u16,
+    /// $F001：位元 0 啟用，位元 1-3 計數器寬度
     irq_control: u8,
-    irq_counter: u8,
-    irq_prescaler: i16,
-    irq_enabled: bool,
     irq_pending: bool,
 }
 
-impl Mapper23 {
+impl Mapper18 {
     pub fn new(prg_banks: u8, chr_banks: u8) -> Self {
-        Mapper23 {
-            prg_banks, chr_banks,
-            prg_bank0: 0, prg_bank1: 0,
-            chr_bank_regs: [0; 8],
-            prg_swap_mode: 0,
-            mirror_mode: MirrorMode::Vertical,
-            irq_latch: 0, irq_control: 0,
-            irq_counter: 0, irq_prescaler: 0,
-            irq_enabled: false, irq_pending: false,
+        Mapper18 {
+            prg_banks,
+            chr_banks,
+            prg_regs: [0; 3],
+            chr_regs: [0; 8],
+            prg_ram_control: 0,
+            mirror_mode: MirrorMode::Horizontal,
+            irq_reload: 0,
+            irq_counter: 0,
+            irq_control: 0,
+            irq_pending: false,
+        }
+    }
+
+    /// 計數器參與遞減的位元
+    fn irq_counter_mask(&self) -> u16 {
+        if self.irq_control & 0x08 != 0 {
+            0x000F
+        } else if self.irq_control & 0x04 != 0 {
+            0x00FF
+        } else if self.irq_control & 0x02 != 0 {
+            0x0FFF
+        } else {
+            0xFFFF
         }
     }
 }
 
-impl MapperTrait for Mapper23 {
+/// 把 4 位元寫入 reg 的低或高半位元組
+fn set_nibble(reg: &mut u8, high: bool, data: u8) {
+    *reg = if high { (*reg & 0x0F) | (data & 0x0F) << 4 } else { (*reg & 0xF0) | (data & 0x0F) };
+}
+
+impl MapperTrait for Mapper18 {
+    mapper_state!(prg_regs, chr_regs, prg_ram_control, mirror_mode, irq_reload, irq_counter, irq_control, irq_pending);
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
-        let total = self.prg_banks as u32 * 2; // 8KB banks
-        match addr {
-            0x8000..=0x9FFF => {
-                let bank = if self.prg_swap_mode != 0 { total - 2 } else { self.prg_bank0 as u32 };
-                Some((bank % total) * 8192 + (addr & 0x1FFF) as u32)
-            }
-            0xA000..=0xBFFF => {
-                Some((self.prg_bank1 as u32 % total) * 8192 + (addr & 0x1FFF) as u32)
-            }
-            0xC000..=0xDFFF => {
-                let bank = if self.prg_swap_mode != 0 { self.prg_bank0 as u32 } else { total - 2 };
-                Some((bank % total) * 8192 + (addr & 0x1FFF) as u32)
-            }
-            0xE000..=0xFFFF => {
-                Some((total - 1) * 8192 + (addr & 0x1FFF) as u32)
-            }
-            _ => None,
+        if addr < 0x8000 {
+            return None;
         }
+        let count = (self.prg_banks as u32 * 2).max(1);
+        let bank = match addr {
+            0xE000..=0xFFFF => count - 1,
+            _ => self.prg_regs[((addr - 0x8000) >> 13) as usize] as u32,
+        };
+        Some((bank % count) * 8192 + (addr & 0x1FFF) as u32)
     }
 
     fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
-        let a0 = addr & 0x0001;
-        let a1 = (addr & 0x0002) >> 1;
-        let reg = (addr & 0xF000) | (a1 << 1) | a0;
-
-        match reg {
+        if addr < 0x8000 {
+            return None;
+        }
+        let high = addr & 0x01 != 0;
+        match addr & 0xF003 {
+            0x8000..=0x8003 => set_nibble(&mut self.prg_regs[(addr as usize >> 1) & 1], high, data),
+            0x9000 | 0x9001 => set_nibble(&mut self.prg_regs[2], high, data),
+            0x9002 => self.prg_ram_control = data & 0x03,
+            0xA000..=0xDFFF => {
+                let index = (((addr - 0xA000) >> 12) * 2 + ((addr >> 1) & 1)) as usize;
+                set_nibble(&mut self.chr_regs[index], high, data);
+                return Some(MapperWriteResult::none());
+            }
+            0xE000..=0xE003 => {
+                let shift = (addr & 0x03) * 4;
+                self.irq_reload = (self.irq_reload & !(0x0F << shift)) | ((data as u16 & 0x0F) << shift);
+            }
+            0xF000 => {
+                self.irq_counter = self.irq_reload;
+                self.irq_pending = false;
+            }
+            0xF001 => {
+                self.irq_control = data & 0x0F;
+                self.irq_pending = false;
+            }
+            0xF002 => {
+                self.mirror_mode = match data & 0x03 {
+                    0 => MirrorMode::Horizontal,
+                    1 => MirrorMode::Vertical,
+                    2 => MirrorMode::SingleScreenLow,
+                    _ => MirrorMode::SingleScreenHigh,
+                };
+                return Some(MapperWriteResult::with_mirror(self.mirror_mode));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        if addr >= 0x2000 {
+            return None;
+        }
+        if self.chr_banks == 0 {
+            return Some(addr as u32);
+        }
+        let bank = self.chr_regs[(addr >> 10) as usize] as u32;
+        Some((bank % (self.chr_banks as u32 * 8)) * 1024 + (addr & 0x03FF) as u32)
+    }
+
+    fn ppu_write(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 && self.chr_banks == 0 { Some(addr as u32) } else { None }
+    }
+
+    fn reset(&mut self) {
+        self.prg_regs = [0; 3];
+        self.chr_regs = [0; 8];
+        self.prg_ram_control = 0;
+        self.mirror_mode = MirrorMode::Horizontal;
+        self.irq_reload = 0;
+        self.irq_counter = 0;
+        self.irq_control = 0;
+        self.irq_pending = false;
+    }
+
+    fn prg_ram_access(&self) -> PrgRamAccess {
+        match self.prg_ram_control {
+            0x03 => PrgRamAccess::ReadWrite,
+            0x01 => PrgRamAccess::ReadOnly,
+            _ => PrgRamAccess::Disabled,
+        }
+    }
+
+    fn cpu_clock(&mut self) {
+        if self.irq_control & 0x01 == 0 {
+            return;
+        }
+        let mask = self.irq_counter_mask();
+        let low = self.irq_counter & mask;
+        self.irq_counter = (self.irq_counter & !mask) | (low.wrapping_sub(1) & mask);
+        if low == 0 {
+            self.irq_pending = true;
+        }
+    }
+
+    fn check_irq(&mut self) -> bool {
+        // 寫入 $F000/$F001 才確認
+        self.irq_pending
+    }
+}
+
+// ============================================================
+// Mapper 19 (Namco 163) - 內建 RAM 與波表擴充音效
+// ============================================================
+// PRG ROM: 三個可切換的 8KB bank（$8000/$A000/$C000），$E000 固定為最後一個
+// CHR ROM: 八個 1KB bank（$8000-$BFFF 每 $800 一個暫存器）
+// 名稱表: $C000-$DFFF 四個暫存器，$E0 以上選擇主機 VRAM 的第 0/1 頁
+//         （以 CHR ROM 當名稱表的設定不支援，維持原本的鏡像）
+// 內建 RAM: 128 位元組，經 $F800 設定位址、$4800 讀寫（可自動遞增），
+//          $40-$7F 同時是 8 個波表聲道的暫存器
+// IRQ: 15 位元計數器，每個 CPU 週期加 1，到 $7FFF 時觸發
+//
+// 波表聲道每 15 個 CPU 週期輪流更新一個（從第 7 聲道往下，啟用的聲道數
+// 由 $7F 位元 4-6 決定），聲道越多每個聲道的更新越慢。實機是分時輸出，
+// 這裡取啟用聲道的平均值，避免分時造成的高頻雜音。
+//
+// 用於：女神轉生 II、三國志 II、Digital Devil Story 等
+//
+// 參考：
+// - https://www.nesdev.org/wiki/INES_Mapper_019
+// - https://www.nesdev.org/wiki/Namco_163_audio
+// ============================================================
+
+/// N163 波表聲道更新間隔（CPU 週期）
+const N163_UPDATE_CYCLES: u8 = 15;
+/// N163 相對於 APU 混音的音量
+const N163_GAIN: f32 = 0.3;
+
+pub struct Mapper19 {
+    prg_banks: u8,
+    /// $8000/$A000/$C000 的 8KB PRG bank
+    prg_regs: [u8; 3],
+    /// 1KB CHR bank
+    chr_regs: [u8; 8],
+    /// 名稱表選擇
+    nametable_regs: [u8; 4],
+    /// 內建 RAM（$40-$7F 為音效暫存器）
+    ram: [u8; 128],
+    /// 內建 RAM 位址
+    ram_addr: u8,
+    /// 讀寫 $4800 後位址自動加 1
+    auto_increment: bool,
+    irq_counter: u16,
+    irq_enabled: bool,
+    irq_pending: bool,
+    /// $E000 位元 6：關閉擴充音效
+    sound_disabled: bool,
+    /// 距離下次聲道更新的 CPU 週期
+    audio_timer: u8,
+    /// 下一個要更新的聲道
+    audio_channel: usize,
+    /// 各聲道最近一次的輸出（-120 ~ 105）
+    channel_output: [i16; 8],
+}
+
+impl Mapper19 {
+    pub fn new(prg_banks: u8, _chr_banks: u8) -> Self {
+        Mapper19 {
+            prg_banks,
+            prg_regs: [0, 1, 2],
+            chr_regs: [0; 8],
+            nametable_regs: [0; 4],
+            ram: [0; 128],
+            ram_addr: 0,
+            auto_increment: false,
+            irq_counter: 0,
+            irq_enabled: false,
+            irq_pending: false,
+            sound_disabled: false,
+            audio_timer: 0,
+            audio_channel: 7,
+            channel_output: [0; 8],
+        }
+    }
+
+    /// 啟用的波表聲道數（1-8）
+    fn audio_channels(&self) -> usize {
+        ((self.ram[0x7F] >> 4) & 0x07) as usize + 1
+    }
+
+    /// 讀寫 $4800 後推進內建 RAM 位址
+    fn advance_ram_addr(&mut self) {
+        if self.auto_increment {
+            self.ram_addr = (self.ram_addr + 1) & 0x7F;
+        }
+    }
+
+    /// 名稱表暫存器全部選擇主機 VRAM 時換算成鏡像模式
+    fn nametable_mirror(&self) -> Option<MirrorMode> {
+        if self.nametable_regs.iter().any(|&reg| reg < 0xE0) {
+            return None;
+        }
+        match self.nametable_regs.map(|reg| reg & 1) {
+            [0, 0, 1, 1] => Some(MirrorMode::Horizontal),
+            [0, 1, 0, 1] => Some(MirrorMode::Vertical),
+            [0, 0, 0, 0] => Some(MirrorMode::SingleScreenLow),
+            [1, 1, 1, 1] => Some(MirrorMode::SingleScreenHigh),
+            _ => None,
+        }
+    }
+
+    /// 更新一個波表聲道：相位加上頻率，依相位從內建 RAM 取出 4 位元取樣
+    fn clock_audio_channel(&mut self) {
+        let channels = self.audio_channels();
+        if !(8 - channels..8).contains(&self.audio_channel) {
+            self.audio_channel = 7;
+        }
+        let ch = self.audio_channel;
+        let regs = 0x40 + ch * 8;
+        let r = &mut self.ram;
+        let freq = r[regs] as u32 | (r[regs + 2] as u32) << 8 | ((r[regs + 4] & 0x03) as u32) << 16;
+        let length = (256 - (r[regs + 4] & 0xFC) as u32) << 16;
+        let mut phase = r[regs + 1] as u32 | (r[regs + 3] as u32) << 8 | (r[regs + 5] as u32) << 16;
+        phase = (phase + freq) % length;
+        r[regs + 1] = phase as u8;
+        r[regs + 3] = (phase >> 8) as u8;
+        r[regs + 5] = (phase >> 16) as u8;
+
+        let index = ((phase >> 16) + r[regs + 6] as u32) & 0xFF;
+        let byte = r[(index >> 1) as usize];
+        let sample = if index & 1 != 0 { byte >> 4 } else { byte & 0x0F };
+        self.channel_output[ch] = (sample as i16 - 8) * (r[regs + 7] & 0x0F) as i16;
+
+        self.audio_channel = if ch == 0 { 7 } else { ch - 1 };
+    }
+}
+
+impl MapperTrait for Mapper19 {
+    mapper_state!(
+        prg_regs, chr_regs, nametable_regs, ram, ram_addr, auto_increment,
+        irq_counter, irq_enabled, irq_pending, sound_disabled, audio_timer, audio_channel, channel_output,
+    );
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        let bank = match addr {
+            0x8000..=0xDFFF => self.prg_regs[((addr - 0x8000) >> 13) as usize] as u32,
+            0xE000..=0xFFFF => self.prg_banks as u32 * 2 - 1,
+            _ => return None,
+        };
+        Some(bank * 8192 + (addr & 0x1FFF) as u32)
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        match addr {
+            0x4800..=0x4FFF => {
+                self.ram[self.ram_addr as usize] = data;
+                self.advance_ram_addr();
+            }
+            0x5000..=0x57FF => {
+                self.irq_counter = (self.irq_counter & 0x7F00) | data as u16;
+                self.irq_pending = false;
+            }
+            0x5800..=0x5FFF => {
+                self.irq_counter = (self.irq_counter & 0x00FF) | ((data as u16 & 0x7F) << 8);
+                self.irq_enabled = data & 0x80 != 0;
+                self.irq_pending = false;
+            }
+            0x8000..=0xBFFF => {
+                self.chr_regs[((addr - 0x8000) >> 11) as usize] = data;
+                return Some(MapperWriteResult::none());
+            }
+            0xC000..=0xDFFF => {
+                self.nametable_regs[((addr - 0xC000) >> 11) as usize] = data;
+                return self.nametable_mirror().map(MapperWriteResult::with_mirror);
+            }
+            0xE000..=0xE7FF => {
+                self.prg_regs[0] = data & 0x3F;
+                self.sound_disabled = data & 0x40 != 0;
+            }
+            0xE800..=0xEFFF => self.prg_regs[1] = data & 0x3F,
+            0xF000..=0xF7FF => self.prg_regs[2] = data & 0x3F,
+            0xF800..=0xFFFF => {
+                self.ram_addr = data & 0x7F;
+                self.auto_increment = data & 0x80 != 0;
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn read_register(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x4800..=0x4FFF => {
+                let value = self.ram[self.ram_addr as usize];
+                self.advance_ram_addr();
+                Some(value)
+            }
+            0x5000..=0x57FF => Some(self.irq_counter as u8),
+            0x5800..=0x5FFF => Some((self.irq_counter >> 8) as u8 | (self.irq_enabled as u8) << 7),
+            _ => None,
+        }
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 {
+            Some(self.chr_regs[(addr >> 10) as usize] as u32 * 1024 + (addr & 0x03FF) as u32)
+        } else {
+            None
+        }
+    }
+
+    fn ppu_write(&self, _addr: u16) -> Option<u32> {
+        None
+    }
+
+    fn reset(&mut self) {
+        self.prg_regs = [0, 1, 2];
+        self.chr_regs = [0; 8];
+        self.nametable_regs = [0; 4];
+        self.irq_counter = 0;
+        self.irq_enabled = false;
+        self.irq_pending = false;
+        self.sound_disabled = false;
+        self.channel_output = [0; 8];
+    }
+
+    fn cpu_clock(&mut self) {
+        if self.irq_enabled && self.irq_counter < 0x7FFF {
+            self.irq_counter += 1;
+            if self.irq_counter == 0x7FFF {
+                self.irq_pending = true;
+            }
+        }
+    }
+
+    fn check_irq(&mut self) -> bool {
+        // 計數器停在 $7FFF，寫入計數器才確認
+        self.irq_pending
+    }
+
+    fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> {
+        Some(self)
+    }
+}
+
+impl ExpansionAudio for Mapper19 {
+    fn chip(&self) -> ExpansionChip {
+        ExpansionChip::N163
+    }
+
+    fn clock(&mut self) {
+        self.audio_timer += 1;
+        if self.audio_timer == N163_UPDATE_CYCLES {
+            self.audio_timer = 0;
+            self.clock_audio_channel();
+        }
+    }
+
+    fn output(&self) -> f32 {
+        if self.sound_disabled {
+            return 0.0;
+        }
+        let channels = self.audio_channels();
+        let sum: i16 = self.channel_output[8 - channels..].iter().sum();
+        sum as f32 / (channels as f32 * 120.0) * N163_GAIN
+    }
+}
+
+// ============================================================
+// Konami VRC IRQ - VRC4/VRC6/VRC7 共用的 IRQ 計數器
+// ============================================================
+// 8 位元計數器每次「時鐘」加 1，從 $FF 溢位時重新載入 latch 並觸發 IRQ。
+// 時鐘來源由控制暫存器的 M 位元選擇：
+// - 週期模式：每個 CPU 週期
+// - 掃描線模式：預除器從 341 起每個 CPU 週期減 3，≤ 0 時加回 341
+//   並送出一次時鐘，間隔為 114/114/113 個 CPU 週期（約一條掃描線），
+//   與 PPU 是否渲染無關
+//
+// 參考：https://www.nesdev.org/wiki/VRC_IRQ
+// ============================================================
+
+/// VRC IRQ 計數器
+#[derive(Debug, Clone, Default)]
+pub struct VrcIrq {
+    latch: u8,
+    counter: u8,
+    prescaler: i16,
+    /// E：計數器啟用
+    enabled: bool,
+    /// A：確認（acknowledge）後 E 改為此值
+    enable_after_ack: bool,
+    /// M：true 為週期模式，false 為掃描線模式
+    cycle_mode: bool,
+    pending: bool,
+}
+
+impl_state_fields!(VrcIrq { latch, counter, prescaler, enabled, enable_after_ack, cycle_mode, pending });
+
+impl VrcIrq {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 重新載入值
+    pub fn latch(&self) -> u8 {
+        self.latch
+    }
+
+    /// 設定重新載入值（VRC4 以高低 4 位元分兩次寫入，由呼叫端組合）
+    pub fn set_latch(&mut self, value: u8) {
+        self.latch = value;
+    }
+
+    /// 寫入控制暫存器（位元 0：A、位元 1：E、位元 2：M）
+    /// 會清除待處理的 IRQ；E 為 1 時重新載入計數器與預除器
+    pub fn write_control(&mut self, data: u8) {
+        self.enable_after_ack = data & 0x01 != 0;
+        self.enabled = data & 0x02 != 0;
+        self.cycle_mode = data & 0x04 != 0;
+        self.pending = false;
+        if self.enabled {
+            self.counter = self.latch;
+            self.prescaler = 341;
+        }
+    }
+
+    /// 確認 IRQ：清除待處理的 IRQ，E 改為 A
+    pub fn acknowledge(&mut self) {
+        self.pending = false;
+        self.enabled = self.enable_after_ack;
+    }
+
+    /// 每個 CPU 週期呼叫一次
+    #[inline]
+    pub fn clock(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        if self.cycle_mode {
+            self.clock_counter();
+        } else {
+            self.prescaler -= 3;
+            if self.prescaler <= 0 {
+                self.prescaler += 341;
+                self.clock_counter();
+            }
+        }
+    }
+
+    fn clock_counter(&mut self) {
+        if self.counter == 0xFF {
+            self.counter = self.latch;
+            self.pending = true;
+        } else {
+            self.counter += 1;
+        }
+    }
+
+    /// IRQ 是否待處理（確認前持續維持）
+    pub fn pending(&self) -> bool {
+        self.pending
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+// ============================================================
+// Mapper 23 (VRC2b/VRC4) - Konami VRC 系列
+// ============================================================
+// 支援精細的 PRG/CHR bank 切換和 IRQ
+// 用於：魂斗羅 Force 等 Konami 遊戲
+// ============================================================
+pub struct Mapper23 {
+    prg_banks: u8,
+    chr_banks: u8,
+    prg_bank0: u8,
+    prg_bank1: u8,
+    chr_bank_regs: [u8; 8],
+    prg_swap_mode: u8,
+    mirror_mode: MirrorMode,
+    // IRQ (VRC4)
+    irq: VrcIrq,
+}
+
+impl Mapper23 {
+    pub fn new(prg_banks: u8, chr_banks: u8) -> Self {
+        Mapper23 {
+            prg_banks, chr_banks,
+            prg_bank0: 0, prg_bank1: 0,
+            chr_bank_regs: [0; 8],
+            prg_swap_mode: 0,
+            mirror_mode: MirrorMode::Vertical,
+            irq: VrcIrq::new(),
+        }
+    }
+}
+
+impl MapperTrait for Mapper23 {
+    mapper_state!(prg_bank0, prg_bank1, chr_bank_regs, prg_swap_mode, mirror_mode, irq);
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        let total = self.prg_banks as u32 * 2; // 8KB banks
+        match addr {
+            0x8000..=0x9FFF => {
+                let bank = if self.prg_swap_mode != 0 { total - 2 } else { self.prg_bank0 as u32 };
+                Some((bank % total) * 8192 + (addr & 0x1FFF) as u32)
+            }
+            0xA000..=0xBFFF => {
+                Some((self.prg_bank1 as u32 % total) * 8192 + (addr & 0x1FFF) as u32)
+            }
+            0xC000..=0xDFFF => {
+                let bank = if self.prg_swap_mode != 0 { self.prg_bank0 as u32 } else { total - 2 };
+                Some((bank % total) * 8192 + (addr & 0x1FFF) as u32)
+            }
+            0xE000..=0xFFFF => {
+                Some((total - 1) * 8192 + (addr & 0x1FFF) as u32)
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        let a0 = addr & 0x0001;
+        let a1 = (addr & 0x0002) >> 1;
+        let reg = (addr & 0xF000) | (a1 << 1) | a0;
+
+        match reg {
             0x8000..=0x8003 => { self.prg_bank0 = data & 0x1F; }
             0x9000 | 0x9001 => {
                 self.mirror_mode = match data & 0x03 {
@@ -1008,87 +1996,664 @@ impl MapperTrait for Mapper23 {
                 };
                 return Some(MapperWriteResult::with_mirror(self.mirror_mode));
             }
-            0x9002 | 0x9003 => { self.prg_swap_mode = (data >> 1) & 0x01; }
-            0xA000..=0xA003 => { self.prg_bank1 = data & 0x1F; }
-            // CHR banks（每個暫存器分高低 4 位元寫入）
-            0xB000 => { self.chr_bank_regs[0] = (self.chr_bank_regs[0] & 0xF0) | (data & 0x0F); }
-            0xB001 => { self.chr_bank_regs[0] = (self.chr_bank_regs[0] & 0x0F) | ((data & 0x0F) << 4); }
-            0xB002 => { self.chr_bank_regs[1] = (self.chr_bank_regs[1] & 0xF0) | (data & 0x0F); }
-            0xB003 => { self.chr_bank_regs[1] = (self.chr_bank_regs[1] & 0x0F) | ((data & 0x0F) << 4); }
-            0xC000 => { self.chr_bank_regs[2] = (self.chr_bank_regs[2] & 0xF0) | (data & 0x0F); }
-            0xC001 => { self.chr_bank_regs[2] = (self.chr_bank_regs[2] & 0x0F) | ((data & 0x0F) << 4); }
-            0xC002 => { self.chr_bank_regs[3] = (self.chr_bank_regs[3] & 0xF0) | (data & 0x0F); }
-            0xC003 => { self.chr_bank_regs[3] = (self.chr_bank_regs[3] & 0x0F) | ((data & 0x0F) << 4); }
-            0xD000 => { self.chr_bank_regs[4] = (self.chr_bank_regs[4] & 0xF0) | (data & 0x0F); }
-            0xD001 => { self.chr_bank_regs[4] = (self.chr_bank_regs[4] & 0x0F) | ((data & 0x0F) << 4); }
-            0xD002 => { self.chr_bank_regs[5] = (self.chr_bank_regs[5] & 0xF0) | (data & 0x0F); }
-            0xD003 => { self.chr_bank_regs[5] = (self.chr_bank_regs[5] & 0x0F) | ((data & 0x0F) << 4); }
-            0xE000 => { self.chr_bank_regs[6] = (self.chr_bank_regs[6] & 0xF0) | (data & 0x0F); }
-            0xE001 => { self.chr_bank_regs[6] = (self.chr_bank_regs[6] & 0x0F) | ((data & 0x0F) << 4); }
-            0xE002 => { self.chr_bank_regs[7] = (self.chr_bank_regs[7] & 0xF0) | (data & 0x0F); }
-            0xE003 => { self.chr_bank_regs[7] = (self.chr_bank_regs[7] & 0x0F) | ((data & 0x0F) << 4); }
-            // IRQ
-            0xF000 => { self.irq_latch = (self.irq_latch & 0xF0) | (data & 0x0F); }
-            0xF001 => { self.irq_latch = (self.irq_latch & 0x0F) | ((data & 0x0F) << 4); }
-            0xF002 => {
-                self.irq_control = data;
-                self.irq_enabled = (data & 0x02) != 0;
-                if data & 0x02 != 0 {
-                    self.irq_counter = self.irq_latch;
-                    self.irq_prescaler = 341;
-                }
+            0x9002 | 0x9003 => { self.prg_swap_mode = (data >> 1) & 0x01; }
+            0xA000..=0xA003 => { self.prg_bank1 = data & 0x1F; }
+            // CHR banks（每個暫存器分高低 4 位元寫入）
+            0xB000 => { self.chr_bank_regs[0] = (self.chr_bank_regs[0] & 0xF0) | (data & 0x0F); }
+            0xB001 => { self.chr_bank_regs[0] = (self.chr_bank_regs[0] & 0x0F) | ((data & 0x0F) << 4); }
+            0xB002 => { self.chr_bank_regs[1] = (self.chr_bank_regs[1] & 0xF0) | (data & 0x0F); }
+            0xB003 => { self.chr_bank_regs[1] = (self.chr_bank_regs[1] & 0x0F) | ((data & 0x0F) << 4); }
+            0xC000 => { self.chr_bank_regs[2] = (self.chr_bank_regs[2] & 0xF0) | (data & 0x0F); }
+            0xC001 => { self.chr_bank_regs[2] = (self.chr_bank_regs[2] & 0x0F) | ((data & 0x0F) << 4); }
+            0xC002 => { self.chr_bank_regs[3] = (self.chr_bank_regs[3] & 0xF0) | (data & 0x0F); }
+            0xC003 => { self.chr_bank_regs[3] = (self.chr_bank_regs[3] & 0x0F) | ((data & 0x0F) << 4); }
+            0xD000 => { self.chr_bank_regs[4] = (self.chr_bank_regs[4] & 0xF0) | (data & 0x0F); }
+            0xD001 => { self.chr_bank_regs[4] = (self.chr_bank_regs[4] & 0x0F) | ((data & 0x0F) << 4); }
+            0xD002 => { self.chr_bank_regs[5] = (self.chr_bank_regs[5] & 0xF0) | (data & 0x0F); }
+            0xD003 => { self.chr_bank_regs[5] = (self.chr_bank_regs[5] & 0x0F) | ((data & 0x0F) << 4); }
+            0xE000 => { self.chr_bank_regs[6] = (self.chr_bank_regs[6] & 0xF0) | (data & 0x0F); }
+            0xE001 => { self.chr_bank_regs[6] = (self.chr_bank_regs[6] & 0x0F) | ((data & 0x0F) << 4); }
+            0xE002 => { self.chr_bank_regs[7] = (self.chr_bank_regs[7] & 0xF0) | (data & 0x0F); }
+            0xE003 => { self.chr_bank_regs[7] = (self.chr_bank_regs[7] & 0x0F) | ((data & 0x0F) << 4); }
+            // IRQ
+            0xF000 => { self.irq.set_latch((self.irq.latch() & 0xF0) | (data & 0x0F)); }
+            0xF001 => { self.irq.set_latch((self.irq.latch() & 0x0F) | ((data & 0x0F) << 4)); }
+            0xF002 => { self.irq.write_control(data); }
+            0xF003 => { self.irq.acknowledge(); }
+            _ => {}
+        }
+        if (0xB000..0xF000).contains(&reg) {
+            return Some(MapperWriteResult::none());
+        }
+        None
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 {
+            let region = (addr >> 10) as usize;
+            let bank = self.chr_bank_regs[region] as u32;
+            let total = self.chr_banks as u32 * 8;
+            Some((bank % total.max(1)) * 1024 + (addr & 0x3FF) as u32)
+        } else {
+            None
+        }
+    }
+
+    fn ppu_write(&self, _addr: u16) -> Option<u32> { None }
+
+    fn reset(&mut self) {
+        self.prg_bank0 = 0; self.prg_bank1 = 0;
+        self.chr_bank_regs = [0; 8];
+        self.prg_swap_mode = 0;
+        self.irq.reset();
+    }
+
+    fn cpu_clock(&mut self) {
+        self.irq.clock();
+    }
+
+    fn check_irq(&mut self) -> bool {
+        self.irq.pending()
+    }
+}
+
+// ============================================================
+// Mapper 24/26 (VRC6a/VRC6b) - Konami VRC6 與擴充音效
+// ============================================================
+// PRG ROM: $8000-$BFFF 可切換 16KB，$C000-$DFFF 可切換 8KB，
+//          $E000-$FFFF 固定為最後一個 8KB
+// CHR ROM: 八個 1KB bank（$D000-$E003）
+// 鏡像: $B003 位元 2-3；位元 7 啟用 PRG RAM
+// IRQ: 與 VRC4 相同的 VrcIrq（$F000 重新載入值、$F001 控制、$F002 確認）
+// Mapper 26 的 A0/A1 位址線對調
+//
+// 擴充音效：兩個脈衝波（16 階佔空比、4 位元音量）與一個鋸齒波
+// （累加器每兩次計時加一次，第 7 次後歸零，輸出高 5 位元），
+// 計時器都以 CPU 週期倒數，$9003 可暫停全部聲道或把頻率提高 16/256 倍。
+//
+// 只實作 $B003 位元 0-1 為 0 的 CHR 模式（八個 1KB bank），市售遊戲都使用這個模式。
+//
+// 用於：惡魔城傳說（日版）、Esper Dream 2、魍魎戰記 MADARA
+//
+// 參考：
+// - https://www.nesdev.org/wiki/VRC6
+// - https://www.nesdev.org/wiki/VRC6_audio
+// ============================================================
+
+/// VRC6 每一階音量相對於 APU 混音的大小（音量 15 的脈衝波與 APU 脈衝波同樣響）
+const VRC6_GAIN: f32 = 0.0099;
+
+/// VRC6 脈衝波聲道
+#[derive(Debug, Clone)]
+struct Vrc6Pulse {
+    volume: u8,
+    duty: u8,
+    /// 忽略佔空比，持續輸出音量
+    constant: bool,
+    period: u16,
+    enabled: bool,
+    timer: u16,
+    /// 佔空比序列位置（15 往下數到 0）
+    step: u8,
+}
+
+impl_state_fields!(Vrc6Pulse { volume, duty, constant, period, enabled, timer, step });
+
+impl Vrc6Pulse {
+    fn new() -> Self {
+        Vrc6Pulse { volume: 0, duty: 0, constant: false, period: 0, enabled: false, timer: 0, step: 15 }
+    }
+
+    fn write(&mut self, reg: u16, data: u8) {
+        match reg {
+            0 => {
+                self.volume = data & 0x0F;
+                self.duty = (data >> 4) & 0x07;
+                self.constant = data & 0x80 != 0;
+            }
+            1 => self.period = (self.period & 0x0F00) | data as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | ((data as u16 & 0x0F) << 8);
+                self.enabled = data & 0x80 != 0;
+                if !self.enabled {
+                    self.step = 15;
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self, shift: u8) {
+        if !self.enabled {
+            return;
+        }
+        if self.timer == 0 {
+            self.timer = self.period >> shift;
+            self.step = self.step.wrapping_sub(1) & 0x0F;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        if self.enabled && (self.constant || self.step <= self.duty) { self.volume } else { 0 }
+    }
+}
+
+/// VRC6 鋸齒波聲道
+#[derive(Debug, Clone)]
+struct Vrc6Saw {
+    rate: u8,
+    period: u16,
+    enabled: bool,
+    timer: u16,
+    /// 本次週期已計時的次數（0-13）
+    step: u8,
+    accumulator: u8,
+}
+
+impl_state_fields!(Vrc6Saw { rate, period, enabled, timer, step, accumulator });
+
+impl Vrc6Saw {
+    fn new() -> Self {
+        Vrc6Saw { rate: 0, period: 0, enabled: false, timer: 0, step: 0, accumulator: 0 }
+    }
+
+    fn write(&mut self, reg: u16, data: u8) {
+        match reg {
+            0 => self.rate = data & 0x3F,
+            1 => self.period = (self.period & 0x0F00) | data as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | ((data as u16 & 0x0F) << 8);
+                self.enabled = data & 0x80 != 0;
+                if !self.enabled {
+                    self.step = 0;
+                    self.accumulator = 0;
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self, shift: u8) {
+        if !self.enabled {
+            return;
+        }
+        if self.timer > 0 {
+            self.timer -= 1;
+            return;
+        }
+        self.timer = self.period >> shift;
+        self.step += 1;
+        if self.step >= 14 {
+            self.step = 0;
+            self.accumulator = 0;
+        } else if self.step.is_multiple_of(2) {
+            self.accumulator = self.accumulator.wrapping_add(self.rate);
+        }
+    }
+
+    fn output(&self) -> u8 {
+        self.accumulator >> 3
+    }
+}
+
+pub struct Mapper24 {
+    prg_banks: u8,
+    /// Mapper 26：A0/A1 對調
+    swap_lines: bool,
+    prg_bank_16k: u8,
+    prg_bank_8k: u8,
+    chr_bank_regs: [u8; 8],
+    /// $B003：位元 2-3 鏡像、位元 7 PRG RAM 啟用
+    ppu_control: u8,
+    irq: VrcIrq,
+    pulse: [Vrc6Pulse; 2],
+    saw: Vrc6Saw,
+    /// $9003：位元 0 暫停、位元 1/2 頻率 ×16/×256
+    frequency_control: u8,
+}
+
+impl Mapper24 {
+    pub fn new(prg_banks: u8, _chr_banks: u8) -> Self {
+        Mapper24 {
+            prg_banks,
+            swap_lines: false,
+            prg_bank_16k: 0,
+            prg_bank_8k: 0,
+            chr_bank_regs: [0; 8],
+            ppu_control: 0x80,
+            irq: VrcIrq::new(),
+            pulse: [Vrc6Pulse::new(), Vrc6Pulse::new()],
+            saw: Vrc6Saw::new(),
+            frequency_control: 0,
+        }
+    }
+
+    /// Mapper 26（VRC6b）：A0/A1 位址線對調
+    pub fn new_vrc6b(prg_banks: u8, chr_banks: u8) -> Self {
+        Mapper24 { swap_lines: true, ..Self::new(prg_banks, chr_banks) }
+    }
+
+    /// $9003 設定的頻率位移量
+    fn frequency_shift(&self) -> u8 {
+        match self.frequency_control & 0x06 {
+            0 => 0,
+            0x02 => 4,
+            _ => 8,
+        }
+    }
+}
+
+impl MapperTrait for Mapper24 {
+    mapper_state!(prg_bank_16k, prg_bank_8k, chr_bank_regs, ppu_control, irq, pulse, saw, frequency_control);
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        match addr {
+            0x8000..=0xBFFF => Some(self.prg_bank_16k as u32 * 16384 + (addr & 0x3FFF) as u32),
+            0xC000..=0xDFFF => Some(self.prg_bank_8k as u32 * 8192 + (addr & 0x1FFF) as u32),
+            0xE000..=0xFFFF => Some((self.prg_banks as u32 * 2 - 1) * 8192 + (addr & 0x1FFF) as u32),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        let low = if self.swap_lines {
+            ((addr & 0x01) << 1) | ((addr & 0x02) >> 1)
+        } else {
+            addr & 0x03
+        };
+        match (addr & 0xF000, low) {
+            (0x8000, _) => self.prg_bank_16k = data & 0x0F,
+            (0x9000, 3) => self.frequency_control = data,
+            (0x9000, reg) => self.pulse[0].write(reg, data),
+            (0xA000, 3) => {}
+            (0xA000, reg) => self.pulse[1].write(reg, data),
+            (0xB000, 3) => {
+                self.ppu_control = data;
+                let mirror = match (data >> 2) & 0x03 {
+                    0 => MirrorMode::Vertical,
+                    1 => MirrorMode::Horizontal,
+                    2 => MirrorMode::SingleScreenLow,
+                    _ => MirrorMode::SingleScreenHigh,
+                };
+                return Some(MapperWriteResult::with_mirror(mirror));
+            }
+            (0xB000, reg) => self.saw.write(reg, data),
+            (0xC000, _) => self.prg_bank_8k = data & 0x1F,
+            (0xD000, reg) => {
+                self.chr_bank_regs[reg as usize] = data;
+                return Some(MapperWriteResult::none());
+            }
+            (0xE000, reg) => {
+                self.chr_bank_regs[4 + reg as usize] = data;
+                return Some(MapperWriteResult::none());
+            }
+            (0xF000, 0) => self.irq.set_latch(data),
+            (0xF000, 1) => self.irq.write_control(data),
+            (0xF000, 2) => self.irq.acknowledge(),
+            _ => {}
+        }
+        None
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 {
+            Some(self.chr_bank_regs[(addr >> 10) as usize] as u32 * 1024 + (addr & 0x03FF) as u32)
+        } else {
+            None
+        }
+    }
+
+    fn ppu_write(&self, _addr: u16) -> Option<u32> {
+        None
+    }
+
+    fn reset(&mut self) {
+        self.prg_bank_16k = 0;
+        self.prg_bank_8k = 0;
+        self.chr_bank_regs = [0; 8];
+        self.ppu_control = 0x80;
+        self.irq.reset();
+        self.pulse = [Vrc6Pulse::new(), Vrc6Pulse::new()];
+        self.saw = Vrc6Saw::new();
+        self.frequency_control = 0;
+    }
+
+    fn cpu_clock(&mut self) {
+        self.irq.clock();
+    }
+
+    fn check_irq(&mut self) -> bool {
+        self.irq.pending()
+    }
+
+    fn prg_ram_access(&self) -> PrgRamAccess {
+        if self.ppu_control & 0x80 != 0 { PrgRamAccess::ReadWrite } else { PrgRamAccess::Disabled }
+    }
+
+    fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> {
+        Some(self)
+    }
+}
+
+impl ExpansionAudio for Mapper24 {
+    fn chip(&self) -> ExpansionChip {
+        ExpansionChip::Vrc6
+    }
+
+    fn clock(&mut self) {
+        if self.frequency_control & 0x01 == 0 {
+            let shift = self.frequency_shift();
+            self.pulse[0].clock(shift);
+            self.pulse[1].clock(shift);
+            self.saw.clock(shift);
+        }
+    }
+
+    fn output(&self) -> f32 {
+        let level = self.pulse[0].output() + self.pulse[1].output() + self.saw.output();
+        level as f32 * VRC6_GAIN
+    }
+}
+
+// ============================================================
+// Mapper 64 (Tengen RAMBO-1) - 加強版 MMC3
+// ============================================================
+// 暫存器位址與 MMC3 相同，差異在於：
+// - $8000 位元 5（K）：R0/R1 改成 1KB bank，另外由 R8/R9 補上 $0400/$0C00
+// - 多一個 PRG 暫存器 RF，$E000 才固定為最後一個 bank
+// - IRQ 計數器可選擇 A12 模式（與 MMC3 相同），或每 4 個 CPU 週期計數一次的週期模式
+//   （$C001 位元 0），寫入 $C001 也會重設 4 分頻的預除器
+// 計數器在 $C001 之後的第一次時鐘重新載入 latch + 1（latch ≤ 1 時為 latch），
+// 歸零後才會觸發 IRQ。
+// 用於：Klax、Skull & Crossbones、Shinobi（Tengen 版）等
+//
+// 參考：https://www.nesdev.org/wiki/RAMBO-1
+// ============================================================
+pub struct Mapper64 {
+    prg_banks: u8,
+    chr_banks: u8,
+    /// Bank 暫存器（R0-R9、RF，索引即暫存器編號）
+    registers: [u8; 16],
+    /// $8000：位元 0-3 暫存器編號、5 = 1KB CHR 模式、6 = PRG 模式、7 = CHR A12 反轉
+    bank_select: u8,
+    mirror_mode: MirrorMode,
+
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    /// IRQ 以 CPU 週期（而非 A12 上升緣）計數
+    irq_cycle_mode: bool,
+    /// 週期模式的 4 分頻預除器
+    irq_prescaler: u8,
+    irq_pending: bool,
+}
+
+impl Mapper64 {
+    pub fn new(prg_banks: u8, chr_banks: u8) -> Self {
+        Mapper64 {
+            prg_banks,
+            chr_banks,
+            registers: [0; 16],
+            bank_select: 0,
+            mirror_mode: MirrorMode::Vertical,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq_cycle_mode: false,
+            irq_prescaler: 0,
+            irq_pending: false,
+        }
+    }
+
+    /// 計數器時鐘（A12 上升緣或每 4 個 CPU 週期）
+    fn clock_irq_counter(&mut self) {
+        if self.irq_reload {
+            self.irq_counter = if self.irq_latch <= 1 { self.irq_latch } else { self.irq_latch.wrapping_add(1) };
+            self.irq_reload = false;
+        } else if self.irq_counter == 0 {
+            self.irq_counter = self.irq_latch;
+        } else {
+            self.irq_counter -= 1;
+        }
+        if self.irq_counter == 0 && self.irq_enabled {
+            self.irq_pending = true;
+        }
+    }
+}
+
+impl MapperTrait for Mapper64 {
+    mapper_state!(
+        registers, bank_select, mirror_mode,
+        irq_latch, irq_counter, irq_reload, irq_enabled, irq_cycle_mode, irq_prescaler, irq_pending,
+    );
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        if addr < 0x8000 {
+            return None;
+        }
+        let count = (self.prg_banks as u32 * 2).max(1);
+        let r = |index: usize| self.registers[index] as u32;
+        let bank = match (addr >> 13) & 0x03 {
+            3 => count - 1,
+            slot if self.bank_select & 0x40 == 0 => [r(6), r(7), r(15)][slot as usize],
+            slot => [r(15), r(6), r(7)][slot as usize],
+        };
+        Some((bank % count) * 8192 + (addr & 0x1FFF) as u32)
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        if addr < 0x8000 {
+            return None;
+        }
+        let even = addr & 1 == 0;
+        match (addr >> 13) & 0x03 {
+            0 if even => {
+                self.bank_select = data;
+                return Some(MapperWriteResult::none());
+            }
+            0 => {
+                self.registers[(self.bank_select & 0x0F) as usize] = data;
+                return Some(MapperWriteResult::none());
+            }
+            1 if even => {
+                self.mirror_mode = if data & 1 != 0 { MirrorMode::Horizontal } else { MirrorMode::Vertical };
+                return Some(MapperWriteResult::with_mirror(self.mirror_mode));
+            }
+            2 if even => self.irq_latch = data,
+            2 => {
+                self.irq_cycle_mode = data & 0x01 != 0;
+                self.irq_prescaler = 0;
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            }
+            3 if even => {
+                self.irq_enabled = false;
+                self.irq_pending = false;
+            }
+            3 => self.irq_enabled = true,
+            _ => {}
+        }
+        None
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        if addr >= 0x2000 {
+            return None;
+        }
+        if self.chr_banks == 0 {
+            return Some(addr as u32);
+        }
+        // A12 反轉時左右兩半對調
+        let region = ((addr >> 10) as usize) ^ if self.bank_select & 0x80 != 0 { 4 } else { 0 };
+        let r = |index: usize| self.registers[index] as u32;
+        let bank = match region {
+            0..=3 if self.bank_select & 0x20 != 0 => [r(0), r(8), r(1), r(9)][region],
+            0..=3 => (r(region >> 1) & 0xFE) | (region as u32 & 1),
+            _ => r(region - 2),
+        };
+        Some((bank % (self.chr_banks as u32 * 8)) * 1024 + (addr & 0x03FF) as u32)
+    }
+
+    fn ppu_write(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 && self.chr_banks == 0 { Some(addr as u32) } else { None }
+    }
+
+    fn reset(&mut self) {
+        self.registers = [0; 16];
+        self.bank_select = 0;
+        self.mirror_mode = MirrorMode::Vertical;
+        self.irq_latch = 0;
+        self.irq_counter = 0;
+        self.irq_reload = false;
+        self.irq_enabled = false;
+        self.irq_cycle_mode = false;
+        self.irq_prescaler = 0;
+        self.irq_pending = false;
+    }
+
+    fn a12_clock(&mut self) {
+        if !self.irq_cycle_mode {
+            self.clock_irq_counter();
+        }
+    }
+
+    fn cpu_clock(&mut self) {
+        if self.irq_cycle_mode {
+            self.irq_prescaler = (self.irq_prescaler + 1) & 0x03;
+            if self.irq_prescaler == 0 {
+                self.clock_irq_counter();
+            }
+        }
+    }
+
+    fn check_irq(&mut self) -> bool {
+        self.irq_pending
+    }
+}
+
+// ============================================================
+// Mapper 65 (Irem H3001) - 16 位元 CPU 週期 IRQ
+// ============================================================
+// - $8000/$A000/$C000：三個 8KB PRG bank，$E000 固定為最後一個
+// - $9001：位元 7 選擇鏡像（0 = 垂直、1 = 水平）
+// - $9003：位元 7 啟用 IRQ；$9004：把 latch 載入計數器；兩者都會確認 IRQ
+// - $9005/$9006：16 位元 latch 的高/低位元組
+// - $B000-$B007：8 個 1KB CHR bank
+// 啟用時計數器每個 CPU 週期減 1，減到 0 時觸發 IRQ 並停住。
+// 用於：大工の源さん 2、Spartan X 2 等
+//
+// 參考：https://www.nesdev.org/wiki/INES_Mapper_065
+// ============================================================
+pub struct Mapper65 {
+    prg_banks: u8,
+    chr_banks: u8,
+    prg_regs: [u8; 3],
+    chr_regs: [u8; 8],
+    mirror_mode: MirrorMode,
+    irq_enabled: bool,
+    irq_latch: u16,
+    irq_counter: u16,
+    irq_pending: bool,
+}
+
+impl Mapper65 {
+    pub fn new(prg_banks: u8, chr_banks: u8) -> Self {
+        Mapper65 {
+            prg_banks,
+            chr_banks,
+            prg_regs: [0, 1, 0xFE],
+            chr_regs: [0; 8],
+            mirror_mode: MirrorMode::Vertical,
+            irq_enabled: false,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_pending: false,
+        }
+    }
+}
+
+impl MapperTrait for Mapper65 {
+    mapper_state!(prg_regs, chr_regs, mirror_mode, irq_enabled, irq_latch, irq_counter, irq_pending);
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        if addr < 0x8000 {
+            return None;
+        }
+        let count = (self.prg_banks as u32 * 2).max(1);
+        let bank = match addr {
+            0xE000..=0xFFFF => count - 1,
+            _ => self.prg_regs[((addr - 0x8000) >> 13) as usize] as u32,
+        };
+        Some((bank % count) * 8192 + (addr & 0x1FFF) as u32)
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        match addr {
+            0x8000 => self.prg_regs[0] = data,
+            0xA000 => self.prg_regs[1] = data,
+            0xC000 => self.prg_regs[2] = data,
+            0x9001 => {
+                self.mirror_mode = if data & 0x80 != 0 { MirrorMode::Horizontal } else { MirrorMode::Vertical };
+                return Some(MapperWriteResult::with_mirror(self.mirror_mode));
+            }
+            0x9003 => {
+                self.irq_enabled = data & 0x80 != 0;
                 self.irq_pending = false;
             }
-            0xF003 => {
-                self.irq_enabled = (self.irq_control & 0x01) != 0;
+            0x9004 => {
+                self.irq_counter = self.irq_latch;
                 self.irq_pending = false;
             }
+            0x9005 => self.irq_latch = (self.irq_latch & 0x00FF) | (data as u16) << 8,
+            0x9006 => self.irq_latch = (self.irq_latch & 0xFF00) | data as u16,
+            0xB000..=0xB007 => {
+                self.chr_regs[(addr & 0x07) as usize] = data;
+                return Some(MapperWriteResult::none());
+            }
             _ => {}
         }
         None
     }
 
     fn ppu_read(&self, addr: u16) -> Option<u32> {
-        if addr < 0x2000 {
-            let region = (addr >> 10) as usize;
-            let bank = self.chr_bank_regs[region] as u32;
-            let total = self.chr_banks as u32 * 8;
-            Some((bank % total.max(1)) * 1024 + (addr & 0x3FF) as u32)
-        } else {
-            None
+        if addr >= 0x2000 {
+            return None;
+        }
+        if self.chr_banks == 0 {
+            return Some(addr as u32);
         }
+        let bank = self.chr_regs[(addr >> 10) as usize] as u32;
+        Some((bank % (self.chr_banks as u32 * 8)) * 1024 + (addr & 0x03FF) as u32)
     }
 
-    fn ppu_write(&self, _addr: u16) -> Option<u32> { None }
+    fn ppu_write(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 && self.chr_banks == 0 { Some(addr as u32) } else { None }
+    }
 
     fn reset(&mut self) {
-        self.prg_bank0 = 0; self.prg_bank1 = 0;
-        self.chr_bank_regs = [0; 8];
-        self.prg_swap_mode = 0;
-        self.irq_latch = 0; self.irq_control = 0;
-        self.irq_counter = 0; self.irq_prescaler = 0;
-        self.irq_enabled = false; self.irq_pending = false;
+        self.prg_regs = [0, 1, 0xFE];
+        self.chr_regs = [0; 8];
+        self.mirror_mode = MirrorMode::Vertical;
+        self.irq_enabled = false;
+        self.irq_latch = 0;
+        self.irq_counter = 0;
+        self.irq_pending = false;
     }
 
-    fn scanline(&mut self) {
-        if self.irq_enabled {
-            self.irq_prescaler -= 3;
-            if self.irq_prescaler <= 0 {
-                self.irq_prescaler += 341;
-                if self.irq_counter == 0xFF {
-                    self.irq_counter = self.irq_latch;
-                    self.irq_pending = true;
-                } else {
-                    self.irq_counter += 1;
-                }
+    fn cpu_clock(&mut self) {
+        if self.irq_enabled && self.irq_counter > 0 {
+            self.irq_counter -= 1;
+            if self.irq_counter == 0 {
+                self.irq_pending = true;
             }
         }
     }
 
     fn check_irq(&mut self) -> bool {
-        let p = self.irq_pending;
-        self.irq_pending = false;
-        p
+        // 寫入 $9003/$9004 才確認
+        self.irq_pending
     }
 }
 
@@ -1109,6 +2674,8 @@ impl Mapper66 {
 }
 
 impl MapperTrait for Mapper66 {
+    mapper_state!(prg_bank, chr_bank);
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
         if addr >= 0x8000 {
             let bank = self.prg_bank as u32 % self.prg_banks.max(1) as u32;
@@ -1119,6 +2686,7 @@ impl MapperTrait for Mapper66 {
         if addr >= 0x8000 {
             self.chr_bank = data & 0x03;
             self.prg_bank = (data >> 4) & 0x03;
+            return Some(MapperWriteResult::none());
         }
         None
     }
@@ -1132,6 +2700,367 @@ impl MapperTrait for Mapper66 {
     fn reset(&mut self) { self.prg_bank = 0; self.chr_bank = 0; }
 }
 
+// ============================================================
+// Mapper 69 (Sunsoft FME-7 / 5B) - 命令/參數暫存器與 5B 擴充音效
+// ============================================================
+// 所有設定都經過兩個暫存器：$8000 寫入命令編號，$A000 寫入參數
+// - 命令 0-7：八個 1KB CHR bank
+// - 命令 8：$6000-$7FFF，位元 0-5 為 ROM bank，位元 6 = 1 改為 PRG RAM，
+//           位元 7 啟用 RAM（選 RAM 但未啟用時為開放匯流排）
+// - 命令 9-B：$8000/$A000/$C000 的 8KB PRG bank；$E000 固定為最後一個
+// - 命令 C：鏡像（0 垂直、1 水平、2/3 單屏）
+// - 命令 D：IRQ 控制（位元 0 啟用 IRQ、位元 7 啟用計數器），寫入同時確認 IRQ
+// - 命令 E/F：16 位元計數器低/高位元組，每個 CPU 週期減 1，
+//             從 $0000 減到 $FFFF 時觸發
+//
+// 5B 在 FME-7 之外加上 YM2149（AY-3-8910 相容）音源：$C000 選擇
+// 暫存器（高 4 位元必須為 0）、$E000 寫入資料。三個方波聲道、一個
+// 17 位元 LFSR 雜訊與共用的包絡線，時脈為 CPU 的 1/16；音量是對數
+// 刻度，每一階約 3dB。
+//
+// 用於：Gimmick!、Batman: Return of the Joker、Hebereke
+//
+// 參考：
+// - https://www.nesdev.org/wiki/Sunsoft_FME-7
+// - https://www.nesdev.org/wiki/Sunsoft_5B_audio
+// ============================================================
+
+/// 5B 聲道音量 15 時相對於 APU 混音的大小（與 APU 脈衝波的最大音量相近）
+const SUNSOFT_5B_GAIN: f32 = 0.15;
+
+/// 5B 的 4 位元音量換算成振幅（每一階約 3dB，0 為靜音）
+const SUNSOFT_5B_VOLUME: [f32; 16] = [
+    0.0, 0.0078, 0.0110, 0.0156, 0.0221, 0.0313, 0.0442, 0.0625,
+    0.0884, 0.1250, 0.1768, 0.2500, 0.3536, 0.5000, std::f32::consts::FRAC_1_SQRT_2, 1.0,
+];
+
+/// 5B 音源（YM2149 的子集：沒有 I/O 埠）
+#[derive(Debug, Clone)]
+struct Sunsoft5b {
+    /// $C000 選擇的暫存器
+    address: u8,
+    /// $C000 高 4 位元不為 0 時忽略資料寫入
+    write_enabled: bool,
+    /// 每 16 個 CPU 週期推進一次音源
+    prescaler: u8,
+    tone_period: [u16; 3],
+    tone_timer: [u16; 3],
+    tone_high: [bool; 3],
+    noise_period: u8,
+    noise_timer: u8,
+    noise_lfsr: u32,
+    /// 位元 0-2 關閉方波、位元 3-5 關閉雜訊（A/B/C）
+    mixer: u8,
+    /// 位元 0-3 音量，位元 4 改用包絡線
+    volume: [u8; 3],
+    envelope_period: u16,
+    envelope_timer: u16,
+    /// 位元 0 保持、1 交替、2 上升、3 持續
+    envelope_shape: u8,
+    envelope_step: u8,
+    envelope_rising: bool,
+    envelope_holding: bool,
+}
+
+impl_state_fields!(Sunsoft5b {
+    address, write_enabled, prescaler, tone_period, tone_timer, tone_high,
+    noise_period, noise_timer, noise_lfsr, mixer, volume,
+    envelope_period, envelope_timer, envelope_shape, envelope_step, envelope_rising, envelope_holding,
+});
+
+impl Sunsoft5b {
+    fn new() -> Self {
+        Sunsoft5b {
+            address: 0,
+            write_enabled: true,
+            prescaler: 0,
+            tone_period: [0; 3],
+            tone_timer: [0; 3],
+            tone_high: [false; 3],
+            noise_period: 0,
+            noise_timer: 0,
+            noise_lfsr: 1,
+            mixer: 0xFF,
+            volume: [0; 3],
+            envelope_period: 0,
+            envelope_timer: 0,
+            envelope_shape: 0,
+            envelope_step: 0,
+            envelope_rising: false,
+            envelope_holding: true,
+        }
+    }
+
+    /// $C000：選擇暫存器
+    fn select(&mut self, data: u8) {
+        self.address = data & 0x0F;
+        self.write_enabled = data & 0xF0 == 0;
+    }
+
+    /// $E000：寫入選擇的暫存器
+    fn write(&mut self, data: u8) {
+        if !self.write_enabled {
+            return;
+        }
+        match self.address {
+            reg @ (0x00 | 0x02 | 0x04) => {
+                let ch = reg as usize / 2;
+                self.tone_period[ch] = (self.tone_period[ch] & 0x0F00) | data as u16;
+            }
+            reg @ (0x01 | 0x03 | 0x05) => {
+                let ch = reg as usize / 2;
+                self.tone_period[ch] = (self.tone_period[ch] & 0x00FF) | ((data as u16 & 0x0F) << 8);
+            }
+            0x06 => self.noise_period = data & 0x1F,
+            0x07 => self.mixer = data,
+            reg @ 0x08..=0x0A => self.volume[reg as usize - 8] = data & 0x1F,
+            0x0B => self.envelope_period = (self.envelope_period & 0xFF00) | data as u16,
+            0x0C => self.envelope_period = (self.envelope_period & 0x00FF) | ((data as u16) << 8),
+            0x0D => {
+                // 寫入形狀時包絡線從頭開始
+                self.envelope_shape = data & 0x0F;
+                self.envelope_step = 0;
+                self.envelope_timer = 0;
+                self.envelope_rising = data & 0x04 != 0;
+                self.envelope_holding = false;
+            }
+            _ => {}
+        }
+    }
+
+    /// 每個 CPU 週期呼叫
+    fn clock(&mut self) {
+        self.prescaler += 1;
+        if self.prescaler < 16 {
+            return;
+        }
+        self.prescaler = 0;
+
+        for ch in 0..3 {
+            self.tone_timer[ch] += 1;
+            if self.tone_timer[ch] >= self.tone_period[ch].max(1) {
+                self.tone_timer[ch] = 0;
+                self.tone_high[ch] = !self.tone_high[ch];
+            }
+        }
+
+        // 雜訊與包絡線的計時器比方波再慢一半
+        self.noise_timer += 1;
+        if self.noise_timer >= self.noise_period.max(1) * 2 {
+            self.noise_timer = 0;
+            let feedback = (self.noise_lfsr ^ (self.noise_lfsr >> 3)) & 1;
+            self.noise_lfsr = (self.noise_lfsr >> 1) | (feedback << 16);
+        }
+
+        self.envelope_timer += 1;
+        if self.envelope_timer as u32 >= self.envelope_period.max(1) as u32 * 2 {
+            self.envelope_timer = 0;
+            self.clock_envelope();
+        }
+    }
+
+    /// 包絡線前進一階；一輪 16 階結束時依形狀決定保持、交替或重複
+    fn clock_envelope(&mut self) {
+        if self.envelope_holding {
+            return;
+        }
+        self.envelope_step += 1;
+        if self.envelope_step < 16 {
+            return;
+        }
+        let shape = self.envelope_shape;
+        if shape & 0x08 == 0 {
+            // 不持續：一輪後停在 0
+            self.envelope_step = 15;
+            self.envelope_rising = false;
+            self.envelope_holding = true;
+        } else if shape & 0x01 != 0 {
+            // 保持：停在最後一階（交替時停在另一端）
+            self.envelope_step = 15;
+            self.envelope_rising ^= shape & 0x02 != 0;
+            self.envelope_holding = true;
+        } else {
+            self.envelope_step = 0;
+            self.envelope_rising ^= shape & 0x02 != 0;
+        }
+    }
+
+    fn envelope_level(&self) -> u8 {
+        if self.envelope_rising { self.envelope_step } else { 15 - self.envelope_step }
+    }
+
+    /// 三個聲道的輸出總和（0.0 - 3.0）
+    fn output(&self) -> f32 {
+        let noise = self.noise_lfsr & 1 != 0;
+        (0..3).map(|ch| {
+            let tone_on = self.tone_high[ch] || self.mixer & (1 << ch) != 0;
+            let noise_on = noise || self.mixer & (8 << ch) != 0;
+            if !(tone_on && noise_on) {
+                return 0.0;
+            }
+            let volume = self.volume[ch];
+            let level = if volume & 0x10 != 0 { self.envelope_level() } else { volume & 0x0F };
+            SUNSOFT_5B_VOLUME[level as usize]
+        }).sum()
+    }
+}
+
+pub struct Mapper69 {
+    prg_banks: u8,
+    /// $8000 寫入的命令編號
+    command: u8,
+    chr_regs: [u8; 8],
+    /// 命令 8：$6000 的 bank 與 RAM 設定
+    prg_6000: u8,
+    /// 命令 9-B：$8000/$A000/$C000 的 8KB PRG bank
+    prg_regs: [u8; 3],
+    mirror_mode: MirrorMode,
+    irq_enabled: bool,
+    irq_counter_enabled: bool,
+    irq_counter: u16,
+    irq_pending: bool,
+    audio: Sunsoft5b,
+}
+
+impl Mapper69 {
+    pub fn new(prg_banks: u8, _chr_banks: u8) -> Self {
+        Mapper69 {
+            prg_banks,
+            command: 0,
+            chr_regs: [0; 8],
+            prg_6000: 0,
+            prg_regs: [0; 3],
+            mirror_mode: MirrorMode::Vertical,
+            irq_enabled: false,
+            irq_counter_enabled: false,
+            irq_counter: 0,
+            irq_pending: false,
+            audio: Sunsoft5b::new(),
+        }
+    }
+
+    /// $A000：寫入目前命令的參數
+    fn write_parameter(&mut self, data: u8) -> Option<MapperWriteResult> {
+        match self.command {
+            reg @ 0x0..=0x7 => {
+                self.chr_regs[reg as usize] = data;
+                return Some(MapperWriteResult::none());
+            }
+            0x8 => self.prg_6000 = data,
+            reg @ 0x9..=0xB => self.prg_regs[reg as usize - 9] = data & 0x3F,
+            0xC => {
+                self.mirror_mode = match data & 0x03 {
+                    0 => MirrorMode::Vertical,
+                    1 => MirrorMode::Horizontal,
+                    2 => MirrorMode::SingleScreenLow,
+                    _ => MirrorMode::SingleScreenHigh,
+                };
+                return Some(MapperWriteResult::with_mirror(self.mirror_mode));
+            }
+            0xD => {
+                self.irq_enabled = data & 0x01 != 0;
+                self.irq_counter_enabled = data & 0x80 != 0;
+                self.irq_pending = false;
+            }
+            0xE => self.irq_counter = (self.irq_counter & 0xFF00) | data as u16,
+            _ => self.irq_counter = (self.irq_counter & 0x00FF) | ((data as u16) << 8),
+        }
+        None
+    }
+}
+
+impl MapperTrait for Mapper69 {
+    mapper_state!(
+        command, chr_regs, prg_6000, prg_regs, mirror_mode,
+        irq_enabled, irq_counter_enabled, irq_counter, irq_pending, audio,
+    );
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        let bank = match addr {
+            // 位元 6 為 0 時 $6000 映射 PRG ROM（此時 prg_ram_access 為 Disabled）
+            0x6000..=0x7FFF if self.prg_6000 & 0x40 == 0 => (self.prg_6000 & 0x3F) as u32,
+            0x8000..=0xDFFF => self.prg_regs[((addr - 0x8000) >> 13) as usize] as u32,
+            0xE000..=0xFFFF => self.prg_banks as u32 * 2 - 1,
+            _ => return None,
+        };
+        Some(bank * 8192 + (addr & 0x1FFF) as u32)
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        match addr {
+            0x8000..=0x9FFF => self.command = data & 0x0F,
+            0xA000..=0xBFFF => return self.write_parameter(data),
+            0xC000..=0xDFFF => self.audio.select(data),
+            0xE000..=0xFFFF => self.audio.write(data),
+            _ => {}
+        }
+        None
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 {
+            Some(self.chr_regs[(addr >> 10) as usize] as u32 * 1024 + (addr & 0x03FF) as u32)
+        } else {
+            None
+        }
+    }
+
+    fn ppu_write(&self, _addr: u16) -> Option<u32> {
+        None
+    }
+
+    fn reset(&mut self) {
+        self.command = 0;
+        self.chr_regs = [0; 8];
+        self.prg_6000 = 0;
+        self.prg_regs = [0; 3];
+        self.irq_enabled = false;
+        self.irq_counter_enabled = false;
+        self.irq_counter = 0;
+        self.irq_pending = false;
+        self.audio = Sunsoft5b::new();
+    }
+
+    fn cpu_clock(&mut self) {
+        if self.irq_counter_enabled {
+            self.irq_counter = self.irq_counter.wrapping_sub(1);
+            if self.irq_counter == 0xFFFF && self.irq_enabled {
+                self.irq_pending = true;
+            }
+        }
+    }
+
+    fn check_irq(&mut self) -> bool {
+        // 寫入命令 D 才確認
+        self.irq_pending
+    }
+
+    fn prg_ram_access(&self) -> PrgRamAccess {
+        match self.prg_6000 & 0xC0 {
+            0xC0 => PrgRamAccess::ReadWrite,
+            _ => PrgRamAccess::Disabled,
+        }
+    }
+
+    fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> {
+        Some(self)
+    }
+}
+
+impl ExpansionAudio for Mapper69 {
+    fn chip(&self) -> ExpansionChip {
+        ExpansionChip::Sunsoft5b
+    }
+
+    fn clock(&mut self) {
+        self.audio.clock();
+    }
+
+    fn output(&self) -> f32 {
+        self.audio.output() * SUNSOFT_5B_GAIN
+    }
+}
+
 // ============================================================
 // Mapper 71 (Camerica/Codemasters)
 // ============================================================
@@ -1148,15 +3077,17 @@ impl Mapper71 {
 }
 
 impl MapperTrait for Mapper71 {
+    mapper_state!(selected_bank, mirror_mode);
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
-        if addr >= 0x8000 && addr < 0xC000 {
+        if (0x8000..0xC000).contains(&addr) {
             Some(self.selected_bank as u32 * 16384 + (addr & 0x3FFF) as u32)
         } else if addr >= 0xC000 {
             Some((self.prg_banks as u32 - 1) * 16384 + (addr & 0x3FFF) as u32)
         } else { None }
     }
     fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
-        if addr >= 0x9000 && addr < 0xA000 {
+        if (0x9000..0xA000).contains(&addr) {
             self.mirror_mode = if data & 0x10 != 0 {
                 MirrorMode::SingleScreenHigh
             } else {
@@ -1174,7 +3105,62 @@ impl MapperTrait for Mapper71 {
     fn ppu_write(&self, addr: u16) -> Option<u32> {
         if addr < 0x2000 { Some(addr as u32) } else { None }
     }
-    fn reset(&mut self) { self.selected_bank = 0; }
+    fn reset(&mut self) { self.selected_bank = 0; }
+}
+
+// ============================================================
+// Mapper 99 (VS. UniSystem) - 以 $4016 位元 2 切換 CHR
+// ============================================================
+// VS. 系統的卡帶沒有 Mapper 晶片，寫入 $4016 的位元 2（控制器
+// 閂鎖旁的 OUT2 線）選擇 8KB CHR bank。PRG 固定 32KB；只有 Gumshoe
+// 的 40KB PRG 同時以這個位元切換 $8000-$9FFF（bank 0 或 4）。
+// 參考：https://www.nesdev.org/wiki/INES_Mapper_099
+// ============================================================
+pub struct Mapper99 {
+    prg_banks: u8,
+    chr_banks: u8,
+    bank_select: u8,
+}
+
+impl Mapper99 {
+    pub fn new(prg_banks: u8, chr_banks: u8) -> Self {
+        Mapper99 { prg_banks, chr_banks, bank_select: 0 }
+    }
+}
+
+impl MapperTrait for Mapper99 {
+    mapper_state!(bank_select);
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        match addr {
+            0x8000..=0x9FFF if self.prg_banks > 2 => {
+                Some(self.bank_select as u32 * 4 * 8192 + (addr & 0x1FFF) as u32)
+            }
+            0x8000..=0xFFFF => {
+                let mask = if self.prg_banks > 1 { 0x7FFF } else { 0x3FFF };
+                Some((addr & mask) as u32)
+            }
+            _ => None,
+        }
+    }
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        if addr == 0x4016 {
+            let select = (data >> 2) & 0x01;
+            if select != self.bank_select {
+                self.bank_select = select;
+                return Some(MapperWriteResult::none());
+            }
+        }
+        None
+    }
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 {
+            let bank = self.bank_select as u32 % self.chr_banks.max(1) as u32;
+            Some(bank * 8192 + addr as u32)
+        } else { None }
+    }
+    fn ppu_write(&self, _addr: u16) -> Option<u32> { None }
+    fn reset(&mut self) { self.bank_select = 0; }
 }
 
 // ============================================================
@@ -1201,6 +3187,8 @@ impl Mapper113 {
 }
 
 impl MapperTrait for Mapper113 {
+    mapper_state!(prg_bank, chr_bank, mirror_mode);
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
         if addr >= 0x8000 {
             let bank = self.prg_bank as u32 % self.prg_banks.max(1) as u32;
@@ -1208,7 +3196,7 @@ impl MapperTrait for Mapper113 {
         } else { None }
     }
     fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
-        if addr >= 0x4100 && addr < 0x6000 {
+        if (0x4100..0x6000).contains(&addr) {
             self.prg_bank = (data >> 3) & 0x07;
             self.chr_bank = (data & 0x07) | ((data >> 3) & 0x08);
             self.mirror_mode = if data & 0x80 != 0 {
@@ -1230,6 +3218,159 @@ impl MapperTrait for Mapper113 {
     fn reset(&mut self) { self.prg_bank = 0; self.chr_bank = 0; }
 }
 
+// ============================================================
+// Mapper 163 (Nanjing FC-001) - 中文 RPG 卡帶
+// ============================================================
+// 暫存器在 $5000-$5FFF（寫入以 $7300 遮罩）：
+// - $5000：位元 0-3 為 32KB PRG bank 低 4 位元，位元 7 啟用 CHR 自動切換
+// - $5200：32KB PRG bank 高位元
+// - $5100、$5300：保護用暫存器，讀取 $5100/$5500 時回傳組合值
+// - $5101：寫入非 0 後再寫入 0 會翻轉 $5500 的觸發旗標
+// 卡帶只有 8KB CHR RAM。自動切換時，第 127 條掃描線結束後兩個圖案表都
+// 改指向後 4KB，第 239 條結束後再指回前 4KB，讓上下半畫面各有 256 個圖磚。
+// 寫入 bank 暫存器會恢復成一般的 8KB 映射。
+//
+// scanline 通知不帶掃描線編號：距離上一次通知超過一條掃描線的 CPU 週期數
+// （VBlank 或關閉渲染）之後的第一次通知視為預渲染線。
+//
+// 參考：
+// - https://www.nesdev.org/wiki/INES_Mapper_163
+// - FCEUX boards/164.cpp
+// ============================================================
+
+/// 超過這麼多 CPU 週期沒有 scanline 通知，下一次即為新的一幀
+const NANJING_FRAME_GAP: u16 = 200;
+
+pub struct Mapper163 {
+    prg_banks: u8,
+    /// $5200、$5000、$5300、$5100 的值
+    regs: [u8; 4],
+    /// $5101 上一次寫入的值
+    strobe: u8,
+    /// $5500 讀取用的觸發旗標
+    trigger: bool,
+    /// $5100 寫入 6 時暫時切到 PRG bank 3（保護檢查）
+    prg_bank3: bool,
+    /// 自動切換選擇的 4KB CHR 頁；None 為一般的 8KB 映射
+    chr_page: Option<u8>,
+    /// 目前掃描線（-1 為預渲染線）
+    line: i16,
+    /// 距離上一次 scanline 通知的 CPU 週期數
+    line_gap: u16,
+}
+
+impl Mapper163 {
+    pub fn new(prg_banks: u8, _chr_banks: u8) -> Self {
+        Mapper163 {
+            prg_banks,
+            regs: [0; 4],
+            strobe: 0,
+            trigger: false,
+            prg_bank3: false,
+            chr_page: None,
+            line: -1,
+            line_gap: 0,
+        }
+    }
+
+    fn chr_offset(&self, addr: u16) -> u32 {
+        match self.chr_page {
+            Some(page) => page as u32 * 0x1000 + (addr & 0x0FFF) as u32,
+            None => addr as u32,
+        }
+    }
+}
+
+impl MapperTrait for Mapper163 {
+    mapper_state!(regs, strobe, trigger, prg_bank3, chr_page, line, line_gap);
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        if addr < 0x8000 {
+            return None;
+        }
+        let count = (self.prg_banks as u32 / 2).max(1);
+        let bank = if self.prg_bank3 { 3 } else { (self.regs[0] as u32) << 4 | (self.regs[1] & 0x0F) as u32 };
+        Some((bank % count) * 32768 + (addr & 0x7FFF) as u32)
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        if !(0x5000..0x6000).contains(&addr) {
+            return None;
+        }
+        if addr == 0x5101 {
+            if self.strobe != 0 && data == 0 {
+                self.trigger = !self.trigger;
+            }
+            self.strobe = data;
+            return None;
+        }
+        if addr == 0x5100 && data == 6 {
+            self.prg_bank3 = true;
+            return None;
+        }
+        match addr & 0x7300 {
+            0x5200 => self.regs[0] = data,
+            0x5000 => self.regs[1] = data,
+            0x5100 => self.regs[3] = data,
+            0x5300 => {
+                self.regs[2] = data;
+                return None;
+            }
+            _ => return None,
+        }
+        self.prg_bank3 = false;
+        self.chr_page = None;
+        Some(MapperWriteResult::none())
+    }
+
+    fn read_register(&mut self, addr: u16) -> Option<u8> {
+        if !(0x5000..0x6000).contains(&addr) {
+            return None;
+        }
+        let [prg_high, control, protect, extra] = self.regs;
+        Some(match addr & 0x7700 {
+            0x5100 => protect | prg_high | control | (extra ^ 0xFF),
+            0x5500 if self.trigger => protect | control,
+            0x5500 => 0,
+            _ => 4,
+        })
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 { Some(self.chr_offset(addr)) } else { None }
+    }
+
+    fn ppu_write(&self, addr: u16) -> Option<u32> {
+        self.ppu_read(addr)
+    }
+
+    fn reset(&mut self) {
+        self.regs = [0; 4];
+        self.strobe = 0;
+        self.trigger = false;
+        self.prg_bank3 = false;
+        self.chr_page = None;
+        self.line = -1;
+        self.line_gap = 0;
+    }
+
+    fn cpu_clock(&mut self) {
+        self.line_gap = self.line_gap.saturating_add(1);
+    }
+
+    fn scanline(&mut self) {
+        self.line = if self.line_gap > NANJING_FRAME_GAP { -1 } else { self.line + 1 };
+        self.line_gap = 0;
+        if self.regs[1] & 0x80 != 0 {
+            match self.line {
+                127 => self.chr_page = Some(1),
+                239 => self.chr_page = Some(0),
+                _ => {}
+            }
+        }
+    }
+}
+
 // ============================================================
 // Mapper 202 - 150合1 等合集卡帶
 // ============================================================
@@ -1253,6 +3394,8 @@ impl Mapper202 {
 }
 
 impl MapperTrait for Mapper202 {
+    mapper_state!(prg_bank, chr_bank, prg_mode, mirror_mode);
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
         if addr >= 0x8000 {
             let total_prg = self.prg_banks as u32 * 16384;
@@ -1307,6 +3450,215 @@ impl MapperTrait for Mapper202 {
     }
 }
 
+// ============================================================
+// Mapper 206 (Namco 108 / DxROM) - MMC3 的前身
+// ============================================================
+// 只有 MMC3 的 bank 暫存器：$8000（偶數）選擇 R0-R7、$8001（奇數）寫入。
+// 沒有 PRG/CHR 模式位元、IRQ 與鏡像控制，鏡像由電路板焊死（看標頭）。
+// - PRG：R6 → $8000、R7 → $A000，$C000-$FFFF 固定為最後 16KB
+// - CHR：R0/R1 為 $0000/$0800 的 2KB bank，R2-R5 為 $1000-$1C00 的 1KB bank
+// 用於：Gauntlet、Pac-Mania、女神轉生等早期 Namco/Tengen 遊戲
+//
+// 參考：https://www.nesdev.org/wiki/INES_Mapper_206
+// ============================================================
+pub struct Mapper206 {
+    prg_banks: u8,
+    chr_banks: u8,
+    /// Bank 暫存器（R0-R7）
+    registers: [u8; 8],
+    /// Bank 選擇暫存器
+    bank_select: u8,
+}
+
+impl Mapper206 {
+    pub fn new(prg_banks: u8, chr_banks: u8) -> Self {
+        Mapper206 { prg_banks, chr_banks, registers: [0; 8], bank_select: 0 }
+    }
+}
+
+impl MapperTrait for Mapper206 {
+    mapper_state!(registers, bank_select);
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        if addr < 0x8000 {
+            return None;
+        }
+        let count = (self.prg_banks as u32 * 2).max(2);
+        let bank = match addr {
+            0x8000..=0x9FFF => (self.registers[6] & 0x0F) as u32,
+            0xA000..=0xBFFF => (self.registers[7] & 0x0F) as u32,
+            0xC000..=0xDFFF => count - 2,
+            _ => count - 1,
+        };
+        Some((bank % count) * 8192 + (addr & 0x1FFF) as u32)
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        // $A000 以上的 MMC3 暫存器不存在
+        if !(0x8000..=0x9FFF).contains(&addr) {
+            return None;
+        }
+        if addr & 1 == 0 {
+            self.bank_select = data & 0x07;
+            None
+        } else {
+            self.registers[self.bank_select as usize] = data & 0x3F;
+            Some(MapperWriteResult::none())
+        }
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        if addr >= 0x2000 {
+            return None;
+        }
+        if self.chr_banks == 0 {
+            return Some(addr as u32);
+        }
+        let bank = match addr >> 10 {
+            0 => (self.registers[0] & 0x3E) as u32,
+            1 => (self.registers[0] & 0x3E) as u32 | 1,
+            2 => (self.registers[1] & 0x3E) as u32,
+            3 => (self.registers[1] & 0x3E) as u32 | 1,
+            region => self.registers[region as usize - 2] as u32,
+        };
+        Some((bank % (self.chr_banks as u32 * 8)) * 1024 + (addr & 0x03FF) as u32)
+    }
+
+    fn ppu_write(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 && self.chr_banks == 0 { Some(addr as u32) } else { None }
+    }
+
+    fn reset(&mut self) {
+        self.registers = [0; 8];
+        self.bank_select = 0;
+    }
+}
+
+// ============================================================
+// Mapper 210 (Namco 175/340) - Namco 163 去掉音效與 IRQ
+// ============================================================
+// bank 暫存器與 Namco 163 相同：
+// - $8000-$BFFF：每 $800 一個 1KB CHR bank（8 個）
+// - $E000/$E800/$F000：$8000/$A000/$C000 的 8KB PRG bank，$E000 固定為最後一個
+// 兩種晶片的差異：
+// - 175（子 Mapper 1）：鏡像焊死（看標頭），$C000 位元 0 啟用 PRG RAM
+// - 340（子 Mapper 2）：沒有 PRG RAM，$E000 的位元 6-7 選擇鏡像
+//   （0 = 單屏 A、1 = 垂直、2 = 單屏 B、3 = 水平）
+// 用於：Famista 系列、Splatterhouse: Wanpaku Graffiti、Dream Master 等
+//
+// 參考：https://www.nesdev.org/wiki/INES_Mapper_210
+// ============================================================
+pub struct Mapper210 {
+    prg_banks: u8,
+    chr_banks: u8,
+    /// Namco 340（軟體控制鏡像）；false 為 Namco 175
+    namco340: bool,
+    prg_regs: [u8; 3],
+    chr_regs: [u8; 8],
+    /// Namco 175 的 PRG RAM 啟用位元
+    prg_ram_enabled: bool,
+    mirror_mode: MirrorMode,
+}
+
+impl Mapper210 {
+    /// Namco 175
+    pub fn new(prg_banks: u8, chr_banks: u8) -> Self {
+        Mapper210 {
+            prg_banks,
+            chr_banks,
+            namco340: false,
+            prg_regs: [0, 1, 2],
+            chr_regs: [0; 8],
+            prg_ram_enabled: false,
+            mirror_mode: MirrorMode::Vertical,
+        }
+    }
+
+    /// Namco 340：鏡像由 $E000 控制
+    pub fn new_namco340(prg_banks: u8, chr_banks: u8) -> Self {
+        Mapper210 { namco340: true, ..Self::new(prg_banks, chr_banks) }
+    }
+}
+
+impl MapperTrait for Mapper210 {
+    mapper_state!(prg_regs, chr_regs, prg_ram_enabled, mirror_mode);
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        if addr < 0x8000 {
+            return None;
+        }
+        let count = (self.prg_banks as u32 * 2).max(1);
+        let bank = match addr {
+            0x8000..=0x9FFF => self.prg_regs[0] as u32,
+            0xA000..=0xBFFF => self.prg_regs[1] as u32,
+            0xC000..=0xDFFF => self.prg_regs[2] as u32,
+            _ => count - 1,
+        };
+        Some((bank % count) * 8192 + (addr & 0x1FFF) as u32)
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        match addr {
+            0x8000..=0xBFFF => {
+                self.chr_regs[((addr - 0x8000) >> 11) as usize] = data;
+                Some(MapperWriteResult::none())
+            }
+            0xC000..=0xC7FF if !self.namco340 => {
+                self.prg_ram_enabled = data & 0x01 != 0;
+                None
+            }
+            0xE000..=0xE7FF => {
+                self.prg_regs[0] = data & 0x3F;
+                if !self.namco340 {
+                    return None;
+                }
+                self.mirror_mode = match data >> 6 {
+                    0 => MirrorMode::SingleScreenLow,
+                    1 => MirrorMode::Vertical,
+                    2 => MirrorMode::SingleScreenHigh,
+                    _ => MirrorMode::Horizontal,
+                };
+                Some(MapperWriteResult::with_mirror(self.mirror_mode))
+            }
+            0xE800..=0xEFFF => {
+                self.prg_regs[1] = data & 0x3F;
+                None
+            }
+            0xF000..=0xF7FF => {
+                self.prg_regs[2] = data & 0x3F;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        if addr >= 0x2000 {
+            return None;
+        }
+        if self.chr_banks == 0 {
+            return Some(addr as u32);
+        }
+        let bank = self.chr_regs[(addr >> 10) as usize] as u32;
+        Some((bank % (self.chr_banks as u32 * 8)) * 1024 + (addr & 0x03FF) as u32)
+    }
+
+    fn ppu_write(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 && self.chr_banks == 0 { Some(addr as u32) } else { None }
+    }
+
+    fn reset(&mut self) {
+        self.prg_regs = [0, 1, 2];
+        self.chr_regs = [0; 8];
+        self.prg_ram_enabled = false;
+        self.mirror_mode = MirrorMode::Vertical;
+    }
+
+    fn prg_ram_access(&self) -> PrgRamAccess {
+        if !self.namco340 && self.prg_ram_enabled { PrgRamAccess::ReadWrite } else { PrgRamAccess::Disabled }
+    }
+}
+
 // ============================================================
 // Mapper 225 - 52/64/72合1 等合集卡帶
 // ============================================================
@@ -1332,6 +3684,8 @@ impl Mapper225 {
 }
 
 impl MapperTrait for Mapper225 {
+    mapper_state!(prg_bank, chr_bank, prg_mode, mirror_mode);
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
         if addr >= 0x8000 {
             let total_prg = self.prg_banks as u32 * 16384;
@@ -1359,9 +3713,9 @@ impl MapperTrait for Mapper225 {
             //   O = bit 12    → PRG mode (0=32KB, 1=16KB)
             //   M = bit 13    → Mirroring (0=Vert, 1=Horz)
             //   H = bit 14    → High bit (bank extension)
-            let hi_bit = ((addr >> 14) & 1) as u16;
-            self.chr_bank = (addr & 0x3F) as u16 | (hi_bit << 6);
-            self.prg_bank = ((addr >> 6) & 0x3F) as u16 | (hi_bit << 6);
+            let hi_bit = (addr >> 14) & 1;
+            self.chr_bank = (addr & 0x3F) | (hi_bit << 6);
+            self.prg_bank = ((addr >> 6) & 0x3F) | (hi_bit << 6);
             self.prg_mode = ((addr >> 12) & 1) as u8;
             // FCEUX 225.cpp: mirr = (A>>13)&1; setmirror(mirr^1)
             // MI_V=0, MI_H=1, 所以 mirr=0→Horizontal, mirr=1→Vertical
@@ -1432,6 +3786,8 @@ impl Mapper227 {
 }
 
 impl MapperTrait for Mapper227 {
+    mapper_state!(s_bit, o_bit, l_bit, inner_bank, outer_bank, mirror_mode);
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
         if addr >= 0x8000 {
             let total_prg = self.prg_banks as u32 * 16384;
@@ -1546,6 +3902,11 @@ impl Mapper245 {
 }
 
 impl MapperTrait for Mapper245 {
+    mapper_state!(
+        bank_regs, bank_select, mirror_mode, irq_counter, irq_latch, irq_enabled, irq_reload, irq_pending,
+        prg_high_bit,
+    );
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
         let count = self.prg_banks as u32 * 2; // 8KB banks
         match addr {
@@ -1589,15 +3950,13 @@ impl MapperTrait for Mapper245 {
                     self.bank_select = data;
                 }
             }
-            0xA000..=0xBFFF => {
-                if addr & 1 == 0 {
-                    self.mirror_mode = if data & 0x01 != 0 {
-                        MirrorMode::Horizontal
-                    } else {
-                        MirrorMode::Vertical
-                    };
-                    return Some(MapperWriteResult::with_mirror(self.mirror_mode));
-                }
+            0xA000..=0xBFFF if addr & 1 == 0 => {
+                self.mirror_mode = if data & 0x01 != 0 {
+                    MirrorMode::Horizontal
+                } else {
+                    MirrorMode::Vertical
+                };
+                return Some(MapperWriteResult::with_mirror(self.mirror_mode));
             }
             0xC000..=0xDFFF => {
                 if addr & 1 != 0 { self.irq_reload = true; }
@@ -1626,7 +3985,7 @@ impl MapperTrait for Mapper245 {
         self.prg_high_bit = 0;
     }
 
-    fn scanline(&mut self) {
+    fn a12_clock(&mut self) {
         if self.irq_reload || self.irq_counter == 0 {
             self.irq_counter = self.irq_latch;
             self.irq_reload = false;
@@ -1639,7 +3998,7 @@ impl MapperTrait for Mapper245 {
     }
 
     fn check_irq(&mut self) -> bool {
-        let p = self.irq_pending; self.irq_pending = false; p
+        self.irq_pending
     }
 }
 
@@ -1701,7 +4060,7 @@ impl Mapper253 {
             // 使用 CHR RAM：偏移量 = chr_rom_size + (chr & 1) * 1024 * 4
             // FCEUX: setchr1r(0x10, i << 10, chr & 1)
             // 0x10 = CHR RAM，chr & 1 選擇 CHR RAM 中的 4KB 頁面
-            let ram_bank = (chr & 1) as u32;
+            let ram_bank = chr & 1;
             let offset = self.chr_rom_size + ram_bank * 4096 + (region as u32 & 3) * 1024;
             (offset, true)
         } else {
@@ -1714,6 +4073,11 @@ impl Mapper253 {
 }
 
 impl MapperTrait for Mapper253 {
+    mapper_state!(
+        prg_bank0, prg_bank1, chr_lo, chr_hi, vlock, mirror_mode,
+        irq_latch, irq_control, irq_counter, irq_enabled, irq_pending, irq_prescaler,
+    );
+
     fn cpu_read(&self, addr: u16) -> Option<u32> {
         let count = self.prg_banks as u32 * 2;
         match addr {
@@ -1766,6 +4130,7 @@ impl MapperTrait for Mapper253 {
                         self.vlock = true;  // 鎖定：停用 CHR RAM 替換
                     }
                 }
+                return Some(MapperWriteResult::none());
             }
             0xF000 => {
                 // IRQ 暫存器
@@ -1842,7 +4207,7 @@ impl MapperTrait for Mapper253 {
     }
 
     fn check_irq(&mut self) -> bool {
-        let p = self.irq_pending; self.irq_pending = false; p
+        self.irq_pending
     }
 
     fn chr_writable_mask(&self) -> u8 {
@@ -1864,29 +4229,229 @@ impl MapperTrait for Mapper253 {
 
 /// 建立 Mapper 實例
 /// 根據卡帶的 Mapper 編號，建立對應的 Mapper 實作
-pub fn create_mapper(mapper_id: u8, prg_banks: u8, chr_banks: u8) -> Box<dyn MapperTrait> {
+pub fn create_mapper(mapper_id: u16, prg_banks: u8, chr_banks: u8) -> Mapper {
     match mapper_id {
-        0   => Box::new(Mapper0::new(prg_banks, chr_banks)),
-        1   => Box::new(Mapper1::new(prg_banks, chr_banks)),
-        2   => Box::new(Mapper2::new(prg_banks, chr_banks)),
-        3   => Box::new(Mapper3::new(prg_banks, chr_banks)),
-        4   => Box::new(Mapper4::new(prg_banks, chr_banks)),
-        7   => Box::new(Mapper7::new(prg_banks, chr_banks)),
-        11  => Box::new(Mapper11::new(prg_banks, chr_banks)),
-        15  => Box::new(Mapper15::new(prg_banks, chr_banks)),
-        16  => Box::new(Mapper16::new(prg_banks, chr_banks)),
-        23  => Box::new(Mapper23::new(prg_banks, chr_banks)),
-        66  => Box::new(Mapper66::new(prg_banks, chr_banks)),
-        71  => Box::new(Mapper71::new(prg_banks, chr_banks)),
-        113 => Box::new(Mapper113::new(prg_banks, chr_banks)),
-        202 => Box::new(Mapper202::new(prg_banks, chr_banks)),
-        225 => Box::new(Mapper225::new(prg_banks, chr_banks)),
-        227 => Box::new(Mapper227::new(prg_banks, chr_banks)),
-        245 => Box::new(Mapper245::new(prg_banks, chr_banks)),
-        253 => Box::new(Mapper253::new(prg_banks, chr_banks)),
+        0   => Mapper0::new(prg_banks, chr_banks).into(),
+        1   => Mapper1::new(prg_banks, chr_banks).into(),
+        2   => Mapper2::new(prg_banks, chr_banks).into(),
+        3   => Mapper3::new(prg_banks, chr_banks).into(),
+        4   => Mapper4::new(prg_banks, chr_banks).into(),
+        7   => Mapper7::new(prg_banks, chr_banks).into(),
+        9   => Mapper9::new(prg_banks, chr_banks).into(),
+        10  => Mapper10::new(prg_banks, chr_banks).into(),
+        11  => Mapper11::new(prg_banks, chr_banks).into(),
+        15  => Mapper15::new(prg_banks, chr_banks).into(),
+        16  => Mapper16::new(prg_banks, chr_banks).into(),
+        18  => Mapper18::new(prg_banks, chr_banks).into(),
+        19  => Mapper19::new(prg_banks, chr_banks).into(),
+        23  => Mapper23::new(prg_banks, chr_banks).into(),
+        24  => Mapper24::new(prg_banks, chr_banks).into(),
+        26  => Mapper24::new_vrc6b(prg_banks, chr_banks).into(),
+        64  => Mapper64::new(prg_banks, chr_banks).into(),
+        65  => Mapper65::new(prg_banks, chr_banks).into(),
+        66  => Mapper66::new(prg_banks, chr_banks).into(),
+        69  => Mapper69::new(prg_banks, chr_banks).into(),
+        71  => Mapper71::new(prg_banks, chr_banks).into(),
+        85  => Mapper85::new(prg_banks, chr_banks).into(),
+        99  => Mapper99::new(prg_banks, chr_banks).into(),
+        113 => Mapper113::new(prg_banks, chr_banks).into(),
+        163 => Mapper163::new(prg_banks, chr_banks).into(),
+        202 => Mapper202::new(prg_banks, chr_banks).into(),
+        206 => Mapper206::new(prg_banks, chr_banks).into(),
+        210 => Mapper210::new(prg_banks, chr_banks).into(),
+        225 => Mapper225::new(prg_banks, chr_banks).into(),
+        227 => Mapper227::new(prg_banks, chr_banks).into(),
+        245 => Mapper245::new(prg_banks, chr_banks).into(),
+        253 => Mapper253::new(prg_banks, chr_banks).into(),
         // 未支援的 Mapper 預設使用 Mapper 0
-        _   => {
-            Box::new(Mapper0::new(prg_banks, chr_banks))
-        }
+        _   => Mapper0::new(prg_banks, chr_banks).into(),
+    }
+}
+
+/// 是否有專屬實作（false 表示 create_mapper 會退回 Mapper 0）
+pub fn is_mapper_supported(mapper_id: u16) -> bool {
+    matches!(
+        mapper_id,
+        0 | 1 | 2 | 3 | 4 | 7 | 9 | 10 | 11 | 15 | 16 | 18 | 19 | 23 | 24 | 26 | 64 | 65 | 66 | 69 | 71
+            | 85 | 99 | 113 | 163 | 202 | 206 | 210 | 225 | 227 | 245 | 253
+    )
+}
+
+/// Mapper 名稱（常見板型名稱，未知編號回傳 "Unknown"）
+pub fn mapper_name(mapper_id: u16) -> &'static str {
+    match mapper_id {
+        0 => "NROM",
+        1 => "MMC1",
+        2 => "UxROM",
+        3 => "CNROM",
+        4 => "MMC3",
+        5 => "MMC5",
+        7 => "AxROM",
+        9 => "MMC2",
+        10 => "MMC4",
+        11 => "Color Dreams",
+        15 => "100-in-1 Contra Function 16",
+        16 => "Bandai FCG",
+        18 => "Jaleco SS88006",
+        19 => "Namco 163",
+        20 => "Famicom Disk System",
+        21 | 22 | 23 | 25 => "Konami VRC2/VRC4",
+        24 | 26 => "Konami VRC6",
+        64 => "Tengen RAMBO-1",
+        65 => "Irem H3001",
+        66 => "GxROM",
+        69 => "Sunsoft FME-7/5B",
+        71 => "Camerica",
+        85 => "Konami VRC7",
+        99 => "VS. UniSystem",
+        113 => "NINA-03/06",
+        163 => "Nanjing",
+        202 => "150-in-1",
+        206 => "Namco 108",
+        210 => "Namco 175/340",
+        225 => "72-in-1",
+        227 => "1200-in-1",
+        245 => "Waixing MMC3",
+        253 => "Waixing VRC4",
+        _ => "Unknown",
     }
 }
+
+// ============================================================
+// Mapper 列舉分派 - 熱路徑不經過 dyn 虛擬呼叫
+// ============================================================
+// 每次 CPU/PPU 存取都會查詢 Mapper。Box<dyn MapperTrait> 的間接呼叫
+// 無法內聯，在 WASM 建置中特別明顯；內建 Mapper 改以列舉包裝、用
+// match 分派。MapperTrait 仍是實作介面，外部 Mapper 可放進 Custom。
+// ============================================================
+
+macro_rules! mapper_enum {
+    ($($variant:ident),* $(,)?) => {
+        /// Mapper 實例（內建 Mapper 靜態分派）
+        pub enum Mapper {
+            $($variant($variant),)*
+            /// 外部實作（動態分派）
+            Custom(Box<dyn MapperTrait>),
+        }
+
+        $(
+            impl From<$variant> for Mapper {
+                fn from(mapper: $variant) -> Self {
+                    Mapper::$variant(mapper)
+                }
+            }
+        )*
+
+        impl From<Box<dyn MapperTrait>> for Mapper {
+            fn from(mapper: Box<dyn MapperTrait>) -> Self {
+                Mapper::Custom(mapper)
+            }
+        }
+
+        impl MapperTrait for Mapper {
+            #[inline]
+            fn cpu_read(&self, addr: u16) -> Option<u32> {
+                match self { $(Mapper::$variant(m) => m.cpu_read(addr),)* Mapper::Custom(m) => m.cpu_read(addr) }
+            }
+
+            #[inline]
+            fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+                match self { $(Mapper::$variant(m) => m.cpu_write(addr, data),)* Mapper::Custom(m) => m.cpu_write(addr, data) }
+            }
+
+            #[inline]
+            fn ppu_read(&self, addr: u16) -> Option<u32> {
+                match self { $(Mapper::$variant(m) => m.ppu_read(addr),)* Mapper::Custom(m) => m.ppu_read(addr) }
+            }
+
+            #[inline]
+            fn ppu_write(&self, addr: u16) -> Option<u32> {
+                match self { $(Mapper::$variant(m) => m.ppu_write(addr),)* Mapper::Custom(m) => m.ppu_write(addr) }
+            }
+
+            fn reset(&mut self) {
+                match self { $(Mapper::$variant(m) => m.reset(),)* Mapper::Custom(m) => m.reset() }
+            }
+
+            #[inline]
+            fn scanline(&mut self) {
+                match self { $(Mapper::$variant(m) => m.scanline(),)* Mapper::Custom(m) => m.scanline() }
+            }
+
+            #[inline]
+            fn a12_clock(&mut self) {
+                match self { $(Mapper::$variant(m) => m.a12_clock(),)* Mapper::Custom(m) => m.a12_clock() }
+            }
+
+            #[inline]
+            fn cpu_clock(&mut self) {
+                match self { $(Mapper::$variant(m) => m.cpu_clock(),)* Mapper::Custom(m) => m.cpu_clock() }
+            }
+
+            #[inline]
+            fn check_irq(&mut self) -> bool {
+                match self { $(Mapper::$variant(m) => m.check_irq(),)* Mapper::Custom(m) => m.check_irq() }
+            }
+
+            #[inline]
+            fn chr_writable_mask(&self) -> u8 {
+                match self { $(Mapper::$variant(m) => m.chr_writable_mask(),)* Mapper::Custom(m) => m.chr_writable_mask() }
+            }
+
+            #[inline]
+            fn prg_ram_access(&self) -> PrgRamAccess {
+                match self { $(Mapper::$variant(m) => m.prg_ram_access(),)* Mapper::Custom(m) => m.prg_ram_access() }
+            }
+
+            #[inline]
+            fn prg_ram_bank(&self) -> usize {
+                match self { $(Mapper::$variant(m) => m.prg_ram_bank(),)* Mapper::Custom(m) => m.prg_ram_bank() }
+            }
+
+            fn apply_hack(&mut self, hack: CompatHack) {
+                match self { $(Mapper::$variant(m) => m.apply_hack(hack),)* Mapper::Custom(m) => m.apply_hack(hack) }
+            }
+
+            #[inline]
+            fn ppu_fetch(&mut self, addr: u16) -> bool {
+                match self { $(Mapper::$variant(m) => m.ppu_fetch(addr),)* Mapper::Custom(m) => m.ppu_fetch(addr) }
+            }
+
+            fn watches_ppu_fetch(&self) -> bool {
+                match self { $(Mapper::$variant(m) => m.watches_ppu_fetch(),)* Mapper::Custom(m) => m.watches_ppu_fetch() }
+            }
+
+            fn read_register(&mut self, addr: u16) -> Option<u8> {
+                match self { $(Mapper::$variant(m) => m.read_register(addr),)* Mapper::Custom(m) => m.read_register(addr) }
+            }
+
+            fn eeprom(&self) -> Option<&Eeprom24C02> {
+                match self { $(Mapper::$variant(m) => m.eeprom(),)* Mapper::Custom(m) => m.eeprom() }
+            }
+
+            fn eeprom_mut(&mut self) -> Option<&mut Eeprom24C02> {
+                match self { $(Mapper::$variant(m) => m.eeprom_mut(),)* Mapper::Custom(m) => m.eeprom_mut() }
+            }
+
+            #[inline]
+            fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> {
+                match self { $(Mapper::$variant(m) => m.expansion_audio(),)* Mapper::Custom(m) => m.expansion_audio() }
+            }
+
+            fn save_state(&self, out: &mut Vec<u8>) {
+                match self { $(Mapper::$variant(m) => m.save_state(out),)* Mapper::Custom(m) => m.save_state(out) }
+            }
+
+            fn load_state(&mut self, r: &mut StateReader) -> Option<()> {
+                match self { $(Mapper::$variant(m) => m.load_state(r),)* Mapper::Custom(m) => m.load_state(r) }
+            }
+        }
+    };
+}
+
+mapper_enum!(
+    Mapper0, Mapper1, Mapper2, Mapper3, Mapper4, Mapper7, Mapper9, Mapper10, Mapper11, Mapper15,
+    Mapper16, Mapper18, Mapper19, Mapper23, Mapper24, Mapper64, Mapper65, Mapper66, Mapper69, Mapper71,
+    Mapper85, Mapper99, Mapper113, Mapper163, Mapper202, Mapper206, Mapper210, Mapper225, Mapper227,
+    Mapper245, Mapper253, NsfMapper, FdsMapper,
+);
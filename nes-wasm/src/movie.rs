@@ -0,0 +1,135 @@
+// ============================================================
+// 輸入影片（Movie）- TAS 錄製與重錄
+// ============================================================
+// 影片由「起始存檔」加上「每幀的控制器輸入」組成：
+// - 起始存檔：錄製開始當下的完整狀態（從開機錄製時為重置後的狀態），
+//   播放時先讀入此存檔，確保每次重播都從完全相同的狀態開始
+// - 每幀輸入：兩個控制器的按鈕狀態（各 8 位元）
+//
+// 重錄（re-record）：錄製中讀取先前的存檔時，影片會截斷在該存檔的幀，
+// 之後的輸入重新錄製，並累計重錄次數（TAS 慣例的統計數字）。
+//
+// 匯出格式（與 FM2 相同的「標頭 + 每幀一筆輸入」結構，改為二進位）：
+//   "NESM" | 版本 u8 | 旗標 u8（位元 0：從開機錄製）| 重錄次數 u32 |
+//   起始幀 u64 | 起始存檔長度 u32 + 存檔 | 幀數 u32 | 每幀 [1P, 2P]
+// 數值一律為小端序。
+//
+// 參考：
+// - https://tasvideos.org/Glossary#Rerecord
+// - https://fceux.com/web/help/fm2.html
+// ============================================================
+
+use crate::savestate::{StateField, StateReader};
+
+/// 影片檔格式版本
+const MOVIE_VERSION: u8 = 1;
+
+/// 影片模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MovieMode {
+    /// 未錄製也未播放
+    Inactive,
+    /// 錄製中：每幀記錄控制器輸入
+    Recording,
+    /// 播放中：每幀以影片內容取代控制器輸入
+    Playing,
+}
+
+/// 輸入影片
+#[derive(Debug, Clone, PartialEq)]
+pub struct Movie {
+    /// 起始存檔（二進位存檔格式）
+    pub start_state: Vec<u8>,
+    /// 起始存檔對應的模擬器幀數
+    pub start_frame: u64,
+    /// 是否從開機（重置）開始錄製
+    pub from_power_on: bool,
+    /// 每幀的控制器輸入（[控制器 1, 控制器 2]）
+    pub inputs: Vec<[u8; 2]>,
+    /// 重錄次數
+    pub rerecord_count: u32,
+}
+
+impl Movie {
+    /// 以起始存檔建立空白影片
+    pub fn new(start_state: Vec<u8>, start_frame: u64, from_power_on: bool) -> Self {
+        Movie {
+            start_state,
+            start_frame,
+            from_power_on,
+            inputs: Vec::new(),
+            rerecord_count: 0,
+        }
+    }
+
+    /// 影片長度（幀數）
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// 影片是否沒有任何輸入
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// 將模擬器幀數換算成影片內的幀索引（早於起始幀時回傳 None）
+    pub fn index_of(&self, frame: u64) -> Option<usize> {
+        frame.checked_sub(self.start_frame).map(|i| i as usize)
+    }
+
+    /// 取得指定幀索引的輸入
+    pub fn input_at(&self, index: usize) -> Option<[u8; 2]> {
+        self.inputs.get(index).copied()
+    }
+
+    /// 記錄指定幀索引的輸入（索引之後的舊輸入會被捨棄）
+    pub fn record(&mut self, index: usize, input: [u8; 2]) {
+        self.inputs.resize(index, [0, 0]);
+        self.inputs.push(input);
+    }
+
+    /// 截斷在指定幀索引並累計一次重錄
+    pub fn rerecord_at(&mut self, index: usize) {
+        self.inputs.truncate(index);
+        self.rerecord_count += 1;
+    }
+
+    /// 匯出影片檔
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(26 + self.start_state.len() + self.inputs.len() * 2);
+        out.extend_from_slice(b"NESM");
+        MOVIE_VERSION.save(&mut out);
+        u8::from(self.from_power_on).save(&mut out);
+        self.rerecord_count.save(&mut out);
+        self.start_frame.save(&mut out);
+        (self.start_state.len() as u32).save(&mut out);
+        out.extend_from_slice(&self.start_state);
+        (self.inputs.len() as u32).save(&mut out);
+        for input in &self.inputs {
+            out.extend_from_slice(input);
+        }
+        out
+    }
+
+    /// 讀取影片檔，格式錯誤或長度不符時回傳 None
+    pub fn from_bytes(data: &[u8]) -> Option<Movie> {
+        let mut r = StateReader::new(data);
+        if r.take(4)? != b"NESM" || r.array::<1>()? != [MOVIE_VERSION] {
+            return None;
+        }
+        let [flags] = r.array()?;
+        let rerecord_count = u32::from_le_bytes(r.array()?);
+        let start_frame = u64::from_le_bytes(r.array()?);
+        let state_len = u32::from_le_bytes(r.array()?) as usize;
+        let start_state = r.take(state_len)?.to_vec();
+        let frames = u32::from_le_bytes(r.array()?) as usize;
+        let inputs = r.take(frames.checked_mul(2)?)?
+            .chunks_exact(2)
+            .map(|pair| [pair[0], pair[1]])
+            .collect();
+        if r.position() != data.len() {
+            return None;
+        }
+        Some(Movie { start_state, start_frame, from_power_on: flags & 1 != 0, inputs, rerecord_count })
+    }
+}
@@ -0,0 +1,223 @@
+// ============================================================
+// NSF 音樂播放 - NES Sound Format
+// ============================================================
+// NSF 檔案是從遊戲中抽出的音樂驅動程式與資料，本身沒有主程式：
+// - INIT：A = 曲目編號（從 0 開始）、X = 0（NTSC）/ 1（PAL），
+//   初始化指定曲目
+// - PLAY：每幀呼叫一次，推進音樂
+//
+// 播放時不需要額外的驅動程式 ROM：模擬器在堆疊推入返回位址
+// NSF_RETURN_ADDR - 1 後跳到 INIT/PLAY，常式以 RTS 返回時 PC 會停在
+// NSF_RETURN_ADDR，CPU 在此閒置直到下一幀再呼叫 PLAY。
+//
+// Bank 切換：標頭的 8 個初始 bank 不全為 0 時，$8000-$FFFF 分成
+// 8 個 4KB 視窗，由 $5FF8-$5FFF 選擇各視窗的 bank。
+//
+// 參考：https://www.nesdev.org/wiki/NSF
+// ============================================================
+
+use crate::config::Region;
+use crate::mappers::{MapperTrait, MapperWriteResult};
+use crate::savestate::mapper_state;
+
+/// INIT/PLAY 返回後 CPU 閒置的位址（開放匯流排區域，NSF 不會在此執行程式）
+pub const NSF_RETURN_ADDR: u16 = 0x4100;
+
+/// NSF 標頭
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NsfHeader {
+    /// 格式版本
+    pub version: u8,
+    /// 曲目總數
+    pub total_songs: u8,
+    /// 起始曲目（從 1 開始，與檔案內容相同）
+    pub starting_song: u8,
+    /// 資料載入位址
+    pub load_addr: u16,
+    /// INIT 常式位址
+    pub init_addr: u16,
+    /// PLAY 常式位址
+    pub play_addr: u16,
+    /// 曲名
+    pub title: String,
+    /// 作者
+    pub artist: String,
+    /// 版權
+    pub copyright: String,
+    /// NTSC 播放間隔（微秒）
+    pub ntsc_speed: u16,
+    /// 初始 bank（全為 0 表示不使用 bank 切換）
+    pub bankswitch: [u8; 8],
+    /// PAL 播放間隔（微秒）
+    pub pal_speed: u16,
+    /// 地區旗標（位元 0：PAL，位元 1：雙制式）
+    pub region_flags: u8,
+    /// 擴充音源旗標（VRC6、VRC7、FDS、MMC5、N163、5B）
+    pub expansion: u8,
+}
+
+/// NSF 檔案
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NsfFile {
+    /// 標頭
+    pub header: NsfHeader,
+    /// 音樂資料（標頭之後的全部內容）
+    pub data: Vec<u8>,
+}
+
+impl NsfFile {
+    /// 是否為 NSF 檔案
+    pub fn is_nsf(data: &[u8]) -> bool {
+        data.starts_with(b"NESM\x1A")
+    }
+
+    /// 解析 NSF 檔案，格式錯誤時回傳 None
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if !Self::is_nsf(data) || data.len() <= 0x80 {
+            return None;
+        }
+        let word = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+        let text = |range: std::ops::Range<usize>| {
+            let bytes = &data[range];
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).into_owned()
+        };
+        let mut bankswitch = [0u8; 8];
+        bankswitch.copy_from_slice(&data[0x70..0x78]);
+        let header = NsfHeader {
+            version: data[5],
+            total_songs: data[6],
+            // 超出曲目總數的起始曲目改為最後一首
+            starting_song: data[7].clamp(1, data[6].max(1)),
+            load_addr: word(0x08),
+            init_addr: word(0x0A),
+            play_addr: word(0x0C),
+            title: text(0x0E..0x2E),
+            artist: text(0x2E..0x4E),
+            copyright: text(0x4E..0x6E),
+            ntsc_speed: word(0x6E),
+            bankswitch,
+            pal_speed: word(0x78),
+            region_flags: data[0x7A],
+            expansion: data[0x7B],
+        };
+        if header.total_songs == 0 || (header.load_addr < 0x8000 && !header.is_banked()) {
+            return None;
+        }
+        Some(NsfFile { header, data: data[0x80..].to_vec() })
+    }
+
+    /// 建立 PRG ROM 映像（以 4KB 為單位），回傳（PRG 資料, 初始 bank）
+    pub fn build_prg(&self) -> (Vec<u8>, [u8; 8]) {
+        let h = &self.header;
+        if h.is_banked() {
+            // 資料從 load_addr 在 4KB bank 內的偏移開始排列
+            let padding = (h.load_addr & 0x0FFF) as usize;
+            let mut prg = vec![0u8; padding];
+            prg.extend_from_slice(&self.data);
+            prg.resize(prg.len().div_ceil(0x1000) * 0x1000, 0);
+            (prg, h.bankswitch)
+        } else {
+            // 不切換 bank：資料直接放在 load_addr，超出 $FFFF 的部分捨棄
+            let mut prg = vec![0u8; 0x8000];
+            let start = (h.load_addr - 0x8000) as usize;
+            let len = self.data.len().min(0x8000 - start);
+            prg[start..start + len].copy_from_slice(&self.data[..len]);
+            (prg, [0, 1, 2, 3, 4, 5, 6, 7])
+        }
+    }
+}
+
+impl NsfHeader {
+    /// 是否使用 bank 切換
+    pub fn is_banked(&self) -> bool {
+        self.bankswitch.iter().any(|&b| b != 0)
+    }
+
+    /// INIT 的 X 暫存器（0 = NTSC、1 = PAL）：雙制式的曲目依主機地區選擇，
+    /// 只支援一種制式的曲目一律用它自己的制式；Dendy 使用 NTSC 的速度表
+    pub fn init_region_x(&self, region: Region) -> u8 {
+        match self.region_flags & 0x03 {
+            0x01 => 1,
+            0x00 => 0,
+            _ => (region == Region::Pal) as u8,
+        }
+    }
+
+    /// 擴充音源名稱（以逗號分隔，無擴充音源時為空字串）
+    pub fn expansion_names(&self) -> String {
+        const NAMES: [&str; 6] = ["VRC6", "VRC7", "FDS", "MMC5", "N163", "5B"];
+        let names: Vec<&str> = NAMES.iter().enumerate()
+            .filter(|(i, _)| self.expansion & (1 << i) != 0)
+            .map(|(_, n)| *n)
+            .collect();
+        names.join(",")
+    }
+}
+
+// ============================================================
+// NSF Mapper - 4KB bank 切換（$5FF8-$5FFF）
+// ============================================================
+pub struct NsfMapper {
+    banks: [u8; 8],
+    initial_banks: [u8; 8],
+    bank_count: u16,
+}
+
+impl NsfMapper {
+    pub fn new(initial_banks: [u8; 8], prg_size: usize) -> Self {
+        let bank_count = (prg_size / 0x1000).clamp(1, 256) as u16;
+        // 與 $5FF8-$5FFF 的寫入相同，超出資料大小的 bank 編號折返
+        let initial_banks = initial_banks.map(|bank| (bank as u16 % bank_count) as u8);
+        NsfMapper { banks: initial_banks, initial_banks, bank_count }
+    }
+}
+
+impl MapperTrait for NsfMapper {
+    mapper_state!(banks);
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        if addr >= 0x8000 {
+            let window = ((addr - 0x8000) >> 12) as usize;
+            Some(self.banks[window] as u32 * 0x1000 + (addr & 0x0FFF) as u32)
+        } else {
+            None
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        if (0x5FF8..=0x5FFF).contains(&addr) {
+            self.banks[(addr - 0x5FF8) as usize] = (data as u16 % self.bank_count) as u8;
+            return Some(MapperWriteResult::none());
+        }
+        None
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 { Some(addr as u32) } else { None }
+    }
+
+    fn ppu_write(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 { Some(addr as u32) } else { None }
+    }
+
+    fn reset(&mut self) {
+        self.banks = self.initial_banks;
+    }
+}
+
+/// NSF 播放狀態
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NsfPlayer {
+    /// 標頭
+    pub header: NsfHeader,
+    /// 目前播放中的曲目（從 0 開始，停止時為 None）
+    pub current_track: Option<u8>,
+}
+
+impl NsfPlayer {
+    /// 建立播放狀態（尚未開始播放）
+    pub fn new(header: NsfHeader) -> Self {
+        NsfPlayer { header, current_track: None }
+    }
+}
@@ -8,13 +8,40 @@
 // - 精靈渲染：支援 64 個精靈，每條掃描線最多 8 個
 // - 捲軸（Scrolling）：支援水平和垂直捲軸
 // - VRAM 位址管理：使用 v/t 暫存器（loopy 捲軸）
+// - 色彩強調：PPUMASK 位元 5-7，由預先計算的調色盤查詢表套用
+// - 位址線 A12：追蹤每次圖案讀取與閒置時的 v，低電位持續夠久後的
+//   上升緣通知 Mapper（MMC3 系列以此計數掃描線）
+// - 開路鎖存器：CPU 與 PPU 之間的資料線靠電容保持最後的值，讀取唯寫
+//   暫存器會讀到它，沒有被重新驅動的位元約 600 毫秒後衰減為 0
+// - 圖案表匯流排：$0000-$1FFF 的讀寫經過 PpuBus 交給卡帶，名稱表的
+//   鏡像也由卡帶決定（CIRAM 的 A10 接在卡帶上），VRAM 與調色盤才是
+//   PPU 內部的記憶體
 //
 // 參考資料：
 // - https://www.nesdev.org/wiki/PPU_rendering
+// - https://www.nesdev.org/wiki/MMC3#IRQ_Specifics
 // - https://www.nesdev.org/wiki/PPU_scrolling
 // - https://www.nesdev.org/wiki/PPU_registers
+// - https://www.nesdev.org/wiki/Open_bus_behavior#PPU_open_bus
+// - https://www.nesdev.org/wiki/PPU_memory_map
 // ============================================================
 
+use crate::config::Region;
+use crate::savestate::{impl_state_fields, StateField, StateReader};
+use crate::vs::VsPpu;
+
+/// A12 至少要維持低電位這麼多個 PPU 週期，接著的上升緣才算數
+/// MMC3 以 M2 濾掉圖案讀取之間短暫的低電位；背景在 $1000 時，掃描線
+/// 之間（第 337 週期到下一條的第 4 週期）有 9 個週期的低電位也必須濾掉
+const A12_FILTER_CYCLES: u8 = 10;
+
+/// 開路鎖存器的位元沒有被重新驅動時，經過這麼多幀衰減為 0（約 600 毫秒）
+const OPEN_BUS_DECAY_FRAMES: u8 = 36;
+
+/// VBlank 開始的 NMI 在這個週期之前還可以被 $2002 讀取或關閉 NMI 取消
+/// 參考：https://www.nesdev.org/wiki/PPU_frame_timing#VBL_Flag_Timing
+const NMI_RACE_END_CYCLE: u16 = 4;
+
 /// NES 系統調色盤（64 色 RGB 值）
 /// 這是標準的 2C02 調色盤，每個顏色以 (R, G, B) 表示
 const PALETTE: [(u8, u8, u8); 64] = [
@@ -39,6 +66,265 @@ const PALETTE: [(u8, u8, u8); 64] = [
     (160, 214, 228), (160, 162, 160), (0, 0, 0),       (0, 0, 0),
 ];
 
+/// 色彩強調（PPUMASK 位元 5-7）對未強調通道的衰減（約 0.816，以 /256 表示）
+const EMPHASIS_ATTENUATION: u32 = 209;
+
+/// 2C07（PAL）與 2C02 的色相差（近似值），以 cos/sin 表示
+///
+/// PAL PPU 的色彩副載波相位與 NTSC 不同，同一個顏色索引的色相略有偏移；
+/// 這裡只以把 NTSC 調色盤的色度旋轉 15° 近似，不模擬 PAL 的逐行相位交替。
+const PAL_HUE_COS: f32 = 0.965_926;
+const PAL_HUE_SIN: f32 = -0.258_819;
+
+/// 近似的 PAL 調色盤：NTSC 調色盤在 YUV 空間旋轉色度 15°，亮度不變
+///
+/// 不是 2C07 的實測值，也不是由視訊訊號模擬產生，只讓 PAL 遊戲的色相
+/// 大致接近實機。
+const PAL_PALETTE: [(u8, u8, u8); 64] = {
+    let mut pal = [(0u8, 0u8, 0u8); 64];
+    let mut i = 0;
+    while i < 64 {
+        let (r, g, b) = PALETTE[i];
+        let (r, g, b) = (r as f32, g as f32, b as f32);
+        let y = 0.299 * r + 0.587 * g + 0.114 * b;
+        let (u, v) = (b - y, r - y);
+        let (u, v) = (u * PAL_HUE_COS - v * PAL_HUE_SIN, u * PAL_HUE_SIN + v * PAL_HUE_COS);
+        let r = y + v;
+        let b = y + u;
+        let g = (y - 0.299 * r - 0.114 * b) / 0.587;
+        pal[i] = (clamp_channel(r), clamp_channel(g), clamp_channel(b));
+        i += 1;
+    }
+    pal
+};
+
+const fn clamp_channel(value: f32) -> u8 {
+    if value <= 0.0 { 0 } else if value >= 255.0 { 255 } else { (value + 0.5) as u8 }
+}
+
+/// 建立調色盤查詢表：[強調位元][顏色索引] → RGBA 像素（小端序 u32，直接寫入幀緩衝區）
+///
+/// 強調位元依 NTSC 2C02：位元 0 = 紅、位元 1 = 綠、位元 2 = 藍；
+/// PAL 2C07 的紅、綠兩個位元對調（swap_red_green）。
+/// 每個通道只要有「其他」通道被強調就變暗，三個位元全開時整體變暗。
+///
+/// 參考：
+/// - https://www.nesdev.org/wiki/NTSC_video#Color_Tint_Bits
+/// - https://www.nesdev.org/wiki/PPU_registers#Color_control
+const fn build_palette_lut(palette: &[(u8, u8, u8); 64], swap_red_green: bool) -> [[u32; 64]; 8] {
+    let mut lut = [[0u32; 64]; 8];
+    let mut bits = 0;
+    while bits < 8 {
+        // 換算成 NTSC 順序的強調位元
+        let emphasis = if swap_red_green {
+            (bits & 0x04) | ((bits & 0x01) << 1) | ((bits & 0x02) >> 1)
+        } else {
+            bits
+        };
+        let mut i = 0;
+        while i < 64 {
+            let (r, g, b) = palette[i];
+            let mut rgb = [r as u32, g as u32, b as u32];
+            let mut channel = 0;
+            while channel < 3 {
+                if emphasis & !(1 << channel) != 0 {
+                    rgb[channel] = rgb[channel] * EMPHASIS_ATTENUATION / 256;
+                }
+                channel += 1;
+            }
+            lut[bits][i] = rgb[0] | (rgb[1] << 8) | (rgb[2] << 16) | (0xFF << 24);
+            i += 1;
+        }
+        bits += 1;
+    }
+    lut
+}
+
+/// NTSC（2C02）調色盤查詢表
+const PALETTE_LUT: [[u32; 64]; 8] = build_palette_lut(&PALETTE, false);
+
+/// PAL / Dendy 調色盤查詢表（強調位元紅綠對調）
+const PAL_PALETTE_LUT: [[u32; 64]; 8] = build_palette_lut(&PAL_PALETTE, true);
+
+/// VS. System 的 RGB PPU（2C03/2C04/2C05）調色盤，以 RGB333（八進位，每位數一個通道）表示
+///
+/// $xD-$xF 在 2C02 上是黑色，這裡填入 2C04 打亂順序後會用到的額外顏色。
+/// 參考：https://www.nesdev.org/wiki/PPU_palettes#RGB_PPU_palettes
+const RGB_PPU_PALETTE: [u16; 64] = [
+    0o333, 0o014, 0o006, 0o326, 0o403, 0o503, 0o510, 0o420, 0o320, 0o120, 0o031, 0o040, 0o022, 0o111, 0o003, 0o020,
+    0o555, 0o036, 0o027, 0o407, 0o507, 0o704, 0o700, 0o630, 0o430, 0o140, 0o040, 0o053, 0o044, 0o222, 0o200, 0o310,
+    0o777, 0o357, 0o447, 0o637, 0o707, 0o737, 0o740, 0o750, 0o660, 0o360, 0o070, 0o276, 0o077, 0o444, 0o000, 0o000,
+    0o777, 0o567, 0o657, 0o757, 0o747, 0o755, 0o764, 0o772, 0o773, 0o572, 0o473, 0o276, 0o467, 0o666, 0o653, 0o760,
+];
+
+/// 2C04 四種型號的調色盤打亂表：PPU 顏色索引 → RGB_PPU_PALETTE 的索引
+/// 參考：https://www.nesdev.org/wiki/PPU_palettes#2C04
+const RP2C04_SCRAMBLE: [[u8; 64]; 4] = [
+    [
+        0x35, 0x23, 0x16, 0x22, 0x1C, 0x09, 0x1D, 0x15, 0x20, 0x00, 0x27, 0x05, 0x04, 0x28, 0x08, 0x20,
+        0x21, 0x3E, 0x1F, 0x29, 0x3C, 0x32, 0x36, 0x12, 0x3F, 0x2B, 0x2E, 0x1E, 0x3D, 0x2D, 0x24, 0x01,
+        0x0E, 0x31, 0x33, 0x2A, 0x2C, 0x0C, 0x1B, 0x14, 0x2E, 0x07, 0x34, 0x06, 0x13, 0x02, 0x26, 0x2E,
+        0x2E, 0x19, 0x10, 0x0A, 0x39, 0x03, 0x37, 0x17, 0x0F, 0x11, 0x0B, 0x0D, 0x38, 0x25, 0x18, 0x3A,
+    ],
+    [
+        0x2E, 0x27, 0x18, 0x39, 0x3A, 0x25, 0x1C, 0x31, 0x16, 0x13, 0x38, 0x34, 0x20, 0x23, 0x3C, 0x0B,
+        0x0F, 0x21, 0x06, 0x3D, 0x1B, 0x29, 0x1E, 0x22, 0x1D, 0x24, 0x0E, 0x2B, 0x32, 0x08, 0x2E, 0x03,
+        0x04, 0x36, 0x26, 0x33, 0x11, 0x1F, 0x10, 0x02, 0x14, 0x3F, 0x00, 0x09, 0x12, 0x2E, 0x28, 0x20,
+        0x3E, 0x0D, 0x2A, 0x17, 0x0C, 0x01, 0x15, 0x19, 0x2E, 0x2C, 0x07, 0x37, 0x35, 0x05, 0x0A, 0x2D,
+    ],
+    [
+        0x14, 0x25, 0x3A, 0x10, 0x0B, 0x20, 0x31, 0x09, 0x01, 0x2E, 0x36, 0x08, 0x15, 0x3D, 0x3E, 0x3C,
+        0x22, 0x1C, 0x05, 0x12, 0x19, 0x18, 0x17, 0x1B, 0x00, 0x03, 0x2E, 0x02, 0x16, 0x06, 0x34, 0x35,
+        0x23, 0x0F, 0x0E, 0x37, 0x0D, 0x27, 0x26, 0x20, 0x29, 0x04, 0x21, 0x24, 0x11, 0x2D, 0x2E, 0x1F,
+        0x2C, 0x1E, 0x39, 0x33, 0x07, 0x2A, 0x28, 0x1D, 0x0A, 0x2E, 0x32, 0x38, 0x13, 0x2B, 0x3F, 0x0C,
+    ],
+    [
+        0x18, 0x03, 0x1C, 0x28, 0x2E, 0x35, 0x01, 0x17, 0x10, 0x1F, 0x2A, 0x0E, 0x36, 0x37, 0x0B, 0x39,
+        0x25, 0x1E, 0x12, 0x34, 0x2E, 0x1D, 0x06, 0x26, 0x3E, 0x1B, 0x22, 0x19, 0x04, 0x2E, 0x3A, 0x21,
+        0x05, 0x0A, 0x07, 0x02, 0x13, 0x14, 0x00, 0x15, 0x0C, 0x3D, 0x11, 0x0F, 0x0D, 0x38, 0x2D, 0x24,
+        0x33, 0x20, 0x08, 0x16, 0x3F, 0x2B, 0x20, 0x3C, 0x2E, 0x27, 0x23, 0x31, 0x29, 0x32, 0x2C, 0x09,
+    ],
+];
+
+/// RGB333 的一個通道（取低 3 位元）換算成 0-255
+const fn rgb333_level(value: u16) -> u8 {
+    ((value & 0x07) * 255 / 7) as u8
+}
+
+/// 把 RGB PPU 調色盤（依 scramble 打亂順序）展開成 8 位元 RGB
+const fn build_rgb_ppu_palette(scramble: Option<&[u8; 64]>) -> [(u8, u8, u8); 64] {
+    let mut pal = [(0u8, 0u8, 0u8); 64];
+    let mut i = 0;
+    while i < 64 {
+        let index = match scramble {
+            Some(table) => table[i] as usize,
+            None => i,
+        };
+        let rgb = RGB_PPU_PALETTE[index];
+        pal[i] = (rgb333_level(rgb >> 6), rgb333_level(rgb >> 3), rgb333_level(rgb));
+        i += 1;
+    }
+    pal
+}
+
+/// 2C03/2C05 調色盤查詢表
+const RGB_PPU_LUT: [[u32; 64]; 8] = build_palette_lut(&build_rgb_ppu_palette(None), false);
+
+/// 2C04-0001 到 -0004 的調色盤查詢表
+const RP2C04_LUT: [[[u32; 64]; 8]; 4] = [
+    build_palette_lut(&build_rgb_ppu_palette(Some(&RP2C04_SCRAMBLE[0])), false),
+    build_palette_lut(&build_rgb_ppu_palette(Some(&RP2C04_SCRAMBLE[1])), false),
+    build_palette_lut(&build_rgb_ppu_palette(Some(&RP2C04_SCRAMBLE[2])), false),
+    build_palette_lut(&build_rgb_ppu_palette(Some(&RP2C04_SCRAMBLE[3])), false),
+];
+
+/// 名稱表檢視的大小（四個名稱表排成 2x2）
+pub const NAMETABLE_VIEW_WIDTH: usize = 512;
+pub const NAMETABLE_VIEW_HEIGHT: usize = 480;
+/// 圖案表檢視的大小（兩個圖案表左右並排，各 16x16 個圖磚）
+pub const PATTERN_VIEW_WIDTH: usize = 256;
+pub const PATTERN_VIEW_HEIGHT: usize = 128;
+/// 名稱表檢視中捲軸視窗外框的顏色（RGBA）
+const SCROLL_OVERLAY_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+/// OAM 中的一個精靈（除錯檢視用）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OamEntry {
+    /// 精靈編號（0-63）
+    pub index: u8,
+    /// 左上角 X
+    pub x: u8,
+    /// OAM 中的 Y（畫面上的位置為 y + 1）
+    pub y: u8,
+    /// 圖磚編號
+    pub tile: u8,
+    /// 屬性位元組（調色盤、優先級、翻轉）
+    pub attributes: u8,
+}
+
+impl OamEntry {
+    /// 輸出為 JSON 物件
+    pub fn to_json(&self) -> String {
+        let a = self.attributes;
+        format!(
+            "{{\"index\":{},\"x\":{},\"y\":{},\"tile\":{},\"attributes\":{},\"palette\":{},\
+             \"behindBackground\":{},\"flipH\":{},\"flipV\":{}}}",
+            self.index, self.x, self.y, self.tile, a,
+            a & 0x03, a & 0x20 != 0, a & 0x40 != 0, a & 0x80 != 0,
+        )
+    }
+}
+
+/// PPU 位址空間 $0000-$1FFF（圖案表）的外部匯流排，由卡帶提供
+///
+/// 渲染與 $2007 的圖案讀取都經過 read，Mapper 可以在這裡觀察位址
+/// （MMC2/MMC4 的 CHR 鎖存器）；peek 給除錯檢視器使用，不得有副作用。
+pub trait PpuBus {
+    /// PPU 讀取圖案表
+    fn read(&mut self, addr: u16) -> u8;
+    /// 不改變任何狀態的讀取
+    fn peek(&self, addr: u16) -> u8;
+    /// PPU 寫入圖案表（CHR ROM 忽略寫入）
+    fn write(&mut self, addr: u16, data: u8);
+    /// 目前的名稱表鏡像模式
+    fn mirroring(&self) -> MirrorMode;
+}
+
+/// 平坦的 8KB CHR 與固定的鏡像模式
+///
+/// 沒有卡帶時（單獨測試 PPU、工具）當作 PPU 的匯流排使用。
+///
+/// ```
+/// use nes_wasm::ppu::{ChrMemory, PpuBus};
+/// let mut chr = ChrMemory::new(vec![0; 0x2000], true);
+/// chr.write(0x1234, 0x5A);
+/// assert_eq!(chr.read(0x1234), 0x5A);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChrMemory {
+    /// CHR 資料
+    data: Vec<u8>,
+    /// 是否可寫入（CHR RAM）
+    ram: bool,
+    /// 名稱表鏡像模式
+    pub mirror_mode: MirrorMode,
+}
+
+impl Default for ChrMemory {
+    fn default() -> Self {
+        Self::new(Vec::new(), true)
+    }
+}
+
+impl ChrMemory {
+    /// 建立 CHR 記憶體（水平鏡像）
+    pub fn new(data: Vec<u8>, is_ram: bool) -> Self {
+        ChrMemory { data, ram: is_ram, mirror_mode: MirrorMode::Horizontal }
+    }
+}
+
+impl PpuBus for ChrMemory {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.peek(addr)
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        self.data.get(addr as usize & 0x1FFF).copied().unwrap_or(0)
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        if self.ram {
+            if let Some(byte) = self.data.get_mut(addr as usize & 0x1FFF) {
+                *byte = data;
+            }
+        }
+    }
+
+    fn mirroring(&self) -> MirrorMode {
+        self.mirror_mode
+    }
+}
+
 /// PPU 結構體
 pub struct Ppu {
     // ===== PPU 暫存器 =====
@@ -75,6 +361,10 @@ pub struct Ppu {
 
     /// PPU 資料讀取緩衝區
     pub data_buffer: u8,
+    /// 開路鎖存器（CPU 與 PPU 之間資料線上最後的值）
+    pub open_bus: u8,
+    /// 開路鎖存器每個位元剩餘的衰減幀數
+    pub open_bus_decay: [u8; 8],
 
     // ===== 記憶體 =====
     /// 名稱表 VRAM（2KB，可能被鏡像映射到 4KB 位址空間）
@@ -83,11 +373,12 @@ pub struct Ppu {
     pub palette: [u8; 32],
     /// OAM（Object Attribute Memory，精靈屬性記憶體，256 位元組）
     pub oam: [u8; 256],
-    /// 次要 OAM（掃描線精靈評估用，32 位元組 = 8 個精靈）
-    pub secondary_oam: [u8; 32],
+    /// 次要 OAM（掃描線精靈評估用，硬體為 32 位元組 = 8 個精靈；
+    /// 關閉精靈數量限制時最多容納 64 個精靈）
+    pub secondary_oam: [u8; 256],
 
     // ===== 渲染狀態 =====
-    /// 目前掃描線（0-261，其中 0-239 為可見掃描線）
+    /// 目前掃描線（-1 為預渲染線，0-239 為可見掃描線，NTSC 到 260、PAL 到 310）
     pub scanline: i16,
     /// 目前掃描線上的週期（0-340）
     pub cycle: u16,
@@ -114,13 +405,23 @@ pub struct Ppu {
     /// 背景移位暫存器（屬性高位元）
     bg_shifter_attr_hi: u16,
 
+    // ===== 背景快速路徑（8 像素區段） =====
+    /// 區段內各像素的背景值（調色盤 << 2 | 像素）
+    bg_span: [u8; 8],
+    /// 區段內各像素的背景顏色索引（已查過調色盤）
+    bg_span_color: [u8; 8],
+    /// 區段快取有效（區段中途寫入 PPU 暫存器時失效，改回逐點計算）
+    bg_span_valid: bool,
+    /// 區段已整段寫入幀緩衝區（該掃描線沒有精靈時）
+    bg_span_drawn: bool,
+
     // ===== 精靈渲染 =====
     /// 當前掃描線的精靈數量
     sprite_count: u8,
     /// 精靈圖案移位暫存器（低位元）
-    sprite_shifter_lo: [u8; 8],
+    sprite_shifter_lo: [u8; 64],
     /// 精靈圖案移位暫存器（高位元）
-    sprite_shifter_hi: [u8; 8],
+    sprite_shifter_hi: [u8; 64],
     /// 精靈零是否在次要 OAM 中
     sprite_zero_hit_possible: bool,
     /// 精靈零是否正在渲染
@@ -129,32 +430,38 @@ pub struct Ppu {
     // ===== 中斷 =====
     /// NMI 觸發旗標
     pub nmi_occurred: bool,
-    /// Scanline IRQ 旗標（用於 MMC3 等 Mapper）
+    /// VBlank 旗標設定的前一個點讀取了 $2002：這一幀不設定旗標也不觸發 NMI
+    vbl_suppressed: bool,
+    /// Scanline IRQ 旗標（每條渲染中的掃描線固定在第 260 週期設定）
     pub scanline_irq: bool,
+    /// A12 經過濾波的上升緣（MMC3 系列的 IRQ 時鐘）
+    a12_rise: bool,
+    /// 目前 PPU 位址匯流排的 A12
+    a12_high: bool,
+    /// A12 已維持低電位的 PPU 週期數（飽和計數）
+    a12_low_cycles: u8,
 
     // ===== 畫面輸出 =====
     /// 幀緩衝區（RGBA 格式，256x240 像素）
     pub frame_buffer: Vec<u8>,
 
-    // ===== 外部連接 =====
-    /// CHR ROM/RAM 資料（由卡帶提供）
-    chr_data: Vec<u8>,
-    /// 是否使用 CHR RAM
-    chr_ram: bool,
-    /// 鏡像模式
-    mirror_mode: MirrorMode,
-
-    // ===== Mapper CHR Bank 映射 =====
-    /// CHR bank 偏移量表（8 個 1KB bank）
-    /// 每個元素代表 PPU 位址空間中 1KB 區域對應到 chr_data 中的起始偏移量
-    /// $0000-$03FF -> chr_bank_offsets[0]
-    /// $0400-$07FF -> chr_bank_offsets[1]
-    /// ...以此類推
-    chr_bank_offsets: [u32; 8],
-    /// 是否使用 bank 映射（false 時直接存取，用於 CHR RAM 等簡單情況）
-    chr_use_bank_mapping: bool,
-    /// CHR bank 可寫入遮罩：每個位元代表一個 1KB bank 是否可寫入（用於混合 CHR ROM/RAM mapper 如 253）
-    chr_writable_mask: u8,
+    // ===== 設定選項 =====
+    /// 是否保留每條掃描線 8 個精靈的限制
+    sprite_limit: bool,
+    /// 目前使用的調色盤查詢表（依地區選擇 NTSC 或 PAL，VS. System 依 PPU 型號）
+    palette_lut: &'static [[u32; 64]; 8],
+    /// 目前的主機地區（VS. PPU 型號取消時用來還原調色盤）
+    region: Region,
+    /// VS. System 的 PPU 型號（None 為一般的 2C02/2C07）
+    vs_ppu: Option<VsPpu>,
+    /// 設定 VBlank 旗標的掃描線（NTSC/PAL 241、Dendy 291）
+    vblank_line: i16,
+    /// 每幀最後一條 VBlank 掃描線（NTSC 260、PAL/Dendy 310），之後回到預渲染線
+    last_vblank_line: i16,
+    /// 奇數幀是否跳過一個週期（PAL 的 2C07 與 Dendy 不跳）
+    odd_frame_skip: bool,
+    /// 跳過像素輸出（跳幀模式：照常計算時序與 Sprite 0 Hit，但不寫入幀緩衝區）
+    skip_output: bool,
 }
 
 /// 名稱表鏡像模式
@@ -167,8 +474,47 @@ pub enum MirrorMode {
     FourScreen,       // 四屏（需要額外 VRAM）
 }
 
+impl StateField for MirrorMode {
+    fn save(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> Option<()> {
+        *self = match r.array::<1>()?[0] {
+            0 => MirrorMode::Horizontal,
+            1 => MirrorMode::Vertical,
+            2 => MirrorMode::SingleScreenLow,
+            3 => MirrorMode::SingleScreenHigh,
+            4 => MirrorMode::FourScreen,
+            _ => return None,
+        };
+        Some(())
+    }
+}
+
+// 渲染中途的內部狀態（暫存器、名稱表、調色盤與 OAM 由存檔主體保存）
+impl_state_fields!(Ppu {
+    scanline, cycle, odd_frame, secondary_oam,
+    bg_next_tile_id, bg_next_tile_attr, bg_next_tile_lsb, bg_next_tile_msb,
+    bg_shifter_pattern_lo, bg_shifter_pattern_hi, bg_shifter_attr_lo, bg_shifter_attr_hi,
+    sprite_count, sprite_shifter_lo, sprite_shifter_hi,
+    sprite_zero_hit_possible, sprite_zero_being_rendered,
+    nmi_occurred, scanline_irq,
+});
+
+impl Default for Ppu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Ppu {
     /// 建立新的 PPU 實例
+    ///
+    /// ```
+    /// let ppu = nes_wasm::ppu::Ppu::new();
+    /// assert_eq!(ppu.frame_buffer.len(), 256 * 240 * 4);
+    /// ```
     pub fn new() -> Self {
         Ppu {
             ctrl: 0,
@@ -180,10 +526,12 @@ impl Ppu {
             fine_x: 0,
             write_latch: false,
             data_buffer: 0,
+            open_bus: 0,
+            open_bus_decay: [0; 8],
             nametable: [0; 2048],
             palette: [0; 32],
             oam: [0; 256],
-            secondary_oam: [0xFF; 32],
+            secondary_oam: [0xFF; 256],
             scanline: 0,
             cycle: 0,
             frame_complete: false,
@@ -196,20 +544,30 @@ impl Ppu {
             bg_shifter_pattern_hi: 0,
             bg_shifter_attr_lo: 0,
             bg_shifter_attr_hi: 0,
+            bg_span: [0; 8],
+            bg_span_color: [0; 8],
+            bg_span_valid: false,
+            bg_span_drawn: false,
             sprite_count: 0,
-            sprite_shifter_lo: [0; 8],
-            sprite_shifter_hi: [0; 8],
+            sprite_shifter_lo: [0; 64],
+            sprite_shifter_hi: [0; 64],
             sprite_zero_hit_possible: false,
             sprite_zero_being_rendered: false,
             nmi_occurred: false,
+            vbl_suppressed: false,
             scanline_irq: false,
+            a12_rise: false,
+            a12_high: false,
+            a12_low_cycles: 0,
             frame_buffer: vec![0; 256 * 240 * 4],
-            chr_data: Vec::new(),
-            chr_ram: false,
-            mirror_mode: MirrorMode::Horizontal,
-            chr_bank_offsets: [0, 0x400, 0x800, 0xC00, 0x1000, 0x1400, 0x1800, 0x1C00],
-            chr_use_bank_mapping: false,
-            chr_writable_mask: 0,
+            sprite_limit: true,
+            palette_lut: &PALETTE_LUT,
+            region: Region::Ntsc,
+            vs_ppu: None,
+            vblank_line: 241,
+            last_vblank_line: 260,
+            odd_frame_skip: true,
+            skip_output: false,
         }
     }
 
@@ -229,7 +587,11 @@ impl Ppu {
         self.frame_complete = false;
         self.odd_frame = false;
         self.nmi_occurred = false;
+        self.vbl_suppressed = false;
         self.scanline_irq = false;
+        self.a12_rise = false;
+        self.a12_high = false;
+        self.a12_low_cycles = 0;
         self.bg_next_tile_id = 0;
         self.bg_next_tile_attr = 0;
         self.bg_next_tile_lsb = 0;
@@ -238,78 +600,155 @@ impl Ppu {
         self.bg_shifter_pattern_hi = 0;
         self.bg_shifter_attr_lo = 0;
         self.bg_shifter_attr_hi = 0;
+        self.invalidate_bg_span();
         self.sprite_count = 0;
     }
 
-    /// 設定 CHR 資料（由卡帶載入時呼叫）
-    pub fn set_chr_data(&mut self, data: Vec<u8>, is_ram: bool) {
-        self.chr_data = data;
-        self.chr_ram = is_ram;
-        // CHR RAM 使用直接存取，CHR ROM 使用 bank 映射
-        if is_ram {
-            self.chr_use_bank_mapping = false;
-            self.chr_bank_offsets = [0, 0x400, 0x800, 0xC00, 0x1000, 0x1400, 0x1800, 0x1C00];
-        } else {
-            self.chr_use_bank_mapping = true;
-        }
+    /// 設定是否保留每條掃描線 8 個精靈的限制（精靈溢出旗標不受影響）
+    pub fn set_sprite_limit(&mut self, enabled: bool) {
+        self.sprite_limit = enabled;
+    }
+
+    /// 設定是否跳過像素輸出（跳幀模式）
+    pub fn set_skip_output(&mut self, skip: bool) {
+        self.skip_output = skip;
+        self.invalidate_bg_span();
     }
 
-    /// 更新 CHR bank 映射表（由 Emulator 在 Mapper 狀態變化時呼叫）
-    /// offsets: 8 個 1KB bank 的起始位元組偏移量（在 chr_data 中的位置）
-    pub fn set_chr_bank_offsets(&mut self, offsets: [u32; 8]) {
-        self.chr_bank_offsets = offsets;
+    /// 依主機地區選擇調色盤與幀長度
+    ///
+    /// PAL 與 Dendy 使用近似 2C07 色相的調色盤（見 PAL_PALETTE）與 2C07 的
+    /// 強調位元順序；兩者每幀都是 312 條掃描線且奇數幀不跳過週期，PAL 多出的
+    /// 50 條在 VBlank 內，Dendy 則在 VBlank 之前（NMI 延後到第 291 條）。
+    pub fn set_region(&mut self, region: Region) {
+        self.region = region;
+        self.select_palette();
+        self.vblank_line = region.vblank_start_line();
+        self.last_vblank_line = region.scanlines_per_frame() as i16 - 2;
+        self.odd_frame_skip = region == Region::Ntsc;
+        self.invalidate_bg_span();
     }
 
-    /// 設定 CHR bank 可寫入遮罩
-    /// 每個位元代表一個 1KB bank 是否可寫入（用於混合 CHR ROM/RAM mapper 如 253）
-    pub fn set_chr_writable_mask(&mut self, mask: u8) {
-        self.chr_writable_mask = mask;
+    /// 設定 VS. System 的 PPU 型號（None 還原為依地區選擇的 2C02/2C07）
+    ///
+    /// RGB PPU 使用自己的調色盤；2C04 各型號的顏色索引順序不同，
+    /// 2C05 對調 $2000/$2001 並在 $2002 回傳識別碼。
+    pub fn set_vs_ppu(&mut self, vs_ppu: Option<VsPpu>) {
+        self.vs_ppu = vs_ppu;
+        self.select_palette();
     }
 
-    /// 設定鏡像模式
-    pub fn set_mirror_mode(&mut self, mode: MirrorMode) {
-        self.mirror_mode = mode;
+    /// 目前的 VS. System PPU 型號
+    pub fn vs_ppu(&self) -> Option<VsPpu> {
+        self.vs_ppu
+    }
+
+    /// 依 VS. PPU 型號或地區選擇調色盤查詢表
+    fn select_palette(&mut self) {
+        self.palette_lut = match (self.vs_ppu, self.region) {
+            (Some(vs_ppu), _) => match vs_ppu.scramble() {
+                Some(variant) => &RP2C04_LUT[variant],
+                None => &RGB_PPU_LUT,
+            },
+            (None, Region::Ntsc) => &PALETTE_LUT,
+            (None, Region::Pal | Region::Dendy) => &PAL_PALETTE_LUT,
+        };
+        self.invalidate_bg_span();
     }
 
     // ===== 暫存器讀寫 =====
 
     /// CPU 讀取 PPU 暫存器（$2000-$2007 的映射）
-    pub fn cpu_read(&mut self, addr: u16) -> u8 {
+    ///
+    /// 只有被驅動的位元會更新開路鎖存器，其餘位元（以及唯寫暫存器的
+    /// 整個位元組）讀到的是鎖存器的值。bus 為圖案表所在的卡帶匯流排。
+    pub fn cpu_read<B: PpuBus>(&mut self, addr: u16, bus: &mut B) -> u8 {
         match addr & 0x0007 {
             // $2002 - PPUSTATUS
             0x0002 => {
-                // 讀取狀態時清除 VBlank 旗標和寫入鎖存器
-                let data = (self.status & 0xE0) | (self.data_buffer & 0x1F);
+                // 讀取狀態時清除 VBlank 旗標和寫入鎖存器；只驅動高 3 位元，
+                // 2C05 的低 6 位元（含精靈溢位旗標）固定為識別碼
+                if self.scanline == self.vblank_line {
+                    match self.cycle {
+                        // 旗標設定的前一個點：讀到 0，而且這一幀不會再設定
+                        1 => self.vbl_suppressed = true,
+                        // 剛設定：讀到 1，但 NMI 還沒送出就被清掉
+                        2..NMI_RACE_END_CYCLE => self.nmi_occurred = false,
+                        _ => {}
+                    }
+                }
+                let data = match self.vs_ppu.and_then(VsPpu::status_signature) {
+                    Some(signature) => self.drive_open_bus((self.status & 0xC0) | signature, 0xFF),
+                    None => self.drive_open_bus(self.status, 0xE0),
+                };
                 self.status &= !0x80; // 清除 VBlank
                 self.write_latch = false;
                 data
             }
             // $2004 - OAMDATA
             0x0004 => {
-                self.oam[self.oam_addr as usize]
+                self.drive_open_bus(self.oam[self.oam_addr as usize], 0xFF)
             }
             // $2007 - PPUDATA
             0x0007 => {
-                let mut data = self.data_buffer;
-                self.data_buffer = self.ppu_read(self.v);
-
-                // 調色盤位址直接回傳（不經過緩衝區）
-                if self.v >= 0x3F00 {
-                    data = self.data_buffer;
-                    // 但緩衝區需要填入鏡像的名稱表資料
-                    self.data_buffer = self.ppu_read(self.v - 0x1000);
-                }
-
-                // 根據 PPUCTRL 第 2 位元決定 VRAM 遞增量
-                self.v = self.v.wrapping_add(if self.ctrl & 0x04 != 0 { 32 } else { 1 });
+                let addr = self.v & 0x3FFF;
+                let data = if addr >= 0x3F00 {
+                    // 調色盤直接回傳（不經過緩衝區），但緩衝區仍會填入
+                    // 調色盤「底下」的名稱表資料（$3Fxx → $2Fxx）
+                    self.data_buffer = self.nametable_read(bus, addr & 0x2FFF);
+                    let color = self.palette_read(addr);
+                    // 灰階模式同樣作用在讀回的值；調色盤只有 6 位元，高 2 位元為開路
+                    let color = if self.mask & 0x01 != 0 { color & 0x30 } else { color };
+                    self.drive_open_bus(color, 0x3F)
+                } else {
+                    let value = self.ppu_read(bus, addr);
+                    let data = std::mem::replace(&mut self.data_buffer, value);
+                    self.drive_open_bus(data, 0xFF)
+                };
+                self.increment_vram_addr();
+                self.idle_bus_a12();
                 data
             }
-            _ => 0,
+            // 唯寫暫存器
+            _ => self.open_bus,
+        }
+    }
+
+    /// 以 value 驅動 mask 指定的開路鎖存器位元並重設它們的衰減計時，回傳鎖存器的值
+    fn drive_open_bus(&mut self, value: u8, mask: u8) -> u8 {
+        self.open_bus = (self.open_bus & !mask) | (value & mask);
+        for (bit, frames) in self.open_bus_decay.iter_mut().enumerate() {
+            if mask & (1 << bit) != 0 {
+                *frames = OPEN_BUS_DECAY_FRAMES;
+            }
+        }
+        self.open_bus
+    }
+
+    /// 每幀結束時呼叫：計時歸零的位元衰減為 0
+    fn decay_open_bus(&mut self) {
+        for (bit, frames) in self.open_bus_decay.iter_mut().enumerate() {
+            if *frames > 0 {
+                *frames -= 1;
+                if *frames == 0 {
+                    self.open_bus &= !(1 << bit);
+                }
+            }
         }
     }
 
     /// CPU 寫入 PPU 暫存器
-    pub fn cpu_write(&mut self, addr: u16, data: u8) {
+    pub fn cpu_write<B: PpuBus>(&mut self, addr: u16, data: u8, bus: &mut B) {
+        self.drive_open_bus(data, 0xFF);
+        // 2C05 的 $2000 與 $2001 位址對調
+        let addr = match self.vs_ppu {
+            Some(vs_ppu) if vs_ppu.swaps_ctrl_mask() && addr & 0x0006 == 0 => addr ^ 0x0001,
+            _ => addr,
+        };
+        // 捲軸、遮罩與調色盤都可能在掃描線中途改變，剩餘像素改回逐點計算
+        if !matches!(addr & 0x0007, 0x0003 | 0x0004) {
+            self.invalidate_bg_span();
+        }
         match addr & 0x0007 {
             // $2000 - PPUCTRL
             0x0000 => {
@@ -317,10 +756,13 @@ impl Ppu {
                 self.ctrl = data;
                 // 更新 t 暫存器的名稱表選擇位元
                 self.t = (self.t & 0xF3FF) | ((data as u16 & 0x03) << 10);
-                // 如果 NMI 剛被啟用且 VBlank 中，立即觸發 NMI
+                // 如果 NMI 剛被啟用且 VBlank 中，立即觸發 NMI；
+                // 尚未送出的 NMI 在關閉時取消（NMI 輸出是旗標與致能的 AND）
                 let new_nmi = data & 0x80 != 0;
                 if !prev_nmi && new_nmi && (self.status & 0x80 != 0) {
                     self.nmi_occurred = true;
+                } else if !new_nmi && self.nmi_pending_release() {
+                    self.nmi_occurred = false;
                 }
             }
             // $2001 - PPUMASK
@@ -359,83 +801,103 @@ impl Ppu {
                     // 第二次寫入：低位元組
                     self.t = (self.t & 0xFF00) | (data as u16);
                     self.v = self.t; // 複製 t 到 v
+                    self.idle_bus_a12();
                 }
                 self.write_latch = !self.write_latch;
             }
             // $2007 - PPUDATA
             0x0007 => {
-                self.ppu_write(self.v, data);
-                self.v = self.v.wrapping_add(if self.ctrl & 0x04 != 0 { 32 } else { 1 });
+                self.ppu_write(bus, self.v, data);
+                self.increment_vram_addr();
+                self.idle_bus_a12();
             }
             _ => {}
         }
     }
 
+    /// $2007 存取後遞增 v
+    ///
+    /// 一般情況依 PPUCTRL 第 2 位元 +1 或 +32；渲染中（預渲染與可見
+    /// 掃描線、背景或精靈啟用）v 正被渲染管線使用，硬體會同時觸發
+    /// coarse X 與 Y 遞增，遊戲在畫面中途寫 $2007 時捲軸會因此跳動。
+    ///
+    /// 參考：https://www.nesdev.org/wiki/PPU_scrolling#$2007_(PPUDATA)_reads_and_writes
+    fn increment_vram_addr(&mut self) {
+        if self.rendering_enabled() && self.scanline < 240 {
+            self.increment_scroll_x();
+            self.increment_scroll_y();
+        } else {
+            let step = if self.ctrl & 0x04 != 0 { 32 } else { 1 };
+            self.v = self.v.wrapping_add(step) & 0x7FFF;
+        }
+    }
+
+    /// 沒有渲染時，位址匯流排停在 v（$2006/$2007 存取後更新 A12）
+    fn idle_bus_a12(&mut self) {
+        if !(self.rendering_enabled() && self.scanline < 240) {
+            self.set_a12(self.v & 0x1000 != 0);
+        }
+    }
+
+    /// 更新位址匯流排的 A12，低電位夠久後的上升緣設定 a12_rise
+    #[inline]
+    fn set_a12(&mut self, high: bool) {
+        if high && !self.a12_high && self.a12_low_cycles >= A12_FILTER_CYCLES {
+            self.a12_rise = true;
+        }
+        if high {
+            self.a12_low_cycles = 0;
+        }
+        self.a12_high = high;
+    }
+
+    /// 精靈讀取階段（第 257-320 週期）第 slot 個精靈的圖案表 A12：
+    /// 8x8 由 PPUCTRL 位元 3 決定，8x16 由圖磚編號位元 0 決定（空位讀取圖磚 $FF）
+    fn sprite_fetch_a12(&self, slot: usize) -> bool {
+        if self.ctrl & 0x20 != 0 {
+            self.secondary_oam[slot * 4 + 1] & 0x01 != 0
+        } else {
+            self.ctrl & 0x08 != 0
+        }
+    }
+
     // ===== PPU 內部記憶體讀寫 =====
 
     /// 讀取 PPU 位址空間
-    fn ppu_read(&self, addr: u16) -> u8 {
+    fn ppu_read<B: PpuBus>(&self, bus: &mut B, addr: u16) -> u8 {
         let addr = addr & 0x3FFF; // PPU 位址空間為 $0000-$3FFF
-
         if addr < 0x2000 {
-            // $0000-$1FFF: 圖案表（CHR ROM/RAM）
-            if self.chr_data.is_empty() {
-                return 0;
-            }
-            if self.chr_use_bank_mapping {
-                // 使用 Mapper 的 bank 映射
-                let bank_index = (addr >> 10) as usize; // 0-7（每 1KB 一個 bank）
-                let bank_offset = self.chr_bank_offsets[bank_index] as usize;
-                let offset_in_bank = (addr & 0x03FF) as usize;
-                let chr_index = (bank_offset + offset_in_bank) % self.chr_data.len();
-                self.chr_data[chr_index]
-            } else {
-                // 直接存取（CHR RAM 或無 bank 切換）
-                let index = addr as usize;
-                if index < self.chr_data.len() {
-                    self.chr_data[index]
-                } else {
-                    0
-                }
-            }
+            // $0000-$1FFF: 圖案表（卡帶的 CHR ROM/RAM）
+            bus.read(addr)
         } else if addr < 0x3F00 {
-            // $2000-$3EFF: 名稱表（含鏡像）
-            let mirrored = self.mirror_nametable_addr(addr);
-            self.nametable[mirrored]
+            self.nametable_read(bus, addr)
         } else {
-            // $3F00-$3FFF: 調色盤
-            let palette_addr = self.mirror_palette_addr(addr);
-            self.palette[palette_addr]
+            self.palette_read(addr)
         }
     }
 
+    /// 讀取名稱表（$2000-$3EFF，依卡帶的鏡像模式）
+    #[inline]
+    fn nametable_read<B: PpuBus>(&self, bus: &B, addr: u16) -> u8 {
+        self.nametable[Self::mirror_nametable_addr(bus.mirroring(), addr)]
+    }
+
+    /// 讀取調色盤（$3F00-$3FFF）
+    #[inline]
+    fn palette_read(&self, addr: u16) -> u8 {
+        self.palette[self.mirror_palette_addr(addr)]
+    }
+
     /// 寫入 PPU 位址空間
-    fn ppu_write(&mut self, addr: u16, data: u8) {
+    fn ppu_write<B: PpuBus>(&mut self, bus: &mut B, addr: u16, data: u8) {
         let addr = addr & 0x3FFF;
 
         if addr < 0x2000 {
-            // 圖案表：CHR RAM 可寫入，或混合模式下特定 bank 可寫入
-            let bank_index = (addr >> 10) as usize;
-            let writable = self.chr_ram || (self.chr_writable_mask & (1 << bank_index)) != 0;
-            if writable {
-                if self.chr_use_bank_mapping {
-                    let bank_index = (addr >> 10) as usize;
-                    let bank_offset = self.chr_bank_offsets[bank_index] as usize;
-                    let offset_in_bank = (addr & 0x03FF) as usize;
-                    let chr_index = (bank_offset + offset_in_bank) % self.chr_data.len().max(1);
-                    if chr_index < self.chr_data.len() {
-                        self.chr_data[chr_index] = data;
-                    }
-                } else {
-                    let index = addr as usize;
-                    if index < self.chr_data.len() {
-                        self.chr_data[index] = data;
-                    }
-                }
-            }
+            // 圖案表：是否可寫入由卡帶決定（CHR RAM 或部分 bank 可寫入的 Mapper）
+            bus.write(addr, data);
         } else if addr < 0x3F00 {
             // 名稱表
-            let mirrored = self.mirror_nametable_addr(addr);
+            let mirrored = Self::mirror_nametable_addr(bus.mirroring(), addr);
             self.nametable[mirrored] = data;
         } else {
             // 調色盤
@@ -445,9 +907,9 @@ impl Ppu {
     }
 
     /// 名稱表位址鏡像映射
-    fn mirror_nametable_addr(&self, addr: u16) -> usize {
+    fn mirror_nametable_addr(mode: MirrorMode, addr: u16) -> usize {
         let addr = (addr - 0x2000) & 0x0FFF; // 對齊到 $0000-$0FFF
-        match self.mirror_mode {
+        match mode {
             MirrorMode::Horizontal => {
                 // A 和 B 各佔一半
                 // $2000/$2400 -> 第一頁, $2800/$2C00 -> 第二頁
@@ -519,8 +981,8 @@ impl Ppu {
     // ===== 主要時鐘方法 =====
 
     /// PPU 時鐘週期
-    /// 每個 PPU 週期處理一個像素的渲染
-    pub fn clock(&mut self) {
+    /// 每個 PPU 週期處理一個像素的渲染，圖案表經由 bus 讀取
+    pub fn clock<B: PpuBus>(&mut self, bus: &mut B) {
         // -1（預渲染掃描線）到 239（最後一條可見掃描線）
         if self.scanline >= -1 && self.scanline < 240 {
             // 可見掃描線和預渲染掃描線的處理
@@ -530,12 +992,12 @@ impl Ppu {
                 // 清除 VBlank、Sprite 0 Hit、Sprite Overflow 旗標
                 self.status &= !0xE0;
                 // 清除精靈移位暫存器
-                self.sprite_shifter_lo = [0; 8];
-                self.sprite_shifter_hi = [0; 8];
+                self.sprite_shifter_lo = [0; 64];
+                self.sprite_shifter_hi = [0; 64];
             }
 
             // 奇數幀跳過 (0,0) 週期
-            if self.scanline == 0 && self.cycle == 0 && self.odd_frame && self.rendering_enabled() {
+            if self.scanline == 0 && self.cycle == 0 && self.odd_frame && self.odd_frame_skip && self.rendering_enabled() {
                 self.cycle = 1;
             }
 
@@ -549,7 +1011,10 @@ impl Ppu {
                         // 將新的圖磚資料載入移位暫存器
                         self.load_bg_shifters();
                         // 從名稱表讀取圖磚 ID
-                        self.bg_next_tile_id = self.ppu_read(0x2000 | (self.v & 0x0FFF));
+                        self.bg_next_tile_id = self.nametable_read(bus, 0x2000 | (self.v & 0x0FFF));
+                        if self.rendering_enabled() {
+                            self.set_a12(false);
+                        }
                     }
                     2 => {
                         // 讀取屬性表
@@ -557,7 +1022,7 @@ impl Ppu {
                             | (self.v & 0x0C00)
                             | ((self.v >> 4) & 0x38)
                             | ((self.v >> 2) & 0x07);
-                        self.bg_next_tile_attr = self.ppu_read(attr_addr);
+                        self.bg_next_tile_attr = self.nametable_read(bus, attr_addr);
 
                         // 根據圖磚在 2x2 方塊中的位置選擇正確的 2 位元調色盤
                         if self.v & 0x40 != 0 {
@@ -573,7 +1038,10 @@ impl Ppu {
                         let bg_pattern_addr = ((self.ctrl as u16 & 0x10) << 8)
                             + (self.bg_next_tile_id as u16 * 16)
                             + ((self.v >> 12) & 0x07);
-                        self.bg_next_tile_lsb = self.ppu_read(bg_pattern_addr);
+                        if self.rendering_enabled() {
+                            self.set_a12(bg_pattern_addr & 0x1000 != 0);
+                        }
+                        self.bg_next_tile_lsb = bus.read(bg_pattern_addr);
                     }
                     6 => {
                         // 讀取圖案表高位元組（偏移 8 位元組）
@@ -581,7 +1049,7 @@ impl Ppu {
                             + (self.bg_next_tile_id as u16 * 16)
                             + ((self.v >> 12) & 0x07)
                             + 8;
-                        self.bg_next_tile_msb = self.ppu_read(bg_pattern_addr);
+                        self.bg_next_tile_msb = bus.read(bg_pattern_addr);
                     }
                     7 => {
                         // 水平位置遞增
@@ -609,7 +1077,19 @@ impl Ppu {
 
             // 超出畫面的名稱表讀取（模擬真實硬體行為）
             if self.cycle == 338 || self.cycle == 340 {
-                self.bg_next_tile_id = self.ppu_read(0x2000 | (self.v & 0x0FFF));
+                self.bg_next_tile_id = self.nametable_read(bus, 0x2000 | (self.v & 0x0FFF));
+            }
+
+            // 精靈圖案讀取的位址線：每個精靈 8 週期，前半為無用的名稱表讀取，
+            // 後半讀取圖案
+            if self.rendering_enabled() && self.cycle >= 257 && self.cycle <= 320 {
+                let step = (self.cycle - 257) % 8;
+                if step == 0 {
+                    self.set_a12(false);
+                } else if step == 4 {
+                    let high = self.sprite_fetch_a12((self.cycle as usize - 257) / 8);
+                    self.set_a12(high);
+                }
             }
 
             // ===== 精靈評估 =====
@@ -617,19 +1097,27 @@ impl Ppu {
                 self.evaluate_sprites();
             }
 
-            // 在第 340 週期載入精靈圖案
-            if self.cycle == 340 && self.scanline >= 0 {
-                self.load_sprite_patterns();
+            // 精靈圖案在各自的讀取時段載入（每個精靈的第 5、7 週期讀取低、高位元組），
+            // IRQ 在掃描線中途切換 CHR bank 時，只有之後才讀取的精靈使用新的 bank
+            if self.scanline >= 0 && self.cycle >= 257 && self.cycle <= 320 {
+                let slot = (self.cycle as usize - 257) / 8;
+                match (self.cycle - 257) % 8 {
+                    4 => self.fetch_sprite_pattern(bus, slot, false),
+                    6 => self.fetch_sprite_pattern(bus, slot, true),
+                    _ => {}
+                }
             }
         }
 
         // ===== VBlank 期間 =====
-        if self.scanline == 241 && self.cycle == 1 {
-            // 設定 VBlank 旗標
-            self.status |= 0x80;
-            // 如果 NMI 使能，觸發 NMI
-            if self.ctrl & 0x80 != 0 {
-                self.nmi_occurred = true;
+        if self.scanline == self.vblank_line && self.cycle == 1 {
+            // 設定 VBlank 旗標（前一個點讀取過 $2002 時略過）
+            if !std::mem::take(&mut self.vbl_suppressed) {
+                self.status |= 0x80;
+                // 如果 NMI 使能，觸發 NMI
+                if self.ctrl & 0x80 != 0 {
+                    self.nmi_occurred = true;
+                }
             }
         }
 
@@ -638,20 +1126,24 @@ impl Ppu {
             self.render_pixel();
         }
 
-        // ===== Scanline IRQ 計數器（用於 MMC3） =====
+        // ===== Scanline IRQ 計數器（不看 A12 的 Mapper 使用） =====
         if self.rendering_enabled() && self.cycle == 260 && self.scanline < 240 {
             self.scanline_irq = true;
         }
+        if !self.a12_high {
+            self.a12_low_cycles = self.a12_low_cycles.saturating_add(1);
+        }
 
         // ===== 推進時序 =====
         self.cycle += 1;
         if self.cycle > 340 {
             self.cycle = 0;
             self.scanline += 1;
-            if self.scanline > 260 {
+            if self.scanline > self.last_vblank_line {
                 self.scanline = -1;
                 self.frame_complete = true;
                 self.odd_frame = !self.odd_frame;
+                self.decay_open_bus();
             }
         }
     }
@@ -748,7 +1240,7 @@ impl Ppu {
 
     /// 評估精靈：找出當前掃描線上的精靈
     fn evaluate_sprites(&mut self) {
-        self.secondary_oam = [0xFF; 32];
+        self.secondary_oam = [0xFF; 256];
         self.sprite_count = 0;
         self.sprite_zero_hit_possible = false;
 
@@ -759,70 +1251,73 @@ impl Ppu {
             let diff = self.scanline - y;
 
             if diff >= 0 && diff < sprite_height {
-                if self.sprite_count < 8 {
-                    if i == 0 {
-                        self.sprite_zero_hit_possible = true;
-                    }
-
-                    // 複製精靈資料到次要 OAM
-                    let offset = self.sprite_count as usize * 4;
-                    self.secondary_oam[offset] = self.oam[i * 4];
-                    self.secondary_oam[offset + 1] = self.oam[i * 4 + 1];
-                    self.secondary_oam[offset + 2] = self.oam[i * 4 + 2];
-                    self.secondary_oam[offset + 3] = self.oam[i * 4 + 3];
-
-                    self.sprite_count += 1;
-                } else {
+                if self.sprite_count >= 8 {
                     // 第 9 個命中精靈 → 設定精靈溢出旗標
                     self.status |= 0x20; // Sprite Overflow
-                    break;
+                    if self.sprite_limit {
+                        break;
+                    }
                 }
+                if i == 0 {
+                    self.sprite_zero_hit_possible = true;
+                }
+
+                // 複製精靈資料到次要 OAM
+                let offset = self.sprite_count as usize * 4;
+                self.secondary_oam[offset] = self.oam[i * 4];
+                self.secondary_oam[offset + 1] = self.oam[i * 4 + 1];
+                self.secondary_oam[offset + 2] = self.oam[i * 4 + 2];
+                self.secondary_oam[offset + 3] = self.oam[i * 4 + 3];
+
+                self.sprite_count += 1;
             }
         }
     }
 
-    /// 載入精靈圖案到移位暫存器
-    fn load_sprite_patterns(&mut self) {
-        for i in 0..self.sprite_count as usize {
-            let sprite_y = self.secondary_oam[i * 4] as i16;
-            let tile_id = self.secondary_oam[i * 4 + 1];
-            let attributes = self.secondary_oam[i * 4 + 2];
-            let flip_v = attributes & 0x80 != 0;
-
-            let mut row = self.scanline - sprite_y;
+    /// 讀取第 slot 個精靈的圖案低/高位元組到移位暫存器（空位不讀取）
+    fn fetch_sprite_pattern<B: PpuBus>(&mut self, bus: &mut B, slot: usize, high: bool) {
+        if slot >= self.sprite_count as usize {
+            return;
+        }
+        let mut data = bus.read(self.sprite_pattern_addr(slot) + if high { 8 } else { 0 });
+        // 水平翻轉
+        if self.secondary_oam[slot * 4 + 2] & 0x40 != 0 {
+            data = Self::reverse_bits(data);
+        }
+        if high {
+            self.sprite_shifter_hi[slot] = data;
+        } else {
+            self.sprite_shifter_lo[slot] = data;
+        }
+    }
 
-            let pattern_addr = if self.ctrl & 0x20 != 0 {
-                // 8x16 精靈模式
-                if flip_v {
-                    row = 15 - row;
-                }
-                let table = (tile_id as u16 & 0x01) * 0x1000;
-                let tile = tile_id as u16 & 0xFE;
-                if row >= 8 {
-                    table + (tile + 1) * 16 + (row as u16 - 8)
-                } else {
-                    table + tile * 16 + row as u16
-                }
-            } else {
-                // 8x8 精靈模式
-                if flip_v {
-                    row = 7 - row;
-                }
-                let table = ((self.ctrl as u16 >> 3) & 0x01) * 0x1000;
-                table + tile_id as u16 * 16 + row as u16
-            };
+    /// 次要 OAM 第 slot 個精靈在目前掃描線的圖案低位元組位址
+    fn sprite_pattern_addr(&self, slot: usize) -> u16 {
+        let sprite_y = self.secondary_oam[slot * 4] as i16;
+        let tile_id = self.secondary_oam[slot * 4 + 1];
+        let flip_v = self.secondary_oam[slot * 4 + 2] & 0x80 != 0;
 
-            let mut lo = self.ppu_read(pattern_addr);
-            let mut hi = self.ppu_read(pattern_addr + 8);
+        let mut row = self.scanline - sprite_y;
 
-            // 水平翻轉
-            if attributes & 0x40 != 0 {
-                lo = Self::reverse_bits(lo);
-                hi = Self::reverse_bits(hi);
+        if self.ctrl & 0x20 != 0 {
+            // 8x16 精靈模式
+            if flip_v {
+                row = 15 - row;
             }
-
-            self.sprite_shifter_lo[i] = lo;
-            self.sprite_shifter_hi[i] = hi;
+            let table = (tile_id as u16 & 0x01) * 0x1000;
+            let tile = tile_id as u16 & 0xFE;
+            if row >= 8 {
+                table + (tile + 1) * 16 + (row as u16 - 8)
+            } else {
+                table + tile * 16 + row as u16
+            }
+        } else {
+            // 8x8 精靈模式
+            if flip_v {
+                row = 7 - row;
+            }
+            let table = ((self.ctrl as u16 >> 3) & 0x01) * 0x1000;
+            table + tile_id as u16 * 16 + row as u16
         }
     }
 
@@ -835,29 +1330,96 @@ impl Ppu {
         b
     }
 
+    /// 讀回存檔中的渲染內部狀態，並修正超出範圍的值（損毀的存檔不會造成越界）
+    pub fn load_render_state(&mut self, r: &mut StateReader) -> Option<()> {
+        self.load(r)?;
+        self.scanline = self.scanline.clamp(-1, self.last_vblank_line);
+        self.cycle = self.cycle.min(340);
+        self.sprite_count = self.sprite_count.min(64);
+        self.invalidate_bg_span();
+        Some(())
+    }
+
     // ===== 像素渲染 =====
 
+    /// 讓背景區段快取失效，剩餘像素改回逐點計算
+    #[inline]
+    fn invalidate_bg_span(&mut self) {
+        self.bg_span_valid = false;
+        self.bg_span_drawn = false;
+    }
+
+    /// 在圖磚邊界一次算出接下來 8 個像素的背景
+    ///
+    /// 邊界時移位暫存器的高位元組是目前圖磚、低位元組是下一個圖磚，
+    /// 之後 7 個週期只會左移、不會載入，因此第 k 個像素就是
+    /// 「目前的暫存器」第 15 - fine_x - k 位元。沒有精靈的掃描線直接
+    /// 整段寫入幀緩衝區，其餘週期不再計算。
+    fn fill_bg_span(&mut self, x: usize, y: usize) {
+        let show = self.bg_enabled() && (self.bg_left_enabled() || x >= 8);
+        for k in 0..8 {
+            let value = if show {
+                let bit = 15 - self.fine_x as u16 - k as u16;
+                let p0 = (self.bg_shifter_pattern_lo >> bit) & 1;
+                let p1 = (self.bg_shifter_pattern_hi >> bit) & 1;
+                let a0 = (self.bg_shifter_attr_lo >> bit) & 1;
+                let a1 = (self.bg_shifter_attr_hi >> bit) & 1;
+                ((a1 << 3) | (a0 << 2) | (p1 << 1) | p0) as u8
+            } else {
+                0
+            };
+            self.bg_span[k] = value;
+            // 透明像素一律顯示通用背景色 $3F00
+            let addr = if value & 0x03 == 0 { 0x3F00 } else { 0x3F00 + value as u16 };
+            self.bg_span_color[k] = self.palette_read(addr);
+        }
+        self.bg_span_valid = true;
+        self.bg_span_drawn = false;
+
+        let no_sprites = self.sprite_count == 0 || !self.spr_enabled();
+        if !no_sprites || self.skip_output {
+            return;
+        }
+        let table = self.palette_lut;
+        let lut = &table[(self.mask >> 5) as usize];
+        let row = &mut self.frame_buffer[(y * 256 + x) * 4..(y * 256 + x + 8) * 4];
+        for (pixel, &color) in row.chunks_exact_mut(4).zip(&self.bg_span_color) {
+            let rgba = lut[(color & 0x3F) as usize];
+            pixel.copy_from_slice(&rgba.to_le_bytes());
+        }
+        self.bg_span_drawn = true;
+    }
+
     /// 渲染當前週期的像素
     fn render_pixel(&mut self) {
         let x = (self.cycle - 1) as usize;
         let y = self.scanline as usize;
 
+        if x & 7 == 0 {
+            self.fill_bg_span(x, y);
+        }
+        if self.bg_span_drawn {
+            return;
+        }
+
         // 計算背景像素
         let mut bg_pixel: u8 = 0;
         let mut bg_palette: u8 = 0;
 
-        if self.bg_enabled() {
-            if self.bg_left_enabled() || x >= 8 {
-                let mux = 0x8000 >> self.fine_x;
+        if self.bg_span_valid {
+            let value = self.bg_span[x & 7];
+            bg_pixel = value & 0x03;
+            bg_palette = value >> 2;
+        } else if self.bg_enabled() && (self.bg_left_enabled() || x >= 8) {
+            let mux = 0x8000 >> self.fine_x;
 
-                let p0 = if self.bg_shifter_pattern_lo & mux != 0 { 1 } else { 0 };
-                let p1 = if self.bg_shifter_pattern_hi & mux != 0 { 1 } else { 0 };
-                bg_pixel = (p1 << 1) | p0;
+            let p0 = if self.bg_shifter_pattern_lo & mux != 0 { 1 } else { 0 };
+            let p1 = if self.bg_shifter_pattern_hi & mux != 0 { 1 } else { 0 };
+            bg_pixel = (p1 << 1) | p0;
 
-                let a0 = if self.bg_shifter_attr_lo & mux != 0 { 1 } else { 0 };
-                let a1 = if self.bg_shifter_attr_hi & mux != 0 { 1 } else { 0 };
-                bg_palette = (a1 << 1) | a0;
-            }
+            let a0 = if self.bg_shifter_attr_lo & mux != 0 { 1 } else { 0 };
+            let a1 = if self.bg_shifter_attr_hi & mux != 0 { 1 } else { 0 };
+            bg_palette = (a1 << 1) | a0;
         }
 
         // 計算精靈像素
@@ -866,23 +1428,21 @@ impl Ppu {
         let mut spr_priority: bool = false; // false = 前景
         self.sprite_zero_being_rendered = false;
 
-        if self.spr_enabled() {
-            if self.spr_left_enabled() || x >= 8 {
-                for i in 0..self.sprite_count as usize {
-                    if self.secondary_oam[i * 4 + 3] == 0 {
-                        // 精靈正在當前像素位置
-                        let p0 = if self.sprite_shifter_lo[i] & 0x80 != 0 { 1 } else { 0 };
-                        let p1 = if self.sprite_shifter_hi[i] & 0x80 != 0 { 1 } else { 0 };
-                        spr_pixel = (p1 << 1) | p0;
-                        spr_palette = (self.secondary_oam[i * 4 + 2] & 0x03) + 4;
-                        spr_priority = self.secondary_oam[i * 4 + 2] & 0x20 != 0;
-
-                        if spr_pixel != 0 {
-                            if i == 0 {
-                                self.sprite_zero_being_rendered = true;
-                            }
-                            break;
+        if self.spr_enabled() && (self.spr_left_enabled() || x >= 8) {
+            for i in 0..self.sprite_count as usize {
+                if self.secondary_oam[i * 4 + 3] == 0 {
+                    // 精靈正在當前像素位置
+                    let p0 = if self.sprite_shifter_lo[i] & 0x80 != 0 { 1 } else { 0 };
+                    let p1 = if self.sprite_shifter_hi[i] & 0x80 != 0 { 1 } else { 0 };
+                    spr_pixel = (p1 << 1) | p0;
+                    spr_palette = (self.secondary_oam[i * 4 + 2] & 0x03) + 4;
+                    spr_priority = self.secondary_oam[i * 4 + 2] & 0x20 != 0;
+
+                    if spr_pixel != 0 {
+                        if i == 0 {
+                            self.sprite_zero_being_rendered = true;
                         }
+                        break;
                     }
                 }
             }
@@ -894,18 +1454,9 @@ impl Ppu {
             (0, _) => (spr_pixel, spr_palette), // 背景透明 -> 精靈
             (_, 0) => (bg_pixel, bg_palette),   // 精靈透明 -> 背景
             (_, _) => {
-                // 都不透明 -> 檢查精靈零碰撞和優先級
-                // Sprite 0 Hit 判斷
-                if self.sprite_zero_hit_possible && self.sprite_zero_being_rendered {
-                    if self.bg_enabled() && self.spr_enabled() {
-                        // 左 8 像素裁切
-                        let left_clip = !(self.bg_left_enabled() && self.spr_left_enabled());
-                        if !left_clip || x >= 8 {
-                            if x < 255 {
-                                self.status |= 0x40; // Sprite 0 Hit
-                            }
-                        }
-                    }
+                // 都不透明 -> 檢查精靈零碰撞和優先級（碰撞與優先級無關）
+                if self.sprite_zero_being_rendered && self.sprite_zero_hit_allowed(x) {
+                    self.status |= 0x40; // Sprite 0 Hit
                 }
 
                 if !spr_priority {
@@ -916,22 +1467,47 @@ impl Ppu {
             }
         };
 
-        // 從調色盤讀取顏色並寫入幀緩衝區
-        let color_index = self.ppu_read(0x3F00 + (final_palette as u16 * 4) + final_pixel as u16);
-        let (r, g, b) = PALETTE[(color_index & 0x3F) as usize];
+        // 跳幀模式：優先級與 Sprite 0 Hit 已處理完，省略調色盤查表與寫入
+        if self.skip_output {
+            return;
+        }
+
+        // 從調色盤讀取顏色並寫入幀緩衝區（背景像素沿用區段快取的顏色）
+        let color_index = if self.bg_span_valid && final_palette < 4 {
+            self.bg_span_color[x & 7]
+        } else {
+            self.palette_read(0x3F00 + (final_palette as u16 * 4) + final_pixel as u16)
+        };
+        let rgba = self.palette_lut[(self.mask >> 5) as usize][(color_index & 0x3F) as usize];
 
         let pixel_offset = (y * 256 + x) * 4;
-        if pixel_offset + 3 < self.frame_buffer.len() {
-            self.frame_buffer[pixel_offset] = r;
-            self.frame_buffer[pixel_offset + 1] = g;
-            self.frame_buffer[pixel_offset + 2] = b;
-            self.frame_buffer[pixel_offset + 3] = 255; // Alpha
+        if let Some(pixel) = self.frame_buffer.get_mut(pixel_offset..pixel_offset + 4) {
+            pixel.copy_from_slice(&rgba.to_le_bytes());
+        }
+    }
+
+    /// 背景與精靈 0 在 x 都不透明時，是否設定 Sprite 0 Hit
+    ///
+    /// - x = 255 永不命中（硬體在最後一個像素不比對）
+    /// - 背景或精靈任一裁切左 8 像素時，x = 0..7 不命中
+    /// - 旗標在輸出該像素的週期（cycle = x + 1）設定，
+    ///   直到預渲染掃描線 dot 1 才清除
+    ///
+    /// 參考：https://www.nesdev.org/wiki/PPU_OAM#Sprite_zero_hits
+    #[inline]
+    fn sprite_zero_hit_allowed(&self, x: usize) -> bool {
+        if !self.sprite_zero_hit_possible || !self.bg_enabled() || !self.spr_enabled() || x == 255 {
+            return false;
         }
+        x >= 8 || (self.bg_left_enabled() && self.spr_left_enabled())
     }
 
     /// 檢查並清除 NMI 旗標
+    ///
+    /// VBlank 開始的 NMI 要等競爭窗口過去才送給 CPU，在那之前讀取 $2002
+    /// 或關閉 NMI 都能把它取消。
     pub fn check_nmi(&mut self) -> bool {
-        if self.nmi_occurred {
+        if self.nmi_occurred && !self.nmi_pending_release() {
             self.nmi_occurred = false;
             true
         } else {
@@ -939,6 +1515,16 @@ impl Ppu {
         }
     }
 
+    /// 是否處於 VBlank 開始後、NMI 還沒送出的競爭窗口
+    fn nmi_pending_release(&self) -> bool {
+        self.nmi_occurred && self.scanline == self.vblank_line && self.cycle < NMI_RACE_END_CYCLE
+    }
+
+    /// 檢查並清除 A12 上升緣旗標
+    pub fn check_a12_rise(&mut self) -> bool {
+        std::mem::take(&mut self.a12_rise)
+    }
+
     /// 檢查並清除 Scanline IRQ 旗標
     pub fn check_scanline_irq(&mut self) -> bool {
         if self.scanline_irq {
@@ -948,4 +1534,92 @@ impl Ppu {
             false
         }
     }
+
+    // ===== 除錯檢視（只讀取，不影響模擬狀態） =====
+
+    /// 把四個名稱表畫成 512x480 的 RGBA 影像（依卡帶目前的鏡像與 CHR bank），
+    /// 並以外框標出 t 暫存器指向的 256x240 捲軸視窗
+    pub fn render_nametables<B: PpuBus>(&self, bus: &B, out: &mut Vec<u8>) {
+        out.clear();
+        out.resize(NAMETABLE_VIEW_WIDTH * NAMETABLE_VIEW_HEIGHT * 4, 0);
+        let pattern_base: u16 = if self.ctrl & 0x10 != 0 { 0x1000 } else { 0 };
+        for table in 0..4u16 {
+            let base = 0x2000 + table * 0x400;
+            let (left, top) = ((table & 1) as usize * 256, (table >> 1) as usize * 240);
+            for row in 0..30u16 {
+                for col in 0..32u16 {
+                    let tile = self.nametable_read(bus, base + row * 32 + col) as u16;
+                    let attr = self.nametable_read(bus, base + 0x3C0 + (row / 4) * 8 + col / 4);
+                    let palette = (attr >> (((row & 2) << 1) | (col & 2))) & 0x03;
+                    self.draw_tile(
+                        bus, out, NAMETABLE_VIEW_WIDTH, pattern_base + tile * 16, palette,
+                        left + col as usize * 8, top + row as usize * 8,
+                    );
+                }
+            }
+        }
+
+        let scroll_x = (((self.t & 0x1F) << 3) | self.fine_x as u16) as usize
+            + ((self.t >> 10) & 1) as usize * 256;
+        let scroll_y = ((((self.t >> 5) & 0x1F) << 3) | ((self.t >> 12) & 0x07)) as usize
+            + ((self.t >> 11) & 1) as usize * 240;
+        let mut mark = |x: usize, y: usize| {
+            let i = ((y % NAMETABLE_VIEW_HEIGHT) * NAMETABLE_VIEW_WIDTH + x % NAMETABLE_VIEW_WIDTH) * 4;
+            out[i..i + 4].copy_from_slice(&SCROLL_OVERLAY_COLOR);
+        };
+        for dx in 0..256 {
+            mark(scroll_x + dx, scroll_y);
+            mark(scroll_x + dx, scroll_y + 239);
+        }
+        for dy in 0..240 {
+            mark(scroll_x, scroll_y + dy);
+            mark(scroll_x + 255, scroll_y + dy);
+        }
+    }
+
+    /// 把兩個圖案表（$0000、$1000）畫成 256x128 的 RGBA 影像，
+    /// palette 為調色盤編號（0-3 背景、4-7 精靈）
+    pub fn render_pattern_tables<B: PpuBus>(&self, bus: &B, palette: u8, out: &mut Vec<u8>) {
+        out.clear();
+        out.resize(PATTERN_VIEW_WIDTH * PATTERN_VIEW_HEIGHT * 4, 0);
+        for table in 0..2u16 {
+            for tile in 0..256u16 {
+                let x = table as usize * 128 + (tile % 16) as usize * 8;
+                let y = (tile / 16) as usize * 8;
+                self.draw_tile(bus, out, PATTERN_VIEW_WIDTH, table * 0x1000 + tile * 16, palette & 0x07, x, y);
+            }
+        }
+    }
+
+    /// 調色盤 RAM（32 位元組，$3F10/$3F14/$3F18/$3F1C 已套用鏡像）
+    pub fn palette_ram(&self) -> [u8; 32] {
+        std::array::from_fn(|i| self.palette_read(0x3F00 + i as u16))
+    }
+
+    /// OAM 中的 64 個精靈
+    pub fn oam_entries(&self) -> Vec<OamEntry> {
+        self.oam
+            .chunks_exact(4)
+            .enumerate()
+            .map(|(i, s)| OamEntry { index: i as u8, y: s[0], tile: s[1], attributes: s[2], x: s[3] })
+            .collect()
+    }
+
+    /// 以指定調色盤把一個 8x8 圖磚畫到 RGBA 影像的 (x, y)（圖案以 peek 讀取，不觸發鎖存器）
+    #[allow(clippy::too_many_arguments)]
+    fn draw_tile<B: PpuBus>(&self, bus: &B, out: &mut [u8], width: usize, pattern: u16, palette: u8, x: usize, y: usize) {
+        for fine_y in 0..8u16 {
+            let lo = bus.peek(pattern + fine_y);
+            let hi = bus.peek(pattern + fine_y + 8);
+            for fine_x in 0..8 {
+                let pixel = (((hi >> (7 - fine_x)) & 1) << 1) | ((lo >> (7 - fine_x)) & 1);
+                // 透明像素一律顯示背景色 $3F00
+                let entry = if pixel == 0 { 0 } else { palette as u16 * 4 + pixel as u16 };
+                let color = self.palette_read(0x3F00 + entry) & 0x3F;
+                let rgba = self.palette_lut[0][color as usize].to_le_bytes();
+                let i = ((y + fine_y as usize) * width + x + fine_x) * 4;
+                out[i..i + 4].copy_from_slice(&rgba);
+            }
+        }
+    }
 }
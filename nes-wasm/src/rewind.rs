@@ -0,0 +1,144 @@
+// ============================================================
+// 倒帶 - 定期把壓縮存檔放進環形緩衝區
+// ============================================================
+// 每隔 interval 幀擷取一次存檔（與快速存檔相同的二進位格式，PackBits
+// 壓縮），保留最近 depth 份；滿了之後覆寫最舊的一份。倒帶時載入目標幀
+// 以前最新的一份，並丟棄比它新的記錄，持續倒帶就會一路往回走。
+//
+// 每格的壓縮緩衝區重複使用，擷取時不必重新配置記憶體；存檔本身不經過
+// hex 字串，也不傳回 JavaScript。
+// ============================================================
+
+use crate::savestate::{compress_into, decompress_into};
+
+/// 一份倒帶記錄
+#[derive(Default)]
+struct RewindEntry {
+    /// 擷取時的幀數
+    frame: u64,
+    /// 壓縮後的存檔
+    data: Vec<u8>,
+}
+
+/// 倒帶環形緩衝區
+#[derive(Default)]
+pub struct RewindBuffer {
+    /// 記錄格（長度即保留份數，0 表示停用）
+    entries: Vec<RewindEntry>,
+    /// 下一份記錄寫入的位置
+    head: usize,
+    /// 目前保存的份數
+    len: usize,
+    /// 擷取間隔（幀）
+    interval: u32,
+    /// 距離下次擷取還有幾幀
+    countdown: u32,
+    /// 壓縮前的存檔暫存區（每次擷取重複使用）
+    scratch: Vec<u8>,
+}
+
+impl RewindBuffer {
+    /// 建立停用的倒帶緩衝區
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 建立指定保留份數與擷取間隔的倒帶緩衝區
+    pub fn with_settings(depth: usize, interval: u32) -> Self {
+        let mut buffer = Self::default();
+        buffer.configure(depth, interval);
+        buffer
+    }
+
+    /// 設定保留份數與擷取間隔（與目前不同時清除既有記錄）
+    pub fn configure(&mut self, depth: usize, interval: u32) {
+        let interval = interval.max(1);
+        if depth == self.entries.len() && interval == self.interval {
+            return;
+        }
+        self.entries = (0..depth).map(|_| RewindEntry::default()).collect();
+        self.interval = interval;
+        self.clear();
+    }
+
+    /// 是否啟用
+    pub fn is_enabled(&self) -> bool {
+        !self.entries.is_empty()
+    }
+
+    /// 清除所有記錄（保留已配置的緩衝區）
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+        self.countdown = 0;
+    }
+
+    /// 目前保存的份數
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 是否沒有任何記錄
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 最舊一份記錄的幀數
+    pub fn oldest_frame(&self) -> Option<u64> {
+        (self.len > 0).then(|| self.entries[self.index_from_newest(self.len - 1)].frame)
+    }
+
+    /// 每幀結束時呼叫，回傳這一幀是否該擷取
+    pub fn tick(&mut self) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+        if self.countdown == 0 {
+            self.countdown = self.interval - 1;
+            true
+        } else {
+            self.countdown -= 1;
+            false
+        }
+    }
+
+    /// 倒帶後從頭計算擷取間隔
+    pub fn restart_interval(&mut self) {
+        self.countdown = self.interval.saturating_sub(1);
+    }
+
+    /// 由 save 把存檔寫入暫存區，壓縮後保存（滿了時覆寫最舊的一份）
+    pub fn capture(&mut self, frame: u64, save: impl FnOnce(&mut Vec<u8>)) {
+        let Some(entry) = self.entries.get_mut(self.head) else { return };
+        entry.frame = frame;
+        save(&mut self.scratch);
+        compress_into(&self.scratch, &mut entry.data);
+        self.head = (self.head + 1) % self.entries.len();
+        self.len = (self.len + 1).min(self.entries.len());
+    }
+
+    /// 取出 target 幀以前最新的一份記錄並解壓，比它新的記錄丟棄；
+    /// 所有記錄都比 target 新時取最舊的一份。沒有記錄時回傳 None
+    pub fn restore(&mut self, target: u64) -> Option<&[u8]> {
+        while self.len > 1 && self.entries[self.index_from_newest(0)].frame > target {
+            self.head = self.index_from_newest(0);
+            self.len -= 1;
+        }
+        if self.len == 0 {
+            return None;
+        }
+        let newest = self.index_from_newest(0);
+        decompress_into(&self.entries[newest].data, &mut self.scratch).then_some(&self.scratch[..])
+    }
+
+    /// 佔用的記憶體（壓縮記錄與暫存區）
+    pub fn memory_size(&self) -> usize {
+        self.entries.iter().map(|e| e.data.capacity()).sum::<usize>() + self.scratch.capacity()
+    }
+
+    /// 由新到舊第 n 份記錄的位置
+    fn index_from_newest(&self, n: usize) -> usize {
+        let size = self.entries.len();
+        (self.head + size - 1 - n) % size
+    }
+}
@@ -0,0 +1,395 @@
+// ============================================================
+// 存檔槽管理 - 在 WASM 記憶體中保存多組壓縮存檔
+// ============================================================
+// 快速存檔不必把資料來回傳給 JavaScript：核心直接保存最多
+// MAX_SLOTS 組存檔，每組附帶時間戳與畫面雜湊（可當縮圖識別）。
+//
+// 存檔內容大量為 0（RAM、名稱表、PRG RAM），使用 PackBits
+// 行程編碼壓縮，實作簡單且解壓速度快。
+// 參考：https://en.wikipedia.org/wiki/PackBits
+// ============================================================
+
+/// 存檔槽數量上限
+pub const MAX_SLOTS: usize = 10;
+
+/// 存檔槽資訊
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlotInfo {
+    /// 槽位編號
+    pub slot: usize,
+    /// 存檔時間（毫秒，由前端提供）
+    pub timestamp: f64,
+    /// 存檔當下的畫面雜湊
+    pub frame_hash: u32,
+    /// 壓縮後大小（位元組）
+    pub size: usize,
+}
+
+/// 單一存檔槽
+struct Slot {
+    info: SlotInfo,
+    data: Vec<u8>,
+}
+
+/// 存檔槽集合
+pub struct SaveSlots {
+    slots: Vec<Option<Slot>>,
+}
+
+impl Default for SaveSlots {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SaveSlots {
+    /// 建立空的存檔槽集合
+    pub fn new() -> Self {
+        SaveSlots {
+            slots: (0..MAX_SLOTS).map(|_| None).collect(),
+        }
+    }
+
+    /// 將未壓縮的存檔資料存入指定槽位，槽位超出範圍回傳 false
+    pub fn store(&mut self, slot: usize, state: &[u8], timestamp: f64, frame_hash: u32) -> bool {
+        if slot >= MAX_SLOTS { return false; }
+        let data = compress(state);
+        let info = SlotInfo { slot, timestamp, frame_hash, size: data.len() };
+        self.slots[slot] = Some(Slot { info, data });
+        true
+    }
+
+    /// 取出指定槽位的存檔資料（已解壓）
+    pub fn load(&self, slot: usize) -> Option<Vec<u8>> {
+        let s = self.slots.get(slot)?.as_ref()?;
+        decompress(&s.data)
+    }
+
+    /// 把指定槽位的存檔解壓到呼叫端的緩衝區（沿用既有容量），
+    /// 槽位為空或資料無效時回傳 false
+    pub fn load_into(&self, slot: usize, out: &mut Vec<u8>) -> bool {
+        match self.slots.get(slot) {
+            Some(Some(s)) => decompress_into(&s.data, out),
+            _ => false,
+        }
+    }
+
+    /// 清除指定槽位
+    pub fn clear(&mut self, slot: usize) {
+        if let Some(s) = self.slots.get_mut(slot) {
+            *s = None;
+        }
+    }
+
+    /// 列出所有已使用的槽位
+    pub fn list(&self) -> Vec<SlotInfo> {
+        self.slots.iter().flatten().map(|s| s.info.clone()).collect()
+    }
+
+    /// 所有槽位的壓縮後總大小（位元組）
+    pub fn total_size(&self) -> usize {
+        self.slots.iter().flatten().map(|s| s.data.len()).sum()
+    }
+
+    /// 將槽位清單輸出為 JSON 陣列
+    pub fn list_json(&self) -> String {
+        let items: Vec<String> = self.list().iter().map(|i| {
+            format!(
+                "{{\"slot\":{},\"timestamp\":{},\"frameHash\":{},\"size\":{}}}",
+                i.slot, i.timestamp, i.frame_hash, i.size,
+            )
+        }).collect();
+        format!("[{}]", items.join(","))
+    }
+}
+
+// ============================================================
+// PackBits 壓縮
+// ============================================================
+// 控制位元組 n：
+// - 0..=127：後面接 n+1 個原樣位元組
+// - 129..=255：下一個位元組重複 257-n 次
+// - 128：保留不用
+
+/// 壓縮資料
+pub fn compress(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() / 4);
+    compress_into(input, &mut out);
+    out
+}
+
+/// 壓縮到呼叫端的緩衝區（先清空，沿用既有容量）
+pub fn compress_into(input: &[u8], out: &mut Vec<u8>) {
+    out.clear();
+    let mut i = 0;
+    while i < input.len() {
+        // 計算從 i 開始的重複長度
+        let mut run = 1;
+        while i + run < input.len() && run < 128 && input[i + run] == input[i] {
+            run += 1;
+        }
+        if run >= 3 {
+            out.push((257 - run) as u8);
+            out.push(input[i]);
+            i += run;
+            continue;
+        }
+        // 收集原樣位元組，直到遇到 3 個以上的重複
+        let start = i;
+        while i < input.len() && i - start < 128 {
+            if i + 2 < input.len() && input[i] == input[i + 1] && input[i] == input[i + 2] {
+                break;
+            }
+            i += 1;
+        }
+        out.push((i - start - 1) as u8);
+        out.extend_from_slice(&input[start..i]);
+    }
+}
+
+/// 解壓資料，格式錯誤時回傳 None
+pub fn decompress(input: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len() * 4);
+    decompress_into(input, &mut out).then_some(out)
+}
+
+/// 解壓到呼叫端的緩衝區（先清空），格式錯誤時回傳 false
+pub fn decompress_into(input: &[u8], out: &mut Vec<u8>) -> bool {
+    out.clear();
+    let mut i = 0;
+    while i < input.len() {
+        let n = input[i];
+        i += 1;
+        match n {
+            0..=127 => {
+                let len = n as usize + 1;
+                let Some(literal) = input.get(i..i + len) else { return false };
+                out.extend_from_slice(literal);
+                i += len;
+            }
+            128 => return false,
+            _ => {
+                let Some(&value) = input.get(i) else { return false };
+                i += 1;
+                out.extend(std::iter::repeat_n(value, 257 - n as usize));
+            }
+        }
+    }
+    true
+}
+
+// ============================================================
+// Hex 編碼 - 文字格式的存檔（匯出/匯入字串）
+// ============================================================
+
+/// 小寫 hex 字元
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// 把資料以小寫 hex 附加到字串後面
+pub fn hex_encode_into(data: &[u8], out: &mut String) {
+    out.reserve(data.len() * 2);
+    for &b in data {
+        out.push(HEX_DIGITS[(b >> 4) as usize] as char);
+        out.push(HEX_DIGITS[(b & 0x0F) as usize] as char);
+    }
+}
+
+/// 解碼 hex 字串到呼叫端的緩衝區（先清空），格式錯誤時回傳 false
+pub fn hex_decode_into(hex: &str, out: &mut Vec<u8>) -> bool {
+    out.clear();
+    let bytes = hex.as_bytes();
+    if !bytes.len().is_multiple_of(2) { return false; }
+    out.reserve(bytes.len() / 2);
+    for pair in bytes.chunks_exact(2) {
+        let (Some(hi), Some(lo)) = (hex_value(pair[0]), hex_value(pair[1])) else { return false };
+        out.push((hi << 4) | lo);
+    }
+    true
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+// ============================================================
+// 狀態欄位序列化 - 各子系統的內部狀態（v5 起）
+// ============================================================
+// 各結構以 impl_state_fields! 列出需要保存的欄位，存檔與讀檔共用同一份
+// 清單，欄位順序不會不一致。由 ROM 決定的常數（bank 數量等）與設定值
+// 不列入，讀檔時沿用目前載入的卡帶。
+// 數值一律以小端序存放；Vec 先存長度，讀檔時長度必須與目前的相同。
+// ============================================================
+
+/// 存檔資料讀取游標
+pub struct StateReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> StateReader<'a> {
+    /// 從 data 開頭開始讀取
+    pub fn new(data: &'a [u8]) -> Self {
+        StateReader { data, pos: 0 }
+    }
+
+    /// 讀取 n 個位元組，資料不足時回傳 None
+    pub fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(bytes)
+    }
+
+    /// 讀取固定長度的位元組陣列
+    pub fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take(N)?.try_into().ok()
+    }
+
+    /// 已讀取的位元組數
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+}
+
+/// 可以寫入存檔的欄位
+pub trait StateField {
+    /// 附加到存檔資料後面
+    fn save(&self, out: &mut Vec<u8>);
+    /// 依 save 的格式讀回，資料不足或不符時回傳 None
+    fn load(&mut self, r: &mut StateReader) -> Option<()>;
+}
+
+macro_rules! impl_state_number {
+    ($($ty:ty),*) => {
+        $(
+            impl StateField for $ty {
+                fn save(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn load(&mut self, r: &mut StateReader) -> Option<()> {
+                    *self = <$ty>::from_le_bytes(r.array()?);
+                    Some(())
+                }
+            }
+        )*
+    };
+}
+
+impl_state_number!(u8, u16, u32, u64, i16, i32, i64, f32, f64);
+
+impl StateField for bool {
+    fn save(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> Option<()> {
+        *self = r.array::<1>()?[0] != 0;
+        Some(())
+    }
+}
+
+/// usize 固定存成 64 位元，wasm32 與原生平台的存檔可以互通
+impl StateField for usize {
+    fn save(&self, out: &mut Vec<u8>) {
+        (*self as u64).save(out);
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> Option<()> {
+        let mut value = 0u64;
+        value.load(r)?;
+        *self = usize::try_from(value).ok()?;
+        Some(())
+    }
+}
+
+impl<T: StateField + Default> StateField for Option<T> {
+    fn save(&self, out: &mut Vec<u8>) {
+        self.is_some().save(out);
+        if let Some(value) = self {
+            value.save(out);
+        }
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> Option<()> {
+        let mut present = false;
+        present.load(r)?;
+        *self = if present {
+            let mut value = T::default();
+            value.load(r)?;
+            Some(value)
+        } else {
+            None
+        };
+        Some(())
+    }
+}
+
+impl<T: StateField, const N: usize> StateField for [T; N] {
+    fn save(&self, out: &mut Vec<u8>) {
+        for item in self {
+            item.save(out);
+        }
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> Option<()> {
+        for item in self {
+            item.load(r)?;
+        }
+        Some(())
+    }
+}
+
+impl StateField for Vec<u8> {
+    fn save(&self, out: &mut Vec<u8>) {
+        (self.len() as u32).save(out);
+        out.extend_from_slice(self);
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> Option<()> {
+        let mut len = 0u32;
+        len.load(r)?;
+        if len as usize != self.len() {
+            return None;
+        }
+        let bytes = r.take(self.len())?;
+        self.copy_from_slice(bytes);
+        Some(())
+    }
+}
+
+/// 為結構實作 StateField：依序存取列出的欄位
+macro_rules! impl_state_fields {
+    ($ty:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::savestate::StateField for $ty {
+            fn save(&self, out: &mut Vec<u8>) {
+                $( $crate::savestate::StateField::save(&self.$field, out); )*
+            }
+
+            fn load(&mut self, r: &mut $crate::savestate::StateReader) -> Option<()> {
+                $( $crate::savestate::StateField::load(&mut self.$field, r)?; )*
+                Some(())
+            }
+        }
+    };
+}
+pub(crate) use impl_state_fields;
+
+/// 在 MapperTrait 實作中產生 save_state/load_state：依序存取列出的暫存器欄位
+macro_rules! mapper_state {
+    ($($field:ident),* $(,)?) => {
+        fn save_state(&self, out: &mut Vec<u8>) {
+            $( $crate::savestate::StateField::save(&self.$field, out); )*
+        }
+
+        fn load_state(&mut self, r: &mut $crate::savestate::StateReader) -> Option<()> {
+            $( $crate::savestate::StateField::load(&mut self.$field, r)?; )*
+            Some(())
+        }
+    };
+}
+pub(crate) use mapper_state;
@@ -0,0 +1,477 @@
+// ============================================================
+// Mapper 85 (Konami VRC7) - bank 切換、VRC IRQ 與 FM 擴充音效
+// ============================================================
+// PRG ROM: $8000/$8010/$9000 三個 8KB bank，$E000-$FFFF 固定為最後一個
+// CHR: 八個 1KB bank（$A000-$D010，每個暫存器區的 +$00/+$10 各一個）
+// $E000: 位元 0-1 鏡像、位元 6 靜音、位元 7 PRG RAM 啟用
+// IRQ: $E010 重新載入值、$F000 控制、$F010 確認（與 VRC4/VRC6 相同）
+// VRC7b 以 A3 取代 A4 選擇 +$08 暫存器，這裡兩種都接受。
+//
+// 擴充音效：$9010 選擇暫存器、$9030 寫入資料。VRC7 的音源是 YM2413
+// （OPLL）的精簡版：6 個雙運算子 FM 聲道，15 種內建音色加上 1 組自訂
+// 音色（暫存器 $00-$07）。
+//
+// 這裡不是逐位元的 OPLL 模擬，而是以浮點數計算的近似核心：
+// - 每 36 個 CPU 週期產生一個取樣（約 49.7kHz，與實機相同）
+// - 調變器 → 載波器的相位調變，調變器有自我回授，波形可選半正弦
+// - ADSR 包絡線以 dB 計算（最大衰減 48dB），速率依鍵位縮放（KSR）調整
+// - AM（3.7Hz、4.8dB）與顫音（6.4Hz、約 ±7 音分）共用一組 LFO
+// - 不實作音階衰減（KSL），內建音色使用公開的 VRC7 音色表
+//
+// 用於：Lagrange Point、Tiny Toon Adventures 2（日版）
+//
+// 參考：
+// - https://www.nesdev.org/wiki/VRC7
+// - https://www.nesdev.org/wiki/VRC7_audio
+// ============================================================
+
+use std::f32::consts::{PI, TAU};
+
+use crate::apu::{ExpansionAudio, ExpansionChip};
+use crate::mappers::{MapperTrait, MapperWriteResult, PrgRamAccess, VrcIrq};
+use crate::ppu::MirrorMode;
+use crate::savestate::{impl_state_fields, mapper_state};
+
+/// VRC7 內建音色 1-15（每組 8 個位元組，格式與自訂音色暫存器 $00-$07 相同）
+const VRC7_PATCHES: [[u8; 8]; 15] = [
+    [0x03, 0x21, 0x05, 0x06, 0xE8, 0x81, 0x42, 0x27], // Buzzy Bell
+    [0x13, 0x41, 0x14, 0x0D, 0xD8, 0xF6, 0x23, 0x12], // Guitar
+    [0x11, 0x11, 0x08, 0x08, 0xFA, 0xB2, 0x20, 0x12], // Wurly
+    [0x31, 0x61, 0x0C, 0x07, 0xA8, 0x64, 0x61, 0x27], // Flute
+    [0x32, 0x21, 0x1E, 0x06, 0xE1, 0x76, 0x01, 0x28], // Clarinet
+    [0x02, 0x01, 0x06, 0x00, 0xA3, 0xE2, 0xF4, 0xF4], // Synth
+    [0x21, 0x61, 0x1D, 0x07, 0x82, 0x81, 0x11, 0x07], // Trumpet
+    [0x23, 0x21, 0x22, 0x17, 0xA2, 0x72, 0x01, 0x17], // Organ
+    [0x35, 0x11, 0x25, 0x00, 0x40, 0x73, 0x72, 0x01], // Bells
+    [0xB5, 0x01, 0x0F, 0x0F, 0xA8, 0xA5, 0x51, 0x02], // Vibes
+    [0x17, 0xC1, 0x24, 0x07, 0xF8, 0xF8, 0x22, 0x12], // Vibraphone
+    [0x71, 0x23, 0x11, 0x06, 0x65, 0x74, 0x18, 0x16], // Tutti
+    [0x01, 0x02, 0xD3, 0x05, 0xC9, 0x95, 0x03, 0x02], // Fretless
+    [0x61, 0x63, 0x0C, 0x00, 0x94, 0xC0, 0x33, 0xF6], // Synth Bass
+    [0x21, 0x72, 0x0D, 0x00, 0xC1, 0xD5, 0x56, 0x06], // Sweep
+];
+
+/// 每個 FM 取樣的 CPU 週期數
+const SAMPLE_CYCLES: u8 = 36;
+/// FM 取樣率（NTSC CPU 時脈 / 36）
+const SAMPLE_RATE: f32 = 1_789_773.0 / 36.0;
+/// 頻率倍數（MULT 暫存器 0-15）
+const MULTIPLIER: [f32; 16] = [0.5, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 10.0, 12.0, 12.0, 15.0, 15.0];
+/// 包絡線的最大衰減（dB），到這裡視為靜音
+const MAX_ATTENUATION: f32 = 48.0;
+/// 速率 1 時起音從靜音到最大聲的時間（秒）
+const ATTACK_SECONDS: f32 = 2.826;
+/// 速率 1 時衰減/釋放 48dB 的時間（秒）
+const DECAY_SECONDS: f32 = 19.64;
+/// 滿幅調變器輸出造成的相位偏移（弧度）
+const MODULATION_DEPTH: f32 = 4.0 * PI;
+const AM_DEPTH_DB: f32 = 4.8;
+const AM_HZ: f32 = 3.7;
+const VIBRATO_DEPTH: f32 = 0.004;
+const VIBRATO_HZ: f32 = 6.4;
+/// 單一聲道滿幅時相對於 APU 混音的大小
+const VRC7_GAIN: f32 = 0.12;
+
+// 包絡線階段
+const ATTACK: u8 = 0;
+const DECAY: u8 = 1;
+const SUSTAIN: u8 = 2;
+const RELEASE: u8 = 3;
+
+/// 由音色位元組解出的單一運算子參數
+struct OperatorPatch {
+    am: bool,
+    vibrato: bool,
+    /// 包絡線類型：true 在按鍵期間停在持續音量，false 持續以釋放速率衰減
+    sustained: bool,
+    ksr: bool,
+    multiplier: f32,
+    half_sine: bool,
+    attack: u8,
+    decay: u8,
+    /// 持續音量（dB）
+    sustain_level: f32,
+    release: u8,
+}
+
+impl OperatorPatch {
+    /// 從 8 位元組音色取出調變器（carrier = false）或載波器的參數
+    fn decode(patch: &[u8; 8], carrier: bool) -> Self {
+        let i = carrier as usize;
+        let flags = patch[i];
+        OperatorPatch {
+            am: flags & 0x80 != 0,
+            vibrato: flags & 0x40 != 0,
+            sustained: flags & 0x20 != 0,
+            ksr: flags & 0x10 != 0,
+            multiplier: MULTIPLIER[(flags & 0x0F) as usize],
+            half_sine: patch[3] & (0x08 << i) != 0,
+            attack: patch[4 + i] >> 4,
+            decay: patch[4 + i] & 0x0F,
+            sustain_level: (patch[6 + i] >> 4) as f32 * 3.0,
+            release: patch[6 + i] & 0x0F,
+        }
+    }
+}
+
+/// 包絡線速率（有效速率 = rate × 4 + 鍵位縮放）換算成每個取樣的比例；速率 0 不變化
+fn envelope_rate(rate: u8, key_scale: u8, seconds: f32) -> f32 {
+    if rate == 0 {
+        return 0.0;
+    }
+    let effective = (rate * 4 + key_scale).min(63) as f32;
+    let time = seconds * (-(effective - 4.0) / 4.0).exp2();
+    1.0 / (time * SAMPLE_RATE)
+}
+
+/// FM 運算子（相位與包絡線）
+#[derive(Debug, Clone)]
+struct FmOperator {
+    /// 相位（以週期為單位，0.0-1.0）
+    phase: f32,
+    /// 包絡線衰減（dB）
+    attenuation: f32,
+    stage: u8,
+}
+
+impl_state_fields!(FmOperator { phase, attenuation, stage });
+
+impl FmOperator {
+    fn new() -> Self {
+        FmOperator { phase: 0.0, attenuation: MAX_ATTENUATION, stage: RELEASE }
+    }
+
+    /// 包絡線前進一個取樣
+    fn clock_envelope(&mut self, patch: &OperatorPatch, key_scale: u8, release: u8) {
+        let key_scale = if patch.ksr { key_scale } else { key_scale >> 2 };
+        match self.stage {
+            ATTACK => {
+                if patch.attack == 15 {
+                    self.attenuation = 0.0;
+                } else {
+                    // 起音在 dB 上呈指數下降（ln 480 ≈ 6.17：48dB 降到 0.1dB）
+                    let k = (envelope_rate(patch.attack, key_scale, ATTACK_SECONDS) * 6.17).min(1.0);
+                    self.attenuation -= self.attenuation * k;
+                }
+                if self.attenuation < 0.1 {
+                    self.attenuation = 0.0;
+                    self.stage = DECAY;
+                }
+            }
+            DECAY => {
+                self.attenuation += MAX_ATTENUATION * envelope_rate(patch.decay, key_scale, DECAY_SECONDS);
+                if self.attenuation >= patch.sustain_level {
+                    self.attenuation = patch.sustain_level;
+                    self.stage = SUSTAIN;
+                }
+            }
+            SUSTAIN if patch.sustained => {}
+            _ => {
+                let rate = if self.stage == RELEASE { release } else { patch.release };
+                self.attenuation += MAX_ATTENUATION * envelope_rate(rate, key_scale, DECAY_SECONDS);
+            }
+        }
+        self.attenuation = self.attenuation.min(MAX_ATTENUATION);
+    }
+
+    /// 運算子輸出：modulation 為相位偏移（弧度），attenuation 為額外的衰減（dB）
+    fn output(&self, modulation: f32, half_sine: bool, attenuation: f32) -> f32 {
+        let total = self.attenuation + attenuation;
+        if total >= MAX_ATTENUATION {
+            return 0.0;
+        }
+        let wave = (self.phase * TAU + modulation).sin();
+        if half_sine && wave < 0.0 {
+            0.0
+        } else {
+            wave * 10f32.powf(-total / 20.0)
+        }
+    }
+}
+
+/// FM 聲道（調變器 + 載波器）
+#[derive(Debug, Clone)]
+struct FmChannel {
+    /// 9 位元 F-Number
+    fnum: u16,
+    /// 八度（0-7）
+    block: u8,
+    key_on: bool,
+    /// 持續（$2x 位元 5）：放開後以較慢的速率 5 釋放
+    sustain: bool,
+    /// 音色（0 為自訂）
+    instrument: u8,
+    /// 音量（每階衰減 3dB）
+    volume: u8,
+    modulator: FmOperator,
+    carrier: FmOperator,
+    /// 調變器最近兩次的輸出（回授用）
+    feedback: [f32; 2],
+}
+
+impl_state_fields!(FmChannel { fnum, block, key_on, sustain, instrument, volume, modulator, carrier, feedback });
+
+impl FmChannel {
+    fn new() -> Self {
+        FmChannel {
+            fnum: 0,
+            block: 0,
+            key_on: false,
+            sustain: false,
+            instrument: 0,
+            volume: 0,
+            modulator: FmOperator::new(),
+            carrier: FmOperator::new(),
+            feedback: [0.0; 2],
+        }
+    }
+
+    /// 按下或放開（只在狀態改變時重新開始包絡線）
+    fn set_key(&mut self, on: bool) {
+        if on && !self.key_on {
+            for op in [&mut self.modulator, &mut self.carrier] {
+                op.phase = 0.0;
+                op.stage = ATTACK;
+            }
+        } else if !on && self.key_on {
+            self.modulator.stage = RELEASE;
+            self.carrier.stage = RELEASE;
+        }
+        self.key_on = on;
+    }
+
+    /// 產生一個取樣；am 為 AM 衰減（dB），vibrato 為頻率倍數
+    fn sample(&mut self, patch: &[u8; 8], am: f32, vibrato: f32) -> f32 {
+        let modulator = OperatorPatch::decode(patch, false);
+        let carrier = OperatorPatch::decode(patch, true);
+        let key_scale = self.block * 2 + (self.fnum >> 8) as u8;
+        let base = self.fnum as f32 * (self.block as f32).exp2() / 524_288.0;
+
+        for (op, p) in [(&mut self.modulator, &modulator), (&mut self.carrier, &carrier)] {
+            let release = if self.sustain { 5 } else { p.release };
+            op.clock_envelope(p, key_scale, release);
+            let step = base * p.multiplier * if p.vibrato { vibrato } else { 1.0 };
+            op.phase = (op.phase + step).fract();
+        }
+
+        let feedback_level = patch[3] & 0x07;
+        let feedback = if feedback_level == 0 {
+            0.0
+        } else {
+            (self.feedback[0] + self.feedback[1]) / 2.0 * MODULATION_DEPTH / ((7 - feedback_level) as f32).exp2()
+        };
+        let total_level = (patch[2] & 0x3F) as f32 * 0.75;
+        let m = self.modulator.output(feedback, modulator.half_sine, total_level + if modulator.am { am } else { 0.0 });
+        self.feedback = [self.feedback[1], m];
+
+        let volume = self.volume as f32 * 3.0;
+        self.carrier.output(m * MODULATION_DEPTH, carrier.half_sine, volume + if carrier.am { am } else { 0.0 })
+    }
+}
+
+/// VRC7 FM 音源（OPLL 近似）
+#[derive(Debug, Clone)]
+struct Vrc7Audio {
+    /// $9010 選擇的暫存器
+    address: u8,
+    /// 自訂音色（暫存器 $00-$07）
+    custom: [u8; 8],
+    channels: [FmChannel; 6],
+    /// 距離下一個取樣的 CPU 週期
+    timer: u8,
+    /// LFO 經過的取樣數
+    lfo_samples: u32,
+    /// 最近一個取樣（6 個聲道的和）
+    output: f32,
+}
+
+impl_state_fields!(Vrc7Audio { address, custom, channels, timer, lfo_samples, output });
+
+impl Vrc7Audio {
+    fn new() -> Self {
+        Vrc7Audio {
+            address: 0,
+            custom: [0; 8],
+            channels: std::array::from_fn(|_| FmChannel::new()),
+            timer: 0,
+            lfo_samples: 0,
+            output: 0.0,
+        }
+    }
+
+    /// $9030：寫入選擇的暫存器
+    fn write(&mut self, data: u8) {
+        let reg = self.address;
+        let ch = (reg & 0x0F) as usize;
+        match reg {
+            0x00..=0x07 => self.custom[reg as usize] = data,
+            0x10..=0x15 => {
+                let channel = &mut self.channels[ch];
+                channel.fnum = (channel.fnum & 0x100) | data as u16;
+            }
+            0x20..=0x25 => {
+                let channel = &mut self.channels[ch];
+                channel.fnum = (channel.fnum & 0xFF) | ((data as u16 & 0x01) << 8);
+                channel.block = (data >> 1) & 0x07;
+                channel.sustain = data & 0x20 != 0;
+                channel.set_key(data & 0x10 != 0);
+            }
+            0x30..=0x35 => {
+                let channel = &mut self.channels[ch];
+                channel.instrument = data >> 4;
+                channel.volume = data & 0x0F;
+            }
+            _ => {}
+        }
+    }
+
+    /// 每個 CPU 週期呼叫
+    fn clock(&mut self) {
+        self.timer += 1;
+        if self.timer < SAMPLE_CYCLES {
+            return;
+        }
+        self.timer = 0;
+
+        let t = self.lfo_samples as f32 / SAMPLE_RATE;
+        self.lfo_samples = self.lfo_samples.wrapping_add(1);
+        let am = AM_DEPTH_DB / 2.0 * (1.0 - (TAU * AM_HZ * t).cos());
+        let vibrato = 1.0 + VIBRATO_DEPTH * (TAU * VIBRATO_HZ * t).sin();
+
+        let custom = self.custom;
+        self.output = self.channels.iter_mut().map(|channel| {
+            let patch = match channel.instrument {
+                0 => &custom,
+                n => &VRC7_PATCHES[n as usize - 1],
+            };
+            channel.sample(patch, am, vibrato)
+        }).sum();
+    }
+}
+
+pub struct Mapper85 {
+    prg_banks: u8,
+    chr_banks: u8,
+    /// $8000/$A000/$C000 的 8KB PRG bank
+    prg_regs: [u8; 3],
+    chr_regs: [u8; 8],
+    /// $E000：位元 0-1 鏡像、位元 6 靜音、位元 7 PRG RAM 啟用
+    control: u8,
+    irq: VrcIrq,
+    audio: Vrc7Audio,
+}
+
+impl Mapper85 {
+    pub fn new(prg_banks: u8, chr_banks: u8) -> Self {
+        Mapper85 {
+            prg_banks,
+            chr_banks,
+            prg_regs: [0; 3],
+            chr_regs: [0; 8],
+            control: 0,
+            irq: VrcIrq::new(),
+            audio: Vrc7Audio::new(),
+        }
+    }
+}
+
+impl MapperTrait for Mapper85 {
+    mapper_state!(prg_regs, chr_regs, control, irq, audio);
+
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        let bank = match addr {
+            0x8000..=0xDFFF => self.prg_regs[((addr - 0x8000) >> 13) as usize] as u32,
+            0xE000..=0xFFFF => self.prg_banks as u32 * 2 - 1,
+            _ => return None,
+        };
+        Some(bank * 8192 + (addr & 0x1FFF) as u32)
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Option<MapperWriteResult> {
+        // VRC7a 以 A4、VRC7b 以 A3 選擇每個暫存器區的第二個暫存器
+        let second = addr & 0x18 != 0;
+        match (addr & 0xF000, second) {
+            (0x8000, false) => self.prg_regs[0] = data & 0x3F,
+            (0x8000, true) => self.prg_regs[1] = data & 0x3F,
+            (0x9000, false) => self.prg_regs[2] = data & 0x3F,
+            (0x9000, true) if addr & 0x20 != 0 => self.audio.write(data),
+            (0x9000, true) => self.audio.address = data,
+            (0xA000..=0xD000, _) => {
+                let reg = ((addr - 0xA000) >> 12) as usize * 2 + second as usize;
+                self.chr_regs[reg] = data;
+                return Some(MapperWriteResult::none());
+            }
+            (0xE000, false) => {
+                self.control = data;
+                let mirror = match data & 0x03 {
+                    0 => MirrorMode::Vertical,
+                    1 => MirrorMode::Horizontal,
+                    2 => MirrorMode::SingleScreenLow,
+                    _ => MirrorMode::SingleScreenHigh,
+                };
+                return Some(MapperWriteResult::with_mirror(mirror));
+            }
+            (0xE000, true) => self.irq.set_latch(data),
+            (0xF000, false) => self.irq.write_control(data),
+            (0xF000, true) => self.irq.acknowledge(),
+            _ => {}
+        }
+        None
+    }
+
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        if addr < 0x2000 {
+            // CHR RAM（Lagrange Point）只有 8KB，bank 編號折返
+            let total = (self.chr_banks as u32 * 8).max(8);
+            let bank = self.chr_regs[(addr >> 10) as usize] as u32 % total;
+            Some(bank * 1024 + (addr & 0x03FF) as u32)
+        } else {
+            None
+        }
+    }
+
+    fn ppu_write(&self, addr: u16) -> Option<u32> {
+        if self.chr_banks == 0 { self.ppu_read(addr) } else { None }
+    }
+
+    fn reset(&mut self) {
+        self.prg_regs = [0; 3];
+        self.chr_regs = [0; 8];
+        self.control = 0;
+        self.irq.reset();
+        self.audio = Vrc7Audio::new();
+    }
+
+    fn cpu_clock(&mut self) {
+        self.irq.clock();
+    }
+
+    fn check_irq(&mut self) -> bool {
+        self.irq.pending()
+    }
+
+    fn prg_ram_access(&self) -> PrgRamAccess {
+        if self.control & 0x80 != 0 { PrgRamAccess::ReadWrite } else { PrgRamAccess::Disabled }
+    }
+
+    fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> {
+        Some(self)
+    }
+}
+
+impl ExpansionAudio for Mapper85 {
+    fn chip(&self) -> ExpansionChip {
+        ExpansionChip::Vrc7
+    }
+
+    fn clock(&mut self) {
+        self.audio.clock();
+    }
+
+    fn output(&self) -> f32 {
+        if self.control & 0x40 != 0 {
+            return 0.0;
+        }
+        self.audio.output * VRC7_GAIN
+    }
+}
@@ -0,0 +1,215 @@
+// ============================================================
+// VS. UniSystem - 大型機台版的 PPU 型號、DIP 開關、投幣與保護
+// ============================================================
+// VS. 系統的遊戲跑在與家用機幾乎相同的硬體上，差別在於：
+// - PPU：RGB 輸出的 2C03/2C04/2C05。2C04 的四種型號把調色盤索引
+//   打亂成不同順序（防止換 ROM），2C05 對調 $2000/$2001，
+//   並在 $2002 的低 6 位元回傳型號識別碼
+// - $4016 讀取：位元 2 = 服務鍵、位元 3-4 = DIP 1-2、位元 5-6 = 投幣口 1/2
+// - $4017 讀取：位元 2-7 = DIP 3-8
+// - $4016 寫入位元 2：Mapper 99 的 CHR（與 Gumshoe 的 PRG）bank
+// - 部分遊戲在 $5E00 附近有保護晶片，讀不到預期的值就不啟動
+//
+// PPU 型號與保護種類只有 NES 2.0 標頭會標示；iNES 1.0 的 VS. ROM
+// 預設為 2C03，需要時由前端以 set_vs_ppu 指定。
+//
+// 參考：
+// - https://www.nesdev.org/wiki/VS._System
+// - https://www.nesdev.org/wiki/NES_2.0#Vs._System_Type
+// - https://www.nesdev.org/wiki/PPU_palettes#2C04
+// ============================================================
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// 投幣開關保持按下的幀數（太短遊戲會漏接）
+pub const COIN_PULSE_FRAMES: u8 = 4;
+
+/// VS. 系統的 PPU 型號（RP2C03B/G 與 RC2C03B/C 的調色盤相同，合併為 Rp2c03）
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VsPpu {
+    Rp2c03 = 0,
+    /// RP2C04-0001
+    Rp2c04v1 = 1,
+    /// RP2C04-0002
+    Rp2c04v2 = 2,
+    /// RP2C04-0003
+    Rp2c04v3 = 3,
+    /// RP2C04-0004
+    Rp2c04v4 = 4,
+    /// RC2C05-01
+    Rc2c05v1 = 5,
+    /// RC2C05-02
+    Rc2c05v2 = 6,
+    /// RC2C05-03
+    Rc2c05v3 = 7,
+    /// RC2C05-04
+    Rc2c05v4 = 8,
+    /// RC2C05-05
+    Rc2c05v5 = 9,
+}
+
+impl VsPpu {
+    /// 由 NES 2.0 標頭位元組 13 的低 4 位元換算（未定義的值視為 2C03）
+    pub fn from_nes2(value: u8) -> Self {
+        match value & 0x0F {
+            2 => VsPpu::Rp2c04v1,
+            3 => VsPpu::Rp2c04v2,
+            4 => VsPpu::Rp2c04v3,
+            5 => VsPpu::Rp2c04v4,
+            8 => VsPpu::Rc2c05v1,
+            9 => VsPpu::Rc2c05v2,
+            10 => VsPpu::Rc2c05v3,
+            11 => VsPpu::Rc2c05v4,
+            12 => VsPpu::Rc2c05v5,
+            _ => VsPpu::Rp2c03,
+        }
+    }
+
+    /// 型號名稱（用於 RomInfo）
+    pub fn name(self) -> &'static str {
+        match self {
+            VsPpu::Rp2c03 => "RP2C03",
+            VsPpu::Rp2c04v1 => "RP2C04-0001",
+            VsPpu::Rp2c04v2 => "RP2C04-0002",
+            VsPpu::Rp2c04v3 => "RP2C04-0003",
+            VsPpu::Rp2c04v4 => "RP2C04-0004",
+            VsPpu::Rc2c05v1 => "RC2C05-01",
+            VsPpu::Rc2c05v2 => "RC2C05-02",
+            VsPpu::Rc2c05v3 => "RC2C05-03",
+            VsPpu::Rc2c05v4 => "RC2C05-04",
+            VsPpu::Rc2c05v5 => "RC2C05-05",
+        }
+    }
+
+    /// 2C04 的調色盤打亂順序（0-3），其他型號為 None
+    pub fn scramble(self) -> Option<usize> {
+        match self {
+            VsPpu::Rp2c04v1 => Some(0),
+            VsPpu::Rp2c04v2 => Some(1),
+            VsPpu::Rp2c04v3 => Some(2),
+            VsPpu::Rp2c04v4 => Some(3),
+            _ => None,
+        }
+    }
+
+    /// 2C05：$2000 與 $2001 對調
+    pub fn swaps_ctrl_mask(self) -> bool {
+        matches!(self, VsPpu::Rc2c05v1 | VsPpu::Rc2c05v2 | VsPpu::Rc2c05v3 | VsPpu::Rc2c05v4 | VsPpu::Rc2c05v5)
+    }
+
+    /// 2C05 在 $2002 低 6 位元回傳的識別碼（-05 沒有識別碼）
+    pub fn status_signature(self) -> Option<u8> {
+        match self {
+            VsPpu::Rc2c05v1 | VsPpu::Rc2c05v4 => Some(0x1B),
+            VsPpu::Rc2c05v2 => Some(0x3D),
+            VsPpu::Rc2c05v3 => Some(0x1C),
+            _ => None,
+        }
+    }
+}
+
+/// 保護晶片種類（NES 2.0 標頭位元組 13 的高 4 位元）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VsProtection {
+    None,
+    /// R.B.I. Baseball：$5E00 重設計數器，$5E01 第 10 次讀取回傳 $6F
+    RbiBaseball,
+    /// Super Xevious：$54FF/$5567/$5678/$578F 的固定回應
+    SuperXevious,
+}
+
+impl VsProtection {
+    /// 由 NES 2.0 標頭換算（TKO Boxing 等其他保護尚未支援，視為沒有）
+    pub fn from_nes2(value: u8) -> Self {
+        match value >> 4 {
+            1 => VsProtection::RbiBaseball,
+            3 => VsProtection::SuperXevious,
+            _ => VsProtection::None,
+        }
+    }
+}
+
+/// VS. 系統的機台輸入與保護晶片
+#[derive(Debug, Clone)]
+pub struct VsSystem {
+    /// DIP 開關（位元 0 = DIP 1）
+    dip_switches: u8,
+    /// 服務鍵
+    service: bool,
+    /// 兩個投幣口剩餘的按下幀數
+    coin_frames: [u8; 2],
+    protection: VsProtection,
+    protection_counter: u8,
+}
+
+impl VsSystem {
+    pub fn new(protection: VsProtection) -> Self {
+        VsSystem { dip_switches: 0, service: false, coin_frames: [0; 2], protection, protection_counter: 0 }
+    }
+
+    /// 設定 DIP 開關（位元 0 = DIP 1，位元 7 = DIP 8）
+    pub fn set_dip_switches(&mut self, value: u8) {
+        self.dip_switches = value;
+    }
+
+    /// 目前的 DIP 開關
+    pub fn dip_switches(&self) -> u8 {
+        self.dip_switches
+    }
+
+    /// 按住或放開服務鍵
+    pub fn set_service(&mut self, pressed: bool) {
+        self.service = pressed;
+    }
+
+    /// 投幣（slot 0 或 1），投幣開關保持 COIN_PULSE_FRAMES 幀
+    pub fn insert_coin(&mut self, slot: usize) {
+        if let Some(frames) = self.coin_frames.get_mut(slot) {
+            *frames = COIN_PULSE_FRAMES;
+        }
+    }
+
+    /// 每幀結束時呼叫
+    pub fn end_frame(&mut self) {
+        for frames in &mut self.coin_frames {
+            *frames = frames.saturating_sub(1);
+        }
+    }
+
+    /// $4016/$4017 讀取時與控制器資料合併的位元
+    pub fn read_inputs(&self, addr: u16) -> u8 {
+        if addr == 0x4016 {
+            (self.service as u8) << 2
+                | (self.dip_switches & 0x03) << 3
+                | ((self.coin_frames[0] > 0) as u8) << 5
+                | ((self.coin_frames[1] > 0) as u8) << 6
+        } else {
+            self.dip_switches & 0xFC
+        }
+    }
+
+    /// 保護晶片的讀取（不是保護位址時回傳 None）
+    pub fn read_protection(&mut self, addr: u16) -> Option<u8> {
+        match (self.protection, addr) {
+            (VsProtection::RbiBaseball, 0x5E00) => {
+                self.protection_counter = 0;
+                Some(0)
+            }
+            (VsProtection::RbiBaseball, 0x5E01) => {
+                let count = self.protection_counter;
+                self.protection_counter = self.protection_counter.wrapping_add(1);
+                Some(if count == 9 { 0x6F } else { 0xB4 })
+            }
+            (VsProtection::SuperXevious, 0x54FF) => Some(0x05),
+            (VsProtection::SuperXevious, 0x5678) => Some(if self.protection_counter != 0 { 0x00 } else { 0x01 }),
+            (VsProtection::SuperXevious, 0x578F) => Some(if self.protection_counter != 0 { 0xD1 } else { 0x89 }),
+            (VsProtection::SuperXevious, 0x5567) => {
+                self.protection_counter ^= 1;
+                Some(if self.protection_counter != 0 { 0x37 } else { 0x3E })
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,1067 @@
+// ============================================================
+// WASM 匯出介面 - 供 JavaScript 呼叫
+// ============================================================
+// 只在啟用 wasm feature 時編譯；模擬器核心本身不依賴 wasm-bindgen。
+// ============================================================
+
+use wasm_bindgen::prelude::*;
+
+use crate::apu::{AudioChannel, ExpansionChip};
+use crate::config::Region;
+use crate::controller::{Button, InputDevice};
+use crate::debugger::StopReason;
+use crate::vs::VsPpu;
+use crate::{emulator, ppu};
+
+/// NES 模擬器 WASM 包裝器
+/// 這是暴露給 JavaScript 的主要介面
+#[wasm_bindgen]
+pub struct NesWasm {
+    /// 內部模擬器實例
+    emu: emulator::Emulator,
+    /// 電池 RAM 儲存事件回呼（參數為 Uint8Array）
+    sram_callback: Option<js_sys::Function>,
+    /// 遊戲要求換面時的回呼
+    disk_swap_callback: Option<js_sys::Function>,
+}
+
+impl Default for NesWasm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl NesWasm {
+    /// 建立新的 NES 模擬器實例
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> NesWasm {
+        NesWasm {
+            emu: emulator::Emulator::new(),
+            sram_callback: None,
+            disk_swap_callback: None,
+        }
+    }
+
+    /// 載入 ROM 資料（iNES/NES 2.0，也接受 NSF 音樂檔）
+    /// 傳入 ROM 的 Uint8Array，回傳是否載入成功
+    /// 可以直接換卡：設定與輸入裝置保留，金手指、存檔槽與影片清除
+    #[wasm_bindgen(js_name = "loadRom")]
+    pub fn load_rom(&mut self, rom_data: &[u8]) -> bool {
+        self.emu.load_rom(rom_data)
+    }
+
+    /// 卸下卡帶並釋放 ROM 記憶體；設定與輸入裝置保留
+    #[wasm_bindgen(js_name = "unloadRom")]
+    pub fn unload_rom(&mut self) {
+        self.emu.unload_rom();
+    }
+
+    /// 取得已載入 ROM 的資訊（JS 物件）
+    /// 欄位：mapperId、submapper、mapperName、mapperSupported、prgRomSize、chrRomSize、
+    /// chrRam、battery、prgRamSize、mirroring、trainer、nes2、region、crc32、vsPpu（VS. System 以外為 null）
+    #[wasm_bindgen(js_name = "getRomInfo")]
+    pub fn get_rom_info(&self) -> JsValue {
+        let info = self.emu.cartridge.rom_info();
+        let mirroring = match info.mirror_mode {
+            ppu::MirrorMode::Horizontal => "horizontal",
+            ppu::MirrorMode::Vertical => "vertical",
+            ppu::MirrorMode::SingleScreenLow | ppu::MirrorMode::SingleScreenHigh => "single",
+            ppu::MirrorMode::FourScreen => "four",
+        };
+        let obj = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&obj, &JsValue::from_str(key), &value);
+        };
+        set("mapperId", info.mapper_id.into());
+        set("submapper", info.submapper.into());
+        set("mapperName", info.mapper_name.into());
+        set("mapperSupported", info.mapper_supported.into());
+        set("prgRomSize", (info.prg_rom_size as u32).into());
+        set("chrRomSize", (info.chr_rom_size as u32).into());
+        set("chrRam", info.chr_ram.into());
+        set("battery", info.has_battery.into());
+        set("prgRamSize", (info.prg_ram_size as u32).into());
+        set("mirroring", mirroring.into());
+        set("trainer", info.has_trainer.into());
+        set("nes2", info.is_nes2.into());
+        set("region", info.region.name().into());
+        set("crc32", info.crc32.into());
+        set("headerCleaned", info.header_cleaned.into());
+        set("vsPpu", info.vs_ppu.map_or(JsValue::NULL, JsValue::from_str));
+        set("compatHacks", info.compat_hacks.iter().map(|&h| JsValue::from_str(h)).collect::<js_sys::Array>().into());
+        obj.into()
+    }
+
+    /// 重置模擬器（維持舊版行為：清除 RAM 並重置 Mapper）
+    pub fn reset(&mut self) {
+        self.emu.reset();
+    }
+
+    /// 軟體重置：相當於按下主機 Reset 鍵，RAM 與 Mapper 狀態保留
+    #[wasm_bindgen(js_name = "softReset")]
+    pub fn soft_reset(&mut self) {
+        self.emu.soft_reset();
+    }
+
+    /// 重新開機：相當於關閉電源再開啟，只保留電池備份的存檔
+    #[wasm_bindgen(js_name = "powerCycle")]
+    pub fn power_cycle(&mut self) {
+        self.emu.power_cycle();
+    }
+
+    /// 以預先執行（run-ahead）取代 frame：正常執行一幀後，以目前的輸入
+    /// 再往前執行 frames 幀並顯示該畫面，然後還原狀態，用來降低輸入延遲
+    /// 一般遊戲設 1-2 即可；0 與 frame 相同
+    #[wasm_bindgen(js_name = "runFrameWithRunahead")]
+    pub fn run_frame_with_runahead(&mut self, frames: u32) {
+        self.emu.frame_with_runahead(frames);
+        self.dispatch_events();
+    }
+
+    /// 執行一幀（包含所有 CPU/PPU/APU 週期）
+    pub fn frame(&mut self) {
+        self.emu.frame();
+        self.dispatch_events();
+    }
+
+    /// 執行一幀但跳過像素輸出（快轉/跳幀用，音訊與遊戲邏輯照常）
+    #[wasm_bindgen(js_name = "emulateFrameSkipped")]
+    pub fn emulate_frame_skipped(&mut self) {
+        self.emu.frame_skipped();
+        self.dispatch_events();
+    }
+
+    /// 設定快轉倍率（1 為正常速度，最多 16），由 frameAtSpeed 使用
+    #[wasm_bindgen(js_name = "setSpeed")]
+    pub fn set_speed(&mut self, multiplier: u32) {
+        self.emu.set_speed(multiplier);
+    }
+
+    /// 取得目前的快轉倍率
+    #[wasm_bindgen(js_name = "getSpeed")]
+    pub fn get_speed(&self) -> u32 {
+        self.emu.speed()
+    }
+
+    /// 依快轉倍率執行多幀，只輸出最後一幀的畫面；快轉時的聲音以
+    /// setConfig({ turboAudio: "mute" | "resample" }) 選擇
+    #[wasm_bindgen(js_name = "frameAtSpeed")]
+    pub fn frame_at_speed(&mut self) {
+        self.emu.frame_at_speed();
+        self.dispatch_events();
+    }
+
+    /// 執行 n 個 CPU 週期（可以停在幀的中間，供追光渲染或分段取出音訊），
+    /// 回傳實際執行的週期數；命中中斷點時提前停止
+    #[wasm_bindgen(js_name = "runCycles")]
+    pub fn run_cycles(&mut self, n: u32) -> f64 {
+        let cycles = self.emu.run_cycles(n);
+        self.dispatch_events();
+        cycles as f64
+    }
+
+    /// 執行到 n 條掃描線之後的掃描線開頭，回傳實際執行的 CPU 週期數
+    #[wasm_bindgen(js_name = "runScanlines")]
+    pub fn run_scanlines(&mut self, n: u32) -> f64 {
+        let cycles = self.emu.run_scanlines(n);
+        self.dispatch_events();
+        cycles as f64
+    }
+
+    /// 連續執行 n 幀（無頭回歸測試用，不需逐幀回到 JavaScript）
+    #[wasm_bindgen(js_name = "runFrames")]
+    pub fn run_frames(&mut self, n: u32) {
+        self.emu.run_frames(n);
+        self.dispatch_events();
+    }
+
+    /// 取得目前畫面的雜湊值（FNV-1a），可與黃金值比對
+    #[wasm_bindgen(js_name = "getFrameHash")]
+    pub fn get_frame_hash(&self) -> u32 {
+        self.emu.frame_hash()
+    }
+
+    /// 取得測試 ROM 狀態碼（$6000），無協定簽章時回傳 -1
+    #[wasm_bindgen(js_name = "getTestStatus")]
+    pub fn get_test_status(&self) -> i32 {
+        self.emu.test_rom_status().map_or(-1, |s| s as i32)
+    }
+
+    /// 取得測試 ROM 結果訊息（$6004 起的字串）
+    #[wasm_bindgen(js_name = "getTestMessage")]
+    pub fn get_test_message(&self) -> String {
+        self.emu.test_rom_message()
+    }
+
+    /// 執行測試 ROM 直到完成，回傳結果碼（0 = 通過），逾時回傳 -1
+    #[wasm_bindgen(js_name = "runTestRom")]
+    pub fn run_test_rom(&mut self, max_frames: u32) -> i32 {
+        self.emu.run_test_rom(max_frames).map_or(-1, |s| s as i32)
+    }
+
+    /// 取得要顯示的畫面指標（RGBA 像素資料，大小見 getFrameWidth/getFrameHeight；
+    /// 開啟 cropOverscan 時為 256x224，否則為 256x240）
+    /// 回傳的是 WASM 記憶體中的指標，JavaScript 可直接存取
+    #[wasm_bindgen(js_name = "getFrameBufferPtr")]
+    pub fn get_frame_buffer_ptr(&self) -> *const u8 {
+        self.emu.get_frame_buffer_ptr()
+    }
+
+    /// 取得要顯示的畫面長度（位元組數）
+    #[wasm_bindgen(js_name = "getFrameBufferLen")]
+    pub fn get_frame_buffer_len(&self) -> usize {
+        self.emu.get_frame_buffer_len()
+    }
+
+    /// 取得要顯示的畫面寬度（像素）
+    #[wasm_bindgen(js_name = "getFrameWidth")]
+    pub fn get_frame_width(&self) -> u32 {
+        self.emu.frame_size().0
+    }
+
+    /// 取得要顯示的畫面高度（像素）
+    #[wasm_bindgen(js_name = "getFrameHeight")]
+    pub fn get_frame_height(&self) -> u32 {
+        self.emu.frame_size().1
+    }
+
+    /// 設定控制器按鈕狀態
+    /// controller: 控制器編號（0-3；2、3 需先以 setFourScore 接上四人轉接器）
+    /// button: 按鈕（Button.A、Button.Start 等，數值與舊版編號相同）
+    /// pressed: 是否按下
+    #[wasm_bindgen(js_name = "setButton")]
+    pub fn set_button(&mut self, controller: u8, button: Button, pressed: bool) {
+        self.emu.set_button(controller, button as u8, pressed);
+    }
+
+    /// 接上或拔除 Four Score 四人轉接器（Gauntlet II、Nintendo World Cup 等）
+    #[wasm_bindgen(js_name = "setFourScore")]
+    pub fn set_four_score(&mut self, connected: bool) {
+        self.emu.set_four_score(connected);
+    }
+
+    /// 設定控制器埠上連接的裝置
+    #[wasm_bindgen(js_name = "setInputDevice")]
+    pub fn set_input_device(&mut self, port: u8, device: InputDevice) {
+        self.emu.set_input_device(port, device);
+    }
+
+    /// 設定 Zapper 瞄準位置
+    /// x, y 為畫布座標，canvasWidth/canvasHeight 為畫布顯示大小，
+    /// 畫布顯示的是 getFrameBufferPtr 的畫面（裁切過掃描時少了上下各 8 條），
+    /// 換算回 256x240 的畫面像素；瞄準畫布外時視為瞄準畫面外
+    #[wasm_bindgen(js_name = "setZapperPosition")]
+    pub fn set_zapper_position(&mut self, x: f64, y: f64, canvas_width: f64, canvas_height: f64) {
+        let to_pixel = |v: f64, size: f64, pixels: f64| {
+            if size > 0.0 && v >= 0.0 && v < size { (v * pixels / size) as i32 } else { -1 }
+        };
+        let (width, height) = self.emu.frame_size();
+        let top = (240 - height) as i32 / 2;
+        let px = to_pixel(x, canvas_width, width as f64);
+        let py = match to_pixel(y, canvas_height, height as f64) {
+            -1 => -1,
+            row => row + top,
+        };
+        self.emu.set_zapper_position(px, py);
+    }
+
+    /// 設定 Zapper 扳機
+    #[wasm_bindgen(js_name = "setZapperTrigger")]
+    pub fn set_zapper_trigger(&mut self, pulled: bool) {
+        self.emu.set_zapper_trigger(pulled);
+    }
+
+    /// 接上或拔除 Family BASIC 鍵盤（擴充埠）
+    #[wasm_bindgen(js_name = "setKeyboardConnected")]
+    pub fn set_keyboard_connected(&mut self, connected: bool) {
+        self.emu.set_keyboard_connected(connected);
+    }
+
+    /// 設定 Family BASIC 鍵盤按鍵：row 0-8、col 0-7
+    /// 例如 RETURN 為 (0, 2)、SPACE 為 (8, 6)
+    #[wasm_bindgen(js_name = "setKeyState")]
+    pub fn set_key_state(&mut self, row: usize, col: usize, pressed: bool) {
+        self.emu.set_key_state(row, col, pressed);
+    }
+
+    /// 資料記錄器開始錄音（Family BASIC 的 SAVE）
+    #[wasm_bindgen(js_name = "tapeRecord")]
+    pub fn tape_record(&mut self) {
+        self.emu.tape_record();
+    }
+
+    /// 資料記錄器播放錄音帶（Family BASIC 的 LOAD）
+    #[wasm_bindgen(js_name = "tapePlay")]
+    pub fn tape_play(&mut self, tape: &[u8]) {
+        self.emu.tape_play(tape);
+    }
+
+    /// 資料記錄器停止，回傳錄音帶內容（可存檔後再以 tapePlay 播放）
+    #[wasm_bindgen(js_name = "tapeStop")]
+    pub fn tape_stop(&mut self) -> Vec<u8> {
+        self.emu.tape_stop()
+    }
+
+    /// 瞄準畫面外扣一次 Zapper 扳機（例如滑鼠右鍵），不影響游標位置
+    #[wasm_bindgen(js_name = "fireZapperOffscreen")]
+    pub fn fire_zapper_offscreen(&mut self) {
+        self.emu.fire_zapper_offscreen();
+    }
+
+    /// 設定旋鈕位置（0-255）
+    #[wasm_bindgen(js_name = "setPaddleValue")]
+    pub fn set_paddle_value(&mut self, value: u8) {
+        self.emu.set_paddle_value(value);
+    }
+
+    /// 設定旋鈕按鈕
+    #[wasm_bindgen(js_name = "setPaddleButton")]
+    pub fn set_paddle_button(&mut self, pressed: bool) {
+        self.emu.set_paddle_button(pressed);
+    }
+
+    /// 取得目前卡帶標頭宣告的地區
+    #[wasm_bindgen(js_name = "getRomRegion")]
+    pub fn get_rom_region(&self) -> Region {
+        self.emu.cartridge.rom_info().region
+    }
+
+    /// 強制指定主機地區（"NTSC"、"PAL"、"Dendy"，不分大小寫），
+    /// "auto" 回到依 ROM 標頭判斷；無法辨識的名稱回傳 false
+    #[wasm_bindgen(js_name = "setRegion")]
+    pub fn set_region(&mut self, name: &str) -> bool {
+        if name.eq_ignore_ascii_case("auto") {
+            self.emu.set_region(None);
+            return true;
+        }
+        match Region::from_name(name) {
+            Some(region) => {
+                self.emu.set_region(Some(region));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 取得目前採用的主機地區
+    #[wasm_bindgen(js_name = "getRegion")]
+    pub fn get_region(&self) -> Region {
+        self.emu.region()
+    }
+
+    /// 設定音頻取樣率
+    #[wasm_bindgen(js_name = "setAudioSampleRate")]
+    pub fn set_audio_sample_rate(&mut self, rate: f64) {
+        self.emu.set_audio_sample_rate(rate);
+    }
+
+    /// 以 JSON 更新模擬器設定（只需包含要變更的欄位）
+    /// 例如：{"sampleRate": 48000, "spriteLimit": false}
+    /// 格式錯誤或型別不符時回傳 false，設定維持不變
+    #[wasm_bindgen(js_name = "setConfig")]
+    pub fn set_config(&mut self, json: &str) -> bool {
+        let mut config = self.emu.config().clone();
+        if !config.merge_json(json) {
+            return false;
+        }
+        self.emu.set_config(config);
+        true
+    }
+
+    /// 取得目前設定（JSON 字串）
+    #[wasm_bindgen(js_name = "getConfig")]
+    pub fn get_config(&self) -> String {
+        self.emu.config().to_json()
+    }
+
+    /// 取得音頻緩衝區指標
+    #[wasm_bindgen(js_name = "getAudioBufferPtr")]
+    pub fn get_audio_buffer_ptr(&mut self) -> *const f32 {
+        self.emu.get_audio_buffer_ptr()
+    }
+
+    /// 取得可用的音頻取樣數（立體聲時為 L/R 交錯的數值個數，是幀數的兩倍）
+    #[wasm_bindgen(js_name = "getAudioBufferLen")]
+    pub fn get_audio_buffer_len(&self) -> usize {
+        self.emu.get_audio_buffer_len()
+    }
+
+    /// 消費音頻取樣（讀取後清除緩衝區）
+    #[wasm_bindgen(js_name = "consumeAudioSamples")]
+    pub fn consume_audio_samples(&mut self) -> usize {
+        self.emu.consume_audio_samples()
+    }
+
+    /// 取出所有可用的音頻取樣，回傳大小剛好的 Float32Array（複製品）
+    /// 不需要指標與 WASM 記憶體視圖，記憶體成長後也不會讀到錯誤資料
+    #[wasm_bindgen(js_name = "takeAudioSamples")]
+    pub fn take_audio_samples(&mut self) -> Vec<f32> {
+        self.emu.take_audio_samples()
+    }
+
+    /// 把音頻取樣填入呼叫端提供的 Float32Array，回傳填入的數量
+    /// 放不下的取樣保留到下次讀取
+    #[wasm_bindgen(js_name = "fillAudioSamples")]
+    pub fn fill_audio_samples(&mut self, out: &mut [f32]) -> usize {
+        self.emu.fill_audio_samples(out)
+    }
+
+    /// 音頻緩衝區已滿而丟棄的取樣總數（持續增加表示取用太慢，可加大取用量）
+    #[wasm_bindgen(js_name = "getAudioOverflowCount")]
+    pub fn get_audio_overflow_count(&self) -> f64 {
+        self.emu.audio_overflow_count() as f64
+    }
+
+    /// 在混音中啟用或靜音聲道（用於獨奏或排查音效）
+    #[wasm_bindgen(js_name = "setChannelEnabled")]
+    pub fn set_channel_enabled(&mut self, channel: AudioChannel, enabled: bool) {
+        self.emu.apu.set_channel_enabled(channel, enabled);
+    }
+
+    /// 設定聲道在立體聲輸出中的左右位置（-1.0 最左、0.0 置中、1.0 最右）
+    /// 立體聲以 setConfig({ stereo: true }) 開啟，音頻取樣改為 L/R 交錯
+    #[wasm_bindgen(js_name = "setChannelPan")]
+    pub fn set_channel_pan(&mut self, channel: AudioChannel, pan: f32) {
+        self.emu.apu.set_channel_pan(channel, pan);
+    }
+
+    /// 取得各聲道目前的輸出準位（Float32Array，依 AudioChannel 順序，0.0-1.0）
+    /// 靜音的聲道仍會回報準位，可直接驅動視覺化
+    #[wasm_bindgen(js_name = "getChannelLevels")]
+    pub fn get_channel_levels(&self) -> Vec<f32> {
+        self.emu.apu.channel_levels().to_vec()
+    }
+
+    /// 設定擴充音源晶片的音量增益（1.0 為預設）
+    #[wasm_bindgen(js_name = "setExpansionGain")]
+    pub fn set_expansion_gain(&mut self, chip: ExpansionChip, gain: f32) {
+        self.emu.apu.set_expansion_gain(chip, gain);
+    }
+
+    /// 匯出二進位存檔（Uint8Array）
+    #[wasm_bindgen(js_name = "exportSaveStateBytes")]
+    pub fn export_save_state_bytes(&self) -> Vec<u8> {
+        self.emu.export_save_state_bytes()
+    }
+
+    /// 匯出存檔為 hex 字串（大小是二進位的兩倍，建議改用 exportSaveStateBytes）
+    #[wasm_bindgen(js_name = "exportSaveState")]
+    pub fn export_save_state(&self) -> String {
+        self.emu.export_save_state()
+    }
+
+    /// 從 hex 字串匯入存檔
+    #[wasm_bindgen(js_name = "importSaveState")]
+    pub fn import_save_state(&mut self, hex: &str) -> bool {
+        self.emu.import_save_state(hex)
+    }
+
+    /// 把二進位存檔複製到 JS 端重複使用的 Uint8Array，回傳存檔大小
+    /// 陣列放不下時不寫入，依回傳值重新配置後再呼叫（倒帶等每幀存檔用）
+    #[wasm_bindgen(js_name = "exportSaveStateInto")]
+    pub fn export_save_state_into(&mut self, out: &mut [u8]) -> usize {
+        self.emu.save_state_to_slice(out)
+    }
+
+    /// 讀取 exportSaveStateBytes/exportSaveStateInto 輸出的二進位存檔
+    #[wasm_bindgen(js_name = "importSaveStateBytes")]
+    pub fn import_save_state_bytes(&mut self, data: &[u8]) -> bool {
+        self.emu.load_state(data)
+    }
+
+    /// 存入快速存檔槽（0 ~ 9），記錄目前時間與畫面雜湊
+    #[wasm_bindgen(js_name = "saveSlot")]
+    pub fn save_slot(&mut self, slot: usize) -> bool {
+        self.emu.save_slot(slot, js_sys::Date::now())
+    }
+
+    /// 從快速存檔槽讀檔
+    #[wasm_bindgen(js_name = "loadSlot")]
+    pub fn load_slot(&mut self, slot: usize) -> bool {
+        self.emu.load_slot(slot)
+    }
+
+    /// 清除快速存檔槽
+    #[wasm_bindgen(js_name = "clearSlot")]
+    pub fn clear_slot(&mut self, slot: usize) {
+        self.emu.clear_slot(slot);
+    }
+
+    /// 列出已使用的存檔槽（JSON 陣列：slot、timestamp、frameHash、size）
+    #[wasm_bindgen(js_name = "listSlots")]
+    pub fn list_slots(&self) -> String {
+        self.emu.slots().list_json()
+    }
+
+    /// 倒退約 frames 幀，回傳實際倒退的幀數（沒有倒帶記錄時為 0）
+    /// 保留份數與擷取間隔由設定的 rewindDepth / rewindInterval 決定
+    pub fn rewind(&mut self, frames: u32) -> u32 {
+        self.emu.rewind(frames)
+    }
+
+    /// 目前最多可以倒退的幀數
+    #[wasm_bindgen(js_name = "rewindAvailable")]
+    pub fn rewind_available(&self) -> f64 {
+        self.emu.rewind_available() as f64
+    }
+
+    /// 目前載入的是否為 NSF 音樂檔
+    #[wasm_bindgen(js_name = "isNsf")]
+    pub fn is_nsf(&self) -> bool {
+        self.emu.nsf().is_some()
+    }
+
+    /// NSF 曲目總數（未載入 NSF 時為 0）
+    #[wasm_bindgen(js_name = "nsfTrackCount")]
+    pub fn nsf_track_count(&self) -> u32 {
+        self.emu.nsf().map_or(0, |n| n.header.total_songs as u32)
+    }
+
+    /// 播放 NSF 曲目（從 0 開始）
+    #[wasm_bindgen(js_name = "nsfPlayTrack")]
+    pub fn nsf_play_track(&mut self, track: u8) -> bool {
+        self.emu.nsf_play_track(track)
+    }
+
+    /// 停止 NSF 播放
+    #[wasm_bindgen(js_name = "nsfStop")]
+    pub fn nsf_stop(&mut self) {
+        self.emu.nsf_stop();
+    }
+
+    /// 取得 NSF 資訊（未載入 NSF 時為 null）
+    /// 欄位：title、artist、copyright、trackCount、startingTrack（從 0 開始）、
+    /// currentTrack（停止時為 null）、pal、expansion（擴充音源名稱，以逗號分隔）
+    #[wasm_bindgen(js_name = "getNsfInfo")]
+    pub fn get_nsf_info(&self) -> JsValue {
+        let Some(nsf) = self.emu.nsf() else { return JsValue::NULL };
+        let h = &nsf.header;
+        let obj = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&obj, &JsValue::from_str(key), &value);
+        };
+        set("title", h.title.as_str().into());
+        set("artist", h.artist.as_str().into());
+        set("copyright", h.copyright.as_str().into());
+        set("trackCount", h.total_songs.into());
+        set("startingTrack", (h.starting_song - 1).into());
+        set("currentTrack", nsf.current_track.map_or(JsValue::NULL, JsValue::from));
+        set("pal", (h.region_flags & 0x03 == 0x01).into());
+        set("expansion", h.expansion_names().into());
+        obj.into()
+    }
+
+    /// 載入 FDS BIOS（8KB 的 disksys.rom），載入磁碟映像檔前必須先載入
+    #[wasm_bindgen(js_name = "loadFdsBios")]
+    pub fn load_fds_bios(&mut self, data: &[u8]) -> bool {
+        self.emu.load_fds_bios(data)
+    }
+
+    /// 載入 FDS 磁碟映像檔（.fds，可含 fwNES 標頭），插入第一面並開機
+    /// 尚未載入 BIOS 時回傳 false（loadRom 也接受 .fds）
+    #[wasm_bindgen(js_name = "loadDiskImage")]
+    pub fn load_disk_image(&mut self, data: &[u8]) -> bool {
+        self.emu.load_disk_image(data)
+    }
+
+    /// 列出磁碟面（JSON 陣列，沒有載入磁碟時為空陣列）
+    /// 每筆：{ index, gameName, diskNumber, side: "A" | "B", inserted }
+    #[wasm_bindgen(js_name = "listDiskSides")]
+    pub fn list_disk_sides(&self) -> String {
+        self.emu.disk_drive().map_or_else(|| "[]".to_string(), |drive| drive.sides_json())
+    }
+
+    /// 換面：先退出磁碟，約半秒後插入指定的面（索引對應 listDiskSides）
+    /// BIOS 要先看到磁碟退出才會重新讀取，一般換面請用這個
+    #[wasm_bindgen(js_name = "setDiskSide")]
+    pub fn set_disk_side(&mut self, side: usize) -> bool {
+        self.emu.set_disk_side(side)
+    }
+
+    /// 立即插入指定的磁碟面（不經過退出）
+    #[wasm_bindgen(js_name = "insertDisk")]
+    pub fn insert_disk(&mut self, side: usize) -> bool {
+        self.emu.disk_drive_mut().is_some_and(|drive| drive.insert(side))
+    }
+
+    /// 退出磁碟
+    #[wasm_bindgen(js_name = "ejectDisk")]
+    pub fn eject_disk(&mut self) {
+        if let Some(drive) = self.emu.disk_drive_mut() {
+            drive.eject();
+        }
+    }
+
+    /// 目前插入的磁碟面（未插入時為 undefined）
+    #[wasm_bindgen(js_name = "getInsertedDisk")]
+    pub fn get_inserted_disk(&self) -> Option<usize> {
+        self.emu.disk_drive().and_then(|drive| drive.inserted())
+    }
+
+    /// 設定換面事件回呼：遊戲要求換面時於幀結束後呼叫一次（不帶參數）
+    #[wasm_bindgen(js_name = "setDiskSwapCallback")]
+    pub fn set_disk_swap_callback(&mut self, callback: Option<js_sys::Function>) {
+        self.disk_swap_callback = callback;
+    }
+
+    /// 是否為 VS. System 卡帶（有 DIP 開關與投幣口）
+    #[wasm_bindgen(js_name = "isVsSystem")]
+    pub fn is_vs_system(&self) -> bool {
+        self.emu.is_vs_system()
+    }
+
+    /// 投幣（slot 0 = 投幣口 1，1 = 投幣口 2）；不是 VS. 卡帶時回傳 false
+    #[wasm_bindgen(js_name = "insertCoin")]
+    pub fn insert_coin(&mut self, slot: usize) -> bool {
+        self.emu.insert_coin(slot)
+    }
+
+    /// 設定 DIP 開關（位元 0 = DIP 1 … 位元 7 = DIP 8）
+    #[wasm_bindgen(js_name = "setDipSwitches")]
+    pub fn set_dip_switches(&mut self, value: u8) {
+        self.emu.set_dip_switches(value);
+    }
+
+    /// 按住或放開服務鍵（投入一次服務額度）
+    #[wasm_bindgen(js_name = "setServiceButton")]
+    pub fn set_service_button(&mut self, pressed: bool) {
+        self.emu.set_vs_service(pressed);
+    }
+
+    /// 指定 VS. System 的 PPU 型號（iNES 1.0 的 ROM 無法從標頭得知時使用）
+    #[wasm_bindgen(js_name = "setVsPpu")]
+    pub fn set_vs_ppu(&mut self, vs_ppu: VsPpu) -> bool {
+        self.emu.set_vs_ppu(vs_ppu)
+    }
+
+    /// 新增金手指，自動判斷 Game Genie（6/8 字母）或原始碼（AAAA:VV、AAAA:VV:CC、AAAA?CC:VV）
+    /// 回傳金手指 ID，格式錯誤時回傳 undefined；金手指清單隨存檔保存
+    #[wasm_bindgen(js_name = "addCheat")]
+    pub fn add_cheat_code(&mut self, code: &str) -> Option<u32> {
+        self.emu.cheats.add(code)
+    }
+
+    /// 移除金手指
+    #[wasm_bindgen(js_name = "removeCheat")]
+    pub fn remove_cheat(&mut self, id: u32) -> bool {
+        self.emu.cheats.remove(id)
+    }
+
+    /// 啟用或停用金手指
+    #[wasm_bindgen(js_name = "enableCheat")]
+    pub fn enable_cheat(&mut self, id: u32, enabled: bool) -> bool {
+        self.emu.cheats.set_enabled(id, enabled)
+    }
+
+    /// 移除所有金手指
+    #[wasm_bindgen(js_name = "clearCheats")]
+    pub fn clear_cheats(&mut self) {
+        self.emu.cheats.clear();
+    }
+
+    /// 列出金手指（JSON 陣列）
+    /// 每筆：{ id, code, kind: "gameGenie" | "raw", address, value, compare, enabled }
+    #[wasm_bindgen(js_name = "listCheats")]
+    pub fn list_cheats(&self) -> String {
+        self.emu.cheats.to_json()
+    }
+
+    /// 設定電池 RAM 儲存事件回呼
+    /// 遊戲寫入電池 RAM（或 Bandai 卡帶的 EEPROM）並靜止 sramFlushDelay 幀後，
+    /// 以 Uint8Array 呼叫一次
+    #[wasm_bindgen(js_name = "setSramCallback")]
+    pub fn set_sram_callback(&mut self, callback: Option<js_sys::Function>) {
+        self.sram_callback = callback;
+    }
+
+    /// 立即取出尚未儲存的電池 RAM（例如頁面關閉前），沒有變更或卡帶沒有電池時回傳空陣列
+    #[wasm_bindgen(js_name = "flushSram")]
+    pub fn flush_sram(&mut self) -> Vec<u8> {
+        self.emu.flush_sram_now().unwrap_or_default()
+    }
+
+    /// 載入先前儲存的電池 RAM（或 EEPROM），卡帶沒有電池或大小不符時回傳 false
+    #[wasm_bindgen(js_name = "loadSram")]
+    pub fn load_sram(&mut self, data: &[u8]) -> bool {
+        self.emu.load_sram(data)
+    }
+
+    /// 卡帶是否有 PRG RAM；沒有 PRG RAM 或沒有電池（getRomInfo 的 battery）時
+    /// 前端可以隱藏 SRAM 存檔按鈕
+    #[wasm_bindgen(js_name = "hasPrgRam")]
+    pub fn has_prg_ram(&self) -> bool {
+        self.emu.cartridge.has_prg_ram()
+    }
+
+    /// 排程輸入：在第 frame 幀開始時把控制器 port 的按鈕狀態設為 buttons
+    /// （位元 0-7 = A, B, Select, Start, Up, Down, Left, Right，維持到下一筆排程）
+    #[wasm_bindgen(js_name = "queueInput")]
+    pub fn queue_input(&mut self, frame: f64, port: u8, buttons: u8) {
+        self.emu.queue_input(frame as u64, port, buttons);
+    }
+
+    /// 批次排程輸入：傳入 [frame, port, buttons, frame, port, buttons, ...]
+    #[wasm_bindgen(js_name = "queueInputBatch")]
+    pub fn queue_input_batch(&mut self, entries: &[u32]) {
+        for e in entries.chunks_exact(3) {
+            self.emu.queue_input(e[0] as u64, e[1] as u8, e[2] as u8);
+        }
+    }
+
+    /// 清除所有尚未套用的排程輸入
+    #[wasm_bindgen(js_name = "clearInputQueue")]
+    pub fn clear_input_queue(&mut self) {
+        self.emu.clear_input_queue();
+    }
+
+    /// 取得記憶體用量（JSON，單位為位元組）
+    /// 欄位：frameBuffer、audioBuffer、prgRom、chr、ram、saveSlots、rewind、movie、trace、total
+    /// 每個 NesWasm 實例各自持有所有狀態（沒有全域變數），可在同一頁面建立多個實例
+    #[wasm_bindgen(js_name = "getMemoryUsage")]
+    pub fn get_memory_usage(&self) -> String {
+        self.emu.memory_usage().to_json()
+    }
+
+    /// 取得指令追蹤記錄（nestest.log 格式，每行一條指令，保留最近 10000 行；
+    /// DMC DMA 暫停過 CPU 的行尾另有「STALL:週期數」）
+    /// 以 NesDebugger.setTraceEnabled 開啟；與 takeTrace 不同，讀取後不清空
+    #[wasm_bindgen(js_name = "getTraceLog")]
+    pub fn get_trace_log(&self) -> String {
+        self.emu.debugger.trace_log()
+    }
+
+    /// 取得狀態快照（JSON），供除錯 HUD 每幀呼叫一次
+    /// 欄位：pc、a、x、y、sp、p、scanline、dot、frame、cpuCycles、instructions、
+    /// prgBanks（4 個 8KB 視窗）、chrBanks（8 個 1KB 視窗）、audioSamples、paused
+    #[wasm_bindgen(js_name = "getStatus")]
+    pub fn get_status(&self) -> String {
+        self.emu.status().to_json()
+    }
+
+    /// 取得目前幀數（排程輸入以此為基準）
+    #[wasm_bindgen(js_name = "getFrameCount")]
+    pub fn get_frame_count(&self) -> f64 {
+        self.emu.frame_count() as f64
+    }
+
+    /// 開始錄製輸入影片
+    /// fromPowerOn 為 true 時先重置再錄製，否則以目前狀態（存檔）為起點
+    #[wasm_bindgen(js_name = "startMovieRecording")]
+    pub fn start_movie_recording(&mut self, from_power_on: bool) {
+        self.emu.movie_start_recording(from_power_on);
+    }
+
+    /// 從起點重播目前的影片
+    #[wasm_bindgen(js_name = "replayMovie")]
+    pub fn replay_movie(&mut self) -> bool {
+        self.emu.movie_start_playback()
+    }
+
+    /// 停止錄製或播放
+    #[wasm_bindgen(js_name = "stopMovie")]
+    pub fn stop_movie(&mut self) {
+        self.emu.movie_stop();
+    }
+
+    /// 停止錄製並匯出影片檔（起始存檔 + 每幀輸入），沒有影片時回傳空陣列
+    #[wasm_bindgen(js_name = "stopRecording")]
+    pub fn stop_recording(&mut self) -> Vec<u8> {
+        self.emu.movie_stop();
+        self.emu.movie().map_or_else(Vec::new, |m| m.to_bytes())
+    }
+
+    /// 讀入 stopRecording 匯出的影片檔並從起點播放，每幀輸入取代控制器 1、2
+    #[wasm_bindgen(js_name = "playMovie")]
+    pub fn play_movie(&mut self, data: &[u8]) -> bool {
+        self.emu.movie_play(data)
+    }
+
+    /// 在目前的幀截斷影片並從這裡重新錄製（重錄次數 +1）
+    #[wasm_bindgen(js_name = "truncateMovie")]
+    pub fn truncate_movie(&mut self) -> bool {
+        self.emu.movie_truncate()
+    }
+
+    /// 取得影片長度（幀數）
+    #[wasm_bindgen(js_name = "getMovieLength")]
+    pub fn get_movie_length(&self) -> usize {
+        self.emu.movie().map_or(0, |m| m.len())
+    }
+
+    /// 取得影片重錄次數
+    #[wasm_bindgen(js_name = "getRerecordCount")]
+    pub fn get_rerecord_count(&self) -> u32 {
+        self.emu.movie().map_or(0, |m| m.rerecord_count)
+    }
+
+    /// 效能基準測試：盡速執行 n 幀，回傳 JSON
+    /// （frames、totalMs、fps、cpuMs、ppuMs、apuMs、mapperMs、otherMs），結束後還原遊戲狀態
+    pub fn benchmark(&mut self, frames: u32) -> String {
+        self.emu.benchmark(frames, performance_now()).to_json()
+    }
+
+    /// 開關每幀耗時量測（以 performance.now() 計時，會明顯拖慢執行）
+    /// 關閉時仍會累計週期數與指令數；需以 profiling feature 建置
+    #[cfg(feature = "profiling")]
+    #[wasm_bindgen(js_name = "setProfiling")]
+    pub fn set_profiling(&mut self, timing: bool) {
+        let clock: Option<Box<dyn FnMut() -> f64>> = if timing { Some(Box::new(performance_now())) } else { None };
+        self.emu.set_profiler_clock(clock);
+    }
+
+    /// 取得上一幀的效能計數 JSON（frame、cpuCycles、dmaCycles、instructions、
+    /// ppuDots、audioSamples、totalMs 與各子系統耗時）
+    #[cfg(feature = "profiling")]
+    #[wasm_bindgen(js_name = "getFrameProfile")]
+    pub fn get_frame_profile(&self) -> String {
+        self.emu.last_frame_profile().to_json()
+    }
+
+    /// 取得 WASM 記憶體（供 JavaScript 直接存取畫面/音頻緩衝區）
+    #[wasm_bindgen(js_name = "getWasmMemory")]
+    pub fn get_wasm_memory(&self) -> JsValue {
+        wasm_bindgen::memory()
+    }
+
+    /// 取得除錯介面（除錯功能集中在 NesDebugger，呼叫時傳入本實例）
+    pub fn debugger(&self) -> NesDebugger {
+        NesDebugger {}
+    }
+}
+
+// ============================================================
+// 除錯介面 - 單步執行、中斷點、記憶體、反組譯、追蹤、暫存器
+// ============================================================
+// 除錯狀態保存在模擬器核心（Emulator::debugger），NesDebugger 本身
+// 不持有資料，每個方法都傳入要操作的 NesWasm 實例：
+//   const dbg = nes.debugger();
+//   dbg.addBreakpoint(nes, 0xC000);
+//   nes.frame();                     // 命中中斷點時提前返回
+//   dbg.getBreakAddress(nes);        // 0xC000
+//   dbg.stepInstruction(nes);
+// ============================================================
+
+/// NES 除錯介面
+#[wasm_bindgen]
+pub struct NesDebugger {}
+
+#[wasm_bindgen]
+impl NesDebugger {
+    /// 執行一條指令，回傳停止的原因（StopReason）
+    #[wasm_bindgen(js_name = "stepInstruction")]
+    pub fn step_instruction(&self, nes: &mut NesWasm) -> StopReason {
+        nes.emu.step_instruction()
+    }
+
+    /// 執行到目前掃描線結束，回傳停止的原因
+    #[wasm_bindgen(js_name = "stepScanline")]
+    pub fn step_scanline(&self, nes: &mut NesWasm) -> StopReason {
+        nes.emu.step_scanline()
+    }
+
+    /// 執行到幀結束，回傳停止的原因（中途命中中斷點或監看點時提前停止）
+    #[wasm_bindgen(js_name = "stepFrame")]
+    pub fn step_frame(&self, nes: &mut NesWasm) -> StopReason {
+        let reason = nes.emu.step_frame();
+        nes.dispatch_events();
+        reason
+    }
+
+    /// 新增監看點：write 為 true 時 CPU 寫入該位址後停止，否則讀取後停止
+    /// 停在觸發存取的那條指令執行完之後
+    #[wasm_bindgen(js_name = "addWatchpoint")]
+    pub fn add_watchpoint(&self, nes: &mut NesWasm, addr: u16, write: bool) {
+        nes.emu.debugger.add_watchpoint(addr, write);
+    }
+
+    /// 移除監看點
+    #[wasm_bindgen(js_name = "removeWatchpoint")]
+    pub fn remove_watchpoint(&self, nes: &mut NesWasm, addr: u16, write: bool) {
+        nes.emu.debugger.remove_watchpoint(addr, write);
+    }
+
+    /// 清除所有監看點
+    #[wasm_bindgen(js_name = "clearWatchpoints")]
+    pub fn clear_watchpoints(&self, nes: &mut NesWasm) {
+        nes.emu.debugger.clear_watchpoints();
+    }
+
+    /// 目前暫停的原因（未暫停時為 undefined）
+    #[wasm_bindgen(js_name = "getStopReason")]
+    pub fn get_stop_reason(&self, nes: &NesWasm) -> Option<StopReason> {
+        nes.emu.debugger.stop_reason()
+    }
+
+    /// 觸發暫停的監看位址（不是因監看點暫停時為 undefined）
+    #[wasm_bindgen(js_name = "getWatchAddress")]
+    pub fn get_watch_address(&self, nes: &NesWasm) -> Option<u16> {
+        nes.emu.debugger.watch_hit()
+    }
+
+    /// 新增中斷點（CPU 執行到該位址前停止）
+    #[wasm_bindgen(js_name = "addBreakpoint")]
+    pub fn add_breakpoint(&self, nes: &mut NesWasm, addr: u16) {
+        nes.emu.debugger.add_breakpoint(addr);
+    }
+
+    /// 移除中斷點
+    #[wasm_bindgen(js_name = "removeBreakpoint")]
+    pub fn remove_breakpoint(&self, nes: &mut NesWasm, addr: u16) {
+        nes.emu.debugger.remove_breakpoint(addr);
+    }
+
+    /// 清除所有中斷點
+    #[wasm_bindgen(js_name = "clearBreakpoints")]
+    pub fn clear_breakpoints(&self, nes: &mut NesWasm) {
+        nes.emu.debugger.clear_breakpoints();
+    }
+
+    /// 列出所有中斷點
+    #[wasm_bindgen(js_name = "getBreakpoints")]
+    pub fn get_breakpoints(&self, nes: &NesWasm) -> Vec<u16> {
+        nes.emu.debugger.breakpoints()
+    }
+
+    /// 目前停住的中斷點位址（未暫停時為 undefined）
+    #[wasm_bindgen(js_name = "getBreakAddress")]
+    pub fn get_break_address(&self, nes: &NesWasm) -> Option<u16> {
+        nes.emu.debugger.break_hit()
+    }
+
+    /// 四個名稱表的 RGBA 影像（512x480），以外框標出目前的捲軸視窗
+    #[wasm_bindgen(js_name = "renderNametables")]
+    pub fn render_nametables(&self, nes: &NesWasm) -> Vec<u8> {
+        let mut out = Vec::new();
+        nes.emu.ppu.render_nametables(&nes.emu.cartridge, &mut out);
+        out
+    }
+
+    /// 兩個圖案表的 RGBA 影像（256x128），paletteIndex 為 0-3 背景、4-7 精靈調色盤
+    #[wasm_bindgen(js_name = "renderPatternTables")]
+    pub fn render_pattern_tables(&self, nes: &NesWasm, palette_index: u8) -> Vec<u8> {
+        let mut out = Vec::new();
+        nes.emu.ppu.render_pattern_tables(&nes.emu.cartridge, palette_index, &mut out);
+        out
+    }
+
+    /// 調色盤 RAM（32 個 NES 色碼）
+    #[wasm_bindgen(js_name = "getPaletteRam")]
+    pub fn get_palette_ram(&self, nes: &NesWasm) -> Vec<u8> {
+        nes.emu.ppu.palette_ram().to_vec()
+    }
+
+    /// OAM 精靈列表（JSON 陣列，64 筆）
+    /// 每筆：{ index, x, y, tile, attributes, palette, behindBackground, flipH, flipV }
+    #[wasm_bindgen(js_name = "getOamEntries")]
+    pub fn get_oam_entries(&self, nes: &NesWasm) -> String {
+        let items: Vec<String> = nes.emu.ppu.oam_entries().iter().map(|e| e.to_json()).collect();
+        format!("[{}]", items.join(","))
+    }
+
+    /// 讀取記憶體（不觸發暫存器副作用，$2000-$401F 讀為 0）
+    #[wasm_bindgen(js_name = "readMemory")]
+    pub fn read_memory(&self, nes: &NesWasm, addr: u16, len: u32) -> Vec<u8> {
+        (0..len.min(0x10000)).map(|i| nes.emu.peek(addr.wrapping_add(i as u16))).collect()
+    }
+
+    /// 寫入記憶體（RAM/PRG RAM 直接修改，其他位址視同 CPU 寫入）
+    #[wasm_bindgen(js_name = "writeMemory")]
+    pub fn write_memory(&self, nes: &mut NesWasm, addr: u16, data: &[u8]) {
+        for (i, &value) in data.iter().enumerate() {
+            nes.emu.poke(addr.wrapping_add(i as u16), value);
+        }
+    }
+
+    /// 從 addr 開始反組譯 count 條指令
+    pub fn disassemble(&self, nes: &NesWasm, addr: u16, count: u32) -> Vec<String> {
+        nes.emu.disassemble(addr, count as usize)
+    }
+
+    /// 開啟或關閉指令追蹤
+    #[wasm_bindgen(js_name = "setTraceEnabled")]
+    pub fn set_trace_enabled(&self, nes: &mut NesWasm, enabled: bool) {
+        nes.emu.debugger.set_trace_enabled(enabled);
+    }
+
+    /// 取出追蹤記錄（每行一條指令）並清空
+    #[wasm_bindgen(js_name = "takeTrace")]
+    pub fn take_trace(&self, nes: &mut NesWasm) -> String {
+        nes.emu.debugger.take_trace()
+    }
+
+    /// 取得暫存器：{ a, x, y, sp, pc, p, scanline, ppuCycle, instructions }
+    #[wasm_bindgen(js_name = "getRegisters")]
+    pub fn get_registers(&self, nes: &NesWasm) -> JsValue {
+        let emu = &nes.emu;
+        let obj = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&obj, &JsValue::from_str(key), &value);
+        };
+        set("a", emu.cpu.a.into());
+        set("x", emu.cpu.x.into());
+        set("y", emu.cpu.y.into());
+        set("sp", emu.cpu.sp.into());
+        set("pc", emu.cpu.pc.into());
+        set("p", emu.cpu.status.into());
+        set("scanline", emu.ppu.scanline.into());
+        set("ppuCycle", emu.ppu.cycle.into());
+        set("instructions", (emu.instruction_count() as f64).into());
+        obj.into()
+    }
+
+    /// 設定暫存器（a、x、y、sp、pc、p），名稱無效時回傳 false
+    #[wasm_bindgen(js_name = "setRegister")]
+    pub fn set_register(&self, nes: &mut NesWasm, name: &str, value: u16) -> bool {
+        let cpu = &mut nes.emu.cpu;
+        match name {
+            "a" => cpu.a = value as u8,
+            "x" => cpu.x = value as u8,
+            "y" => cpu.y = value as u8,
+            "sp" => cpu.sp = value as u8,
+            "p" => cpu.status = value as u8 | 0x20,
+            "pc" => cpu.pc = value,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl NesWasm {
+    /// 每幀結束後通知前端的事件
+    fn dispatch_events(&mut self) {
+        self.dispatch_sram_flush();
+        self.dispatch_disk_swap();
+    }
+
+    /// 遊戲要求換面時通知前端
+    fn dispatch_disk_swap(&mut self) {
+        if !self.emu.disk_drive_mut().is_some_and(|drive| drive.take_swap_request()) { return; }
+        if let Some(callback) = &self.disk_swap_callback {
+            let _ = callback.call0(&JsValue::NULL);
+        }
+    }
+
+    /// 若電池 RAM 已穩定且有設定回呼，則通知前端儲存
+    fn dispatch_sram_flush(&mut self) {
+        let Some(callback) = &self.sram_callback else { return };
+        if let Some(data) = self.emu.take_sram_flush() {
+            let array = js_sys::Uint8Array::from(&data[..]);
+            let _ = callback.call1(&JsValue::NULL, &array);
+        }
+    }
+}
+
+/// 以 performance.now() 計時（毫秒），不支援時退回 Date.now()
+fn performance_now() -> impl FnMut() -> f64 {
+    let global = js_sys::global();
+    let perf = js_sys::Reflect::get(&global, &JsValue::from_str("performance"))
+        .unwrap_or(JsValue::UNDEFINED);
+    let now_fn = js_sys::Reflect::get(&perf, &JsValue::from_str("now"))
+        .ok()
+        .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+    move || match &now_fn {
+        Some(f) => f.call0(&perf).ok().and_then(|v| v.as_f64()).unwrap_or(0.0),
+        None => js_sys::Date::now(),
+    }
+}
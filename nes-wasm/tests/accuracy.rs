@@ -0,0 +1,69 @@
+// ============================================================
+// 準確度報告測試 - 清單解析與目錄執行（需啟用 native feature）
+// ============================================================
+
+#![cfg(feature = "native")]
+
+mod common;
+
+use nes_wasm::accuracy::{self, AccuracyOutcome, DEFAULT_MAX_FRAMES};
+
+/// 寫入 $6000 簽章與結果碼後無限迴圈的 NROM ROM
+fn protocol_rom(code: u8) -> Vec<u8> {
+    let mut prg = vec![0xEAu8; 0x4000];
+    let mut asm = Vec::new();
+    for (addr, value) in [(0x6001u16, 0xDE), (0x6002, 0xB0), (0x6003, 0x61), (0x6004, b'x'), (0x6000, code)] {
+        asm.extend_from_slice(&[0xA9, value, 0x8D, addr as u8, (addr >> 8) as u8]);
+    }
+    let end = 0xC000 + asm.len() as u16;
+    asm.extend_from_slice(&[0x4C, end as u8, (end >> 8) as u8]);
+    prg[..asm.len()].copy_from_slice(&asm);
+    prg[0x3FFA..].copy_from_slice(&[0x00, 0xC0, 0x00, 0xC0, 0x00, 0xC0]);
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend_from_slice(&prg);
+    rom.extend(std::iter::repeat_n(0u8, 0x2000));
+    rom
+}
+
+#[test]
+fn manifest_lines_set_frames_and_hash() {
+    let tests = accuracy::parse_manifest(
+        "# blargg\ncpu/official.nes\nppu/sprite_hit.nes 120 0x1234abcd  # 畫面判定\n",
+    )
+    .unwrap();
+    assert_eq!(tests.len(), 2);
+    assert_eq!(tests[0].frames, DEFAULT_MAX_FRAMES);
+    assert_eq!(tests[0].expected_hash, None);
+    assert_eq!((tests[1].frames, tests[1].expected_hash), (120, Some(0x1234_ABCD)));
+    assert!(accuracy::parse_manifest("a.nes many").unwrap_err().starts_with("第 1 行"));
+}
+
+#[test]
+fn directory_report_covers_protocol_and_hash_tests() {
+    let dir = std::env::temp_dir().join(format!("nes-accuracy-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("pass.nes"), protocol_rom(0)).unwrap();
+    std::fs::write(dir.join("fail.nes"), protocol_rom(3)).unwrap();
+    std::fs::write(dir.join("screen.nes"), common::build_test_rom()).unwrap();
+
+    let mut emu = common::boot();
+    emu.run_frames(5);
+    std::fs::write(
+        dir.join(accuracy::MANIFEST_NAME),
+        format!("pass.nes 30\nfail.nes 30\nscreen.nes 5 {:08x}\nmissing.nes\n", emu.frame_hash()),
+    )
+    .unwrap();
+
+    let report = accuracy::run_directory(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let outcomes: Vec<_> = report.results.iter().map(|r| r.outcome.clone()).collect();
+    assert_eq!(outcomes[..3], [AccuracyOutcome::Passed, AccuracyOutcome::Failed(3), AccuracyOutcome::Passed]);
+    assert!(matches!(outcomes[3], AccuracyOutcome::Error(_)));
+    assert_eq!(report.passed(), 2);
+
+    let json = report.to_json();
+    assert!(json.contains("\"total\":4,\"passed\":2"), "{json}");
+    assert!(json.contains("{\"rom\":\"fail.nes\",\"status\":\"fail\""), "{json}");
+    assert!(json.contains("\"code\":3,\"message\":\"x\""), "{json}");
+}
@@ -0,0 +1,137 @@
+// ============================================================
+// APU 測試 - 幀計數器與長度計數器的時序、三角波的靜音模式、DMC 讀取時序
+// ============================================================
+
+use nes_wasm::apu::{Apu, AudioChannel};
+
+fn clock(apu: &mut Apu, cycles: u32) {
+    for _ in 0..cycles {
+        apu.clock();
+    }
+}
+
+#[test]
+fn frame_irq_follows_delayed_4017_write_for_three_cycles() {
+    // 寫在 APU 週期上：3 個週期後重置，之後第 29828 個週期設定 IRQ 旗標
+    let mut apu = Apu::new();
+    apu.cpu_write(0x4017, 0x00);
+    clock(&mut apu, 3 + 29827);
+    assert!(!apu.check_irq());
+    for _ in 0..3 {
+        apu.clock();
+        assert!(apu.check_irq());
+        assert_eq!(apu.cpu_read() & 0x40, 0x40);
+    }
+    apu.clock();
+    assert!(!apu.check_irq());
+
+    // 寫在兩個 APU 週期之間多等一個週期
+    let mut apu = Apu::new();
+    apu.clock();
+    apu.cpu_write(0x4017, 0x00);
+    clock(&mut apu, 4 + 29827);
+    assert!(!apu.check_irq());
+    apu.clock();
+    assert!(apu.check_irq());
+}
+
+#[test]
+fn length_reload_is_ignored_when_clocked_in_the_same_cycle() {
+    let mut apu = Apu::new();
+    apu.cpu_write(0x4015, 0x01);
+    apu.cpu_write(0x4003, 0x18); // 長度 2
+    clock(&mut apu, 2);
+
+    // 5 步模式的寫入生效時立即時鐘長度計數器，同一週期載入的 30 被忽略
+    apu.cpu_write(0x4017, 0x80);
+    clock(&mut apu, 2);
+    apu.cpu_write(0x4003, 0xF8);
+    apu.clock();
+    assert_eq!(apu.cpu_read() & 0x01, 0x01);
+
+    // 剩下的 1 在下一次時鐘歸零
+    apu.cpu_write(0x4017, 0x80);
+    clock(&mut apu, 4);
+    assert_eq!(apu.cpu_read() & 0x01, 0x00);
+}
+
+/// 以指定週期播放三角波（線性計數器控制位元持續重新載入，不會自己停）
+fn play_triangle(apu: &mut Apu, period: u16) {
+    apu.cpu_write(0x4015, 0x04);
+    apu.cpu_write(0x4008, 0xFF);
+    apu.cpu_write(0x400A, period as u8);
+    apu.cpu_write(0x400B, (period >> 8) as u8);
+    // 5 步模式的寫入生效時立即時鐘，載入線性計數器
+    apu.cpu_write(0x4017, 0x80);
+    clock(apu, 4);
+}
+
+fn triangle_level(apu: &Apu) -> f32 {
+    apu.channel_levels()[AudioChannel::Triangle as usize]
+}
+
+#[test]
+fn ultrasonic_triangle_keeps_its_dac_output_in_accurate_mode() {
+    let mut apu = Apu::new();
+    play_triangle(&mut apu, 0);
+    assert_eq!(triangle_level(&apu), 0.0);
+
+    // 週期 0 時序列器每個週期前進一步，32 個週期走完整個波形
+    apu.set_accurate_triangle(true);
+    let sum: f32 = (0..32).map(|_| { apu.clock(); triangle_level(&apu) }).sum();
+    assert!((sum - 16.0).abs() < 1e-4, "{sum}");
+}
+
+#[test]
+fn halted_triangle_ramps_down_or_holds_by_mode() {
+    let mut apu = Apu::new();
+    play_triangle(&mut apu, 0x40);
+    while triangle_level(&apu) < 0.5 {
+        apu.clock();
+    }
+    let level = triangle_level(&apu);
+    apu.cpu_write(0x4015, 0x00);
+    apu.clock();
+    assert_eq!(triangle_level(&apu), 0.0);
+
+    // 減少爆音：從停止時的準位逐步降到 0
+    apu.set_reduce_popping(true);
+    assert_eq!(triangle_level(&apu), level);
+    clock(&mut apu, 64);
+    assert!(triangle_level(&apu) < level);
+    clock(&mut apu, 15 * 64);
+    assert_eq!(triangle_level(&apu), 0.0);
+
+    // 準確模式：序列器停在原位，DAC 保持最後的輸出
+    apu.set_accurate_triangle(true);
+    assert_eq!(triangle_level(&apu), level);
+}
+
+#[test]
+fn dmc_fetches_once_per_byte_period_and_raises_irq_on_the_last_byte() {
+    let mut apu = Apu::new();
+    apu.cpu_write(0x4010, 0x8F); // IRQ、最快速率（每位元 54 週期）
+    apu.cpu_write(0x4013, 0x01); // 17 位元組
+    apu.cpu_write(0x4015, 0x10);
+    // 緩衝區是空的，啟用後 2 個週期才開始讀取
+    clock(&mut apu, 2);
+    assert!(apu.dmc_read_request.is_none());
+
+    let mut fetches = Vec::new();
+    for cycle in 0..20000 {
+        apu.clock();
+        if let Some(addr) = apu.dmc_read_request {
+            fetches.push((cycle, addr));
+            apu.dmc_provide_sample(0x55);
+            assert_eq!(apu.check_irq(), fetches.len() == 17);
+        }
+    }
+    assert_eq!(fetches.len(), 17);
+    assert_eq!(fetches[0], (0, 0xC000));
+    assert_eq!(fetches[16].1, 0xC010);
+    // 緩衝區只有一個位元組：輸出單元每取走一個（8 位元）才再讀一次
+    for pair in fetches[1..].windows(2) {
+        assert_eq!(pair[1].0 - pair[0].0, 8 * 54);
+    }
+    assert_eq!(apu.cpu_read() & 0x90, 0x80);
+}
@@ -0,0 +1,34 @@
+// ============================================================
+// 音訊輸出測試 - 環形緩衝區與頻寬受限的階躍合成
+// ============================================================
+
+use nes_wasm::audio::{AudioRing, BlipSynth, BLIP_WIDTH};
+
+#[test]
+fn ring_overwrites_oldest_samples_and_reads_across_the_wrap() {
+    let mut ring = AudioRing::new(4);
+    for i in 0..6 {
+        ring.push(i as f32);
+    }
+    assert_eq!((ring.len(), ring.overflowed()), (4, 2));
+
+    let mut out = [0.0; 3];
+    assert_eq!(ring.read_into(&mut out), 3);
+    assert_eq!(out, [2.0, 3.0, 4.0]);
+    ring.push(6.0);
+    ring.push(7.0);
+    assert_eq!(ring.make_contiguous(), &[5.0, 6.0, 7.0]);
+}
+
+#[test]
+fn blip_step_is_delayed_smoothed_and_settles_to_its_height() {
+    let mut synth = BlipSynth::default();
+    synth.add(1.0, 0.25);
+    let out: Vec<f32> = (0..BLIP_WIDTH * 2).map(|_| synth.next_sample()).collect();
+    // 步階前後各半個核心：之前幾乎為 0，之後穩定在步階高度
+    assert!(out[..BLIP_WIDTH / 2 - 2].iter().all(|s| s.abs() < 0.05));
+    assert!(out[BLIP_WIDTH / 2 + 2..].iter().all(|s| (s - 1.0).abs() < 0.05));
+    assert!((out[BLIP_WIDTH * 2 - 1] - 1.0).abs() < 1e-6);
+    // 邊緣落在兩個取樣之間，中間的取樣是過渡值而不是直接跳到 1
+    assert!(out.iter().any(|&s| s > 0.2 && s < 0.8));
+}
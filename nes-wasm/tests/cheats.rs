@@ -0,0 +1,60 @@
+// ============================================================
+// 金手指測試 - 解碼與讀取替換
+// ============================================================
+
+mod common;
+
+use common::boot;
+use nes_wasm::cheats::{decode_game_genie, decode_raw};
+use nes_wasm::controller::BTN_A;
+
+#[test]
+fn decodes_game_genie_and_raw_codes() {
+    // Super Mario Bros. 無限生命
+    assert_eq!(decode_game_genie("SXIOPO"), Some((0x91D9, 0xAD, None)));
+    assert_eq!(decode_game_genie("SXIOPOAA").map(|c| c.0), Some(0x91D9));
+    assert_eq!(decode_game_genie("SXIOP"), None);
+    assert_eq!(decode_raw("0075:09"), Some((0x0075, 0x09, None)));
+    assert_eq!(decode_raw("C050?21:16"), Some((0xC050, 0x16, Some(0x21))));
+    assert_eq!(decode_raw("C050:16:21"), Some((0xC050, 0x16, Some(0x21))));
+    assert_eq!(decode_raw("C050?21:16:21"), None);
+    assert_eq!(decode_raw("C050:"), None);
+}
+
+#[test]
+fn cheat_replaces_cpu_reads_until_disabled() {
+    let mut pressed = boot();
+    pressed.set_button(0, BTN_A, true);
+    pressed.run_frames(10);
+
+    let mut emu = boot();
+    emu.run_frames(8);
+    let idle = emu.frame_hash();
+    // 背景色表第 0 項改成「按下」的顏色
+    let id = emu.cheats.add("C050?21:16").unwrap();
+    emu.run_frames(2);
+    assert_eq!(emu.frame_hash(), pressed.frame_hash());
+
+    assert!(emu.cheats.set_enabled(id, false));
+    emu.run_frames(2);
+    assert_eq!(emu.frame_hash(), idle);
+    assert!(emu.cheats.remove(id));
+    assert!(emu.cheats.list().is_empty());
+}
+
+#[test]
+fn cheats_travel_with_save_states() {
+    let mut emu = boot();
+    let infinite = emu.cheats.add("SXIOPO").unwrap();
+    let palette = emu.cheats.add("C050:16:21").unwrap();
+    assert!(emu.cheats.set_enabled(infinite, false));
+    let state = emu.export_save_state_bytes();
+
+    emu.cheats.clear();
+    emu.cheats.add("0075:09");
+    assert!(emu.load_state(&state));
+    let cheats = emu.cheats.list();
+    assert_eq!(cheats.iter().map(|c| (c.id, c.enabled)).collect::<Vec<_>>(), [(infinite, false), (palette, true)]);
+    assert_eq!(cheats[1].compare, Some(0x21));
+    assert_eq!(emu.cheats.add("0075:09"), Some(palette + 1), "ID 接續存檔當時的編號");
+}
@@ -0,0 +1,148 @@
+// ============================================================
+// 整合測試共用：記憶體中組出的最小測試 ROM
+// ============================================================
+// - 重置後等待 VBlank，開啟 NMI 與背景顯示
+// - NMI 中讀取控制器 1 的 A 鍵，依狀態把背景色寫入 $3F00
+// ============================================================
+
+#![allow(dead_code)]
+
+use nes_wasm::emulator::Emulator;
+use nes_wasm::mappers::MapperTrait;
+
+/// 背景色（A 未按下 / 按下）
+pub const COLORS: [u8; 2] = [0x21, 0x16];
+
+/// 組出 16KB PRG + 8KB CHR 的 NROM ROM
+pub fn build_test_rom() -> Vec<u8> {
+    let mut prg = vec![0xEAu8; 0x4000];
+    let reset: &[u8] = &[
+        0x78,             // SEI
+        0xD8,             // CLD
+        0xA2, 0xFF,       // LDX #$FF
+        0x9A,             // TXS
+        0xAD, 0x02, 0x20, // LDA $2002
+        0x10, 0xFB,       // BPL -5
+        0xAD, 0x02, 0x20, // LDA $2002
+        0x10, 0xFB,       // BPL -5
+        0xA9, 0x80,       // LDA #$80
+        0x8D, 0x00, 0x20, // STA $2000
+        0xA9, 0x0A,       // LDA #$0A
+        0x8D, 0x01, 0x20, // STA $2001
+        0x4C, 0x19, 0xC0, // JMP $C019
+    ];
+    let nmi: &[u8] = &[
+        0xA9, 0x01,       // LDA #$01
+        0x8D, 0x16, 0x40, // STA $4016
+        0xA9, 0x00,       // LDA #$00
+        0x8D, 0x16, 0x40, // STA $4016
+        0xAD, 0x16, 0x40, // LDA $4016
+        0x29, 0x01,       // AND #$01
+        0xAA,             // TAX
+        0xA9, 0x3F,       // LDA #$3F
+        0x8D, 0x06, 0x20, // STA $2006
+        0xA9, 0x00,       // LDA #$00
+        0x8D, 0x06, 0x20, // STA $2006
+        0xBD, 0x50, 0xC0, // LDA $C050,X
+        0x8D, 0x07, 0x20, // STA $2007
+        0xA9, 0x00,       // LDA #$00
+        0x8D, 0x06, 0x20, // STA $2006
+        0x8D, 0x06, 0x20, // STA $2006
+        0x8D, 0x05, 0x20, // STA $2005
+        0x8D, 0x05, 0x20, // STA $2005
+        0x40,             // RTI
+    ];
+    prg[..reset.len()].copy_from_slice(reset);
+    prg[0x20..0x20 + nmi.len()].copy_from_slice(nmi);
+    prg[0x50..0x52].copy_from_slice(&COLORS);
+    // 向量：NMI=$C020, RESET=$C000, IRQ=$C04E（RTI）
+    prg[0x3FFA..].copy_from_slice(&[0x20, 0xC0, 0x00, 0xC0, 0x4E, 0xC0]);
+
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend_from_slice(&prg);
+    rom.extend(std::iter::repeat_n(0u8, 0x2000));
+    rom
+}
+
+/// 建立已載入測試 ROM 的模擬器
+pub fn boot() -> Emulator {
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&build_test_rom()));
+    emu
+}
+
+// ===== Bandai FCG 的 24C02 EEPROM（$800D 位元 5 = SCL、位元 6 = SDA）=====
+
+/// 設定 SCL/SDA 兩條線
+fn i2c_lines(mapper: &mut impl MapperTrait, scl: u8, sda: u8) {
+    mapper.cpu_write(0x800D, scl << 5 | sda << 6);
+}
+
+/// START：SCL 高電位時 SDA 由高變低
+pub fn i2c_start(mapper: &mut impl MapperTrait) {
+    i2c_lines(mapper, 0, 1);
+    i2c_lines(mapper, 1, 1);
+    i2c_lines(mapper, 1, 0);
+    i2c_lines(mapper, 0, 0);
+}
+
+/// STOP：SCL 高電位時 SDA 由低變高
+pub fn i2c_stop(mapper: &mut impl MapperTrait) {
+    i2c_lines(mapper, 0, 0);
+    i2c_lines(mapper, 1, 0);
+    i2c_lines(mapper, 1, 1);
+}
+
+/// 送出一個位元組，回傳 EEPROM 是否回 ACK
+pub fn i2c_send(mapper: &mut impl MapperTrait, byte: u8) -> bool {
+    for i in (0..8).rev() {
+        let bit = byte >> i & 1;
+        i2c_lines(mapper, 0, bit);
+        i2c_lines(mapper, 1, bit);
+        i2c_lines(mapper, 0, bit);
+    }
+    i2c_lines(mapper, 0, 1);
+    i2c_lines(mapper, 1, 1);
+    let ack = mapper.read_register(0x6000) == Some(0);
+    i2c_lines(mapper, 0, 1);
+    ack
+}
+
+/// 讀取一個位元組，ack 為 false 時結束讀取
+pub fn i2c_receive(mapper: &mut impl MapperTrait, ack: bool) -> u8 {
+    let mut byte = 0;
+    for _ in 0..8 {
+        i2c_lines(mapper, 0, 1);
+        i2c_lines(mapper, 1, 1);
+        byte = byte << 1 | mapper.read_register(0x6000).unwrap_or(0) >> 4 & 1;
+        i2c_lines(mapper, 0, 1);
+    }
+    let sda = !ack as u8;
+    i2c_lines(mapper, 0, sda);
+    i2c_lines(mapper, 1, sda);
+    i2c_lines(mapper, 0, sda);
+    byte
+}
+
+/// 從 address 開始寫入 data
+pub fn eeprom_write(mapper: &mut impl MapperTrait, address: u8, data: &[u8]) {
+    i2c_start(mapper);
+    assert!(i2c_send(mapper, 0xA0));
+    assert!(i2c_send(mapper, address));
+    for &byte in data {
+        assert!(i2c_send(mapper, byte));
+    }
+    i2c_stop(mapper);
+}
+
+/// 從 address 開始讀取 len 個位元組（先寫入位址，再重新 START 讀取）
+pub fn eeprom_read(mapper: &mut impl MapperTrait, address: u8, len: usize) -> Vec<u8> {
+    i2c_start(mapper);
+    assert!(i2c_send(mapper, 0xA0));
+    assert!(i2c_send(mapper, address));
+    i2c_start(mapper);
+    assert!(i2c_send(mapper, 0xA1));
+    let data = (0..len).map(|i| i2c_receive(mapper, i + 1 < len)).collect();
+    i2c_stop(mapper);
+    data
+}
@@ -0,0 +1,61 @@
+// ============================================================
+// EmulatorConfig JSON 合併
+// ============================================================
+
+use nes_wasm::config::{EmulatorConfig, Region, TurboAudio};
+
+#[test]
+fn merge_updates_only_present_fields() {
+    let mut config = EmulatorConfig::default();
+    assert!(config.merge_json(r#"{ "sampleRate": 48000, "spriteLimit": false, "future": "x" }"#));
+    assert_eq!(config.sample_rate, 48000.0);
+    assert!(!config.sprite_limit);
+    assert!(config.audio_filter);
+}
+
+#[test]
+fn merge_rejects_bad_input_without_side_effects() {
+    let mut config = EmulatorConfig::default();
+    assert!(!config.merge_json(r#"{ "sampleRate": 48000, "spriteLimit": 1 }"#));
+    assert!(!config.merge_json(r#"{ "sampleRate": -1 }"#));
+    assert!(!config.merge_json("{ \"sampleRate\": 48000"));
+    assert_eq!(config, EmulatorConfig::default());
+}
+
+#[test]
+fn json_round_trip() {
+    let config = EmulatorConfig {
+        crop_overscan: true,
+        sample_rate: 22050.0,
+        power_on_alignment: None,
+        power_on_seed: 42,
+        region: Some(Region::Pal),
+        turbo_audio: TurboAudio::Mute,
+        ..EmulatorConfig::default()
+    };
+    let mut parsed = EmulatorConfig::default();
+    assert!(parsed.merge_json(&config.to_json()));
+    assert_eq!(parsed, config);
+}
+
+#[test]
+fn power_on_alignment_accepts_phase_or_random() {
+    let mut config = EmulatorConfig::default();
+    assert!(config.merge_json(r#"{ "powerOnAlignment": 2 }"#));
+    assert_eq!(config.power_on_alignment, Some(2));
+    assert!(config.merge_json(r#"{ "powerOnAlignment": "random", "powerOnSeed": 7 }"#));
+    assert_eq!((config.power_on_alignment, config.power_on_seed), (None, 7));
+    assert!(config.merge_json(r#"{ "powerOnAlignment": 3 }"#));
+    assert!(!config.merge_json(r#"{ "powerOnAlignment": 4 }"#));
+    assert!(!config.merge_json(r#"{ "powerOnAlignment": "late" }"#));
+}
+
+#[test]
+fn region_accepts_names_or_auto() {
+    let mut config = EmulatorConfig::default();
+    assert!(config.merge_json(r#"{ "region": "PAL" }"#));
+    assert_eq!(config.region, Some(Region::Pal));
+    assert!(config.merge_json(r#"{ "region": "auto" }"#));
+    assert_eq!(config.region, None);
+    assert!(!config.merge_json(r#"{ "region": "secam" }"#));
+}
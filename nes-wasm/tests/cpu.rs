@@ -0,0 +1,143 @@
+// ============================================================
+// CPU：中斷輪詢的時間點、非官方指令與 dummy 讀寫
+// ============================================================
+
+mod common;
+
+use common::boot;
+use nes_wasm::cpu::{Cpu, CpuBus};
+use nes_wasm::Emulator;
+
+/// 64KB 平面 RAM，記錄每次寫入（直接驅動 CPU 核心用）
+struct Ram {
+    mem: Vec<u8>,
+    writes: Vec<(u16, u8)>,
+}
+
+impl CpuBus for Ram {
+    fn read(&mut self, addr: u16) -> u8 { self.mem[addr as usize] }
+    fn write(&mut self, addr: u16, data: u8) {
+        self.mem[addr as usize] = data;
+        self.writes.push((addr, data));
+    }
+}
+
+/// 把程式放到 RAM $0300 並從那裡開始執行（主迴圈的 JMP 執行到一半也沒關係）
+fn run_from_ram(code: &[u8]) -> Emulator {
+    let mut emu = boot();
+    emu.run_frames(2);
+    // 開機時 $4017 = 0，幀計數器 IRQ 已經觸發，只是被 I 旗標遮蔽
+    assert!(emu.apu.check_irq());
+    for (i, &byte) in code.iter().enumerate() {
+        emu.poke(0x0300 + i as u16, byte);
+    }
+    emu.cpu.pc = 0x0300;
+    emu
+}
+
+#[test]
+fn cli_delays_a_pending_irq_by_one_instruction() {
+    let mut emu = run_from_ram(&[0x58, 0xEA, 0xEA]); // CLI, NOP, NOP
+    emu.step_instruction();
+    assert_eq!(emu.cpu.pc, 0x0301);
+    // CLI 在最後一個週期才清除 I，這條指令的輪詢仍被遮蔽
+    emu.step_instruction();
+    assert_eq!(emu.cpu.pc, 0x0302);
+    // NOP 之後進入 IRQ，處理常式的 RTI 回到 $0302
+    let sp = emu.cpu.sp;
+    emu.step_instruction();
+    assert_eq!(emu.cpu.pc, 0x0302);
+    let stack = |offset: u8| emu.bus.ram[0x100 + sp.wrapping_sub(offset) as usize];
+    assert_eq!((stack(0), stack(1)), (0x03, 0x02));
+}
+
+#[test]
+fn acknowledged_irq_is_not_taken() {
+    // LDA $4015 確認幀計數器 IRQ 後 CLI：IRQ 線已放開，不會進入中斷
+    let mut emu = run_from_ram(&[0xAD, 0x15, 0x40, 0x58, 0xEA, 0xEA]);
+    for _ in 0..4 {
+        emu.step_instruction();
+    }
+    assert_eq!(emu.cpu.pc, 0x0306);
+}
+
+#[test]
+fn unofficial_immediate_and_high_byte_stores() {
+    let mut emu = run_from_ram(&[
+        0xA9, 0xF0, // LDA #$F0
+        0x38,       // SEC
+        0x6B, 0xFF, // ARR #$FF -> A = $F8，C = 1，V = 0
+        0xA2, 0x0F, // LDX #$0F
+        0xCB, 0x03, // AXS #$03 -> X = ($F8 & $0F) - 3 = $05
+        0xA0, 0xFF, // LDY #$FF
+        0x9E, 0x10, 0x02, // SHX $0210,Y：跨頁，X & $03 = $01 寫到 $010F 而非 $030F
+        0x8B, 0xFF, // XAA #$FF
+    ]);
+    let mut config = emu.config().clone();
+    config.unstable_magic = 0x00;
+    emu.set_config(config);
+    for _ in 0..5 {
+        emu.step_instruction();
+    }
+    assert_eq!(emu.cpu.a, 0xF8);
+    assert_eq!(emu.cpu.status & 0x41, 0x01);
+    assert_eq!(emu.cpu.x, 0x05);
+    emu.step_instruction();
+    emu.step_instruction();
+    assert_eq!(emu.peek(0x010F), 0x01);
+    assert_ne!(emu.peek(0x030F), 0x01);
+    // magic = 0：A = A & X & imm
+    emu.step_instruction();
+    assert_eq!(emu.cpu.a, 0xF8 & 0x05);
+}
+
+#[test]
+fn dummy_read_and_rmw_double_write_reach_ppudata() {
+    let mut emu = run_from_ram(&[
+        0xA9, 0x00,       // LDA #$00
+        0x8D, 0x00, 0x20, // STA $2000（關閉 NMI，遞增 1）
+        0x8D, 0x01, 0x20, // STA $2001（關閉畫面）
+        0xA9, 0x24,       // LDA #$24
+        0x8D, 0x06, 0x20, // STA $2006
+        0xA9, 0x00,       // LDA #$00
+        0x8D, 0x06, 0x20, // STA $2006
+        0xA2, 0x0F,       // LDX #$0F
+        0xBD, 0xF8, 0x20, // LDA $20F8,X：跨頁，先 dummy read $2007 再讀 $2107
+        0xEE, 0x07, 0x20, // INC $2007：讀一次、寫兩次
+    ]);
+    for _ in 0..8 {
+        emu.step_instruction();
+    }
+    assert_eq!(emu.ppu.v, 0x2400);
+    emu.step_instruction();
+    assert_eq!(emu.ppu.v, 0x2402);
+    emu.step_instruction();
+    assert_eq!(emu.ppu.v, 0x2405);
+}
+
+#[test]
+fn cpu_core_runs_on_a_plain_ram_bus() {
+    let mut ram = Ram { mem: vec![0; 0x10000], writes: Vec::new() };
+    let code: &[u8] = &[
+        0xA2, 0x03,       // LDX #$03
+        0xCA,             // DEX
+        0xD0, 0xFD,       // BNE -3
+        0xEE, 0x00, 0x02, // INC $0200
+    ];
+    ram.mem[0x8000..0x8000 + code.len()].copy_from_slice(code);
+    ram.mem[0x0200] = 0x41;
+    ram.mem[0xFFFC..0xFFFE].copy_from_slice(&[0x00, 0x80]);
+
+    let mut cpu = Cpu::new();
+    cpu.reset(&mut ram);
+    let mut instructions = 0;
+    while cpu.pc != 0x8008 {
+        if !cpu.tick() {
+            cpu.step(&mut ram);
+            instructions += 1;
+        }
+    }
+    assert_eq!((cpu.x, instructions), (0, 8));
+    // RMW：先寫回原值再寫入結果
+    assert_eq!(ram.writes, [(0x0200, 0x41), (0x0200, 0x42)]);
+}
@@ -0,0 +1,172 @@
+// ============================================================
+// 除錯器測試 - 中斷點、單步執行、反組譯、追蹤
+// ============================================================
+
+mod common;
+
+use common::boot;
+use nes_wasm::debugger::StopReason;
+
+#[test]
+fn breakpoint_stops_frame_and_step_resumes() {
+    let mut emu = boot();
+    emu.debugger.add_breakpoint(0xC020);
+    let mut frames = 0;
+    while emu.debugger.break_hit().is_none() && frames < 10 {
+        emu.frame();
+        frames += 1;
+    }
+    assert_eq!(emu.debugger.break_hit(), Some(0xC020));
+    assert_eq!(emu.cpu.pc, 0xC020);
+
+    // LDA #$01
+    emu.step_instruction();
+    assert_eq!(emu.debugger.break_hit(), None);
+    assert_eq!(emu.cpu.pc, 0xC022);
+    assert_eq!(emu.cpu.a, 0x01);
+}
+
+/// 最多執行 frames 幀，中途停止時回傳原因
+fn step_frames(emu: &mut nes_wasm::Emulator, frames: u32) -> StopReason {
+    for _ in 0..frames {
+        let reason = emu.step_frame();
+        if reason != StopReason::Completed {
+            return reason;
+        }
+    }
+    StopReason::Completed
+}
+
+#[test]
+fn watchpoints_stop_after_the_accessing_instruction() {
+    let mut emu = boot();
+    // 重置程式：C005 LDA $2002 … C011 STA $2000
+    emu.debugger.add_watchpoint(0x2000, true);
+    assert_eq!(step_frames(&mut emu, 3), StopReason::WriteWatch);
+    assert_eq!(emu.debugger.watch_hit(), Some(0x2000));
+    assert_eq!(emu.cpu.pc, 0xC014);
+    assert_eq!(emu.step_instruction(), StopReason::Completed);
+    assert_eq!(emu.debugger.stop_reason(), None);
+
+    // NMI 中以 LDA $C050,X 讀背景色
+    emu.debugger.clear_watchpoints();
+    emu.debugger.add_watchpoint(0xC050, false);
+    assert_eq!(step_frames(&mut emu, 3), StopReason::ReadWatch);
+    assert_eq!(emu.cpu.pc, 0xC03D);
+}
+
+#[test]
+fn step_scanline_advances_one_line() {
+    let mut emu = boot();
+    emu.run_frames(1);
+    let line = emu.ppu.scanline;
+    assert_eq!(emu.step_scanline(), StopReason::Completed);
+    assert_eq!(emu.ppu.scanline, (line + 1) % 262);
+
+    emu.debugger.add_breakpoint(0xC020);
+    let mut reason = StopReason::Completed;
+    for _ in 0..262 * 3 {
+        reason = emu.step_scanline();
+        if reason != StopReason::Completed { break; }
+    }
+    assert_eq!(reason, StopReason::Breakpoint);
+    assert_eq!(emu.cpu.pc, 0xC020);
+}
+
+#[test]
+fn disassemble_reset_code() {
+    let emu = boot();
+    assert_eq!(
+        emu.disassemble(0xC000, 3),
+        ["C000  78        SEI", "C001  D8        CLD", "C002  A2 FF     LDX #$FF"],
+    );
+}
+
+#[test]
+fn trace_records_executed_instructions() {
+    let mut emu = boot();
+    emu.debugger.set_trace_enabled(true);
+    emu.step_instruction();
+    emu.step_instruction();
+    let trace = emu.debugger.take_trace();
+    let lines: Vec<&str> = trace.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("C000  78"));
+    assert!(lines[1].starts_with("C001  D8"));
+    assert!(emu.debugger.take_trace().is_empty());
+}
+
+#[test]
+fn trace_uses_nestest_layout() {
+    let mut emu = boot();
+    emu.debugger.set_trace_enabled(true);
+    for _ in 0..5 {
+        emu.step_instruction();
+    }
+    let log = emu.debugger.trace_log();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(&lines[2][..48], "C002  A2 FF     LDX #$FF                        ");
+    let status = emu.peek(0x2002);
+    assert!(lines[4].starts_with(&format!("C005  AD 02 20  LDA $2002 = {status:02X}")), "{}", lines[4]);
+    // 暫存器欄位與 nestest.log 對齊在第 48 欄
+    assert!(lines[4][48..].starts_with("A:00 X:FF Y:00 P:A4 SP:FF PPU:"), "{}", lines[4]);
+    let cycles = |line: &str| line.rsplit("CYC:").next().unwrap().parse::<u64>().unwrap();
+    assert!(cycles(lines[4]) > cycles(lines[3]));
+    assert_eq!(emu.debugger.take_trace(), log);
+}
+
+#[test]
+fn trace_reports_dmc_stall_cycles() {
+    let mut emu = boot();
+    emu.run_frames(2);
+    emu.debugger.set_trace_enabled(true);
+    // DMC 讀取一個位元組的取樣
+    for (addr, value) in [(0x4010, 0x0F), (0x4012, 0x00), (0x4013, 0x00), (0x4015, 0x10)] {
+        emu.poke(addr, value);
+    }
+    for _ in 0..4 {
+        emu.step_instruction();
+    }
+    let log = emu.debugger.take_trace();
+    let stalled: Vec<&str> = log.lines().filter(|line| line.contains("STALL:")).collect();
+    assert_eq!(stalled.len(), 1, "{log}");
+    assert!(stalled[0].ends_with(" STALL:4"), "{}", stalled[0]);
+}
+
+#[test]
+fn status_snapshot_reports_cpu_and_banks() {
+    let mut emu = boot();
+    emu.run_frames(2);
+    let status = emu.status();
+    assert_eq!(status.frame, 2);
+    assert_eq!(status.pc, emu.cpu.pc);
+    assert_eq!(status.prg_banks, [0, 1, 0, 1]);
+    let json = emu.status().to_json();
+    assert!(json.starts_with(&format!("{{\"pc\":{},", emu.cpu.pc)));
+    assert!(json.contains("\"chrBanks\":[0,1,2,3,4,5,6,7]"));
+}
+
+#[test]
+fn ppu_viewers_leave_emulation_untouched() {
+    let mut emu = boot();
+    emu.run_frames(5);
+    let state = emu.export_save_state_bytes();
+
+    let mut view = Vec::new();
+    emu.ppu.render_nametables(&emu.cartridge, &mut view);
+    assert_eq!(view.len(), 512 * 480 * 4);
+    // 捲軸在 (0, 0)：左上角是視窗外框，視窗內與畫面相同（CHR 全為 0，整片背景色）
+    assert_eq!(view[..4], [255, 0, 255, 255]);
+    let inside = (10 * 512 + 10) * 4;
+    let pixel = (10 * 256 + 10) * 4;
+    assert_eq!(view[inside..inside + 4], emu.ppu.frame_buffer[pixel..pixel + 4]);
+
+    emu.ppu.render_pattern_tables(&emu.cartridge, 0, &mut view);
+    assert_eq!(view.len(), 256 * 128 * 4);
+    assert_eq!(emu.ppu.palette_ram()[0], common::COLORS[0]);
+    let oam = emu.ppu.oam_entries();
+    assert_eq!(oam.len(), 64);
+    assert_eq!(oam[63].index, 63);
+
+    assert_eq!(emu.export_save_state_bytes(), state);
+}
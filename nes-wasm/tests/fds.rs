@@ -0,0 +1,119 @@
+// ============================================================
+// FDS 測試 - 映像檔解析、換面與 RAM 轉接器的磁碟讀取
+// ============================================================
+
+use nes_wasm::fds::{DiskDrive, FdsImage, BIOS_SIZE, DISK_SWAP_DELAY, SIDE_SIZE};
+use nes_wasm::apu::AudioChannel;
+use nes_wasm::Emulator;
+
+/// 組出指定面數的映像檔（含 fwNES 標頭）
+fn build_image(sides: u8) -> Vec<u8> {
+    let mut data = vec![b'F', b'D', b'S', 0x1A, sides, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    for i in 0..sides {
+        let mut side = vec![0u8; SIDE_SIZE];
+        side[..15].copy_from_slice(b"\x01*NINTENDO-HVC*");
+        side[16..19].copy_from_slice(b"SMB");
+        side[21] = i & 1;
+        side[22] = i / 2;
+        data.extend_from_slice(&side);
+    }
+    data
+}
+
+#[test]
+fn parses_sides_and_swaps_disks() {
+    let data = build_image(2);
+    assert!(FdsImage::is_fds(&data));
+    let image = FdsImage::parse(&data).unwrap();
+    assert_eq!(image.side_count(), 2);
+    assert_eq!(image.side_info()[1].side, 1);
+    assert_eq!(image.side_info()[1].game_name, "SMB");
+
+    let mut drive = DiskDrive::new();
+    drive.load_image(image);
+    assert_eq!(drive.inserted(), Some(0));
+    drive.request_swap();
+    drive.eject();
+    assert!(drive.take_swap_request());
+    assert!(!drive.take_swap_request());
+    assert!(!drive.insert(2));
+    assert!(drive.insert(1));
+    assert!(drive.sides_json().contains("\"side\":\"B\",\"inserted\":true"));
+}
+
+#[test]
+fn rejects_truncated_image() {
+    let data = build_image(1);
+    assert!(FdsImage::parse(&data[..SIDE_SIZE]).is_none());
+}
+
+/// 最小的 BIOS：啟動馬達讀取磁碟，把最先傳來的 16 個位元組存到 $0200
+fn build_bios() -> Vec<u8> {
+    let mut bios = vec![0xEAu8; BIOS_SIZE];
+    let code: &[u8] = &[
+        0xA9, 0x01,       // LDA #$01
+        0x8D, 0x23, 0x40, // STA $4023（啟用磁碟暫存器）
+        0xA9, 0x65,       // LDA #$65
+        0x8D, 0x25, 0x40, // STA $4025（馬達、讀取模式、開始傳輸）
+        0xA2, 0x00,       // LDX #$00
+        0xAD, 0x30, 0x40, // LDA $4030
+        0x29, 0x02,       // AND #$02
+        0xF0, 0xF9,       // BEQ -7
+        0xAD, 0x31, 0x40, // LDA $4031
+        0x9D, 0x00, 0x02, // STA $0200,X
+        0xE8,             // INX
+        0xE0, 0x10,       // CPX #$10
+        0xD0, 0xEE,       // BNE -18
+        0x4C, 0x1E, 0xE0, // JMP $E01E
+    ];
+    bios[..code.len()].copy_from_slice(code);
+    bios[0x1FFA..].copy_from_slice(&[0x1E, 0xE0, 0x00, 0xE0, 0x1E, 0xE0]);
+    bios
+}
+
+#[test]
+fn adapter_reads_blocks_past_the_gap() {
+    let mut emu = Emulator::new();
+    assert!(!emu.load_rom(&build_image(2)), "沒有 BIOS 不能開機");
+    assert!(!emu.load_fds_bios(&[0; 100]));
+    assert!(emu.load_fds_bios(&build_bios()));
+    assert!(emu.load_rom(&build_image(2)));
+    assert!(emu.cartridge.rom_info().mapper_supported);
+
+    emu.run_frames(30);
+    let read: Vec<u8> = (0x200..0x210).map(|addr| emu.peek(addr)).collect();
+    // 起始標記之後是磁碟資訊區塊
+    assert_eq!(read[0], 0x80);
+    assert_eq!(&read[1..], b"\x01*NINTENDO-HVC*");
+
+    // 換面：先退出，一段時間後才插入
+    assert!(emu.set_disk_side(1));
+    assert_eq!(emu.disk_drive().unwrap().inserted(), None);
+    emu.run_frames(DISK_SWAP_DELAY / 29780 + 2);
+    assert_eq!(emu.disk_drive().unwrap().inserted(), Some(1));
+    assert!(!emu.set_disk_side(2));
+}
+
+#[test]
+fn wavetable_channel_reaches_the_mixer() {
+    let mut emu = Emulator::new();
+    emu.load_fds_bios(&build_bios());
+    emu.load_rom(&build_image(1));
+    emu.poke(0x4023, 0x03);
+    // 波表只在 $4089 位元 7 開啟時可寫入：前半 0、後半 63 的方波
+    emu.poke(0x4089, 0x80);
+    for i in 0..64 {
+        emu.poke(0x4040 + i, if i < 32 { 0 } else { 63 });
+    }
+    emu.poke(0x4089, 0x00);
+    for (addr, value) in [(0x4080, 0xA0), (0x4082, 0x00), (0x4083, 0x08)] {
+        emu.poke(addr, value);
+    }
+
+    let mut peak = 0.0f32;
+    for _ in 0..2000 {
+        emu.step_instruction();
+        peak = peak.max(emu.apu.channel_levels()[AudioChannel::Expansion as usize]);
+    }
+    assert!(peak > 0.3, "peak {peak}");
+}
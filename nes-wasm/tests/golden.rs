@@ -0,0 +1,58 @@
+// ============================================================
+// Golden image 測試 - 參考畫面比對與 PNG 編解碼（需啟用 native feature）
+// ============================================================
+// 參考圖遺失時以 NES_UPDATE_GOLDEN=1 cargo test --features native 重新產生。
+// ============================================================
+
+#![cfg(feature = "native")]
+
+mod common;
+
+use std::path::PathBuf;
+
+use nes_wasm::golden::{self, HEIGHT, WIDTH};
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(name)
+}
+
+#[test]
+fn test_rom_matches_reference_frame() {
+    let rom = common::build_test_rom();
+    golden::check_golden(&rom, 10, &golden_path("test_rom_10.png"), 0).unwrap();
+}
+
+#[test]
+fn png_round_trip_preserves_pixels() {
+    let pixels: Vec<u8> = (0..WIDTH * HEIGHT)
+        .flat_map(|i| [(i % 256) as u8, (i / 256) as u8, (i * 7) as u8, 0xFF])
+        .collect();
+    let png = golden::encode_png(WIDTH, HEIGHT, &pixels);
+    assert_eq!(golden::decode_png(&png), Some((WIDTH, HEIGHT, pixels)));
+}
+
+#[test]
+fn mismatch_writes_actual_and_diff_images() {
+    let rom = common::build_test_rom();
+    let mut frame = golden::render_frames(&rom, 10).unwrap();
+    // 左上角 4 個像素偏移 3：容許值 3 通過，2 則失敗
+    for px in frame.chunks_exact_mut(4).take(4) {
+        px[0] = px[0].wrapping_add(3);
+    }
+    let reference = golden_path("test_rom_10.png");
+    assert!(golden::check_frame(&frame, &reference, 3).is_ok());
+
+    let dir = std::env::temp_dir().join(format!("nes-golden-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let copy = dir.join("frame.png");
+    std::fs::copy(&reference, &copy).unwrap();
+    let err = golden::check_frame(&frame, &copy, 2).unwrap_err();
+    assert!(err.starts_with("4 個像素"), "{err}");
+
+    let diff = std::fs::read(dir.join("frame.diff.png")).unwrap();
+    let (_, _, diff) = golden::decode_png(&diff).unwrap();
+    assert_eq!(&diff[..4], &[0xFF, 0x00, 0x00, 0xFF]);
+    assert_ne!(&diff[16..20], &[0xFF, 0x00, 0x00, 0xFF]);
+    assert!(dir.join("frame.actual.png").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,394 @@
+// ============================================================
+// 無頭回歸測試 - 以原生目標在 cargo test 下執行
+// ============================================================
+// 透過 run_frames + frame_hash 驗證畫面輸出可重現且會反映腳本輸入。
+// ============================================================
+
+mod common;
+
+use common::boot;
+use nes_wasm::apu::AudioChannel;
+use nes_wasm::config::{Region, TurboAudio};
+use nes_wasm::controller::{InputDevice, BTN_A};
+
+#[test]
+fn frame_hash_is_deterministic() {
+    let mut a = boot();
+    let mut b = boot();
+    a.run_frames(10);
+    b.run_frames(10);
+    assert_eq!(a.frame_hash(), b.frame_hash());
+}
+
+#[test]
+fn run_frames_matches_frame_loop() {
+    let mut a = boot();
+    let mut b = boot();
+    a.run_frames(5);
+    for _ in 0..5 {
+        b.frame();
+    }
+    assert_eq!(a.frame_hash(), b.frame_hash());
+}
+
+#[test]
+fn partial_frame_runs_add_up_to_whole_frames() {
+    let mut whole = boot();
+    whole.run_frames(3);
+
+    let mut partial = boot();
+    let start = partial.status().cpu_cycles;
+    assert_eq!(partial.run_cycles(20000), 20000);
+    assert_eq!(partial.status().cpu_cycles - start, 20000);
+    partial.frame();
+    // 停在 10 條掃描線之後的掃描線開頭，一條掃描線約 113.67 個 CPU 週期
+    let line = partial.status().scanline;
+    let cycles = partial.run_scanlines(10);
+    assert!((1136..=1137).contains(&cycles), "{cycles}");
+    assert_eq!((partial.status().scanline, partial.status().dot), (line + 10, 0));
+    // 跨過幀界線的部分照常計入幀數
+    while partial.frame_count() < 2 {
+        partial.run_scanlines(7);
+    }
+    partial.frame();
+
+    assert_eq!(partial.frame_count(), 3);
+    assert_eq!(partial.frame_hash(), whole.frame_hash());
+    assert_eq!(partial.export_save_state_bytes(), whole.export_save_state_bytes());
+}
+
+#[test]
+fn turbo_runs_several_frames_and_applies_the_audio_policy() {
+    let mut normal = boot();
+    normal.run_frames(2);
+    normal.take_audio_samples();
+    normal.frame();
+    let one_frame = normal.take_audio_samples().len();
+    normal.run_frames(3);
+
+    let mut turbo = boot();
+    turbo.run_frames(2);
+    turbo.take_audio_samples();
+    turbo.set_speed(4);
+    turbo.frame_at_speed();
+    assert_eq!(turbo.frame_count(), normal.frame_count());
+    assert_eq!(turbo.frame_hash(), normal.frame_hash());
+    // 降頻：四幀的聲音壓成約一幀的取樣數
+    let resampled = turbo.take_audio_samples().len();
+    assert!(resampled.abs_diff(one_frame) <= 2, "{resampled} vs {one_frame}");
+
+    let mut config = turbo.config().clone();
+    config.turbo_audio = TurboAudio::Mute;
+    turbo.set_config(config);
+    turbo.frame_at_speed();
+    assert!(turbo.take_audio_samples().is_empty());
+    assert_eq!(turbo.frame_count(), 10);
+}
+
+#[test]
+fn skipped_frames_still_draw_while_a_zapper_is_attached() {
+    let mut emu = boot();
+    emu.run_frames(2);
+    emu.ppu.frame_buffer.fill(0);
+    emu.frame_skipped();
+    assert!(emu.ppu.frame_buffer.iter().all(|&b| b == 0));
+
+    // 光線感應讀取幀緩衝區，接著 Zapper 時不能跳過輸出
+    emu.set_input_device(1, InputDevice::Zapper);
+    emu.frame_skipped();
+    assert!(emu.ppu.frame_buffer.iter().any(|&b| b != 0));
+}
+
+#[test]
+fn crop_overscan_exposes_a_smaller_visible_frame() {
+    let mut emu = boot();
+    emu.run_frames(3);
+    assert_eq!(emu.frame_size(), (256, 240));
+    assert_eq!(emu.visible_frame(), emu.frame_buffer());
+
+    let mut config = emu.config().clone();
+    config.crop_overscan = true;
+    emu.set_config(config);
+    emu.run_frames(1);
+    assert_eq!(emu.frame_size(), (256, 224));
+    assert_eq!(emu.get_frame_buffer_len(), 256 * 224 * 4);
+    // 裁切的是輸出範圍，完整畫面本身不會被塗黑
+    let full = emu.frame_buffer();
+    assert_eq!(emu.visible_frame(), &full[8 * 256 * 4..232 * 256 * 4]);
+    assert_eq!(emu.get_frame_buffer_ptr(), full[8 * 256 * 4..].as_ptr());
+
+    let mut uncropped = boot();
+    uncropped.run_frames(4);
+    assert_eq!(emu.frame_hash(), uncropped.frame_hash());
+}
+
+#[test]
+fn scripted_input_changes_frame_hash() {
+    let mut emu = boot();
+    emu.run_frames(10);
+    let idle = emu.frame_hash();
+
+    emu.set_button(0, BTN_A, true);
+    emu.run_frames(2);
+    let pressed = emu.frame_hash();
+    assert_ne!(idle, pressed);
+
+    emu.set_button(0, BTN_A, false);
+    emu.run_frames(2);
+    assert_eq!(emu.frame_hash(), idle);
+}
+
+#[test]
+fn benchmark_restores_state() {
+    let mut emu = boot();
+    emu.run_frames(3);
+    let before = emu.export_save_state();
+    let hash = emu.frame_hash();
+
+    let start = std::time::Instant::now();
+    let result = emu.benchmark(5, || start.elapsed().as_secs_f64() * 1000.0);
+    assert_eq!(result.frames, 5);
+    assert!(result.fps > 0.0);
+    assert!(result.cpu_ms > 0.0 && result.ppu_ms > 0.0);
+    assert_eq!(emu.export_save_state(), before);
+    assert_eq!(emu.frame_hash(), hash);
+
+    // 之後的幀與沒跑過基準測試時相同
+    let mut reference = boot();
+    reference.run_frames(4);
+    emu.run_frames(1);
+    assert_eq!(emu.frame_hash(), reference.frame_hash());
+}
+
+#[cfg(feature = "profiling")]
+#[test]
+fn frame_profile_counts_one_frame() {
+    let mut emu = boot();
+    emu.run_frames(2);
+    let profile = *emu.last_frame_profile();
+    assert_eq!(profile.frame, emu.frame_count());
+    // 奇數幀開啟渲染時會少一個 dot
+    assert!((341 * 262 - 1..=341 * 262).contains(&profile.ppu_dots));
+    assert_eq!(profile.cpu_cycles, profile.ppu_dots / 3);
+    assert!(profile.instructions > 0 && profile.audio_samples > 0);
+    assert_eq!(profile.total_ms, 0.0);
+
+    let start = std::time::Instant::now();
+    emu.set_profiler_clock(Some(Box::new(move || start.elapsed().as_secs_f64() * 1000.0)));
+    emu.frame();
+    let timed = emu.last_frame_profile();
+    assert!(timed.total_ms > 0.0);
+    assert!(timed.subsystem_ms.iter().sum::<f64>() <= timed.total_ms);
+}
+
+#[test]
+fn queued_input_matches_live_input() {
+    let mut live = boot();
+    live.run_frames(10);
+    live.set_button(0, BTN_A, true);
+    live.run_frames(3);
+
+    let mut scripted = boot();
+    scripted.queue_input(10, 0, 1 << BTN_A);
+    scripted.run_frames(13);
+    assert_eq!(scripted.queued_input_count(), 0);
+    assert_eq!(scripted.frame_hash(), live.frame_hash());
+}
+
+#[test]
+fn instances_do_not_share_state() {
+    let mut a = boot();
+    let mut b = boot();
+    a.set_button(0, BTN_A, true);
+    a.save_slot(0, 0.0);
+    a.run_frames(10);
+    b.run_frames(10);
+    assert_ne!(a.frame_hash(), b.frame_hash());
+    assert!(b.slots().list().is_empty());
+    assert!(a.memory_usage().save_slots > 0);
+    assert_eq!(b.memory_usage().save_slots, 0);
+    assert_eq!(b.memory_usage().prg_rom, 0x4000);
+}
+
+#[test]
+fn dmc_sample_fetches_stall_the_cpu() {
+    let mut plain = boot();
+    let mut dmc = boot();
+    plain.run_frames(2);
+    dmc.run_frames(2);
+    // 最快速率播放一次 65 位元組的取樣
+    for (addr, value) in [(0x4010, 0x0F), (0x4012, 0x00), (0x4013, 0x04), (0x4015, 0x10)] {
+        dmc.poke(addr, value);
+    }
+    // 主迴圈（JMP 自己）一次所花的 CPU 週期
+    let mut probe = boot();
+    probe.run_frames(2);
+    probe.step_instruction();
+    let before = probe.status().cpu_cycles;
+    probe.step_instruction();
+    let loop_cycles = probe.status().cpu_cycles - before;
+
+    let start = (plain.instruction_count(), dmc.instruction_count());
+    plain.run_frames(10);
+    dmc.run_frames(10);
+
+    // 65 次讀取 × 4 週期
+    let lost = (plain.instruction_count() - start.0) - (dmc.instruction_count() - start.1);
+    assert!((lost * loop_cycles).abs_diff(65 * 4) < loop_cycles, "lost {lost} loops of {loop_cycles} cycles");
+}
+
+#[test]
+fn oam_dma_stall_and_dmc_steal_cycles() {
+    // 兩台同步的模擬器，其中一台在主迴圈中觸發 DMA，比較同樣指令數花的週期
+    let dma_cycles = |setup: &[(u16, u8)]| {
+        let mut plain = boot();
+        let mut emu = boot();
+        plain.run_frames(2);
+        emu.run_frames(2);
+        for &(addr, value) in setup {
+            emu.poke(addr, value);
+        }
+        for _ in 0..100 {
+            plain.step_instruction();
+            emu.step_instruction();
+        }
+        emu.status().cpu_cycles - plain.status().cpu_cycles
+    };
+    // 暫停 + 視奇偶的對齊週期 + 256 組讀寫
+    let oam = dma_cycles(&[(0x4014, 0x02)]);
+    assert!(oam == 513 || oam == 514, "OAM DMA took {oam} cycles");
+    // DMC 單獨讀取一個位元組：暫停、空轉、對齊、讀取
+    let dmc = [(0x4010, 0x0F), (0x4012, 0x00), (0x4013, 0x00), (0x4015, 0x10)];
+    assert_eq!(dma_cycles(&dmc), 4);
+    // OAM DMA 進行中 DMC 只多佔一個 get 與一個重新對齊週期
+    let both = dma_cycles(&[dmc[0], dmc[1], dmc[2], (0x4014, 0x02), dmc[3]]);
+    assert!(both == 515 || both == 516, "OAM + DMC DMA took {both} cycles");
+}
+
+#[test]
+fn pal_region_lengthens_frame_and_slows_cpu() {
+    let mut emu = boot();
+    let frame_cycles = |emu: &mut nes_wasm::Emulator| {
+        emu.frame();
+        let start = emu.status().cpu_cycles;
+        emu.frame();
+        emu.status().cpu_cycles - start
+    };
+    // NTSC：341 × 262 / 3
+    assert!(frame_cycles(&mut emu).abs_diff(29781) <= 1);
+
+    emu.set_region(Some(Region::Pal));
+    assert_eq!(emu.region(), Region::Pal);
+    // PAL：341 × 312 / 3.2，且奇數幀不跳週期
+    assert!(frame_cycles(&mut emu).abs_diff(33248) <= 1);
+
+    emu.set_region(None);
+    assert_eq!(emu.region(), Region::Ntsc);
+}
+
+#[test]
+fn dendy_region_keeps_ntsc_cpu_ratio_with_late_vblank() {
+    let mut emu = boot();
+    emu.set_region(Some(Region::Dendy));
+    emu.frame();
+    // VBlank 旗標延後到第 291 條才設定
+    while emu.ppu.status & 0x80 == 0 {
+        emu.ppu.clock(&mut emu.cartridge);
+    }
+    assert_eq!((emu.ppu.scanline, emu.ppu.cycle), (291, 2));
+    emu.frame();
+    let start = emu.status().cpu_cycles;
+    emu.frame();
+    // 341 × 312 / 3，奇數幀不跳週期
+    assert!((emu.status().cpu_cycles - start).abs_diff(35464) <= 1);
+}
+
+#[test]
+fn runahead_shows_future_frame_without_advancing() {
+    let mut reference = boot();
+    let mut states = Vec::new();
+    let mut hashes = Vec::new();
+    for f in 0..12 {
+        reference.set_button(0, BTN_A, f >= 4);
+        reference.frame();
+        states.push(reference.export_save_state_bytes());
+        hashes.push(reference.frame_hash());
+    }
+    let audio = reference.take_audio_samples();
+
+    let mut emu = boot();
+    let mut ahead_audio = Vec::new();
+    for f in 0..10 {
+        emu.set_button(0, BTN_A, f >= 4);
+        emu.frame_with_runahead(2);
+        ahead_audio.extend(emu.take_audio_samples());
+        assert_eq!(emu.export_save_state_bytes(), states[f]);
+        if f >= 4 {
+            assert_eq!(emu.frame_hash(), hashes[f + 2], "frame {f}");
+        }
+    }
+    assert_eq!(ahead_audio, audio[..ahead_audio.len()]);
+}
+
+#[test]
+fn muted_channel_drops_out_of_the_mix() {
+    let tone = |emu: &mut nes_wasm::Emulator| {
+        // 脈衝 1：固定音量 15、週期 $200
+        for (addr, value) in [(0x4015, 0x01), (0x4000, 0xBF), (0x4002, 0x00), (0x4003, 0x02)] {
+            emu.poke(addr, value);
+        }
+    };
+    let mut plain = boot();
+    let mut playing = boot();
+    let mut muted = boot();
+    for emu in [&mut plain, &mut playing, &mut muted] {
+        emu.run_frames(2);
+        emu.take_audio_samples();
+    }
+    tone(&mut playing);
+    tone(&mut muted);
+    muted.apu.set_channel_enabled(AudioChannel::Pulse1, false);
+    assert!(!muted.apu.channel_enabled(AudioChannel::Pulse1));
+
+    for emu in [&mut plain, &mut playing, &mut muted] {
+        emu.run_frames(3);
+    }
+    let silence = plain.take_audio_samples();
+    assert_ne!(playing.take_audio_samples(), silence);
+    assert_eq!(muted.take_audio_samples(), silence);
+
+    // 靜音只影響混音，準位照常回報
+    let mut peak = 0.0f32;
+    for _ in 0..3000 {
+        muted.step_instruction();
+        peak = peak.max(muted.apu.channel_levels()[AudioChannel::Pulse1 as usize]);
+    }
+    assert_eq!(peak, 1.0);
+}
+
+#[test]
+fn stereo_pan_moves_a_channel_to_one_side() {
+    let stereo = |tone: bool| {
+        let mut emu = boot();
+        let mut config = emu.config().clone();
+        config.stereo = true;
+        emu.set_config(config);
+        emu.apu.set_channel_pan(AudioChannel::Pulse1, -1.0);
+        emu.run_frames(2);
+        emu.take_audio_samples();
+        if tone {
+            for (addr, value) in [(0x4015, 0x01), (0x4000, 0xBF), (0x4002, 0x00), (0x4003, 0x02)] {
+                emu.poke(addr, value);
+            }
+        }
+        emu.run_frames(3);
+        let samples = emu.take_audio_samples();
+        assert_eq!(samples.len() % 2, 0);
+        let side = |offset: usize| samples.iter().skip(offset).step_by(2).copied().collect::<Vec<f32>>();
+        (side(0), side(1))
+    };
+    let (silent_left, silent_right) = stereo(false);
+    let (left, right) = stereo(true);
+    assert_ne!(left, silent_left);
+    assert_eq!(right, silent_right);
+}
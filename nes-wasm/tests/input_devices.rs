@@ -0,0 +1,185 @@
+// ============================================================
+// 輸入裝置測試 - Zapper、旋鈕與 Family BASIC 鍵盤的埠協定
+// ============================================================
+
+use nes_wasm::controller::{Controller, InputDevice, BTN_A, BTN_START, FOUR_SCORE_SIGNATURES};
+use nes_wasm::keyboard::{DataRecorder, FamilyKeyboard, TapeMode, TAPE_SAMPLE_CYCLES};
+use nes_wasm::ppu::Ppu;
+
+/// 在畫面上塗一塊白色矩形（含端點）
+fn paint(ppu: &mut Ppu, x0: usize, y0: usize, x1: usize, y1: usize) {
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let i = (y * 256 + x) * 4;
+            ppu.frame_buffer[i..i + 3].copy_from_slice(&[255, 255, 255]);
+        }
+    }
+}
+
+fn zapper_at(x: i32, y: i32) -> Controller {
+    let mut zapper = Controller::new();
+    zapper.set_device(InputDevice::Zapper);
+    zapper.set_zapper_position(x, y);
+    zapper
+}
+
+#[test]
+fn zapper_senses_bright_pixel_after_beam_passes() {
+    let mut ppu = Ppu::new();
+    paint(&mut ppu, 9, 19, 11, 21);
+    let mut zapper = zapper_at(10, 20);
+
+    ppu.scanline = 10;
+    assert_eq!(zapper.read(&ppu), 0x08);
+    ppu.scanline = 22;
+    assert_eq!(zapper.read(&ppu), 0x00);
+    ppu.scanline = 100;
+    assert_eq!(zapper.read(&ppu), 0x08);
+
+    zapper.set_zapper_trigger(true);
+    zapper.set_zapper_position(-1, -1);
+    ppu.scanline = 22;
+    assert_eq!(zapper.read(&ppu), 0x18);
+}
+
+#[test]
+fn zapper_needs_lit_region_and_pulse_follows_it() {
+    let mut ppu = Ppu::new();
+    // 單一亮點不足以觸發
+    paint(&mut ppu, 50, 50, 50, 50);
+    let mut zapper = zapper_at(50, 50);
+    ppu.scanline = 60;
+    assert_eq!(zapper.read(&ppu) & 0x08, 0x08);
+
+    // 視野下緣的亮區：脈衝從累積到門檻的那一列起算
+    paint(&mut ppu, 48, 53, 52, 53);
+    ppu.scanline = 53;
+    ppu.cycle = 50; // 本列只掃過 48、49 兩點，加上 (50,50) 共 3 點
+    assert_eq!(zapper.read(&ppu) & 0x08, 0x08);
+    ppu.cycle = 51;
+    assert_eq!(zapper.read(&ppu) & 0x08, 0x00);
+    ppu.scanline = 53 + 20;
+    assert_eq!(zapper.read(&ppu) & 0x08, 0x00);
+    ppu.scanline = 53 + 21;
+    assert_eq!(zapper.read(&ppu) & 0x08, 0x08);
+}
+
+#[test]
+fn zapper_trigger_is_a_short_pulse() {
+    let mut ppu = Ppu::new();
+    paint(&mut ppu, 0, 0, 255, 239);
+    ppu.scanline = 120;
+    let mut zapper = zapper_at(128, 110);
+
+    zapper.set_zapper_trigger(true);
+    for _ in 0..6 {
+        assert_eq!(zapper.read(&ppu), 0x10);
+        zapper.end_frame();
+    }
+    // 按住不放：脈衝結束後不再讀到扳機
+    assert_eq!(zapper.read(&ppu), 0x00);
+    zapper.set_zapper_trigger(false);
+    zapper.set_zapper_trigger(true);
+    assert_eq!(zapper.read(&ppu), 0x10);
+
+    // 打空：扳機脈衝期間看不到光，結束後恢復原本的瞄準
+    for _ in 0..6 {
+        zapper.end_frame();
+    }
+    zapper.fire_zapper_offscreen();
+    assert_eq!(zapper.read(&ppu), 0x18);
+    for _ in 0..6 {
+        zapper.end_frame();
+    }
+    assert_eq!(zapper.read(&ppu), 0x00);
+}
+
+#[test]
+fn four_score_shifts_out_both_players_and_signature() {
+    let ppu = Ppu::new();
+    let mut port = Controller::new();
+    port.set_button(BTN_START, true);
+    port.set_extra_button(BTN_A, true);
+    port.set_four_score(Some(FOUR_SCORE_SIGNATURES[1]));
+    port.write(1);
+    port.write(0);
+
+    let bits: Vec<u8> = (0..26).map(|_| port.read(&ppu)).collect();
+    assert_eq!(bits[..8], [0, 0, 0, 1, 0, 0, 0, 0]);
+    assert_eq!(bits[8..16], [1, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(bits[16..24], [0, 0, 1, 0, 0, 0, 0, 0]);
+    assert_eq!(bits[24..], [1, 1]);
+
+    // 未接轉接器：讀完 8 個按鈕後就固定讀到 1
+    port.set_four_score(None);
+    port.write(1);
+    port.write(0);
+    let bits: Vec<u8> = (0..10).map(|_| port.read(&ppu)).collect();
+    assert_eq!(bits[8..], [1, 1]);
+}
+
+#[test]
+fn paddle_shifts_out_inverted_position() {
+    let ppu = Ppu::new();
+    let mut paddle = Controller::new();
+    paddle.set_device(InputDevice::Paddle);
+    paddle.set_paddle_value(0xA5);
+    paddle.set_paddle_button(true);
+    paddle.write(1);
+    paddle.write(0);
+
+    let mut value = 0u8;
+    for _ in 0..8 {
+        let bits = paddle.read(&ppu);
+        assert_eq!(bits & 0x08, 0x08);
+        value = (value << 1) | ((bits >> 4) & 1);
+    }
+    assert_eq!(value, !0xA5);
+}
+
+#[test]
+fn keyboard_scans_rows_and_columns() {
+    let mut keyboard = FamilyKeyboard::new();
+    keyboard.set_key_state(0, 2, true); // RETURN
+    keyboard.set_key_state(8, 6, true); // SPACE
+    keyboard.write(0x05);
+    assert_eq!(keyboard.read(), 0, "未接上時不影響 $4017");
+    keyboard.set_connected(true);
+
+    // Family BASIC 的掃描順序：重置後每列先讀欄 0 再讀欄 1
+    keyboard.write(0x05);
+    let mut rows = Vec::new();
+    for _ in 0..10 {
+        keyboard.write(0x04);
+        let low = keyboard.read();
+        keyboard.write(0x06);
+        rows.push((low, keyboard.read()));
+    }
+    assert_eq!(rows[0], (0x1E & !0x08, 0x1E));
+    assert_eq!(rows[8], (0x1E, 0x1E & !0x08));
+    assert!(rows[1..8].iter().all(|&r| r == (0x1E, 0x1E)));
+    assert_eq!(rows[9], (0x1E, 0x1E));
+
+    keyboard.write(0x00);
+    assert_eq!(keyboard.read(), 0, "停用矩陣時讀到 0");
+}
+
+#[test]
+fn data_recorder_plays_back_recorded_levels() {
+    let mut recorder = DataRecorder::new();
+    recorder.record(1000);
+    let pattern = [true, true, false, true, false, false, true, false, true, true];
+    for (i, &level) in pattern.iter().enumerate() {
+        recorder.write(if level { 0x04 } else { 0 }, 1000 + i as u64 * TAPE_SAMPLE_CYCLES);
+    }
+    let tape = recorder.stop(1000 + pattern.len() as u64 * TAPE_SAMPLE_CYCLES);
+    assert_eq!(tape.len(), 2);
+
+    recorder.play(&tape, 50_000);
+    for (i, &level) in pattern.iter().enumerate() {
+        let bit = recorder.read(50_000 + i as u64 * TAPE_SAMPLE_CYCLES + 5);
+        assert_eq!(bit, if level { 0x02 } else { 0 }, "sample {i}");
+    }
+    recorder.read(50_000 + 16 * TAPE_SAMPLE_CYCLES);
+    assert_eq!(recorder.mode(), TapeMode::Stopped);
+}
@@ -0,0 +1,774 @@
+// ============================================================
+// Mapper 測試 - bank 切換反映到 PPU、列舉分派、外部實作與 IRQ 計時
+// ============================================================
+
+mod common;
+
+use common::{build_test_rom, eeprom_read, eeprom_write, i2c_send, i2c_start, i2c_stop};
+use nes_wasm::apu::{Apu, AudioChannel, ExpansionChip};
+use nes_wasm::cartridge::Cartridge;
+use nes_wasm::compat::CompatHack;
+use nes_wasm::emulator::Emulator;
+use nes_wasm::mappers::{create_mapper, Mapper, Mapper16, MapperTrait, MapperWriteResult, PrgRamAccess};
+use nes_wasm::ppu::{MirrorMode, PpuBus};
+
+/// 把測試 ROM 改成 CNROM（Mapper 3），CHR bank 1 的圖磚 0 全為像素 1
+fn boot_cnrom() -> Emulator {
+    let mut rom = build_test_rom();
+    rom[5] = 2;
+    rom[6] = 0x30;
+    let mut bank1 = vec![0u8; 0x2000];
+    bank1[..8].fill(0xFF);
+    rom.extend_from_slice(&bank1);
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&rom));
+    // 前幾幀等待 VBlank，NMI 寫入背景色後畫面才穩定
+    emu.run_frames(5);
+    emu
+}
+
+#[test]
+fn chr_bank_switch_reaches_ppu() {
+    let mut emu = boot_cnrom();
+    let blank = emu.frame_hash();
+    emu.poke(0x8000, 1);
+    emu.run_frames(1);
+    let switched = emu.frame_hash();
+    assert_ne!(switched, blank);
+
+    emu.poke(0x8000, 0);
+    emu.run_frames(1);
+    assert_eq!(emu.frame_hash(), blank);
+}
+
+#[test]
+fn prg_ram_write_does_not_disturb_chr_banks() {
+    let mut emu = boot_cnrom();
+    emu.poke(0x8000, 1);
+    emu.run_frames(1);
+    let switched = emu.frame_hash();
+    for addr in 0x6000..0x6010 {
+        emu.poke(addr, 0xAA);
+    }
+    emu.run_frames(1);
+    assert_eq!(emu.frame_hash(), switched);
+}
+
+/// 固定把 $8000-$FFFF 映射到 PRG 最後 32KB 的外部 Mapper
+struct FixedLastBank;
+
+impl MapperTrait for FixedLastBank {
+    fn cpu_read(&self, addr: u16) -> Option<u32> {
+        (addr >= 0x8000).then(|| 0x18000 + (addr - 0x8000) as u32)
+    }
+    fn cpu_write(&mut self, _addr: u16, _data: u8) -> Option<MapperWriteResult> {
+        None
+    }
+    fn ppu_read(&self, addr: u16) -> Option<u32> {
+        (addr < 0x2000).then_some(addr as u32)
+    }
+    fn ppu_write(&self, _addr: u16) -> Option<u32> {
+        None
+    }
+    fn reset(&mut self) {}
+}
+
+#[test]
+fn builtin_and_custom_mappers_share_the_enum() {
+    let mut uxrom = create_mapper(2, 8, 0);
+    assert!(matches!(uxrom, Mapper::Mapper2(_)));
+    uxrom.cpu_write(0x8000, 3);
+    assert_eq!(uxrom.cpu_read(0x8000), Some(3 * 0x4000));
+
+    let custom: Box<dyn MapperTrait> = Box::new(FixedLastBank);
+    let custom = Mapper::from(custom);
+    assert_eq!(custom.cpu_read(0x8000), Some(0x18000));
+    assert_eq!(custom.ppu_read(0x1234), Some(0x1234));
+}
+
+/// UxROM（Mapper 2），每個 16KB bank 填入自己的編號
+fn uxrom_cartridge(banks: u8) -> Cartridge {
+    cartridge_with_mapper(2, banks)
+}
+
+/// 每個 16KB PRG bank 填滿自己的編號（CHR RAM）
+fn cartridge_with_mapper(mapper: u8, banks: u8) -> Cartridge {
+    let flags6 = (mapper & 0x0F) << 4;
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, banks, 0, flags6, mapper & 0xF0, 0, 0, 0, 0, 0, 0, 0, 0];
+    for bank in 0..banks {
+        rom.extend(std::iter::repeat_n(bank, 0x4000));
+    }
+    let mut cart = Cartridge::new();
+    assert!(cart.load_rom(&rom));
+    cart
+}
+
+#[test]
+fn prg_bank_numbers_wrap_to_rom_size() {
+    for banks in [3, 4] {
+        let mut cart = uxrom_cartridge(banks);
+        for select in 0..8 {
+            cart.cpu_write(0x8000, select);
+            assert_eq!(cart.cpu_read(0x8000), select % banks, "{} banks, select {}", banks, select);
+            assert_eq!(cart.cpu_read(0xC000), banks - 1);
+        }
+    }
+}
+
+/// CNROM（Mapper 3），每個 8KB CHR bank 填滿自己的編號
+fn cnrom_cartridge(banks: u8) -> Cartridge {
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 2, banks, 0x30, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend(std::iter::repeat_n(0xFF, 0x8000));
+    for bank in 0..banks {
+        rom.extend(std::iter::repeat_n(bank, 0x2000));
+    }
+    let mut cart = Cartridge::new();
+    assert!(cart.load_rom(&rom));
+    cart
+}
+
+#[test]
+fn chr_bank_numbers_wrap_to_rom_size() {
+    for banks in [3, 4] {
+        let mut cart = cnrom_cartridge(banks);
+        for select in 0..4 {
+            cart.cpu_write(0x8000, select);
+            assert_eq!(cart.peek(0x1FFF), select % banks, "{} banks, select {}", banks, select);
+        }
+    }
+
+    // 不是 1KB 倍數的 CHR 也在範圍內折返
+    let mut cart = cnrom_cartridge(1);
+    cart.set_chr_data(vec![0xAA; 0x600]);
+    assert_eq!(cart.peek(0x0700), 0xAA);
+}
+
+/// 設定 VRC4 IRQ（latch = $FD，溢位前要 3 次時鐘），回傳觸發時經過的 CPU 週期數
+fn vrc4_cycles_until_irq(control: u8) -> Option<u32> {
+    let mut mapper = create_mapper(23, 8, 16);
+    mapper.cpu_write(0xF000, 0x0D);
+    mapper.cpu_write(0xF001, 0x0F);
+    mapper.cpu_write(0xF002, control);
+    (1..=1000).find(|_| {
+        mapper.cpu_clock();
+        mapper.check_irq()
+    })
+}
+
+#[test]
+fn bandai_eeprom_reads_back_over_i2c() {
+    let mut mapper = Mapper16::with_eeprom(2, 1);
+    assert_eq!(mapper.prg_ram_access(), PrgRamAccess::Disabled);
+    eeprom_write(&mut mapper, 0x10, &[0x12, 0x34, 0x56]);
+    let eeprom = mapper.eeprom().unwrap();
+    assert!(eeprom.dirty);
+    assert_eq!(eeprom.data[0x10..0x13], [0x12, 0x34, 0x56]);
+    assert_eq!(eeprom_read(&mut mapper, 0x11, 2), [0x34, 0x56]);
+
+    // 晶片位址不是 1010xxxx 時不回應
+    i2c_start(&mut mapper);
+    assert!(!i2c_send(&mut mapper, 0x40));
+    i2c_stop(&mut mapper);
+
+    // FCG-1/2 沒有 EEPROM，$6000-$7FFF 照常是 PRG RAM
+    let plain = Mapper16::new(2, 1);
+    assert!(plain.eeprom().is_none());
+    assert_eq!(plain.prg_ram_access(), PrgRamAccess::ReadWrite);
+}
+
+#[test]
+fn vrc_irq_counts_cpu_cycles() {
+    // 週期模式：每個 CPU 週期一次時鐘
+    assert_eq!(vrc4_cycles_until_irq(0x06), Some(3));
+    // 掃描線模式：預除器間隔 114、114、113，不依賴 PPU 渲染
+    assert_eq!(vrc4_cycles_until_irq(0x02), Some(341));
+    // E 未設定時不計數
+    assert_eq!(vrc4_cycles_until_irq(0x04), None);
+}
+
+#[test]
+fn mmc3_prg_ram_protect_bits() {
+    let mut cart = cartridge_with_mapper(4, 4);
+    cart.cpu_write(0x6000, 0x11);
+    assert_eq!(cart.cpu_read(0x6000), 0x11);
+
+    // 防寫：可讀不可寫
+    cart.cpu_write(0xA001, 0xC0);
+    cart.cpu_write(0x6000, 0x22);
+    assert_eq!(cart.cpu_read(0x6000), 0x11);
+
+    // 停用：讀不到資料
+    cart.cpu_write(0xA001, 0x00);
+    assert_eq!(cart.cpu_read(0x6000), 0);
+    cart.cpu_write(0xA001, 0x80);
+    assert_eq!(cart.cpu_read(0x6000), 0x11);
+}
+
+#[test]
+fn mmc1_prg_bank_bit4_disables_prg_ram() {
+    let mut cart = cartridge_with_mapper(1, 4);
+    let write_prg_reg = |cart: &mut Cartridge, value: u8| {
+        for bit in 0..5 {
+            cart.cpu_write(0xE000, (value >> bit) & 1);
+            cart.cpu_clock();
+        }
+    };
+    cart.cpu_write(0x6123, 0x5A);
+    write_prg_reg(&mut cart, 0x10);
+    assert_eq!(cart.cpu_read(0x6123), 0);
+    cart.cpu_write(0x6123, 0x00);
+    write_prg_reg(&mut cart, 0x00);
+    assert_eq!(cart.cpu_read(0x6123), 0x5A);
+}
+
+#[test]
+fn sxrom_chr_bank_bits_select_prg_a18_and_prg_ram_bank() {
+    // SXROM：512KB PRG、CHR RAM、NES 2.0 標頭標示 32KB PRG RAM
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 32, 0, 0x10, 0x08, 0, 0, 0x09, 0, 0, 0, 0, 0];
+    for bank in 0..32u8 {
+        rom.extend(std::iter::repeat_n(bank, 0x4000));
+    }
+    let mut cart = Cartridge::new();
+    assert!(cart.load_rom(&rom));
+    let write_chr0_reg = |cart: &mut Cartridge, value: u8| {
+        for bit in 0..5 {
+            cart.cpu_write(0xA000, (value >> bit) & 1);
+            cart.cpu_clock();
+        }
+    };
+    assert_eq!((cart.cpu_read(0x8000), cart.cpu_read(0xC000)), (0, 15));
+    cart.cpu_write(0x6000, 0xA0);
+
+    // 位元 4 = PRG A18：固定的最後一個 bank 也跟著切到後半
+    write_chr0_reg(&mut cart, 0x10);
+    assert_eq!((cart.cpu_read(0x8000), cart.cpu_read(0xC000)), (16, 31));
+
+    // 位元 3-2 = PRG RAM bank
+    write_chr0_reg(&mut cart, 0x04);
+    assert_eq!(cart.cpu_read(0x6000), 0);
+    cart.cpu_write(0x6000, 0xA1);
+    write_chr0_reg(&mut cart, 0x00);
+    assert_eq!(cart.cpu_read(0x6000), 0xA0);
+}
+
+/// latch = 0 時連續 4 條掃描線中觸發 IRQ 的次數（每次觸發後如同遊戲確認再啟用）
+fn mmc3_irqs_with_zero_latch(mut cart: Cartridge) -> usize {
+    cart.cpu_write(0xC000, 0);
+    cart.cpu_write(0xC001, 0);
+    cart.cpu_write(0xE001, 0);
+    (0..4).filter(|_| {
+        cart.a12_clock();
+        let irq = cart.mapper.check_irq();
+        cart.cpu_write(0xE000, 0);
+        cart.cpu_write(0xE001, 0);
+        irq
+    }).count()
+}
+
+#[test]
+fn mmc3_alt_irq_hack_fires_once_for_zero_latch() {
+    assert_eq!(mmc3_irqs_with_zero_latch(cartridge_with_mapper(4, 4)), 4);
+    let mut cart = cartridge_with_mapper(4, 4);
+    cart.set_compat_hacks(&[CompatHack::Mmc3AltIrq]);
+    assert_eq!(mmc3_irqs_with_zero_latch(cart), 1);
+}
+
+#[test]
+fn nes2_submapper_4_selects_mmc3a_irq() {
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 4, 0, 0x40, 0x08, 0x40, 0, 0, 0, 0, 0, 0, 0];
+    rom.resize(16 + 4 * 0x4000, 0);
+    let mut cart = Cartridge::new();
+    assert!(cart.load_rom(&rom));
+    assert_eq!(mmc3_irqs_with_zero_latch(cart), 1);
+}
+
+#[test]
+fn four_screen_ignores_mapper_mirroring() {
+    let mut cart = cartridge_with_mapper(4, 4);
+    cart.set_compat_hacks(&[CompatHack::FourScreen]);
+    assert_eq!(cart.header.mirror_mode, MirrorMode::FourScreen);
+    cart.cpu_write(0xA000, 1);
+    assert_eq!(cart.header.mirror_mode, MirrorMode::FourScreen);
+}
+
+#[test]
+fn mmc2_and_mmc4_latches_switch_on_tile_fetches() {
+    for (id, exact_low) in [(9, true), (10, false)] {
+        let mut mapper = create_mapper(id, 8, 16);
+        assert!(mapper.watches_ppu_fetch());
+        for (i, addr) in [0xB000, 0xC000, 0xD000, 0xE000].into_iter().enumerate() {
+            mapper.cpu_write(addr, i as u8 + 1);
+        }
+        // 開機時兩區都選 $FE 組
+        assert_eq!(mapper.ppu_read(0x0010), Some(2 * 0x1000 + 0x10));
+        assert_eq!(mapper.ppu_read(0x1010), Some(4 * 0x1000 + 0x10));
+
+        assert!(mapper.ppu_fetch(0x0FD8));
+        assert_eq!(mapper.ppu_read(0x0010), Some(0x1010));
+        assert!(!mapper.ppu_fetch(0x0FD8), "已經是 $FD 組");
+        // MMC2 第 0 區只認 $0FE8，MMC4 整列都會觸發
+        assert_eq!(mapper.ppu_fetch(0x0FEB), !exact_low, "mapper {id}");
+        assert!(mapper.ppu_fetch(0x1FDF));
+        assert_eq!(mapper.ppu_read(0x1FFF), Some(3 * 0x1000 + 0xFFF));
+        assert!(!mapper.ppu_fetch(0x1FD0), "低位元平面不觸發");
+    }
+}
+
+#[test]
+fn cartridge_pattern_reads_follow_the_mmc2_latch_immediately() {
+    // MMC2，128KB CHR ROM，每個 4KB bank 填入自己的編號
+    let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 8, 16, 0x90, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend(std::iter::repeat_n(0xEA, 8 * 0x4000));
+    rom.extend((0..32u8).flat_map(|bank| std::iter::repeat_n(bank, 0x1000)));
+    let mut cart = Cartridge::new();
+    assert!(cart.load_rom(&rom));
+    cart.cpu_write(0xB000, 5);
+    cart.cpu_write(0xC000, 6);
+
+    // 觸發讀取本身仍是 $FE 組，下一次讀取已換成 $FD 組，不需要同步
+    assert_eq!(cart.read(0x0FD8), 6);
+    assert_eq!(cart.peek(0x0010), 5);
+    // peek 不觸發鎖存器
+    cart.cpu_write(0xC000, 7);
+    cart.peek(0x0FE8);
+    assert_eq!(cart.peek(0x0010), 5);
+}
+
+#[test]
+fn mmc2_fixes_last_three_prg_banks() {
+    let mut mapper = create_mapper(9, 8, 16);
+    mapper.cpu_write(0xA000, 5);
+    assert_eq!(mapper.cpu_read(0x8000), Some(5 * 0x2000));
+    assert_eq!(mapper.cpu_read(0xA000), Some(13 * 0x2000));
+    assert_eq!(mapper.cpu_read(0xFFFF), Some(16 * 0x2000 - 1));
+}
+
+#[test]
+fn namco_108_has_only_the_mmc3_bank_registers() {
+    let mut mapper = create_mapper(206, 8, 8);
+    for (reg, value) in [(0, 0x05), (2, 0x21), (6, 0x13), (7, 0x02)] {
+        mapper.cpu_write(0x8000, reg);
+        mapper.cpu_write(0x8001, value);
+    }
+    // R6 只有 4 位元；$C000 以後固定為最後兩個 8KB bank
+    assert_eq!(mapper.cpu_read(0x8000), Some(3 * 0x2000));
+    assert_eq!(mapper.cpu_read(0xA000), Some(2 * 0x2000));
+    assert_eq!(mapper.cpu_read(0xE000), Some(15 * 0x2000));
+    // 2KB bank 忽略位元 0
+    assert_eq!(mapper.ppu_read(0x0400), Some(5 * 0x400));
+    assert_eq!(mapper.ppu_read(0x1000), Some(0x21 * 0x400));
+
+    // 沒有模式位元、鏡像與 IRQ 暫存器
+    mapper.cpu_write(0x8000, 0xC6);
+    assert!(mapper.cpu_write(0xA000, 0x01).is_none());
+    mapper.cpu_write(0xC000, 0x00);
+    mapper.cpu_write(0xE001, 0x00);
+    mapper.a12_clock();
+    assert!(!mapper.check_irq());
+    assert_eq!(mapper.cpu_read(0x8000), Some(3 * 0x2000));
+    assert_eq!(mapper.ppu_read(0x1000), Some(0x21 * 0x400));
+}
+
+/// 推進 n 條掃描線（每條約 113 個 CPU 週期）
+fn run_scanlines(mapper: &mut Mapper, n: usize) {
+    for _ in 0..n {
+        for _ in 0..113 {
+            mapper.cpu_clock();
+        }
+        mapper.scanline();
+    }
+}
+
+#[test]
+fn nanjing_prg_protection_and_chr_auto_switch() {
+    let mut mapper = create_mapper(163, 64, 0);
+    mapper.cpu_write(0x5200, 0x01);
+    mapper.cpu_write(0x5000, 0x82);
+    assert_eq!(mapper.cpu_read(0x8000), Some(0x12 * 0x8000));
+    mapper.cpu_write(0x5100, 0x06);
+    assert_eq!(mapper.cpu_read(0x8000), Some(3 * 0x8000));
+
+    mapper.cpu_write(0x5300, 0x04);
+    mapper.cpu_write(0x5100, 0xF0);
+    assert_eq!(mapper.cpu_read(0x8000), Some(0x12 * 0x8000));
+    assert_eq!(mapper.read_register(0x5100), Some(0x04 | 0x01 | 0x82 | 0x0F));
+    assert_eq!(mapper.read_register(0x5500), Some(0));
+    mapper.cpu_write(0x5101, 0x01);
+    mapper.cpu_write(0x5101, 0x00);
+    assert_eq!(mapper.read_register(0x5500), Some(0x04 | 0x82));
+
+    // VBlank 之後的第一條為預渲染線；第 127 條結束後兩個圖案表都指向後 4KB
+    for _ in 0..300 {
+        mapper.cpu_clock();
+    }
+    mapper.scanline();
+    run_scanlines(&mut mapper, 127);
+    assert_eq!(mapper.ppu_read(0x0400), Some(0x0400));
+    run_scanlines(&mut mapper, 1);
+    assert_eq!(mapper.ppu_read(0x0400), Some(0x1400));
+    assert_eq!(mapper.ppu_read(0x1400), Some(0x1400));
+    run_scanlines(&mut mapper, 112);
+    assert_eq!(mapper.ppu_read(0x1400), Some(0x0400));
+}
+
+#[test]
+fn namco_340_mirroring_and_175_prg_ram() {
+    // iNES 1.0、沒有電池：視為 Namco 340
+    let mut cart = cartridge_with_mapper(210, 8);
+    cart.cpu_write(0xE000, 0xC3);
+    assert_eq!(cart.cpu_read(0x8000), 1);
+    assert_eq!(cart.mirror_mode(), MirrorMode::Horizontal);
+    cart.cpu_write(0xE000, 0x80);
+    assert_eq!(cart.mirror_mode(), MirrorMode::SingleScreenHigh);
+
+    // 有電池：Namco 175，鏡像看標頭，$C000 位元 0 開關 PRG RAM
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 8, 0, 0x23, 0xD0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend(std::iter::repeat_n(0, 8 * 0x4000));
+    let mut cart = Cartridge::new();
+    assert!(cart.load_rom(&rom));
+    cart.cpu_write(0xE000, 0xC0);
+    assert_eq!(cart.mirror_mode(), MirrorMode::Vertical);
+    cart.cpu_write(0x6000, 0x5A);
+    assert_ne!(cart.cpu_read(0x6000), 0x5A);
+    cart.cpu_write(0xC000, 0x01);
+    cart.cpu_write(0x6000, 0x5A);
+    assert_eq!(cart.cpu_read(0x6000), 0x5A);
+}
+
+#[test]
+fn jaleco_ss88006_nibble_banks_and_counter_width() {
+    let mut mapper = create_mapper(18, 8, 32);
+    // PRG bank 0 = $0B、CHR bank 3 = $2C，各由兩個 4 位元寫入組成
+    mapper.cpu_write(0x8000, 0x0B);
+    mapper.cpu_write(0x8001, 0x00);
+    mapper.cpu_write(0xB002, 0x0C);
+    mapper.cpu_write(0xB003, 0x02);
+    assert_eq!(mapper.cpu_read(0x8000), Some(0x0B * 0x2000));
+    assert_eq!(mapper.cpu_read(0xE000), Some(15 * 0x2000));
+    assert_eq!(mapper.ppu_read(0x0C00), Some(0x2C * 0x400));
+
+    // 重新載入值 $1232，4 位元寬度只遞減低 4 位元：2 → 1 → 0 → 繞回時觸發
+    for (i, nibble) in [0x2, 0x3, 0x2, 0x1].into_iter().enumerate() {
+        mapper.cpu_write(0xE000 + i as u16, nibble);
+    }
+    mapper.cpu_write(0xF000, 0);
+    mapper.cpu_write(0xF001, 0x09);
+    let cycles = (1..=100).find(|_| {
+        mapper.cpu_clock();
+        mapper.check_irq()
+    });
+    assert_eq!(cycles, Some(3));
+    assert!(mapper.check_irq());
+    mapper.cpu_write(0xF001, 0x09);
+    assert!(!mapper.check_irq());
+
+    // 16 位元寬度：$1232 要 $1233 個週期才繞回
+    mapper.cpu_write(0xF000, 0);
+    mapper.cpu_write(0xF001, 0x01);
+    for _ in 0..0x1232 {
+        mapper.cpu_clock();
+    }
+    assert!(!mapper.check_irq());
+    mapper.cpu_clock();
+    assert!(mapper.check_irq());
+}
+
+#[test]
+fn n163_ram_port_and_irq_counter() {
+    let mut mapper = create_mapper(19, 8, 16);
+    mapper.cpu_write(0xF800, 0x80 | 0x10);
+    for value in [0x11, 0x22, 0x33] {
+        mapper.cpu_write(0x4800, value);
+    }
+    mapper.cpu_write(0xF800, 0x80 | 0x11);
+    assert_eq!(mapper.read_register(0x4800), Some(0x22));
+    assert_eq!(mapper.read_register(0x4800), Some(0x33));
+    assert_eq!(mapper.read_register(0x6000), None);
+
+    mapper.cpu_write(0x5000, 0xFD);
+    mapper.cpu_write(0x5800, 0xFF);
+    mapper.cpu_clock();
+    assert!(!mapper.check_irq());
+    mapper.cpu_clock();
+    assert!(mapper.check_irq());
+    assert_eq!(mapper.read_register(0x5800), Some(0xFF));
+    mapper.cpu_write(0x5800, 0x7F);
+    assert!(!mapper.check_irq());
+}
+
+/// 擴充音源目前的輸出
+fn audio_level(mapper: &mut Mapper) -> f32 {
+    mapper.expansion_audio().unwrap().output()
+}
+
+/// 推進擴充音源一個 CPU 週期後的輸出
+fn audio_step(mapper: &mut Mapper) -> f32 {
+    let audio = mapper.expansion_audio().unwrap();
+    audio.clock();
+    audio.output()
+}
+
+#[test]
+fn n163_wavetable_channel_reaches_expansion_output() {
+    let mut mapper = create_mapper(19, 8, 16);
+    assert!(create_mapper(0, 1, 1).expansion_audio().is_none());
+    // 波形：前 4 個取樣為 $F，後 4 個為 $0
+    mapper.cpu_write(0xF800, 0x80);
+    for value in [0xFF, 0xFF, 0x00, 0x00] {
+        mapper.cpu_write(0x4800, value);
+    }
+    // 第 7 聲道（$78-$7F）：長度 256 - $F8 = 8、頻率 $30000（每次前進 3 個取樣）、
+    // 音量 15；$7F 的位元 4-6 為 0，只啟用 1 個聲道
+    mapper.cpu_write(0xF800, 0x80 | 0x78);
+    for value in [0x00, 0x00, 0x00, 0x00, 0xFB, 0x00, 0x00, 0x0F] {
+        mapper.cpu_write(0x4800, value);
+    }
+
+    let mut levels = Vec::new();
+    for _ in 0..8 * 15 {
+        levels.push(audio_step(&mut mapper));
+    }
+    assert!(levels.iter().any(|&l| l > 0.2) && levels.iter().any(|&l| l < -0.2));
+
+    mapper.cpu_write(0xE000, 0x40);
+    assert_eq!(audio_level(&mut mapper), 0.0);
+}
+
+#[test]
+fn vrc6b_swaps_address_lines() {
+    for (id, chr_reg) in [(24, 0xD001), (26, 0xD002)] {
+        let mut mapper = create_mapper(id, 8, 16);
+        mapper.cpu_write(chr_reg, 7);
+        assert_eq!(mapper.ppu_read(0x0400), Some(7 * 0x400), "mapper {id}");
+    }
+}
+
+#[test]
+fn vrc6_pulse_and_saw_reach_expansion_output() {
+    let mut mapper = create_mapper(24, 8, 16);
+    assert_eq!(audio_level(&mut mapper), 0.0);
+
+    // 脈衝波 1：音量 15、佔空比 8/16、週期 9（每 10 個 CPU 週期前進一階）
+    mapper.cpu_write(0x9000, 0x7F);
+    mapper.cpu_write(0x9001, 0x09);
+    mapper.cpu_write(0x9002, 0x80);
+    let mut pulse = Vec::new();
+    for _ in 0..16 * 10 {
+        pulse.push(audio_step(&mut mapper));
+    }
+    let high = pulse.iter().filter(|&&l| l > 0.0).count();
+    assert_eq!(high, 8 * 10);
+    mapper.cpu_write(0x9002, 0x00);
+
+    // 鋸齒波：rate 8，7 次累加後歸零，最高輸出 (6 × 8) >> 3 = 6
+    mapper.cpu_write(0xB000, 0x08);
+    mapper.cpu_write(0xB002, 0x80);
+    let max = (0..14 * 2).map(|_| {
+        audio_step(&mut mapper)
+    }).fold(0.0f32, f32::max);
+    assert!((max - vrc6_level(6)).abs() < 1e-6);
+
+    // $9003 位元 0：暫停所有聲道
+    mapper.cpu_write(0x9003, 0x01);
+    let before = audio_level(&mut mapper);
+    for _ in 0..100 {
+        audio_step(&mut mapper);
+    }
+    assert_eq!(audio_level(&mut mapper), before);
+}
+
+/// VRC6 輸出 level 階時的擴充音效值
+fn vrc6_level(level: u8) -> f32 {
+    let mut mapper = create_mapper(24, 8, 16);
+    mapper.cpu_write(0x9000, 0x80 | level);
+    mapper.cpu_write(0x9002, 0x80);
+    audio_level(&mut mapper)
+}
+
+#[test]
+fn rambo1_extended_banks_and_cycle_irq() {
+    let mut mapper = create_mapper(64, 8, 16);
+    let write_reg = |mapper: &mut Mapper, select: u8, value: u8| {
+        mapper.cpu_write(0x8000, select);
+        mapper.cpu_write(0x8001, value);
+    };
+    for (reg, value) in [(0, 0x10), (1, 0x20), (8, 0x31), (9, 0x41), (6, 3), (7, 4), (15, 5)] {
+        write_reg(&mut mapper, reg, value);
+    }
+    // 2KB 模式忽略 R8/R9；1KB 模式（K）由 R8/R9 填補
+    assert_eq!(mapper.ppu_read(0x0400), Some(0x11 * 0x400));
+    mapper.cpu_write(0x8000, 0x20);
+    assert_eq!(mapper.ppu_read(0x0400), Some(0x31 * 0x400));
+    assert_eq!(mapper.ppu_read(0x0C00), Some(0x41 * 0x400));
+    // PRG 模式 1：RF、R6、R7，$E000 固定
+    mapper.cpu_write(0x8000, 0x40);
+    let banks: Vec<_> = [0x8000, 0xA000, 0xC000, 0xE000].iter().map(|&a| mapper.cpu_read(a).unwrap() / 0x2000).collect();
+    assert_eq!(banks, [5, 3, 4, 15]);
+
+    // 週期模式：latch 2 → 重新載入為 3，每 4 個 CPU 週期減 1
+    mapper.cpu_write(0xC000, 2);
+    mapper.cpu_write(0xC001, 1);
+    mapper.cpu_write(0xE001, 0);
+    let cycles = (1..=100).find(|_| {
+        mapper.cpu_clock();
+        mapper.check_irq()
+    });
+    assert_eq!(cycles, Some(4 * 4));
+    // 確認後改回 A12 模式，CPU 週期不計數
+    mapper.cpu_write(0xE000, 0);
+    mapper.cpu_write(0xE001, 0);
+    mapper.cpu_write(0xC001, 0);
+    for _ in 0..100 {
+        mapper.cpu_clock();
+    }
+    assert!(!mapper.check_irq());
+    for _ in 0..3 {
+        mapper.a12_clock();
+    }
+    assert!(!mapper.check_irq());
+    mapper.a12_clock();
+    assert!(mapper.check_irq());
+}
+
+#[test]
+fn irem_h3001_banks_and_cycle_irq() {
+    let mut mapper = create_mapper(65, 8, 16);
+    assert_eq!(mapper.cpu_read(0xC000), Some(14 * 0x2000));
+    mapper.cpu_write(0xA000, 5);
+    mapper.cpu_write(0xB003, 0x42);
+    assert_eq!(mapper.cpu_read(0xA000), Some(5 * 0x2000));
+    assert_eq!(mapper.ppu_read(0x0C00), Some(0x42 * 0x400));
+
+    // latch $0103：載入後 $103 個週期觸發，之後停在 0 不再重複
+    mapper.cpu_write(0x9005, 0x01);
+    mapper.cpu_write(0x9006, 0x03);
+    mapper.cpu_write(0x9004, 0);
+    mapper.cpu_write(0x9003, 0x80);
+    let cycles = (1..=0x200).find(|_| {
+        mapper.cpu_clock();
+        mapper.check_irq()
+    });
+    assert_eq!(cycles, Some(0x103));
+    mapper.cpu_write(0x9003, 0x80);
+    for _ in 0..0x200 {
+        mapper.cpu_clock();
+    }
+    assert!(!mapper.check_irq());
+}
+
+#[test]
+fn fme7_maps_rom_or_ram_at_6000_and_counts_down_to_irq() {
+    let mut cart = cartridge_with_mapper(69, 8);
+    // 命令 8：ROM bank 5（8KB）→ 16KB bank 2 的後半
+    cart.cpu_write(0x8000, 0x08);
+    cart.cpu_write(0xA000, 0x05);
+    assert_eq!(cart.cpu_read(0x6000), 2);
+    // 改為啟用的 RAM
+    cart.cpu_write(0xA000, 0xC0);
+    cart.cpu_write(0x6000, 0x5A);
+    assert_eq!(cart.cpu_read(0x6000), 0x5A);
+    assert_eq!(cart.cpu_read(0xE000), 7);
+
+    let mut mapper = create_mapper(69, 8, 16);
+    for (command, value) in [(0x0E, 0x02), (0x0F, 0x00), (0x0D, 0x81)] {
+        mapper.cpu_write(0x8000, command);
+        mapper.cpu_write(0xA000, value);
+    }
+    // 從 2 減到 $FFFF 要 3 個週期；確認前一直維持
+    let cycles = (1..=10).find(|_| {
+        mapper.cpu_clock();
+        mapper.check_irq()
+    });
+    assert_eq!(cycles, Some(3));
+    assert!(mapper.check_irq());
+    mapper.cpu_write(0x8000, 0x0D);
+    mapper.cpu_write(0xA000, 0x00);
+    assert!(!mapper.check_irq());
+}
+
+#[test]
+fn sunsoft_5b_tone_reaches_expansion_output() {
+    let mut mapper = create_mapper(69, 8, 16);
+    let mut write = |reg: u8, value: u8| {
+        mapper.cpu_write(0xC000, reg);
+        mapper.cpu_write(0xE000, value);
+    };
+    write(0x00, 0x04); // 聲道 A 週期 4：每 4 × 16 個 CPU 週期翻轉一次
+    write(0x07, 0x3E); // 只開聲道 A 的方波
+    write(0x08, 0x0F);
+
+    let mut levels = Vec::new();
+    for _ in 0..4 * 16 * 4 {
+        levels.push(audio_step(&mut mapper));
+    }
+    let high = levels.iter().filter(|&&l| l > 0.1).count();
+    assert_eq!(high, levels.len() / 2);
+
+    // 高 4 位元不為 0 的選擇會讓資料寫入失效，音量維持不變
+    mapper.cpu_write(0xC000, 0x18);
+    mapper.cpu_write(0xE000, 0x00);
+    let max = (0..4 * 16 * 2).map(|_| {
+        audio_step(&mut mapper)
+    }).fold(0.0f32, f32::max);
+    assert!(max > 0.1);
+}
+
+#[test]
+fn vrc7_fm_channel_reaches_expansion_output() {
+    let mut mapper = create_mapper(85, 8, 16);
+    assert_eq!(audio_level(&mut mapper), 0.0);
+    let mut write = |reg: u8, value: u8| {
+        mapper.cpu_write(0x9010, reg);
+        mapper.cpu_write(0x9030, value);
+    };
+    // 聲道 0：音色 4（Flute）、最大音量、約 440Hz 後按下
+    write(0x30, 0x40);
+    write(0x10, 0x20);
+    write(0x20, 0x10 | (4 << 1) | 0x01);
+
+    let levels: Vec<f32> = (0..36 * 2000).map(|_| {
+        audio_step(&mut mapper)
+    }).collect();
+    let peak = levels.iter().fold(0.0f32, |m, &l| m.max(l.abs()));
+    assert!(peak > 0.05, "peak {peak}");
+    assert!(levels.iter().any(|&l| l < 0.0));
+
+    // $E000 位元 6：靜音
+    mapper.cpu_write(0xE000, 0x40);
+    assert_eq!(audio_level(&mut mapper), 0.0);
+}
+
+#[test]
+fn expansion_gain_scales_the_mixed_source() {
+    // $9000 位元 7：脈衝波固定輸出音量
+    let mut mapper = create_mapper(24, 8, 16);
+    mapper.cpu_write(0x9000, 0x8F);
+    mapper.cpu_write(0x9002, 0x80);
+    let level = audio_level(&mut mapper);
+    assert!(level > 0.0);
+
+    let mut apu = Apu::new();
+    apu.set_filter_enabled(false);
+    // 階躍合成的輸出延遲 BLIP_WIDTH / 2 個取樣，執行足夠的週期讓輸出穩定
+    let mut mixed = |apu: &mut Apu| {
+        for _ in 0..2000 {
+            apu.clock_expansion(mapper.expansion_audio().unwrap());
+            apu.clock();
+        }
+        let mut out = [0.0f32; 64];
+        let n = apu.take_samples_into(&mut out);
+        out[n - 1]
+    };
+    let full = mixed(&mut apu);
+    assert!(full > 0.0);
+
+    // 增益只套用在該晶片的混音，回報的準位維持原值
+    apu.set_expansion_gain(ExpansionChip::Vrc6, 0.5);
+    apu.set_expansion_gain(ExpansionChip::Fds, -1.0);
+    assert_eq!(apu.expansion_gain(ExpansionChip::Fds), 0.0);
+    assert_eq!(apu.expansion_gain(ExpansionChip::N163), 1.0);
+    assert!((mixed(&mut apu) * 2.0 - full).abs() < 1e-6);
+    apu.set_expansion_gain(ExpansionChip::Vrc6, 0.0);
+    assert_eq!(mixed(&mut apu), 0.0);
+    assert_eq!(apu.channel_levels()[AudioChannel::Expansion as usize], level);
+}
@@ -0,0 +1,65 @@
+// ============================================================
+// 輸入影片：錄製、重播與重錄
+// ============================================================
+
+mod common;
+
+use common::boot;
+use nes_wasm::controller::BTN_A;
+use nes_wasm::movie::MovieMode;
+
+#[test]
+fn replay_reproduces_recording() {
+    let mut emu = boot();
+    emu.run_frames(2);
+    emu.movie_start_recording(false);
+    for f in 0..12 {
+        emu.set_button(0, BTN_A, (4..8).contains(&f));
+        emu.frame();
+    }
+    let recorded = emu.frame_hash();
+    emu.movie_stop();
+
+    emu.set_button(0, BTN_A, false);
+    assert!(emu.movie_start_playback());
+    emu.run_frames(12);
+    assert_eq!(emu.frame_hash(), recorded);
+    assert_eq!(emu.movie().unwrap().len(), 12);
+}
+
+#[test]
+fn loading_state_while_recording_rerecords() {
+    let mut emu = boot();
+    emu.movie_start_recording(true);
+    emu.run_frames(5);
+    assert!(emu.save_slot(0, 0.0));
+    emu.run_frames(5);
+    assert_eq!(emu.movie().unwrap().len(), 10);
+
+    assert!(emu.load_slot(0));
+    assert_eq!(emu.movie_mode(), MovieMode::Recording);
+    let movie = emu.movie().unwrap();
+    assert_eq!(movie.len(), 5);
+    assert_eq!(movie.rerecord_count, 1);
+}
+
+#[test]
+fn exported_movie_plays_back_on_another_instance() {
+    let mut emu = boot();
+    emu.movie_start_recording(true);
+    for f in 0..10 {
+        emu.set_button(0, BTN_A, f % 3 == 0);
+        emu.frame();
+    }
+    emu.movie_stop();
+    let data = emu.movie().unwrap().to_bytes();
+
+    let mut other = boot();
+    other.run_frames(7);
+    assert!(!other.movie_play(&data[..data.len() - 1]));
+    assert!(other.movie_play(&data));
+    assert_eq!(other.movie_mode(), MovieMode::Playing);
+    other.run_frames(10);
+    assert_eq!(other.frame_hash(), emu.frame_hash());
+    assert_eq!(other.movie().unwrap().to_bytes(), data);
+}
@@ -0,0 +1,127 @@
+// ============================================================
+// NSF 播放測試 - INIT/PLAY 呼叫、曲目切換、地區與 bank 設定
+// ============================================================
+
+use nes_wasm::emulator::Emulator;
+use nes_wasm::mappers::MapperTrait;
+use nes_wasm::nsf::NsfMapper;
+use nes_wasm::{EmulatorConfig, Region};
+
+/// 組出 3 首曲目的 NSF：INIT 把曲目編號存到 $00，PLAY 每次遞增 $01
+fn build_nsf() -> Vec<u8> {
+    let mut data = vec![0u8; 0x80];
+    data[..5].copy_from_slice(b"NESM\x1A");
+    data[5] = 1;
+    data[6] = 3; // 曲目總數
+    data[7] = 2; // 起始曲目（從 1 開始）
+    data[0x08..0x0E].copy_from_slice(&[0x00, 0x80, 0x00, 0x80, 0x03, 0x80]);
+    data[0x0E..0x13].copy_from_slice(b"Tests");
+    data[0x6E..0x70].copy_from_slice(&16639u16.to_le_bytes());
+    data.extend_from_slice(&[
+        0x85, 0x00, 0x60, // INIT: STA $00; RTS
+        0xE6, 0x01, 0x60, // PLAY: INC $01; RTS
+    ]);
+    data
+}
+
+#[test]
+fn plays_starting_track_and_switches() {
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&build_nsf()));
+    let nsf = emu.nsf().unwrap();
+    assert_eq!(nsf.header.title, "Tests");
+    assert_eq!(nsf.current_track, Some(1));
+
+    // 第 1 幀執行 INIT，之後每幀呼叫一次 PLAY
+    emu.run_frames(10);
+    assert_eq!(emu.bus.ram[0x00], 1);
+    assert_eq!(emu.bus.ram[0x01], 9);
+
+    assert!(emu.nsf_play_track(2));
+    assert!(!emu.nsf_play_track(3));
+    emu.run_frames(3);
+    assert_eq!(emu.bus.ram[0x00], 2);
+    assert_eq!(emu.bus.ram[0x01], 2);
+
+    emu.nsf_stop();
+    emu.run_frames(3);
+    assert_eq!(emu.bus.ram[0x01], 2);
+}
+
+#[test]
+fn partial_runs_call_play_once_per_frame() {
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&build_nsf()));
+    emu.frame();
+    // 約 1.7 幀：每次從幀中間繼續，不會重新開始這一幀
+    for _ in 0..50 {
+        emu.run_cycles(1000);
+    }
+    emu.frame();
+    assert_eq!(emu.frame_count(), 3);
+    assert_eq!(emu.bus.ram[0x01], 2);
+}
+
+#[test]
+fn stepping_waits_for_the_next_play_call() {
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&build_nsf()));
+    emu.frame();
+
+    // PLAY 返回後在返回位址閒置：單步跨過幀界線，執行下一幀 PLAY 的第一條指令
+    emu.step_instruction(); // INC $01
+    emu.step_instruction(); // RTS
+    let instructions = emu.instruction_count();
+    emu.step_instruction();
+    assert_eq!(emu.instruction_count(), instructions + 1);
+    assert_eq!(emu.frame_count(), 2);
+    assert_eq!(emu.bus.ram[0x01], 2);
+
+    // 停止播放後沒有指令可執行，單步最多執行一幀
+    emu.nsf_stop();
+    emu.step_instruction();
+    assert_eq!(emu.instruction_count(), instructions + 1);
+    assert_eq!(emu.frame_count(), 3);
+}
+
+/// INIT 改為把 X 存到 $00（STX $00; RTS）
+fn build_region_nsf(region_flags: u8) -> Vec<u8> {
+    let mut data = build_nsf();
+    data[0x7A] = region_flags;
+    data[0x80] = 0x86;
+    data
+}
+
+#[test]
+fn init_receives_the_tune_region_in_x() {
+    let init_x = |region_flags: u8, region: Option<Region>| {
+        let mut emu = Emulator::new();
+        emu.set_config(EmulatorConfig { region, ..EmulatorConfig::default() });
+        assert!(emu.load_rom(&build_region_nsf(region_flags)));
+        emu.frame();
+        emu.bus.ram[0x00]
+    };
+    assert_eq!(init_x(0x00, None), 0);
+    assert_eq!(init_x(0x01, None), 1);
+    // 雙制式依主機地區選擇，只支援一種制式時不受地區影響
+    assert_eq!(init_x(0x02, None), 0);
+    assert_eq!(init_x(0x02, Some(Region::Pal)), 1);
+    assert_eq!(init_x(0x02, Some(Region::Dendy)), 0);
+    assert_eq!(init_x(0x00, Some(Region::Pal)), 0);
+}
+
+#[test]
+fn starting_song_past_the_end_plays_the_last_track() {
+    let mut data = build_nsf();
+    data[7] = 9;
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&data));
+    assert_eq!(emu.nsf().unwrap().current_track, Some(2));
+}
+
+#[test]
+fn initial_banks_wrap_to_the_data_size() {
+    // 資料只有 2 個 4KB bank：標頭的 bank 9 折返成 bank 1
+    let mapper = NsfMapper::new([0, 9, 0, 0, 0, 0, 0, 0], 0x2000);
+    assert_eq!(mapper.cpu_read(0x9000), Some(0x1000));
+}
@@ -0,0 +1,35 @@
+// ============================================================
+// CPU 匯流排開路：未映射的位址回傳匯流排上最後的值
+// ============================================================
+
+mod common;
+
+use common::boot;
+
+#[test]
+fn unmapped_reads_return_the_last_bus_value() {
+    let mut emu = boot();
+    emu.run_frames(1);
+    let code: &[u8] = &[
+        0xAD, 0x00, 0x50, // LDA $5000（擴充區沒有裝置）
+        0x85, 0x10,       // STA $10
+        0xAD, 0x18, 0x40, // LDA $4018
+        0x85, 0x11,       // STA $11
+        0xAD, 0x16, 0x40, // LDA $4016
+        0x85, 0x12,       // STA $12
+        0xAD, 0x00, 0x40, // LDA $4000（唯寫暫存器）
+        0x85, 0x13,       // STA $13
+    ];
+    for (i, &byte) in code.iter().enumerate() {
+        emu.poke(0x0300 + i as u16, byte);
+    }
+    emu.cpu.pc = 0x0300;
+    for _ in 0..8 {
+        emu.step_instruction();
+    }
+    // 讀取前匯流排上是運算元的高位元組
+    assert_eq!(emu.peek(0x10), 0x50);
+    assert_eq!(emu.peek(0x11), 0x40);
+    assert_eq!(emu.peek(0x12) & 0xE0, 0x40, "控制器埠只驅動低 5 位元");
+    assert_eq!(emu.peek(0x13), 0x40);
+}
@@ -0,0 +1,132 @@
+// ============================================================
+// PPUDATA（$2007）測試 - 讀取緩衝、調色盤讀取與渲染中的位址遞增
+// ============================================================
+
+use nes_wasm::ppu::{ChrMemory, Ppu};
+
+fn set_addr(ppu: &mut Ppu, chr: &mut ChrMemory, addr: u16) {
+    ppu.cpu_write(0x2006, (addr >> 8) as u8, chr);
+    ppu.cpu_write(0x2006, addr as u8, chr);
+}
+
+#[test]
+fn palette_read_skips_buffer_and_latches_nametable_below() {
+    let mut ppu = Ppu::new();
+    let mut chr = ChrMemory::default();
+    set_addr(&mut ppu, &mut chr, 0x2F05);
+    ppu.cpu_write(0x2007, 0x5A, &mut chr);
+    set_addr(&mut ppu, &mut chr, 0x3F05);
+    ppu.cpu_write(0x2007, 0x2C, &mut chr);
+
+    set_addr(&mut ppu, &mut chr, 0x3F05);
+    assert_eq!(ppu.cpu_read(0x2007, &mut chr), 0x2C);
+    // 緩衝區此時是 $2F05 的名稱表資料
+    set_addr(&mut ppu, &mut chr, 0x2000);
+    assert_eq!(ppu.cpu_read(0x2007, &mut chr), 0x5A);
+
+    // 灰階模式只保留亮度位元
+    ppu.cpu_write(0x2001, 0x01, &mut chr);
+    set_addr(&mut ppu, &mut chr, 0x3F05);
+    assert_eq!(ppu.cpu_read(0x2007, &mut chr), 0x20);
+}
+
+#[test]
+fn address_wraps_past_3fff_into_pattern_tables() {
+    let mut ppu = Ppu::new();
+    let mut data = vec![0u8; 0x2000];
+    data[0] = 0x77;
+    let mut chr = ChrMemory::new(data, true);
+
+    set_addr(&mut ppu, &mut chr, 0x3FFF);
+    ppu.cpu_read(0x2007, &mut chr);
+    // v 越過 $3FFF 後回到 $0000，應走一般的緩衝讀取
+    ppu.cpu_read(0x2007, &mut chr);
+    assert_eq!(ppu.cpu_read(0x2007, &mut chr), 0x77);
+}
+
+#[test]
+fn access_during_rendering_bumps_coarse_x_and_y() {
+    let mut ppu = Ppu::new();
+    let mut chr = ChrMemory::default();
+    ppu.reset();
+    ppu.cpu_write(0x2001, 0x08, &mut chr);
+    while ppu.scanline != 20 || ppu.cycle != 100 {
+        ppu.clock(&mut chr);
+    }
+    let v = ppu.v;
+    ppu.cpu_read(0x2007, &mut chr);
+    let coarse_x = |v: u16| v & 0x1F;
+    let fine_y = |v: u16| v >> 12;
+    assert_eq!(coarse_x(ppu.v), (coarse_x(v) + 1) & 0x1F);
+    assert_eq!(fine_y(ppu.v), (fine_y(v) + 1) & 7);
+
+    // VBlank 中照常 +1
+    while ppu.scanline != 245 {
+        ppu.clock(&mut chr);
+    }
+    set_addr(&mut ppu, &mut chr, 0x2000);
+    ppu.cpu_write(0x2007, 0, &mut chr);
+    assert_eq!(ppu.v, 0x2001);
+}
+
+#[test]
+fn write_only_registers_read_the_decaying_open_bus_latch() {
+    let mut ppu = Ppu::new();
+    let mut chr = ChrMemory::default();
+    ppu.reset();
+    ppu.cpu_write(0x2003, 0xB5, &mut chr);
+    assert_eq!(ppu.cpu_read(0x2000, &mut chr), 0xB5);
+    assert_eq!(ppu.cpu_read(0x2005, &mut chr), 0xB5);
+    // $2002 只驅動高 3 位元（VBlank 未設定）
+    assert_eq!(ppu.cpu_read(0x2002, &mut chr), 0x15);
+
+    // 約 600 毫秒沒有存取就衰減為 0
+    let frames = |ppu: &mut Ppu, chr: &mut ChrMemory, n: u32| {
+        for _ in 0..n {
+            ppu.frame_complete = false;
+            while !ppu.frame_complete {
+                ppu.clock(chr);
+            }
+        }
+    };
+    frames(&mut ppu, &mut chr, 30);
+    assert_eq!(ppu.cpu_read(0x2001, &mut chr), 0x15);
+    frames(&mut ppu, &mut chr, 10);
+    assert_eq!(ppu.cpu_read(0x2001, &mut chr), 0x00);
+}
+
+#[test]
+fn status_read_at_vblank_start_suppresses_nmi() {
+    // 在第 241 條掃描線的指定週期讀取 $2002（或關閉 NMI），回傳讀到的值與是否送出 NMI
+    let race = |cycle: u16, disable: bool| {
+        let mut ppu = Ppu::new();
+        let mut chr = ChrMemory::default();
+        ppu.reset();
+        ppu.cpu_write(0x2000, 0x80, &mut chr);
+        while ppu.scanline != 241 || ppu.cycle != cycle {
+            assert!(!ppu.check_nmi());
+            ppu.clock(&mut chr);
+        }
+        let status = if disable {
+            ppu.cpu_write(0x2000, 0x00, &mut chr);
+            ppu.status
+        } else {
+            ppu.cpu_read(0x2002, &mut chr)
+        };
+        let mut nmi = false;
+        for _ in 0..10 {
+            nmi |= ppu.check_nmi();
+            ppu.clock(&mut chr);
+        }
+        (status & 0x80, nmi, ppu.status & 0x80)
+    };
+    // 前一個點：讀到 0，旗標與 NMI 都被跳過
+    assert_eq!(race(1, false), (0x00, false, 0x00));
+    // 同一個點與下一個點：讀到 1，但 NMI 被取消
+    assert_eq!(race(2, false), (0x80, false, 0x00));
+    assert_eq!(race(3, false), (0x80, false, 0x00));
+    // 之後讀取 NMI 已經送出
+    assert_eq!(race(4, false), (0x80, true, 0x00));
+    // 旗標剛設定時關閉 NMI 也會取消
+    assert_eq!(race(2, true), (0x80, false, 0x80));
+}
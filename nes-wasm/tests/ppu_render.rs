@@ -0,0 +1,260 @@
+// ============================================================
+// PPU 渲染測試 - 背景區段快速路徑、掃描線中途的暫存器寫入與 CHR 切換、Sprite 0 Hit 與 A12
+// ============================================================
+
+use nes_wasm::cartridge::Cartridge;
+use nes_wasm::ppu::{ChrMemory, Ppu};
+use nes_wasm::Region;
+
+/// 圖磚 0 全為像素 1，調色盤：背景色 $0F（黑）、像素 1 為 $30（白）
+fn solid_background_ppu() -> (Ppu, ChrMemory) {
+    let mut data = vec![0u8; 0x2000];
+    data[..8].fill(0xFF);
+    let mut chr = ChrMemory::new(data, true);
+    let mut ppu = Ppu::new();
+    ppu.palette[0] = 0x0F;
+    ppu.palette[1] = 0x30;
+    ppu.reset();
+    ppu.cpu_write(0x2001, 0x0A, &mut chr);
+    (ppu, chr)
+}
+
+fn pixel(ppu: &Ppu, x: usize, y: usize) -> &[u8] {
+    let i = (y * 256 + x) * 4;
+    &ppu.frame_buffer[i..i + 4]
+}
+
+#[test]
+fn mask_write_mid_tile_takes_effect_on_next_dot() {
+    let (mut ppu, mut chr) = solid_background_ppu();
+    while !ppu.frame_complete {
+        ppu.clock(&mut chr);
+        // 週期 100 之前剛輸出 x = 98，下一個像素起關閉背景
+        if ppu.scanline == 100 && ppu.cycle == 100 {
+            ppu.cpu_write(0x2001, 0x00, &mut chr);
+        }
+    }
+    let white = pixel(&ppu, 0, 99);
+    let black = pixel(&ppu, 0, 101);
+    assert_ne!(white, black);
+    assert_eq!(pixel(&ppu, 98, 100), white);
+    assert_eq!(pixel(&ppu, 99, 100), black);
+    assert_eq!(pixel(&ppu, 103, 100), black);
+}
+
+#[test]
+fn palette_write_mid_tile_recolors_remaining_pixels() {
+    let (mut ppu, mut chr) = solid_background_ppu();
+    while !ppu.frame_complete {
+        ppu.clock(&mut chr);
+        if ppu.scanline == 60 && ppu.cycle == 13 {
+            ppu.cpu_write(0x2006, 0x3F, &mut chr);
+            ppu.cpu_write(0x2006, 0x01, &mut chr);
+            ppu.cpu_write(0x2007, 0x16, &mut chr);
+        }
+    }
+    let before = pixel(&ppu, 11, 60);
+    assert_eq!(before, pixel(&ppu, 0, 59));
+    assert_ne!(pixel(&ppu, 12, 60), before);
+    assert_eq!(pixel(&ppu, 12, 60), pixel(&ppu, 15, 60));
+}
+
+#[test]
+fn chr_bank_offsets_wrap_to_rom_size() {
+    // CNROM 卡帶，16KB CHR ROM，只有第 1 個 8KB bank 的圖磚 0 有像素
+    let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 1, 2, 0x30, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend(std::iter::repeat_n(0xEA, 0x4000));
+    let mut chr = vec![0u8; 0x4000];
+    chr[0x2000..0x2008].fill(0xFF);
+    rom.extend_from_slice(&chr);
+    let render = |bank: u8| {
+        let mut cart = Cartridge::new();
+        assert!(cart.load_rom(&rom));
+        cart.cpu_write(0x8000, bank);
+        let mut ppu = Ppu::new();
+        ppu.palette[1] = 0x30;
+        ppu.reset();
+        ppu.cpu_write(0x2001, 0x0A, &mut cart);
+        while !ppu.frame_complete {
+            ppu.clock(&mut cart);
+        }
+        pixel(&ppu, 0, 10).to_vec()
+    };
+    let blank = render(0);
+    assert_ne!(render(1), blank);
+    assert_eq!(render(3), render(1));
+}
+
+#[test]
+fn chr_switch_during_sprite_fetches_only_affects_later_sprites() {
+    // CNROM：bank 0 的圖磚 1 全為像素 1，bank 1 的圖磚 1 為空白
+    let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 1, 2, 0x30, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend(std::iter::repeat_n(0xEA, 0x4000));
+    let mut chr = vec![0u8; 0x4000];
+    chr[0x10..0x18].fill(0xFF);
+    rom.extend_from_slice(&chr);
+    let mut cart = Cartridge::new();
+    assert!(cart.load_rom(&rom));
+
+    let mut ppu = Ppu::new();
+    ppu.palette[0] = 0x0F;
+    ppu.palette[0x11] = 0x30;
+    ppu.reset();
+    ppu.oam.fill(0xFF);
+    ppu.oam[..8].copy_from_slice(&[49, 1, 0, 10, 49, 1, 0, 100]);
+    ppu.cpu_write(0x2001, 0x14, &mut cart);
+    while !ppu.frame_complete {
+        ppu.clock(&mut cart);
+        // 精靈 0 的圖案已在第 261、263 週期讀取，精靈 1 還沒有
+        if ppu.scanline == 49 && ppu.cycle == 266 {
+            cart.cpu_write(0x8000, 1);
+        }
+    }
+    assert_ne!(pixel(&ppu, 10, 50), pixel(&ppu, 50, 50));
+    assert_eq!(pixel(&ppu, 100, 50), pixel(&ppu, 50, 50));
+}
+
+#[test]
+fn color_emphasis_darkens_other_channels() {
+    let render = |mask: u8| {
+        let (mut ppu, mut chr) = solid_background_ppu();
+        ppu.cpu_write(0x2001, mask, &mut chr);
+        while !ppu.frame_complete {
+            ppu.clock(&mut chr);
+        }
+        pixel(&ppu, 40, 40).to_vec()
+    };
+    let plain = render(0x0A);
+    let red = render(0x0A | 0x20);
+    assert_eq!(red[0], plain[0]);
+    assert!(red[1] < plain[1] && red[2] < plain[2]);
+    assert_eq!(red[3], 255);
+
+    let all = render(0x0A | 0xE0);
+    assert!((0..3).all(|c| all[c] < plain[c]));
+}
+
+/// 全畫面為不透明背景，精靈 0（圖磚 0，屬性 attr）放在 (x, y)，
+/// 回傳第一次設定 Sprite 0 Hit 時正在輸出的像素座標（scanline, x）
+fn sprite_zero_hit_position(x: u8, y: u8, attr: u8, mask: u8) -> Option<(i16, u16)> {
+    let (mut ppu, mut chr) = solid_background_ppu();
+    ppu.oam.fill(0xFF);
+    ppu.oam[..4].copy_from_slice(&[y, 0, attr, x]);
+    ppu.cpu_write(0x2001, mask, &mut chr);
+    while !ppu.frame_complete {
+        // clock 內先輸出像素再推進週期，所以輸出的是 cycle - 1
+        let dot = (ppu.scanline, ppu.cycle);
+        ppu.clock(&mut chr);
+        if ppu.status & 0x40 != 0 {
+            return Some((dot.0, dot.1 - 1));
+        }
+    }
+    None
+}
+
+#[test]
+fn sprite_zero_hit_sets_on_first_overlapping_pixel() {
+    // 精靈 Y 座標延後一條掃描線顯示
+    assert_eq!(sprite_zero_hit_position(100, 50, 0, 0x1E), Some((51, 100)));
+    assert_eq!(sprite_zero_hit_position(0, 0, 0, 0x1E), Some((1, 0)));
+    // 精靈在背景後方仍會命中
+    assert_eq!(sprite_zero_hit_position(100, 50, 0x20, 0x1E), Some((51, 100)));
+}
+
+#[test]
+fn sprite_zero_hit_respects_left_clipping() {
+    // 背景或精靈任一裁切左 8 像素時，x = 0..7 不會命中
+    assert_eq!(sprite_zero_hit_position(2, 20, 0, 0x18), Some((21, 8)));
+    assert_eq!(sprite_zero_hit_position(2, 20, 0, 0x1A), Some((21, 8)));
+    assert_eq!(sprite_zero_hit_position(2, 20, 0, 0x1C), Some((21, 8)));
+    assert_eq!(sprite_zero_hit_position(0, 20, 0, 0x18), None);
+}
+
+#[test]
+fn sprite_zero_hit_never_at_x_255() {
+    assert_eq!(sprite_zero_hit_position(255, 30, 0, 0x1E), None);
+    assert_eq!(sprite_zero_hit_position(254, 30, 0, 0x1E), Some((31, 254)));
+}
+
+#[test]
+fn pal_region_swaps_red_and_green_emphasis() {
+    let render = |region: Region, emphasis: u8| {
+        let (mut ppu, mut chr) = solid_background_ppu();
+        ppu.set_region(region);
+        ppu.cpu_write(0x2001, 0x0A | emphasis, &mut chr);
+        while !ppu.frame_complete {
+            ppu.clock(&mut chr);
+        }
+        pixel(&ppu, 0, 10).to_vec()
+    };
+    // 白色（$30）強調紅色時其他通道變暗；PAL 的位元 5 是綠色
+    let ntsc_red = render(Region::Ntsc, 0x20);
+    assert!(ntsc_red[0] > ntsc_red[1]);
+    let pal_green = render(Region::Pal, 0x20);
+    assert!(pal_green[1] > pal_green[0]);
+    let pal_red = render(Region::Pal, 0x40);
+    assert!(pal_red[0] > pal_red[1]);
+}
+
+/// 以 PPUCTRL = ctrl 渲染沒有精靈的一幀，回傳每次 A12 上升緣發生的週期
+fn a12_rise_cycles(ctrl: u8) -> Vec<u16> {
+    let (mut ppu, mut chr) = solid_background_ppu();
+    ppu.oam.fill(0xFF);
+    ppu.cpu_write(0x2000, ctrl, &mut chr);
+    ppu.cpu_write(0x2001, 0x18, &mut chr);
+    let mut rises = Vec::new();
+    while !ppu.frame_complete {
+        let cycle = ppu.cycle;
+        ppu.clock(&mut chr);
+        if ppu.check_a12_rise() {
+            rises.push(cycle);
+        }
+    }
+    rises
+}
+
+#[test]
+fn a12_rises_once_per_rendered_scanline() {
+    // 精靈在 $1000：第一個精靈圖案讀取時上升（預渲染線加 240 條可見掃描線）
+    let sprites_high = a12_rise_cycles(0x08);
+    assert_eq!(sprites_high.len(), 241);
+    assert!(sprites_high.iter().all(|&c| c == 261));
+    // 背景在 $1000：預先讀取下一條掃描線的圖磚時上升，圖磚之間與
+    // 掃描線交界的短暫低電位都被濾掉
+    let bg_high = a12_rise_cycles(0x10);
+    assert_eq!(bg_high.len(), 241);
+    assert!(bg_high.iter().all(|&c| c == 325));
+    // 兩者都在 $0000 時沒有上升緣；8x16 的空精靈讀取圖磚 $FF，位於 $1000
+    assert!(a12_rise_cycles(0x00).is_empty());
+    assert_eq!(a12_rise_cycles(0x20).len(), 241);
+}
+
+#[test]
+fn a12_follows_ppuaddr_when_idle_with_filter() {
+    let mut ppu = Ppu::new();
+    let mut chr = ChrMemory::default();
+    let set_addr = |ppu: &mut Ppu, chr: &mut ChrMemory, addr: u16| {
+        ppu.cpu_write(0x2006, (addr >> 8) as u8, chr);
+        ppu.cpu_write(0x2006, addr as u8, chr);
+    };
+    set_addr(&mut ppu, &mut chr, 0x0000);
+    for _ in 0..10 {
+        ppu.clock(&mut chr);
+    }
+    set_addr(&mut ppu, &mut chr, 0x1000);
+    assert!(ppu.check_a12_rise());
+    // 低電位太短：不算上升緣
+    set_addr(&mut ppu, &mut chr, 0x0000);
+    for _ in 0..9 {
+        ppu.clock(&mut chr);
+    }
+    set_addr(&mut ppu, &mut chr, 0x1000);
+    assert!(!ppu.check_a12_rise());
+    // $2007 遞增跨過 $1000 也會上升
+    set_addr(&mut ppu, &mut chr, 0x0FFF);
+    for _ in 0..10 {
+        ppu.clock(&mut chr);
+    }
+    ppu.cpu_write(0x2007, 0, &mut chr);
+    assert!(ppu.check_a12_rise());
+}
@@ -0,0 +1,155 @@
+// ============================================================
+// 重置測試 - 軟體重置與重新開機的差異、電池 RAM
+// ============================================================
+
+mod common;
+
+use common::{boot, build_test_rom};
+use nes_wasm::{Emulator, EmulatorConfig, InputDevice, Region};
+
+#[test]
+fn soft_reset_keeps_ram() {
+    let mut emu = boot();
+    emu.bus.ram[0x0300] = 0x5A;
+    emu.cartridge.prg_ram[0] = 0xA5;
+    let sp = emu.cpu.sp;
+    emu.soft_reset();
+    assert_eq!(emu.bus.ram[0x0300], 0x5A);
+    assert_eq!(emu.cartridge.prg_ram[0], 0xA5);
+    assert_eq!(emu.cpu.sp, sp.wrapping_sub(3));
+    assert_eq!(emu.cpu.pc, 0xC000);
+}
+
+#[test]
+fn power_cycle_clears_ram() {
+    let mut emu = boot();
+    emu.bus.ram[0x0300] = 0x5A;
+    emu.cartridge.prg_ram[0] = 0xA5;
+    emu.ppu.oam[0] = 0x12;
+    emu.power_cycle();
+    assert_eq!(emu.bus.ram[0x0300], 0);
+    assert_eq!(emu.cartridge.prg_ram[0], 0);
+    assert_eq!(emu.ppu.oam[0], 0);
+    assert_eq!(emu.cpu.sp, 0xFD);
+}
+
+/// 把測試 ROM 標成有電池的卡帶
+fn boot_battery() -> Emulator {
+    let mut rom = build_test_rom();
+    rom[6] |= 0x02;
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&rom));
+    emu
+}
+
+#[test]
+fn power_cycle_keeps_battery_ram_unless_disabled() {
+    let mut emu = boot_battery();
+    emu.cartridge.prg_ram[0] = 0xA5;
+    emu.power_cycle();
+    assert_eq!(emu.cartridge.prg_ram[0], 0xA5);
+
+    emu.set_config(EmulatorConfig { keep_battery_ram: false, ..EmulatorConfig::default() });
+    emu.power_cycle();
+    assert_eq!(emu.cartridge.prg_ram[0], 0);
+}
+
+#[test]
+fn sram_api_requires_battery() {
+    let mut emu = boot();
+    emu.cartridge.prg_ram[0] = 0xA5;
+    emu.cartridge.prg_ram_dirty = true;
+    assert_eq!(emu.flush_sram_now(), None);
+    assert!(!emu.load_sram(&[0; 8192]));
+
+    let mut emu = boot_battery();
+    assert!(emu.load_sram(&[0x42; 8192]));
+    assert_eq!(emu.cartridge.prg_ram[0], 0x42);
+}
+
+/// 開機後執行第一條指令時 PPU 所在的點
+fn first_instruction_dot(emu: &mut Emulator) -> u16 {
+    emu.power_cycle();
+    emu.step_instruction();
+    emu.ppu.cycle
+}
+
+#[test]
+fn power_on_alignment_shifts_ppu_against_cpu() {
+    let mut emu = boot();
+    let mut dots = Vec::new();
+    for phase in 0..3 {
+        emu.set_config(EmulatorConfig { power_on_alignment: Some(phase), ..EmulatorConfig::default() });
+        dots.push(first_instruction_dot(&mut emu));
+    }
+    assert_eq!(dots[1], dots[0] + 1);
+    assert_eq!(dots[2], dots[0] + 2);
+
+    // 隨機相位：同一個種子重設後得到相同的開機序列
+    let random = EmulatorConfig { power_on_alignment: None, power_on_seed: 99, ..EmulatorConfig::default() };
+    let mut sequence = || {
+        emu.set_config(EmulatorConfig::default());
+        emu.set_config(random.clone());
+        (0..12).map(|_| first_instruction_dot(&mut emu) - dots[0]).collect::<Vec<_>>()
+    };
+    let first = sequence();
+    assert_eq!(first, sequence());
+    assert!((0..3).all(|d| first.contains(&d)), "{first:?}");
+}
+
+#[test]
+fn pal_power_on_alignment_has_four_phases() {
+    // PAL 的 CPU 週期間隔為 3 或 4 個點，相位 3 只在 PAL 有意義
+    let mut emu = boot();
+    let dots: Vec<u16> = (0..4)
+        .map(|phase| {
+            let config = EmulatorConfig { power_on_alignment: Some(phase), region: Some(Region::Pal), ..EmulatorConfig::default() };
+            emu.set_config(config);
+            first_instruction_dot(&mut emu)
+        })
+        .collect();
+    assert_eq!(dots, [dots[0], dots[0] + 1, dots[0] + 2, dots[0] + 3]);
+}
+
+#[test]
+fn hot_swap_keeps_config_and_clears_session() {
+    let mut emu = boot();
+    let config = EmulatorConfig { sprite_limit: false, sample_rate: 48000.0, ..EmulatorConfig::default() };
+    emu.set_config(config.clone());
+    emu.set_input_device(1, InputDevice::Zapper);
+    assert!(emu.cheats.add("SXIOPO").is_some());
+    emu.run_frames(5);
+    assert!(emu.save_slot(0, 0.0));
+    emu.movie_start_recording(false);
+    emu.run_frames(2);
+
+    assert!(emu.load_rom(&build_test_rom()));
+    assert_eq!(emu.config(), &config);
+    assert_eq!(emu.ctrl2.device(), InputDevice::Zapper);
+    assert!(emu.cheats.list().is_empty());
+    assert!(emu.slots().list().is_empty());
+    assert!(emu.movie().is_none());
+    assert_eq!(emu.frame_count(), 0);
+    assert!(emu.ppu.palette.iter().all(|&c| c == 0));
+}
+
+#[test]
+fn unload_frees_rom_memory() {
+    let mut emu = boot();
+    emu.run_frames(2);
+    emu.unload_rom();
+    let usage = emu.memory_usage();
+    assert_eq!((usage.prg_rom, usage.chr, usage.save_slots), (0, 0, 0));
+    assert!(!emu.cartridge.loaded);
+    emu.run_frames(2);
+
+    assert!(emu.load_rom(&build_test_rom()));
+    emu.run_frames(2);
+    assert_eq!(emu.frame_hash(), boot_after(2));
+}
+
+fn boot_after(frames: u32) -> u32 {
+    let mut emu = boot();
+    emu.run_frames(frames);
+    emu.frame_hash()
+}
@@ -0,0 +1,126 @@
+// ============================================================
+// ROM 資訊解析
+// ============================================================
+
+mod common;
+
+use nes_wasm::cartridge::{crc32, Cartridge};
+use nes_wasm::compat::{self, CompatHack, COMPAT_DB};
+use nes_wasm::config::Region;
+
+#[test]
+fn crc32_matches_reference() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+}
+
+#[test]
+fn reports_ines_header_fields() {
+    let rom = common::build_test_rom();
+    let mut cart = Cartridge::new();
+    assert!(cart.load_rom(&rom));
+    let info = cart.rom_info();
+    assert_eq!(info.mapper_id, 0);
+    assert_eq!(info.mapper_name, "NROM");
+    assert!(info.mapper_supported);
+    assert_eq!(info.prg_rom_size, 0x4000);
+    assert_eq!(info.chr_rom_size, 0x2000);
+    assert!(!info.is_nes2);
+    assert_eq!(info.region, Region::Ntsc);
+    assert_eq!(info.crc32, crc32(&rom[16..]));
+}
+
+#[test]
+fn reports_nes2_mapper_and_region() {
+    let mut rom = common::build_test_rom();
+    rom[6] = 0x10; // Mapper 低 4 位元 = 1
+    rom[7] = 0x08; // NES 2.0
+    rom[8] = 0x21; // 子 Mapper 2，Mapper 位元 8-11 = 1
+    rom[12] = 0x01; // PAL
+    let mut cart = Cartridge::new();
+    assert!(cart.load_rom(&rom));
+    let info = cart.rom_info();
+    assert!(info.is_nes2);
+    assert_eq!(info.mapper_id, 0x101);
+    assert_eq!(info.submapper, 2);
+    assert!(!info.mapper_supported);
+    assert_eq!(info.region, Region::Pal);
+}
+
+#[test]
+fn failed_load_keeps_previous_cartridge() {
+    let rom = common::build_test_rom();
+    let mut cart = Cartridge::new();
+    assert!(cart.load_rom(&rom));
+
+    let mut bad = rom.clone();
+    bad[6] = 0x11; // Mapper 1，但 PRG 不完整
+    assert!(!cart.load_rom(&bad[..0x2000]));
+    bad[4] = 0; // PRG 大小為 0
+    assert!(!cart.load_rom(&bad));
+    assert_eq!(cart.rom_info().mapper_id, 0);
+    assert_eq!(cart.crc32, crc32(&rom[16..]));
+}
+
+#[test]
+fn crc32_ignores_trailing_bytes() {
+    let rom = common::build_test_rom();
+    let mut trailer = rom.clone();
+    trailer.extend_from_slice(b"trailing title block");
+    let mut cart = Cartridge::new();
+    assert!(cart.load_rom(&trailer));
+    assert_eq!(cart.crc32, crc32(&rom[16..]));
+}
+
+#[test]
+fn compat_hacks_are_looked_up_and_reported() {
+    let entry = &COMPAT_DB[0];
+    assert_eq!(compat::lookup(entry.crc32), entry.hacks);
+    assert!(compat::lookup(0).is_empty());
+
+    let mut cart = Cartridge::new();
+    assert!(cart.load_rom(&common::build_test_rom()));
+    assert!(cart.rom_info().compat_hacks.is_empty());
+    cart.set_compat_hacks(&[CompatHack::FourScreen]);
+    assert_eq!(cart.rom_info().compat_hacks, vec!["fourScreen"]);
+}
+
+#[test]
+fn dirty_header_bytes_are_ignored() {
+    let mut rom = common::build_test_rom();
+    rom[6] = 0x40; // Mapper 低 4 位元 = 4
+    rom[7..16].copy_from_slice(b"DiskDude!");
+    let mut cart = Cartridge::new();
+    assert!(cart.load_rom(&rom));
+    let info = cart.rom_info();
+    assert_eq!(info.mapper_id, 4);
+    assert!(info.header_cleaned);
+
+    // 位元組 12-15 有垃圾：連同 flags7 的 Mapper 高 4 位元與 PAL 位元一起忽略
+    rom[7..16].copy_from_slice(&[0x10, 0, 0x01, 0, 0, 0x20, 0x41, 0x42, 0x43]);
+    assert!(cart.load_rom(&rom));
+    let info = cart.rom_info();
+    assert_eq!((info.mapper_id, info.region), (4, Region::Ntsc));
+    assert!(info.header_cleaned);
+
+    rom[12..16].fill(0);
+    assert!(cart.load_rom(&rom));
+    assert_eq!(cart.rom_info().mapper_id, 0x14);
+    assert!(!cart.rom_info().header_cleaned);
+}
+
+#[test]
+fn nes2_prg_ram_size_is_honored_and_mirrored() {
+    let mut rom = common::build_test_rom();
+    rom[7] = 0x08; // NES 2.0
+    rom[10] = 0x05; // 64 << 5 = 2KB PRG RAM
+    let mut cart = Cartridge::new();
+    assert!(cart.load_rom(&rom));
+    assert_eq!(cart.rom_info().prg_ram_size, 0x800);
+    cart.cpu_write(0x6001, 0x5A);
+    assert_eq!(cart.cpu_read(0x6801), 0x5A, "2KB RAM 在 $6000-$7FFF 鏡像");
+
+    rom[10] = 0x00;
+    assert!(cart.load_rom(&rom));
+    assert!(!cart.has_prg_ram());
+    assert_eq!(cart.cpu_read_mapped(0x6001), None);
+}
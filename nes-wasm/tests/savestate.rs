@@ -0,0 +1,227 @@
+// ============================================================
+// 存檔往返：匯流排 DMA、控制器讀取鎖存與各子系統內部狀態
+// ============================================================
+
+mod common;
+
+use common::{boot, build_test_rom};
+use nes_wasm::Emulator;
+use nes_wasm::controller::BTN_B;
+
+#[test]
+fn round_trip_keeps_dma_and_controller_latch() {
+    let mut emu = boot();
+    emu.run_frames(3);
+
+    // 模擬讀檔時正處於 OAM DMA 中段、控制器已讀出一位元
+    emu.bus.dma_page = 0x02;
+    emu.bus.dma_address = 0x40;
+    emu.bus.dma_transfer = true;
+    emu.bus.dma_dummy = false;
+    emu.ctrl1.set_button(BTN_B, true);
+    emu.ctrl1.write(1);
+    emu.ctrl1.write(0);
+    emu.ctrl1.read(&emu.ppu);
+
+    let state = emu.export_save_state();
+    let mut restored = boot();
+    assert!(restored.import_save_state(&state));
+    assert_eq!(restored.bus.dma_page, 0x02);
+    assert_eq!(restored.bus.dma_address, 0x40);
+    assert!(restored.bus.dma_transfer);
+    assert!(!restored.bus.dma_dummy);
+    assert_eq!(restored.ctrl1.latch_state(), emu.ctrl1.latch_state());
+    assert_eq!(restored.export_save_state(), state);
+}
+
+#[test]
+fn binary_and_hex_states_match() {
+    let mut emu = boot();
+    emu.run_frames(2);
+    let bytes = emu.export_save_state_bytes();
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    assert_eq!(emu.export_save_state().to_lowercase(), hex);
+
+    let mut restored = boot();
+    assert!(restored.load_state(&bytes));
+    assert_eq!(restored.export_save_state_bytes(), bytes);
+}
+
+#[test]
+fn rejects_truncated_state() {
+    let mut emu = boot();
+    let state = emu.export_save_state();
+    assert!(!emu.import_save_state(&state[..state.len() - 4]));
+}
+
+#[test]
+fn rejected_state_leaves_emulator_untouched() {
+    let mut emu = boot();
+    emu.run_frames(2);
+    let mut state = Vec::new();
+    emu.save_state_into(&mut state);
+    emu.run_frames(1);
+    let before = emu.export_save_state();
+
+    // 截斷在 PRG RAM 之後，以及 PRG RAM 長度欄位損毀（接近 u32 上限）
+    assert!(!emu.load_state(&state[..state.len() - 20]));
+    let mut huge = state.clone();
+    huge[4407..4411].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(!emu.load_state(&huge));
+    // 長度正確但 CHR RAM 長度欄位不符（要讀到 v5 區段結尾才發現）；
+    // 欄位後面還有金手指清單（8 位元組）、兩個控制器的移位暫存器（8 位元組）
+    // DMC DMA 狀態（3 位元組）、匯流排開路值（1 位元組）、PPU 開路鎖存器（9 位元組）
+    // 中斷輪詢（主迴圈中為 4 位元組）、音頻階躍合成器（276 位元組）與
+    // 等待生效的 $4017/長度計數器寫入（14 位元組）、三角波減少爆音的準位（2 位元組）
+    // 與 DMC 開始讀取前的延遲（1 位元組）
+    let mut bad_chr = state.clone();
+    let chr_len_high = state.len() - 326 - 1;
+    bad_chr[chr_len_high] = 1;
+    assert!(!emu.load_state(&bad_chr));
+    assert_eq!(emu.export_save_state(), before);
+}
+
+#[test]
+fn mid_frame_state_resumes_mapper_and_audio() {
+    // CNROM（Mapper 3），兩個 CHR bank
+    let mut rom = build_test_rom();
+    rom[5] = 2;
+    rom[6] = 0x30;
+    rom.extend(std::iter::repeat_n(0xFFu8, 0x2000));
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&rom));
+    emu.poke(0x8000, 1);
+    // 脈衝波 1 持續發聲
+    emu.poke(0x4015, 0x01);
+    emu.poke(0x4000, 0xBF);
+    emu.poke(0x4002, 0xFD);
+    emu.poke(0x4003, 0x00);
+    emu.run_frames(2);
+    for _ in 0..300 {
+        emu.step_instruction();
+    }
+    let state = emu.export_save_state_bytes();
+    emu.take_audio_samples();
+
+    emu.run_frames(3);
+    let expected = (emu.frame_hash(), emu.take_audio_samples());
+
+    emu.poke(0x8000, 0);
+    emu.poke(0x4015, 0x00);
+    emu.run_frames(1);
+    assert!(emu.load_state(&state));
+    emu.take_audio_samples();
+    emu.run_frames(3);
+    assert_eq!((emu.frame_hash(), emu.take_audio_samples()), expected);
+}
+
+#[test]
+fn corrupt_scroll_registers_are_masked() {
+    let mut emu = boot();
+    let mut state = Vec::new();
+    emu.save_state_into(&mut state);
+    // v、t 與 fine_x 位於 PPU 暫存器區段（CPU RAM 之後）
+    state[2064..2068].fill(0xFF);
+    state[2068] = 0xFF;
+    assert!(emu.load_state(&state));
+    assert_eq!((emu.ppu.v, emu.ppu.t, emu.ppu.fine_x), (0x7FFF, 0x7FFF, 7));
+    emu.run_frames(2);
+}
+
+#[test]
+fn slots_restore_compressed_state() {
+    let mut emu = boot();
+    emu.run_frames(5);
+    let state = emu.export_save_state();
+    assert!(emu.save_slot(3, 1000.0));
+    assert!(!emu.save_slot(99, 0.0));
+
+    emu.run_frames(5);
+    assert!(emu.load_slot(3));
+    assert_eq!(emu.export_save_state(), state);
+    assert!(!emu.load_slot(0));
+
+    let info = emu.slots().list();
+    assert_eq!(info.len(), 1);
+    assert_eq!(info[0].slot, 3);
+    assert!(info[0].size < state.len() / 2);
+}
+
+#[test]
+fn rewind_walks_back_through_captured_frames() {
+    let mut emu = boot();
+    let mut config = emu.config().clone();
+    config.rewind_depth = 4;
+    config.rewind_interval = 2;
+    emu.set_config(config);
+
+    let mut states = Vec::new();
+    for _ in 0..12 {
+        emu.run_frames(1);
+        states.push(emu.export_save_state_bytes());
+    }
+    // 擷取於第 1、3、5、7、9、11 幀，只保留最後 4 份（5 ~ 11）
+    assert_eq!(emu.rewind_available(), 7);
+    assert_eq!(emu.rewind(2), 3);
+    assert_eq!(emu.frame_count(), 9);
+    assert_eq!(emu.export_save_state_bytes(), states[8]);
+
+    assert_eq!(emu.rewind(100), 4);
+    assert_eq!(emu.frame_count(), 5);
+    assert_eq!(emu.rewind(1), 0, "最舊的一份仍保留，不再往回");
+    assert_eq!(emu.frame_count(), 5);
+
+    assert!(emu.load_state(&states[11]));
+    assert_eq!(emu.rewind(1), 0, "外部讀檔後清除倒帶記錄");
+}
+
+#[test]
+fn reused_buffers_match_fresh_exports() {
+    let mut emu = boot();
+    emu.run_frames(2);
+    let hex = emu.export_save_state();
+
+    let mut buf = Vec::new();
+    emu.save_state_into(&mut buf);
+    assert_eq!(buf.len() * 2, hex.len());
+    let capacity = buf.capacity();
+    emu.save_state_into(&mut buf);
+    assert_eq!(buf.capacity(), capacity);
+
+    let mut text = String::from("stale");
+    emu.export_save_state_into(&mut text);
+    assert_eq!(text, hex);
+
+    // 緩衝區太小時只回傳需要的大小
+    let mut small = [0u8; 4];
+    assert_eq!(emu.save_state_to_slice(&mut small), buf.len());
+    assert_eq!(small, [0; 4]);
+    let mut exact = vec![0u8; buf.len()];
+    assert_eq!(emu.save_state_to_slice(&mut exact), buf.len());
+    assert_eq!(exact, buf);
+
+    emu.run_frames(3);
+    assert!(emu.load_state(&buf));
+    assert_eq!(emu.export_save_state(), hex);
+    assert!(!emu.import_save_state("zz"));
+}
+
+#[test]
+fn state_omits_prg_ram_when_cartridge_has_none() {
+    let mut with_ram = boot();
+    let full = with_ram.export_save_state();
+
+    // NES 2.0 標頭，PRG RAM 與 NVRAM 大小皆為 0
+    let mut rom = build_test_rom();
+    rom[7] = 0x08;
+    rom[10] = 0;
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&rom));
+    assert!(emu.cartridge.prg_ram.is_empty());
+    let state = emu.export_save_state();
+    assert_eq!(full.len() - state.len(), 8192 * 2);
+    assert!(emu.import_save_state(&state));
+    // 大小不符的存檔不可套用
+    assert!(!emu.import_save_state(&full));
+    assert!(!with_ram.import_save_state(&state));
+}
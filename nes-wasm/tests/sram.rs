@@ -0,0 +1,80 @@
+// ============================================================
+// 電池 RAM 測試 - 寫入去抖動後觸發的儲存事件、Mapper EEPROM 存檔
+// ============================================================
+
+mod common;
+
+use common::{build_test_rom, eeprom_read, eeprom_write};
+use nes_wasm::emulator::Emulator;
+
+#[test]
+fn sram_flush_waits_for_quiet_frames_and_fires_once() {
+    // 主迴圈改為不斷把 $00 寫到 $6000（NMI 會改掉 A，所以用 Y）
+    let mut rom = build_test_rom();
+    rom[6] |= 0x02;
+    rom[0x10 + 0x1A..0x10 + 0x1C].copy_from_slice(&[0x60, 0xC0]);
+    rom[0x10 + 0x60..0x10 + 0x68].copy_from_slice(&[
+        0xA4, 0x00,       // LDY $00
+        0x8C, 0x00, 0x60, // STY $6000
+        0x4C, 0x60, 0xC0, // JMP $C060
+    ]);
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&rom));
+    let delay = emu.config().sram_flush_delay;
+    emu.run_frames(2);
+    assert_eq!(emu.take_sram_flush(), None);
+
+    emu.bus.ram[0x0000] = 0x5A;
+    emu.frame();
+    // 之後一直寫入相同的值，不算新的變更
+    for _ in 0..delay - 1 {
+        emu.frame();
+        assert_eq!(emu.take_sram_flush(), None);
+    }
+    emu.frame();
+    let saved = emu.take_sram_flush().expect("靜止滿設定幀數後觸發");
+    assert_eq!(saved[0], 0x5A);
+    assert_eq!(emu.take_sram_flush(), None);
+
+    emu.run_frames(delay * 2);
+    assert_eq!(emu.take_sram_flush(), None);
+}
+
+/// 把測試 ROM 改成有電池的 Bandai FCG（Mapper 16），以 24C02 EEPROM 存檔
+fn boot_bandai_eeprom() -> Emulator {
+    let mut rom = build_test_rom();
+    rom[6] |= 0x02;
+    rom[7] |= 0x10;
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&rom));
+    emu
+}
+
+#[test]
+fn eeprom_writes_raise_the_sram_flush() {
+    let mut emu = boot_bandai_eeprom();
+    let delay = emu.config().sram_flush_delay;
+    emu.run_frames(2);
+    assert_eq!(emu.flush_sram_now(), None);
+
+    eeprom_write(&mut emu.cartridge.mapper, 0x20, &[0xA5]);
+    emu.run_frames(delay);
+    assert_eq!(emu.take_sram_flush(), None);
+    emu.frame();
+    let saved = emu.take_sram_flush().expect("靜止滿設定幀數後觸發");
+    assert_eq!(saved.len(), 256);
+    assert_eq!(saved[0x20], 0xA5);
+
+    // 存檔資料的大小以 EEPROM 為準，不是 PRG RAM
+    assert!(!emu.load_sram(&[0; 8192]));
+    assert!(emu.load_sram(&[0x11; 256]));
+    assert_eq!(eeprom_read(&mut emu.cartridge.mapper, 0x20, 1), [0x11]);
+    emu.run_frames(delay * 2);
+    assert_eq!(emu.take_sram_flush(), None);
+
+    // 即時存檔也保存 EEPROM 的內容
+    let state = emu.export_save_state_bytes();
+    eeprom_write(&mut emu.cartridge.mapper, 0x20, &[0x22]);
+    assert!(emu.load_state(&state));
+    assert_eq!(eeprom_read(&mut emu.cartridge.mapper, 0x20, 1), [0x11]);
+}
@@ -0,0 +1,50 @@
+// ============================================================
+// 測試 ROM 結果協定（$6000/$6004）
+// ============================================================
+// 模擬 blargg 測試 ROM 的回報方式：先寫入執行中狀態與簽章，
+// 再寫入結果訊息與結果碼，確認 run_test_rom 能正確解讀。
+// ============================================================
+
+use nes_wasm::emulator::Emulator;
+
+/// 組出把 (位址, 值) 依序寫入後無限迴圈的 NROM ROM
+fn build_rom(writes: &[(u16, u8)]) -> Vec<u8> {
+    let mut code = Vec::new();
+    for &(addr, value) in writes {
+        code.extend_from_slice(&[0xA9, value, 0x8D, addr as u8, (addr >> 8) as u8]);
+    }
+    let loop_addr = 0xC000 + code.len() as u16;
+    code.extend_from_slice(&[0x4C, loop_addr as u8, (loop_addr >> 8) as u8]);
+
+    let mut prg = vec![0xEAu8; 0x4000];
+    prg[..code.len()].copy_from_slice(&code);
+    prg[0x3FFA..].copy_from_slice(&[0x00, 0xC0, 0x00, 0xC0, 0x00, 0xC0]);
+
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    rom.extend_from_slice(&prg);
+    rom.extend(std::iter::repeat_n(0u8, 0x2000));
+    rom
+}
+
+#[test]
+fn reports_result_code_and_message() {
+    let mut writes = vec![(0x6000, 0x80), (0x6001, 0xDE), (0x6002, 0xB0), (0x6003, 0x61)];
+    for (i, &c) in b"Passed\0".iter().enumerate() {
+        writes.push((0x6004 + i as u16, c));
+    }
+    writes.push((0x6000, 0x00));
+
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&build_rom(&writes)));
+    assert_eq!(emu.run_test_rom(10), Some(0));
+    assert_eq!(emu.test_rom_message(), "Passed");
+}
+
+#[test]
+fn ignores_ram_without_signature() {
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&build_rom(&[(0x6000, 0x00)])));
+    assert_eq!(emu.run_test_rom(5), None);
+    assert_eq!(emu.test_rom_status(), None);
+    assert!(emu.test_rom_message().is_empty());
+}
@@ -0,0 +1,68 @@
+// ============================================================
+// VS. System 測試 - DIP 開關、投幣、CHR 切換與 2C05 暫存器
+// ============================================================
+
+use nes_wasm::ppu::{ChrMemory, Ppu};
+use nes_wasm::vs::VsPpu;
+use nes_wasm::Emulator;
+
+/// Mapper 99 的 VS. ROM：每幀讀取 $4016/$4017 存到 $00/$01，並以 $4016 位元 2 切換 CHR
+fn build_vs_rom() -> Vec<u8> {
+    let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 2, 2, 0x30, 0x61, 0, 0, 0, 0, 0, 0, 0, 0];
+    let mut prg = vec![0xEAu8; 0x8000];
+    let code: &[u8] = &[
+        0xAD, 0x16, 0x40, // LDA $4016
+        0x85, 0x00,       // STA $00
+        0xAD, 0x17, 0x40, // LDA $4017
+        0x85, 0x01,       // STA $01
+        0xA9, 0x04,       // LDA #$04
+        0x8D, 0x16, 0x40, // STA $4016（CHR bank 1）
+        0x4C, 0x00, 0x80, // JMP $8000
+    ];
+    prg[..code.len()].copy_from_slice(code);
+    prg[0x7FFC..0x7FFE].copy_from_slice(&[0x00, 0x80]);
+    rom.extend_from_slice(&prg);
+    rom.extend(std::iter::repeat_n(0x11, 0x2000));
+    rom.extend(std::iter::repeat_n(0x22, 0x2000));
+    rom
+}
+
+#[test]
+fn coin_and_dip_switches_reach_the_ports() {
+    let mut emu = Emulator::new();
+    assert!(emu.load_rom(&build_vs_rom()));
+    assert!(emu.is_vs_system());
+    assert_eq!(emu.cartridge.rom_info().mapper_name, "VS. UniSystem");
+    assert_eq!(emu.cartridge.rom_info().vs_ppu, Some("RP2C03"));
+
+    emu.set_dip_switches(0xA7);
+    assert!(emu.insert_coin(0));
+    emu.frame();
+    assert_eq!(emu.peek(0x00) & 0x7C, 0x38, "DIP 1-2 與投幣口 1");
+    assert_eq!(emu.peek(0x01) & 0xFC, 0xA4, "DIP 3-8");
+
+    // 投幣開關只保持幾幀
+    emu.run_frames(5);
+    assert_eq!(emu.peek(0x00) & 0x60, 0x00);
+
+    // $4016 位元 2 切換到第二個 CHR bank
+    emu.ppu.cpu_write(0x2006, 0x00, &mut emu.cartridge);
+    emu.ppu.cpu_write(0x2006, 0x00, &mut emu.cartridge);
+    emu.ppu.cpu_read(0x2007, &mut emu.cartridge);
+    assert_eq!(emu.ppu.cpu_read(0x2007, &mut emu.cartridge), 0x22);
+}
+
+#[test]
+fn rc2c05_swaps_ctrl_and_mask_and_reports_signature() {
+    let mut ppu = Ppu::new();
+    let mut chr = ChrMemory::default();
+    ppu.set_vs_ppu(Some(VsPpu::Rc2c05v2));
+    ppu.cpu_write(0x2000, 0x1E, &mut chr);
+    ppu.cpu_write(0x2001, 0x80, &mut chr);
+    assert_eq!((ppu.ctrl, ppu.mask), (0x80, 0x1E));
+    assert_eq!(ppu.cpu_read(0x2002, &mut chr) & 0x3F, 0x3D);
+
+    ppu.set_vs_ppu(None);
+    ppu.cpu_write(0x2000, 0x00, &mut chr);
+    assert_eq!(ppu.ctrl, 0x00);
+}
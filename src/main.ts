@@ -9,7 +9,7 @@
  * - 使用 Rust/WASM 核心取代 TypeScript 硬體模擬
  */
 
-import init, { NesWasm } from '../nes-wasm/pkg/nes_wasm.js';
+import init, { NesWasm, Button as ControllerButton, InputDevice } from '../nes-wasm/pkg/nes_wasm.js';
 
 // ===== 型別定義 =====
 
@@ -22,19 +22,6 @@ interface RomListResponse {
   roms: RomInfo[];
 }
 
-// 控制器按鈕編號（與 Rust 端一致）
-const ControllerButton = {
-  A: 0,
-  B: 1,
-  Select: 2,
-  Start: 3,
-  Up: 4,
-  Down: 5,
-  Left: 6,
-  Right: 7,
-} as const;
-type ControllerButton = typeof ControllerButton[keyof typeof ControllerButton];
-
 // ===== 全域變數 =====
 
 let nes: NesWasm | null = null;
@@ -90,6 +77,9 @@ async function initWasm(): Promise<void> {
   // 設定鍵盤輸入（直接對 WASM 控制器操作）
   setupKeyboardInput();
 
+  // 設定 Zapper 光線槍（滑鼠瞄準畫面）
+  setupZapperInput();
+
   // 設定虛擬控制器
   setupVirtualController();
 
@@ -136,6 +126,48 @@ function setupKeyboardInput(): void {
   });
 }
 
+// ===== Zapper 光線槍 =====
+
+/** 控制器埠 2 是否接上 Zapper（Duck Hunt、Wild Gunman 等） */
+let zapperConnected = false;
+
+/**
+ * 切換控制器埠 2 的裝置：標準控制器 / Zapper
+ */
+function toggleZapper(): void {
+  if (!nes) return;
+  zapperConnected = !zapperConnected;
+  nes.setInputDevice(1, zapperConnected ? InputDevice.Zapper : InputDevice.Gamepad);
+  console.log(zapperConnected ? 'Zapper 已接上控制器埠 2（F9 切換）' : '控制器埠 2 恢復為標準控制器');
+}
+
+/**
+ * 設定 Zapper 滑鼠輸入：移動瞄準、左鍵扣扳機、右鍵對畫面外射擊（換彈）
+ */
+function setupZapperInput(): void {
+  if (!canvas) return;
+  const screen = canvas;
+  screen.addEventListener('mousemove', (e) => {
+    nes?.setZapperPosition(e.offsetX, e.offsetY, screen.clientWidth, screen.clientHeight);
+  });
+  screen.addEventListener('mouseleave', () => {
+    nes?.setZapperPosition(-1, -1, screen.clientWidth, screen.clientHeight);
+  });
+  screen.addEventListener('mousedown', (e) => {
+    if (!zapperConnected || e.button !== 0) return;
+    nes?.setZapperPosition(e.offsetX, e.offsetY, screen.clientWidth, screen.clientHeight);
+    nes?.setZapperTrigger(true);
+  });
+  screen.addEventListener('mouseup', (e) => {
+    if (e.button === 0) nes?.setZapperTrigger(false);
+  });
+  screen.addEventListener('contextmenu', (e) => {
+    if (!zapperConnected) return;
+    e.preventDefault();
+    nes?.fireZapperOffscreen();
+  });
+}
+
 // ===== ROM 選擇器 =====
 
 /**
@@ -992,6 +1024,11 @@ function setupKeyboardShortcuts(): void {
       const slot = parseInt(e.key);
       loadState(slot);
     }
+    // F9 切換 Zapper 光線槍
+    if (e.key === 'F9') {
+      e.preventDefault();
+      toggleZapper();
+    }
     // ESC 鍵返回選擇畫面
     if (e.key === 'Escape') {
       showRomSelector();